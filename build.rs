@@ -0,0 +1,62 @@
+//! Embeds the build metadata reported by `cql_stress::version`.
+
+use std::process::Command;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+fn main() {
+    if let Some(git_commit) = git_commit() {
+        println!("cargo:rustc-env=CQL_STRESS_GIT_COMMIT={}", git_commit);
+    }
+    println!("cargo:rerun-if-changed=.git/HEAD");
+    println!("cargo:rerun-if-changed=.git/refs");
+
+    // Reproducible builds pin the build date with SOURCE_DATE_EPOCH.
+    let build_timestamp = std::env::var("SOURCE_DATE_EPOCH")
+        .ok()
+        .and_then(|epoch| epoch.parse::<u64>().ok())
+        .unwrap_or_else(|| {
+            SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap()
+                .as_secs()
+        });
+    println!(
+        "cargo:rustc-env=CQL_STRESS_BUILD_TIMESTAMP={}",
+        build_timestamp
+    );
+    println!("cargo:rerun-if-env-changed=SOURCE_DATE_EPOCH");
+
+    let lock_file = std::fs::read_to_string("Cargo.lock").unwrap_or_default();
+    if let Some(driver_version) = locked_version(&lock_file, "scylla") {
+        println!(
+            "cargo:rustc-env=CQL_STRESS_DRIVER_VERSION={}",
+            driver_version
+        );
+    }
+    println!("cargo:rerun-if-changed=Cargo.lock");
+}
+
+fn git_commit() -> Option<String> {
+    let output = Command::new("git")
+        .args(["rev-parse", "HEAD"])
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    let commit = String::from_utf8(output.stdout).ok()?;
+    Some(commit.trim().to_owned())
+}
+
+// Returns the version of the package resolved in Cargo.lock,
+// where the `version` line follows the `name` line of the package.
+fn locked_version(lock_file: &str, package: &str) -> Option<String> {
+    let name_line = format!("name = \"{}\"", package);
+    let mut lines = lock_file.lines();
+    lines.find(|line| *line == name_line)?;
+    let version = lines
+        .next()?
+        .strip_prefix("version = \"")?
+        .strip_suffix('"')?;
+    Some(version.to_owned())
+}
@@ -0,0 +1,165 @@
+//! Pluggable authenticators, enabled with the `auth-plugins` crate feature.
+//!
+//! Both frontends select an authenticator by its name, optionally followed
+//! by the options interpreted by the authenticator, e.g. `sasl-plain` or
+//! `name:options`. A custom authenticator (e.g. AWS SigV4 or another SASL
+//! mechanism) is added by implementing [AuthenticatorProvider] and listing
+//! its constructor in [AUTHENTICATORS].
+
+use std::sync::Arc;
+
+use anyhow::{Context, Result};
+use scylla::authentication::{AuthError, AuthenticatorProvider, AuthenticatorSession};
+
+/// A named authenticator which can be selected from the command line.
+pub struct AuthenticatorPlugin {
+    pub name: &'static str,
+    pub description: &'static str,
+    /// Creates the authenticator from the options given after the name.
+    pub create: fn(&str) -> Result<Arc<dyn AuthenticatorProvider>>,
+}
+
+/// The authenticators available in the current build.
+pub const AUTHENTICATORS: &[AuthenticatorPlugin] = &[AuthenticatorPlugin {
+    name: "sasl-plain",
+    description: "SASL PLAIN with the credentials read from the CQL_STRESS_AUTH_USERNAME \
+        and CQL_STRESS_AUTH_PASSWORD environment variables",
+    create: create_sasl_plain,
+}];
+
+/// The authenticator selected from the command line: the plugin and its options.
+pub struct AuthenticatorSpec {
+    pub plugin: &'static AuthenticatorPlugin,
+    pub options: String,
+}
+
+impl AuthenticatorSpec {
+    /// Parses `name[:options]`, where `name` is one of [AUTHENTICATORS].
+    pub fn parse(spec: &str) -> Result<Self> {
+        let (name, options) = spec.split_once(':').unwrap_or((spec, ""));
+        let plugin = AUTHENTICATORS
+            .iter()
+            .find(|plugin| plugin.name == name)
+            .with_context(|| {
+                let names = AUTHENTICATORS
+                    .iter()
+                    .map(|plugin| plugin.name)
+                    .collect::<Vec<_>>();
+                format!(
+                    "Unknown authenticator: {}. Must be one of: {}",
+                    name,
+                    names.join(", ")
+                )
+            })?;
+        Ok(Self {
+            plugin,
+            options: options.to_owned(),
+        })
+    }
+
+    pub fn create(&self) -> Result<Arc<dyn AuthenticatorProvider>> {
+        (self.plugin.create)(&self.options)
+            .with_context(|| format!("Failed to create the {} authenticator", self.plugin.name))
+    }
+}
+
+pub const USERNAME_ENV: &str = "CQL_STRESS_AUTH_USERNAME";
+pub const PASSWORD_ENV: &str = "CQL_STRESS_AUTH_PASSWORD";
+
+fn create_sasl_plain(options: &str) -> Result<Arc<dyn AuthenticatorProvider>> {
+    anyhow::ensure!(
+        options.is_empty(),
+        "sasl-plain doesn't accept any options, got: {}",
+        options
+    );
+    let read_env = |name: &str| std::env::var(name).with_context(|| format!("{} is not set", name));
+    Ok(Arc::new(SaslPlainAuthenticator {
+        username: read_env(USERNAME_ENV)?,
+        password: read_env(PASSWORD_ENV)?,
+    }))
+}
+
+/// Authenticates with the SASL PLAIN mechanism (RFC 4616).
+///
+/// Unlike `-username`/`user=`, the credentials don't appear in the command line
+/// of the process.
+pub struct SaslPlainAuthenticator {
+    username: String,
+    password: String,
+}
+
+impl SaslPlainAuthenticator {
+    pub fn new(username: String, password: String) -> Self {
+        Self { username, password }
+    }
+
+    fn initial_response(&self) -> Vec<u8> {
+        let mut response = Vec::with_capacity(self.username.len() + self.password.len() + 2);
+        response.push(0);
+        response.extend_from_slice(self.username.as_bytes());
+        response.push(0);
+        response.extend_from_slice(self.password.as_bytes());
+        response
+    }
+}
+
+#[async_trait]
+impl AuthenticatorProvider for SaslPlainAuthenticator {
+    async fn start_authentication_session(
+        &self,
+        _authenticator_name: &str,
+    ) -> Result<(Option<Vec<u8>>, Box<dyn AuthenticatorSession>), AuthError> {
+        Ok((
+            Some(self.initial_response()),
+            Box::new(SaslPlainAuthenticatorSession),
+        ))
+    }
+}
+
+struct SaslPlainAuthenticatorSession;
+
+#[async_trait]
+impl AuthenticatorSession for SaslPlainAuthenticatorSession {
+    async fn evaluate_challenge(
+        &mut self,
+        _token: Option<&[u8]>,
+    ) -> Result<Option<Vec<u8>>, AuthError> {
+        Err("Challenges are not expected by the SASL PLAIN mechanism".to_string())
+    }
+
+    async fn success(&mut self, _token: Option<&[u8]>) -> Result<(), AuthError> {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_spec_test() {
+        let spec = AuthenticatorSpec::parse("sasl-plain").unwrap();
+        assert_eq!("sasl-plain", spec.plugin.name);
+        assert_eq!("", spec.options);
+
+        let spec = AuthenticatorSpec::parse("sasl-plain:foo=bar:baz").unwrap();
+        assert_eq!("sasl-plain", spec.plugin.name);
+        assert_eq!("foo=bar:baz", spec.options);
+        assert!(spec.create().is_err());
+
+        assert!(AuthenticatorSpec::parse("unknown").is_err());
+        assert!(AuthenticatorSpec::parse("").is_err());
+    }
+
+    #[tokio::test]
+    async fn sasl_plain_initial_response_test() {
+        let authenticator = SaslPlainAuthenticator::new(String::from("user"), String::from("pass"));
+        let (response, mut session) = authenticator
+            .start_authentication_session("org.apache.cassandra.auth.PasswordAuthenticator")
+            .await
+            .unwrap();
+        assert_eq!(Some(b"\0user\0pass".to_vec()), response);
+        assert!(session.evaluate_challenge(None).await.is_err());
+        assert!(session.success(None).await.is_ok());
+    }
+}
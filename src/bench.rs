@@ -0,0 +1,416 @@
+//! A high-level API for embedding the stress logic in other tools.
+//!
+//! The [Builder] creates a benchmark which repeatedly executes a single
+//! prepared statement with values produced by a user-provided generator.
+//! For more complicated workloads, implement [OperationFactory] directly
+//! and use [run] with a hand-written [Configuration].
+
+use std::future::Future;
+use std::ops::ControlFlow;
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::Result;
+use hdrhistogram::Histogram;
+use scylla::prepared_statement::PreparedStatement;
+use scylla::serialize::row::SerializeRow;
+use scylla::Session;
+
+use crate::configuration::{
+    make_runnable, Arrival, Configuration, DutyCycle, Operation, OperationContext,
+    OperationFactory, RateLimitMode, RetryBackoff, StreamConfiguration,
+};
+use crate::run::{run, RunController};
+use crate::sharded_stats::{
+    self, ErrorCounts, ErrorKind, OperationTiming, ShardedStats, StatsBuffer,
+};
+
+/// Generates the values bound to the statement executed by the benchmark.
+///
+/// The generator receives the ID of the operation. It should be deterministic,
+/// because the values are generated anew when the operation is retried.
+///
+/// Implemented for all closures of type `Fn(u64) -> V`.
+pub trait ValueGenerator: Send + Sync + 'static {
+    type Values: SerializeRow + Send + Sync;
+
+    fn generate(&self, operation_id: u64) -> Self::Values;
+}
+
+impl<F, V> ValueGenerator for F
+where
+    F: Fn(u64) -> V + Send + Sync + 'static,
+    V: SerializeRow + Send + Sync,
+{
+    type Values = V;
+
+    fn generate(&self, operation_id: u64) -> V {
+        self(operation_id)
+    }
+}
+
+/// Statistics gathered by the benchmark created with the [Builder].
+pub struct BenchStats {
+    pub operations: u64,
+    pub errors: u64,
+    pub error_counts: ErrorCounts,
+
+    /// Latencies of the successful operations in nanoseconds, measured from
+    /// the scheduled start of the operation (see [OperationContext]).
+    pub latency: Histogram<u64>,
+}
+
+impl sharded_stats::Stats for BenchStats {
+    fn clear(&mut self) {
+        self.operations = 0;
+        self.errors = 0;
+        self.error_counts.clear();
+        self.latency.reset();
+    }
+
+    fn combine(&mut self, other: &Self) {
+        self.operations += other.operations;
+        self.errors += other.errors;
+        self.error_counts.combine(&other.error_counts);
+        self.latency.add(&other.latency).unwrap();
+    }
+}
+
+/// The outcome of a single operation of the benchmark,
+/// buffered before it's recorded to [BenchStats].
+pub struct BenchSample {
+    timing: OperationTiming,
+    // None if the operation succeeded.
+    error: Option<ErrorKind>,
+}
+
+impl BenchSample {
+    fn new(ctx: &OperationContext, result: &Result<()>) -> Self {
+        Self {
+            timing: OperationTiming::ended_now(ctx),
+            error: result.as_ref().err().map(ErrorKind::classify),
+        }
+    }
+}
+
+impl sharded_stats::RecordSample for BenchStats {
+    type Sample = BenchSample;
+
+    fn record_sample(&mut self, sample: &BenchSample) {
+        match sample.error {
+            None => {
+                self.operations += 1;
+                let latency = sample.timing.co_fixed_latency();
+                let _ = self.latency.record(latency.as_nanos() as u64);
+            }
+            Some(kind) => self.account_error(kind),
+        }
+    }
+}
+
+impl BenchStats {
+    fn account_error(&mut self, kind: ErrorKind) {
+        self.operations += 1;
+        self.errors += 1;
+        self.error_counts.record_kind(kind);
+    }
+}
+
+pub struct BenchStatsFactory;
+
+impl sharded_stats::StatsFactory for BenchStatsFactory {
+    type Stats = BenchStats;
+
+    fn create(&self) -> BenchStats {
+        BenchStats {
+            operations: 0,
+            errors: 0,
+            error_counts: ErrorCounts::default(),
+            latency: Histogram::new(3).unwrap(),
+        }
+    }
+}
+
+/// Builds a benchmark executing a single prepared statement.
+///
+/// ```no_run
+/// # async fn example(session: std::sync::Arc<scylla::Session>) -> anyhow::Result<()> {
+/// let statement = session
+///     .prepare("INSERT INTO ks.t (pk, v) VALUES (?, ?)")
+///     .await?;
+/// let (_controller, fut, stats) =
+///     cql_stress::bench::Builder::new(session, statement, |id: u64| (id as i64, id as i64))
+///         .concurrency(64)
+///         .operation_count(1_000_000)
+///         .run()?;
+/// fut.await?;
+/// println!("Operations: {}", stats.get_combined_and_clear().operations);
+/// # Ok(())
+/// # }
+/// ```
+pub struct Builder<G: ValueGenerator> {
+    session: Arc<Session>,
+    statement: PreparedStatement,
+    generator: G,
+    concurrency: u64,
+    max_duration: Option<Duration>,
+    rate_limit_per_second: Option<f64>,
+    max_operations: Option<u64>,
+    max_retries_per_op: usize,
+    operation_timeout: Option<Duration>,
+    retry_backoff: RetryBackoff,
+    retry_budget: Option<f64>,
+    max_in_flight: Option<usize>,
+    runtimes: Option<usize>,
+    duty_cycle: Option<DutyCycle>,
+}
+
+impl<G: ValueGenerator> Builder<G> {
+    /// Creates a builder of the benchmark which executes `statement`
+    /// with values produced by `generator`.
+    ///
+    /// By default, the benchmark runs with concurrency 1 until it is stopped
+    /// via [RunController], without rate limit and retries.
+    pub fn new(session: Arc<Session>, statement: PreparedStatement, generator: G) -> Self {
+        Self {
+            session,
+            statement,
+            generator,
+            concurrency: 1,
+            max_duration: None,
+            rate_limit_per_second: None,
+            max_operations: None,
+            max_retries_per_op: 0,
+            operation_timeout: None,
+            retry_backoff: RetryBackoff::None,
+            retry_budget: None,
+            max_in_flight: None,
+            runtimes: None,
+            duty_cycle: None,
+        }
+    }
+
+    /// Sets the number of concurrently executed operations. Must not be zero.
+    pub fn concurrency(mut self, concurrency: u64) -> Self {
+        self.concurrency = concurrency;
+        self
+    }
+
+    /// Sets the maximum duration of the benchmark.
+    pub fn duration(mut self, duration: Duration) -> Self {
+        self.max_duration = Some(duration);
+        self
+    }
+
+    /// Limits the number of operations performed per second.
+    pub fn rate(mut self, ops_per_second: f64) -> Self {
+        self.rate_limit_per_second = Some(ops_per_second);
+        self
+    }
+
+    /// Sets the number of operations after which the benchmark finishes.
+    pub fn operation_count(mut self, count: u64) -> Self {
+        self.max_operations = Some(count);
+        self
+    }
+
+    /// Sets the maximum number of retries of a failed operation.
+    pub fn max_retries_per_op(mut self, retries: usize) -> Self {
+        self.max_retries_per_op = retries;
+        self
+    }
+
+    /// Sets the maximum duration of a single operation attempt.
+    pub fn operation_timeout(mut self, timeout: Duration) -> Self {
+        self.operation_timeout = Some(timeout);
+        self
+    }
+
+    /// Sets the delay between the retries of a failed operation.
+    pub fn retry_backoff(mut self, backoff: RetryBackoff) -> Self {
+        self.retry_backoff = backoff;
+        self
+    }
+
+    /// Limits the number of retries per second. Must be positive.
+    pub fn retry_budget(mut self, retries_per_second: f64) -> Self {
+        self.retry_budget = Some(retries_per_second);
+        self
+    }
+
+    /// Limits the number of operations executed at the same time,
+    /// independently of the concurrency. Must not be zero.
+    pub fn max_in_flight(mut self, max_in_flight: usize) -> Self {
+        self.max_in_flight = Some(max_in_flight);
+        self
+    }
+
+    /// Spreads the operations between this many dedicated single-threaded
+    /// runtimes, each with its share of the rate. Must not be zero.
+    pub fn runtimes(mut self, runtimes: usize) -> Self {
+        self.runtimes = Some(runtimes);
+        self
+    }
+
+    /// Runs the operations only in the `on` windows of the duty cycle,
+    /// pausing them in the `off` ones.
+    pub fn duty_cycle(mut self, on: Duration, off: Duration) -> Self {
+        self.duty_cycle = Some(DutyCycle { on, off });
+        self
+    }
+
+    /// Starts the benchmark.
+    ///
+    /// Returns the controller and the future of the run (see [run]),
+    /// together with the statistics which are updated during the run.
+    #[allow(clippy::type_complexity)]
+    pub fn run(
+        self,
+    ) -> Result<(
+        RunController,
+        impl Future<Output = Result<()>>,
+        Arc<ShardedStats<BenchStatsFactory>>,
+    )> {
+        anyhow::ensure!(self.concurrency > 0, "Concurrency must not be zero");
+        anyhow::ensure!(
+            self.rate_limit_per_second.map_or(true, |rate| rate > 0.0),
+            "Rate must be positive"
+        );
+        anyhow::ensure!(
+            self.max_in_flight != Some(0),
+            "The maximum number of operations in flight must not be zero"
+        );
+        anyhow::ensure!(
+            self.runtimes != Some(0),
+            "The number of runtimes must not be zero"
+        );
+        anyhow::ensure!(
+            self.duty_cycle
+                .map_or(true, |cycle| !cycle.on.is_zero() && !cycle.off.is_zero()),
+            "The windows of the duty cycle must not be empty"
+        );
+
+        let stats = Arc::new(ShardedStats::new(Arc::new(BenchStatsFactory)));
+        let operation_factory = BenchOperationFactory {
+            session: self.session,
+            statement: self.statement,
+            generator: Arc::new(self.generator),
+            stats: Arc::clone(&stats),
+        };
+
+        let config = Configuration {
+            max_duration: self.max_duration,
+            max_operations: self.max_operations,
+            streams: vec![StreamConfiguration {
+                name: String::from("bench"),
+                concurrency: self.concurrency,
+                rate_limit_per_second: self.rate_limit_per_second,
+                rate_limit_mode: RateLimitMode::Schedule,
+                arrival: Arrival::Uniform,
+                operation_factory: Arc::new(operation_factory),
+            }],
+            max_retries_per_op: self.max_retries_per_op,
+            operation_timeout: self.operation_timeout,
+            retry_backoff: self.retry_backoff,
+            retry_budget: self.retry_budget,
+            max_in_flight: self.max_in_flight,
+            runtimes: self.runtimes,
+            duty_cycle: self.duty_cycle,
+        };
+
+        let (controller, fut) = run(config);
+        Ok((controller, fut, stats))
+    }
+}
+
+struct BenchOperationFactory<G: ValueGenerator> {
+    session: Arc<Session>,
+    statement: PreparedStatement,
+    generator: Arc<G>,
+    stats: Arc<ShardedStats<BenchStatsFactory>>,
+}
+
+impl<G: ValueGenerator> OperationFactory for BenchOperationFactory<G> {
+    fn create(&self) -> Box<dyn Operation> {
+        Box::new(BenchOperation {
+            session: Arc::clone(&self.session),
+            statement: self.statement.clone(),
+            generator: Arc::clone(&self.generator),
+            stats: StatsBuffer::new(Arc::clone(&self.stats)),
+        })
+    }
+
+    fn record_timeout(&self, _ctx: &OperationContext, err: &anyhow::Error) {
+        self.stats
+            .get_shard_mut()
+            .account_error(ErrorKind::classify(err));
+    }
+}
+
+struct BenchOperation<G: ValueGenerator> {
+    session: Arc<Session>,
+    statement: PreparedStatement,
+    generator: Arc<G>,
+    stats: StatsBuffer<BenchStatsFactory>,
+}
+
+make_runnable!(BenchOperation<G: ValueGenerator>, stats);
+impl<G: ValueGenerator> BenchOperation<G> {
+    async fn execute(&mut self, ctx: &OperationContext) -> Result<ControlFlow<()>> {
+        let values = self.generator.generate(ctx.operation_id);
+        let result = self
+            .session
+            .execute_unpaged(&self.statement, values)
+            .await
+            .map(|_| ())
+            .map_err(anyhow::Error::from);
+
+        self.stats.record(BenchSample::new(ctx, &result));
+
+        result?;
+        Ok(ControlFlow::Continue(()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Builder;
+    use crate::test_util::new_test_session;
+
+    #[tokio::test]
+    async fn test_builder_run_to_completion() {
+        let session = new_test_session().await;
+        session
+            .query_unpaged(
+                "CREATE KEYSPACE IF NOT EXISTS cql_stress_bench_test WITH replication = \
+                {'class': 'SimpleStrategy', 'replication_factor': 1}",
+                (),
+            )
+            .await
+            .unwrap();
+        session
+            .query_unpaged(
+                "CREATE TABLE IF NOT EXISTS cql_stress_bench_test.t (pk bigint PRIMARY KEY, v bigint)",
+                (),
+            )
+            .await
+            .unwrap();
+        let statement = session
+            .prepare("INSERT INTO cql_stress_bench_test.t (pk, v) VALUES (?, ?)")
+            .await
+            .unwrap();
+
+        let (_, fut, stats) =
+            Builder::new(session, statement, |id: u64| (id as i64, 2 * id as i64))
+                .concurrency(10)
+                .operation_count(100)
+                .run()
+                .unwrap();
+        fut.await.unwrap();
+
+        let stats = stats.get_combined_and_clear();
+        assert_eq!(stats.operations, 100);
+        assert_eq!(stats.errors, 0);
+        assert_eq!(stats.latency.len(), 100);
+    }
+}
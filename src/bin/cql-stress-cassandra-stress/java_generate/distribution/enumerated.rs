@@ -1,4 +1,5 @@
 use anyhow::Result;
+use rand::Rng;
 use rand_distr::{Distribution, WeightedIndex};
 
 #[derive(Clone)]
@@ -14,8 +15,13 @@ impl<T: Copy> EnumeratedDistribution<T> {
         Ok(Self { items, dist })
     }
 
-    pub fn sample(&self) -> T {
-        self.items[self.dist.sample(&mut rand::thread_rng())].0
+    pub fn sample(&self, rng: &mut impl Rng) -> T {
+        self.items[self.dist.sample(rng)].0
+    }
+
+    /// Returns the items along with their weights.
+    pub fn items(&self) -> &[(T, f64)] {
+        &self.items
     }
 }
 
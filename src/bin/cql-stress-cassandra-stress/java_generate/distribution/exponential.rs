@@ -0,0 +1,214 @@
+use anyhow::{Context, Result};
+use cql_stress::distribution::Description;
+
+use super::{
+    fixed::FixedDistributionFactory, Distribution, DistributionFactory, ThreadLocalRandom,
+};
+
+lazy_static! {
+    /// Used by the sampling algorithm - the i-th element is the sum of ln(2)^k / k! for k in 1..=i+1.
+    /// See: https://commons.apache.org/proper/commons-math/javadocs/api-3.6.1/src-html/org/apache/commons/math3/distribution/ExponentialDistribution.html#line.61.
+    static ref EXPONENTIAL_SA_QI: Vec<f64> = {
+        let ln2 = rust_strictmath::log(2f64);
+        let mut result = Vec::new();
+        let mut qi = 0f64;
+        let mut factorial = 1f64;
+        let mut i = 1;
+        while qi < 1f64 {
+            factorial *= i as f64;
+            qi += ln2.powi(i) / factorial;
+            result.push(qi);
+            i += 1;
+        }
+        result
+    };
+}
+
+/// Exponential distribution over the range [min, max].
+/// Based on https://commons.apache.org/proper/commons-math/javadocs/api-3.6.1/src-html/org/apache/commons/math3/distribution/ExponentialDistribution.html
+/// and https://github.com/scylladb/scylla-tools-java/blob/master/tools/stress/src/org/apache/cassandra/stress/generate/DistributionOffsetApache.java.
+struct ExponentialDistribution {
+    min: i64,
+    max: i64,
+    mean: f64,
+    rng: ThreadLocalRandom,
+}
+
+impl ExponentialDistribution {
+    fn new(min: i64, max: i64, mean: f64) -> Self {
+        Self {
+            min,
+            max,
+            mean,
+            rng: ThreadLocalRandom::new(),
+        }
+    }
+
+    /// Algorithm SA from Ahrens & Dieter (1972), exactly as in Apache Commons Math,
+    /// so that the samples match the Java implementation for the same seed.
+    fn sample(&self) -> f64 {
+        let mut rng = self.rng.get();
+        let qi = &*EXPONENTIAL_SA_QI;
+
+        let mut a = 0f64;
+        let mut u = rng.next_double();
+        while u < 0.5 {
+            a += qi[0];
+            u *= 2f64;
+        }
+        u += u - 1f64;
+
+        if u <= qi[0] {
+            return self.mean * (a + u);
+        }
+
+        let mut i = 0;
+        let mut u2 = rng.next_double();
+        let mut umin = u2;
+        loop {
+            i += 1;
+            u2 = rng.next_double();
+            if u2 < umin {
+                umin = u2;
+            }
+            // The loop terminates, since the last element of `qi` is not less than 1.
+            if u <= qi[i] {
+                break;
+            }
+        }
+
+        self.mean * (a + umin * qi[0])
+    }
+}
+
+impl Distribution for ExponentialDistribution {
+    fn next_i64(&self) -> i64 {
+        self.min + (self.max - self.min).min(self.sample() as i64)
+    }
+
+    fn next_f64(&self) -> f64 {
+        self.min as f64 + ((self.max - self.min) as f64).min(self.sample())
+    }
+
+    fn set_seed(&self, seed: i64) {
+        self.rng.get().set_seed(seed as u64)
+    }
+
+    fn min_value(&self) -> i64 {
+        self.min
+    }
+
+    fn max_value(&self) -> i64 {
+        self.max
+    }
+}
+
+pub struct ExponentialDistributionFactory {
+    min: i64,
+    max: i64,
+    mean: f64,
+}
+
+impl DistributionFactory for ExponentialDistributionFactory {
+    fn create(&self) -> Box<dyn Distribution> {
+        Box::new(ExponentialDistribution::new(self.min, self.max, self.mean))
+    }
+}
+
+impl ExponentialDistributionFactory {
+    fn do_parse_from_description(desc: &Description<'_>) -> Result<Box<dyn DistributionFactory>> {
+        // See https://github.com/scylladb/scylla-tools-java/blob/master/tools/stress/src/org/apache/cassandra/stress/settings/OptionDistribution.java#L247.
+        desc.check_argument_count(2)?;
+        let (min, max) = (desc.args[0].parse::<i64>()?, desc.args[1].parse::<i64>()?);
+        anyhow::ensure!(
+            min <= max,
+            "Upper bound ({}) for exponential distribution is lower than the lower bound ({}).",
+            max,
+            min
+        );
+
+        if min == max {
+            return Ok(Box::new(FixedDistributionFactory(min)));
+        }
+
+        // The probability of sampling the max value should be roughly equal to 1/(max-min),
+        // so all values are visitable. That results in an overly skewed distribution, so c-s takes sqrt.
+        // For the exponential distribution with mean 1, inverseCumulativeProbability(p) = -ln(1 - p).
+        let range = (max - min) as f64;
+        let bound = -rust_strictmath::log(1f64 - (1f64 - (1f64 / range).sqrt()));
+        let mean = range / bound;
+
+        Ok(Box::new(Self { min, max, mean }))
+    }
+
+    pub fn parse_from_description(desc: Description<'_>) -> Result<Box<dyn DistributionFactory>> {
+        Self::do_parse_from_description(&desc).with_context(|| {
+            format!(
+                "Invalid parameter list for exponential distribution: {:?}",
+                desc.args
+            )
+        })
+    }
+
+    pub fn help_description() -> String {
+        format!(
+            "      {:<36} An exponential distribution over the range [min..max]",
+            "EXP(min..max)"
+        )
+    }
+}
+
+impl std::fmt::Display for ExponentialDistributionFactory {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "EXP({}..{},mean={})", self.min, self.max, self.mean)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use cql_stress::distribution::Description;
+
+    use super::{ExponentialDistributionFactory, EXPONENTIAL_SA_QI};
+
+    fn exp(args: Vec<&str>) -> anyhow::Result<Box<dyn super::DistributionFactory>> {
+        ExponentialDistributionFactory::parse_from_description(Description {
+            name: "EXP",
+            args,
+            inverted: false,
+        })
+    }
+
+    #[test]
+    fn exponential_sa_qi_test() {
+        let qi = &*EXPONENTIAL_SA_QI;
+        assert!((qi[0] - std::f64::consts::LN_2).abs() < 1e-15);
+        assert!(qi.windows(2).all(|w| w[0] < w[1]));
+        assert!(*qi.last().unwrap() >= 1f64);
+    }
+
+    #[test]
+    fn exponential_distribution_test() {
+        let dist = exp(vec!["1", "1000"]).unwrap().create();
+
+        dist.set_seed(0xdeadcafe);
+        let first = (0..1000).map(|_| dist.next_i64()).collect::<Vec<_>>();
+        assert!(first.iter().all(|v| (1..=1000).contains(v)));
+
+        // The smaller values should be sampled much more often.
+        let lower_half = first.iter().filter(|v| **v <= 500).count();
+        assert!(lower_half > 700);
+
+        // Sampling is deterministic for the same seed.
+        dist.set_seed(0xdeadcafe);
+        let second = (0..1000).map(|_| dist.next_i64()).collect::<Vec<_>>();
+        assert_eq!(first, second);
+    }
+
+    #[test]
+    fn exponential_distribution_parse_test() {
+        assert_eq!("FIXED(5)", exp(vec!["5", "5"]).unwrap().to_string());
+        assert!(exp(vec!["1"]).is_err());
+        assert!(exp(vec!["10", "1"]).is_err());
+        assert!(exp(vec!["1", "10", "2"]).is_err());
+    }
+}
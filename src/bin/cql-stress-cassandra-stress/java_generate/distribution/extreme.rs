@@ -0,0 +1,184 @@
+use anyhow::{Context, Result};
+use cql_stress::distribution::Description;
+
+use super::{
+    fixed::FixedDistributionFactory, Distribution, DistributionFactory, ThreadLocalRandom,
+};
+
+/// Extreme value (Weibull) distribution over the range [min, max].
+/// Based on https://commons.apache.org/proper/commons-math/javadocs/api-3.6.1/src-html/org/apache/commons/math3/distribution/WeibullDistribution.html
+/// and https://github.com/scylladb/scylla-tools-java/blob/master/tools/stress/src/org/apache/cassandra/stress/generate/DistributionOffsetApache.java.
+struct ExtremeDistribution {
+    min: i64,
+    max: i64,
+    shape: f64,
+    scale: f64,
+    rng: ThreadLocalRandom,
+}
+
+impl ExtremeDistribution {
+    fn new(min: i64, max: i64, shape: f64, scale: f64) -> Self {
+        Self {
+            min,
+            max,
+            shape,
+            scale,
+            rng: ThreadLocalRandom::new(),
+        }
+    }
+
+    /// Inversion sampling - the same as the default `sample()` of Apache Commons Math distributions.
+    fn sample(&self) -> f64 {
+        let p = self.rng.get().next_double();
+        inverse_cumulative_probability(p, self.shape, self.scale)
+    }
+}
+
+/// See: https://commons.apache.org/proper/commons-math/javadocs/api-3.6.1/src-html/org/apache/commons/math3/distribution/WeibullDistribution.html#line.227.
+fn inverse_cumulative_probability(p: f64, shape: f64, scale: f64) -> f64 {
+    if p == 0f64 {
+        0f64
+    } else if p == 1f64 {
+        f64::INFINITY
+    } else {
+        scale * (-rust_strictmath::log1p(-p)).powf(1f64 / shape)
+    }
+}
+
+impl Distribution for ExtremeDistribution {
+    fn next_i64(&self) -> i64 {
+        self.min + (self.max - self.min).min(self.sample() as i64)
+    }
+
+    fn next_f64(&self) -> f64 {
+        self.min as f64 + ((self.max - self.min) as f64).min(self.sample())
+    }
+
+    fn set_seed(&self, seed: i64) {
+        self.rng.get().set_seed(seed as u64)
+    }
+
+    fn min_value(&self) -> i64 {
+        self.min
+    }
+
+    fn max_value(&self) -> i64 {
+        self.max
+    }
+}
+
+pub struct ExtremeDistributionFactory {
+    min: i64,
+    max: i64,
+    shape: f64,
+    scale: f64,
+}
+
+impl DistributionFactory for ExtremeDistributionFactory {
+    fn create(&self) -> Box<dyn Distribution> {
+        Box::new(ExtremeDistribution::new(
+            self.min, self.max, self.shape, self.scale,
+        ))
+    }
+}
+
+impl ExtremeDistributionFactory {
+    fn do_parse_from_description(desc: &Description<'_>) -> Result<Box<dyn DistributionFactory>> {
+        // See https://github.com/scylladb/scylla-tools-java/blob/master/tools/stress/src/org/apache/cassandra/stress/settings/OptionDistribution.java#L273.
+        desc.check_argument_count(3)?;
+        let (min, max) = (desc.args[0].parse::<i64>()?, desc.args[1].parse::<i64>()?);
+        anyhow::ensure!(
+            min <= max,
+            "Upper bound ({}) for extreme distribution is lower than the lower bound ({}).",
+            max,
+            min
+        );
+
+        if min == max {
+            return Ok(Box::new(FixedDistributionFactory(min)));
+        }
+
+        let shape = desc.args[2].parse::<f64>()?;
+        anyhow::ensure!(shape > 0f64, "Shape must be positive");
+
+        // Same as for the exponential distribution, the scale is chosen so that
+        // the probability of sampling the max value is roughly sqrt(1/(max-min)).
+        let range = (max - min) as f64;
+        let bound = inverse_cumulative_probability(1f64 - (1f64 / range).sqrt(), shape, 1f64);
+        let scale = range / bound;
+
+        Ok(Box::new(Self {
+            min,
+            max,
+            shape,
+            scale,
+        }))
+    }
+
+    pub fn parse_from_description(desc: Description<'_>) -> Result<Box<dyn DistributionFactory>> {
+        Self::do_parse_from_description(&desc).with_context(|| {
+            format!(
+                "Invalid parameter list for extreme distribution: {:?}",
+                desc.args
+            )
+        })
+    }
+
+    pub fn help_description() -> String {
+        format!(
+            "      {:<36} An extreme value (Weibull) distribution over the range [min..max]",
+            "EXTREME(min..max,shape)"
+        )
+    }
+}
+
+impl std::fmt::Display for ExtremeDistributionFactory {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "EXTREME({}..{},shape={},scale={})",
+            self.min, self.max, self.shape, self.scale
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use cql_stress::distribution::Description;
+
+    use super::ExtremeDistributionFactory;
+
+    fn extreme(args: Vec<&str>) -> anyhow::Result<Box<dyn super::DistributionFactory>> {
+        ExtremeDistributionFactory::parse_from_description(Description {
+            name: "EXTREME",
+            args,
+            inverted: false,
+        })
+    }
+
+    #[test]
+    fn extreme_distribution_test() {
+        let dist = extreme(vec!["1", "1000", "1.5"]).unwrap().create();
+
+        dist.set_seed(0xdeadcafe);
+        let first = (0..1000).map(|_| dist.next_i64()).collect::<Vec<_>>();
+        assert!(first.iter().all(|v| (1..=1000).contains(v)));
+
+        // Sampling is deterministic for the same seed.
+        dist.set_seed(0xdeadcafe);
+        let second = (0..1000).map(|_| dist.next_i64()).collect::<Vec<_>>();
+        assert_eq!(first, second);
+    }
+
+    #[test]
+    fn extreme_distribution_parse_test() {
+        assert_eq!(
+            "FIXED(5)",
+            extreme(vec!["5", "5", "2"]).unwrap().to_string()
+        );
+        assert!(extreme(vec!["1", "10"]).is_err());
+        assert!(extreme(vec!["10", "1", "2"]).is_err());
+        assert!(extreme(vec!["1", "10", "0"]).is_err());
+        assert!(extreme(vec!["1", "10", "abc"]).is_err());
+    }
+}
@@ -0,0 +1,70 @@
+use std::sync::Arc;
+
+use anyhow::Result;
+use cql_stress::key_file::KeyFile;
+
+use super::{Distribution, DistributionFactory};
+
+/// Distribution sampling the values from a file, in the order they appear in it
+/// (see [cql_stress::key_file]). Once the end of the file is reached,
+/// the cycle starts over again, the same as for the sequence distribution.
+///
+/// It's not present in the original cassandra-stress. It's used by `-pop file=`
+/// to replay the partitions of specific seeds.
+pub struct FileDistribution {
+    keys: KeyFile,
+}
+
+impl Distribution for FileDistribution {
+    fn next_i64(&self) -> i64 {
+        // The file contains at least one key, and it's looped.
+        self.keys.next_key().unwrap().pk
+    }
+
+    fn next_f64(&self) -> f64 {
+        self.next_i64() as f64
+    }
+
+    fn set_seed(&self, _seed: i64) {}
+
+    fn min_value(&self) -> i64 {
+        self.keys.min_pk()
+    }
+
+    fn max_value(&self) -> i64 {
+        self.keys.max_pk()
+    }
+}
+
+pub struct FileDistributionFactory {
+    path: Arc<str>,
+}
+
+impl FileDistributionFactory {
+    /// Validates the contents of the file, so that the distributions
+    /// created later don't run into a malformed value.
+    pub fn new(path: &str) -> Result<Self> {
+        let keys = KeyFile::open(path, true)?;
+        anyhow::ensure!(
+            !keys.has_clustering_keys(),
+            "The seeds file {} must contain a single seed per line",
+            path
+        );
+        Ok(Self { path: path.into() })
+    }
+}
+
+impl DistributionFactory for FileDistributionFactory {
+    fn create(&self) -> Box<dyn Distribution> {
+        // Each of the distributions reads the file from the beginning.
+        let keys = KeyFile::open(&*self.path, true)
+            .unwrap_or_else(|err| panic!("Failed to reopen the seeds file: {:#}", err));
+        Box::new(FileDistribution { keys })
+    }
+}
+
+impl std::fmt::Display for FileDistributionFactory {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "FILE({})", self.path)
+    }
+}
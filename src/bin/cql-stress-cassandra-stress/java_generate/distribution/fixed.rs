@@ -26,6 +26,14 @@ impl Distribution for FixedDistribution {
     }
 
     fn set_seed(&self, _seed: i64) {}
+
+    fn min_value(&self) -> i64 {
+        self.value
+    }
+
+    fn max_value(&self) -> i64 {
+        self.value
+    }
 }
 
 pub struct FixedDistributionFactory(pub i64);
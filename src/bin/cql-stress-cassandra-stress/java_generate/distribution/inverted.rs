@@ -0,0 +1,115 @@
+use super::{Distribution, DistributionFactory};
+
+/// Distribution which flips the wrapped distribution about the midpoint of its range,
+/// i.e. the values close to `min` become close to `max` and vice versa.
+/// See: https://github.com/scylladb/scylla-tools-java/blob/master/tools/stress/src/org/apache/cassandra/stress/generate/DistributionInverted.java.
+pub struct InvertedDistribution {
+    wrapped: Box<dyn Distribution>,
+    min: i64,
+    max: i64,
+}
+
+impl InvertedDistribution {
+    pub fn new(wrapped: Box<dyn Distribution>) -> Self {
+        let min = wrapped.min_value();
+        let max = wrapped.max_value();
+        Self { wrapped, min, max }
+    }
+}
+
+impl Distribution for InvertedDistribution {
+    fn next_i64(&self) -> i64 {
+        self.max - (self.wrapped.next_i64() - self.min)
+    }
+
+    fn next_f64(&self) -> f64 {
+        self.max as f64 - (self.wrapped.next_f64() - self.min as f64)
+    }
+
+    fn set_seed(&self, seed: i64) {
+        self.wrapped.set_seed(seed)
+    }
+
+    fn min_value(&self) -> i64 {
+        self.min
+    }
+
+    fn max_value(&self) -> i64 {
+        self.max
+    }
+}
+
+pub struct InvertedDistributionFactory(pub Box<dyn DistributionFactory>);
+
+impl DistributionFactory for InvertedDistributionFactory {
+    fn create(&self) -> Box<dyn Distribution> {
+        Box::new(InvertedDistribution::new(self.0.create()))
+    }
+}
+
+impl std::fmt::Display for InvertedDistributionFactory {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "~{}", self.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::java_generate::distribution::{
+        sequence::SeqDistributionFactory, uniform::UniformDistributionFactory, Distribution,
+        DistributionFactory,
+    };
+    use cql_stress::distribution::Description;
+
+    use super::InvertedDistributionFactory;
+
+    #[test]
+    fn inverted_seq_distribution_test() {
+        let seq = SeqDistributionFactory::parse_from_description(Description {
+            name: "SEQ",
+            args: vec!["1", "10"],
+            inverted: false,
+        })
+        .unwrap();
+        let dist = InvertedDistributionFactory(seq).create();
+
+        assert_eq!(1, dist.min_value());
+        assert_eq!(10, dist.max_value());
+        let values = (0..10).map(|_| dist.next_i64()).collect::<Vec<_>>();
+        assert_eq!((1..=10).rev().collect::<Vec<_>>(), values);
+    }
+
+    #[test]
+    fn inverted_uniform_distribution_test() {
+        let factory = || {
+            UniformDistributionFactory::parse_from_description(Description {
+                name: "UNIFORM",
+                args: vec!["1", "100"],
+                inverted: false,
+            })
+            .unwrap()
+        };
+        let dist = factory().create();
+        let inverted = InvertedDistributionFactory(factory()).create();
+
+        dist.set_seed(0xdeadcafe);
+        inverted.set_seed(0xdeadcafe);
+        for _ in 0..100 {
+            assert_eq!(101 - dist.next_i64(), inverted.next_i64());
+        }
+    }
+
+    #[test]
+    fn inverted_distribution_display_test() {
+        let seq = SeqDistributionFactory::parse_from_description(Description {
+            name: "SEQ",
+            args: vec!["1", "10"],
+            inverted: false,
+        })
+        .unwrap();
+        assert_eq!(
+            format!("~{}", seq),
+            InvertedDistributionFactory(seq).to_string()
+        );
+    }
+}
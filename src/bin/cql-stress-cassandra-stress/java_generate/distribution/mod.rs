@@ -1,17 +1,24 @@
 use std::{
     cell::{RefCell, RefMut},
+    sync::OnceLock,
     time::{SystemTime, UNIX_EPOCH},
 };
 
+use cql_stress::seed::SeedSequence;
 use thread_local::ThreadLocal;
 
 use super::Random;
 
 pub mod enumerated;
+pub mod exponential;
+pub mod extreme;
+pub mod file;
 pub mod fixed;
+pub mod inverted;
 pub mod normal;
 pub mod sequence;
 pub mod uniform;
+pub mod zipf;
 
 /// A distribution that atomically performs the operations.
 /// It implies that the distribution can be safely used in a multi-threaded environment.
@@ -19,6 +26,18 @@ pub trait Distribution: Send + Sync {
     fn next_i64(&self) -> i64;
     fn next_f64(&self) -> f64;
     fn set_seed(&self, seed: i64);
+    fn min_value(&self) -> i64;
+    fn max_value(&self) -> i64;
+}
+
+/// Seeds of the thread-local generators, set via [set_base_seed].
+static SEEDS: OnceLock<SeedSequence> = OnceLock::new();
+
+/// Seeds the thread-local generators of the distributions created
+/// from now on with the seeds derived from `seed`, instead of the current time.
+/// Has no effect if called more than once.
+pub fn set_base_seed(seed: u64) {
+    let _ = SEEDS.set(SeedSequence::new(Some(seed)));
 }
 
 /// A thread_local wrapper for [java_random::Random].
@@ -37,12 +56,14 @@ impl ThreadLocalRandom {
     fn get(&self) -> RefMut<'_, Random> {
         self.rng
             .get_or(|| {
-                RefCell::new(Random::with_seed(
-                    SystemTime::now()
+                let seed = match SEEDS.get() {
+                    Some(seeds) => seeds.next_seed(),
+                    None => SystemTime::now()
                         .duration_since(UNIX_EPOCH)
                         .map(|duration| duration.as_millis() as u64)
                         .unwrap_or_default(),
-                ))
+                };
+                RefCell::new(Random::with_seed(seed))
             })
             .borrow_mut()
     }
@@ -56,6 +56,14 @@ impl Distribution for NormalDistribution {
     fn set_seed(&self, seed: i64) {
         self.rng.get().set_seed(seed as u64)
     }
+
+    fn min_value(&self) -> i64 {
+        self.min
+    }
+
+    fn max_value(&self) -> i64 {
+        self.max
+    }
 }
 
 pub struct NormalDistributionFactory {
@@ -103,6 +111,10 @@ impl NormalDistributionFactory {
                 let stdevs_to_edge = maybe_stdvrng
                     .map(|s| s.parse::<f64>())
                     .unwrap_or(Ok(3f64))?;
+                anyhow::ensure!(
+                    stdevs_to_edge.is_finite() && stdevs_to_edge > 0f64,
+                    "The number of standard deviations (stdvrng) must be positive"
+                );
 
                 let mean = ((min + max) as f64) / 2f64;
                 let stdev = (((max - min) as f64) / 2f64) / stdevs_to_edge;
@@ -267,4 +279,75 @@ mod tests {
             result_seed_max_i64
         );
     }
+
+    fn gaussian(args: Vec<&str>) -> Box<dyn super::Distribution> {
+        NormalDistributionFactory::parse_from_description(Description {
+            name: "GAUSSIAN",
+            args,
+            inverted: false,
+        })
+        .unwrap()
+        .create()
+    }
+
+    fn sample_with_seed(dist: &dyn super::Distribution, seed: i64) -> Vec<i64> {
+        dist.set_seed(seed);
+        (0..1000).map(|_| dist.next_i64()).collect()
+    }
+
+    #[test]
+    fn gaussian_distribution_stdvrng_parity_test() {
+        // All of the forms below describe the same distribution, so they must produce
+        // identical sequences - the two-argument form was verified against Java above.
+        for seed in [0, 0xdeadcafe, i64::MIN, i64::MAX] {
+            let expected = sample_with_seed(gaussian(vec!["1", "1000000000"]).as_ref(), seed);
+            assert_eq!(
+                expected,
+                sample_with_seed(gaussian(vec!["1", "1000000000", "3"]).as_ref(), seed)
+            );
+            assert_eq!(
+                expected,
+                sample_with_seed(
+                    gaussian(vec!["1", "1000000000", "500000000.5", "166666666.5"]).as_ref(),
+                    seed
+                )
+            );
+
+            // mean=(min+max)/2, stdev=(mean-min)/stdvrng
+            assert_eq!(
+                sample_with_seed(gaussian(vec!["1", "1000000000", "2"]).as_ref(), seed),
+                sample_with_seed(
+                    gaussian(vec!["1", "1000000000", "500000000.5", "249999999.75"]).as_ref(),
+                    seed
+                )
+            );
+        }
+    }
+
+    #[test]
+    fn gaussian_distribution_parse_test() {
+        let parse = |args: Vec<&str>| {
+            NormalDistributionFactory::parse_from_description(Description {
+                name: "GAUSSIAN",
+                args,
+                inverted: false,
+            })
+        };
+
+        assert_eq!(
+            "GAUSSIAN(0..100,mean=50,stdev=10)",
+            parse(vec!["0", "100", "5"]).unwrap().to_string()
+        );
+        assert_eq!(
+            "GAUSSIAN(0..100,mean=20,stdev=5)",
+            parse(vec!["0", "100", "20", "5"]).unwrap().to_string()
+        );
+        assert!(parse(vec!["0"]).is_err());
+        assert!(parse(vec!["100", "0"]).is_err());
+        assert!(parse(vec!["0", "100", "0"]).is_err());
+        assert!(parse(vec!["0", "100", "-3"]).is_err());
+        assert!(parse(vec!["0", "100", "abc"]).is_err());
+        assert!(parse(vec!["0", "100", "50", "0"]).is_err());
+        assert!(parse(vec!["0", "100", "50", "10", "1"]).is_err());
+    }
 }
@@ -54,6 +54,14 @@ impl Distribution for SeqDistribution {
     fn set_seed(&self, seed: i64) {
         self.seed.store(seed, Ordering::Relaxed);
     }
+
+    fn min_value(&self) -> i64 {
+        self.start
+    }
+
+    fn max_value(&self) -> i64 {
+        self.end
+    }
 }
 
 pub struct SeqDistributionFactory {
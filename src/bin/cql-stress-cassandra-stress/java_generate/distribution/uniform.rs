@@ -49,6 +49,14 @@ impl Distribution for UniformDistribution {
     fn set_seed(&self, seed: i64) {
         self.rng.get().set_seed(seed as u64)
     }
+
+    fn min_value(&self) -> i64 {
+        self.lower as i64
+    }
+
+    fn max_value(&self) -> i64 {
+        self.upper as i64
+    }
 }
 
 pub struct UniformDistributionFactory {
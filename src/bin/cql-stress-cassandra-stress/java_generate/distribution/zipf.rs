@@ -0,0 +1,229 @@
+use anyhow::{Context, Result};
+use cql_stress::distribution::Description;
+
+use super::{
+    fixed::FixedDistributionFactory, Distribution, DistributionFactory, ThreadLocalRandom,
+};
+
+/// Zipf distribution over the range [min, max]. The k-th value of the range
+/// (counting from 1) is sampled with probability proportional to 1/k^exponent,
+/// so the values close to `min` are the hot ones.
+/// Based on https://commons.apache.org/proper/commons-math/javadocs/api-3.6.1/src-html/org/apache/commons/math3/distribution/ZipfDistribution.html.
+struct ZipfDistribution {
+    min: i64,
+    max: i64,
+    sampler: RejectionInversionZipfSampler,
+    rng: ThreadLocalRandom,
+}
+
+impl ZipfDistribution {
+    fn new(min: i64, max: i64, exponent: f64) -> Self {
+        Self {
+            min,
+            max,
+            sampler: RejectionInversionZipfSampler::new(max - min + 1, exponent),
+            rng: ThreadLocalRandom::new(),
+        }
+    }
+}
+
+impl Distribution for ZipfDistribution {
+    fn next_i64(&self) -> i64 {
+        self.min + self.sampler.sample(&mut || self.rng.get().next_double()) - 1
+    }
+
+    fn next_f64(&self) -> f64 {
+        self.next_i64() as f64
+    }
+
+    fn set_seed(&self, seed: i64) {
+        self.rng.get().set_seed(seed as u64)
+    }
+
+    fn min_value(&self) -> i64 {
+        self.min
+    }
+
+    fn max_value(&self) -> i64 {
+        self.max
+    }
+}
+
+/// Rejection-inversion sampler by Hörmann and Derflinger, exactly as in Apache Commons Math.
+/// Samples values from range [1, number_of_elements].
+/// See: https://commons.apache.org/proper/commons-math/javadocs/api-3.6.1/src-html/org/apache/commons/math3/distribution/ZipfDistribution.html#line.289.
+struct RejectionInversionZipfSampler {
+    exponent: f64,
+    number_of_elements: i64,
+    h_integral_x1: f64,
+    h_integral_number_of_elements: f64,
+    s: f64,
+}
+
+impl RejectionInversionZipfSampler {
+    fn new(number_of_elements: i64, exponent: f64) -> Self {
+        let mut sampler = Self {
+            exponent,
+            number_of_elements,
+            h_integral_x1: 0f64,
+            h_integral_number_of_elements: 0f64,
+            s: 0f64,
+        };
+        sampler.h_integral_x1 = sampler.h_integral(1.5) - 1f64;
+        sampler.h_integral_number_of_elements = sampler.h_integral(number_of_elements as f64 + 0.5);
+        sampler.s = 2f64 - sampler.h_integral_inverse(sampler.h_integral(2.5) - sampler.h(2f64));
+        sampler
+    }
+
+    fn sample(&self, next_double: &mut impl FnMut() -> f64) -> i64 {
+        loop {
+            let u = self.h_integral_number_of_elements
+                + next_double() * (self.h_integral_x1 - self.h_integral_number_of_elements);
+            let x = self.h_integral_inverse(u);
+            let k = ((x + 0.5) as i64).clamp(1, self.number_of_elements);
+
+            if k as f64 - x <= self.s || u >= self.h_integral(k as f64 + 0.5) - self.h(k as f64) {
+                return k;
+            }
+        }
+    }
+
+    fn h_integral(&self, x: f64) -> f64 {
+        let log_x = rust_strictmath::log(x);
+        helper2((1f64 - self.exponent) * log_x) * log_x
+    }
+
+    fn h(&self, x: f64) -> f64 {
+        (-self.exponent * rust_strictmath::log(x)).exp()
+    }
+
+    fn h_integral_inverse(&self, x: f64) -> f64 {
+        let t = (x * (1f64 - self.exponent)).max(-1f64);
+        (helper1(t) * x).exp()
+    }
+}
+
+/// log(1+x)/x, with the Taylor expansion used close to 0.
+fn helper1(x: f64) -> f64 {
+    if x.abs() > 1e-8 {
+        rust_strictmath::log1p(x) / x
+    } else {
+        1f64 - x * (0.5 - x * (1f64 / 3f64 - 0.25 * x))
+    }
+}
+
+/// (exp(x)-1)/x, with the Taylor expansion used close to 0.
+fn helper2(x: f64) -> f64 {
+    if x.abs() > 1e-8 {
+        rust_strictmath::expm1(x) / x
+    } else {
+        1f64 + x * 0.5 * (1f64 + x * 1f64 / 3f64 * (1f64 + 0.25 * x))
+    }
+}
+
+pub struct ZipfDistributionFactory {
+    min: i64,
+    max: i64,
+    exponent: f64,
+}
+
+impl DistributionFactory for ZipfDistributionFactory {
+    fn create(&self) -> Box<dyn Distribution> {
+        Box::new(ZipfDistribution::new(self.min, self.max, self.exponent))
+    }
+}
+
+impl ZipfDistributionFactory {
+    fn do_parse_from_description(desc: &Description<'_>) -> Result<Box<dyn DistributionFactory>> {
+        desc.check_argument_count(3)?;
+        let (min, max) = (desc.args[0].parse::<i64>()?, desc.args[1].parse::<i64>()?);
+        anyhow::ensure!(
+            min <= max,
+            "Upper bound ({}) for zipf distribution is lower than the lower bound ({}).",
+            max,
+            min
+        );
+
+        let exponent = desc.args[2].parse::<f64>()?;
+        anyhow::ensure!(
+            exponent.is_finite() && exponent > 0f64,
+            "Exponent must be positive"
+        );
+
+        if min == max {
+            return Ok(Box::new(FixedDistributionFactory(min)));
+        }
+
+        Ok(Box::new(Self { min, max, exponent }))
+    }
+
+    pub fn parse_from_description(desc: Description<'_>) -> Result<Box<dyn DistributionFactory>> {
+        Self::do_parse_from_description(&desc).with_context(|| {
+            format!(
+                "Invalid parameter list for zipf distribution: {:?}",
+                desc.args
+            )
+        })
+    }
+
+    pub fn help_description() -> String {
+        format!(
+            "      {:<36} A zipf distribution over the range [min..max], where the values close to min are the most frequent",
+            "ZIPF(min..max,exponent)"
+        )
+    }
+}
+
+impl std::fmt::Display for ZipfDistributionFactory {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "ZIPF({}..{},exponent={})",
+            self.min, self.max, self.exponent
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use cql_stress::distribution::Description;
+
+    use super::ZipfDistributionFactory;
+
+    fn zipf(args: Vec<&str>) -> anyhow::Result<Box<dyn super::DistributionFactory>> {
+        ZipfDistributionFactory::parse_from_description(Description {
+            name: "ZIPF",
+            args,
+            inverted: false,
+        })
+    }
+
+    #[test]
+    fn zipf_distribution_test() {
+        let dist = zipf(vec!["1", "100", "1.2"]).unwrap().create();
+
+        dist.set_seed(0xdeadcafe);
+        let first = (0..10000).map(|_| dist.next_i64()).collect::<Vec<_>>();
+        assert!(first.iter().all(|v| (1..=100).contains(v)));
+
+        // The frequency of the values decreases with the rank.
+        let count = |value: i64| first.iter().filter(|v| **v == value).count();
+        assert!(count(1) > count(2));
+        assert!(count(2) > count(10));
+        assert!(count(10) > count(100));
+
+        // Sampling is deterministic for the same seed.
+        dist.set_seed(0xdeadcafe);
+        let second = (0..10000).map(|_| dist.next_i64()).collect::<Vec<_>>();
+        assert_eq!(first, second);
+    }
+
+    #[test]
+    fn zipf_distribution_parse_test() {
+        assert_eq!("FIXED(5)", zipf(vec!["5", "5", "1.5"]).unwrap().to_string());
+        assert!(zipf(vec!["1", "10"]).is_err());
+        assert!(zipf(vec!["10", "1", "1.5"]).is_err());
+        assert!(zipf(vec!["1", "10", "0"]).is_err());
+        assert!(zipf(vec!["1", "10", "abc"]).is_err());
+    }
+}
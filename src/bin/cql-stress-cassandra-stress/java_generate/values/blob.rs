@@ -1,5 +1,6 @@
 use std::cmp::min;
 
+use cql_stress::compressible::make_compressible;
 use scylla::frame::response::result::CqlValue;
 
 use super::ValueGenerator;
@@ -8,9 +9,12 @@ use crate::java_generate::faster_random::FasterRandom;
 
 /// Blob generator based on c-s Bytes generator.
 /// See https://github.com/scylladb/scylla-tools-java/blob/master/tools/stress/src/org/apache/cassandra/stress/generate/values/Bytes.java#L41
+/// With a non-zero `compressible` fraction (`-col compressible=`), the bytes
+/// are made compressible (see [cql_stress::compressible]) after generation.
 #[derive(Default)]
 pub struct Blob {
     rng: FasterRandom,
+    compressible: f64,
 }
 
 impl ValueGenerator for Blob {
@@ -19,12 +23,46 @@ impl ValueGenerator for Blob {
         identity_distribution: &mut dyn Distribution,
         size_distribution: &mut dyn Distribution,
     ) -> CqlValue {
+        let mut result = Vec::new();
+        self.fill(identity_distribution, size_distribution, &mut result);
+        CqlValue::Blob(result)
+    }
+
+    fn generate_into(
+        &mut self,
+        identity_distribution: &mut dyn Distribution,
+        size_distribution: &mut dyn Distribution,
+        value: &mut CqlValue,
+    ) {
+        match value {
+            CqlValue::Blob(result) => self.fill(identity_distribution, size_distribution, result),
+            _ => *value = self.generate(identity_distribution, size_distribution),
+        }
+    }
+}
+
+impl Blob {
+    pub fn with_compressible(compressible: f64) -> Self {
+        Self {
+            rng: FasterRandom::default(),
+            compressible,
+        }
+    }
+
+    // Replaces the contents of `result` with the generated bytes.
+    fn fill(
+        &mut self,
+        identity_distribution: &mut dyn Distribution,
+        size_distribution: &mut dyn Distribution,
+        result: &mut Vec<u8>,
+    ) {
         let seed = identity_distribution.next_i64();
         size_distribution.set_seed(seed);
         self.rng.set_seed(!seed);
         let size = size_distribution.next_i64() as usize;
 
-        let mut result = Vec::with_capacity(size);
+        result.clear();
+        result.reserve(size);
         let mut i = 0;
         while i < size {
             let v = self.rng.next_i64().to_le_bytes();
@@ -32,8 +70,9 @@ impl ValueGenerator for Blob {
             result.extend_from_slice(&v[0..n]);
             i += n;
         }
-
-        CqlValue::Blob(result)
+        if self.compressible > 0.0 {
+            make_compressible(result, self.compressible);
+        }
     }
 }
 
@@ -111,4 +150,32 @@ mod tests {
         let row5 = gen.generate();
         assert_eq!(to_vec_i8(row5), vec![-36, -98, 27, -16, 94]);
     }
+
+    #[test]
+    fn blob_generate_into_test() {
+        let config = GeneratorConfig::new(
+            "randomstrC0",
+            None,
+            Some(Box::new(FixedDistribution::new(5))),
+        );
+        let mut gen = Generator::new(Box::<Blob>::default(), config, String::from("C0"));
+
+        let mut value = CqlValue::Blob(Vec::with_capacity(64));
+        let buffer_ptr = value.as_blob().unwrap().as_ptr();
+        for seed in [1338786723438483, 2138651199823976, 2158326113993629] {
+            gen.set_seed(seed);
+            let expected = gen.generate();
+            gen.set_seed(seed);
+            gen.generate_into(&mut value);
+            assert_eq!(expected, value);
+            // The buffer of the previous value is reused.
+            assert_eq!(buffer_ptr, value.as_blob().unwrap().as_ptr());
+        }
+
+        // Values of other types are replaced.
+        let mut value = CqlValue::Int(1);
+        gen.set_seed(1338786723438483);
+        gen.generate_into(&mut value);
+        assert_eq!(to_vec_i8(value), vec![-123, 24, 47, -33, -25]);
+    }
 }
@@ -28,6 +28,8 @@ pub mod text;
 pub mod uuid;
 #[cfg(feature = "user-profile")]
 pub mod varint;
+#[cfg(feature = "user-profile")]
+pub mod words;
 
 pub use blob::Blob;
 pub use hex_blob::HexBlob;
@@ -126,6 +128,15 @@ impl Generator {
         )
     }
 
+    /// Same as [Generator::generate], but reuses the buffers of `value`.
+    pub fn generate_into(&mut self, value: &mut CqlValue) {
+        self.gen.generate_into(
+            self.identity_distribution.as_mut(),
+            self.size_distribution.as_mut(),
+            value,
+        )
+    }
+
     #[cfg(feature = "user-profile")]
     pub fn get_col_name(&self) -> &str {
         &self.col_name
@@ -152,6 +163,10 @@ pub struct GeneratorConfig {
 impl GeneratorConfig {
     /// As in c-s, receive some seed string which is hashed to retrieve the
     /// salt value. See https://github.com/scylladb/scylla-tools-java/blob/master/tools/stress/src/org/apache/cassandra/stress/generate/values/GeneratorConfig.java#L39.
+    ///
+    /// The string is always hashed with murmur3, whatever the partitioner
+    /// of the cluster (`-mode partitioner=`), so the generated values
+    /// don't depend on the partitioner, the same as in c-s.
     pub fn new(
         seed_str: &str,
         identity_distribution: Option<Box<dyn Distribution>>,
@@ -174,6 +189,17 @@ pub trait ValueGenerator: Send + Sync + 'static {
         identity_distribution: &mut dyn Distribution,
         size_distribution: &mut dyn Distribution,
     ) -> CqlValue;
+
+    /// Generates the value into `value`, reusing its buffers if possible.
+    /// The generated value is the same as the one returned by `generate`.
+    fn generate_into(
+        &mut self,
+        identity_distribution: &mut dyn Distribution,
+        size_distribution: &mut dyn Distribution,
+        value: &mut CqlValue,
+    ) {
+        *value = self.generate(identity_distribution, size_distribution);
+    }
 }
 
 /// This trait provides an infallible way to create a corresponding
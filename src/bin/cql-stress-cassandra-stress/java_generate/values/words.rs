@@ -0,0 +1,137 @@
+use std::sync::Arc;
+
+use scylla::frame::response::result::CqlValue;
+
+use crate::java_generate::distribution::{Distribution, DistributionFactory};
+
+use super::{ValueGenerator, ValueGeneratorFactory};
+
+/// The most frequent English words, from the most frequent one.
+/// With a zipf distribution of the ranks, the generated text resembles
+/// a natural language text, and it compresses similarly.
+const DICTIONARY: &[&str] = &[
+    "the", "of", "and", "to", "a", "in", "is", "it", "you", "that", "he", "was", "for", "on",
+    "are", "with", "as", "his", "they", "be", "at", "one", "have", "this", "from", "or", "had",
+    "by", "not", "word", "but", "what", "some", "we", "can", "out", "other", "were", "all",
+    "there", "when", "up", "use", "your", "how", "said", "an", "each", "she", "which", "do",
+    "their", "time", "if", "will", "way", "about", "many", "then", "them", "write", "would",
+    "like", "so", "these", "her", "long", "make", "thing", "see", "him", "two", "has", "look",
+    "more", "day", "could", "go", "come", "did", "number", "sound", "no", "most", "people", "my",
+    "over", "know", "water", "than", "call", "first", "who", "may", "down", "side", "been", "now",
+    "find", "any", "new", "work", "part", "take", "get", "place", "made", "live", "where", "after",
+    "back", "little", "only", "round", "man", "year", "came", "show", "every", "good", "me",
+    "give", "our", "under", "name", "very", "through", "just", "form", "sentence", "great",
+    "think", "say", "help", "low", "line", "differ", "turn", "cause", "much", "mean", "before",
+    "move", "right", "boy", "old", "too", "same", "tell", "does", "set", "three", "want", "air",
+    "well", "also", "play", "small", "end", "put", "home", "read", "hand", "port", "large",
+    "spell", "add", "even", "land", "here", "must", "big", "high", "such", "follow", "act", "why",
+    "ask", "men", "change", "went", "light", "kind", "off", "need", "house", "picture", "try",
+    "us", "again", "animal", "point", "mother", "world", "near", "build", "self", "earth",
+    "father", "head", "stand", "own", "page", "should", "country", "found", "answer", "school",
+    "grow", "study", "still", "learn", "plant", "cover", "food", "sun", "four", "between", "state",
+    "keep", "eye", "never", "last", "let", "thought", "city", "tree", "cross", "farm", "hard",
+    "start", "might", "story", "saw", "far", "sea", "draw", "left", "late", "run", "while",
+    "press", "close", "night", "real", "life", "few", "north",
+];
+
+/// Text generator producing the words of [DICTIONARY] separated by spaces,
+/// instead of the random characters of [super::text::Text].
+/// The ranks of the words (counting from the minimum of the distribution)
+/// are sampled from `words`, wrapping around the dictionary.
+/// As in [super::text::Text], the text is truncated to the sampled size.
+pub struct Words {
+    words: Box<dyn Distribution>,
+}
+
+impl Words {
+    fn next_word(&self) -> &'static str {
+        let rank = self.words.next_i64() - self.words.min_value();
+        DICTIONARY[rank.rem_euclid(DICTIONARY.len() as i64) as usize]
+    }
+}
+
+impl ValueGenerator for Words {
+    fn generate(
+        &mut self,
+        identity_distribution: &mut dyn Distribution,
+        size_distribution: &mut dyn Distribution,
+    ) -> CqlValue {
+        let seed = identity_distribution.next_i64();
+        size_distribution.set_seed(seed);
+        self.words.set_seed(!seed);
+        let size = size_distribution.next_i64().max(0) as usize;
+
+        let mut result = String::with_capacity(size);
+        while result.len() < size {
+            if !result.is_empty() {
+                result.push(' ');
+            }
+            result.push_str(self.next_word());
+        }
+        // The dictionary consists of ASCII words, so any index is a char boundary.
+        result.truncate(size);
+
+        CqlValue::Text(result)
+    }
+}
+
+pub struct WordsFactory(pub Arc<dyn DistributionFactory>);
+
+impl ValueGeneratorFactory for WordsFactory {
+    fn create(&self) -> Box<dyn ValueGenerator> {
+        Box::new(Words {
+            words: self.0.create(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use crate::java_generate::{
+        distribution::{
+            fixed::{FixedDistribution, FixedDistributionFactory},
+            uniform::UniformDistributionFactory,
+            DistributionFactory,
+        },
+        values::{Generator, GeneratorConfig, ValueGeneratorFactory},
+    };
+
+    use super::{WordsFactory, DICTIONARY};
+
+    fn words_generator(words: Arc<dyn DistributionFactory>, size: i64) -> Generator {
+        let config = GeneratorConfig::new(
+            "randomstrC0",
+            None,
+            Some(Box::new(FixedDistribution::new(size))),
+        );
+        Generator::new(WordsFactory(words).create(), config, String::from("C0"))
+    }
+
+    #[test]
+    fn words_generator_test() {
+        let words = UniformDistributionFactory::new(1.0, 1000.0).unwrap();
+        let mut gen = words_generator(Arc::new(words), 100);
+        gen.set_seed(0);
+        let texts = (0..100)
+            .map(|_| gen.generate().into_string().unwrap())
+            .collect::<Vec<_>>();
+        for text in texts.iter() {
+            assert_eq!(100, text.len());
+            // All of the words, except for the truncated last one, come from the dictionary.
+            let words = text.split(' ').collect::<Vec<_>>();
+            assert!(words[..words.len() - 1]
+                .iter()
+                .all(|word| DICTIONARY.contains(word)));
+        }
+        // The values are generated deterministically from the seeds.
+        gen.set_seed(0);
+        assert_eq!(texts[0], gen.generate().into_string().unwrap());
+
+        // The minimum of the distribution is the rank of the first word.
+        let mut gen = words_generator(Arc::new(FixedDistributionFactory(5)), 11);
+        gen.set_seed(0);
+        assert_eq!("the the the", gen.generate().into_string().unwrap());
+    }
+}
@@ -11,131 +11,430 @@ extern crate lazy_static;
 
 use crate::{
     operation::{RegularReadOperationFactory, RowGeneratorFactory},
-    settings::{parse_cassandra_stress_args, Command, ThreadsInfo},
+    settings::{parse_cassandra_stress_args, Command, OutputLevel, ThreadsInfo},
 };
 use anyhow::{Context, Result};
 use cql_stress::{
-    configuration::{Configuration, OperationFactory},
+    clock,
+    cluster_info::ClusterInfo,
+    configuration::{self, Configuration, OperationFactory, StreamConfiguration},
+    control_file,
+    driver_metrics::{DriverMetricsSampler, SAMPLING_PERIOD},
+    dry_run::print_operations,
+    error_log::ErrorLog,
+    exit_code::ExitCode,
+    latency_target::{self, LatencyTargetController},
     run::RunController,
     sharded_stats::Stats as _,
     sharded_stats::StatsFactory as _,
+    signals::{DumpSignals, StopSignals},
+    stats_report::StatsReporter,
+    trace_log::TraceLog,
+    version::{features_report, flag_requested, BuildInfo, LIST_FEATURES_FLAG, VERSION_JSON_FLAG},
 };
 #[cfg(feature = "user-profile")]
 use operation::UserOperationFactory;
 use operation::{
-    CounterReadOperationFactory, CounterWriteOperationFactory, MixedOperationFactory,
+    CounterReadOperationFactory, CounterWriteOperationFactory, IndexReadOperationFactory,
+    MixedOperationFactory, ReadModifyWriteOperationFactory, ScanOperationFactory,
     WriteOperationFactory,
 };
-use scylla::{ExecutionProfile, Session, SessionBuilder};
-use stats::{ShardedStats, StatsFactory, StatsPrinter};
-use std::{env, sync::Arc, time::Duration};
+use scylla::{
+    transport::{
+        session::PoolSize,
+        session_builder::{GenericSessionBuilder, SessionBuilderKind},
+    },
+    CloudSessionBuilder, ExecutionProfile, Session, SessionBuilder,
+};
+use stats::{HeatmapLog, ShardedStats, Stats, StatsFactory, StatsPrinter};
+use std::{env, sync::Arc};
+use tokio::time::Instant;
 use tracing_subscriber::EnvFilter;
 
 use settings::{CassandraStressParsingResult, CassandraStressSettings};
 
 #[tokio::main]
-async fn main() -> Result<()> {
+async fn main() -> std::process::ExitCode {
     tracing_subscriber::fmt()
         .with_ansi(false)
         .with_env_filter(EnvFilter::try_from_default_env().unwrap_or(EnvFilter::new("warn")))
         .init();
 
+    if flag_requested(env::args(), VERSION_JSON_FLAG) {
+        println!("{}", BuildInfo::current().to_json());
+        return ExitCode::Success.into();
+    }
+    if flag_requested(env::args(), LIST_FEATURES_FLAG) {
+        print!("{}", features_report());
+        return ExitCode::Success.into();
+    }
+
     let settings = match parse_cassandra_stress_args(env::args()) {
         // Special commands: help, print, version
-        Ok(CassandraStressParsingResult::SpecialCommand) => return Ok(()),
+        Ok(CassandraStressParsingResult::SpecialCommand) => return ExitCode::Success.into(),
         Ok(CassandraStressParsingResult::Workload(payload)) => Arc::new(*payload),
         Err(e) => {
             // For some reason cassandra-stress writes all parsing-related
             // error messages to stdout. We will follow the same approach.
             println!("\n{:?}", e);
-            return Err(anyhow::anyhow!("Failed to parse CLI arguments."));
+            let err = anyhow::anyhow!("Failed to parse CLI arguments.");
+            return ExitCode::InvalidArguments.with_error(&err).into();
         }
     };
 
-    settings.print_settings();
+    let code = match run_benchmark(settings).await {
+        Ok(code) => code,
+        Err(err) => ExitCode::OtherError.with_error(&err),
+    };
+    code.into()
+}
+
+async fn run_benchmark(settings: Arc<CassandraStressSettings>) -> Result<ExitCode> {
+    if settings.log.level != OutputLevel::Minimal {
+        settings.print_settings();
+    }
+
+    if let Some(seed) = settings.seed.seed {
+        java_generate::distribution::set_base_seed(seed);
+    }
+
+    if let Some(operation_count) = settings.dry_run.operation_count {
+        let mut renderers =
+            match operation::create_renderers(&settings).context("Failed to prepare the dry run") {
+                Ok(renderers) => renderers,
+                Err(err) => return Ok(ExitCode::PreparationFailed.with_error(&err)),
+            };
+        println!();
+        print_operations(
+            &mut renderers,
+            operation_count.get() as u64,
+            &mut std::io::stdout(),
+        )?;
+        return Ok(ExitCode::Success);
+    }
 
     let stats_factory = Arc::new(StatsFactory::new(&settings));
-    let sharded_stats = Arc::new(ShardedStats::new(Arc::clone(&stats_factory)));
 
-    let run_config = prepare_run(Arc::clone(&settings), Arc::clone(&sharded_stats))
-        .await
-        .context("Failed to prepare benchmark")?;
+    let metadata = settings.run_metadata();
+    let mut error_log = match &settings.log.errors_file {
+        Some(path) => {
+            let (log, writer) = ErrorLog::new(path, settings.log.errors_rate, &metadata).await?;
+            Some((log, tokio::task::spawn(writer.run())))
+        }
+        None => None,
+    };
+    let mut trace_log = match &settings.log.trace_file {
+        Some(path) => {
+            let (log, writer) = TraceLog::new(path, settings.log.trace_ratio, &metadata).await?;
+            Some((log, tokio::task::spawn(writer.run())))
+        }
+        None => None,
+    };
 
+    let (run_config, stream_stats, session) = match prepare_run(
+        Arc::clone(&settings),
+        Arc::clone(&stats_factory),
+        error_log.as_ref().map(|(log, _)| Arc::clone(log)),
+        trace_log.as_ref().map(|(log, _)| Arc::clone(log)),
+    )
+    .await
+    .context("Failed to prepare benchmark")
+    {
+        Ok(prepared) => prepared,
+        Err(err) => return Ok(ExitCode::PreparationFailed.with_error(&err)),
+    };
+
+    let stream_names = run_config
+        .streams
+        .iter()
+        .map(|stream| stream.name.clone())
+        .collect::<Vec<_>>();
     let mut combined_stats = stats_factory.create();
+    let mut combined_stream_stats: Vec<Stats> = stream_stats
+        .iter()
+        .map(|_| stats_factory.create())
+        .collect();
+
+    let stop_signals = StopSignals::new()?;
+    let mut dump_signals = DumpSignals::new()?;
+
+    let cluster_info = if settings.log.cluster_info {
+        let info = ClusterInfo::fetch(&session).await;
+        info.write("Cluster at the start", &mut std::io::stdout())?;
+        Some(info)
+    } else {
+        None
+    };
 
     let (ctrl, run_finished) = cql_stress::run::run(run_config);
+    let ctrl = Arc::new(ctrl);
 
-    // Run a background task waiting for a stop-signal (Ctrl+C).
-    tokio::task::spawn(stop_on_signal(ctrl));
+    // Run a background task waiting for a stop-signal (Ctrl+C or SIGTERM).
+    tokio::task::spawn(stop_on_signal(Arc::clone(&ctrl), stop_signals));
+    if let Some(path) = &settings.control_file.path {
+        control_file::watch(path.into(), &ctrl);
+    }
 
-    let mut printer = StatsPrinter::new();
+    let mut printer = StatsPrinter::new(settings.log.level);
+    let mut heatmap = match &settings.log.heatmap_file {
+        Some(path) => Some(HeatmapLog::create(path, &stats_factory, &metadata).await?),
+        None => None,
+    };
+    let mut reporter = settings.report.target.clone().map(StatsReporter::spawn);
+    let driver_metrics = (settings.log.level == OutputLevel::Verbose)
+        .then(|| DriverMetricsSampler::spawn(vec![Arc::clone(&session)], SAMPLING_PERIOD));
+    let mut latency_target = match settings.rate.threads_info {
+        ThreadsInfo::AutoLatency {
+            percentile, target, ..
+        } => Some((
+            percentile,
+            LatencyTargetController::new(target, latency_target::INITIAL_RATE),
+        )),
+        _ => None,
+    };
+    let mut interval_start = Instant::now();
 
-    // TODO: change the interval based on -log option (when supported).
-    let mut ticker = tokio::time::interval(Duration::from_secs(1));
+    // The intervals end at the wall-clock multiples of their length
+    // (e.g. at full seconds), so the first ones are shorter.
+    let mut ticker = clock::aligned_interval(settings.log.interval);
+    let mut summary_ticker = settings.log.summary_interval.map(clock::aligned_interval);
+    let mut flush_ticker = settings.log.flush_interval.map(clock::aligned_interval);
 
     // Pin the future so it can be polled in tokio::select.
     tokio::pin!(run_finished);
 
-    // Skip the immediate tick.
-    ticker.tick().await;
-
     printer.print_header();
 
     loop {
         tokio::select! {
+            // If the intervals end at the same time, the stats of the last
+            // interval are collected before the summary is printed.
+            biased;
+
             _ = ticker.tick() => {
-                let partial_stats = sharded_stats.get_combined_and_clear();
+                let partial_stats = collect_partial_stats(
+                    &stats_factory,
+                    &stream_stats,
+                    &mut combined_stream_stats,
+                );
+                combined_stats.combine(&partial_stats);
+                printer.print_partial(&partial_stats);
+                if let Some(heatmap) = &mut heatmap {
+                    heatmap.write_partial(&partial_stats).await?;
+                }
+                if let Some(reporter) = &reporter {
+                    reporter.report(partial_stats.report_snapshot());
+                }
+                if let Some(sampler) = &driver_metrics {
+                    printer.print_partial_driver_metrics(&sampler.take_interval());
+                }
+                let now = Instant::now();
+                if let Some((percentile, controller)) = &mut latency_target {
+                    let rate = controller.update(
+                        partial_stats.latency_at_percentile(*percentile),
+                        partial_stats.op_rate(now - interval_start),
+                    );
+                    ctrl.set_rate_limit(0, rate);
+                    printer.print_rate_limit(rate);
+                }
+                interval_start = now;
+            }
+            _ = clock::tick_optional(&mut summary_ticker) => {
+                printer.print_intermediate_summary(&combined_stats, &session);
+            }
+            _ = clock::tick_optional(&mut flush_ticker) => {
+                if let Some(heatmap) = &mut heatmap {
+                    heatmap.flush().await?;
+                }
+            }
+            _ = dump_signals.recv() => {
+                // Dump the summary of the operations performed so far (SIGHUP or SIGUSR1).
+                let partial_stats = collect_partial_stats(
+                    &stats_factory,
+                    &stream_stats,
+                    &mut combined_stream_stats,
+                );
                 combined_stats.combine(&partial_stats);
                 printer.print_partial(&partial_stats);
+                printer.print_summary(&combined_stats, &session);
+                if let Some(heatmap) = &mut heatmap {
+                    heatmap.write_partial(&partial_stats).await?;
+                    heatmap.flush().await?;
+                }
+                if let Some(reporter) = &reporter {
+                    reporter.report(partial_stats.report_snapshot());
+                }
+                interval_start = Instant::now();
             }
             result = &mut run_finished => {
                 if result.is_ok() {
                     // Combine stats for the last time
-                    let partial_stats = sharded_stats.get_combined_and_clear();
+                    let partial_stats = collect_partial_stats(
+                        &stats_factory,
+                        &stream_stats,
+                        &mut combined_stream_stats,
+                    );
                     combined_stats.combine(&partial_stats);
-                    printer.print_summary(&combined_stats);
+                    printer.print_summary(&combined_stats, &session);
+                    if let Some(heatmap) = &mut heatmap {
+                        heatmap.write_partial(&partial_stats).await?;
+                    }
+                    if let Some(reporter) = &reporter {
+                        reporter.report(partial_stats.report_snapshot());
+                    }
+                    if stream_names.len() > 1 {
+                        for (name, stats) in stream_names.iter().zip(&combined_stream_stats) {
+                            printer.print_stream_summary(name, stats);
+                        }
+                    }
+                    if let Some((_, controller)) = &latency_target {
+                        printer.print_latency_target_summary(controller.best_rate());
+                    }
+                    if let Some(start) = &cluster_info {
+                        let end = ClusterInfo::fetch(&session).await;
+                        let mut out = std::io::stdout();
+                        end.write("Cluster at the end", &mut out)?;
+                        end.write_changes_since(start, &mut out)?;
+                    }
+                }
+                if let Some((log, writer)) = error_log.take() {
+                    log.close();
+                    writer.await?.context("Failed to write the error log")?;
+                }
+                if let Some((log, writer)) = trace_log.take() {
+                    log.close();
+                    writer.await?.context("Failed to write the trace log")?;
                 }
-                return result.context("An error occurred during the benchmark");
+                if let Some(heatmap) = &mut heatmap {
+                    heatmap.flush().await?;
+                }
+                if let Some(reporter) = reporter.take() {
+                    reporter.close().await;
+                }
+                let code = ExitCode::of_run(&result);
+                if let Err(err) = result.context("An error occurred during the benchmark") {
+                    code.with_error(&err);
+                }
+                return Ok(code);
             }
         }
     }
 }
 
-async fn stop_on_signal(runner: RunController) {
+// Collects the stats of the last interval from each of the streams,
+// and accumulates them in the per-stream totals.
+fn collect_partial_stats(
+    stats_factory: &StatsFactory,
+    stream_stats: &[Arc<ShardedStats>],
+    combined_stream_stats: &mut [Stats],
+) -> Stats {
+    let mut partial_stats = stats_factory.create();
+    for (stats, combined) in stream_stats.iter().zip(combined_stream_stats) {
+        let stream_partial_stats = stats.get_combined_and_clear();
+        combined.combine(&stream_partial_stats);
+        partial_stats.combine(&stream_partial_stats);
+    }
+    partial_stats
+}
+
+async fn stop_on_signal(runner: Arc<RunController>, mut signals: StopSignals) {
     // Try stopping gracefully upon receiving first signal.
-    tokio::signal::ctrl_c().await.unwrap();
+    signals.recv().await;
     runner.ask_to_stop();
 
     // Abort after second signal.
-    tokio::signal::ctrl_c().await.unwrap();
+    signals.recv().await;
     runner.abort();
 }
 
-async fn prepare_run(
-    settings: Arc<CassandraStressSettings>,
-    stats: Arc<ShardedStats>,
-) -> Result<Configuration> {
-    let mut builder = SessionBuilder::new()
-        .known_nodes(&settings.node.nodes)
-        .compression(settings.mode.compression);
-
-    if let Some(creds) = &settings.mode.user_credentials {
-        builder = builder.user(&creds.username, &creds.password);
+// Applies the settings shared by both regular and cloud sessions.
+fn configure_session<K: SessionBuilderKind>(
+    mut builder: GenericSessionBuilder<K>,
+    settings: &CassandraStressSettings,
+) -> Result<GenericSessionBuilder<K>> {
+    builder = builder.compression(settings.mode.compression);
+
+    let mut exec_profile_builder = ExecutionProfile::builder()
+        .load_balancing_policy(
+            settings
+                .node
+                .load_balancing_policy(settings.mode.partitioner.token_aware())?,
+        )
+        .retry_policy(settings.mode.retry_policy.create());
+    if let Some(request_timeout) = settings.mode.request_timeout {
+        exec_profile_builder = exec_profile_builder.request_timeout(Some(request_timeout));
     }
-
-    let default_exec_profile = ExecutionProfile::builder()
-        .load_balancing_policy(settings.node.load_balancing_policy())
-        .build();
+    let default_exec_profile = exec_profile_builder.build();
     builder = builder.default_execution_profile_handle(default_exec_profile.into_handle());
 
-    // TODO: Adjust port when `-port` option is supported.
-    if let Some(host_filter) = settings.node.host_filter(9042) {
+    if let Some(host_filter) = settings.node.host_filter() {
         builder = builder.host_filter(host_filter?)
     }
 
-    builder = builder.pool_size(settings.mode.pool_size);
+    builder = builder
+        .pool_size(settings.mode.pool_size)
+        .disallow_shard_aware_port(!settings.mode.shard_aware);
+    if let Some(keepalive_interval) = settings.mode.keepalive_interval {
+        builder = builder.keepalive_interval(keepalive_interval);
+    }
+
+    Ok(builder)
+}
+
+// The number of the connections the session opens to the cluster,
+// according to the pool size and the number of shards of each node.
+fn connection_count(session: &Session, pool_size: PoolSize) -> usize {
+    session
+        .get_cluster_data()
+        .get_nodes_info()
+        .iter()
+        .map(|node| match pool_size {
+            PoolSize::PerHost(connections) => connections.get(),
+            PoolSize::PerShard(connections) => {
+                let shards = node
+                    .sharder()
+                    .map_or(1, |sharder| sharder.nr_shards.get() as usize);
+                connections.get() * shards
+            }
+        })
+        .sum::<usize>()
+        // Let the run proceed, even if the session doesn't know the nodes yet.
+        .max(1)
+}
+
+async fn prepare_run(
+    settings: Arc<CassandraStressSettings>,
+    stats_factory: Arc<StatsFactory>,
+    error_log: Option<Arc<ErrorLog>>,
+    trace_log: Option<Arc<TraceLog>>,
+) -> Result<(Configuration, Vec<Arc<ShardedStats>>, Arc<Session>)> {
+    let session = match &settings.node.cloud_config {
+        Some(cloud_config) => {
+            // The connection bundle provides the endpoints, credentials and TLS settings.
+            let builder = CloudSessionBuilder::new(cloud_config)
+                .with_context(|| format!("Failed to load cloud config: {}", cloud_config))?;
+            configure_session(builder, &settings)?.build().await?
+        }
+        None => {
+            let mut builder = SessionBuilder::new().known_nodes(&settings.node.nodes);
+
+            if let Some(creds) = &settings.mode.user_credentials {
+                builder = builder.user(&creds.username, &creds.password);
+            }
+
+            #[cfg(feature = "auth-plugins")]
+            if let Some(authenticator) = &settings.mode.authenticator {
+                let spec = cql_stress::auth::AuthenticatorSpec::parse(authenticator)?;
+                builder = builder.authenticator_provider(spec.create()?);
+            }
+
+            if let Some(ssl_context) = settings.transport.generate_ssl_context()? {
+                builder = builder.ssl_context(Some(ssl_context));
+            }
 
-    let session = builder.build().await?;
+            configure_session(builder, &settings)?.build().await?
+        }
+    };
     let session = Arc::new(session);
 
     settings
@@ -144,6 +443,29 @@ async fn prepare_run(
         .context("Failed to create schema")?;
 
     let duration = settings.command_params.common.duration;
+    let max_operations = settings.command_params.common.operation_count;
+    // -mode maxPending= bounds the requests of each of the connections.
+    let max_pending = settings
+        .mode
+        .max_pending
+        .map(|max_pending| max_pending.get() * connection_count(&session, settings.mode.pool_size));
+    let max_in_flight = settings
+        .rate
+        .max_in_flight
+        .map(|max_in_flight| max_in_flight as usize)
+        .into_iter()
+        .chain(max_pending)
+        .min();
+    let runtimes = settings.rate.runtimes.map(|runtimes| runtimes as usize);
+    // A scan operation issues many paged requests, each of them is
+    // already limited by the request timeout.
+    let operation_timeout = match settings.command {
+        Command::Scan => None,
+        _ => settings
+            .mode
+            .request_timeout
+            .map(configuration::operation_timeout),
+    };
 
     let (concurrency, throttle) = match settings.rate.threads_info {
         ThreadsInfo::Fixed {
@@ -152,45 +474,210 @@ async fn prepare_run(
         ThreadsInfo::Auto { .. } => {
             anyhow::bail!("Runtime not implemented for auto-adjusting rate configuration");
         }
+        // The rate limit is adjusted by the controller during the run.
+        ThreadsInfo::AutoLatency { threads, .. } => (threads, Some(latency_target::INITIAL_RATE)),
     };
 
-    let operation_factory = create_operation_factory(session, settings, stats).await?;
+    let (streams, stream_stats) = if settings.rate.streams {
+        create_mixed_streams(
+            Arc::clone(&session),
+            settings,
+            concurrency,
+            throttle,
+            stats_factory,
+            error_log,
+            trace_log,
+        )
+        .await?
+    } else {
+        let stats = Arc::new(ShardedStats::new(stats_factory));
+        let name = settings.command.show().to_owned();
+        let rate_limit_mode = settings.rate.threads_info.rate_limit_mode();
+        let arrival = settings.rate.arrival;
+        let operation_factory = create_operation_factory(
+            Arc::clone(&session),
+            settings,
+            Arc::clone(&stats),
+            error_log,
+            trace_log,
+        )
+        .await?;
+        let stream = StreamConfiguration {
+            name,
+            concurrency,
+            rate_limit_per_second: throttle,
+            rate_limit_mode,
+            arrival,
+            operation_factory,
+        };
+        (vec![stream], vec![stats])
+    };
 
-    Ok(Configuration {
+    let config = Configuration {
         max_duration: duration,
-        concurrency,
-        rate_limit_per_second: throttle,
-        operation_factory,
-        // TODO: adjust when -errors option is supported
-        max_retries_per_op: 9,
-    })
+        max_operations,
+        streams,
+        max_retries_per_op: settings.errors.retries as usize,
+        operation_timeout,
+        retry_backoff: settings.errors.backoff,
+        retry_budget: settings.errors.budget.map(|budget| budget as f64),
+        max_in_flight,
+        runtimes,
+        duty_cycle: settings.rate.duty_cycle,
+    };
+    Ok((config, stream_stats, session))
+}
+
+// Creates a stream for each of the operations of the mixed command (see `-rate streams`).
+// The threads and the throttle are split between them according to the ratio.
+async fn create_mixed_streams(
+    session: Arc<Session>,
+    settings: Arc<CassandraStressSettings>,
+    concurrency: u64,
+    throttle: Option<f64>,
+    stats_factory: Arc<StatsFactory>,
+    error_log: Option<Arc<ErrorLog>>,
+    trace_log: Option<Arc<TraceLog>>,
+) -> Result<(Vec<StreamConfiguration>, Vec<Arc<ShardedStats>>)> {
+    let ratio = &settings
+        .command_params
+        .mixed
+        .as_ref()
+        .unwrap()
+        .operation_ratio;
+    let total_weight: f64 = ratio.items().iter().map(|(_, weight)| weight).sum();
+    // The streams touch the same partitions, so they share the locks.
+    let partition_locks = settings.rate.create_partition_locks();
+
+    let mut streams = Vec::with_capacity(ratio.items().len());
+    let mut stream_stats = Vec::with_capacity(ratio.items().len());
+    for (subcommand, weight) in ratio.items() {
+        let share = weight / total_weight;
+        let stats = Arc::new(ShardedStats::new(Arc::clone(&stats_factory)));
+        let operation_factory = MixedOperationFactory::new_stream(
+            Arc::clone(&settings),
+            Arc::clone(&session),
+            *subcommand,
+            RowGeneratorFactory::new(Arc::clone(&settings)),
+            Arc::clone(&stats),
+            error_log.clone(),
+            trace_log.clone(),
+            partition_locks.clone(),
+        )
+        .await?;
+        streams.push(StreamConfiguration {
+            name: subcommand.to_string(),
+            concurrency: ((concurrency as f64 * share).round() as u64).max(1),
+            rate_limit_per_second: throttle.map(|throttle| throttle * share),
+            rate_limit_mode: settings.rate.threads_info.rate_limit_mode(),
+            arrival: settings.rate.arrival,
+            operation_factory: Arc::new(operation_factory),
+        });
+        stream_stats.push(stats);
+    }
+    Ok((streams, stream_stats))
 }
 
 async fn create_operation_factory(
     session: Arc<Session>,
     settings: Arc<CassandraStressSettings>,
     stats: Arc<ShardedStats>,
+    error_log: Option<Arc<ErrorLog>>,
+    trace_log: Option<Arc<TraceLog>>,
 ) -> Result<Arc<dyn OperationFactory>> {
     let workload_factory = RowGeneratorFactory::new(Arc::clone(&settings));
     match &settings.command {
         Command::Write => Ok(Arc::new(
-            WriteOperationFactory::new(settings, session, workload_factory, stats).await?,
+            WriteOperationFactory::new(
+                settings,
+                session,
+                workload_factory,
+                stats,
+                error_log,
+                trace_log,
+            )
+            .await?,
         )),
         Command::Read => Ok(Arc::new(
-            RegularReadOperationFactory::new(settings, session, workload_factory, stats).await?,
+            RegularReadOperationFactory::new(
+                settings,
+                session,
+                workload_factory,
+                stats,
+                error_log,
+                trace_log,
+            )
+            .await?,
         )),
         Command::CounterWrite => Ok(Arc::new(
-            CounterWriteOperationFactory::new(settings, session, workload_factory, stats).await?,
+            CounterWriteOperationFactory::new(
+                settings,
+                session,
+                workload_factory,
+                stats,
+                error_log,
+                trace_log,
+            )
+            .await?,
         )),
         Command::CounterRead => Ok(Arc::new(
-            CounterReadOperationFactory::new(settings, session, workload_factory, stats).await?,
+            CounterReadOperationFactory::new(
+                settings,
+                session,
+                workload_factory,
+                stats,
+                error_log,
+                trace_log,
+            )
+            .await?,
+        )),
+        Command::IndexRead => Ok(Arc::new(
+            IndexReadOperationFactory::new(
+                settings,
+                session,
+                workload_factory,
+                stats,
+                error_log,
+                trace_log,
+            )
+            .await?,
         )),
         Command::Mixed => Ok(Arc::new(
-            MixedOperationFactory::new(settings, session, workload_factory, stats).await?,
+            MixedOperationFactory::new(
+                settings,
+                session,
+                workload_factory,
+                stats,
+                error_log,
+                trace_log,
+            )
+            .await?,
+        )),
+        Command::ReadModifyWrite => Ok(Arc::new(
+            ReadModifyWriteOperationFactory::new(
+                settings,
+                session,
+                workload_factory,
+                stats,
+                error_log,
+                trace_log,
+            )
+            .await?,
+        )),
+        Command::Scan => Ok(Arc::new(
+            ScanOperationFactory::new(
+                settings,
+                session,
+                workload_factory,
+                stats,
+                error_log,
+                trace_log,
+            )
+            .await?,
         )),
         #[cfg(feature = "user-profile")]
         Command::User => Ok(Arc::new(
-            UserOperationFactory::new(settings, session, stats).await?,
+            UserOperationFactory::new(settings, session, stats, error_log).await?,
         )),
         cmd => Err(anyhow::anyhow!(
             "Runtime for command '{}' not implemented yet.",
@@ -0,0 +1,148 @@
+use anyhow::{Context, Result};
+use scylla::{frame::response::result::CqlValue, QueryResult};
+use sha2::{Digest, Sha256};
+
+use crate::{
+    java_generate::{
+        distribution::Distribution,
+        values::{Blob, ValueGenerator},
+    },
+    settings::CHECKSUM_HEADER_SIZE,
+};
+
+/// Generates the blobs the same way as [Blob], but overwrites their first
+/// bytes with a header describing the value (see `-col checksum`):
+/// - the length of the whole value (4 bytes, little endian),
+/// - the first 8 bytes of the SHA-256 digest of the rest of the value.
+///
+/// The values are still deterministic, so the reads can compare them
+/// with the regenerated row as well.
+#[derive(Default)]
+pub struct ChecksummedBlob {
+    blob: Blob,
+}
+
+impl ChecksummedBlob {
+    pub fn new(blob: Blob) -> Self {
+        Self { blob }
+    }
+}
+
+impl ValueGenerator for ChecksummedBlob {
+    fn generate(
+        &mut self,
+        identity_distribution: &mut dyn Distribution,
+        size_distribution: &mut dyn Distribution,
+    ) -> CqlValue {
+        match self.blob.generate(identity_distribution, size_distribution) {
+            CqlValue::Blob(mut bytes) => {
+                embed_checksum(&mut bytes);
+                CqlValue::Blob(bytes)
+            }
+            value => value,
+        }
+    }
+
+    fn generate_into(
+        &mut self,
+        identity_distribution: &mut dyn Distribution,
+        size_distribution: &mut dyn Distribution,
+        value: &mut CqlValue,
+    ) {
+        self.blob
+            .generate_into(identity_distribution, size_distribution, value);
+        if let CqlValue::Blob(bytes) = value {
+            embed_checksum(bytes);
+        }
+    }
+}
+
+fn digest(payload: &[u8]) -> [u8; 8] {
+    Sha256::digest(payload)[..8].try_into().unwrap()
+}
+
+fn embed_checksum(bytes: &mut [u8]) {
+    // The cells are at least CHECKSUM_HEADER_SIZE long, validated during parsing.
+    let len = bytes.len() as u32;
+    let (header, payload) = bytes.split_at_mut(CHECKSUM_HEADER_SIZE);
+    let digest = digest(payload);
+    header[..4].copy_from_slice(&len.to_le_bytes());
+    header[4..].copy_from_slice(&digest);
+}
+
+/// Verifies the header of the value read from the database, which catches
+/// the corrupted values independently of the regenerated row.
+fn verify_checksum(bytes: &[u8]) -> Result<()> {
+    anyhow::ensure!(
+        bytes.len() >= CHECKSUM_HEADER_SIZE,
+        "The value is too short to contain the checksum: {} bytes",
+        bytes.len()
+    );
+    let (header, payload) = bytes.split_at(CHECKSUM_HEADER_SIZE);
+    let len = u32::from_le_bytes(header[..4].try_into().unwrap()) as usize;
+    anyhow::ensure!(
+        len == bytes.len(),
+        "Invalid value length: {} bytes, written: {} bytes",
+        bytes.len(),
+        len
+    );
+    anyhow::ensure!(header[4..] == digest(payload), "Checksum mismatch");
+    Ok(())
+}
+
+/// Verifies the checksums of the blob values of the rows in the result.
+pub fn verify_result_checksums(result: &QueryResult) -> Result<()> {
+    let col_specs = result.col_specs();
+    for row in result.rows.iter().flatten() {
+        // The first column is the partition key, which has no checksum.
+        for (i, value) in row.columns.iter().enumerate().skip(1) {
+            if let Some(CqlValue::Blob(bytes)) = value {
+                verify_checksum(bytes).with_context(|| {
+                    let column = col_specs.get(i).map_or("?", |spec| spec.name.as_str());
+                    format!("Corrupt value of column {}", column)
+                })?;
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use scylla::frame::response::result::CqlValue;
+
+    use crate::java_generate::{
+        distribution::fixed::FixedDistribution,
+        values::{Generator, GeneratorConfig},
+    };
+
+    use super::{verify_checksum, ChecksummedBlob};
+
+    #[test]
+    fn checksummed_blob_test() {
+        let mut generator = Generator::new(
+            Box::<ChecksummedBlob>::default(),
+            GeneratorConfig::new(
+                "randomstrC0",
+                None,
+                Some(Box::new(FixedDistribution::new(34))),
+            ),
+            String::from("C0"),
+        );
+        generator.set_seed(42);
+        let mut bytes = match generator.generate() {
+            CqlValue::Blob(bytes) => bytes,
+            value => panic!("Unexpected value: {:?}", value),
+        };
+        assert_eq!(34, bytes.len());
+        assert!(verify_checksum(&bytes).is_ok());
+
+        // Truncated value.
+        assert!(verify_checksum(&bytes[..33]).is_err());
+        assert!(verify_checksum(&bytes[..8]).is_err());
+
+        // Corrupted payload.
+        bytes[20] = bytes[20].wrapping_add(1);
+        assert!(verify_checksum(&bytes).is_err());
+    }
+}
@@ -1,15 +1,18 @@
-use anyhow::{Context, Result};
+use anyhow::Result;
 
 use std::{ops::ControlFlow, sync::Arc};
 
+use cql_stress::{dry_run::RenderedStatement, reprepare::reprepare};
 use scylla::frame::response::result::CqlValue;
 use scylla::frame::value::Counter;
-use scylla::{prepared_statement::PreparedStatement, Session};
+use scylla::{history::HistoryListener, prepared_statement::PreparedStatement, Session};
+use uuid::Uuid;
 
 use crate::{java_generate::distribution::Distribution, settings::CassandraStressSettings};
 
 use super::{
-    row_generator::RowGenerator, CassandraStressOperation, CassandraStressOperationFactory,
+    prepare_statements, render_values, row_generator::RowGenerator, CassandraStressOperation,
+    CassandraStressOperationFactory, CassandraStressRenderer, LastTracingId,
 };
 
 pub struct CounterWriteOperation {
@@ -17,6 +20,7 @@ pub struct CounterWriteOperation {
     statement: PreparedStatement,
     non_pk_columns_count: usize,
     add_distribution: Box<dyn Distribution>,
+    tracing_id: LastTracingId,
 }
 
 pub struct CounterWriteOperationFactory {
@@ -40,20 +44,76 @@ impl CassandraStressOperation for CounterWriteOperation {
             );
         }
 
-        result?;
+        self.tracing_id.record(&result?);
         Ok(ControlFlow::Continue(()))
     }
 
+    async fn reprepare(&mut self) -> Result<()> {
+        self.statement = reprepare(&self.session, &self.statement).await?;
+        Ok(())
+    }
+
+    fn set_tracing(&mut self, tracing: bool) {
+        self.statement.set_tracing(tracing);
+    }
+
+    fn set_history_listener(&mut self, listener: Arc<dyn HistoryListener>) {
+        self.statement.set_history_listener(listener);
+    }
+
+    fn take_tracing_id(&self) -> Option<Uuid> {
+        self.tracing_id.take()
+    }
+
     fn generate_row(&self, row_generator: &mut RowGenerator) -> Vec<CqlValue> {
-        let mut values: Vec<CqlValue> = Vec::with_capacity(self.non_pk_columns_count + 1);
+        generate_counter_row(
+            self.non_pk_columns_count,
+            self.add_distribution.as_ref(),
+            row_generator,
+        )
+    }
 
-        for _ in 0..self.non_pk_columns_count {
-            values.push(CqlValue::Counter(Counter(self.add_distribution.next_i64())))
-        }
-        let pk = row_generator.generate_pk();
-        values.push(pk);
-        values
+    fn partition_key<'a>(&self, row: &'a [CqlValue]) -> Option<&'a CqlValue> {
+        // The partition key is bound to the WHERE clause, after the counters.
+        row.last()
+    }
+}
+
+/// Generates the increments of the counters, followed by the partition key.
+fn generate_counter_row(
+    non_pk_columns_count: usize,
+    add_distribution: &dyn Distribution,
+    row_generator: &mut RowGenerator,
+) -> Vec<CqlValue> {
+    let mut values: Vec<CqlValue> = Vec::with_capacity(non_pk_columns_count + 1);
+
+    for _ in 0..non_pk_columns_count {
+        values.push(CqlValue::Counter(Counter(add_distribution.next_i64())))
     }
+    let pk = row_generator.generate_pk();
+    values.push(pk);
+    values
+}
+
+/// Number of the leading counter columns incremented by each update.
+fn incremented_columns_count(settings: &CassandraStressSettings) -> usize {
+    settings
+        .command_params
+        .counter
+        .as_ref()
+        .unwrap()
+        .incremented_columns
+        .map_or(settings.column.columns.len(), |count| count.get())
+}
+
+fn create_add_distribution(settings: &CassandraStressSettings) -> Box<dyn Distribution> {
+    settings
+        .command_params
+        .counter
+        .as_ref()
+        .unwrap()
+        .add_distribution
+        .create()
 }
 
 impl CassandraStressOperationFactory for CounterWriteOperationFactory {
@@ -63,15 +123,9 @@ impl CassandraStressOperationFactory for CounterWriteOperationFactory {
         CounterWriteOperation {
             session: Arc::clone(&self.session),
             statement: self.statement.clone(),
-            non_pk_columns_count: self.settings.column.columns.len(),
-            add_distribution: self
-                .settings
-                .command_params
-                .counter
-                .as_ref()
-                .unwrap()
-                .add_distribution
-                .create(),
+            non_pk_columns_count: incremented_columns_count(&self.settings),
+            add_distribution: create_add_distribution(&self.settings),
+            tracing_id: LastTracingId::default(),
         }
     }
 }
@@ -84,12 +138,14 @@ impl CounterWriteOperationFactory {
         // UPDATE counter1 SET "C0"="C0"+?,"C1"="C1"+?,"C2"="C2"+?,"C3"="C3"+?,"C4"="C4"+? WHERE KEY=?
         let statement_str = Self::build_query(&settings);
 
-        let mut statement = session
-            .prepare(statement_str)
-            .await
-            .context("Failed to prepare statement")?;
+        let mut statement = prepare_statements(
+            &session,
+            vec![(String::from("counter_write"), statement_str)],
+        )
+        .await?
+        .remove(0);
 
-        statement.set_consistency(settings.command_params.common.consistency_level);
+        statement.set_consistency(settings.command_params.write_consistency_level());
         statement.set_serial_consistency(Some(
             settings.command_params.common.serial_consistency_level,
         ));
@@ -101,17 +157,53 @@ impl CounterWriteOperationFactory {
         })
     }
 
-    fn build_query(settings: &Arc<CassandraStressSettings>) -> String {
+    fn build_query(settings: &CassandraStressSettings) -> String {
         // Assuming there are non-pk columns [C0, C1, C2], it generates:
         // "C0"="C0"+?,"C1"="C1"+?,"C2"="C2"+?
         let columns_str = settings
             .column
             .columns
             .iter()
+            .take(incremented_columns_count(settings))
             .map(|col| format!("\"{0}\"=\"{0}\"+?", col))
             .collect::<Vec<_>>()
             .join(",");
 
-        format!("UPDATE counter1 SET {} WHERE KEY=?", columns_str)
+        format!(
+            "UPDATE {} SET {} WHERE KEY=?",
+            settings.schema.counter_table_identifier(),
+            columns_str
+        )
+    }
+}
+
+/// Renders the counter updates of [CounterWriteOperation].
+pub struct CounterWriteRenderer {
+    statement: String,
+    non_pk_columns_count: usize,
+    add_distribution: Box<dyn Distribution>,
+}
+
+impl CounterWriteRenderer {
+    pub fn new(settings: &CassandraStressSettings) -> Self {
+        Self {
+            statement: CounterWriteOperationFactory::build_query(settings),
+            non_pk_columns_count: incremented_columns_count(settings),
+            add_distribution: create_add_distribution(settings),
+        }
+    }
+}
+
+impl CassandraStressRenderer for CounterWriteRenderer {
+    fn render(&mut self, row_generator: &mut RowGenerator) -> Vec<RenderedStatement> {
+        let row = generate_counter_row(
+            self.non_pk_columns_count,
+            self.add_distribution.as_ref(),
+            row_generator,
+        );
+        vec![RenderedStatement::new(
+            self.statement.clone(),
+            render_values(&row),
+        )]
     }
 }
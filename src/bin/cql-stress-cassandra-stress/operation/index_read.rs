@@ -0,0 +1,212 @@
+use std::{ops::ControlFlow, sync::Arc};
+
+use anyhow::{Context, Result};
+use cql_stress::{dry_run::RenderedStatement, reprepare::reprepare, row_count::ExpectedRowCount};
+use scylla::{
+    frame::response::result::CqlValue, history::HistoryListener,
+    prepared_statement::PreparedStatement, Session,
+};
+use uuid::Uuid;
+
+use crate::settings::CassandraStressSettings;
+
+use super::{
+    execute_read, prepare_statements, render_values, row_generator::RowGenerator,
+    CassandraStressOperation, CassandraStressOperationFactory, CassandraStressRenderer,
+    LastTracingId,
+};
+
+/// Reads the row via the secondary index created with `-schema index=`,
+/// i.e. selects the rows by the value of the indexed column.
+pub struct IndexReadOperation {
+    session: Arc<Session>,
+    statement: PreparedStatement,
+    indexed_column_idx: usize,
+    expected_rows: Option<ExpectedRowCount>,
+    paged: bool,
+    tracing_id: LastTracingId,
+}
+
+pub struct IndexReadOperationFactory {
+    session: Arc<Session>,
+    statement: PreparedStatement,
+    indexed_column_idx: usize,
+    expected_rows: Option<ExpectedRowCount>,
+    paged: bool,
+}
+
+impl IndexReadOperation {
+    async fn do_execute(&self, row: &[CqlValue]) -> Result<()> {
+        let pk = &row[0];
+        let indexed_value = &row[self.indexed_column_idx];
+
+        let result =
+            execute_read(&self.session, &self.statement, (indexed_value,), self.paged).await?;
+        self.tracing_id.record(&result);
+
+        let rows = result.rows_or_empty();
+        if let Some(expected_rows) = &self.expected_rows {
+            expected_rows.check(rows.len() as u64)?;
+        }
+
+        // Other rows may happen to have the same value of the indexed column,
+        // so we only check that the generated row is among the results.
+        let found = rows
+            .into_iter()
+            .any(|found_row| matches!(found_row.columns.first(), Some(Some(key)) if key == pk));
+        anyhow::ensure!(found, "Row not found via the secondary index.");
+
+        Ok(())
+    }
+}
+
+impl CassandraStressOperation for IndexReadOperation {
+    type Factory = IndexReadOperationFactory;
+
+    async fn execute(&self, row: &[CqlValue]) -> Result<ControlFlow<()>> {
+        let result = self.do_execute(row).await;
+        if let Err(err) = result.as_ref() {
+            tracing::error!(
+                error = %err,
+                partition_key = ?row[0],
+                "index read error",
+            );
+        }
+
+        result.with_context(|| {
+            format!(
+                "Row with partition_key: {:?} could not be read via the index.",
+                row[0]
+            )
+        })?;
+
+        Ok(ControlFlow::Continue(()))
+    }
+
+    async fn reprepare(&mut self) -> Result<()> {
+        self.statement = reprepare(&self.session, &self.statement).await?;
+        Ok(())
+    }
+
+    fn set_tracing(&mut self, tracing: bool) {
+        self.statement.set_tracing(tracing);
+    }
+
+    fn set_history_listener(&mut self, listener: Arc<dyn HistoryListener>) {
+        self.statement.set_history_listener(listener);
+    }
+
+    fn take_tracing_id(&self) -> Option<Uuid> {
+        self.tracing_id.take()
+    }
+
+    fn generate_row(&self, row_generator: &mut RowGenerator) -> Vec<CqlValue> {
+        row_generator.generate_row()
+    }
+
+    fn generate_row_into(&self, row_generator: &mut RowGenerator, row: &mut Vec<CqlValue>) {
+        row_generator.generate_row_into(row);
+    }
+}
+
+impl CassandraStressOperationFactory for IndexReadOperationFactory {
+    type Operation = IndexReadOperation;
+
+    fn create(&self) -> Self::Operation {
+        IndexReadOperation {
+            session: Arc::clone(&self.session),
+            statement: self.statement.clone(),
+            indexed_column_idx: self.indexed_column_idx,
+            expected_rows: self.expected_rows.clone(),
+            paged: self.paged,
+            tracing_id: LastTracingId::default(),
+        }
+    }
+}
+
+impl IndexReadOperationFactory {
+    pub async fn new(
+        settings: Arc<CassandraStressSettings>,
+        session: Arc<Session>,
+    ) -> Result<Self> {
+        let indexed_column_idx = indexed_column_idx(&settings);
+        let statement_str = build_query(&settings);
+        let mut statement =
+            prepare_statements(&session, vec![(String::from("index_read"), statement_str)])
+                .await?
+                .remove(0);
+
+        statement.set_is_idempotent(true);
+        statement.set_consistency(settings.command_params.common.consistency_level);
+        statement.set_serial_consistency(Some(
+            settings.command_params.common.serial_consistency_level,
+        ));
+        if let Some(fetch_size) = settings.mode.fetch_size {
+            statement.set_page_size(fetch_size);
+        }
+
+        let expected_rows = settings
+            .command_params
+            .read
+            .as_ref()
+            .and_then(|read| read.expected_rows.clone());
+
+        Ok(Self {
+            session,
+            statement,
+            indexed_column_idx,
+            expected_rows,
+            paged: settings.mode.fetch_size.is_some(),
+        })
+    }
+}
+
+/// Returns the index of the value of the indexed column in the generated row.
+fn indexed_column_idx(settings: &CassandraStressSettings) -> usize {
+    // Validated during parsing.
+    let index = settings.schema.index.as_ref().unwrap();
+    settings
+        .column
+        .columns
+        .iter()
+        .position(|column| column == index)
+        .unwrap()
+        // The generated row starts with the partition key.
+        + 1
+}
+
+fn build_query(settings: &CassandraStressSettings) -> String {
+    // Validated during parsing.
+    let index = settings.schema.index.as_ref().unwrap();
+    format!(
+        "SELECT key FROM {} WHERE \"{}\"=?",
+        settings.schema.table_identifier(),
+        index
+    )
+}
+
+/// Renders the reads of [IndexReadOperation], which bind the value
+/// of the indexed column of the generated row.
+pub struct IndexReadRenderer {
+    statement: String,
+    indexed_column_idx: usize,
+}
+
+impl IndexReadRenderer {
+    pub fn new(settings: &CassandraStressSettings) -> Self {
+        Self {
+            statement: build_query(settings),
+            indexed_column_idx: indexed_column_idx(settings),
+        }
+    }
+}
+
+impl CassandraStressRenderer for IndexReadRenderer {
+    fn render(&mut self, row_generator: &mut RowGenerator) -> Vec<RenderedStatement> {
+        let row = row_generator.generate_row();
+        vec![RenderedStatement::new(
+            self.statement.clone(),
+            render_values(&row[self.indexed_column_idx..=self.indexed_column_idx]),
+        )]
+    }
+}
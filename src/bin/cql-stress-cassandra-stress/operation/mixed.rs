@@ -1,29 +1,38 @@
 use anyhow::Result;
 use futures::Future;
+#[cfg(feature = "user-profile")]
+use std::collections::HashMap;
 use std::{ops::ControlFlow, sync::Arc};
 
 use cql_stress::{
     configuration::{Operation, OperationContext, OperationFactory},
+    coordinator::LastCoordinator,
+    error_log::ErrorLog,
     make_runnable,
+    partition_lock::PartitionLocks,
+    seed::SeedSequence,
+    trace_log::TraceLog,
 };
-use scylla::{frame::response::result::CqlValue, Session};
+use scylla::Session;
 
 use crate::{
-    java_generate::distribution::Distribution,
     settings::{CassandraStressSettings, MixedSubcommand, OperationRatio},
-    stats::ShardedStats,
+    stats::{Sample, ShardedStats, StatsBuffer},
 };
 
+#[cfg(feature = "user-profile")]
+use super::user::{UserDefinedOperation, UserOperationFactory};
 use super::{
     counter_write::{CounterWriteOperation, CounterWriteOperationFactory},
+    execute_sampled, log_error,
     read::{
         CounterReadOperation, CounterReadOperationFactory, RegularReadOperation,
         RegularReadOperationFactory,
     },
     row_generator::RowGenerator,
     write::{WriteOperation, WriteOperationFactory},
-    CassandraStressOperation, CassandraStressOperationFactory, RowGeneratorFactory,
-    DEFAULT_COUNTER_TABLE_NAME, DEFAULT_TABLE_NAME,
+    CassandraStressOperation, CassandraStressOperationFactory, OperationSampler, RowBuffer,
+    RowGeneratorFactory,
 };
 
 pub struct MixedOperation {
@@ -31,14 +40,21 @@ pub struct MixedOperation {
     counter_write_operation: Option<CounterWriteOperation>,
     read_operation: Option<RegularReadOperation>,
     counter_read_operation: Option<CounterReadOperation>,
-    cached_row: Option<Vec<CqlValue>>,
+    // The queries of the profile, by their names, and the generator
+    // of the rows of its table.
+    #[cfg(feature = "user-profile")]
+    query_operations: HashMap<String, UserDefinedOperation>,
+    #[cfg(feature = "user-profile")]
+    user_workload: Option<RowGenerator>,
+    row: RowBuffer,
     workload: RowGenerator,
-    max_operations: Option<u64>,
-    stats: Arc<ShardedStats>,
-    operation_ratio: Arc<OperationRatio>,
-    clustering_distribution: Box<dyn Distribution>,
+    stats: StatsBuffer,
+    error_log: Option<Arc<ErrorLog>>,
+    trace_log: Option<Arc<TraceLog>>,
+    partition_locks: Option<Arc<PartitionLocks>>,
+    coordinator: Arc<LastCoordinator>,
+    sampler: OperationSampler<MixedSubcommand>,
     current_operation: MixedSubcommand,
-    current_operation_remaining: usize,
 }
 
 pub struct MixedOperationFactory {
@@ -47,40 +63,76 @@ pub struct MixedOperationFactory {
     counter_write_operation_factory: Option<CounterWriteOperationFactory>,
     read_operation_factory: Option<RegularReadOperationFactory>,
     counter_read_operation_factory: Option<CounterReadOperationFactory>,
+    #[cfg(feature = "user-profile")]
+    user_operation_factory: Option<UserOperationFactory>,
     operation_ratio: Arc<OperationRatio>,
     workload_factory: RowGeneratorFactory,
-    max_operations: Option<u64>,
     stats: Arc<ShardedStats>,
+    error_log: Option<Arc<ErrorLog>>,
+    trace_log: Option<Arc<TraceLog>>,
+    partition_locks: Option<Arc<PartitionLocks>>,
+    seeds: SeedSequence,
 }
 
 fn create_operation_opt<Factory: CassandraStressOperationFactory>(
     factory_opt: &Option<Factory>,
+    coordinator: &Arc<LastCoordinator>,
 ) -> Option<Factory::Operation> {
-    factory_opt.as_ref().map(|f| f.create())
+    factory_opt.as_ref().map(|f| {
+        let mut operation = f.create();
+        operation.set_history_listener(coordinator.clone());
+        operation
+    })
 }
 
 impl OperationFactory for MixedOperationFactory {
     fn create(&self) -> Box<dyn Operation> {
         let mixed_params = self.settings.command_params.mixed.as_ref().unwrap();
 
-        let write_operation = create_operation_opt(&self.write_operation_factory);
-        let counter_write_operation = create_operation_opt(&self.counter_write_operation_factory);
-        let read_operation = create_operation_opt(&self.read_operation_factory);
-        let counter_read_operation = create_operation_opt(&self.counter_read_operation_factory);
+        // The sub-operations are executed one at a time, so they share the listener.
+        let coordinator = Arc::new(LastCoordinator::default());
+        let write_operation = create_operation_opt(&self.write_operation_factory, &coordinator);
+        let counter_write_operation =
+            create_operation_opt(&self.counter_write_operation_factory, &coordinator);
+        let read_operation = create_operation_opt(&self.read_operation_factory, &coordinator);
+        let counter_read_operation =
+            create_operation_opt(&self.counter_read_operation_factory, &coordinator);
+        #[cfg(feature = "user-profile")]
+        let user_workload = self
+            .user_operation_factory
+            .as_ref()
+            .map(|factory| factory.create_workload());
+        #[cfg(feature = "user-profile")]
+        let query_operations = match (&self.user_operation_factory, &user_workload) {
+            (Some(factory), Some(workload)) => factory
+                .create_operations(workload, &coordinator)
+                .map(|(operation, _)| (operation.name().to_owned(), operation))
+                .collect(),
+            _ => HashMap::new(),
+        };
 
         Box::new(MixedOperation {
             write_operation,
             counter_write_operation,
             read_operation,
             counter_read_operation,
-            cached_row: None,
+            #[cfg(feature = "user-profile")]
+            query_operations,
+            #[cfg(feature = "user-profile")]
+            user_workload,
+            row: RowBuffer::default(),
             workload: self.workload_factory.create(),
-            max_operations: self.max_operations,
-            stats: Arc::clone(&self.stats),
-            operation_ratio: Arc::clone(&self.operation_ratio),
-            clustering_distribution: mixed_params.clustering.create(),
+            stats: StatsBuffer::new(Arc::clone(&self.stats)),
+            error_log: self.error_log.clone(),
+            trace_log: self.trace_log.clone(),
+            partition_locks: self.partition_locks.clone(),
+            coordinator,
+            sampler: OperationSampler::new(
+                self.operation_ratio.items().iter().copied(),
+                mixed_params.clustering.as_ref(),
+                self.seeds.next_seed(),
+            ),
             current_operation: MixedSubcommand::Read,
-            current_operation_remaining: 0,
         })
     }
 }
@@ -91,50 +143,121 @@ impl MixedOperationFactory {
         session: Arc<Session>,
         workload_factory: RowGeneratorFactory,
         stats: Arc<ShardedStats>,
+        error_log: Option<Arc<ErrorLog>>,
+        trace_log: Option<Arc<TraceLog>>,
     ) -> Result<Self> {
         let mixed_params = settings.command_params.mixed.as_ref().unwrap();
-        let max_operations = settings.command_params.common.operation_count;
-        let operation_ratio = Arc::new(mixed_params.operation_ratio.clone());
-        let write_operation_factory = Self::conditional_create_factory(
-            &mixed_params.operation_ratio,
-            &MixedSubcommand::Write,
-            || WriteOperationFactory::new(settings.clone(), session.clone()),
-        )
-        .await
-        .transpose()?;
-        let counter_write_operation_factory = Self::conditional_create_factory(
-            &mixed_params.operation_ratio,
-            &MixedSubcommand::CounterWrite,
-            || CounterWriteOperationFactory::new(settings.clone(), session.clone()),
+        let operation_ratio = mixed_params.operation_ratio.clone();
+        let partition_locks = settings.rate.create_partition_locks();
+        Self::with_ratio(
+            settings,
+            session,
+            operation_ratio,
+            workload_factory,
+            stats,
+            error_log,
+            trace_log,
+            partition_locks,
         )
         .await
-        .transpose()?;
-        let read_operation_factory = Self::conditional_create_factory(
-            &mixed_params.operation_ratio,
-            &MixedSubcommand::Read,
-            || {
-                RegularReadOperationFactory::new(
-                    settings.clone(),
-                    session.clone(),
-                    DEFAULT_TABLE_NAME,
-                )
-            },
+    }
+
+    /// Creates the factory of the operations of one of the streams
+    /// (see `-rate streams`), which performs only the given subcommand.
+    pub async fn new_stream(
+        settings: Arc<CassandraStressSettings>,
+        session: Arc<Session>,
+        subcommand: MixedSubcommand,
+        workload_factory: RowGeneratorFactory,
+        stats: Arc<ShardedStats>,
+        error_log: Option<Arc<ErrorLog>>,
+        trace_log: Option<Arc<TraceLog>>,
+        partition_locks: Option<Arc<PartitionLocks>>,
+    ) -> Result<Self> {
+        let operation_ratio = OperationRatio::new(vec![(subcommand, 1.0)])?;
+        Self::with_ratio(
+            settings,
+            session,
+            operation_ratio,
+            workload_factory,
+            stats,
+            error_log,
+            trace_log,
+            partition_locks,
         )
         .await
-        .transpose()?;
-        let counter_read_operation_factory = Self::conditional_create_factory(
-            &mixed_params.operation_ratio,
-            &MixedSubcommand::CounterRead,
-            || {
-                CounterReadOperationFactory::new(
-                    settings.clone(),
+    }
+
+    async fn with_ratio(
+        settings: Arc<CassandraStressSettings>,
+        session: Arc<Session>,
+        operation_ratio: OperationRatio,
+        workload_factory: RowGeneratorFactory,
+        stats: Arc<ShardedStats>,
+        error_log: Option<Arc<ErrorLog>>,
+        trace_log: Option<Arc<TraceLog>>,
+        partition_locks: Option<Arc<PartitionLocks>>,
+    ) -> Result<Self> {
+        let operation_ratio = Arc::new(operation_ratio);
+        let seeds = SeedSequence::new(settings.seed.seed);
+        let table = settings.schema.table_identifier();
+        let counter_table = settings.schema.counter_table_identifier();
+        // The statements of the subcommands are prepared concurrently.
+        let (
+            write_operation_factory,
+            counter_write_operation_factory,
+            read_operation_factory,
+            counter_read_operation_factory,
+        ) = futures::join!(
+            Self::conditional_create_factory(&operation_ratio, &MixedSubcommand::Write, || {
+                WriteOperationFactory::new(settings.clone(), session.clone())
+            }),
+            Self::conditional_create_factory(
+                &operation_ratio,
+                &MixedSubcommand::CounterWrite,
+                || CounterWriteOperationFactory::new(settings.clone(), session.clone()),
+            ),
+            Self::conditional_create_factory(&operation_ratio, &MixedSubcommand::Read, || {
+                RegularReadOperationFactory::new(settings.clone(), session.clone(), &table, "read")
+            }),
+            Self::conditional_create_factory(
+                &operation_ratio,
+                &MixedSubcommand::CounterRead,
+                || {
+                    CounterReadOperationFactory::new(
+                        settings.clone(),
+                        session.clone(),
+                        &counter_table,
+                        "counter_read",
+                    )
+                },
+            ),
+        );
+        let write_operation_factory = write_operation_factory.transpose()?;
+        let counter_write_operation_factory = counter_write_operation_factory.transpose()?;
+        let read_operation_factory = read_operation_factory.transpose()?;
+        let counter_read_operation_factory = counter_read_operation_factory.transpose()?;
+        // The queries of the profile are prepared only if any of them is in the ratio,
+        // e.g. not by the streams of the commands (see `-rate streams`).
+        #[cfg(feature = "user-profile")]
+        let user_operation_factory = match settings
+            .command_params
+            .mixed
+            .as_ref()
+            .and_then(|mixed| mixed.user.as_ref())
+        {
+            Some(user) if operation_ratio.queries().next().is_some() => Some(
+                UserOperationFactory::with_profile(
+                    &settings,
+                    user,
                     session.clone(),
-                    DEFAULT_COUNTER_TABLE_NAME,
+                    stats.clone(),
+                    error_log.clone(),
                 )
-            },
-        )
-        .await
-        .transpose()?;
+                .await?,
+            ),
+            _ => None,
+        };
 
         Ok(Self {
             settings,
@@ -142,10 +265,15 @@ impl MixedOperationFactory {
             counter_write_operation_factory,
             read_operation_factory,
             counter_read_operation_factory,
+            #[cfg(feature = "user-profile")]
+            user_operation_factory,
             operation_ratio,
             workload_factory,
-            max_operations,
             stats,
+            error_log,
+            trace_log,
+            partition_locks,
+            seeds,
         })
     }
 
@@ -162,63 +290,146 @@ impl MixedOperationFactory {
     }
 }
 
-make_runnable!(MixedOperation);
+make_runnable!(MixedOperation, stats);
 impl MixedOperation {
     async fn execute(&mut self, ctx: &OperationContext) -> Result<ControlFlow<()>> {
-        if self
-            .max_operations
-            .is_some_and(|max_ops| ctx.operation_id >= max_ops)
-        {
-            return Ok(ControlFlow::Break(()));
-        }
-
-        if self.current_operation_remaining == 0 {
-            self.current_operation = self.operation_ratio.sample();
-            self.current_operation_remaining =
-                (self.clustering_distribution.next_i64() as usize).max(1);
-        }
+        // The retried operation is the same as the one which generated the row.
+        self.current_operation = match self.row.pending() {
+            Some(_) => *self.sampler.previous_sample(),
+            None => *self.sampler.sample(),
+        };
 
         // FIXME: Get rid of these unwraps once async traits are considered object-safe.
         let result = match &self.current_operation {
             MixedSubcommand::Read => {
                 // This is safe. We create a given operation only if corresponding `MixedSubcommand` is defined in `operation_ratio` map.
-                let read_operation = self.read_operation.as_ref().unwrap();
-                let row = self
-                    .cached_row
-                    .get_or_insert_with(|| read_operation.generate_row(&mut self.workload));
-                read_operation.execute(row).await
+                let read_operation = self.read_operation.as_mut().unwrap();
+                let row = self.row.get_or_generate(|row| {
+                    read_operation.generate_partitions_into(&mut self.workload, row)
+                });
+                if self.workload.sequence_exhausted()? {
+                    return Ok(ControlFlow::Break(()));
+                }
+                execute_sampled(
+                    read_operation,
+                    row,
+                    ctx,
+                    self.stats.sharded(),
+                    self.partition_locks.as_deref(),
+                    &self.coordinator,
+                    self.trace_log.as_deref(),
+                    self.current_operation,
+                )
+                .await
             }
             MixedSubcommand::CounterRead => {
                 // This is safe. We create a given operation only if corresponding `MixedSubcommand` is defined in `operation_ratio` map.
-                let counter_read_operation = self.counter_read_operation.as_ref().unwrap();
-                let row = self
-                    .cached_row
-                    .get_or_insert_with(|| counter_read_operation.generate_row(&mut self.workload));
-                counter_read_operation.execute(row).await
+                let counter_read_operation = self.counter_read_operation.as_mut().unwrap();
+                let row = self.row.get_or_generate(|row| {
+                    counter_read_operation.generate_partitions_into(&mut self.workload, row)
+                });
+                if self.workload.sequence_exhausted()? {
+                    return Ok(ControlFlow::Break(()));
+                }
+                execute_sampled(
+                    counter_read_operation,
+                    row,
+                    ctx,
+                    self.stats.sharded(),
+                    self.partition_locks.as_deref(),
+                    &self.coordinator,
+                    self.trace_log.as_deref(),
+                    self.current_operation,
+                )
+                .await
             }
             MixedSubcommand::Write => {
                 // This is safe. We create a given operation only if corresponding `MixedSubcommand` is defined in `operation_ratio` map.
-                let write_operation = self.write_operation.as_ref().unwrap();
-                let row = self
-                    .cached_row
-                    .get_or_insert_with(|| write_operation.generate_row(&mut self.workload));
-                write_operation.execute(row).await
+                let write_operation = self.write_operation.as_mut().unwrap();
+                let row = self.row.get_or_generate(|row| {
+                    write_operation.generate_partitions_into(&mut self.workload, row)
+                });
+                if self.workload.sequence_exhausted()? {
+                    return Ok(ControlFlow::Break(()));
+                }
+                execute_sampled(
+                    write_operation,
+                    row,
+                    ctx,
+                    self.stats.sharded(),
+                    self.partition_locks.as_deref(),
+                    &self.coordinator,
+                    self.trace_log.as_deref(),
+                    self.current_operation,
+                )
+                .await
             }
             MixedSubcommand::CounterWrite => {
                 // This is safe. We create a given operation only if corresponding `MixedSubcommand` is defined in `operation_ratio` map.
-                let counter_write_operation = self.counter_write_operation.as_ref().unwrap();
-                let row = self.cached_row.get_or_insert_with(|| {
-                    counter_write_operation.generate_row(&mut self.workload)
+                let counter_write_operation = self.counter_write_operation.as_mut().unwrap();
+                let row = self.row.get_or_generate(|row| {
+                    counter_write_operation.generate_partitions_into(&mut self.workload, row)
+                });
+                if self.workload.sequence_exhausted()? {
+                    return Ok(ControlFlow::Break(()));
+                }
+                execute_sampled(
+                    counter_write_operation,
+                    row,
+                    ctx,
+                    self.stats.sharded(),
+                    self.partition_locks.as_deref(),
+                    &self.coordinator,
+                    self.trace_log.as_deref(),
+                    self.current_operation,
+                )
+                .await
+            }
+            #[cfg(feature = "user-profile")]
+            MixedSubcommand::Query(name) => {
+                // This is safe. The operations are created for all of the queries in `operation_ratio` map.
+                let query_operation = self.query_operations.get_mut(*name).unwrap();
+                let user_workload = self.user_workload.as_mut().unwrap();
+                let row = self.row.get_or_generate(|row| {
+                    query_operation.generate_partitions_into(user_workload, row)
                 });
-                counter_write_operation.execute(row).await
+                if user_workload.sequence_exhausted()? {
+                    return Ok(ControlFlow::Break(()));
+                }
+                execute_sampled(
+                    query_operation,
+                    row,
+                    ctx,
+                    self.stats.sharded(),
+                    self.partition_locks.as_deref(),
+                    &self.coordinator,
+                    self.trace_log.as_deref(),
+                    self.current_operation,
+                )
+                .await
             }
         };
 
-        self.stats.get_shard_mut().account_operation(ctx, &result);
+        let coordinator = self.coordinator.take();
+        self.stats
+            .record(Sample::new(ctx, &result).with_coordinator(coordinator));
+        let row = self.row.pending().map_or(&[][..], |row| &row.values);
+        let partition_key = match self.current_operation {
+            MixedSubcommand::CounterWrite => self
+                .counter_write_operation
+                .as_ref()
+                .and_then(|op| op.partition_key(row)),
+            _ => row.first(),
+        };
+        log_error(
+            self.error_log.as_deref(),
+            self.current_operation,
+            partition_key,
+            &result,
+        );
 
         if result.is_ok() {
-            self.current_operation_remaining -= 1;
-            self.cached_row = None;
+            self.row.complete();
         }
 
         result
@@ -1,43 +1,59 @@
+mod checksum;
 mod counter_write;
+mod index_read;
 mod mixed;
+#[cfg(feature = "user-profile")]
+mod offline_schema;
 mod read;
+mod read_modify_write;
 mod row_generator;
+mod scan;
 #[cfg(feature = "user-profile")]
 mod user;
 mod write;
 
-use anyhow::Result;
+use anyhow::{Context, Result};
 use cql_stress::configuration::Operation;
 use cql_stress::configuration::OperationContext;
 use cql_stress::configuration::OperationFactory;
+use cql_stress::coordinator::LastCoordinator;
+use cql_stress::dry_run::{OperationRenderer, RenderedStatement};
+use cql_stress::error_log::ErrorLog;
 use cql_stress::make_runnable;
-#[cfg(feature = "user-profile")]
+use cql_stress::partition_lock::PartitionLocks;
+use cql_stress::reprepare::is_stale_statement_error;
+use cql_stress::trace_log::TraceLog;
+use parking_lot::Mutex;
 use rand_distr::{Distribution as _, WeightedIndex};
+use scylla::history::HistoryListener;
 use scylla::Session;
+use std::fmt::{Debug, Display};
 use std::future::Future;
+use std::hash::{Hash, Hasher};
 use std::num::Wrapping;
 use std::ops::ControlFlow;
 use std::sync::Arc;
+use std::time::{Duration, Instant};
+use uuid::Uuid;
 
 pub use mixed::MixedOperationFactory;
 pub use row_generator::RowGeneratorFactory;
 use scylla::{
     frame::response::result::{CqlValue, Row},
+    prepared_statement::PreparedStatement,
+    serialize::row::SerializeRow,
+    transport::{errors::QueryError, PagingState},
     QueryResult,
 };
 #[cfg(feature = "user-profile")]
 pub use user::UserOperationFactory;
 
-#[cfg(feature = "user-profile")]
 use crate::java_generate::distribution::{Distribution, DistributionFactory};
-use crate::settings::CassandraStressSettings;
-use crate::stats::ShardedStats;
+use crate::settings::{CassandraStressSettings, Command, MixedSubcommand};
+use crate::stats::{Sample, ScannedRows, ShardedStats, StatsBuffer};
 
 use self::row_generator::RowGenerator;
 
-const DEFAULT_TABLE_NAME: &str = "standard1";
-const DEFAULT_COUNTER_TABLE_NAME: &str = "counter1";
-
 /// A specific CassandraStress operation.
 ///
 /// The operation implementing this trait should handle
@@ -51,8 +67,28 @@ const DEFAULT_COUNTER_TABLE_NAME: &str = "counter1";
 /// During the operation retry (i.e. when `execute` returned and error),
 /// we will make use of the same row that we originally used in the previous try.
 ///
-/// We only generate a new row ([`CassandraStressOperation::generate_row`])
-/// during the first try to perform an operation.
+/// We only generate a new row ([`CassandraStressOperation::generate_partitions_into`])
+/// during the first try to perform an operation. The row is generated into
+/// the buffer of the previous row (see [`RowBuffer`]), so the values
+/// are not allocated for each of the operations.
+/// ### Statement re-preparation
+/// If `execute` failed because the statement was invalidated by a schema change,
+/// the statement is prepared again via [`CassandraStressOperation::reprepare`]
+/// and the operation is retried once, before its result is recorded.
+/// ### Tracing
+/// The operations sampled by the trace log (see `-log tracefile=`) are executed
+/// with server-side tracing enabled via [CassandraStressOperation::set_tracing],
+/// and the id of the tracing session is then taken with
+/// [CassandraStressOperation::take_tracing_id]. The operations which
+/// don't support tracing keep the default implementations and aren't traced.
+/// ### Coordinators
+/// Each operation learns the coordinators of its queries from the listener set
+/// via [CassandraStressOperation::set_history_listener], so that the operations
+/// are counted per node. The listener is set again after the re-preparation.
+/// ### Scanned rows
+/// The operations scanning the table report the rows they scanned
+/// via [CassandraStressOperation::take_scanned_rows], which are recorded
+/// along with the result.
 /// ### Stats recording
 /// The result of `execute` is recorded
 /// to [`ShardedStats`] - even if the operation failed, so we keep track
@@ -61,7 +97,54 @@ pub trait CassandraStressOperation: Sync + Send {
     type Factory: CassandraStressOperationFactory<Operation = Self>;
 
     fn execute(&self, row: &[CqlValue]) -> impl Future<Output = Result<ControlFlow<()>>> + Send;
+    /// Prepares the statements of the operation again,
+    /// after a schema change invalidated them.
+    fn reprepare(&mut self) -> impl Future<Output = Result<()>> + Send;
     fn generate_row(&self, row_generator: &mut RowGenerator) -> Vec<CqlValue>;
+
+    /// Generates the row into `row`, reusing its buffers if the operation
+    /// supports it. The generated row is the same as the one returned by `generate_row`.
+    fn generate_row_into(&self, row_generator: &mut RowGenerator, row: &mut Vec<CqlValue>) {
+        *row = self.generate_row(row_generator);
+    }
+
+    /// Generates the row of the next operation into `row`, along with the offsets
+    /// of the partitions written by the operation. By default, the operation
+    /// touches a single partition, whose row is generated by `generate_row_into`.
+    fn generate_partitions_into(&self, row_generator: &mut RowGenerator, row: &mut GeneratedRow) {
+        self.generate_row_into(row_generator, &mut row.values);
+        row.partition_starts.clear();
+    }
+
+    /// Enables or disables server-side tracing of the statements
+    /// executed by the following calls to `execute`.
+    fn set_tracing(&mut self, _tracing: bool) {}
+
+    /// Sets the history listener of all of the statements of the operation.
+    fn set_history_listener(&mut self, _listener: Arc<dyn HistoryListener>) {}
+
+    /// Returns the id of the tracing session of the last traced statement,
+    /// unless it was already taken.
+    fn take_tracing_id(&self) -> Option<Uuid> {
+        None
+    }
+
+    /// Returns the rows scanned by the last successful call to `execute`,
+    /// unless they were already taken.
+    fn take_scanned_rows(&self) -> Option<ScannedRows> {
+        None
+    }
+
+    /// Returns the partition key of the row generated by `generate_row`.
+    fn partition_key<'a>(&self, row: &'a [CqlValue]) -> Option<&'a CqlValue> {
+        row.first()
+    }
+
+    /// Returns the keys of all partitions touched by the operation,
+    /// which are locked for its time if `-rate serialize-partitions` is set.
+    fn partition_keys<'a>(&self, row: &'a GeneratedRow) -> Vec<&'a CqlValue> {
+        self.partition_key(&row.values).into_iter().collect()
+    }
 }
 
 pub trait CassandraStressOperationFactory: Sync + Send + Sized {
@@ -73,56 +156,127 @@ pub trait CassandraStressOperationFactory: Sync + Send + Sized {
 /// Generic CassandraStress operation.
 ///
 /// It handles the common logic for all of the operations, such as:
-/// - checking whether `max_operations` operations have already been performed
 /// - caching the row for operation retries
 /// - recording operation result to statistics structure
 ///
 /// Delegates the specific logic to `cs_operation`.
 pub struct GenericCassandraStressOperation<O: CassandraStressOperation> {
     cs_operation: O,
-    stats: Arc<ShardedStats>,
+    stats: StatsBuffer,
+    error_log: Option<Arc<ErrorLog>>,
+    trace_log: Option<Arc<TraceLog>>,
+    partition_locks: Option<Arc<PartitionLocks>>,
+    coordinator: Arc<LastCoordinator>,
+    operation_name: &'static str,
     workload: RowGenerator,
-    max_operations: Option<u64>,
     // The operation may need to be retried.
     // This is why we cache the row so it can be used
     // during the retry.
-    cached_row: Option<Vec<CqlValue>>,
+    row: RowBuffer,
 }
 
-make_runnable!(GenericCassandraStressOperation<O: CassandraStressOperation>);
+make_runnable!(GenericCassandraStressOperation<O: CassandraStressOperation>, stats);
 impl<O: CassandraStressOperation> GenericCassandraStressOperation<O> {
     async fn execute(&mut self, ctx: &OperationContext) -> Result<ControlFlow<()>> {
-        if self
-            .max_operations
-            .is_some_and(|max_ops| ctx.operation_id >= max_ops)
-        {
+        let row = self.row.get_or_generate(|row| {
+            self.cs_operation
+                .generate_partitions_into(&mut self.workload, row)
+        });
+        if self.workload.sequence_exhausted()? {
             return Ok(ControlFlow::Break(()));
         }
 
-        let row = self
-            .cached_row
-            .get_or_insert_with(|| self.cs_operation.generate_row(&mut self.workload));
-
-        let op_result = self.cs_operation.execute(row).await;
-        self.stats
-            .get_shard_mut()
-            .account_operation(ctx, &op_result);
+        let op_result = execute_sampled(
+            &mut self.cs_operation,
+            row,
+            ctx,
+            self.stats.sharded(),
+            self.partition_locks.as_deref(),
+            &self.coordinator,
+            self.trace_log.as_deref(),
+            self.operation_name,
+        )
+        .await;
+        let coordinator = self.coordinator.take();
+        let scanned_rows = self.cs_operation.take_scanned_rows();
+        self.stats.record(
+            Sample::new(ctx, &op_result)
+                .with_coordinator(coordinator)
+                .with_scanned_rows(scanned_rows),
+        );
+        log_error(
+            self.error_log.as_deref(),
+            self.operation_name,
+            self.cs_operation.partition_key(&row.values),
+            &op_result,
+        );
 
         if op_result.is_ok() {
             // Operation was successful - we will generate new row
             // for the next operation.
-            self.cached_row = None;
+            self.row.complete();
         }
 
         op_result
     }
 }
 
+/// The values generated for an operation.
+///
+/// An operation writing several partitions at once (see `batch=`
+/// of the write command) concatenates their rows in `values`.
+/// The visits of the partitions consist of different numbers of rows
+/// with `-insert visits=`, so the offsets at which each of the partitions
+/// starts are kept in `partition_starts`, which is empty for the operations
+/// touching a single partition.
+#[derive(Default, Debug, PartialEq)]
+pub struct GeneratedRow {
+    pub values: Vec<CqlValue>,
+    pub partition_starts: Vec<usize>,
+}
+
+/// The row of an operation, kept for its retries.
+///
+/// Once the operation completes, the buffer is reused
+/// for the row of the next operation.
+#[derive(Default)]
+struct RowBuffer {
+    row: GeneratedRow,
+    // Whether the row belongs to the operation which is not completed yet.
+    pending: bool,
+}
+
+impl RowBuffer {
+    /// Returns the row of the pending operation.
+    /// If there is no such operation, a new row is generated into the buffer.
+    fn get_or_generate(&mut self, generate: impl FnOnce(&mut GeneratedRow)) -> &GeneratedRow {
+        if !self.pending {
+            generate(&mut self.row);
+            self.pending = true;
+        }
+        &self.row
+    }
+
+    /// Returns the row of the pending operation, if there is one.
+    fn pending(&self) -> Option<&GeneratedRow> {
+        self.pending.then_some(&self.row)
+    }
+
+    /// Marks the operation as completed, so that the next call
+    /// to `get_or_generate` generates a new row.
+    fn complete(&mut self) {
+        self.pending = false;
+    }
+}
+
 pub struct GenericCassandraStressOperationFactory<O: CassandraStressOperation> {
     cs_operation_factory: O::Factory,
     workload_factory: RowGeneratorFactory,
-    max_operations: Option<u64>,
     stats: Arc<ShardedStats>,
+    error_log: Option<Arc<ErrorLog>>,
+    trace_log: Option<Arc<TraceLog>>,
+    partition_locks: Option<Arc<PartitionLocks>>,
+    operation_name: &'static str,
 }
 
 pub type WriteOperationFactory = GenericCassandraStressOperationFactory<write::WriteOperation>;
@@ -132,6 +286,11 @@ pub type RegularReadOperationFactory =
     GenericCassandraStressOperationFactory<read::RegularReadOperation>;
 pub type CounterReadOperationFactory =
     GenericCassandraStressOperationFactory<read::CounterReadOperation>;
+pub type IndexReadOperationFactory =
+    GenericCassandraStressOperationFactory<index_read::IndexReadOperation>;
+pub type ReadModifyWriteOperationFactory =
+    GenericCassandraStressOperationFactory<read_modify_write::ReadModifyWriteOperation>;
+pub type ScanOperationFactory = GenericCassandraStressOperationFactory<scan::ScanOperation>;
 
 impl WriteOperationFactory {
     pub async fn new(
@@ -139,15 +298,20 @@ impl WriteOperationFactory {
         session: Arc<Session>,
         workload_factory: RowGeneratorFactory,
         stats: Arc<ShardedStats>,
+        error_log: Option<Arc<ErrorLog>>,
+        trace_log: Option<Arc<TraceLog>>,
     ) -> Result<Self> {
-        let max_operations = settings.command_params.common.operation_count;
+        let partition_locks = settings.rate.create_partition_locks();
         let cs_operation_factory = write::WriteOperationFactory::new(settings, session).await?;
 
         Ok(Self {
             cs_operation_factory,
-            max_operations,
-            workload_factory,
+            workload_factory: workload_factory.with_visits(),
             stats,
+            error_log,
+            trace_log,
+            partition_locks,
+            operation_name: "write",
         })
     }
 }
@@ -158,16 +322,21 @@ impl CounterWriteOperationFactory {
         session: Arc<Session>,
         workload_factory: RowGeneratorFactory,
         stats: Arc<ShardedStats>,
+        error_log: Option<Arc<ErrorLog>>,
+        trace_log: Option<Arc<TraceLog>>,
     ) -> Result<Self> {
-        let max_operations = settings.command_params.common.operation_count;
+        let partition_locks = settings.rate.create_partition_locks();
         let cs_operation_factory =
             counter_write::CounterWriteOperationFactory::new(settings, session).await?;
 
         Ok(Self {
             cs_operation_factory,
-            max_operations,
             workload_factory,
             stats,
+            error_log,
+            trace_log,
+            partition_locks,
+            operation_name: "counter_write",
         })
     }
 }
@@ -178,16 +347,22 @@ impl RegularReadOperationFactory {
         session: Arc<Session>,
         workload_factory: RowGeneratorFactory,
         stats: Arc<ShardedStats>,
+        error_log: Option<Arc<ErrorLog>>,
+        trace_log: Option<Arc<TraceLog>>,
     ) -> Result<Self> {
-        let max_operations = settings.command_params.common.operation_count;
+        let partition_locks = settings.rate.create_partition_locks();
+        let table = settings.schema.table_identifier();
         let cs_operation_factory =
-            read::RegularReadOperationFactory::new(settings, session, DEFAULT_TABLE_NAME).await?;
+            read::RegularReadOperationFactory::new(settings, session, &table, "read").await?;
 
         Ok(Self {
             cs_operation_factory,
-            max_operations,
             workload_factory,
             stats,
+            error_log,
+            trace_log,
+            partition_locks,
+            operation_name: "read",
         })
     }
 }
@@ -198,17 +373,101 @@ impl CounterReadOperationFactory {
         session: Arc<Session>,
         workload_factory: RowGeneratorFactory,
         stats: Arc<ShardedStats>,
+        error_log: Option<Arc<ErrorLog>>,
+        trace_log: Option<Arc<TraceLog>>,
     ) -> Result<Self> {
-        let max_operations = settings.command_params.common.operation_count;
+        let partition_locks = settings.rate.create_partition_locks();
+        let counter_table = settings.schema.counter_table_identifier();
+        let cs_operation_factory = read::CounterReadOperationFactory::new(
+            settings,
+            session,
+            &counter_table,
+            "counter_read",
+        )
+        .await?;
+
+        Ok(Self {
+            cs_operation_factory,
+            workload_factory,
+            stats,
+            error_log,
+            trace_log,
+            partition_locks,
+            operation_name: "counter_read",
+        })
+    }
+}
+
+impl IndexReadOperationFactory {
+    pub async fn new(
+        settings: Arc<CassandraStressSettings>,
+        session: Arc<Session>,
+        workload_factory: RowGeneratorFactory,
+        stats: Arc<ShardedStats>,
+        error_log: Option<Arc<ErrorLog>>,
+        trace_log: Option<Arc<TraceLog>>,
+    ) -> Result<Self> {
+        let partition_locks = settings.rate.create_partition_locks();
+        let cs_operation_factory =
+            index_read::IndexReadOperationFactory::new(settings, session).await?;
+
+        Ok(Self {
+            cs_operation_factory,
+            workload_factory,
+            stats,
+            error_log,
+            trace_log,
+            partition_locks,
+            operation_name: "index_read",
+        })
+    }
+}
+
+impl ReadModifyWriteOperationFactory {
+    pub async fn new(
+        settings: Arc<CassandraStressSettings>,
+        session: Arc<Session>,
+        workload_factory: RowGeneratorFactory,
+        stats: Arc<ShardedStats>,
+        error_log: Option<Arc<ErrorLog>>,
+        trace_log: Option<Arc<TraceLog>>,
+    ) -> Result<Self> {
+        let partition_locks = settings.rate.create_partition_locks();
         let cs_operation_factory =
-            read::CounterReadOperationFactory::new(settings, session, DEFAULT_COUNTER_TABLE_NAME)
-                .await?;
+            read_modify_write::ReadModifyWriteOperationFactory::new(settings, session).await?;
+
+        Ok(Self {
+            cs_operation_factory,
+            workload_factory,
+            stats,
+            error_log,
+            trace_log,
+            partition_locks,
+            operation_name: "read_modify_write",
+        })
+    }
+}
+
+impl ScanOperationFactory {
+    pub async fn new(
+        settings: Arc<CassandraStressSettings>,
+        session: Arc<Session>,
+        workload_factory: RowGeneratorFactory,
+        stats: Arc<ShardedStats>,
+        error_log: Option<Arc<ErrorLog>>,
+        trace_log: Option<Arc<TraceLog>>,
+    ) -> Result<Self> {
+        let cs_operation_factory = scan::ScanOperationFactory::new(settings, session).await?;
 
         Ok(Self {
             cs_operation_factory,
-            max_operations,
             workload_factory,
             stats,
+            error_log,
+            trace_log,
+            // The scans don't touch single partitions.
+            partition_locks: None,
+            operation_name: "scan",
         })
     }
 }
@@ -217,18 +476,325 @@ impl<O: CassandraStressOperation + 'static> OperationFactory
     for GenericCassandraStressOperationFactory<O>
 {
     fn create(&self) -> Box<dyn Operation> {
-        let cs_operation = self.cs_operation_factory.create();
+        let mut cs_operation = self.cs_operation_factory.create();
+        let coordinator = Arc::new(LastCoordinator::default());
+        cs_operation.set_history_listener(coordinator.clone());
 
         Box::new(GenericCassandraStressOperation {
             cs_operation,
-            stats: Arc::clone(&self.stats),
+            stats: StatsBuffer::new(Arc::clone(&self.stats)),
+            error_log: self.error_log.clone(),
+            trace_log: self.trace_log.clone(),
+            partition_locks: self.partition_locks.clone(),
+            coordinator,
+            operation_name: self.operation_name,
             workload: self.workload_factory.create(),
-            max_operations: self.max_operations,
-            cached_row: None,
+            row: RowBuffer::default(),
         })
     }
 }
 
+/// The dry-run counterpart of [`CassandraStressOperation`].
+///
+/// Instead of sending the statements to the database, it renders them
+/// along with the values bound from the generated row.
+pub trait CassandraStressRenderer: Send {
+    fn render(&mut self, row_generator: &mut RowGenerator) -> Vec<RenderedStatement>;
+}
+
+/// Renders the consecutive operations of a single type in the dry-run mode.
+///
+/// The rows are generated the same way as by the first worker of the benchmark,
+/// so the rendered values are the ones which would be sent to the database.
+pub struct GenericCassandraStressRenderer {
+    cs_renderer: Box<dyn CassandraStressRenderer>,
+    operation_name: String,
+    workload: RowGenerator,
+}
+
+impl GenericCassandraStressRenderer {
+    fn new(
+        operation_name: impl Into<String>,
+        cs_renderer: Box<dyn CassandraStressRenderer>,
+        workload: RowGenerator,
+    ) -> Self {
+        Self {
+            cs_renderer,
+            operation_name: operation_name.into(),
+            workload,
+        }
+    }
+}
+
+impl OperationRenderer for GenericCassandraStressRenderer {
+    fn name(&self) -> &str {
+        &self.operation_name
+    }
+
+    fn render(&mut self) -> Option<Vec<RenderedStatement>> {
+        // The rows are generated endlessly, the number of operations
+        // is limited only by the benchmark settings.
+        Some(self.cs_renderer.render(&mut self.workload))
+    }
+}
+
+/// Creates the renderers of the operations performed by the command,
+/// which replace the operation factories in the dry-run mode.
+/// The mixed command gets a renderer for each of its sub-commands.
+pub fn create_renderers(
+    settings: &Arc<CassandraStressSettings>,
+) -> Result<Vec<Box<dyn OperationRenderer>>> {
+    let workload_factory = RowGeneratorFactory::new(Arc::clone(settings));
+    let renderer = |operation_name: &str, cs_renderer: Box<dyn CassandraStressRenderer>| {
+        Box::new(GenericCassandraStressRenderer::new(
+            operation_name,
+            cs_renderer,
+            workload_factory.create(),
+        )) as Box<dyn OperationRenderer>
+    };
+
+    let renderers = match &settings.command {
+        // Only the writes visit the partitions of `-insert visits=`.
+        Command::Write => vec![Box::new(GenericCassandraStressRenderer::new(
+            "write",
+            Box::new(write::WriteRenderer::new(settings)),
+            RowGeneratorFactory::new(Arc::clone(settings))
+                .with_visits()
+                .create(),
+        )) as Box<dyn OperationRenderer>],
+        Command::Read => vec![renderer(
+            "read",
+            Box::new(read::ReadRenderer::new(
+                settings,
+                &settings.schema.table_identifier(),
+            )),
+        )],
+        Command::CounterWrite => vec![renderer(
+            "counter_write",
+            Box::new(counter_write::CounterWriteRenderer::new(settings)),
+        )],
+        Command::CounterRead => vec![renderer(
+            "counter_read",
+            Box::new(read::ReadRenderer::new(
+                settings,
+                &settings.schema.counter_table_identifier(),
+            )),
+        )],
+        Command::IndexRead => vec![renderer(
+            "index_read",
+            Box::new(index_read::IndexReadRenderer::new(settings)),
+        )],
+        Command::ReadModifyWrite => vec![renderer(
+            "read_modify_write",
+            Box::new(read_modify_write::ReadModifyWriteRenderer::new(settings)),
+        )],
+        Command::Scan => vec![renderer(
+            "scan",
+            Box::new(scan::ScanRenderer::new(settings)),
+        )],
+        Command::Mixed => {
+            // Validated during parsing.
+            let operation_ratio = &settings
+                .command_params
+                .mixed
+                .as_ref()
+                .unwrap()
+                .operation_ratio;
+            #[cfg(feature = "user-profile")]
+            anyhow::ensure!(
+                operation_ratio.queries().next().is_none(),
+                "Dry run of the mixed command doesn't support the queries of a profile yet."
+            );
+            [
+                MixedSubcommand::Read,
+                MixedSubcommand::Write,
+                MixedSubcommand::CounterRead,
+                MixedSubcommand::CounterWrite,
+            ]
+            .into_iter()
+            .filter(|subcommand| operation_ratio.contains(subcommand))
+            .map(|subcommand| {
+                let cs_renderer: Box<dyn CassandraStressRenderer> = match subcommand {
+                    MixedSubcommand::Read => Box::new(read::ReadRenderer::new(
+                        settings,
+                        &settings.schema.table_identifier(),
+                    )),
+                    MixedSubcommand::Write => Box::new(write::WriteRenderer::new(settings)),
+                    MixedSubcommand::CounterRead => Box::new(read::ReadRenderer::new(
+                        settings,
+                        &settings.schema.counter_table_identifier(),
+                    )),
+                    MixedSubcommand::CounterWrite => {
+                        Box::new(counter_write::CounterWriteRenderer::new(settings))
+                    }
+                    #[cfg(feature = "user-profile")]
+                    MixedSubcommand::Query(_) => unreachable!(),
+                };
+                renderer(&subcommand.to_string(), cs_renderer)
+            })
+            .collect()
+        }
+        #[cfg(feature = "user-profile")]
+        Command::User => user::create_renderers(settings)?,
+        cmd => anyhow::bail!("Dry run for command '{}' not implemented yet.", cmd.show()),
+    };
+    Ok(renderers)
+}
+
+/// Renders the values bound to a statement in the dry-run mode.
+/// The blobs are shown in hex and the strings are quoted, the same as in cqlsh.
+fn render_values<'a>(values: impl IntoIterator<Item = &'a CqlValue>) -> Vec<String> {
+    values
+        .into_iter()
+        .map(|value| match value {
+            CqlValue::Blob(bytes) => {
+                let hex: String = bytes.iter().map(|byte| format!("{:02x}", byte)).collect();
+                format!("0x{}", hex)
+            }
+            CqlValue::Text(text) | CqlValue::Ascii(text) => format!("'{}'", text),
+            CqlValue::Counter(counter) => counter.0.to_string(),
+            value => format!("{:?}", value),
+        })
+        .collect()
+}
+
+/// Executes the operation, re-preparing its statements and retrying once
+/// if they were invalidated by a schema change.
+///
+/// The history listener of the operation is the given coordinator,
+/// which is set again on the re-prepared statements.
+///
+/// If the partition locks are given, the partitions touched by the operation
+/// are locked until it finishes, including the retry.
+async fn execute_with_reprepare<O: CassandraStressOperation>(
+    operation: &mut O,
+    row: &GeneratedRow,
+    stats: &ShardedStats,
+    partition_locks: Option<&PartitionLocks>,
+    coordinator: &Arc<LastCoordinator>,
+) -> Result<ControlFlow<()>> {
+    let _guard = match partition_locks {
+        Some(locks) => Some(
+            locks
+                .lock(
+                    operation
+                        .partition_keys(row)
+                        .into_iter()
+                        .map(LockedPartitionKey),
+                )
+                .await,
+        ),
+        None => None,
+    };
+    let result = operation.execute(&row.values).await;
+    if result.as_ref().is_err_and(is_stale_statement_error) {
+        operation.reprepare().await?;
+        operation.set_history_listener(coordinator.clone());
+        stats.get_shard_mut().account_reprepare();
+        return operation.execute(&row.values).await;
+    }
+    result
+}
+
+/// Executes the operation via [execute_with_reprepare]. If the operation
+/// is sampled by the trace log, it's executed with server-side tracing enabled
+/// and its tracing session is recorded to the log.
+async fn execute_sampled<O: CassandraStressOperation>(
+    operation: &mut O,
+    row: &GeneratedRow,
+    ctx: &OperationContext,
+    stats: &ShardedStats,
+    partition_locks: Option<&PartitionLocks>,
+    coordinator: &Arc<LastCoordinator>,
+    trace_log: Option<&TraceLog>,
+    operation_name: impl Display,
+) -> Result<ControlFlow<()>> {
+    let trace_log = match trace_log {
+        Some(trace_log) if trace_log.should_trace(ctx.operation_id) => trace_log,
+        _ => {
+            return execute_with_reprepare(operation, row, stats, partition_locks, coordinator)
+                .await
+        }
+    };
+
+    operation.set_tracing(true);
+    let start = Instant::now();
+    let result = execute_with_reprepare(operation, row, stats, partition_locks, coordinator).await;
+    let latency = start.elapsed();
+    operation.set_tracing(false);
+
+    if let Some(tracing_id) = operation.take_tracing_id() {
+        trace_log.record(
+            operation_name,
+            LoggedPartitionKey(operation.partition_key(&row.values)),
+            latency,
+            &[tracing_id],
+        );
+    }
+    result
+}
+
+/// The id of the tracing session of the last traced statement executed
+/// by an operation. The results of the statements executed without
+/// tracing don't carry an id, so they don't touch the lock.
+#[derive(Default)]
+struct LastTracingId(Mutex<Option<Uuid>>);
+
+impl LastTracingId {
+    fn record(&self, result: &QueryResult) {
+        if let Some(tracing_id) = result.tracing_id {
+            *self.0.lock() = Some(tracing_id);
+        }
+    }
+
+    fn take(&self) -> Option<Uuid> {
+        self.0.lock().take()
+    }
+}
+
+/// Reports the failed operation to the error log, if the log is enabled.
+fn log_error<T>(
+    error_log: Option<&ErrorLog>,
+    operation_name: impl Display,
+    partition_key: Option<&CqlValue>,
+    result: &Result<T>,
+) {
+    if let (Some(error_log), Err(err)) = (error_log, result) {
+        error_log.report(operation_name, LoggedPartitionKey(partition_key), err);
+    }
+}
+
+/// Hashes the partition key to find its lock.
+/// [CqlValue] doesn't implement [Hash], but the keys of the same partition
+/// have the same representation.
+struct LockedPartitionKey<'a>(&'a CqlValue);
+
+impl Hash for LockedPartitionKey<'_> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        match self.0 {
+            CqlValue::Blob(bytes) => bytes.hash(state),
+            value => format!("{:?}", value).hash(state),
+        }
+    }
+}
+
+/// Formats the partition key for the error and trace logs.
+/// The blobs are shown in hex, the same as in cqlsh.
+struct LoggedPartitionKey<'a>(Option<&'a CqlValue>);
+
+impl Debug for LoggedPartitionKey<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.0 {
+            Some(CqlValue::Blob(bytes)) => {
+                write!(f, "0x")?;
+                bytes.iter().try_for_each(|byte| write!(f, "{:02x}", byte))
+            }
+            Some(value) => write!(f, "{:?}", value),
+            None => write!(f, "none"),
+        }
+    }
+}
+
 /// See https://github.com/scylladb/scylla-tools-java/blob/master/tools/stress/src/org/apache/cassandra/stress/generate/PartitionIterator.java#L725.
 fn recompute_seed(seed: i64, partition_key: &CqlValue) -> i64 {
     match partition_key {
@@ -243,61 +809,163 @@ fn recompute_seed(seed: i64, partition_key: &CqlValue) -> i64 {
     }
 }
 
-fn extract_first_row_from_query_result(query_result: &QueryResult) -> Result<&Row> {
-    let rows = match &query_result.rows {
-        Some(rows) => rows,
-        None => anyhow::bail!("Query result doesn't contain any rows.",),
-    };
+/// Executes the read unpaged, unless the page size is set with `-mode fetchsize=`.
+/// Then, all of the pages are fetched, and their rows are gathered into
+/// the result of the first page.
+async fn execute_read(
+    session: &Session,
+    statement: &PreparedStatement,
+    values: impl SerializeRow,
+    paged: bool,
+) -> Result<QueryResult, QueryError> {
+    if !paged {
+        return session.execute_unpaged(statement, values).await;
+    }
 
-    match rows.split_first() {
-        Some((first_row, remaining_rows)) => {
-            // Note that row-generation logic behaves in a way that given partition_key,
-            // there is exactly one row with this partition_key.
-            anyhow::ensure!(
-                remaining_rows.is_empty(),
-                "Multiple rows matched the key. Rows: {:?}",
-                rows
-            );
-            Ok(first_row)
+    let (mut result, mut paging_state_response) = session
+        .execute_single_page(statement, &values, PagingState::start())
+        .await?;
+    while let ControlFlow::Continue(paging_state) = paging_state_response.into_paging_control_flow()
+    {
+        let (page, next_paging_state_response) = session
+            .execute_single_page(statement, &values, paging_state)
+            .await?;
+        if let (Some(rows), Some(page_rows)) = (result.rows.as_mut(), page.rows) {
+            rows.extend(page_rows);
         }
-        None => anyhow::bail!("Query result doesn't contain any rows.",),
+        paging_state_response = next_paging_state_response;
+    }
+    Ok(result)
+}
+
+/// Prepares the named statements concurrently and prints how long
+/// the preparation of each of them took.
+///
+/// The statements are returned in the order they were given.
+/// A failure names the statement which couldn't be prepared.
+async fn prepare_statements(
+    session: &Session,
+    statements: Vec<(String, String)>,
+) -> Result<Vec<PreparedStatement>> {
+    let start = Instant::now();
+    let results = futures::future::join_all(statements.iter().map(|(_, cql)| async move {
+        let start = Instant::now();
+        let result = session.prepare(cql.as_str()).await;
+        (result, start.elapsed())
+    }))
+    .await;
+    let total = start.elapsed();
+
+    let mut prepared = Vec::with_capacity(statements.len());
+    let mut latencies = Vec::with_capacity(statements.len());
+    for ((name, cql), (result, latency)) in statements.iter().zip(results) {
+        let statement = result
+            .with_context(|| format!("Failed to prepare the '{}' statement: {}", name, cql))?;
+        prepared.push(statement);
+        latencies.push((name.as_str(), latency));
     }
+    print!("{}", format_preparation_report(&latencies, total));
+
+    Ok(prepared)
+}
+
+fn format_preparation_report(latencies: &[(&str, Duration)], total: Duration) -> String {
+    let millis = |duration: Duration| duration.as_secs_f64() * 1000.0;
+    let mut report = format!(
+        "Prepared {} statement(s) in {:.3}ms:\n",
+        latencies.len(),
+        millis(total)
+    );
+    for (name, latency) in latencies {
+        report += &format!("- {}: {:.3}ms\n", name, millis(*latency));
+    }
+    report
+}
+
+fn extract_rows_from_query_result(query_result: &QueryResult) -> Result<&[Row]> {
+    match &query_result.rows {
+        Some(rows) if !rows.is_empty() => Ok(rows),
+        _ => anyhow::bail!("Query result doesn't contain any rows.",),
+    }
+}
+
+fn extract_first_row_from_query_result(query_result: &QueryResult) -> Result<&Row> {
+    let rows = extract_rows_from_query_result(query_result)?;
+    // Note that row-generation logic behaves in a way that given partition_key,
+    // there is exactly one row with this partition_key (unless `-col clustering=`
+    // is greater than 1, which isn't supported by the counter operations).
+    anyhow::ensure!(
+        rows.len() == 1,
+        "Multiple rows matched the key. Rows: {:?}",
+        rows
+    );
+    Ok(&rows[0])
 }
 
 pub trait RowValidator: Sync + Send + Default {
     fn validate_row(&self, generated_row: &[CqlValue], query_result: QueryResult) -> Result<()>;
 }
 
+/// Compares the rows of the partition with the generated row.
+///
+/// The generated row consists of the concatenated clustering rows
+/// of the partition (see `-col clustering=`), which are expected
+/// to be returned in the clustering order.
 #[derive(Default)]
 pub struct EqualRowValidator;
 impl RowValidator for EqualRowValidator {
     fn validate_row(&self, generated_row: &[CqlValue], query_result: QueryResult) -> Result<()> {
-        let first_row = extract_first_row_from_query_result(&query_result)?;
+        let rows = extract_rows_from_query_result(&query_result)?;
+        Self::validate_rows(generated_row, rows)
+    }
+}
+
+impl EqualRowValidator {
+    fn validate_rows(generated_row: &[CqlValue], rows: &[Row]) -> Result<()> {
+        let row_len = rows[0].columns.len();
+        let expected_rows = generated_row.chunks(row_len);
 
         anyhow::ensure!(
-            first_row.columns.len() == generated_row.len(),
+            rows.len() == expected_rows.len(),
+            "Expected {} rows matching the key, got {}. Rows: {:?}",
+            expected_rows.len(),
+            rows.len(),
+            rows
+        );
+
+        rows.iter()
+            .zip(expected_rows)
+            .try_for_each(|(row, expected_row)| Self::validate_columns(expected_row, row))
+    }
+
+    fn validate_columns(generated_row: &[CqlValue], result_row: &Row) -> Result<()> {
+        anyhow::ensure!(
+            result_row.columns.len() >= generated_row.len(),
             "Expected row's ({:?}) length: {}. Result row's ({:?}) length: {}",
             generated_row,
             generated_row.len(),
-            first_row.columns,
-            first_row.columns.len(),
+            result_row.columns,
+            result_row.columns.len(),
         );
 
-        let result =
-            first_row
-                .columns
-                .iter()
-                .zip(generated_row.iter())
-                .all(|(maybe_result, expected)| match maybe_result {
-                    Some(result) => result == expected,
-                    // TODO: For now, we don't permit NULLs.
-                    None => false,
-                });
+        // The generated row may be shorter than the result, if the row
+        // was written without the last columns (see `-col n=`).
+        // Such columns are expected to be NULL.
+        let (written, not_written) = result_row.columns.split_at(generated_row.len());
+        let result = written
+            .iter()
+            .zip(generated_row.iter())
+            .all(|(maybe_result, expected)| match maybe_result {
+                Some(result) => result == expected,
+                // TODO: For now, we don't permit NULLs.
+                None => false,
+            })
+            && not_written.iter().all(Option::is_none);
 
         anyhow::ensure!(
             result,
             "The data doesn't match. Result: {:?}. Expected: {:?}.",
-            first_row.columns,
+            result_row.columns,
             generated_row,
         );
         Ok(())
@@ -319,10 +987,12 @@ impl RowValidator for ExistsRowValidator {
 ///
 /// How the sampler works?
 /// One iteration consists of:
-/// - sampling an item based on ratio map. `current_item_index` is sampled from `item_index_dist`.
+/// - sampling an item based on ratio map. `current_item_index` is sampled from `item_index_dist`,
+///   using the sampler's own generator seeded with `seed`.
 ///   The item can then be retrieved via this index from `items` vector.
 /// - sampling a counter which says how many times to return the current item.
-///   The counter is sampled from `counter_dist` distribution.
+///   The counter is sampled from `counter_dist` distribution (`clustering=` of the
+///   mixed command and of the user profile). Non-positive samples are treated as 1.
 ///
 /// The user then can sample the items via `sample` or `previous_sample` method.
 ///
@@ -332,20 +1002,20 @@ impl RowValidator for ExistsRowValidator {
 /// The `previous_sample` method returns a current item without decreasing the counter.
 /// This is helpful when the user wants to, for example, retry an operation that was
 /// sampled before, but failed for some reason.
-#[cfg(feature = "user-profile")]
 struct OperationSampler<T> {
+    rng: rand_pcg::Pcg64Mcg,
     counter_dist: Box<dyn Distribution>,
     items: Vec<T>,
     item_index_dist: WeightedIndex<f64>,
-    current_item_remaining: u8,
+    current_item_remaining: u64,
     current_item_index: usize,
 }
 
-#[cfg(feature = "user-profile")]
 impl<T> OperationSampler<T> {
     pub fn new(
         weights: impl Iterator<Item = (T, f64)>,
         counter_dist_factory: &dyn DistributionFactory,
+        seed: u64,
     ) -> Self {
         let (items, weights): (Vec<_>, Vec<_>) = weights.unzip();
         // We verify the ratio properties during parsing.
@@ -354,6 +1024,7 @@ impl<T> OperationSampler<T> {
         });
 
         Self {
+            rng: rand::SeedableRng::seed_from_u64(seed),
             counter_dist: counter_dist_factory.create(),
             items,
             item_index_dist,
@@ -362,16 +1033,152 @@ impl<T> OperationSampler<T> {
         }
     }
 
-    pub fn sample(&mut self) -> &T {
+    pub fn sample(&mut self) -> &mut T {
         if self.current_item_remaining == 0 {
-            self.current_item_index = self.item_index_dist.sample(&mut rand::thread_rng());
-            self.current_item_remaining = (self.counter_dist.next_i64() as u8).max(1);
+            self.current_item_index = self.item_index_dist.sample(&mut self.rng);
+            self.current_item_remaining = self.counter_dist.next_i64().max(1) as u64;
         }
         self.current_item_remaining -= 1;
-        &self.items[self.current_item_index]
+        &mut self.items[self.current_item_index]
+    }
+
+    pub fn previous_sample(&mut self) -> &mut T {
+        &mut self.items[self.current_item_index]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use scylla::frame::{
+        response::result::{CqlValue, Row},
+        value::Counter,
+    };
+
+    use std::time::Duration;
+
+    use super::{
+        format_preparation_report, render_values, EqualRowValidator, GeneratedRow,
+        OperationSampler, RowBuffer,
+    };
+    use crate::java_generate::distribution::fixed::FixedDistributionFactory;
+
+    #[test]
+    fn preparation_report_test() {
+        let latencies = [
+            ("select", Duration::from_micros(1500)),
+            ("insert", Duration::from_millis(2)),
+        ];
+        assert_eq!(
+            "Prepared 2 statement(s) in 2.100ms:\n- select: 1.500ms\n- insert: 2.000ms\n",
+            format_preparation_report(&latencies, Duration::from_micros(2100))
+        );
+    }
+
+    #[test]
+    fn render_values_test() {
+        let values = [
+            CqlValue::Blob(vec![0x01, 0xab]),
+            CqlValue::Text(String::from("abc")),
+            CqlValue::Counter(Counter(5)),
+            CqlValue::Int(7),
+        ];
+        assert_eq!(
+            vec!["0x01ab", "'abc'", "5", "Int(7)"],
+            render_values(&values)
+        );
     }
 
-    pub fn previous_sample(&self) -> &T {
-        &self.items[self.current_item_index]
+    #[test]
+    fn operation_sampler_runs_test() {
+        // Each item is returned as many times in a row as sampled from the counter distribution.
+        let runs = |run_length: i64| {
+            let mut sampler = OperationSampler::new(
+                [("a", 1.0), ("b", 1.0)].into_iter(),
+                &FixedDistributionFactory(run_length),
+                42,
+            );
+            let samples = (0..3000).map(|_| *sampler.sample()).collect::<Vec<_>>();
+            samples
+                .chunks(run_length.max(1) as usize)
+                .all(|run| run.iter().all(|item| *item == run[0]))
+        };
+        assert!(runs(1));
+        assert!(runs(3));
+        assert!(runs(300));
+        // Non-positive run lengths are treated as 1.
+        assert!(runs(-5));
+    }
+
+    #[test]
+    fn operation_sampler_previous_sample_test() {
+        let mut sampler = OperationSampler::new(
+            [("a", 1.0), ("b", 1.0)].into_iter(),
+            &FixedDistributionFactory(2),
+            42,
+        );
+        let first = *sampler.sample();
+        // Retrying the operation doesn't consume the run.
+        assert_eq!(first, *sampler.previous_sample());
+        assert_eq!(first, *sampler.previous_sample());
+        assert_eq!(first, *sampler.sample());
+    }
+
+    #[test]
+    fn row_buffer_test() {
+        let mut buffer = RowBuffer::default();
+        assert_eq!(None, buffer.pending());
+
+        let generate =
+            |value| move |row: &mut GeneratedRow| row.values = vec![CqlValue::Int(value)];
+        let row = |value| GeneratedRow {
+            values: vec![CqlValue::Int(value)],
+            partition_starts: vec![],
+        };
+        assert_eq!(&row(1), buffer.get_or_generate(generate(1)));
+        // The row is kept until the operation completes.
+        assert_eq!(&row(1), buffer.get_or_generate(generate(2)));
+        assert_eq!(Some(&row(1)), buffer.pending());
+
+        buffer.complete();
+        assert_eq!(None, buffer.pending());
+        assert_eq!(&row(3), buffer.get_or_generate(generate(3)));
+    }
+
+    #[test]
+    fn equal_row_validator_test() {
+        let blob = |byte| CqlValue::Blob(vec![byte]);
+        let generated_row = [
+            blob(0),
+            CqlValue::Int(0),
+            blob(1),
+            blob(0),
+            CqlValue::Int(1),
+            blob(2),
+        ];
+        let result_row = |values: &[CqlValue]| Row {
+            columns: values.iter().cloned().map(Some).collect(),
+        };
+        let rows = vec![
+            result_row(&generated_row[..3]),
+            result_row(&generated_row[3..]),
+        ];
+        assert!(EqualRowValidator::validate_rows(&generated_row, &rows).is_ok());
+
+        // A missing clustering row.
+        assert!(EqualRowValidator::validate_rows(&generated_row, &rows[..1]).is_err());
+
+        // A different value of the second clustering row.
+        let corrupted = vec![
+            result_row(&generated_row[..3]),
+            result_row(&[blob(0), CqlValue::Int(1), blob(3)]),
+        ];
+        assert!(EqualRowValidator::validate_rows(&generated_row, &corrupted).is_err());
+
+        // A single row written without the last column.
+        let rows = vec![Row {
+            columns: vec![Some(blob(0)), Some(blob(1)), None],
+        }];
+        assert!(EqualRowValidator::validate_rows(&[blob(0), blob(1)], &rows).is_ok());
+        assert!(EqualRowValidator::validate_rows(&[blob(0)], &rows).is_err());
     }
 }
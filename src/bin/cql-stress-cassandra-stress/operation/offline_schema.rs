@@ -0,0 +1,413 @@
+//! Offline inspection of the CQL statements of the user profile.
+//!
+//! In the dry-run mode the tool doesn't connect to the cluster, so the schema
+//! of the stressed table and the columns bound by the queries can't be taken
+//! from the cluster metadata. Instead, they are inferred from the CQL text
+//! of the profile. Only the simple forms of the statements are supported.
+
+use std::collections::HashMap;
+use std::str::FromStr;
+
+use anyhow::{Context, Result};
+use scylla::transport::topology::{Column, ColumnKind, CqlType, NativeType, Table};
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum Token {
+    // An unquoted identifier or keyword, lowercased the same as by the server.
+    Word(String),
+    // A double-quoted, case-sensitive identifier.
+    QuotedName(String),
+    // A string or numeric literal.
+    Literal,
+    // Any other character, or one of the two-character comparison operators.
+    Symbol(String),
+}
+
+impl Token {
+    fn name(&self) -> Option<&str> {
+        match self {
+            Token::Word(name) | Token::QuotedName(name) => Some(name),
+            _ => None,
+        }
+    }
+
+    fn is_word(&self, word: &str) -> bool {
+        matches!(self, Token::Word(w) if w == word)
+    }
+
+    fn is_symbol(&self, symbol: &str) -> bool {
+        matches!(self, Token::Symbol(s) if s == symbol)
+    }
+}
+
+fn tokenize(cql: &str) -> Result<Vec<Token>> {
+    let mut tokens = Vec::new();
+    let mut chars = cql.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            c if c.is_whitespace() => {}
+            '\'' | '"' => {
+                // The quote character is escaped by doubling it.
+                let mut content = String::new();
+                loop {
+                    match chars.next() {
+                        Some(next) if next == c => {
+                            if chars.peek() == Some(&c) {
+                                chars.next();
+                                content.push(c);
+                            } else {
+                                break;
+                            }
+                        }
+                        Some(next) => content.push(next),
+                        None => anyhow::bail!("Unterminated quote in: {}", cql),
+                    }
+                }
+                tokens.push(match c {
+                    '"' => Token::QuotedName(content),
+                    _ => Token::Literal,
+                });
+            }
+            c if c.is_ascii_alphabetic() || c == '_' => {
+                let mut word = String::from(c);
+                while let Some(next) = chars.next_if(|n| n.is_ascii_alphanumeric() || *n == '_') {
+                    word.push(next);
+                }
+                tokens.push(Token::Word(word.to_lowercase()));
+            }
+            c if c.is_ascii_digit() => {
+                while chars
+                    .next_if(|n| n.is_ascii_alphanumeric() || *n == '.')
+                    .is_some()
+                {}
+                tokens.push(Token::Literal);
+            }
+            '<' | '>' | '!' if chars.peek() == Some(&'=') => {
+                chars.next();
+                tokens.push(Token::Symbol(format!("{}=", c)));
+            }
+            c => tokens.push(Token::Symbol(c.to_string())),
+        }
+    }
+    Ok(tokens)
+}
+
+/// Splits the tokens enclosed in the parentheses starting at `tokens[0]`
+/// into the comma-separated items. Returns the items and the number
+/// of the consumed tokens, including the parentheses.
+fn split_parenthesized(tokens: &[Token]) -> Result<(Vec<&[Token]>, usize)> {
+    anyhow::ensure!(
+        tokens.first().is_some_and(|t| t.is_symbol("(")),
+        "Expected '('"
+    );
+    let mut items = Vec::new();
+    let mut depth = 0;
+    let mut item_start = 1;
+    for (i, token) in tokens.iter().enumerate() {
+        match token {
+            Token::Symbol(s) if s == "(" || s == "<" => depth += 1,
+            Token::Symbol(s) if s == ")" || s == ">" => {
+                depth -= 1;
+                if depth == 0 {
+                    items.push(&tokens[item_start..i]);
+                    return Ok((items, i + 1));
+                }
+            }
+            Token::Symbol(s) if s == "," && depth == 1 => {
+                items.push(&tokens[item_start..i]);
+                item_start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    anyhow::bail!("Unbalanced parentheses")
+}
+
+fn parse_names(tokens: &[Token]) -> Result<Vec<String>> {
+    let mut names = Vec::new();
+    let mut iter = tokens.iter().peekable();
+    while let Some(token) = iter.next() {
+        let name = token
+            .name()
+            .with_context(|| format!("Expected a column name, got {:?}", token))?;
+        names.push(name.to_owned());
+        if let Some(separator) = iter.next() {
+            anyhow::ensure!(separator.is_symbol(","), "Expected ','");
+        }
+    }
+    Ok(names)
+}
+
+fn parse_type(tokens: &[Token]) -> Result<CqlType> {
+    let type_name = match tokens {
+        [Token::Word(type_name)] => type_name.as_str(),
+        _ => anyhow::bail!(
+            "Only the native column types are supported, got {:?}",
+            tokens
+        ),
+    };
+    let type_name = match type_name {
+        "varchar" => "text",
+        type_name => type_name,
+    };
+    NativeType::from_str(type_name)
+        .map(CqlType::Native)
+        .map_err(|_| anyhow::anyhow!("Unknown column type: {}", type_name))
+}
+
+/// Parses the `CREATE TABLE` statement of the user profile into the table metadata,
+/// the same as the one fetched from the cluster.
+pub fn parse_table_definition(table_definition: &str) -> Result<Table> {
+    let parse = || -> Result<Table> {
+        let tokens = tokenize(table_definition)?;
+        anyhow::ensure!(
+            tokens.len() > 2 && tokens[0].is_word("create") && tokens[1].is_word("table"),
+            "Expected a CREATE TABLE statement"
+        );
+        let columns_start = tokens
+            .iter()
+            .position(|t| t.is_symbol("("))
+            .context("Missing the column definitions")?;
+        let (items, _) = split_parenthesized(&tokens[columns_start..])?;
+
+        let mut columns = HashMap::new();
+        let mut partition_key = Vec::new();
+        let mut clustering_key = Vec::new();
+        for item in items {
+            match item {
+                [primary, key, key_spec @ ..]
+                    if primary.is_word("primary") && key.is_word("key") =>
+                {
+                    let (keys, _) = split_parenthesized(key_spec)?;
+                    let (first, clustering) = keys.split_first().context("Empty primary key")?;
+                    partition_key = match first {
+                        [paren, ..] if paren.is_symbol("(") => {
+                            let (names, _) = split_parenthesized(first)?;
+                            names
+                                .into_iter()
+                                .map(parse_names)
+                                .collect::<Result<Vec<_>>>()?
+                                .concat()
+                        }
+                        _ => parse_names(first)?,
+                    };
+                    clustering_key = clustering
+                        .iter()
+                        .map(|names| parse_names(names))
+                        .collect::<Result<Vec<_>>>()?
+                        .concat();
+                }
+                [name, type_and_options @ ..] => {
+                    let name = name
+                        .name()
+                        .with_context(|| format!("Expected a column name, got {:?}", name))?;
+                    let (type_tokens, options) = match type_and_options
+                        .iter()
+                        .position(|t| t.is_word("primary") || t.is_word("static"))
+                    {
+                        Some(options_start) => type_and_options.split_at(options_start),
+                        None => (type_and_options, &[][..]),
+                    };
+                    let is_static = options.first().is_some_and(|t| t.is_word("static"));
+                    if options.first().is_some_and(|t| t.is_word("primary")) {
+                        partition_key = vec![name.to_owned()];
+                    }
+                    let column = Column {
+                        type_: parse_type(type_tokens)
+                            .with_context(|| format!("Invalid type of column {}", name))?,
+                        kind: if is_static {
+                            ColumnKind::Static
+                        } else {
+                            ColumnKind::Regular
+                        },
+                    };
+                    columns.insert(name.to_owned(), column);
+                }
+                [] => anyhow::bail!("Empty column definition"),
+            }
+        }
+
+        anyhow::ensure!(!partition_key.is_empty(), "Missing the primary key");
+        for (names, kind) in [
+            (&partition_key, ColumnKind::PartitionKey),
+            (&clustering_key, ColumnKind::Clustering),
+        ] {
+            for name in names {
+                let column = columns
+                    .get_mut(name)
+                    .with_context(|| format!("Primary key column {} is not defined", name))?;
+                column.kind = kind.clone();
+            }
+        }
+
+        Ok(Table {
+            columns,
+            partition_key,
+            clustering_key,
+            partitioner: None,
+        })
+    };
+    parse().with_context(|| format!("Failed to parse table_definition: {}", table_definition))
+}
+
+/// Returns the names of the columns bound to the consecutive markers of the query.
+///
+/// The marker is attributed to the column it's compared with or assigned to,
+/// e.g. `pk = ?` or `ck IN ?`. In the INSERT statements, the markers
+/// in VALUES are attributed to the columns listed in the same position.
+/// Named markers, e.g. `:pk`, are attributed to the columns with their names.
+pub fn bound_columns(cql: &str) -> Result<Vec<String>> {
+    let tokens = tokenize(cql)?;
+    let insert_values = insert_values(&tokens)
+        .with_context(|| format!("Failed to parse the INSERT statement: {}", cql))?;
+
+    let mut columns = Vec::new();
+    for (i, token) in tokens.iter().enumerate() {
+        if token.is_symbol(":") {
+            if let Some(name) = tokens.get(i + 1).and_then(Token::name) {
+                columns.push(name.to_owned());
+            }
+            continue;
+        }
+        if !token.is_symbol("?") {
+            continue;
+        }
+        if let Some(column) = insert_values.get(&i) {
+            columns.push(column.clone());
+            continue;
+        }
+        let column = match i.checked_sub(2).map(|j| (&tokens[j], &tokens[j + 1])) {
+            Some((column, Token::Symbol(op)))
+                if ["=", "<", ">", "<=", ">=", "!="].contains(&op.as_str()) =>
+            {
+                column.name()
+            }
+            Some((column, op)) if op.is_word("in") || op.is_word("contains") => column.name(),
+            _ => None,
+        };
+        match column {
+            Some(column) => columns.push(column.to_owned()),
+            None => anyhow::bail!(
+                "Cannot infer the column bound to the marker number {} in: {}",
+                columns.len() + 1,
+                cql
+            ),
+        }
+    }
+    Ok(columns)
+}
+
+/// Maps the positions of the markers in VALUES of the INSERT statement
+/// to the columns they are inserted into.
+fn insert_values(tokens: &[Token]) -> Result<HashMap<usize, String>> {
+    let mut markers = HashMap::new();
+    if !tokens.first().is_some_and(|t| t.is_word("insert")) {
+        return Ok(markers);
+    }
+
+    let columns_start = tokens
+        .iter()
+        .position(|t| t.is_symbol("("))
+        .context("Missing the column list")?;
+    let (column_items, consumed) = split_parenthesized(&tokens[columns_start..])?;
+    let columns = column_items
+        .into_iter()
+        .map(parse_names)
+        .collect::<Result<Vec<_>>>()?
+        .concat();
+
+    let values_start = columns_start + consumed;
+    anyhow::ensure!(
+        tokens
+            .get(values_start)
+            .is_some_and(|t| t.is_word("values")),
+        "Missing VALUES"
+    );
+    let (values, _) = split_parenthesized(&tokens[values_start + 1..])?;
+    anyhow::ensure!(
+        values.len() == columns.len(),
+        "The number of values doesn't match the number of columns"
+    );
+
+    // The position of the first token of the value in `tokens`.
+    let mut position = values_start + 2;
+    for (value, column) in values.iter().zip(columns) {
+        if let [marker] = value {
+            if marker.is_symbol("?") {
+                markers.insert(position, column);
+            }
+        }
+        // Skip the value and the comma after it.
+        position += value.len() + 1;
+    }
+    Ok(markers)
+}
+
+#[cfg(test)]
+mod tests {
+    use scylla::transport::topology::{ColumnKind, CqlType, NativeType};
+
+    use super::{bound_columns, parse_table_definition};
+
+    #[test]
+    fn parse_table_definition_test() {
+        let table = parse_table_definition(
+            "CREATE TABLE IF NOT EXISTS ks.t (pk bigint, ck int, \"Val\" text, s blob STATIC, \
+            PRIMARY KEY (pk, ck)) WITH compaction = { 'class': 'LeveledCompactionStrategy' }",
+        )
+        .unwrap();
+
+        assert_eq!(vec!["pk"], table.partition_key);
+        assert_eq!(vec!["ck"], table.clustering_key);
+        assert_eq!(4, table.columns.len());
+        assert_eq!(ColumnKind::PartitionKey, table.columns["pk"].kind);
+        assert_eq!(ColumnKind::Clustering, table.columns["ck"].kind);
+        assert_eq!(ColumnKind::Static, table.columns["s"].kind);
+        assert_eq!(
+            CqlType::Native(NativeType::Text),
+            table.columns["Val"].type_
+        );
+    }
+
+    #[test]
+    fn parse_table_definition_inline_primary_key_test() {
+        let table =
+            parse_table_definition("create table t (Key blob PRIMARY KEY, v varchar)").unwrap();
+
+        assert_eq!(vec!["key"], table.partition_key);
+        assert!(table.clustering_key.is_empty());
+        assert_eq!(CqlType::Native(NativeType::Text), table.columns["v"].type_);
+    }
+
+    #[test]
+    fn parse_table_definition_bad_test() {
+        assert!(parse_table_definition("CREATE TABLE t (pk int, v int)").is_err());
+        assert!(
+            parse_table_definition("CREATE TABLE t (pk int PRIMARY KEY, v list<int>)").is_err()
+        );
+        assert!(parse_table_definition("CREATE TABLE t (pk int, PRIMARY KEY (x))").is_err());
+        assert!(parse_table_definition("SELECT * FROM t").is_err());
+    }
+
+    #[test]
+    fn bound_columns_test() {
+        assert_eq!(
+            vec!["pk", "ck"],
+            bound_columns("SELECT * FROM t WHERE pk = ? AND ck >= ? LIMIT 10").unwrap()
+        );
+        assert_eq!(
+            vec!["v", "pk"],
+            bound_columns("update t set v = ? where \"pk\" in ?").unwrap()
+        );
+        assert_eq!(
+            vec!["pk", "v"],
+            bound_columns("INSERT INTO t (pk, c, v) VALUES (?, 'abc', ?) USING TTL 100").unwrap()
+        );
+        assert_eq!(
+            vec!["pk"],
+            bound_columns("SELECT * FROM t WHERE pk = :pk").unwrap()
+        );
+        assert!(bound_columns("SELECT * FROM t WHERE token(pk) > ?").is_err());
+        assert!(bound_columns("SELECT * FROM t WHERE pk = 1 LIMIT ?").is_err());
+    }
+}
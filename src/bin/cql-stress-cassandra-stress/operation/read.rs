@@ -1,24 +1,47 @@
 use std::{marker::PhantomData, ops::ControlFlow, sync::Arc};
 
 use anyhow::{Context, Result};
-use scylla::{frame::response::result::CqlValue, prepared_statement::PreparedStatement, Session};
+use cql_stress::{dry_run::RenderedStatement, reprepare::reprepare, row_count::ExpectedRowCount};
+use rand::{Rng, SeedableRng};
+use rand_pcg::Pcg64Mcg;
+use scylla::{
+    frame::response::result::CqlValue, history::HistoryListener,
+    prepared_statement::PreparedStatement, QueryResult, Session,
+};
+use uuid::Uuid;
 
-use crate::settings::CassandraStressSettings;
+use crate::settings::{
+    CassandraStressSettings, ClusteringOrder, ClusteringRestriction, CLUSTERING_COLUMN,
+};
 
 use super::{
-    row_generator::RowGenerator, CassandraStressOperation, CassandraStressOperationFactory,
-    EqualRowValidator, ExistsRowValidator, RowValidator,
+    checksum::verify_result_checksums,
+    execute_read, prepare_statements, recompute_seed, render_values,
+    row_generator::{ColumnCountSampler, RowGenerator},
+    CassandraStressOperation, CassandraStressOperationFactory, CassandraStressRenderer,
+    EqualRowValidator, ExistsRowValidator, LastTracingId, RowValidator,
 };
 
 pub struct ReadOperation<V: RowValidator> {
     session: Arc<Session>,
     statement: PreparedStatement,
     row_validator: V,
+    expected_rows: Option<ExpectedRowCount>,
+    column_count: ColumnCountSampler,
+    shape: ReadShape,
+    verify_checksums: bool,
+    paged: bool,
+    tracing_id: LastTracingId,
 }
 
 pub struct GenericReadOperationFactory<V: RowValidator> {
     session: Arc<Session>,
     statement: PreparedStatement,
+    expected_rows: Option<ExpectedRowCount>,
+    column_count: ColumnCountSampler,
+    shape: ReadShape,
+    verify_checksums: bool,
+    paged: bool,
     _phantom: PhantomData<V>,
 }
 
@@ -31,11 +54,21 @@ pub type CounterReadOperationFactory = GenericReadOperationFactory<ExistsRowVali
 impl<V: RowValidator> ReadOperation<V> {
     async fn do_execute(&self, row: &[CqlValue]) -> Result<ControlFlow<()>> {
         let pk = &row[0];
+        let selected_rows = self.shape.select_rows(pk);
+        let clustering_keys = selected_rows
+            .as_deref()
+            .map(|rows| self.shape.clustering_keys(rows))
+            .unwrap_or_default();
+        let values = std::iter::once(pk)
+            .chain(clustering_keys.iter())
+            .collect::<Vec<_>>();
 
-        // The tool works in a way, that it generates one row per partition.
-        // We make use of `execute_unpaged` here, since we filter the rows
-        // with `WHERE PK = ?`. It means, that the result will have AT MOST 1 row.
-        let result = self.session.execute_unpaged(&self.statement, (pk,)).await;
+        // The tool works in a way, that it generates one row per partition,
+        // or `-col clustering=` rows if it's greater than 1.
+        // The reads are unpaged by default, since we filter the rows
+        // with `WHERE PK = ?`. It means, that the result will have AT MOST
+        // as many rows as the partition.
+        let result = execute_read(&self.session, &self.statement, values, self.paged).await;
         if let Err(err) = result.as_ref() {
             tracing::error!(
                 error = %err,
@@ -44,7 +77,15 @@ impl<V: RowValidator> ReadOperation<V> {
             );
         }
 
-        let validation_result = self.row_validator.validate_row(row, result?);
+        let result = result?;
+        self.tracing_id.record(&result);
+        let validation_result = match selected_rows {
+            Some(selected_rows) => {
+                let expected_row = self.shape.expected_row(row, &selected_rows);
+                self.validate(&expected_row, result)
+            }
+            None => self.validate(row, result),
+        };
         if let Err(err) = validation_result.as_ref() {
             tracing::error!(
                 error = %err,
@@ -57,6 +98,21 @@ impl<V: RowValidator> ReadOperation<V> {
 
         Ok(ControlFlow::Continue(()))
     }
+
+    fn validate(&self, row: &[CqlValue], result: QueryResult) -> Result<()> {
+        if let Some(expected_rows) = &self.expected_rows {
+            let rows = result.rows_num()? as u64;
+            expected_rows.check(rows)?;
+            if rows == 0 {
+                // The row is allowed to be absent, there is nothing more to validate.
+                return Ok(());
+            }
+        }
+        if self.verify_checksums {
+            verify_result_checksums(&result)?;
+        }
+        self.row_validator.validate_row(row, result)
+    }
 }
 
 impl<V: RowValidator> CassandraStressOperation for ReadOperation<V> {
@@ -66,8 +122,33 @@ impl<V: RowValidator> CassandraStressOperation for ReadOperation<V> {
         self.do_execute(row).await
     }
 
+    async fn reprepare(&mut self) -> Result<()> {
+        self.statement = reprepare(&self.session, &self.statement).await?;
+        Ok(())
+    }
+
+    fn set_tracing(&mut self, tracing: bool) {
+        self.statement.set_tracing(tracing);
+    }
+
+    fn set_history_listener(&mut self, listener: Arc<dyn HistoryListener>) {
+        self.statement.set_history_listener(listener);
+    }
+
+    fn take_tracing_id(&self) -> Option<Uuid> {
+        self.tracing_id.take()
+    }
+
     fn generate_row(&self, row_generator: &mut RowGenerator) -> Vec<CqlValue> {
-        row_generator.generate_row()
+        let mut row = row_generator.generate_row();
+        // Only the written columns are expected to be read.
+        self.column_count.truncate_row(&mut row);
+        row
+    }
+
+    fn generate_row_into(&self, row_generator: &mut RowGenerator, row: &mut Vec<CqlValue>) {
+        row_generator.generate_row_into(row);
+        self.column_count.truncate_row(row);
     }
 }
 
@@ -79,6 +160,12 @@ impl<V: RowValidator> CassandraStressOperationFactory for GenericReadOperationFa
             session: Arc::clone(&self.session),
             statement: self.statement.clone(),
             row_validator: Default::default(),
+            expected_rows: self.expected_rows.clone(),
+            column_count: self.column_count.clone(),
+            shape: self.shape.clone(),
+            verify_checksums: self.verify_checksums,
+            paged: self.paged,
+            tracing_id: LastTracingId::default(),
         }
     }
 }
@@ -87,24 +174,292 @@ impl<V: RowValidator> GenericReadOperationFactory<V> {
     pub async fn new(
         settings: Arc<CassandraStressSettings>,
         session: Arc<Session>,
-        stressed_table_name: &'static str,
+        stressed_table_name: &str,
+        operation_name: &str,
     ) -> Result<Self> {
-        let statement_str = format!("SELECT * FROM {} WHERE KEY=?", stressed_table_name);
-        let mut statement = session
-            .prepare(statement_str)
-            .await
-            .context("Failed to prepare statement")?;
+        let shape = ReadShape::new(&settings);
+        let statement_str = shape.build_query(stressed_table_name);
+        let mut statement =
+            prepare_statements(&session, vec![(operation_name.to_owned(), statement_str)])
+                .await?
+                .remove(0);
 
         statement.set_is_idempotent(true);
-        statement.set_consistency(settings.command_params.common.consistency_level);
+        statement.set_consistency(settings.command_params.read_consistency_level());
         statement.set_serial_consistency(Some(
             settings.command_params.common.serial_consistency_level,
         ));
+        if let Some(fetch_size) = settings.mode.fetch_size {
+            statement.set_page_size(fetch_size);
+        }
+
+        let expected_rows = settings
+            .command_params
+            .read
+            .as_ref()
+            .and_then(|read| read.expected_rows.clone());
 
         Ok(Self {
             session,
             statement,
+            expected_rows,
+            column_count: ColumnCountSampler::new(&settings),
+            shape,
+            verify_checksums: settings.column.checksum,
+            paged: settings.mode.fetch_size.is_some(),
             _phantom: PhantomData,
         })
     }
 }
+
+/// The shape of the read query: which clustering rows of the partition
+/// are read, in which order, and whether the cache is bypassed.
+///
+/// The read clustering rows are chosen deterministically based on the partition key,
+/// so a retried read queries the same rows.
+#[derive(Clone, Default)]
+struct ReadShape {
+    restriction: Option<ClusteringRestriction>,
+    order: Option<ClusteringOrder>,
+    bypass_cache: bool,
+    clustering: usize,
+    row_len: usize,
+}
+
+impl ReadShape {
+    fn new(settings: &CassandraStressSettings) -> Self {
+        let row_len = ColumnCountSampler::new(settings).row_len();
+        let clustering = settings.column.clustering;
+        match &settings.command_params.read {
+            Some(read) => Self {
+                restriction: read.restriction,
+                order: read.order,
+                bypass_cache: read.bypass_cache,
+                clustering,
+                row_len,
+            },
+            // The reads of the mixed workload read the whole partition.
+            None => Self {
+                clustering,
+                row_len,
+                ..Default::default()
+            },
+        }
+    }
+
+    fn build_query(&self, stressed_table_name: &str) -> String {
+        let mut query = format!("SELECT * FROM {} WHERE KEY=?", stressed_table_name);
+        match self.restriction {
+            Some(ClusteringRestriction::Slice(_)) => {
+                query.push_str(&format!(" AND {0} >= ? AND {0} < ?", CLUSTERING_COLUMN))
+            }
+            Some(ClusteringRestriction::In(rows)) => query.push_str(&format!(
+                " AND {} IN ({})",
+                CLUSTERING_COLUMN,
+                vec!["?"; rows.get()].join(", ")
+            )),
+            None => (),
+        }
+        if let Some(order) = self.order {
+            query.push_str(&format!(" ORDER BY {} {}", CLUSTERING_COLUMN, order));
+        }
+        if self.bypass_cache {
+            query.push_str(" BYPASS CACHE");
+        }
+        query
+    }
+
+    /// Returns the clustering keys of the read rows in the order in which
+    /// they are expected to be returned, or None if the whole partition
+    /// is read in the natural order.
+    fn select_rows(&self, pk: &CqlValue) -> Option<Vec<usize>> {
+        let mut rows: Vec<usize> = match self.restriction {
+            Some(restriction) => {
+                let mut rng = Pcg64Mcg::seed_from_u64(recompute_seed(0, pk) as u64);
+                match restriction {
+                    ClusteringRestriction::Slice(rows) => {
+                        let start = rng.gen_range(0..=self.clustering - rows.get());
+                        (start..start + rows.get()).collect()
+                    }
+                    ClusteringRestriction::In(rows) => {
+                        let mut keys =
+                            rand::seq::index::sample(&mut rng, self.clustering, rows.get())
+                                .into_vec();
+                        keys.sort_unstable();
+                        keys
+                    }
+                }
+            }
+            None if self.order == Some(ClusteringOrder::Desc) => (0..self.clustering).collect(),
+            None => return None,
+        };
+        if self.order == Some(ClusteringOrder::Desc) {
+            rows.reverse();
+        }
+        Some(rows)
+    }
+
+    /// Returns the clustering key values bound to the query restricting the selected rows.
+    fn clustering_keys(&self, selected_rows: &[usize]) -> Vec<CqlValue> {
+        let value = |ck: usize| CqlValue::Int(ck as i32);
+        match self.restriction {
+            Some(ClusteringRestriction::Slice(_)) => {
+                let first = selected_rows.iter().min().unwrap();
+                let last = selected_rows.iter().max().unwrap();
+                vec![value(*first), value(*last + 1)]
+            }
+            Some(ClusteringRestriction::In(_)) => {
+                selected_rows.iter().copied().map(value).collect()
+            }
+            None => Vec::new(),
+        }
+    }
+
+    /// Returns the concatenated selected rows of the generated partition.
+    fn expected_row(&self, row: &[CqlValue], selected_rows: &[usize]) -> Vec<CqlValue> {
+        selected_rows
+            .iter()
+            .flat_map(|ck| &row[ck * self.row_len..(ck + 1) * self.row_len])
+            .cloned()
+            .collect()
+    }
+}
+
+/// Renders the reads of [ReadOperation], which bind only the partition key
+/// of the generated row (and the clustering keys of the read rows, if restricted).
+/// The rest of the row is used for the validation.
+pub struct ReadRenderer {
+    statement: String,
+    shape: ReadShape,
+}
+
+impl ReadRenderer {
+    pub fn new(settings: &CassandraStressSettings, stressed_table_name: &str) -> Self {
+        let shape = ReadShape::new(settings);
+        Self {
+            statement: shape.build_query(stressed_table_name),
+            shape,
+        }
+    }
+}
+
+impl CassandraStressRenderer for ReadRenderer {
+    fn render(&mut self, row_generator: &mut RowGenerator) -> Vec<RenderedStatement> {
+        let row = row_generator.generate_row();
+        let clustering_keys = self
+            .shape
+            .select_rows(&row[0])
+            .map(|rows| self.shape.clustering_keys(&rows))
+            .unwrap_or_default();
+        vec![RenderedStatement::new(
+            self.statement.clone(),
+            render_values(row[..1].iter().chain(clustering_keys.iter())),
+        )]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::num::NonZeroUsize;
+
+    use scylla::frame::response::result::CqlValue;
+
+    use super::ReadShape;
+    use crate::settings::{ClusteringOrder, ClusteringRestriction};
+
+    fn shape(
+        restriction: Option<ClusteringRestriction>,
+        order: Option<ClusteringOrder>,
+        bypass_cache: bool,
+    ) -> ReadShape {
+        ReadShape {
+            restriction,
+            order,
+            bypass_cache,
+            clustering: 10,
+            row_len: 2,
+        }
+    }
+
+    fn rows(rows: usize) -> NonZeroUsize {
+        NonZeroUsize::new(rows).unwrap()
+    }
+
+    #[test]
+    fn read_shape_query_test() {
+        assert_eq!(
+            "SELECT * FROM standard1 WHERE KEY=?",
+            shape(None, None, false).build_query("standard1")
+        );
+        assert_eq!(
+            "SELECT * FROM standard1 WHERE KEY=? AND ck >= ? AND ck < ? ORDER BY ck DESC BYPASS CACHE",
+            shape(
+                Some(ClusteringRestriction::Slice(rows(3))),
+                Some(ClusteringOrder::Desc),
+                true
+            )
+            .build_query("standard1")
+        );
+        assert_eq!(
+            "SELECT * FROM standard1 WHERE KEY=? AND ck IN (?, ?, ?) ORDER BY ck ASC",
+            shape(
+                Some(ClusteringRestriction::In(rows(3))),
+                Some(ClusteringOrder::Asc),
+                false
+            )
+            .build_query("standard1")
+        );
+    }
+
+    #[test]
+    fn read_shape_select_rows_test() {
+        let pk = CqlValue::Blob(b"0123456789".to_vec());
+
+        assert_eq!(None, shape(None, None, true).select_rows(&pk));
+        assert_eq!(
+            Some((0..10).rev().collect()),
+            shape(None, Some(ClusteringOrder::Desc), false).select_rows(&pk)
+        );
+
+        let slice = shape(Some(ClusteringRestriction::Slice(rows(4))), None, false);
+        let selected = slice.select_rows(&pk).unwrap();
+        assert_eq!(Some(&selected), slice.select_rows(&pk).as_ref());
+        assert!(selected.windows(2).all(|w| w[0] + 1 == w[1]));
+        assert_eq!(
+            vec![
+                CqlValue::Int(selected[0] as i32),
+                CqlValue::Int(selected[3] as i32 + 1)
+            ],
+            slice.clustering_keys(&selected)
+        );
+
+        let desc_in = shape(
+            Some(ClusteringRestriction::In(rows(4))),
+            Some(ClusteringOrder::Desc),
+            false,
+        );
+        let selected = desc_in.select_rows(&pk).unwrap();
+        assert_eq!(4, selected.len());
+        assert!(selected.windows(2).all(|w| w[0] > w[1]));
+        assert!(selected.iter().all(|ck| *ck < 10));
+        assert_eq!(4, desc_in.clustering_keys(&selected).len());
+    }
+
+    #[test]
+    fn read_shape_expected_row_test() {
+        let row = (0..6).map(CqlValue::Int).collect::<Vec<_>>();
+        let partition_shape = ReadShape {
+            clustering: 3,
+            ..shape(None, None, false)
+        };
+        assert_eq!(
+            vec![
+                CqlValue::Int(4),
+                CqlValue::Int(5),
+                CqlValue::Int(0),
+                CqlValue::Int(1)
+            ],
+            partition_shape.expected_row(&row, &[2, 0])
+        );
+    }
+}
@@ -0,0 +1,333 @@
+use std::{ops::ControlFlow, sync::Arc};
+
+use anyhow::Result;
+use cql_stress::{dry_run::RenderedStatement, reprepare::reprepare};
+use scylla::{
+    frame::response::result::CqlValue, history::HistoryListener,
+    prepared_statement::PreparedStatement, QueryResult, Session,
+};
+
+use crate::settings::CassandraStressSettings;
+
+use super::{
+    prepare_statements, render_values, row_generator::RowGenerator, CassandraStressOperation,
+    CassandraStressOperationFactory, CassandraStressRenderer,
+};
+
+/// Reads a row, modifies its values and writes it back.
+///
+/// If the row doesn't exist, the generated row is inserted instead.
+/// With `lwt`, the modified row is written back only if it wasn't modified
+/// since it was read. Otherwise, the operation fails and is retried.
+pub struct ReadModifyWriteOperation {
+    session: Arc<Session>,
+    select_statement: PreparedStatement,
+    insert_statement: PreparedStatement,
+    update_statement: Option<PreparedStatement>,
+}
+
+pub struct ReadModifyWriteOperationFactory {
+    session: Arc<Session>,
+    select_statement: PreparedStatement,
+    insert_statement: PreparedStatement,
+    update_statement: Option<PreparedStatement>,
+}
+
+impl ReadModifyWriteOperation {
+    async fn do_execute(&self, row: &[CqlValue]) -> Result<()> {
+        let (pk, generated_values) = row.split_first().unwrap();
+
+        let current_values = self
+            .session
+            .execute_unpaged(&self.select_statement, (pk,))
+            .await?
+            .maybe_first_row()?
+            .map(|current| current.columns);
+
+        let current_values = match current_values {
+            Some(current_values) => current_values,
+            None => {
+                // The row doesn't exist yet - insert the generated one.
+                let result = self
+                    .session
+                    .execute_unpaged(&self.insert_statement, row)
+                    .await?;
+                if self.update_statement.is_some() {
+                    ensure_applied(result)?;
+                }
+                return Ok(());
+            }
+        };
+
+        let modified_values = current_values
+            .iter()
+            .zip(generated_values)
+            .map(|(current, generated)| match current {
+                Some(current) => Some(modify_value(current)),
+                None => Some(generated.clone()),
+            })
+            .collect::<Vec<_>>();
+
+        match &self.update_statement {
+            Some(update_statement) => {
+                let mut values = modified_values;
+                values.push(Some(pk.clone()));
+                // The null values are compared with `IF column = null`.
+                values.extend(current_values);
+                let result = self
+                    .session
+                    .execute_unpaged(update_statement, values)
+                    .await?;
+                ensure_applied(result)?;
+            }
+            None => {
+                let mut values = Vec::with_capacity(row.len());
+                values.push(Some(pk.clone()));
+                values.extend(modified_values);
+                self.session
+                    .execute_unpaged(&self.insert_statement, values)
+                    .await?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Modifies the value read from the database.
+/// The blobs have each of their bytes incremented, the other values are left intact.
+fn modify_value(value: &CqlValue) -> CqlValue {
+    match value {
+        CqlValue::Blob(bytes) => {
+            CqlValue::Blob(bytes.iter().map(|byte| byte.wrapping_add(1)).collect())
+        }
+        value => value.clone(),
+    }
+}
+
+/// Fails if the conditional statement was not applied,
+/// i.e. the row was modified concurrently since it was read.
+fn ensure_applied(result: QueryResult) -> Result<()> {
+    // The first column of the result of a conditional statement is `[applied]`.
+    let applied = matches!(
+        result.first_row()?.columns.first(),
+        Some(Some(CqlValue::Boolean(true)))
+    );
+    anyhow::ensure!(
+        applied,
+        "Conditional write was not applied, the row was modified concurrently."
+    );
+    Ok(())
+}
+
+impl CassandraStressOperation for ReadModifyWriteOperation {
+    type Factory = ReadModifyWriteOperationFactory;
+
+    async fn execute(&self, row: &[CqlValue]) -> Result<ControlFlow<()>> {
+        let result = self.do_execute(row).await;
+        if let Err(err) = result.as_ref() {
+            tracing::error!(
+                error = %err,
+                partition_key = ?row[0],
+                "read-modify-write error",
+            );
+        }
+
+        result?;
+
+        Ok(ControlFlow::Continue(()))
+    }
+
+    async fn reprepare(&mut self) -> Result<()> {
+        self.select_statement = reprepare(&self.session, &self.select_statement).await?;
+        self.insert_statement = reprepare(&self.session, &self.insert_statement).await?;
+        if let Some(update_statement) = &mut self.update_statement {
+            *update_statement = reprepare(&self.session, update_statement).await?;
+        }
+        Ok(())
+    }
+
+    fn set_history_listener(&mut self, listener: Arc<dyn HistoryListener>) {
+        self.select_statement.set_history_listener(listener.clone());
+        self.insert_statement.set_history_listener(listener.clone());
+        if let Some(update_statement) = &mut self.update_statement {
+            update_statement.set_history_listener(listener);
+        }
+    }
+
+    fn generate_row(&self, row_generator: &mut RowGenerator) -> Vec<CqlValue> {
+        row_generator.generate_row()
+    }
+
+    fn generate_row_into(&self, row_generator: &mut RowGenerator, row: &mut Vec<CqlValue>) {
+        row_generator.generate_row_into(row);
+    }
+}
+
+impl CassandraStressOperationFactory for ReadModifyWriteOperationFactory {
+    type Operation = ReadModifyWriteOperation;
+
+    fn create(&self) -> Self::Operation {
+        ReadModifyWriteOperation {
+            session: Arc::clone(&self.session),
+            select_statement: self.select_statement.clone(),
+            insert_statement: self.insert_statement.clone(),
+            update_statement: self.update_statement.clone(),
+        }
+    }
+}
+
+impl ReadModifyWriteOperationFactory {
+    pub async fn new(
+        settings: Arc<CassandraStressSettings>,
+        session: Arc<Session>,
+    ) -> Result<Self> {
+        let queries = Queries::new(&settings);
+
+        let mut statements = vec![
+            (String::from("read_modify_write select"), queries.select),
+            (String::from("read_modify_write insert"), queries.insert),
+        ];
+        statements.extend(
+            queries
+                .update
+                .map(|update| (String::from("read_modify_write update"), update)),
+        );
+        let mut prepared = prepare_statements(&session, statements)
+            .await?
+            .into_iter()
+            .map(|statement| with_consistency(statement, &settings));
+
+        // The statements are returned in the order they were given.
+        let mut select_statement = prepared.next().unwrap();
+        select_statement.set_is_idempotent(true);
+        let insert_statement = prepared.next().unwrap();
+        let update_statement = prepared.next();
+
+        Ok(Self {
+            session,
+            select_statement,
+            insert_statement,
+            update_statement,
+        })
+    }
+}
+
+/// The statements of the read-modify-write operation.
+struct Queries {
+    select: String,
+    insert: String,
+    // Used only with lightweight transactions.
+    update: Option<String>,
+}
+
+impl Queries {
+    fn new(settings: &CassandraStressSettings) -> Self {
+        let columns = &settings.column.columns;
+        let lwt = settings
+            .command_params
+            .read_modify_write
+            .as_ref()
+            .is_some_and(|read_modify_write| read_modify_write.lwt);
+        let ttl_clause = settings.schema.construct_ttl_clause().unwrap_or_default();
+        let table = settings.schema.table_identifier();
+        let quoted_columns = columns
+            .iter()
+            .map(|column| format!("\"{}\"", column))
+            .collect::<Vec<_>>();
+
+        let select_statement_str = format!(
+            "SELECT {} FROM {} WHERE KEY=?",
+            quoted_columns.join(", "),
+            table,
+        );
+
+        let mut insert_statement_str = format!(
+            "INSERT INTO {} (key, {}) VALUES (?{})",
+            table,
+            quoted_columns.join(", "),
+            ", ?".repeat(columns.len()),
+        );
+        if lwt {
+            insert_statement_str += " IF NOT EXISTS";
+        }
+        insert_statement_str += &ttl_clause;
+
+        // Conditional write - the serial consistency level
+        // is used for the Paxos phase of the LWT.
+        let update_statement_str = lwt.then(|| {
+            let assignments = quoted_columns
+                .iter()
+                .map(|column| format!("{} = ?", column))
+                .collect::<Vec<_>>();
+            format!(
+                "UPDATE {}{} SET {} WHERE KEY=? IF {}",
+                table,
+                ttl_clause,
+                assignments.join(", "),
+                assignments.join(" AND "),
+            )
+        });
+
+        Self {
+            select: select_statement_str,
+            insert: insert_statement_str,
+            update: update_statement_str,
+        }
+    }
+}
+
+fn with_consistency(
+    mut statement: PreparedStatement,
+    settings: &CassandraStressSettings,
+) -> PreparedStatement {
+    statement.set_consistency(settings.command_params.common.consistency_level);
+    statement.set_serial_consistency(Some(
+        settings.command_params.common.serial_consistency_level,
+    ));
+    statement
+}
+
+/// Renders the read-modify-write operations. The result of the read
+/// is not known without the database, so the row is rendered as if
+/// it didn't exist yet, i.e. the generated row is inserted.
+pub struct ReadModifyWriteRenderer {
+    select: String,
+    insert: String,
+}
+
+impl ReadModifyWriteRenderer {
+    pub fn new(settings: &CassandraStressSettings) -> Self {
+        let queries = Queries::new(settings);
+        Self {
+            select: queries.select,
+            insert: queries.insert,
+        }
+    }
+}
+
+impl CassandraStressRenderer for ReadModifyWriteRenderer {
+    fn render(&mut self, row_generator: &mut RowGenerator) -> Vec<RenderedStatement> {
+        let row = row_generator.generate_row();
+        vec![
+            RenderedStatement::new(self.select.clone(), render_values(&row[..1])),
+            RenderedStatement::new(self.insert.clone(), render_values(&row)),
+        ]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use scylla::frame::response::result::CqlValue;
+
+    use super::modify_value;
+
+    #[test]
+    fn modify_value_test() {
+        assert_eq!(
+            CqlValue::Blob(vec![1, 0, 0xff]),
+            modify_value(&CqlValue::Blob(vec![0, 0xff, 0xfe]))
+        );
+        assert_eq!(CqlValue::Int(5), modify_value(&CqlValue::Int(5)));
+    }
+}
@@ -1,17 +1,23 @@
-use scylla::_macro_internal::CqlValue;
+use anyhow::Result;
+use scylla::{_macro_internal::CqlValue, frame::value::MaybeUnset};
 
 use crate::{
     java_generate::{
         distribution::{fixed::FixedDistribution, Distribution},
-        values::{Blob, Generator, GeneratorConfig, HexBlob},
+        values::{Blob, Generator, GeneratorConfig, HexBlob, ValueGenerator},
+    },
+    settings::{CassandraStressSettings, SequenceEnd},
+};
+use std::{
+    collections::HashMap,
+    ops::Range,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
     },
-    settings::CassandraStressSettings,
 };
-#[cfg(feature = "user-profile")]
-use std::collections::HashMap;
-use std::sync::Arc;
 
-use super::recompute_seed;
+use super::{checksum::ChecksummedBlob, recompute_seed};
 
 /// A row generator structure.
 ///
@@ -22,6 +28,14 @@ use super::recompute_seed;
 /// - compute the seed for the `column_generators` based on generated pk
 /// - generate the rest of the row (seeding the `column_generators` with computed seed)
 ///
+/// With `-col clustering=` greater than 1, the partition consists of multiple rows,
+/// which are generated together and concatenated. Each of them starts with the partition key
+/// and the clustering key (0, 1, ...), and the seed of its columns is computed
+/// from both of them. The first row of the partition has then the same columns
+/// as the single row of the partition without the clustering column.
+/// With `-insert visits=`, the rows of a partition are split between multiple
+/// generated rows instead (see [PartitionVisits]).
+///
 /// I think it's a great place to address how read and write workloads cooperate.
 /// For reference, see: https://github.com/scylladb/cql-stress/pull/43#discussion_r1304274035.
 ///
@@ -81,6 +95,15 @@ pub struct RowGenerator {
     pk_seed_distribution: Arc<dyn Distribution>,
     pk_generator: Generator,
     column_generators: Vec<Generator>,
+    // Number of the rows of each partition. If greater than 1,
+    // each row contains the clustering key after the partition key.
+    clustering: usize,
+    sequence_limit: Option<Arc<SequenceLimit>>,
+    // Whether a seed was sampled past the end of the sequence.
+    exhausted: bool,
+    // Mixed into the sampled seeds, see `with_population_seed`.
+    population_salt: i64,
+    visits: Option<PartitionVisits>,
     // Map column name to the index of generated value in resulting vector.
     #[cfg(feature = "user-profile")]
     index_map: HashMap<String, usize>,
@@ -105,11 +128,62 @@ impl RowGenerator {
             pk_seed_distribution,
             pk_generator,
             column_generators,
+            clustering: 1,
+            sequence_limit: None,
+            exhausted: false,
+            population_salt: 0,
+            visits: None,
             #[cfg(feature = "user-profile")]
             index_map,
         }
     }
 
+    /// Makes the generator generate `clustering` rows per partition.
+    pub fn with_clustering(mut self, clustering: usize) -> Self {
+        self.clustering = clustering;
+        self
+    }
+
+    /// Makes the generator count the sampled seeds against the limit
+    /// of `-pop seq=` (see [SequenceLimit]).
+    pub fn with_sequence_limit(mut self, sequence_limit: Option<Arc<SequenceLimit>>) -> Self {
+        self.sequence_limit = sequence_limit;
+        self
+    }
+
+    /// Makes the generator derive the partition keys from the seed of `-pop seed=`
+    /// in addition to the sampled seeds. The runs with the same population seed
+    /// generate the same keys, and thus the same values, while the runs
+    /// with different ones generate different keys from the same sequence.
+    /// The population seed 0 generates the keys of cassandra-stress.
+    pub fn with_population_seed(mut self, seed: u64) -> Self {
+        self.population_salt = population_salt(seed);
+        self
+    }
+
+    /// Makes the generator split the rows of each partition between
+    /// the number of visits sampled from `visits` (see [PartitionVisits]).
+    pub fn with_visits(
+        mut self,
+        visits: Box<dyn Distribution>,
+        revisit: Box<dyn Distribution>,
+    ) -> Self {
+        self.visits = Some(PartitionVisits {
+            visits,
+            revisit,
+            partial: HashMap::new(),
+        });
+        self
+    }
+
+    fn key_len(&self) -> usize {
+        if self.clustering > 1 {
+            2
+        } else {
+            1
+        }
+    }
+
     /// Returns the index of corresponding column's value in generated rows.
     #[cfg(feature = "user-profile")]
     pub fn row_index_of_column_with_name(&self, name: &str) -> Option<usize> {
@@ -117,49 +191,231 @@ impl RowGenerator {
     }
 }
 
+/// Splits the rows of the partitions between multiple writes (see `-insert visits=`).
+///
+/// Each new partition is split into the number of visits sampled from `visits`,
+/// each of which writes the next consecutive part of its clustering rows.
+/// The partially written partitions are kept in the slots sampled from `revisit`,
+/// and visited again once their slot is sampled again. An empty slot
+/// is filled with a new partition. Thus, the range of `revisit` is the number
+/// of the partitions written at the same time by each of the generators.
+struct PartitionVisits {
+    visits: Box<dyn Distribution>,
+    revisit: Box<dyn Distribution>,
+    partial: HashMap<i64, PartialPartition>,
+}
+
+struct PartialPartition {
+    pk_seed: i64,
+    // The index of the next visit.
+    visit: usize,
+    visits: usize,
+}
+
 pub struct RowGeneratorFactory {
     pk_seed_distribution: Arc<dyn Distribution>,
+    sequence_limit: Option<Arc<SequenceLimit>>,
+    // Whether the generators visit the partitions of `-insert visits=`.
+    visits: bool,
     settings: Arc<CassandraStressSettings>,
 }
 
+/// Counts the seeds sampled by all of the generators from `-pop seq=`,
+/// which tells when the sequence is exhausted and would start over.
+/// Not used with `exhausted=wrap`, since the sequence wraps then anyway.
+pub struct SequenceLimit {
+    sampled: AtomicU64,
+    len: u64,
+    end: SequenceEnd,
+}
+
+impl SequenceLimit {
+    pub fn new(settings: &CassandraStressSettings) -> Option<Arc<Self>> {
+        let population = &settings.population;
+        match (population.sequence_len, population.sequence_end) {
+            (_, SequenceEnd::Wrap) | (None, _) => None,
+            (Some(len), end) => Some(Arc::new(Self {
+                sampled: AtomicU64::new(0),
+                len,
+                end,
+            })),
+        }
+    }
+
+    // Returns whether the sampled seed is still within the sequence.
+    fn take(&self) -> bool {
+        self.sampled.fetch_add(1, Ordering::Relaxed) < self.len
+    }
+}
+
 impl RowGenerator {
+    /// Samples the partition_key seed from the shared distribution.
+    fn sample_pk_seed(&mut self) -> i64 {
+        if let Some(sequence_limit) = &self.sequence_limit {
+            self.exhausted |= !sequence_limit.take();
+        }
+        self.pk_seed_distribution.next_i64()
+    }
+
+    /// Returns whether the operation shouldn't be executed, since the last row
+    /// was generated past the end of `-pop seq=` with `exhausted=stop`.
+    /// With `exhausted=error`, an error is returned instead.
+    pub fn sequence_exhausted(&self) -> Result<bool> {
+        if !self.exhausted {
+            return Ok(false);
+        }
+        match self.sequence_limit.as_ref().map(|limit| limit.end) {
+            Some(SequenceEnd::Stop) => Ok(true),
+            Some(SequenceEnd::Error) => {
+                anyhow::bail!("All of the seeds of -pop seq= were used (exhausted=error)")
+            }
+            Some(SequenceEnd::Wrap) | None => Ok(false),
+        }
+    }
+
     pub fn generate_pk(&mut self) -> CqlValue {
-        // Sample the partition_key seed from the shared distribution.
-        let pk_seed = self.pk_seed_distribution.next_i64();
-        self.pk_generator.set_seed(pk_seed);
+        self.seed_pk_generator();
         self.pk_generator.generate()
     }
 
-    pub fn generate_row(&mut self) -> Vec<CqlValue> {
-        // +1 for partition_key.
-        let row_length = self.column_generators.len() + 1;
-        let mut result = Vec::with_capacity(row_length);
+    fn seed_pk_generator(&mut self) {
+        let pk_seed = self.sample_pk_seed();
+        self.set_pk_seed(pk_seed);
+    }
 
-        let key = self.generate_pk();
+    fn set_pk_seed(&mut self, pk_seed: i64) {
+        self.pk_generator.set_seed(pk_seed ^ self.population_salt);
+    }
 
-        // Compute the seed used for generating the rest of the row.
-        let columns_seed = recompute_seed(0, &key);
-        result.push(key);
+    /// Returns the seed of the partition key and the clustering rows
+    /// of the partition written by the next visit.
+    fn next_visit(&mut self) -> (i64, Range<usize>) {
+        let Some(visits) = self.visits.as_mut() else {
+            return (self.sample_pk_seed(), 0..self.clustering);
+        };
+        let slot = visits.revisit.next_i64();
+        let mut partition = match visits.partial.remove(&slot) {
+            Some(partition) => partition,
+            None => {
+                let count = visits.visits.next_i64().clamp(1, self.clustering as i64) as usize;
+                PartialPartition {
+                    pk_seed: self.sample_pk_seed(),
+                    visit: 0,
+                    visits: count,
+                }
+            }
+        };
 
-        for column_generator in self.column_generators.iter_mut() {
-            column_generator.set_seed(columns_seed);
-            result.push(column_generator.generate());
+        let rows = partition.visit * self.clustering / partition.visits
+            ..(partition.visit + 1) * self.clustering / partition.visits;
+        let pk_seed = partition.pk_seed;
+        partition.visit += 1;
+        if partition.visit < partition.visits {
+            // This unwrap is safe, the visits were checked above.
+            self.visits
+                .as_mut()
+                .unwrap()
+                .partial
+                .insert(slot, partition);
         }
+        (pk_seed, rows)
+    }
 
+    pub fn generate_row(&mut self) -> Vec<CqlValue> {
+        let mut result = Vec::new();
+        self.generate_row_into(&mut result);
         result
     }
+
+    /// Generates the row into `row`, reusing the buffers of the values
+    /// of the previous row, so that the operations don't allocate them
+    /// for each of the rows.
+    ///
+    /// With multiple rows per partition, `row` is the concatenation of the rows
+    /// (of the visited part of the partition with `-insert visits=`).
+    pub fn generate_row_into(&mut self, row: &mut Vec<CqlValue>) {
+        self.generate_visit_at(row, 0);
+    }
+
+    /// Generates `partitions` rows into `row`, one after another, the same way
+    /// as [Self::generate_row_into]. The visits of the partitions consist
+    /// of different numbers of rows with `-insert visits=`, so the offsets
+    /// at which each of the partitions starts in `row` are stored in `starts`.
+    pub fn generate_partitions_into(
+        &mut self,
+        partitions: usize,
+        row: &mut Vec<CqlValue>,
+        starts: &mut Vec<usize>,
+    ) {
+        starts.clear();
+        let mut len = 0;
+        for _ in 0..partitions {
+            starts.push(len);
+            len = self.generate_visit_at(row, len);
+        }
+    }
+
+    // Generates the rows of the next visit into `row`, starting at `offset`.
+    // Returns the length of `row`, which ends with the generated rows.
+    fn generate_visit_at(&mut self, row: &mut Vec<CqlValue>, offset: usize) -> usize {
+        let key_len = self.key_len();
+        let row_length = key_len + self.column_generators.len();
+        let (pk_seed, rows) = self.next_visit();
+        let len = offset + row_length * rows.len();
+        // The placeholders are replaced by the generated values.
+        row.resize(len, CqlValue::Empty);
+        let row = &mut row[offset..];
+
+        self.set_pk_seed(pk_seed);
+        self.pk_generator.generate_into(&mut row[0]);
+
+        for (i, ck) in rows.enumerate() {
+            let (previous_rows, current_row) = row.split_at_mut(i * row_length);
+            let current_row = &mut current_row[..row_length];
+            if i > 0 {
+                current_row[0].clone_from(&previous_rows[0]);
+            }
+            if key_len > 1 {
+                current_row[1] = CqlValue::Int(ck as i32);
+            }
+
+            // Compute the seed used for generating the rest of the row.
+            let columns_seed = recompute_seed(ck as i64, &current_row[0]);
+
+            for (column_generator, value) in self
+                .column_generators
+                .iter_mut()
+                .zip(&mut current_row[key_len..])
+            {
+                column_generator.set_seed(columns_seed);
+                column_generator.generate_into(value);
+            }
+        }
+        len
+    }
 }
 
 impl RowGeneratorFactory {
     pub fn new(settings: Arc<CassandraStressSettings>) -> Self {
         let pk_seed_distribution = settings.population.pk_seed_distribution.create().into();
+        let sequence_limit = SequenceLimit::new(&settings);
 
         Self {
             pk_seed_distribution,
+            sequence_limit,
+            visits: false,
             settings,
         }
     }
 
+    /// Makes the created generators split the rows of the partitions between
+    /// the visits of `-insert visits=`, if given. Only the writes visit
+    /// the partitions, the other operations touch whole partitions.
+    pub fn with_visits(mut self) -> Self {
+        self.visits = true;
+        self
+    }
+
     pub fn create(&self) -> RowGenerator {
         // See https://github.com/scylladb/scylla-tools-java/blob/master/tools/stress/src/org/apache/cassandra/stress/settings/SettingsCommandPreDefined.java#L77.
         let pk_generator = Generator::new(
@@ -180,8 +436,14 @@ impl RowGeneratorFactory {
             .columns
             .iter()
             .map(|column| {
+                let blob = Blob::with_compressible(self.settings.column.compressible);
+                let value_generator: Box<dyn ValueGenerator> = if self.settings.column.checksum {
+                    Box::new(ChecksummedBlob::new(blob))
+                } else {
+                    Box::new(blob)
+                };
                 Generator::new(
-                    Box::<Blob>::default(),
+                    value_generator,
                     GeneratorConfig::new(
                         &format!("randomstr{}", column),
                         None,
@@ -192,10 +454,302 @@ impl RowGeneratorFactory {
             })
             .collect();
 
-        RowGenerator::new(
+        let generator = RowGenerator::new(
             Arc::clone(&self.pk_seed_distribution),
             pk_generator,
             column_generators,
         )
+        .with_clustering(self.settings.column.clustering)
+        .with_sequence_limit(self.sequence_limit.clone())
+        .with_population_seed(self.settings.population.seed);
+        match &self.settings.insert.visits {
+            Some(visits) if self.visits => {
+                generator.with_visits(visits.create(), self.settings.insert.revisit.create())
+            }
+            _ => generator,
+        }
+    }
+}
+
+// Spreads the population seed over all of the bits of the salt (the multiplication
+// by an odd constant is a bijection), so that the salted seeds of a sequence
+// don't overlap with the seeds of the same sequence under another population seed.
+// The seed 0 is mapped to the salt 0, which leaves the sampled seeds intact.
+fn population_salt(seed: u64) -> i64 {
+    seed.wrapping_mul(0x9E37_79B9_7F4A_7C15) as i64
+}
+
+/// Samples the number of columns written to a row, which varies
+/// if `-col n=` is given a distribution instead of a fixed number.
+///
+/// The distribution is seeded with the seed computed from the partition key,
+/// so the read workload expects exactly the columns written by the write workload.
+/// The columns which are not written are the last ones of the row.
+#[derive(Clone)]
+pub struct ColumnCountSampler {
+    count_distribution: Option<Arc<dyn Distribution>>,
+    max_count: usize,
+    key_len: usize,
+}
+
+impl ColumnCountSampler {
+    pub fn new(settings: &CassandraStressSettings) -> Self {
+        Self {
+            count_distribution: settings
+                .column
+                .count_distribution
+                .as_ref()
+                .map(|factory| factory.create().into()),
+            max_count: settings.column.columns.len(),
+            key_len: settings.column.key_len(),
+        }
+    }
+
+    /// Returns the number of the values of each generated row.
+    pub fn row_len(&self) -> usize {
+        self.key_len + self.max_count
+    }
+
+    /// Returns the number of the leading values of the row (including
+    /// the primary key) which are written to the database.
+    pub fn written_row_len(&self, row: &[CqlValue]) -> usize {
+        let count = match &self.count_distribution {
+            Some(distribution) => {
+                distribution.set_seed(recompute_seed(0, &row[0]));
+                distribution.next_i64().clamp(0, self.max_count as i64) as usize
+            }
+            None => self.max_count,
+        };
+        count + self.key_len
+    }
+
+    /// Truncates the generated row to the values which are written to the database.
+    /// With a fixed number of columns, all of the values are written, so the row
+    /// is left as is - it may then consist of the multiple rows of the partition.
+    pub fn truncate_row(&self, row: &mut Vec<CqlValue>) {
+        if self.count_distribution.is_some() {
+            row.truncate(self.written_row_len(row));
+        }
+    }
+
+    /// Binds the values of the row, leaving the columns which are not written unset.
+    pub fn bind_row<'a>(&self, row: &'a [CqlValue]) -> Vec<MaybeUnset<&'a CqlValue>> {
+        let written_row_len = self.written_row_len(row);
+        row.iter()
+            .enumerate()
+            .map(|(i, value)| {
+                if i < written_row_len {
+                    MaybeUnset::Set(value)
+                } else {
+                    MaybeUnset::Unset
+                }
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{atomic::AtomicU64, Arc};
+
+    use scylla::frame::{response::result::CqlValue, value::MaybeUnset};
+
+    use crate::java_generate::{
+        distribution::{fixed::FixedDistribution, uniform::UniformDistribution},
+        values::{Blob, Generator, GeneratorConfig, HexBlob},
+    };
+
+    use crate::settings::SequenceEnd;
+
+    use super::{ColumnCountSampler, RowGenerator, SequenceLimit};
+
+    fn row_generator(clustering: usize) -> RowGenerator {
+        let pk_generator = Generator::new(
+            Box::new(HexBlob),
+            GeneratorConfig::new(
+                "randomstrkey",
+                None,
+                Some(Box::new(FixedDistribution::new(10))),
+            ),
+            String::from("key"),
+        );
+        let column_generators = ["C0", "C1"]
+            .into_iter()
+            .map(|column| {
+                Generator::new(
+                    Box::<Blob>::default(),
+                    GeneratorConfig::new(
+                        &format!("randomstr{}", column),
+                        None,
+                        Some(Box::new(FixedDistribution::new(8))),
+                    ),
+                    column.to_owned(),
+                )
+            })
+            .collect();
+        RowGenerator::new(
+            Arc::new(FixedDistribution::new(42)),
+            pk_generator,
+            column_generators,
+        )
+        .with_clustering(clustering)
+    }
+
+    #[test]
+    fn sequence_limit_test() {
+        let limit = |end| {
+            Some(Arc::new(SequenceLimit {
+                sampled: AtomicU64::new(0),
+                len: 2,
+                end,
+            }))
+        };
+
+        let mut generator = row_generator(1).with_sequence_limit(limit(SequenceEnd::Stop));
+        for _ in 0..2 {
+            generator.generate_row();
+            assert!(!generator.sequence_exhausted().unwrap());
+        }
+        generator.generate_pk();
+        assert!(generator.sequence_exhausted().unwrap());
+        // The generator stays exhausted.
+        generator.generate_row();
+        assert!(generator.sequence_exhausted().unwrap());
+
+        // The limit is shared by the generators.
+        let shared = limit(SequenceEnd::Error);
+        let mut first = row_generator(1).with_sequence_limit(shared.clone());
+        let mut second = row_generator(1).with_sequence_limit(shared);
+        first.generate_row();
+        second.generate_row();
+        assert!(first.sequence_exhausted().is_ok());
+        first.generate_row();
+        assert!(first.sequence_exhausted().is_err());
+        assert!(!second.sequence_exhausted().unwrap());
+
+        let mut generator = row_generator(1);
+        for _ in 0..3 {
+            generator.generate_row();
+        }
+        assert!(!generator.sequence_exhausted().unwrap());
+    }
+
+    #[test]
+    fn population_seed_test() {
+        // Two runs, e.g. the write and the read run on different machines,
+        // generate the same rows from the same seeds.
+        let rows = |population_seed| {
+            let mut generator = row_generator(1).with_population_seed(population_seed);
+            (0..3).map(|_| generator.generate_row()).collect::<Vec<_>>()
+        };
+        assert_eq!(rows(7), rows(7));
+
+        // The seed 0 leaves the keys of cassandra-stress intact.
+        let mut generator = row_generator(1);
+        assert_eq!(generator.generate_row(), rows(0)[0]);
+
+        // Different population seeds generate different keys and values.
+        let (first, second) = (rows(0), rows(7));
+        assert_ne!(first[0][0], second[0][0]);
+        assert_ne!(first[0][1..], second[0][1..]);
+    }
+
+    #[test]
+    fn clustering_rows_test() {
+        let single_row = row_generator(1).generate_row();
+        assert_eq!(3, single_row.len());
+
+        let mut generator = row_generator(3);
+        let rows = generator.generate_row();
+        assert_eq!(12, rows.len());
+        for (ck, row) in rows.chunks(4).enumerate() {
+            assert_eq!(single_row[0], row[0]);
+            assert_eq!(CqlValue::Int(ck as i32), row[1]);
+        }
+        // The first row has the same columns as the single row of the partition.
+        assert_eq!(single_row[1..], rows[2..4]);
+        // The columns of the other rows are seeded with their clustering keys.
+        assert_ne!(rows[2..4], rows[6..8]);
+        assert_ne!(rows[6..8], rows[10..12]);
+
+        // The reused buffer contains the same rows.
+        let mut buffer = rows.clone();
+        generator.generate_row_into(&mut buffer);
+        assert_eq!(rows, buffer);
+    }
+
+    #[test]
+    fn partition_visits_test() {
+        let full_partition = row_generator(4).generate_row();
+
+        // Each partition is split into two visits of two rows.
+        let mut generator = row_generator(4).with_visits(
+            Box::new(FixedDistribution::new(2)),
+            Box::new(FixedDistribution::new(1)),
+        );
+        let first_visit = generator.generate_row();
+        let second_visit = generator.generate_row();
+        assert_eq!(8, first_visit.len());
+        assert_eq!(CqlValue::Int(0), first_visit[1]);
+        assert_eq!(CqlValue::Int(2), second_visit[1]);
+        assert_eq!(full_partition, [first_visit, second_visit].concat());
+
+        // The number of visits is limited by the number of rows.
+        let mut generator = row_generator(4).with_visits(
+            Box::new(FixedDistribution::new(10)),
+            Box::new(FixedDistribution::new(1)),
+        );
+        let visits = (0..4).map(|_| generator.generate_row()).collect::<Vec<_>>();
+        assert!(visits.iter().all(|visit| visit.len() == 4));
+        assert_eq!(full_partition, visits.concat());
+        assert!(generator.visits.as_ref().unwrap().partial.is_empty());
+    }
+
+    #[test]
+    fn partition_starts_test() {
+        // The visits of the partition consist of 1, 1 and 2 of its 4 rows (of 4 values each).
+        let mut generator = row_generator(4).with_visits(
+            Box::new(FixedDistribution::new(3)),
+            Box::new(FixedDistribution::new(1)),
+        );
+        let mut row = Vec::new();
+        let mut starts = Vec::new();
+        generator.generate_partitions_into(3, &mut row, &mut starts);
+        assert_eq!(vec![0, 4, 8], starts);
+        assert_eq!(16, row.len());
+        for (ck, values) in row.chunks(4).enumerate() {
+            assert_eq!(CqlValue::Int(ck as i32), values[1]);
+        }
+
+        // Without the visits, the partitions consist of all of their rows.
+        let mut generator = row_generator(4);
+        generator.generate_partitions_into(2, &mut row, &mut starts);
+        assert_eq!(vec![0, 16], starts);
+        assert_eq!(32, row.len());
+    }
+
+    #[test]
+    fn column_count_sampler_test() {
+        let sampler = ColumnCountSampler {
+            count_distribution: Some(Arc::new(UniformDistribution::new(1.0, 3.0).unwrap())),
+            max_count: 3,
+            key_len: 1,
+        };
+
+        for i in 0..100u8 {
+            let row = vec![CqlValue::Blob(vec![i]); 4];
+            let written_row_len = sampler.written_row_len(&row);
+            assert!((2..=4).contains(&written_row_len));
+            // The number of written columns depends only on the partition key.
+            assert_eq!(written_row_len, sampler.written_row_len(&row));
+
+            let bound_row = sampler.bind_row(&row);
+            assert!(bound_row[..written_row_len]
+                .iter()
+                .all(|value| matches!(value, MaybeUnset::Set(_))));
+            assert!(bound_row[written_row_len..]
+                .iter()
+                .all(|value| matches!(value, MaybeUnset::Unset)));
+        }
     }
 }
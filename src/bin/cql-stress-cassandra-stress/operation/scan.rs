@@ -0,0 +1,292 @@
+use std::{
+    ops::ControlFlow,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+};
+
+use anyhow::{Context, Result};
+use cql_stress::{dry_run::RenderedStatement, reprepare::reprepare};
+use parking_lot::Mutex;
+use scylla::{
+    frame::response::result::CqlValue, history::HistoryListener,
+    prepared_statement::PreparedStatement, Session,
+};
+use uuid::Uuid;
+
+use crate::{settings::CassandraStressSettings, stats::ScannedRows};
+
+use super::{
+    execute_read, prepare_statements, render_values, row_generator::RowGenerator,
+    CassandraStressOperation, CassandraStressOperationFactory, CassandraStressRenderer,
+    LastTracingId,
+};
+
+/// Scans one of the token ranges of the table, going around the token ring.
+///
+/// With `filter=`, only the rows matching the predicate are returned
+/// by the server (`ALLOW FILTERING`). The server doesn't report how many
+/// rows it filtered out, so the rows of the range are then counted
+/// with `SELECT COUNT(*)`, which is a part of the operation.
+pub struct ScanOperation {
+    session: Arc<Session>,
+    statement: PreparedStatement,
+    count_statement: Option<PreparedStatement>,
+    filter_value: Option<CqlValue>,
+    range_count: u64,
+    // Shared by all of the operations, so that the ranges are scanned in turn.
+    next_range_idx: Arc<AtomicU64>,
+    paged: bool,
+    tracing_id: LastTracingId,
+    scanned_rows: Mutex<Option<ScannedRows>>,
+}
+
+pub struct ScanOperationFactory {
+    session: Arc<Session>,
+    statement: PreparedStatement,
+    count_statement: Option<PreparedStatement>,
+    filter_value: Option<CqlValue>,
+    range_count: u64,
+    next_range_idx: Arc<AtomicU64>,
+    paged: bool,
+}
+
+impl ScanOperation {
+    async fn do_execute(&self, first: i64, last: i64) -> Result<ScannedRows> {
+        let result = match &self.filter_value {
+            Some(value) => {
+                execute_read(
+                    &self.session,
+                    &self.statement,
+                    (first, last, value),
+                    self.paged,
+                )
+                .await?
+            }
+            None => execute_read(&self.session, &self.statement, (first, last), self.paged).await?,
+        };
+        self.tracing_id.record(&result);
+        let matched = result.rows_or_empty().len() as u64;
+
+        let scanned = match &self.count_statement {
+            Some(count_statement) => {
+                let result = self
+                    .session
+                    .execute_unpaged(count_statement, (first, last))
+                    .await?;
+                let (count,) = result
+                    .first_row_typed::<(i64,)>()
+                    .context("Invalid result of counting the rows of the range")?;
+                count as u64
+            }
+            None => matched,
+        };
+
+        Ok(ScannedRows { scanned, matched })
+    }
+}
+
+impl CassandraStressOperation for ScanOperation {
+    type Factory = ScanOperationFactory;
+
+    async fn execute(&self, _row: &[CqlValue]) -> Result<ControlFlow<()>> {
+        let range_idx = self.next_range_idx.fetch_add(1, Ordering::Relaxed) % self.range_count;
+        let (first, last) = token_range(range_idx, self.range_count);
+
+        let result = self.do_execute(first, last).await;
+        if let Err(err) = result.as_ref() {
+            tracing::error!(error = %err, first, last, "scan error");
+        }
+
+        let rows = result
+            .with_context(|| format!("Token range [{}, {}] could not be scanned.", first, last))?;
+        *self.scanned_rows.lock() = Some(rows);
+
+        Ok(ControlFlow::Continue(()))
+    }
+
+    async fn reprepare(&mut self) -> Result<()> {
+        self.statement = reprepare(&self.session, &self.statement).await?;
+        if let Some(count_statement) = &mut self.count_statement {
+            *count_statement = reprepare(&self.session, count_statement).await?;
+        }
+        Ok(())
+    }
+
+    fn set_tracing(&mut self, tracing: bool) {
+        self.statement.set_tracing(tracing);
+    }
+
+    fn set_history_listener(&mut self, listener: Arc<dyn HistoryListener>) {
+        if let Some(count_statement) = &mut self.count_statement {
+            count_statement.set_history_listener(listener.clone());
+        }
+        self.statement.set_history_listener(listener);
+    }
+
+    fn take_tracing_id(&self) -> Option<Uuid> {
+        self.tracing_id.take()
+    }
+
+    fn take_scanned_rows(&self) -> Option<ScannedRows> {
+        self.scanned_rows.lock().take()
+    }
+
+    // The scans don't use the generated rows.
+    fn generate_row(&self, _row_generator: &mut RowGenerator) -> Vec<CqlValue> {
+        Vec::new()
+    }
+}
+
+impl CassandraStressOperationFactory for ScanOperationFactory {
+    type Operation = ScanOperation;
+
+    fn create(&self) -> Self::Operation {
+        ScanOperation {
+            session: Arc::clone(&self.session),
+            statement: self.statement.clone(),
+            count_statement: self.count_statement.clone(),
+            filter_value: self.filter_value.clone(),
+            range_count: self.range_count,
+            next_range_idx: Arc::clone(&self.next_range_idx),
+            paged: self.paged,
+            tracing_id: LastTracingId::default(),
+            scanned_rows: Mutex::new(None),
+        }
+    }
+}
+
+impl ScanOperationFactory {
+    pub async fn new(
+        settings: Arc<CassandraStressSettings>,
+        session: Arc<Session>,
+    ) -> Result<Self> {
+        let mut statements = vec![(String::from("scan"), build_query(&settings))];
+        if filter_value(&settings).is_some() {
+            statements.push((String::from("scan_count"), build_count_query(&settings)));
+        }
+        let mut statements = prepare_statements(&session, statements).await?;
+        for statement in statements.iter_mut() {
+            statement.set_is_idempotent(true);
+            statement.set_consistency(settings.command_params.common.consistency_level);
+            statement.set_serial_consistency(Some(
+                settings.command_params.common.serial_consistency_level,
+            ));
+        }
+        let count_statement = (statements.len() > 1).then(|| statements.remove(1));
+        let mut statement = statements.remove(0);
+        if let Some(fetch_size) = settings.mode.fetch_size {
+            statement.set_page_size(fetch_size);
+        }
+
+        Ok(Self {
+            session,
+            statement,
+            count_statement,
+            filter_value: filter_value(&settings),
+            range_count: range_count(&settings),
+            next_range_idx: Arc::new(AtomicU64::new(0)),
+            paged: settings.mode.fetch_size.is_some(),
+        })
+    }
+}
+
+fn range_count(settings: &CassandraStressSettings) -> u64 {
+    // Validated during parsing.
+    settings.command_params.scan.as_ref().unwrap().ranges
+}
+
+fn filter_value(settings: &CassandraStressSettings) -> Option<CqlValue> {
+    settings
+        .command_params
+        .scan
+        .as_ref()
+        .and_then(|scan| scan.filter.as_ref())
+        .map(|filter| CqlValue::Blob(filter.value.clone()))
+}
+
+const TOKEN_RESTRICTION: &str = "token(key) >= ? AND token(key) <= ?";
+
+fn build_query(settings: &CassandraStressSettings) -> String {
+    let table = settings.schema.table_identifier();
+    match settings
+        .command_params
+        .scan
+        .as_ref()
+        .and_then(|scan| scan.filter.as_ref())
+    {
+        Some(filter) => format!(
+            "SELECT key FROM {} WHERE {} AND {} ALLOW FILTERING",
+            table,
+            TOKEN_RESTRICTION,
+            filter.restriction()
+        ),
+        None => format!("SELECT key FROM {} WHERE {}", table, TOKEN_RESTRICTION),
+    }
+}
+
+fn build_count_query(settings: &CassandraStressSettings) -> String {
+    format!(
+        "SELECT COUNT(*) FROM {} WHERE {}",
+        settings.schema.table_identifier(),
+        TOKEN_RESTRICTION
+    )
+}
+
+/// Returns the first and the last token of the given range, out of
+/// `range_count` ranges splitting the token ring without overlapping.
+fn token_range(range_idx: u64, range_count: u64) -> (i64, i64) {
+    let bound = |idx: u64| ((idx as i128) << 64) / range_count as i128 + i64::MIN as i128;
+    let first = bound(range_idx) as i64;
+    let last = (bound(range_idx + 1) - 1) as i64;
+    (first, last)
+}
+
+/// Renders the scans of [ScanOperation], which go around the token ring
+/// the same way.
+pub struct ScanRenderer {
+    statement: String,
+    filter_value: Option<CqlValue>,
+    range_count: u64,
+    next_range_idx: u64,
+}
+
+impl ScanRenderer {
+    pub fn new(settings: &CassandraStressSettings) -> Self {
+        Self {
+            statement: build_query(settings),
+            filter_value: filter_value(settings),
+            range_count: range_count(settings),
+            next_range_idx: 0,
+        }
+    }
+}
+
+impl CassandraStressRenderer for ScanRenderer {
+    fn render(&mut self, _row_generator: &mut RowGenerator) -> Vec<RenderedStatement> {
+        let (first, last) = token_range(self.next_range_idx, self.range_count);
+        self.next_range_idx = (self.next_range_idx + 1) % self.range_count;
+        let mut values = vec![first.to_string(), last.to_string()];
+        values.extend(render_values(&self.filter_value));
+        vec![RenderedStatement::new(self.statement.clone(), values)]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::token_range;
+
+    #[test]
+    fn token_range_test() {
+        assert_eq!((i64::MIN, i64::MAX), token_range(0, 1));
+        assert_eq!((i64::MIN, i64::MIN / 2 - 1), token_range(0, 4));
+        assert_eq!((i64::MAX / 2 + 1, i64::MAX), token_range(3, 4));
+
+        // The consecutive ranges don't overlap and leave no gaps.
+        for idx in 0..999 {
+            assert_eq!(token_range(idx, 1000).1 + 1, token_range(idx + 1, 1000).0);
+        }
+        assert_eq!(i64::MAX, token_range(999, 1000).1);
+    }
+}
@@ -1,12 +1,25 @@
-use std::{collections::HashMap, ops::ControlFlow, sync::Arc};
+use std::{
+    collections::{BTreeMap, HashMap},
+    ops::ControlFlow,
+    sync::Arc,
+};
 
 use cql_stress::{
     configuration::{Operation, OperationContext, OperationFactory},
+    coordinator::LastCoordinator,
+    dry_run::{OperationRenderer, RenderedStatement},
+    error_log::ErrorLog,
     make_runnable,
+    partition_lock::PartitionLocks,
+    reprepare::reprepare,
+    seed::SeedSequence,
 };
 use scylla::{
-    frame::response::result::CqlValue, prepared_statement::PreparedStatement,
-    transport::topology::Table, Session,
+    frame::response::result::CqlValue,
+    history::HistoryListener,
+    prepared_statement::PreparedStatement,
+    transport::topology::{CqlType, NativeType, Table},
+    Session,
 };
 
 use anyhow::{Context, Result};
@@ -14,23 +27,30 @@ use anyhow::{Context, Result};
 use crate::{
     java_generate::{
         distribution::{Distribution, DistributionFactory},
-        values::{Generator, GeneratorConfig, ValueGeneratorFactory},
+        values::{words::WordsFactory, Generator, GeneratorConfig, ValueGeneratorFactory},
+    },
+    settings::{
+        CassandraStressSettings, ColumnSpec, OpWeight, UserParams, PREDEFINED_INSERT_OPERATION,
     },
-    settings::{CassandraStressSettings, OpWeight, PREDEFINED_INSERT_OPERATION},
-    stats::ShardedStats,
+    stats::{Sample, ShardedStats, StatsBuffer},
 };
 
 use super::{
-    row_generator::RowGenerator, CassandraStressOperation, CassandraStressOperationFactory,
-    OperationSampler,
+    execute_read, execute_with_reprepare, log_error, offline_schema, prepare_statements,
+    render_values,
+    row_generator::{RowGenerator, SequenceLimit},
+    CassandraStressOperation, CassandraStressOperationFactory, CassandraStressRenderer,
+    GenericCassandraStressRenderer, OperationSampler, RowBuffer,
 };
 
 const SEED_STR: &str = "seed for stress";
 
 pub struct UserDefinedOperation {
+    name: String,
     session: Arc<Session>,
     statement: PreparedStatement,
     argument_index: Vec<usize>,
+    paged: bool,
 }
 
 impl CassandraStressOperation for UserDefinedOperation {
@@ -44,23 +64,37 @@ impl CassandraStressOperation for UserDefinedOperation {
         }
 
         // User can provide a custom query here. In addition, we don't care
-        // about the result of this query. This is why we can use `execute_unpaged`.
-        self.session
-            .execute_unpaged(&self.statement, bound_row)
-            .await?;
+        // about the result of this query. This is why we can execute it unpaged,
+        // unless the page size is set with `-mode fetchsize=`.
+        execute_read(&self.session, &self.statement, bound_row, self.paged).await?;
 
         Ok(ControlFlow::Continue(()))
     }
 
+    async fn reprepare(&mut self) -> Result<()> {
+        self.statement = reprepare(&self.session, &self.statement).await?;
+        Ok(())
+    }
+
+    fn set_history_listener(&mut self, listener: Arc<dyn HistoryListener>) {
+        self.statement.set_history_listener(listener);
+    }
+
     fn generate_row(&self, row_generator: &mut RowGenerator) -> Vec<CqlValue> {
         row_generator.generate_row()
     }
+
+    fn generate_row_into(&self, row_generator: &mut RowGenerator, row: &mut Vec<CqlValue>) {
+        row_generator.generate_row_into(row);
+    }
 }
 
 pub struct UserDefinedOperationFactory {
+    name: String,
     session: Arc<Session>,
     statement: PreparedStatement,
     argument_index: Vec<usize>,
+    paged: bool,
 }
 
 impl CassandraStressOperationFactory for UserDefinedOperationFactory {
@@ -68,9 +102,11 @@ impl CassandraStressOperationFactory for UserDefinedOperationFactory {
 
     fn create(&self) -> Self::Operation {
         UserDefinedOperation {
+            name: self.name.clone(),
             session: Arc::clone(&self.session),
             statement: self.statement.clone(),
             argument_index: self.argument_index.clone(),
+            paged: self.paged,
         }
     }
 }
@@ -78,40 +114,51 @@ impl CassandraStressOperationFactory for UserDefinedOperationFactory {
 pub struct UserOperation {
     sampler: OperationSampler<UserDefinedOperation>,
     workload: RowGenerator,
-    stats: Arc<ShardedStats>,
-    max_operations: Option<u64>,
-    cached_row: Option<Vec<CqlValue>>,
+    stats: StatsBuffer,
+    error_log: Option<Arc<ErrorLog>>,
+    partition_locks: Option<Arc<PartitionLocks>>,
+    coordinator: Arc<LastCoordinator>,
+    row: RowBuffer,
 }
 
-make_runnable!(UserOperation);
+make_runnable!(UserOperation, stats);
 impl UserOperation {
     pub async fn execute(&mut self, ctx: &OperationContext) -> Result<ControlFlow<()>> {
-        if self
-            .max_operations
-            .is_some_and(|max_ops| ctx.operation_id >= max_ops)
-        {
+        // The retried operation is the same as the one which generated the row.
+        let op = match self.row.pending() {
+            Some(_) => self.sampler.previous_sample(),
+            None => self.sampler.sample(),
+        };
+        let row = self
+            .row
+            .get_or_generate(|row| op.generate_partitions_into(&mut self.workload, row));
+        if self.workload.sequence_exhausted()? {
             return Ok(ControlFlow::Break(()));
         }
 
-        let (op, row) = match &mut self.cached_row {
-            Some(cached_row) => (self.sampler.previous_sample(), cached_row),
-            None => {
-                let op = self.sampler.sample();
-                let row = self.cached_row.insert(op.generate_row(&mut self.workload));
-                (op, row)
-            }
-        };
-
-        let op_result = op.execute(row).await;
+        let op_result = execute_with_reprepare(
+            op,
+            row,
+            self.stats.sharded(),
+            self.partition_locks.as_deref(),
+            &self.coordinator,
+        )
+        .await;
 
+        let coordinator = self.coordinator.take();
         self.stats
-            .get_shard_mut()
-            .account_operation(ctx, &op_result);
+            .record(Sample::new(ctx, &op_result).with_coordinator(coordinator));
+        log_error(
+            self.error_log.as_deref(),
+            &op.name,
+            op.partition_key(&row.values),
+            &op_result,
+        );
 
         if op_result.is_ok() {
             // Operation was successful - we will generate new row
             // for the next operation.
-            self.cached_row = None;
+            self.row.complete();
         }
 
         op_result
@@ -120,50 +167,72 @@ impl UserOperation {
 
 pub struct UserOperationFactory {
     session: Arc<Session>,
-    pk_seed_distribution: Arc<dyn Distribution>,
     stats: Arc<ShardedStats>,
+    error_log: Option<Arc<ErrorLog>>,
+    partition_locks: Option<Arc<PartitionLocks>>,
+    // Ordered, so that the sampled operations don't depend on the hashing order.
+    queries_payload: BTreeMap<String, (PreparedStatement, OpWeight)>,
+    workload_factory: UserRowGeneratorFactory,
+    clustering: Arc<dyn DistributionFactory>,
+    fetch_size: Option<i32>,
+    seeds: SeedSequence,
+}
+
+/// Creates the row generators of the table stressed by the user profile.
+struct UserRowGeneratorFactory {
+    pk_seed_distribution: Arc<dyn Distribution>,
+    sequence_limit: Option<Arc<SequenceLimit>>,
+    population_seed: u64,
     table_metadata: Table,
-    queries_payload: HashMap<String, (PreparedStatement, OpWeight)>,
     pk_generator_factory: Box<dyn ValueGeneratorFactory>,
     column_generator_factories: Vec<Box<dyn ValueGeneratorFactory>>,
-    max_operations: Option<u64>,
-    clustering: Arc<dyn DistributionFactory>,
+    column_specs: HashMap<String, ColumnSpec>,
 }
 
-impl UserOperationFactory {
-    async fn prepare_insert_statement(
-        session: &Arc<Session>,
-        table_name: &str,
-        table_metadata: &Table,
-    ) -> Result<PreparedStatement> {
-        let column_names = table_metadata
-            .columns
-            .keys()
-            .map(String::as_str)
-            .collect::<Vec<_>>();
-
-        let column_list_str = column_names.join(", ");
-        let column_values_str = std::iter::repeat("?")
-            .take(column_names.len())
-            .collect::<Vec<_>>()
-            .join(", ");
-
-        let statement_str =
-            format!("INSERT INTO {table_name} ({column_list_str}) VALUES ({column_values_str})");
-        session
-            .prepare(statement_str)
-            .await
-            .context("Failed to prepare statement for 'insert' operation.")
-    }
+fn insert_statement_str(
+    table_name: &str,
+    table_metadata: &Table,
+    ttl_clause: Option<String>,
+) -> String {
+    let column_names = table_metadata
+        .columns
+        .keys()
+        .map(String::as_str)
+        .collect::<Vec<_>>();
+
+    let column_list_str = column_names.join(", ");
+    let column_values_str = std::iter::repeat("?")
+        .take(column_names.len())
+        .collect::<Vec<_>>()
+        .join(", ");
+
+    format!(
+        "INSERT INTO {table_name} ({column_list_str}) VALUES ({column_values_str}){}",
+        ttl_clause.unwrap_or_default()
+    )
+}
 
+impl UserOperationFactory {
     pub async fn new(
         settings: Arc<CassandraStressSettings>,
         session: Arc<Session>,
         stats: Arc<ShardedStats>,
+        error_log: Option<Arc<ErrorLog>>,
     ) -> Result<Self> {
         // We parsed a user command. This unwrap is safe.
         let user_profile = settings.command_params.user.as_ref().unwrap();
+        Self::with_profile(&settings, user_profile, session, stats, error_log).await
+    }
 
+    /// Prepares the queries of the given profile, e.g. the ones mixed
+    /// with the commands by the mixed command.
+    pub async fn with_profile(
+        settings: &CassandraStressSettings,
+        user_profile: &UserParams,
+        session: Arc<Session>,
+        stats: Arc<ShardedStats>,
+        error_log: Option<Arc<ErrorLog>>,
+    ) -> Result<Self> {
         let query_definitions = &user_profile.queries_payload;
         let cluster_data = session.get_cluster_data();
         let table_metadata = cluster_data
@@ -182,27 +251,42 @@ impl UserOperationFactory {
             })?
             .clone();
 
-        anyhow::ensure!(
-            table_metadata.partition_key.len() == 1,
-            "Compound partition keys are not yet supported by the tool!"
-        );
-
         let queries_payload = {
-            let mut queries_payload = HashMap::new();
+            let query_definitions = query_definitions.iter().collect::<Vec<_>>();
+            let mut statements = query_definitions
+                .iter()
+                .map(|(q_name, (q_def, _))| (q_name.to_string(), q_def.cql.clone()))
+                .collect::<Vec<_>>();
+            // Handle 'insert' operation separately.
+            if user_profile.insert_operation_weight.is_some() {
+                statements.push((
+                    PREDEFINED_INSERT_OPERATION.to_owned(),
+                    insert_statement_str(
+                        &user_profile.table,
+                        &table_metadata,
+                        settings.schema.construct_ttl_clause(),
+                    ),
+                ));
+            }
+            let mut prepared = prepare_statements(&session, statements).await?.into_iter();
+
+            let mut queries_payload = BTreeMap::new();
             for (q_name, (q_def, weight)) in query_definitions {
-                queries_payload.insert(
-                    q_name.to_owned(),
-                    (q_def.to_prepared_statement(&session).await?, *weight),
+                // The statements are returned in the order they were given.
+                let mut statement = prepared.next().unwrap();
+                q_def.configure_statement(
+                    &mut statement,
+                    settings.command_params.common.serial_consistency_level,
                 );
+                if let Some(fetch_size) = settings.mode.fetch_size {
+                    statement.set_page_size(fetch_size);
+                }
+                queries_payload.insert(q_name.to_owned(), (statement, *weight));
             }
-            // Handle 'insert' operation separately.
             if let Some(insert_weight) = &user_profile.insert_operation_weight {
-                let insert_statement =
-                    Self::prepare_insert_statement(&session, &user_profile.table, &table_metadata)
-                        .await?;
                 queries_payload.insert(
                     PREDEFINED_INSERT_OPERATION.to_owned(),
-                    (insert_statement, *insert_weight),
+                    (prepared.next().unwrap(), *insert_weight),
                 );
             }
 
@@ -221,11 +305,62 @@ impl UserOperationFactory {
             queries_payload
         };
 
+        let workload_factory =
+            UserRowGeneratorFactory::new(settings, table_metadata, &user_profile.column_specs)?;
+
+        Ok(Self {
+            session,
+            stats,
+            error_log,
+            partition_locks: settings.rate.create_partition_locks(),
+            queries_payload,
+            workload_factory,
+            clustering: user_profile.clustering.clone(),
+            fetch_size: settings.mode.fetch_size,
+            seeds: SeedSequence::new(settings.seed.seed),
+        })
+    }
+}
+
+impl UserRowGeneratorFactory {
+    fn new(
+        settings: &CassandraStressSettings,
+        table_metadata: Table,
+        column_specs: &HashMap<String, ColumnSpec>,
+    ) -> Result<Self> {
+        anyhow::ensure!(
+            table_metadata.partition_key.len() == 1,
+            "Compound partition keys are not yet supported by the tool!"
+        );
+        for col_name in column_specs.keys() {
+            anyhow::ensure!(
+                table_metadata.columns.contains_key(col_name),
+                "Unknown column {} in columnspec",
+                col_name
+            );
+        }
+        let generator_factory = |col_name: &str, typ: &CqlType| match column_specs
+            .get(col_name)
+            .and_then(|spec| spec.words.clone())
+        {
+            Some(words) => {
+                anyhow::ensure!(
+                    *typ == CqlType::Native(NativeType::Text),
+                    "words of columnspec require a text column, but {} is {:?}",
+                    col_name,
+                    typ
+                );
+                Ok(Box::new(WordsFactory(words)) as Box<dyn ValueGeneratorFactory>)
+            }
+            None => Generator::new_generator_factory_from_cql_type(typ),
+        };
+
         let pk_seed_distribution = settings.population.pk_seed_distribution.create().into();
-        let max_operations = settings.command_params.common.operation_count;
+        let sequence_limit = SequenceLimit::new(settings);
 
         let pk_name = &table_metadata.partition_key[0];
-        let pk_generator_factory = Generator::new_generator_factory_from_cql_type(
+        let pk_generator_factory = generator_factory(
+            pk_name,
             &table_metadata
                 .columns
                 .get(pk_name)
@@ -241,29 +376,37 @@ impl UserOperationFactory {
             .columns
             .iter()
             .filter(|&(col_name, _col_def)| (*col_name != *pk_name))
-            .map(|(_col_name, col_def)| {
-                Generator::new_generator_factory_from_cql_type(&col_def.type_)
-            })
+            .map(|(col_name, col_def)| generator_factory(col_name, &col_def.type_))
             .collect::<Result<Vec<_>, _>>()?;
 
         Ok(Self {
-            session,
             pk_seed_distribution,
-            stats,
+            sequence_limit,
+            population_seed: settings.population.seed,
             table_metadata,
-            queries_payload,
-            max_operations,
             pk_generator_factory,
             column_generator_factories,
-            clustering: user_profile.clustering.clone(),
+            column_specs: column_specs.clone(),
         })
     }
 
-    fn create_workload(&self) -> RowGenerator {
+    /// Applies the size and the population distributions of the columnspec of the column.
+    fn generator_config(&self, col_name: &str) -> GeneratorConfig {
+        let spec = self.column_specs.get(col_name);
+        GeneratorConfig::new(
+            &format!("{}{}", SEED_STR, col_name),
+            spec.and_then(|spec| spec.population.as_ref())
+                .map(|population| population.create()),
+            spec.and_then(|spec| spec.size.as_ref())
+                .map(|size| size.create()),
+        )
+    }
+
+    fn create(&self) -> RowGenerator {
         let pk_name = &self.table_metadata.partition_key[0];
         let pk_generator = Generator::new(
             self.pk_generator_factory.create(),
-            GeneratorConfig::new(&format!("{}{}", SEED_STR, pk_name), None, None),
+            self.generator_config(pk_name),
             pk_name.clone(),
         );
 
@@ -276,7 +419,7 @@ impl UserOperationFactory {
             .map(|((col_name, _), gen_factory)| {
                 Generator::new(
                     gen_factory.create(),
-                    GeneratorConfig::new(&format!("{}{}", SEED_STR, col_name), None, None),
+                    self.generator_config(col_name),
                     col_name.to_owned(),
                 )
             })
@@ -287,44 +430,149 @@ impl UserOperationFactory {
             pk_generator,
             column_generators,
         )
+        .with_sequence_limit(self.sequence_limit.clone())
+        .with_population_seed(self.population_seed)
     }
 }
 
-impl OperationFactory for UserOperationFactory {
-    fn create(&self) -> Box<dyn Operation> {
-        let workload = self.create_workload();
+impl UserOperationFactory {
+    /// Creates the generator of the rows of the table of the profile.
+    pub fn create_workload(&self) -> RowGenerator {
+        self.workload_factory.create()
+    }
 
-        let weights_iter =
-            self.queries_payload
-                .iter()
-                .map(|(_op_name, (stmt, weight))| {
-                    let variable_metadata = stmt.get_variable_col_specs();
-                    let argument_index = variable_metadata
-                        .iter()
-                        .map(|col_spec| {
-                            workload.row_index_of_column_with_name(&col_spec.name).expect(
+    /// Creates the operations of the queries, binding the columns
+    /// of the rows of `workload`, together with their weights.
+    pub fn create_operations<'a>(
+        &'a self,
+        workload: &'a RowGenerator,
+        coordinator: &'a Arc<LastCoordinator>,
+    ) -> impl Iterator<Item = (UserDefinedOperation, OpWeight)> + 'a {
+        self.queries_payload
+            .iter()
+            .map(move |(op_name, (stmt, weight))| {
+                let variable_metadata = stmt.get_variable_col_specs();
+                let argument_index = variable_metadata
+                    .iter()
+                    .map(|col_spec| {
+                        workload
+                            .row_index_of_column_with_name(&col_spec.name)
+                            .expect(
                             "Prepared statement metadata is inconsistent with cluster metadata.",
                         )
-                        })
-                        .collect::<Vec<_>>();
-                    (
-                        UserDefinedOperation {
-                            session: Arc::clone(&self.session),
-                            statement: stmt.clone(),
-                            argument_index,
-                        },
-                        *weight,
-                    )
-                });
+                    })
+                    .collect::<Vec<_>>();
+                let mut operation = UserDefinedOperation {
+                    name: op_name.clone(),
+                    session: Arc::clone(&self.session),
+                    statement: stmt.clone(),
+                    argument_index,
+                    paged: self.fetch_size.is_some(),
+                };
+                operation.set_history_listener(coordinator.clone());
+                (operation, *weight)
+            })
+    }
+}
 
-        let sampler = OperationSampler::new(weights_iter, self.clustering.as_ref());
+impl UserDefinedOperation {
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+}
+
+impl OperationFactory for UserOperationFactory {
+    fn create(&self) -> Box<dyn Operation> {
+        let workload = self.workload_factory.create();
+        // The sampled operations are executed one at a time, so they share the listener.
+        let coordinator = Arc::new(LastCoordinator::default());
+
+        let sampler = OperationSampler::new(
+            self.create_operations(&workload, &coordinator),
+            self.clustering.as_ref(),
+            self.seeds.next_seed(),
+        );
 
         Box::new(UserOperation {
             workload,
-            stats: Arc::clone(&self.stats),
-            max_operations: self.max_operations,
+            stats: StatsBuffer::new(Arc::clone(&self.stats)),
+            error_log: self.error_log.clone(),
+            partition_locks: self.partition_locks.clone(),
+            coordinator,
             sampler,
-            cached_row: None,
+            row: RowBuffer::default(),
         })
     }
 }
+
+/// Renders the user-defined operations. The bound columns are inferred
+/// from the CQL text, as the statements can't be prepared without a cluster.
+pub struct UserDefinedRenderer {
+    statement: String,
+    argument_index: Vec<usize>,
+}
+
+impl CassandraStressRenderer for UserDefinedRenderer {
+    fn render(&mut self, row_generator: &mut RowGenerator) -> Vec<RenderedStatement> {
+        let row = row_generator.generate_row();
+        vec![RenderedStatement::new(
+            self.statement.clone(),
+            render_values(self.argument_index.iter().map(|&i| &row[i])),
+        )]
+    }
+}
+
+pub fn create_renderers(
+    settings: &Arc<CassandraStressSettings>,
+) -> Result<Vec<Box<dyn OperationRenderer>>> {
+    // We parsed a user command. This unwrap is safe.
+    let user_profile = settings.command_params.user.as_ref().unwrap();
+
+    let table_definition = user_profile.table_definition.as_ref().ok_or_else(|| {
+        anyhow::anyhow!("Dry run of the user command requires table_definition in the profile")
+    })?;
+    let table_metadata = offline_schema::parse_table_definition(table_definition)
+        .context("Failed to parse table_definition")?;
+
+    let mut queries = user_profile
+        .queries_payload
+        .iter()
+        .map(|(q_name, (q_def, _))| (q_name.to_owned(), q_def.cql.clone()))
+        .collect::<BTreeMap<_, _>>();
+    if user_profile.insert_operation_weight.is_some() {
+        queries.insert(
+            PREDEFINED_INSERT_OPERATION.to_owned(),
+            insert_statement_str(
+                &user_profile.table,
+                &table_metadata,
+                settings.schema.construct_ttl_clause(),
+            ),
+        );
+    }
+
+    let workload_factory =
+        UserRowGeneratorFactory::new(settings, table_metadata, &user_profile.column_specs)?;
+    let mut renderers: Vec<Box<dyn OperationRenderer>> = Vec::with_capacity(queries.len());
+    for (q_name, cql) in queries {
+        let workload = workload_factory.create();
+        let argument_index = offline_schema::bound_columns(&cql)
+            .with_context(|| format!("Failed to render statement: {}", cql))?
+            .iter()
+            .map(|col_name| {
+                workload
+                    .row_index_of_column_with_name(col_name)
+                    .ok_or_else(|| anyhow::anyhow!("Unknown column {} in: {}", col_name, cql))
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        renderers.push(Box::new(GenericCassandraStressRenderer::new(
+            q_name,
+            Box::new(UserDefinedRenderer {
+                statement: cql,
+                argument_index,
+            }),
+            workload,
+        )));
+    }
+    Ok(renderers)
+}
@@ -1,30 +1,82 @@
 use std::{ops::ControlFlow, sync::Arc};
 
-use anyhow::{Context, Result};
-use scylla::{frame::response::result::CqlValue, prepared_statement::PreparedStatement, Session};
+use anyhow::Result;
+use scylla::{
+    batch::{Batch, BatchType},
+    frame::response::result::CqlValue,
+    history::HistoryListener,
+    prepared_statement::PreparedStatement,
+    Session,
+};
+use uuid::Uuid;
+
+use cql_stress::{dry_run::RenderedStatement, reprepare::reprepare};
 
-use crate::settings::CassandraStressSettings;
+use crate::settings::{CassandraStressSettings, CLUSTERING_COLUMN};
 
 use super::{
-    row_generator::RowGenerator, CassandraStressOperation, CassandraStressOperationFactory,
+    prepare_statements, render_values,
+    row_generator::{ColumnCountSampler, RowGenerator},
+    CassandraStressOperation, CassandraStressOperationFactory, CassandraStressRenderer,
+    GeneratedRow, LastTracingId,
 };
 
 pub struct WriteOperation {
     session: Arc<Session>,
     statement: PreparedStatement,
+    batch: Option<WriteBatch>,
+    column_count: ColumnCountSampler,
+    tracing_id: LastTracingId,
 }
 
 pub struct WriteOperationFactory {
     session: Arc<Session>,
     statement: PreparedStatement,
+    batch: Option<WriteBatch>,
+    column_count: ColumnCountSampler,
+}
+
+/// An UNLOGGED batch consisting of `batch.statements.len()` inserts.
+/// The row passed to [WriteOperation::execute] is then a concatenation
+/// of the rows inserted by each of the statements.
+///
+/// Each of the `partitions` consists of the clustering rows (see `-col clustering=`)
+/// of a visit of the partition, whose number varies with `-insert visits=`.
+/// Thus, the offsets of the partitions are generated along with the row
+/// (see [GeneratedRow]).
+#[derive(Clone)]
+struct WriteBatch {
+    batch: Batch,
+    row_len: usize,
+    partitions: usize,
 }
 
 impl CassandraStressOperation for WriteOperation {
     type Factory = WriteOperationFactory;
 
     async fn execute(&self, row: &[CqlValue]) -> Result<ControlFlow<()>> {
-        // execute_unpaged, since it's an INSERT statement.
-        let result = self.session.execute_unpaged(&self.statement, &row).await;
+        let result = match &self.batch {
+            Some(WriteBatch { batch, row_len, .. }) => {
+                let values = row
+                    .chunks(*row_len)
+                    .map(|row| self.column_count.bind_row(row))
+                    .collect::<Vec<_>>();
+                if values.len() < batch.statements.len() {
+                    // A visit of a partition (see `-insert visits=`) writes a part of its rows.
+                    let mut batch = batch.clone();
+                    batch.statements.truncate(values.len());
+                    self.session.batch(&batch, values).await
+                } else {
+                    self.session.batch(batch, values).await
+                }
+            }
+            // execute_unpaged, since it's an INSERT statement.
+            None => {
+                self.session
+                    .execute_unpaged(&self.statement, self.column_count.bind_row(row))
+                    .await
+            }
+        };
 
         if let Err(err) = result.as_ref() {
             tracing::error!(
@@ -34,13 +86,68 @@ impl CassandraStressOperation for WriteOperation {
             );
         }
 
-        result?;
+        self.tracing_id.record(&result?);
 
         Ok(ControlFlow::Continue(()))
     }
 
+    async fn reprepare(&mut self) -> Result<()> {
+        self.statement = reprepare(&self.session, &self.statement).await?;
+        if let Some(WriteBatch { batch, .. }) = &mut self.batch {
+            for statement in batch.statements.iter_mut() {
+                *statement = self.statement.clone().into();
+            }
+        }
+        Ok(())
+    }
+
+    fn set_tracing(&mut self, tracing: bool) {
+        self.statement.set_tracing(tracing);
+        if let Some(WriteBatch { batch, .. }) = &mut self.batch {
+            batch.set_tracing(tracing);
+        }
+    }
+
+    fn set_history_listener(&mut self, listener: Arc<dyn HistoryListener>) {
+        if let Some(WriteBatch { batch, .. }) = &mut self.batch {
+            batch.set_history_listener(listener.clone());
+        }
+        self.statement.set_history_listener(listener);
+    }
+
+    fn take_tracing_id(&self) -> Option<Uuid> {
+        self.tracing_id.take()
+    }
+
     fn generate_row(&self, row_generator: &mut RowGenerator) -> Vec<CqlValue> {
-        row_generator.generate_row()
+        let mut row = GeneratedRow::default();
+        self.generate_partitions_into(row_generator, &mut row);
+        row.values
+    }
+
+    fn generate_partitions_into(&self, row_generator: &mut RowGenerator, row: &mut GeneratedRow) {
+        match &self.batch {
+            Some(WriteBatch { partitions, .. }) => row_generator.generate_partitions_into(
+                *partitions,
+                &mut row.values,
+                &mut row.partition_starts,
+            ),
+            None => {
+                row_generator.generate_row_into(&mut row.values);
+                row.partition_starts.clear();
+            }
+        }
+    }
+
+    fn partition_keys<'a>(&self, row: &'a GeneratedRow) -> Vec<&'a CqlValue> {
+        match &self.batch {
+            Some(_) => row
+                .partition_starts
+                .iter()
+                .map(|start| &row.values[*start])
+                .collect(),
+            None => vec![&row.values[0]],
+        }
     }
 }
 
@@ -51,6 +158,9 @@ impl CassandraStressOperationFactory for WriteOperationFactory {
         WriteOperation {
             session: Arc::clone(&self.session),
             statement: self.statement.clone(),
+            batch: self.batch.clone(),
+            column_count: self.column_count.clone(),
+            tracing_id: LastTracingId::default(),
         }
     }
 }
@@ -60,27 +170,162 @@ impl WriteOperationFactory {
         settings: Arc<CassandraStressSettings>,
         session: Arc<Session>,
     ) -> Result<Self> {
-        let mut statement_str = String::from("INSERT INTO standard1 (key");
+        let statement_str = Self::build_query(&settings);
+        let batch_size = batch_size(&settings);
+        let statements = batch_size * settings.column.clustering;
+
+        let mut statement =
+            prepare_statements(&session, vec![(String::from("write"), statement_str)])
+                .await?
+                .remove(0);
+
+        // A retry of a conditional write would fail its condition
+        // if the first attempt was applied.
+        let idempotent = !lwt(&settings);
+        statement.set_is_idempotent(idempotent);
+        statement.set_consistency(settings.command_params.write_consistency_level());
+        statement.set_serial_consistency(Some(
+            settings.command_params.common.serial_consistency_level,
+        ));
+
+        let column_count = ColumnCountSampler::new(&settings);
+        let batch = (statements > 1).then(|| {
+            let mut batch = Batch::new(BatchType::Unlogged);
+            for _ in 0..statements {
+                batch.append_statement(statement.clone());
+            }
+            batch.set_is_idempotent(idempotent);
+            batch.set_consistency(settings.command_params.write_consistency_level());
+            batch.set_serial_consistency(Some(
+                settings.command_params.common.serial_consistency_level,
+            ));
+            WriteBatch {
+                batch,
+                row_len: column_count.row_len(),
+                partitions: batch_size,
+            }
+        });
+
+        Ok(Self {
+            session,
+            statement,
+            batch,
+            column_count,
+        })
+    }
+
+    fn build_query(settings: &CassandraStressSettings) -> String {
+        let mut statement_str = format!("INSERT INTO {} (key", settings.schema.table_identifier());
+        if settings.column.has_clustering_column() {
+            statement_str += &format!(", {}", CLUSTERING_COLUMN);
+        }
         for column in settings.column.columns.iter() {
             statement_str += &format!(", \"{}\"", column);
         }
         statement_str += ") VALUES (?";
-        for _ in settings.column.columns.iter() {
+        for _ in 1..settings.column.row_len() {
             statement_str += ", ?";
         }
         statement_str.push(')');
 
-        let mut statement = session
-            .prepare(statement_str)
-            .await
-            .context("Failed to prepare statement")?;
+        if lwt(settings) {
+            // Conditional write - the serial consistency level
+            // is used for the Paxos phase of the LWT.
+            statement_str += " IF NOT EXISTS";
+        }
+        if let Some(ttl_clause) = settings.schema.construct_ttl_clause() {
+            statement_str += &ttl_clause;
+        }
+        statement_str
+    }
+}
+
+/// Whether the rows are inserted with lightweight transactions.
+fn lwt(settings: &CassandraStressSettings) -> bool {
+    settings
+        .command_params
+        .write
+        .as_ref()
+        .is_some_and(|write| write.lwt)
+}
 
-        statement.set_is_idempotent(true);
-        statement.set_consistency(settings.command_params.common.consistency_level);
-        statement.set_serial_consistency(Some(
-            settings.command_params.common.serial_consistency_level,
-        ));
+/// Number of partitions inserted by a single operation.
+fn batch_size(settings: &CassandraStressSettings) -> usize {
+    settings
+        .command_params
+        .write
+        .as_ref()
+        .map_or(1, |write| write.batch_size.get())
+}
 
-        Ok(Self { session, statement })
+/// Renders the inserts of [WriteOperation], one statement per row of the batch
+/// (and per clustering row of the visit of each of its partitions).
+pub struct WriteRenderer {
+    statement: String,
+    batch_size: usize,
+    column_count: ColumnCountSampler,
+    row: GeneratedRow,
+}
+
+impl WriteRenderer {
+    pub fn new(settings: &CassandraStressSettings) -> Self {
+        Self {
+            statement: WriteOperationFactory::build_query(settings),
+            batch_size: batch_size(settings),
+            column_count: ColumnCountSampler::new(settings),
+            row: GeneratedRow::default(),
+        }
+    }
+}
+
+impl CassandraStressRenderer for WriteRenderer {
+    fn render(&mut self, row_generator: &mut RowGenerator) -> Vec<RenderedStatement> {
+        let row_len = self.column_count.row_len();
+        // The same rows as generated by [WriteOperation::generate_partitions_into].
+        row_generator.generate_partitions_into(
+            self.batch_size,
+            &mut self.row.values,
+            &mut self.row.partition_starts,
+        );
+        self.row
+            .values
+            .chunks(row_len)
+            .map(|row| {
+                let written_row_len = self.column_count.written_row_len(row);
+                let mut values = render_values(&row[..written_row_len]);
+                values.resize(row_len, String::from("unset"));
+                RenderedStatement::new(self.statement.clone(), values)
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use crate::{
+        operation::{row_generator::RowGeneratorFactory, CassandraStressRenderer},
+        settings::{parse_cassandra_stress_args, CassandraStressParsingResult},
+    };
+
+    use super::WriteRenderer;
+
+    #[test]
+    fn render_visits_test() {
+        let args = "cassandra-stress write n=100 -col n=1 clustering=4 -insert visits=fixed(4) -pop seq=1..10";
+        let settings = match parse_cassandra_stress_args(args.split_ascii_whitespace()).unwrap() {
+            CassandraStressParsingResult::Workload(settings) => Arc::new(*settings),
+            CassandraStressParsingResult::SpecialCommand => panic!("expected a workload"),
+        };
+        let mut generator = RowGeneratorFactory::new(Arc::clone(&settings))
+            .with_visits()
+            .create();
+        let mut renderer = WriteRenderer::new(&settings);
+
+        // Each of the visits inserts one of the 4 rows of the partition.
+        for _ in 0..8 {
+            assert_eq!(1, renderer.render(&mut generator).len());
+        }
     }
 }
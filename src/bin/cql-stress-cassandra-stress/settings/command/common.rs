@@ -1,9 +1,6 @@
-use crate::settings::{
-    param::{
-        types::{Count, Parsable, UnitInterval},
-        ParamHandle, ParamsParser, SimpleParamHandle,
-    },
-    ParsePayload,
+use crate::settings::param::{
+    types::{Count, Parsable, UnitInterval},
+    ParamHandle, ParamsParser, SimpleParamHandle,
 };
 use anyhow::{Context, Result};
 use scylla::statement::{Consistency, SerialConsistency};
@@ -11,7 +8,7 @@ use std::{num::NonZeroU32, str::FromStr, time::Duration};
 use strum::IntoEnumIterator;
 use strum_macros::{AsRefStr, EnumIter, EnumString};
 
-use super::{Command, CommandParams};
+use super::Command;
 
 #[derive(Clone, Debug, PartialEq)]
 pub struct Uncertainty {
@@ -276,7 +273,7 @@ pub fn add_common_param_groups(
     let duration = parser.simple_param(
         "duration=",
         None,
-        "Time to run in (in seconds, minutes or hours)",
+        "Time to run in (in seconds, minutes, hours or days, e.g. 1h30m or 1.5d)",
         true,
     );
     let keysize = parser.simple_param("keysize=", Some("10"), "Key size in bytes", false);
@@ -288,6 +285,9 @@ pub fn add_common_param_groups(
     // Usage: read n=? [no-warmup] [truncate=?] [cl=?] [serial-cl=?] [keysize=?]
     //  OR
     // Usage: read duration=? [no-warmup] [truncate=?] [cl=?] [serial-cl=?] [keysize=?]
+    //
+    // cql-stress additionally accepts both n=? and duration=?, in which case
+    // the run stops at whichever limit is reached first.
 
     let groups: Vec<Vec<Box<dyn ParamHandle>>> = vec![
         vec![
@@ -316,6 +316,15 @@ pub fn add_common_param_groups(
             Box::new(serial_cl.clone()),
             Box::new(keysize.clone()),
         ],
+        vec![
+            Box::new(n.clone()),
+            Box::new(duration.clone()),
+            Box::new(no_warmup.clone()),
+            Box::new(truncate.clone()),
+            Box::new(cl.clone()),
+            Box::new(serial_cl.clone()),
+            Box::new(keysize.clone()),
+        ],
     ];
 
     (
@@ -335,6 +344,7 @@ pub fn add_common_param_groups(
     )
 }
 
+#[cfg(test)]
 fn prepare_parser(cmd: &str) -> (ParamsParser, CommonParamHandles) {
     let mut parser = ParamsParser::new(cmd);
 
@@ -377,27 +387,10 @@ pub fn parse_with_handles(handles: CommonParamHandles) -> CommonParams {
     }
 }
 
-pub fn parse_common_params(cmd: &Command, payload: &mut ParsePayload) -> Result<CommandParams> {
-    let args = payload.remove(cmd.show()).unwrap();
-    let (parser, handles) = prepare_parser(cmd.show());
-    parser.parse(args)?;
-    Ok(CommandParams {
-        common: parse_with_handles(handles),
-        counter: None,
-        mixed: None,
-        #[cfg(feature = "user-profile")]
-        user: None,
-    })
-}
-
-pub fn print_help_common(command_str: &str) {
-    let (parser, _) = prepare_parser(command_str);
-    parser.print_help();
-}
-
 #[cfg(test)]
 mod tests {
     use std::num::NonZeroU32;
+    use std::time::Duration;
 
     use scylla::statement::{Consistency, SerialConsistency};
 
@@ -466,6 +459,20 @@ mod tests {
         assert_eq!(NonZeroU32::new(10).unwrap(), params.keysize);
     }
 
+    #[test]
+    fn read_params_parser_with_operation_count_and_duration_test() {
+        let args = vec!["n=10m", "duration=10s"];
+        let (parser, handles) = prepare_parser(CMD.show());
+
+        assert!(parser.parse(args).is_ok());
+
+        let params = parse_with_handles(handles);
+
+        assert_eq!(None, params.uncertainty);
+        assert_eq!(Some(10_000_000), params.operation_count);
+        assert_eq!(Some(Duration::from_secs(10)), params.duration);
+    }
+
     #[test]
     fn read_params_groups_test() {
         // Here we declare uncertainty parameters (err< and n<) with operation count parameter (n=).
@@ -1,3 +1,5 @@
+use std::num::NonZeroUsize;
+
 use anyhow::Result;
 
 use crate::{
@@ -12,63 +14,90 @@ use super::{common::CommonParamHandles, Command, CommandParams};
 
 pub struct CounterParams {
     pub add_distribution: Box<dyn DistributionFactory>,
+    /// Number of the leading counter columns incremented by each update.
+    /// All of the columns are incremented if not specified.
+    pub incremented_columns: Option<NonZeroUsize>,
 }
 
 impl CounterParams {
     pub fn print_settings(&self) {
-        println!("  Counter Increment Distibution: {}", self.add_distribution)
+        println!(
+            "  Counter Increment Distribution: {}",
+            self.add_distribution
+        );
+        if let Some(incremented_columns) = self.incremented_columns {
+            println!("  Incremented Counter Columns: {}", incremented_columns);
+        }
     }
 
     pub fn parse(cmd: &Command, payload: &mut ParsePayload) -> Result<CommandParams> {
         let args = payload.remove(cmd.show()).unwrap();
-        let (parser, common_handles, add_distribution) = prepare_parser(cmd.show());
+        let (parser, common_handles, counter_handles) = prepare_parser(cmd.show());
         parser.parse(args)?;
         Ok(CommandParams {
             common: super::common::parse_with_handles(common_handles),
-            counter: Some(CounterParams {
-                add_distribution: add_distribution.get().unwrap(),
-            }),
+            counter: Some(parse_with_handles(counter_handles)),
             mixed: None,
+            write: None,
+            read: None,
+            read_modify_write: None,
+            scan: None,
             #[cfg(feature = "user-profile")]
             user: None,
         })
     }
 }
 
+pub struct CounterParamHandles {
+    add_distribution: SimpleParamHandle<Box<dyn DistributionFactory>>,
+    incremented_columns: SimpleParamHandle<NonZeroUsize>,
+}
+
+pub fn parse_with_handles(handles: CounterParamHandles) -> CounterParams {
+    CounterParams {
+        add_distribution: handles.add_distribution.get().unwrap(),
+        incremented_columns: handles.incremented_columns.get(),
+    }
+}
+
 pub struct CounterParamGroups {
     pub groups: Vec<Vec<Box<dyn ParamHandle>>>,
     pub common_handles: CommonParamHandles,
-    pub add_distribution_handle: SimpleParamHandle<Box<dyn DistributionFactory>>,
+    pub counter_handles: CounterParamHandles,
 }
 
 pub fn add_counter_param_groups(parser: &mut ParamsParser) -> CounterParamGroups {
     let (mut groups, common_handles) = super::common::add_common_param_groups(parser);
 
-    let add_distribution_handle = parser.distribution_param(
+    let add_distribution = parser.distribution_param(
         "add=",
         Some("fixed(1)"),
         "Distribution of value of counter increments",
         false,
     );
+    let incremented_columns = parser.simple_param(
+        "columns=",
+        None,
+        "Number of counter columns incremented by each update, starting from the first one. All of the columns (see -col n=) are incremented by default",
+        false,
+    );
 
     for group in groups.iter_mut() {
-        group.push(Box::new(add_distribution_handle.clone()));
+        group.push(Box::new(add_distribution.clone()));
+        group.push(Box::new(incremented_columns.clone()));
     }
 
     CounterParamGroups {
         groups,
         common_handles,
-        add_distribution_handle,
+        counter_handles: CounterParamHandles {
+            add_distribution,
+            incremented_columns,
+        },
     }
 }
 
-fn prepare_parser(
-    cmd: &str,
-) -> (
-    ParamsParser,
-    CommonParamHandles,
-    SimpleParamHandle<Box<dyn DistributionFactory>>,
-) {
+fn prepare_parser(cmd: &str) -> (ParamsParser, CommonParamHandles, CounterParamHandles) {
     let mut parser = ParamsParser::new(cmd);
 
     let mut counter_payload = add_counter_param_groups(&mut parser);
@@ -77,10 +106,13 @@ fn prepare_parser(
         parser.group_iter(group.iter().map(|e| e.as_ref()))
     }
 
+    parser.example("n=1000000 add=uniform(1..10)");
+    parser.example("n=1000000 columns=2 -col n=10");
+
     (
         parser,
         counter_payload.common_handles,
-        counter_payload.add_distribution_handle,
+        counter_payload.counter_handles,
     )
 }
 
@@ -88,3 +120,45 @@ pub fn print_help_counter(command_str: &str) {
     let (parser, _, _) = prepare_parser(command_str);
     parser.print_help();
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_with_handles, prepare_parser};
+    use crate::settings::command::Command;
+
+    const CMD: Command = Command::CounterWrite;
+
+    #[test]
+    fn counter_params_default_test() {
+        let (parser, _, handles) = prepare_parser(CMD.show());
+
+        assert!(parser.parse(vec!["n=1000"]).is_ok());
+        let params = parse_with_handles(handles);
+        assert_eq!("FIXED(1)", params.add_distribution.to_string());
+        assert!(params.incremented_columns.is_none());
+    }
+
+    #[test]
+    fn counter_params_add_test() {
+        let (parser, _, handles) = prepare_parser(CMD.show());
+
+        assert!(parser
+            .parse(vec!["n=1000", "add=uniform(1..10)", "columns=2"])
+            .is_ok());
+        let params = parse_with_handles(handles);
+        assert_eq!("UNIFORM(1..10)", params.add_distribution.to_string());
+        assert_eq!(2, params.incremented_columns.unwrap().get());
+
+        let distribution = params.add_distribution.create();
+        assert!((0..100).all(|_| (1..=10).contains(&distribution.next_i64())));
+    }
+
+    #[test]
+    fn counter_params_bad_add_test() {
+        let (parser, _, _) = prepare_parser(CMD.show());
+        assert!(parser.parse(vec!["add=foo(1)"]).is_err());
+
+        let (parser, _, _) = prepare_parser(CMD.show());
+        assert!(parser.parse(vec!["columns=0"]).is_err());
+    }
+}
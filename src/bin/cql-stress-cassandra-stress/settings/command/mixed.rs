@@ -1,4 +1,6 @@
 use std::collections::HashSet;
+#[cfg(feature = "user-profile")]
+use std::{collections::HashMap, sync::Arc};
 
 use crate::{
     java_generate::distribution::{enumerated::EnumeratedDistribution, DistributionFactory},
@@ -8,8 +10,17 @@ use crate::{
     },
 };
 use anyhow::{Context, Result};
+use scylla::statement::Consistency;
 
-use super::{common::CommonParamHandles, counter::CounterParams, Command, CommandParams};
+#[cfg(feature = "user-profile")]
+use super::user::{UserParams, UserProfile};
+use super::{
+    common::{CommonParamHandles, ConsistencyLevel},
+    counter::CounterParamHandles,
+    Command, CommandParams,
+};
+#[cfg(feature = "user-profile")]
+use crate::java_generate::distribution::fixed::FixedDistributionFactory;
 
 // Available subcommands for mixed command.
 #[derive(Copy, Clone, Hash, PartialEq, Eq)]
@@ -18,6 +29,9 @@ pub enum MixedSubcommand {
     Write,
     CounterRead,
     CounterWrite,
+    /// One of the queries of the profile given with `profile=`, or its `insert`.
+    #[cfg(feature = "user-profile")]
+    Query(&'static str),
 }
 
 impl std::fmt::Display for MixedSubcommand {
@@ -27,6 +41,8 @@ impl std::fmt::Display for MixedSubcommand {
             MixedSubcommand::Write => "write",
             MixedSubcommand::CounterRead => "counter_read",
             MixedSubcommand::CounterWrite => "counter_write",
+            #[cfg(feature = "user-profile")]
+            MixedSubcommand::Query(name) => name,
         };
         write!(f, "{}", s)
     }
@@ -40,6 +56,9 @@ pub type OperationRatio = EnumeratedDistribution<MixedSubcommand>;
 // - counter_read
 // - counter_write
 //
+// With the user-profile feature, the other names refer to the queries
+// of the profile given with `profile=`.
+//
 // A user can specify a ratio with which the suboperations will be sampled.
 // The syntax for this parameter is (op1=x, op2=y, op3=z, ...)
 // where op1..n are one of the 4 operations mentioned above, and x,y,z are floats.
@@ -66,12 +85,28 @@ impl OperationRatio {
             }
         };
 
-        let command = match Command::parse(cmd)? {
-            Command::Read => MixedSubcommand::Read,
-            Command::Write => MixedSubcommand::Write,
-            Command::CounterRead => MixedSubcommand::CounterRead,
-            Command::CounterWrite => MixedSubcommand::CounterWrite,
-            _ => anyhow::bail!("Invalid command for mixed workload: {}", cmd),
+        let command = match Command::parse(cmd) {
+            Ok(Command::Read) => MixedSubcommand::Read,
+            Ok(Command::Write) => MixedSubcommand::Write,
+            Ok(Command::CounterRead) => MixedSubcommand::CounterRead,
+            Ok(Command::CounterWrite) => MixedSubcommand::CounterWrite,
+            Ok(_) => anyhow::bail!("Invalid command for mixed workload: {}", cmd),
+            #[cfg(feature = "user-profile")]
+            Err(_) => {
+                anyhow::ensure!(
+                    !cmd.is_empty()
+                        && cmd
+                            .chars()
+                            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-'),
+                    "Invalid query name for mixed workload: {}",
+                    cmd
+                );
+                // The ratio is parsed once, so the names are leaked
+                // to keep the subcommands `Copy`.
+                MixedSubcommand::Query(Box::leak(cmd.to_owned().into_boxed_str()))
+            }
+            #[cfg(not(feature = "user-profile"))]
+            Err(err) => return Err(err.into()),
         };
         let weight = weight.parse::<f64>()?;
         Ok((command, weight))
@@ -106,42 +141,121 @@ impl OperationRatio {
 
         Self::new(weights)
     }
+
+    /// Returns the names of the queries of the profile in the ratio,
+    /// together with their weights.
+    #[cfg(feature = "user-profile")]
+    pub fn queries(&self) -> impl Iterator<Item = (&'static str, f64)> + '_ {
+        self.items()
+            .iter()
+            .filter_map(|(subcommand, weight)| match subcommand {
+                MixedSubcommand::Query(name) => Some((*name, *weight)),
+                _ => None,
+            })
+    }
 }
 
 pub struct MixedParamHandles {
     operation_ratio: SimpleParamHandle<OperationRatio>,
     clustering: SimpleParamHandle<Box<dyn DistributionFactory>>,
+    read_cl: SimpleParamHandle<ConsistencyLevel>,
+    write_cl: SimpleParamHandle<ConsistencyLevel>,
+    #[cfg(feature = "user-profile")]
+    profile: SimpleParamHandle<UserProfile>,
 }
 
 pub struct MixedParams {
     pub operation_ratio: OperationRatio,
     pub clustering: Box<dyn DistributionFactory>,
+    /// Consistency levels of the reads (read, counter_read) and the writes
+    /// (write, counter_write), if they differ from `cl=`.
+    pub read_consistency_level: Option<Consistency>,
+    pub write_consistency_level: Option<Consistency>,
+    /// The queries of the profile in the ratio, if any.
+    #[cfg(feature = "user-profile")]
+    pub user: Option<UserParams>,
 }
 
 impl MixedParams {
     pub fn print_settings(&self) {
         println!("Command ratios: {}", self.operation_ratio);
         println!("Command clustering distribution: {}", self.clustering);
+        if let Some(cl) = self.read_consistency_level {
+            println!("Read consistency level: {}", cl);
+        }
+        if let Some(cl) = self.write_consistency_level {
+            println!("Write consistency level: {}", cl);
+        }
+        #[cfg(feature = "user-profile")]
+        if let Some(user) = &self.user {
+            println!("Profile: {}.{}", user.keyspace, user.table);
+        }
     }
 
     pub fn parse(cmd: &Command, payload: &mut ParsePayload) -> Result<CommandParams> {
         let args = payload.remove(cmd.show()).unwrap();
-        let (parser, common_handles, counter_add_distribution_handle, mixed_handles) =
-            prepare_parser(cmd.show());
+        let (parser, common_handles, counter_handles, mixed_handles) = prepare_parser(cmd.show());
         parser.parse(args)?;
+        let operation_ratio = mixed_handles.operation_ratio.get().unwrap();
+        let clustering = mixed_handles.clustering.get().unwrap();
+        let read_consistency_level = mixed_handles.read_cl.get();
+        let write_consistency_level = mixed_handles.write_cl.get();
+        #[cfg(feature = "user-profile")]
+        let user = Self::parse_user(&operation_ratio, mixed_handles.profile.get())?;
         Ok(CommandParams {
             common: super::common::parse_with_handles(common_handles),
-            counter: Some(CounterParams {
-                add_distribution: counter_add_distribution_handle.get().unwrap(),
-            }),
+            counter: Some(super::counter::parse_with_handles(counter_handles)),
             mixed: Some(MixedParams {
-                operation_ratio: mixed_handles.operation_ratio.get().unwrap(),
-                clustering: mixed_handles.clustering.get().unwrap(),
+                operation_ratio,
+                clustering,
+                read_consistency_level,
+                write_consistency_level,
+                #[cfg(feature = "user-profile")]
+                user,
             }),
+            write: None,
+            read: None,
+            read_modify_write: None,
+            scan: None,
             #[cfg(feature = "user-profile")]
             user: None,
         })
     }
+
+    // Picks the queries of the ratio from the profile.
+    #[cfg(feature = "user-profile")]
+    fn parse_user(
+        operation_ratio: &OperationRatio,
+        profile: Option<UserProfile>,
+    ) -> Result<Option<UserParams>> {
+        let queries_ratio = operation_ratio
+            .queries()
+            .map(|(name, weight)| (name.to_owned(), weight))
+            .collect::<HashMap<_, _>>();
+        match profile {
+            Some(profile) => {
+                anyhow::ensure!(
+                    !queries_ratio.is_empty(),
+                    "profile= requires at least one of its queries in ratio"
+                );
+                // The operations are sampled by the clustering of the mixed command.
+                let clustering = Arc::new(FixedDistributionFactory(1));
+                Ok(Some(UserParams::from_profile(
+                    profile,
+                    queries_ratio,
+                    clustering,
+                )?))
+            }
+            None => {
+                anyhow::ensure!(
+                    queries_ratio.is_empty(),
+                    "Unknown commands in ratio: {:?}. The queries of a profile require profile=",
+                    queries_ratio.keys().collect::<Vec<_>>()
+                );
+                Ok(None)
+            }
+        }
+    }
 }
 
 fn prepare_parser(
@@ -149,7 +263,7 @@ fn prepare_parser(
 ) -> (
     ParamsParser,
     CommonParamHandles,
-    SimpleParamHandle<Box<dyn DistributionFactory>>,
+    CounterParamHandles,
     MixedParamHandles,
 ) {
     let mut parser = ParamsParser::new(cmd);
@@ -163,20 +277,52 @@ fn prepare_parser(
         "Distribution clustering runs of operations of the same kind",
         false,
     );
+    let read_cl = parser.simple_param(
+        "read-cl=",
+        None,
+        "Consistency level of the reads (read, counter_read), if different from cl=",
+        false,
+    );
+    let write_cl = parser.simple_param(
+        "write-cl=",
+        None,
+        "Consistency level of the writes (write, counter_write), if different from cl=",
+        false,
+    );
+    #[cfg(feature = "user-profile")]
+    let profile = parser.simple_param(
+        "profile=",
+        None,
+        "Path to a yaml cql3 profile, whose queries (and insert) can be given in ratio along with the commands, e.g. ratio(read=1,write=1,query1=2). The keyspace of the profile must be the one of -schema",
+        false,
+    );
 
     for group in counter_payload.groups.iter_mut() {
         group.push(Box::new(operation_ratio.clone()));
         group.push(Box::new(clustering.clone()));
+        group.push(Box::new(read_cl.clone()));
+        group.push(Box::new(write_cl.clone()));
+        #[cfg(feature = "user-profile")]
+        group.push(Box::new(profile.clone()));
         parser.group_iter(group.iter().map(|e| e.as_ref()))
     }
 
+    parser.example("n=1000000 ratio(read=2,write=1) clustering=fixed(1)");
+    parser.example("n=1000000 cl=QUORUM write-cl=ONE");
+    #[cfg(feature = "user-profile")]
+    parser.example("n=1000000 ratio(read=2,write=1,query1=1) profile=profile.yaml");
+
     (
         parser,
         counter_payload.common_handles,
-        counter_payload.add_distribution_handle,
+        counter_payload.counter_handles,
         MixedParamHandles {
             operation_ratio,
             clustering,
+            read_cl,
+            write_cl,
+            #[cfg(feature = "user-profile")]
+            profile,
         },
     )
 }
@@ -185,3 +331,127 @@ pub fn print_help_mixed(command_str: &str) {
     let (parser, _, _, _) = prepare_parser(command_str);
     parser.print_help();
 }
+
+#[cfg(test)]
+mod tests {
+    #[cfg(feature = "user-profile")]
+    use std::path::PathBuf;
+
+    #[cfg(feature = "user-profile")]
+    use super::MixedParams;
+    use scylla::statement::Consistency;
+
+    use super::{prepare_parser, MixedSubcommand};
+    use crate::settings::command::Command;
+
+    const CMD: Command = Command::Mixed;
+
+    #[test]
+    fn mixed_params_default_test() {
+        let (parser, _, _, handles) = prepare_parser(CMD.show());
+
+        assert!(parser.parse(vec!["n=1000"]).is_ok());
+        assert_eq!(
+            "GAUSSIAN(1..10,mean=5.5,stdev=1.5)",
+            handles.clustering.get().unwrap().to_string()
+        );
+        let ratio = handles.operation_ratio.get().unwrap();
+        assert!(ratio.contains(&MixedSubcommand::Read));
+        assert!(ratio.contains(&MixedSubcommand::Write));
+    }
+
+    #[test]
+    fn mixed_params_clustering_test() {
+        let (parser, _, _, handles) = prepare_parser(CMD.show());
+
+        assert!(parser
+            .parse(vec![
+                "n=1000",
+                "ratio(read=2,counter_write=1)",
+                "clustering=fixed(100)"
+            ])
+            .is_ok());
+        assert_eq!("FIXED(100)", handles.clustering.get().unwrap().to_string());
+        let ratio = handles.operation_ratio.get().unwrap();
+        assert!(ratio.contains(&MixedSubcommand::CounterWrite));
+        assert!(!ratio.contains(&MixedSubcommand::Write));
+    }
+
+    #[test]
+    fn mixed_params_bad_clustering_test() {
+        let (parser, _, _, _) = prepare_parser(CMD.show());
+        assert!(parser.parse(vec!["clustering=foo(1)"]).is_err());
+    }
+
+    #[test]
+    fn mixed_params_consistency_levels_test() {
+        let (parser, _, _, handles) = prepare_parser(CMD.show());
+        assert!(parser.parse(vec!["n=1000"]).is_ok());
+        assert_eq!(None, handles.read_cl.get());
+        assert_eq!(None, handles.write_cl.get());
+
+        let (parser, _, _, handles) = prepare_parser(CMD.show());
+        assert!(parser
+            .parse(vec!["n=1000", "cl=QUORUM", "write-cl=ONE"])
+            .is_ok());
+        assert_eq!(None, handles.read_cl.get());
+        assert_eq!(Some(Consistency::One), handles.write_cl.get());
+
+        let (parser, _, _, _) = prepare_parser(CMD.show());
+        assert!(parser.parse(vec!["n=1000", "read-cl=FOO"]).is_err());
+    }
+
+    #[cfg(feature = "user-profile")]
+    fn build_file_path(filename: &str) -> String {
+        let mut d = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        d.push("src/bin/cql-stress-cassandra-stress/settings/command/test_user_profile_yamls");
+        d.push(filename);
+        d.into_os_string().into_string().unwrap()
+    }
+
+    #[cfg(feature = "user-profile")]
+    #[test]
+    fn mixed_params_profile_queries_test() {
+        let (parser, _, _, handles) = prepare_parser(CMD.show());
+        let profile = format!("profile={}", build_file_path("full_profile.yaml"));
+
+        assert!(parser
+            .parse(vec!["ratio(write=1,ins=2,insert=1)", &profile])
+            .is_ok());
+        let ratio = handles.operation_ratio.get().unwrap();
+        assert!(ratio.contains(&MixedSubcommand::Write));
+        assert!(ratio.contains(&MixedSubcommand::Query("ins")));
+        assert!(!ratio.contains(&MixedSubcommand::Read));
+
+        let user = MixedParams::parse_user(&ratio, handles.profile.get())
+            .unwrap()
+            .unwrap();
+        assert_eq!("keyspace2", user.keyspace);
+        assert!(user.queries_payload.contains_key("ins"));
+        assert_eq!(Some(1.0), user.insert_operation_weight);
+    }
+
+    #[cfg(feature = "user-profile")]
+    #[test]
+    fn mixed_params_bad_profile_queries_test() {
+        // Queries without a profile.
+        let (parser, _, _, handles) = prepare_parser(CMD.show());
+        assert!(parser.parse(vec!["ratio(read=1,foo=1)"]).is_ok());
+        let ratio = handles.operation_ratio.get().unwrap();
+        assert!(MixedParams::parse_user(&ratio, handles.profile.get()).is_err());
+
+        // A query missing from the profile.
+        let (parser, _, _, handles) = prepare_parser(CMD.show());
+        let profile = format!("profile={}", build_file_path("full_profile.yaml"));
+        assert!(parser.parse(vec!["ratio(read=1,foo=1)", &profile]).is_ok());
+        let ratio = handles.operation_ratio.get().unwrap();
+        assert!(MixedParams::parse_user(&ratio, handles.profile.get()).is_err());
+
+        // A profile without any of its queries.
+        let (parser, _, _, handles) = prepare_parser(CMD.show());
+        let profile = format!("profile={}", build_file_path("full_profile.yaml"));
+        assert!(parser.parse(vec!["ratio(read=1)", &profile]).is_ok());
+        let ratio = handles.operation_ratio.get().unwrap();
+        assert!(MixedParams::parse_user(&ratio, handles.profile.get()).is_err());
+    }
+}
@@ -8,23 +8,36 @@ use strum_macros::EnumIter;
 use strum_macros::EnumString;
 
 use anyhow::Result;
+use scylla::statement::Consistency;
 
 mod common;
 mod counter;
 mod help;
 mod mixed;
+mod print;
+mod read;
+mod read_modify_write;
+mod scan;
 #[cfg(feature = "user-profile")]
 mod user;
+mod write;
 
-use self::common::{parse_common_params, print_help_common};
 use self::counter::print_help_counter;
 use self::counter::CounterParams;
 use self::mixed::print_help_mixed;
 use self::mixed::MixedParams;
+use self::print::print_help_print;
+use self::read::print_help_read;
+use self::read::ReadParams;
+pub use self::read::{ClusteringOrder, ClusteringRestriction};
+use self::read_modify_write::print_help_read_modify_write;
+use self::read_modify_write::ReadModifyWriteParams;
+use self::scan::print_help_scan;
+use self::scan::ScanParams;
 #[cfg(feature = "user-profile")]
-use self::user::UserParams;
-#[cfg(feature = "user-profile")]
-pub use self::user::{OpWeight, PREDEFINED_INSERT_OPERATION};
+pub use self::user::{ColumnSpec, OpWeight, UserParams, PREDEFINED_INSERT_OPERATION};
+use self::write::print_help_write;
+use self::write::WriteParams;
 pub use help::print_help;
 
 use super::ParsePayload;
@@ -32,17 +45,22 @@ use common::CommonParams;
 use help::parse_help_command;
 pub use mixed::MixedSubcommand;
 pub use mixed::OperationRatio;
+use print::parse_print_command;
 
 #[derive(Clone, Debug, PartialEq, Eq, EnumIter, AsRefStr, EnumString)]
 #[strum(serialize_all = "snake_case")]
 #[strum(ascii_case_insensitive)]
 pub enum Command {
     Help,
+    Print,
     Write,
     Read,
     CounterWrite,
     CounterRead,
+    IndexRead,
     Mixed,
+    ReadModifyWrite,
+    Scan,
     #[cfg(feature = "user-profile")]
     User,
 }
@@ -54,17 +72,24 @@ impl Command {
 
     fn parse_params(&self, payload: &mut ParsePayload) -> Result<Option<CommandParams>> {
         match self {
-            Command::Read | Command::Write | Command::CounterRead => {
-                Ok(Some(parse_common_params(self, payload)?))
+            Command::Read | Command::CounterRead | Command::IndexRead => {
+                Ok(Some(ReadParams::parse(self, payload)?))
             }
+            Command::Write => Ok(Some(WriteParams::parse(self, payload)?)),
             Command::CounterWrite => Ok(Some(CounterParams::parse(self, payload)?)),
             Command::Mixed => Ok(Some(MixedParams::parse(self, payload)?)),
+            Command::ReadModifyWrite => Ok(Some(ReadModifyWriteParams::parse(self, payload)?)),
+            Command::Scan => Ok(Some(ScanParams::parse(self, payload)?)),
             #[cfg(feature = "user-profile")]
             Command::User => Ok(Some(UserParams::parse(self, payload)?)),
             Command::Help => {
                 parse_help_command(payload)?;
                 Ok(None)
             }
+            Command::Print => {
+                parse_print_command(payload)?;
+                Ok(None)
+            }
         }
     }
 
@@ -78,10 +103,14 @@ impl Command {
             Command::Write => "Multiple concurrent writes against the cluster.",
             Command::CounterWrite => "Multiple concurrent updates of counters.",
             Command::CounterRead => "Multiple concurrent reads of counters. The cluster must first be populated by a counterwrite test.",
+            Command::IndexRead => "Multiple concurrent reads via the secondary index created with -schema index= - the cluster must first be populated by a write test.",
             Command::Mixed => "Interleaving of any basic commands, with configurable ratio and distribution - the cluster must first be populated by a write test.",
+            Command::ReadModifyWrite => "Multiple concurrent reads, each followed by writing back the modified row - the cluster must first be populated by a write test.",
+            Command::Scan => "Multiple concurrent scans of the token ranges of the table, optionally filtered by the server (ALLOW FILTERING) - the cluster must first be populated by a write test.",
             #[cfg(feature = "user-profile")]
             Command::User => "Interleaving of user provided queries, with configurable ratio and distribution - the cluster must first be populated by a write test.",
             Command::Help => "Print help for a command or option",
+            Command::Print => "Print the statistics of the values sampled from a distribution",
         };
 
         println!("{:<20} : {}", self.show(), desc);
@@ -92,16 +121,27 @@ impl Command {
         for cmd in Self::iter() {
             cmd.print_short_description();
         }
+        #[cfg(not(feature = "user-profile"))]
+        println!(
+            "{:<20} : {}",
+            "user", "Not available - the tool was built without the user-profile feature."
+        );
     }
 
     fn print_help(&self) {
         match self {
-            Command::Read | Command::Write | Command::CounterRead => print_help_common(self.show()),
+            Command::Read | Command::CounterRead | Command::IndexRead => {
+                print_help_read(self.show())
+            }
+            Command::Write => print_help_write(self.show()),
             Command::CounterWrite => print_help_counter(self.show()),
             Command::Mixed => print_help_mixed(self.show()),
+            Command::ReadModifyWrite => print_help_read_modify_write(self.show()),
+            Command::Scan => print_help_scan(self.show()),
             #[cfg(feature = "user-profile")]
             Command::User => UserParams::print_help(self.show()),
             Command::Help => help::print_help(),
+            Command::Print => print_help_print(),
         }
     }
 }
@@ -111,11 +151,31 @@ pub struct CommandParams {
     pub common: CommonParams,
     pub counter: Option<CounterParams>,
     pub mixed: Option<MixedParams>,
+    pub write: Option<WriteParams>,
+    pub read: Option<ReadParams>,
+    pub read_modify_write: Option<ReadModifyWriteParams>,
+    pub scan: Option<ScanParams>,
     #[cfg(feature = "user-profile")]
     pub user: Option<UserParams>,
 }
 
 impl CommandParams {
+    /// Consistency level of the reads, overridden by `read-cl=` of the mixed command.
+    pub fn read_consistency_level(&self) -> Consistency {
+        self.mixed
+            .as_ref()
+            .and_then(|mixed| mixed.read_consistency_level)
+            .unwrap_or(self.common.consistency_level)
+    }
+
+    /// Consistency level of the writes, overridden by `write-cl=` of the mixed command.
+    pub fn write_consistency_level(&self) -> Consistency {
+        self.mixed
+            .as_ref()
+            .and_then(|mixed| mixed.write_consistency_level)
+            .unwrap_or(self.common.consistency_level)
+    }
+
     pub fn print_settings(&self, cmd: &Command) {
         self.common.print_settings(cmd);
         if let Some(counter) = &self.counter {
@@ -124,6 +184,18 @@ impl CommandParams {
         if let Some(mixed) = &self.mixed {
             mixed.print_settings()
         }
+        if let Some(write) = &self.write {
+            write.print_settings()
+        }
+        if let Some(read) = &self.read {
+            read.print_settings()
+        }
+        if let Some(read_modify_write) = &self.read_modify_write {
+            read_modify_write.print_settings()
+        }
+        if let Some(scan) = &self.scan {
+            scan.print_settings()
+        }
     }
 }
 
@@ -131,6 +203,11 @@ pub fn parse_command(
     command_str: &str,
     cl_args: &mut ParsePayload,
 ) -> Result<(Command, Option<CommandParams>)> {
+    #[cfg(not(feature = "user-profile"))]
+    anyhow::ensure!(
+        !command_str.eq_ignore_ascii_case("user"),
+        "The user command requires the user-profile crate feature, which is disabled in this build (see --list-features)"
+    );
     let command = Command::parse(command_str).context("No command specified")?;
     let params = command.parse_params(cl_args)?;
     Ok((command, params))
@@ -0,0 +1,134 @@
+use anyhow::{Context, Result};
+
+use crate::{
+    java_generate::distribution::{Distribution, DistributionFactory},
+    settings::param::{ParamsParser, SimpleParamHandle},
+};
+
+use super::ParsePayload;
+
+/// Number of values sampled from the previewed distribution.
+const SAMPLE_COUNT: usize = 100_000;
+
+/// Percentiles shown in the distribution preview.
+const PERCENTILES: &[f64] = &[
+    0.0, 1.0, 5.0, 10.0, 25.0, 50.0, 75.0, 90.0, 95.0, 99.0, 99.9, 100.0,
+];
+
+/// Statistics of the values sampled from a distribution.
+struct DistributionSummary {
+    // Sorted in ascending order.
+    samples: Vec<i64>,
+    mean: f64,
+}
+
+impl DistributionSummary {
+    fn sample(distribution: &dyn Distribution, count: usize) -> Self {
+        let mut samples: Vec<i64> = (0..count).map(|_| distribution.next_i64()).collect();
+        samples.sort_unstable();
+        let mean = samples.iter().map(|&v| v as f64).sum::<f64>() / count as f64;
+        Self { samples, mean }
+    }
+
+    fn min(&self) -> i64 {
+        self.samples[0]
+    }
+
+    fn max(&self) -> i64 {
+        self.samples[self.samples.len() - 1]
+    }
+
+    /// Returns the sampled value at the given percentile (0..=100).
+    fn percentile(&self, percentile: f64) -> i64 {
+        let rank = (percentile / 100.0 * (self.samples.len() - 1) as f64).round();
+        self.samples[rank as usize]
+    }
+
+    fn print(&self, factory: &dyn DistributionFactory) {
+        println!("Distribution: {}", factory);
+        println!("  Samples: {}", self.samples.len());
+        println!("  Min: {}", self.min());
+        println!("  Max: {}", self.max());
+        println!("  Mean: {:.3}", self.mean);
+        println!("  Percentiles:");
+        for &percentile in PERCENTILES {
+            println!("    {:>5}%: {}", percentile, self.percentile(percentile));
+        }
+    }
+}
+
+pub fn parse_print_command(payload: &mut ParsePayload) -> Result<()> {
+    let params = payload.remove("print").unwrap_or_default();
+    anyhow::ensure!(payload.is_empty(), "print command doesn't accept options");
+
+    let (parser, dist) = prepare_parser();
+    parser
+        .parse(params)
+        .context("Failed to parse print command parameters")?;
+
+    // The parameter is required, so it's present after successful parsing.
+    let factory = dist.get().unwrap();
+    DistributionSummary::sample(factory.create().as_ref(), SAMPLE_COUNT).print(factory.as_ref());
+    Ok(())
+}
+
+fn prepare_parser() -> (
+    ParamsParser,
+    SimpleParamHandle<Box<dyn DistributionFactory>>,
+) {
+    let mut parser = ParamsParser::new("print");
+
+    let dist = parser.distribution_param(
+        "dist=",
+        None,
+        "Distribution to preview, e.g. dist=gaussian(1..10). Prints the statistics of the values sampled from it",
+        true,
+    );
+
+    parser.group(&[&dist]);
+
+    (parser, dist)
+}
+
+pub fn print_help_print() {
+    let (parser, _) = prepare_parser();
+    parser.print_help();
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        java_generate::distribution::DistributionFactory, settings::param::types::Parsable,
+    };
+
+    use super::{prepare_parser, DistributionSummary};
+
+    #[test]
+    fn print_params_test() {
+        let (parser, handle) = prepare_parser();
+
+        assert!(parser.parse(vec!["dist=gaussian(1..10)"]).is_ok());
+        assert!(handle.get().is_some());
+    }
+
+    #[test]
+    fn print_params_missing_dist_test() {
+        let (parser, _) = prepare_parser();
+
+        assert!(parser.parse(vec![]).is_err());
+    }
+
+    #[test]
+    fn distribution_summary_test() {
+        let factory = <Box<dyn DistributionFactory>>::parse("seq(1..100)").unwrap();
+        let summary = DistributionSummary::sample(factory.create().as_ref(), 100);
+
+        assert_eq!(1, summary.min());
+        assert_eq!(100, summary.max());
+        assert_eq!(50.5, summary.mean);
+        assert_eq!(1, summary.percentile(0.0));
+        assert_eq!(51, summary.percentile(50.0));
+        assert_eq!(99, summary.percentile(99.0));
+        assert_eq!(100, summary.percentile(100.0));
+    }
+}
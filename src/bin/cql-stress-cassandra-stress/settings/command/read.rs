@@ -0,0 +1,284 @@
+use std::num::NonZeroUsize;
+
+use anyhow::Result;
+use cql_stress::row_count::ExpectedRowCount;
+
+use crate::settings::{
+    param::{types::Parsable, ParamHandle, ParamsParser, SimpleParamHandle},
+    ParsePayload,
+};
+
+use super::{common::CommonParamHandles, Command, CommandParams};
+
+pub struct ReadParams {
+    pub expected_rows: Option<ExpectedRowCount>,
+    /// Restricts the read clustering rows of the partition (see `-col clustering=`).
+    pub restriction: Option<ClusteringRestriction>,
+    pub order: Option<ClusteringOrder>,
+    pub bypass_cache: bool,
+}
+
+/// Which clustering rows of the partition are read.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ClusteringRestriction {
+    /// A range of consecutive rows: `ck >= ? AND ck < ?`.
+    Slice(NonZeroUsize),
+    /// Rows at distinct clustering keys: `ck IN (?, ...)`.
+    In(NonZeroUsize),
+}
+
+impl std::fmt::Display for ClusteringRestriction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Slice(rows) => write!(f, "range of {} rows", rows),
+            Self::In(rows) => write!(f, "IN of {} rows", rows),
+        }
+    }
+}
+
+/// The order of the read clustering rows: `ORDER BY ck ASC|DESC`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ClusteringOrder {
+    Asc,
+    Desc,
+}
+
+impl std::fmt::Display for ClusteringOrder {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            Self::Asc => "ASC",
+            Self::Desc => "DESC",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+impl Parsable for ClusteringOrder {
+    type Parsed = ClusteringOrder;
+
+    fn parse(s: &str) -> Result<Self::Parsed> {
+        match s.to_lowercase().as_str() {
+            "asc" => Ok(Self::Asc),
+            "desc" => Ok(Self::Desc),
+            _ => anyhow::bail!("Invalid order: {}. Must be one of: asc|desc", s),
+        }
+    }
+
+    fn value_pattern() -> Option<&'static str> {
+        Some("asc|desc")
+    }
+}
+
+impl Parsable for ExpectedRowCount {
+    type Parsed = ExpectedRowCount;
+
+    fn parse(s: &str) -> Result<Self::Parsed> {
+        ExpectedRowCount::parse(s)
+    }
+}
+
+impl ReadParams {
+    pub fn print_settings(&self) {
+        if let Some(expected_rows) = &self.expected_rows {
+            println!("  Expected Rows: {}", expected_rows);
+        }
+        if let Some(restriction) = &self.restriction {
+            println!("  Clustering Restriction: {}", restriction);
+        }
+        if let Some(order) = &self.order {
+            println!("  Clustering Order: {}", order);
+        }
+        println!("  Bypass Cache: {}", self.bypass_cache);
+    }
+
+    pub fn parse(cmd: &Command, payload: &mut ParsePayload) -> Result<CommandParams> {
+        let args = payload.remove(cmd.show()).unwrap();
+        let (parser, common_handles, read_handles) = prepare_parser(cmd.show());
+        parser.parse(args)?;
+        let expected_rows = read_handles.rows.get();
+        let restriction = match (read_handles.slice.get(), read_handles.in_rows.get()) {
+            (Some(_), Some(_)) => anyhow::bail!("slice= cannot be combined with in="),
+            (Some(rows), None) => Some(ClusteringRestriction::Slice(rows)),
+            (None, Some(rows)) => Some(ClusteringRestriction::In(rows)),
+            (None, None) => None,
+        };
+        let order = read_handles.order.get();
+        let bypass_cache = read_handles.bypass_cache.get().is_some();
+        anyhow::ensure!(
+            *cmd != Command::IndexRead
+                || (restriction.is_none() && order.is_none() && !bypass_cache),
+            "slice=, in=, order= and bypass-cache are not supported by the index_read command"
+        );
+        Ok(CommandParams {
+            common: super::common::parse_with_handles(common_handles),
+            counter: None,
+            mixed: None,
+            write: None,
+            read: Some(ReadParams {
+                expected_rows,
+                restriction,
+                order,
+                bypass_cache,
+            }),
+            read_modify_write: None,
+            scan: None,
+            #[cfg(feature = "user-profile")]
+            user: None,
+        })
+    }
+}
+
+pub struct ReadParamHandles {
+    rows: SimpleParamHandle<ExpectedRowCount>,
+    slice: SimpleParamHandle<NonZeroUsize>,
+    in_rows: SimpleParamHandle<NonZeroUsize>,
+    order: SimpleParamHandle<ClusteringOrder>,
+    bypass_cache: SimpleParamHandle<bool>,
+}
+
+pub struct ReadParamGroups {
+    pub groups: Vec<Vec<Box<dyn ParamHandle>>>,
+    pub common_handles: CommonParamHandles,
+    pub read_handles: ReadParamHandles,
+}
+
+pub fn add_read_param_groups(parser: &mut ParamsParser) -> ReadParamGroups {
+    let (mut groups, common_handles) = super::common::add_common_param_groups(parser);
+
+    let rows = parser.simple_param(
+        "rows=",
+        None,
+        "Number of rows each read is expected to return, either exact (e.g. rows=1) or a range (e.g. rows=1..10). Other results are counted as data loss errors",
+        false,
+    );
+
+    let slice = parser.simple_param(
+        "slice=",
+        None,
+        "Read a range of this many consecutive clustering rows of each partition (ck >= ? AND ck < ?). Requires -col clustering=",
+        false,
+    );
+    let in_rows = parser.simple_param(
+        "in=",
+        None,
+        "Read this many clustering rows of each partition at distinct clustering keys (ck IN (?, ...)). Requires -col clustering=",
+        false,
+    );
+    let order = parser.simple_param(
+        "order=",
+        None,
+        "Read the clustering rows in the given order (ORDER BY ck ASC|DESC). Requires -col clustering=",
+        false,
+    );
+    let bypass_cache = parser.simple_param(
+        "bypass-cache",
+        None,
+        "Execute the reads with BYPASS CACHE",
+        false,
+    );
+
+    for group in groups.iter_mut() {
+        group.push(Box::new(rows.clone()));
+        group.push(Box::new(slice.clone()));
+        group.push(Box::new(in_rows.clone()));
+        group.push(Box::new(order.clone()));
+        group.push(Box::new(bypass_cache.clone()));
+    }
+
+    ReadParamGroups {
+        groups,
+        common_handles,
+        read_handles: ReadParamHandles {
+            rows,
+            slice,
+            in_rows,
+            order,
+            bypass_cache,
+        },
+    }
+}
+
+fn prepare_parser(cmd: &str) -> (ParamsParser, CommonParamHandles, ReadParamHandles) {
+    let mut parser = ParamsParser::new(cmd);
+
+    let mut read_payload = add_read_param_groups(&mut parser);
+
+    for group in read_payload.groups.iter_mut() {
+        parser.group_iter(group.iter().map(|e| e.as_ref()))
+    }
+
+    parser.example("n=1000000 cl=quorum");
+    parser.example("duration=10m no-warmup rows=1");
+    parser.example("n=1000000 slice=10 order=desc bypass-cache");
+
+    (
+        parser,
+        read_payload.common_handles,
+        read_payload.read_handles,
+    )
+}
+
+pub fn print_help_read(command_str: &str) {
+    let (parser, _, _) = prepare_parser(command_str);
+    parser.print_help();
+}
+
+#[cfg(test)]
+mod tests {
+    use cql_stress::row_count::ExpectedRowCount;
+
+    use super::{prepare_parser, ClusteringOrder};
+    use crate::settings::command::Command;
+
+    const CMD: Command = Command::IndexRead;
+
+    #[test]
+    fn read_params_rows_test() {
+        let args = vec!["n=10m", "rows=1..10"];
+        let (parser, _, handles) = prepare_parser(CMD.show());
+
+        assert!(parser.parse(args).is_ok());
+        assert_eq!(
+            Some(ExpectedRowCount::parse("1..10").unwrap()),
+            handles.rows.get()
+        );
+    }
+
+    #[test]
+    fn read_params_no_rows_test() {
+        let args = vec!["n=10m"];
+        let (parser, _, handles) = prepare_parser(CMD.show());
+
+        assert!(parser.parse(args).is_ok());
+        assert!(handles.rows.get().is_none());
+    }
+
+    #[test]
+    fn read_params_shape_test() {
+        let args = vec!["n=10m", "slice=10", "order=DESC", "bypass-cache"];
+        let (parser, _, handles) = prepare_parser(CMD.show());
+
+        assert!(parser.parse(args).is_ok());
+        assert_eq!(10, handles.slice.get().unwrap().get());
+        assert!(handles.in_rows.get().is_none());
+        assert_eq!(Some(ClusteringOrder::Desc), handles.order.get());
+        assert!(handles.bypass_cache.get().is_some());
+    }
+
+    #[test]
+    fn read_params_bad_shape_test() {
+        let (parser, _, _) = prepare_parser(CMD.show());
+        assert!(parser.parse(vec!["slice=0"]).is_err());
+
+        let (parser, _, _) = prepare_parser(CMD.show());
+        assert!(parser.parse(vec!["order=random"]).is_err());
+    }
+
+    #[test]
+    fn read_params_bad_rows_test() {
+        let args = vec!["n=10m", "rows=10..1"];
+        let (parser, _, _) = prepare_parser(CMD.show());
+
+        assert!(parser.parse(args).is_err());
+    }
+}
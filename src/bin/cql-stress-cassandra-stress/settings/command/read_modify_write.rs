@@ -0,0 +1,130 @@
+use anyhow::Result;
+
+use crate::settings::{
+    param::{ParamHandle, ParamsParser, SimpleParamHandle},
+    ParsePayload,
+};
+
+use super::{common::CommonParamHandles, Command, CommandParams};
+
+pub struct ReadModifyWriteParams {
+    pub lwt: bool,
+}
+
+impl ReadModifyWriteParams {
+    pub fn print_settings(&self) {
+        println!("  Lightweight Transactions: {}", self.lwt);
+    }
+
+    pub fn parse(cmd: &Command, payload: &mut ParsePayload) -> Result<CommandParams> {
+        let args = payload.remove(cmd.show()).unwrap();
+        let (parser, common_handles, read_modify_write_handles) = prepare_parser(cmd.show());
+        parser.parse(args)?;
+        let lwt = read_modify_write_handles.lwt.get().is_some();
+        Ok(CommandParams {
+            common: super::common::parse_with_handles(common_handles),
+            counter: None,
+            mixed: None,
+            write: None,
+            read: None,
+            read_modify_write: Some(ReadModifyWriteParams { lwt }),
+            scan: None,
+            #[cfg(feature = "user-profile")]
+            user: None,
+        })
+    }
+}
+
+pub struct ReadModifyWriteParamHandles {
+    lwt: SimpleParamHandle<bool>,
+}
+
+pub struct ReadModifyWriteParamGroups {
+    pub groups: Vec<Vec<Box<dyn ParamHandle>>>,
+    pub common_handles: CommonParamHandles,
+    pub read_modify_write_handles: ReadModifyWriteParamHandles,
+}
+
+pub fn add_read_modify_write_param_groups(parser: &mut ParamsParser) -> ReadModifyWriteParamGroups {
+    let (mut groups, common_handles) = super::common::add_common_param_groups(parser);
+
+    let lwt = parser.simple_param(
+        "lwt",
+        None,
+        "Write back the modified row only if it wasn't modified since it was read (UPDATE ... IF), using lightweight transactions. Honors serial-cl=",
+        false,
+    );
+
+    for group in groups.iter_mut() {
+        group.push(Box::new(lwt.clone()));
+    }
+
+    ReadModifyWriteParamGroups {
+        groups,
+        common_handles,
+        read_modify_write_handles: ReadModifyWriteParamHandles { lwt },
+    }
+}
+
+fn prepare_parser(
+    cmd: &str,
+) -> (
+    ParamsParser,
+    CommonParamHandles,
+    ReadModifyWriteParamHandles,
+) {
+    let mut parser = ParamsParser::new(cmd);
+
+    let mut read_modify_write_payload = add_read_modify_write_param_groups(&mut parser);
+
+    for group in read_modify_write_payload.groups.iter_mut() {
+        parser.group_iter(group.iter().map(|e| e.as_ref()))
+    }
+
+    parser.example("n=1000000 lwt");
+
+    (
+        parser,
+        read_modify_write_payload.common_handles,
+        read_modify_write_payload.read_modify_write_handles,
+    )
+}
+
+pub fn print_help_read_modify_write(command_str: &str) {
+    let (parser, _, _) = prepare_parser(command_str);
+    parser.print_help();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::prepare_parser;
+    use crate::settings::command::Command;
+
+    const CMD: Command = Command::ReadModifyWrite;
+
+    #[test]
+    fn read_modify_write_params_lwt_test() {
+        let args = vec!["n=10m", "lwt", "serial-cl=LOCAL_SERIAL"];
+        let (parser, _, handles) = prepare_parser(CMD.show());
+
+        assert!(parser.parse(args).is_ok());
+        assert!(handles.lwt.get().is_some());
+    }
+
+    #[test]
+    fn read_modify_write_params_no_lwt_test() {
+        let args = vec!["n=10m"];
+        let (parser, _, handles) = prepare_parser(CMD.show());
+
+        assert!(parser.parse(args).is_ok());
+        assert!(handles.lwt.get().is_none());
+    }
+
+    #[test]
+    fn read_modify_write_params_bad_param_test() {
+        let args = vec!["n=10m", "batch=10"];
+        let (parser, _, _) = prepare_parser(CMD.show());
+
+        assert!(parser.parse(args).is_err());
+    }
+}
@@ -0,0 +1,249 @@
+use std::fmt::Display;
+
+use anyhow::{Context, Result};
+
+use crate::settings::{
+    param::{types::Parsable, ParamsParser, SimpleParamHandle},
+    ParsePayload,
+};
+
+use super::{common::CommonParamHandles, Command, CommandParams};
+
+/// The comparison of a [ScanFilter].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FilterOperator {
+    Eq,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+impl FilterOperator {
+    // The longer operators go first, so that `<=` isn't taken for `<`.
+    const ALL: [(&'static str, FilterOperator); 5] = [
+        ("<=", FilterOperator::Le),
+        (">=", FilterOperator::Ge),
+        ("=", FilterOperator::Eq),
+        ("<", FilterOperator::Lt),
+        (">", FilterOperator::Gt),
+    ];
+
+    pub fn show(&self) -> &'static str {
+        Self::ALL
+            .iter()
+            .find(|(_, op)| op == self)
+            .map(|(s, _)| *s)
+            .unwrap()
+    }
+}
+
+/// The predicate on one of the columns, which is evaluated by the server
+/// during the scan (`ALLOW FILTERING`), e.g. `C0>0x80`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ScanFilter {
+    pub column: String,
+    pub operator: FilterOperator,
+    pub value: Vec<u8>,
+}
+
+impl ScanFilter {
+    /// Returns the CQL restriction, with the value bound as a marker.
+    pub fn restriction(&self) -> String {
+        format!("\"{}\" {} ?", self.column, self.operator.show())
+    }
+}
+
+impl Display for ScanFilter {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}{}0x", self.column, self.operator.show())?;
+        self.value
+            .iter()
+            .try_for_each(|byte| write!(f, "{:02x}", byte))
+    }
+}
+
+impl Parsable for ScanFilter {
+    type Parsed = ScanFilter;
+
+    fn parse(s: &str) -> Result<Self::Parsed> {
+        let op_start = s
+            .find(['<', '>', '='])
+            .with_context(|| format!("Invalid filter {}, expected <column><op><value>", s))?;
+        let (column, rest) = s.split_at(op_start);
+        anyhow::ensure!(
+            !column.is_empty()
+                && column
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || c == '_'),
+            "Invalid column name in filter {}",
+            s
+        );
+        let (op_str, operator) = FilterOperator::ALL
+            .into_iter()
+            .find(|(op_str, _)| rest.starts_with(op_str))
+            .unwrap();
+        let value = parse_blob(&rest[op_str.len()..])
+            .with_context(|| format!("Invalid value in filter {}", s))?;
+        Ok(ScanFilter {
+            column: column.to_owned(),
+            operator,
+            value,
+        })
+    }
+
+    fn value_pattern() -> Option<&'static str> {
+        Some("<column>(=|<|<=|>|>=)0x<hex>")
+    }
+}
+
+// The columns are blobs, so the compared value is given in hex, the same as in cqlsh.
+fn parse_blob(s: &str) -> Result<Vec<u8>> {
+    let hex = s
+        .strip_prefix("0x")
+        .context("The value must be a blob in hex, e.g. 0x80")?;
+    anyhow::ensure!(
+        hex.len() % 2 == 0 && hex.chars().all(|c| c.is_ascii_hexdigit()),
+        "Invalid hex value {}",
+        s
+    );
+    Ok((0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&hex[i..i + 2], 16).unwrap())
+        .collect())
+}
+
+pub struct ScanParams {
+    pub ranges: u64,
+    pub filter: Option<ScanFilter>,
+}
+
+impl ScanParams {
+    pub fn print_settings(&self) {
+        println!("  Token Ranges: {}", self.ranges);
+        match &self.filter {
+            Some(filter) => println!("  Filter: {}", filter),
+            None => println!("  Filter: none"),
+        }
+    }
+
+    pub fn parse(cmd: &Command, payload: &mut ParsePayload) -> Result<CommandParams> {
+        let args = payload.remove(cmd.show()).unwrap();
+        let (parser, common_handles, scan_handles) = prepare_parser(cmd.show());
+        parser.parse(args)?;
+        let ranges = scan_handles.ranges.get().unwrap();
+        anyhow::ensure!(ranges > 0, "ranges= must be positive");
+        Ok(CommandParams {
+            common: super::common::parse_with_handles(common_handles),
+            counter: None,
+            mixed: None,
+            write: None,
+            read: None,
+            read_modify_write: None,
+            scan: Some(ScanParams {
+                ranges,
+                filter: scan_handles.filter.get(),
+            }),
+            #[cfg(feature = "user-profile")]
+            user: None,
+        })
+    }
+}
+
+pub struct ScanParamHandles {
+    ranges: SimpleParamHandle<u64>,
+    filter: SimpleParamHandle<ScanFilter>,
+}
+
+fn prepare_parser(cmd: &str) -> (ParamsParser, CommonParamHandles, ScanParamHandles) {
+    let mut parser = ParamsParser::new(cmd);
+
+    let (mut groups, common_handles) = super::common::add_common_param_groups(&mut parser);
+
+    let ranges = parser.simple_param(
+        "ranges=",
+        Some("1000"),
+        "The number of token ranges the token ring is split into. Each operation scans one of the ranges, going around the ring",
+        false,
+    );
+    let filter = parser.simple_param(
+        "filter=",
+        None,
+        "Return only the rows whose column satisfies the predicate, evaluated by the server with ALLOW FILTERING, e.g. C0>0x80. The rows of the range are then counted separately, to report the rows scanned and matched",
+        false,
+    );
+
+    for group in groups.iter_mut() {
+        group.push(Box::new(ranges.clone()));
+        group.push(Box::new(filter.clone()));
+        parser.group_iter(group.iter().map(|e| e.as_ref()));
+    }
+
+    parser.example("n=1000 ranges=1000 filter=C0>0x80");
+
+    (parser, common_handles, ScanParamHandles { ranges, filter })
+}
+
+pub fn print_help_scan(command_str: &str) {
+    let (parser, _, _) = prepare_parser(command_str);
+    parser.print_help();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{prepare_parser, FilterOperator, ScanFilter};
+    use crate::settings::{command::Command, param::types::Parsable};
+
+    const CMD: Command = Command::Scan;
+
+    #[test]
+    fn scan_params_test() {
+        let args = vec!["n=100", "ranges=64", "filter=C0>=0x80"];
+        let (parser, _, handles) = prepare_parser(CMD.show());
+
+        assert!(parser.parse(args).is_ok());
+        assert_eq!(Some(64), handles.ranges.get());
+        assert_eq!(
+            Some(ScanFilter {
+                column: String::from("C0"),
+                operator: FilterOperator::Ge,
+                value: vec![0x80],
+            }),
+            handles.filter.get()
+        );
+    }
+
+    #[test]
+    fn scan_params_default_test() {
+        let args = vec!["n=100"];
+        let (parser, _, handles) = prepare_parser(CMD.show());
+
+        assert!(parser.parse(args).is_ok());
+        assert_eq!(Some(1000), handles.ranges.get());
+        assert_eq!(None, handles.filter.get());
+    }
+
+    #[test]
+    fn scan_filter_parse_test() {
+        let filter = ScanFilter::parse("C1=0x00ff").unwrap();
+        assert_eq!(FilterOperator::Eq, filter.operator);
+        assert_eq!(vec![0x00, 0xff], filter.value);
+        assert_eq!("\"C1\" = ?", filter.restriction());
+        assert_eq!("C1=0x00ff", filter.to_string());
+        assert_eq!(
+            FilterOperator::Lt,
+            ScanFilter::parse("C1<0x01").unwrap().operator
+        );
+        assert_eq!(
+            FilterOperator::Le,
+            ScanFilter::parse("C1<=0x01").unwrap().operator
+        );
+
+        assert!(ScanFilter::parse("C1").is_err());
+        assert!(ScanFilter::parse(">0x01").is_err());
+        assert!(ScanFilter::parse("C1>01").is_err());
+        assert!(ScanFilter::parse("C1>0x1").is_err());
+        assert!(ScanFilter::parse("C1>0xzz").is_err());
+        assert!(ScanFilter::parse("C1!=0x01").is_err());
+    }
+}
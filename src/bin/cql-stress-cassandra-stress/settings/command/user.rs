@@ -1,4 +1,4 @@
-use std::{collections::HashMap, fs::File, sync::Arc};
+use std::{collections::HashMap, fs::File, sync::Arc, time::Duration};
 
 use anyhow::{Context, Result};
 use scylla::prepared_statement::PreparedStatement;
@@ -26,6 +26,44 @@ pub struct UserProfile {
     pub table: String,
     pub table_definition: Option<String>,
     pub queries: HashMap<String, QueryDefinitionYaml>,
+    #[serde(default)]
+    pub columnspec: Vec<ColumnSpecYaml>,
+}
+
+/// Generation settings of a column, in the format of c-s `columnspec`.
+/// `words` is specific to the tool - it makes a text column consist of
+/// the words of a built-in dictionary, whose ranks are sampled from the distribution.
+#[derive(Deserialize, Serialize, PartialEq, Debug)]
+#[serde(deny_unknown_fields)]
+pub struct ColumnSpecYaml {
+    pub name: String,
+    pub size: Option<String>,
+    pub population: Option<String>,
+    pub words: Option<String>,
+}
+
+impl ColumnSpecYaml {
+    fn into_column_spec(self) -> Result<(String, ColumnSpec)> {
+        let parse = |dist: Option<String>| -> Result<Option<Arc<dyn DistributionFactory>>> {
+            Ok(dist
+                .map(|dist| <Box<dyn DistributionFactory>>::parse(&dist))
+                .transpose()?
+                .map(Arc::from))
+        };
+        let spec = ColumnSpec {
+            size: parse(self.size).context("Invalid size distribution")?,
+            population: parse(self.population).context("Invalid population distribution")?,
+            words: parse(self.words).context("Invalid words distribution")?,
+        };
+        Ok((self.name, spec))
+    }
+}
+
+#[derive(Clone)]
+pub struct ColumnSpec {
+    pub size: Option<Arc<dyn DistributionFactory>>,
+    pub population: Option<Arc<dyn DistributionFactory>>,
+    pub words: Option<Arc<dyn DistributionFactory>>,
 }
 
 #[derive(Deserialize, Serialize, PartialEq, Debug)]
@@ -34,6 +72,8 @@ pub struct QueryDefinitionYaml {
     pub cql: String,
     pub consistency_level: Option<String>,
     pub serial_consistency_level: Option<String>,
+    pub timeout: Option<String>,
+    pub idempotent: Option<bool>,
 }
 
 impl QueryDefinitionYaml {
@@ -47,11 +87,14 @@ impl QueryDefinitionYaml {
             .serial_consistency_level
             .map(|sc| SerialConsistencyLevel::parse(&sc))
             .transpose()?;
+        let timeout = self.timeout.map(|t| Duration::parse(&t)).transpose()?;
 
         Ok(QueryDefinition {
             cql,
             consistency,
             serial_consistency,
+            timeout,
+            idempotent: self.idempotent,
         })
     }
 }
@@ -61,23 +104,45 @@ pub struct QueryDefinition {
     pub cql: String,
     pub consistency: Option<Consistency>,
     pub serial_consistency: Option<SerialConsistency>,
+    pub timeout: Option<Duration>,
+    pub idempotent: Option<bool>,
 }
 
 impl QueryDefinition {
-    pub async fn to_prepared_statement(&self, session: &Arc<Session>) -> Result<PreparedStatement> {
-        let mut statement = session
-            .prepare(&*self.cql)
-            .await
-            .with_context(|| format!("Failed to prepare statement: {}", self.cql))?;
-
+    /// Applies the settings of the query to its prepared statement.
+    ///
+    /// The serial consistency level (the query's own one, or `serial-cl=`
+    /// otherwise) is set only on the conditional statements, since
+    /// the other ones don't use it.
+    pub fn configure_statement(
+        &self,
+        statement: &mut PreparedStatement,
+        default_serial_consistency: SerialConsistency,
+    ) {
         if let Some(consistency) = self.consistency {
             statement.set_consistency(consistency);
         }
-        if self.serial_consistency.is_some() {
-            statement.set_serial_consistency(self.serial_consistency);
+        if self.is_conditional() {
+            statement.set_serial_consistency(Some(
+                self.serial_consistency
+                    .unwrap_or(default_serial_consistency),
+            ));
+        } else {
+            statement.set_serial_consistency(None);
+        }
+        if let Some(timeout) = self.timeout {
+            statement.set_request_timeout(Some(timeout));
         }
+        if let Some(idempotent) = self.idempotent {
+            statement.set_is_idempotent(idempotent);
+        }
+    }
 
-        Ok(statement)
+    /// Whether the query is a lightweight transaction, i.e. has an `IF` clause.
+    pub fn is_conditional(&self) -> bool {
+        self.cql
+            .split_whitespace()
+            .any(|word| word.eq_ignore_ascii_case("if"))
     }
 }
 
@@ -116,6 +181,8 @@ pub struct UserParams {
     pub queries_payload: HashMap<String, (QueryDefinition, OpWeight)>,
     pub clustering: Arc<dyn DistributionFactory>,
     pub insert_operation_weight: Option<OpWeight>,
+    // Maps a column name to its generation settings given in `columnspec`.
+    pub column_specs: HashMap<String, ColumnSpec>,
 }
 
 impl UserParams {
@@ -127,6 +194,10 @@ impl UserParams {
             common: super::common::parse_with_handles(common_handles),
             counter: None,
             mixed: None,
+            write: None,
+            read: None,
+            read_modify_write: None,
+            scan: None,
             user: Some(Self::parse_with_handles(user_handles)?),
         })
     }
@@ -157,15 +228,26 @@ impl UserParams {
 
     fn parse_with_handles(handles: UserParamHandles) -> Result<Self> {
         // 'profile' is a required parameter. This unwrap is safe since parsing was successful.
+        let profile = handles.profile.get().unwrap();
+        let queries_ratio = handles.ratio.get().unwrap();
+        let clustering: Arc<dyn DistributionFactory> = handles.clustering.get().unwrap().into();
+        Self::from_profile(profile, queries_ratio, clustering)
+    }
+
+    /// Picks the queries of the ratio map from the profile.
+    pub fn from_profile(
+        profile: UserProfile,
+        mut queries_ratio: HashMap<String, OpWeight>,
+        clustering: Arc<dyn DistributionFactory>,
+    ) -> Result<Self> {
         let UserProfile {
             keyspace,
             keyspace_definition,
             table,
             table_definition,
             mut queries,
-        } = handles.profile.get().unwrap();
-        let mut queries_ratio = handles.ratio.get().unwrap();
-        let clustering: Arc<dyn DistributionFactory> = handles.clustering.get().unwrap().into();
+            columnspec,
+        } = profile;
 
         // Handle the `insert` operation separately. This operation is not defined in the yaml file.
         // Its behaviour is predefined by the tool.
@@ -188,6 +270,15 @@ impl UserParams {
             )
             .collect::<Result<HashMap<_, _>, _>>()?;
 
+        let column_specs = columnspec
+            .into_iter()
+            .map(|spec| {
+                let name = spec.name.clone();
+                spec.into_column_spec()
+                    .with_context(|| format!("Invalid columnspec of column {}", name))
+            })
+            .collect::<Result<HashMap<_, _>>>()?;
+
         Ok(Self {
             keyspace,
             keyspace_definition,
@@ -196,6 +287,7 @@ impl UserParams {
             queries_payload,
             clustering,
             insert_operation_weight,
+            column_specs,
         })
     }
 }
@@ -251,7 +343,7 @@ fn prepare_parser(cmd: &str) -> (ParamsParser, CommonParamHandles, UserParamHand
 
 #[cfg(test)]
 mod tests {
-    use std::path::PathBuf;
+    use std::{path::PathBuf, time::Duration};
 
     use scylla::statement::{Consistency, SerialConsistency};
 
@@ -282,6 +374,8 @@ mod tests {
         assert_eq!("select c1 from standard1 where pkey = ?", query.cql);
         assert_eq!(None, query.consistency_level);
         assert_eq!(None, query.serial_consistency_level);
+        assert_eq!(None, query.timeout);
+        assert_eq!(None, query.idempotent);
     }
 
     #[test]
@@ -347,6 +441,8 @@ mod tests {
             Some("local_serial".to_string()),
             ins_query.serial_consistency_level
         );
+        assert_eq!(Some("5s".to_string()), ins_query.timeout);
+        assert_eq!(Some(true), ins_query.idempotent);
 
         let read_query = profile.queries.get("read").unwrap();
         assert_eq!("select c1 from standard1 where pkey = ?", read_query.cql);
@@ -355,6 +451,13 @@ mod tests {
             Some("serial".to_string()),
             read_query.serial_consistency_level
         );
+
+        assert_eq!(1, profile.columnspec.len());
+        let c1_spec = &profile.columnspec[0];
+        assert_eq!("c1", c1_spec.name);
+        assert_eq!(Some("fixed(100)".to_string()), c1_spec.size);
+        assert_eq!(Some("uniform(1..1000)".to_string()), c1_spec.population);
+        assert_eq!(None, c1_spec.words);
     }
 
     #[test]
@@ -382,7 +485,9 @@ mod tests {
                 QueryDefinition {
                     cql: "insert into standard1 (pkey, ckey, c1) values (?, ?, ?)".to_owned(),
                     consistency: Some(Consistency::LocalOne),
-                    serial_consistency: Some(SerialConsistency::LocalSerial)
+                    serial_consistency: Some(SerialConsistency::LocalSerial),
+                    timeout: Some(Duration::from_secs(5)),
+                    idempotent: Some(true),
                 },
                 1.0
             ),
@@ -395,12 +500,63 @@ mod tests {
                 QueryDefinition {
                     cql: "select c1 from standard1 where pkey = ?".to_owned(),
                     consistency: Some(Consistency::Quorum),
-                    serial_consistency: Some(SerialConsistency::Serial)
+                    serial_consistency: Some(SerialConsistency::Serial),
+                    timeout: None,
+                    idempotent: None,
                 },
                 2.0
             ),
             read
         );
+
+        let c1_spec = user.column_specs.get("c1").unwrap();
+        assert_eq!("FIXED(100)", c1_spec.size.as_ref().unwrap().to_string());
+        assert_eq!(
+            "UNIFORM(1..1000)",
+            c1_spec.population.as_ref().unwrap().to_string()
+        );
+        assert!(c1_spec.words.is_none());
+    }
+
+    #[test]
+    fn conditional_query_test() {
+        let query = |cql: &str| QueryDefinition {
+            cql: cql.to_owned(),
+            consistency: None,
+            serial_consistency: None,
+            timeout: None,
+            idempotent: None,
+        };
+        assert!(query("update standard1 set c1 = ? where pkey = ? if c1 = ?").is_conditional());
+        assert!(
+            query("INSERT INTO standard1 (pkey, c1) VALUES (?, ?) IF NOT EXISTS").is_conditional()
+        );
+        assert!(!query("select c1 from standard1 where pkey = ?").is_conditional());
+        assert!(!query("select iff from standard1 where pkey = ?").is_conditional());
+    }
+
+    #[test]
+    fn bad_timeout_profile_yaml_test() {
+        // The timeout of the query is invalid.
+        let yaml_filepath = build_file_path("bad_timeout_profile.yaml");
+        let profile_arg = format!("profile={yaml_filepath}");
+        let args = vec![&profile_arg, "ops(read=1)"];
+
+        let (parser, _common_handles, user_handles) = prepare_parser("user");
+        parser.parse(args).unwrap();
+        assert!(UserParams::parse_with_handles(user_handles).is_err());
+    }
+
+    #[test]
+    fn bad_columnspec_profile_yaml_test() {
+        // The words distribution of the columnspec is invalid.
+        let yaml_filepath = build_file_path("bad_columnspec_profile.yaml");
+        let profile_arg = format!("profile={yaml_filepath}");
+        let args = vec![&profile_arg, "ops(read=1)"];
+
+        let (parser, _common_handles, user_handles) = prepare_parser("user");
+        parser.parse(args).unwrap();
+        assert!(UserParams::parse_with_handles(user_handles).is_err());
     }
 
     #[test]
@@ -0,0 +1,154 @@
+use std::num::NonZeroUsize;
+
+use anyhow::Result;
+
+use crate::settings::{
+    param::{ParamHandle, ParamsParser, SimpleParamHandle},
+    ParsePayload,
+};
+
+use super::{common::CommonParamHandles, Command, CommandParams};
+
+pub struct WriteParams {
+    pub lwt: bool,
+    pub batch_size: NonZeroUsize,
+}
+
+impl WriteParams {
+    pub fn print_settings(&self) {
+        println!("  Lightweight Transactions: {}", self.lwt);
+        println!("  Partitions per UNLOGGED batch: {}", self.batch_size);
+    }
+
+    pub fn parse(cmd: &Command, payload: &mut ParsePayload) -> Result<CommandParams> {
+        let args = payload.remove(cmd.show()).unwrap();
+        let (parser, common_handles, write_handles) = prepare_parser(cmd.show());
+        parser.parse(args)?;
+        let lwt = write_handles.lwt.get().is_some();
+        let batch_size = write_handles.batch.get().unwrap();
+        // Conditional batches must not span multiple partitions.
+        anyhow::ensure!(
+            !lwt || batch_size.get() == 1,
+            "lwt cannot be combined with batch greater than 1"
+        );
+        Ok(CommandParams {
+            common: super::common::parse_with_handles(common_handles),
+            counter: None,
+            mixed: None,
+            write: Some(WriteParams { lwt, batch_size }),
+            read: None,
+            read_modify_write: None,
+            scan: None,
+            #[cfg(feature = "user-profile")]
+            user: None,
+        })
+    }
+}
+
+pub struct WriteParamHandles {
+    lwt: SimpleParamHandle<bool>,
+    batch: SimpleParamHandle<NonZeroUsize>,
+}
+
+pub struct WriteParamGroups {
+    pub groups: Vec<Vec<Box<dyn ParamHandle>>>,
+    pub common_handles: CommonParamHandles,
+    pub write_handles: WriteParamHandles,
+}
+
+pub fn add_write_param_groups(parser: &mut ParamsParser) -> WriteParamGroups {
+    let (mut groups, common_handles) = super::common::add_common_param_groups(parser);
+
+    let lwt = parser.simple_param(
+        "lwt",
+        None,
+        "Perform conditional writes (INSERT ... IF NOT EXISTS) using lightweight transactions. Honors serial-cl=",
+        false,
+    );
+
+    let batch = parser.simple_param(
+        "batch=",
+        Some("1"),
+        "Number of partitions (each of -col clustering= rows) inserted in a single UNLOGGED batch per operation",
+        false,
+    );
+
+    for group in groups.iter_mut() {
+        group.push(Box::new(lwt.clone()));
+        group.push(Box::new(batch.clone()));
+    }
+
+    WriteParamGroups {
+        groups,
+        common_handles,
+        write_handles: WriteParamHandles { lwt, batch },
+    }
+}
+
+fn prepare_parser(cmd: &str) -> (ParamsParser, CommonParamHandles, WriteParamHandles) {
+    let mut parser = ParamsParser::new(cmd);
+
+    let mut write_payload = add_write_param_groups(&mut parser);
+
+    for group in write_payload.groups.iter_mut() {
+        parser.group_iter(group.iter().map(|e| e.as_ref()))
+    }
+
+    parser.example("n=1000000 cl=one");
+    parser.example("duration=1h batch=20");
+
+    (
+        parser,
+        write_payload.common_handles,
+        write_payload.write_handles,
+    )
+}
+
+pub fn print_help_write(command_str: &str) {
+    let (parser, _, _) = prepare_parser(command_str);
+    parser.print_help();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::prepare_parser;
+    use crate::settings::command::Command;
+
+    const CMD: Command = Command::Write;
+
+    #[test]
+    fn write_params_lwt_test() {
+        let args = vec!["n=10m", "lwt", "serial-cl=LOCAL_SERIAL"];
+        let (parser, _, handles) = prepare_parser(CMD.show());
+
+        assert!(parser.parse(args).is_ok());
+        assert!(handles.lwt.get().is_some());
+    }
+
+    #[test]
+    fn write_params_no_lwt_test() {
+        let args = vec!["n=10m"];
+        let (parser, _, handles) = prepare_parser(CMD.show());
+
+        assert!(parser.parse(args).is_ok());
+        assert!(handles.lwt.get().is_none());
+        assert_eq!(1, handles.batch.get().unwrap().get());
+    }
+
+    #[test]
+    fn write_params_batch_test() {
+        let args = vec!["n=10m", "batch=10"];
+        let (parser, _, handles) = prepare_parser(CMD.show());
+
+        assert!(parser.parse(args).is_ok());
+        assert_eq!(10, handles.batch.get().unwrap().get());
+    }
+
+    #[test]
+    fn write_params_bad_batch_test() {
+        let args = vec!["n=10m", "batch=0"];
+        let (parser, _, _) = prepare_parser(CMD.show());
+
+        assert!(parser.parse(args).is_err());
+    }
+}
@@ -6,6 +6,7 @@ mod option;
 mod param;
 use anyhow::Context;
 use anyhow::Result;
+use cql_stress::run_metadata::RunMetadata;
 
 #[cfg(test)]
 mod test;
@@ -14,9 +15,14 @@ pub use command::Command;
 pub use command::CommandParams;
 pub use command::MixedSubcommand;
 pub use command::OperationRatio;
+pub use command::{ClusteringOrder, ClusteringRestriction};
 #[cfg(feature = "user-profile")]
-pub use command::{OpWeight, PREDEFINED_INSERT_OPERATION};
+pub use command::{ColumnSpec, OpWeight, UserParams, PREDEFINED_INSERT_OPERATION};
+pub use option::OutputLevel;
+pub use option::SequenceEnd;
 pub use option::ThreadsInfo;
+pub use option::CHECKSUM_HEADER_SIZE;
+pub use option::CLUSTERING_COLUMN;
 use regex::Regex;
 use scylla::Session;
 
@@ -24,11 +30,19 @@ use crate::settings::command::print_help;
 
 use self::command::parse_command;
 use self::option::ColumnOption;
+use self::option::ControlFileOption;
+use self::option::DryRunOption;
+use self::option::ErrorsOption;
+use self::option::InsertOption;
+use self::option::LogOption;
 use self::option::ModeOption;
 use self::option::NodeOption;
 use self::option::PopulationOption;
 use self::option::RateOption;
+use self::option::ReportOption;
 use self::option::SchemaOption;
+use self::option::SeedOption;
+use self::option::TransportOption;
 
 pub struct CassandraStressSettings {
     pub command: Command,
@@ -36,7 +50,15 @@ pub struct CassandraStressSettings {
     pub node: NodeOption,
     pub rate: RateOption,
     pub mode: ModeOption,
+    pub errors: ErrorsOption,
+    pub insert: InsertOption,
     pub schema: SchemaOption,
+    pub transport: TransportOption,
+    pub log: LogOption,
+    pub seed: SeedOption,
+    pub dry_run: DryRunOption,
+    pub report: ReportOption,
+    pub control_file: ControlFileOption,
     pub column: ColumnOption,
     pub population: PopulationOption,
 }
@@ -47,18 +69,87 @@ impl CassandraStressSettings {
         self.command_params.print_settings(&self.command);
         self.rate.print_settings();
         self.mode.print_settings();
+        self.errors.print_settings();
+        self.insert.print_settings();
         self.node.print_settings();
         self.schema.print_settings();
+        self.transport.print_settings();
+        self.log.print_settings();
+        self.seed.print_settings();
+        self.dry_run.print_settings();
+        self.report.print_settings();
+        self.control_file.print_settings();
         self.column.print_settings();
         self.population.print_settings();
         println!();
     }
 
+    /// Returns the metadata written at the beginning of the output files,
+    /// with the snapshot of the main settings of the run.
+    pub fn run_metadata(&self) -> RunMetadata {
+        let common = &self.command_params.common;
+        let mut configuration = vec![("Command", self.command.show().to_owned())];
+        if let Some(operation_count) = common.operation_count {
+            configuration.push(("Count", operation_count.to_string()));
+        }
+        if let Some(duration) = common.duration {
+            configuration.push(("Duration", format!("{}s", duration.as_secs())));
+        }
+        configuration.push(("Consistency level", common.consistency_level.to_string()));
+        if let Some(mixed) = &self.command_params.mixed {
+            if let Some(cl) = mixed.read_consistency_level {
+                configuration.push(("Read consistency level", cl.to_string()));
+            }
+            if let Some(cl) = mixed.write_consistency_level {
+                configuration.push(("Write consistency level", cl.to_string()));
+            }
+        }
+        if let ThreadsInfo::Fixed { threads, .. } | ThreadsInfo::AutoLatency { threads, .. } =
+            self.rate.threads_info
+        {
+            configuration.push(("Threads", threads.to_string()));
+        }
+        configuration.extend([
+            ("Keyspace", self.schema.keyspace.clone()),
+            ("Table", self.schema.table.clone()),
+            ("Replication", self.schema.replication.to_cql()),
+        ]);
+        if let Some(seed) = self.seed.seed {
+            configuration.push(("Seed", seed.to_string()));
+        }
+
+        RunMetadata::new(
+            self.node.nodes.clone(),
+            configuration
+                .into_iter()
+                .map(|(setting, value)| (setting.to_owned(), value))
+                .collect(),
+        )
+    }
+
     pub async fn create_schema(&self, session: &Session) -> Result<()> {
         #[cfg(feature = "user-profile")]
         if let Some(user) = &self.command_params.user {
             return user.create_schema(session).await;
         }
+        // The table of the profile is created the same way for the mixed command.
+        #[cfg(feature = "user-profile")]
+        if let Some(user) = self
+            .command_params
+            .mixed
+            .as_ref()
+            .and_then(|mixed| mixed.user.as_ref())
+        {
+            user.create_schema(session).await?;
+        }
+
+        if self.schema.existing {
+            session
+                .use_keyspace(&self.schema.keyspace, true)
+                .await
+                .with_context(|| format!("Failed to use keyspace {}", self.schema.keyspace))?;
+            return self.verify_existing_schema(session);
+        }
 
         if matches!(self.command, Command::Write | Command::CounterWrite) {
             session
@@ -72,8 +163,10 @@ impl CassandraStressSettings {
             Command::Write => {
                 session
                     .query_unpaged(
-                        self.schema
-                            .construct_table_creation_query(&self.column.columns),
+                        self.schema.construct_table_creation_query(
+                            &self.column.columns,
+                            self.column.has_clustering_column(),
+                        ),
                         (),
                     )
                     .await
@@ -92,6 +185,51 @@ impl CassandraStressSettings {
             _ => (),
         }
 
+        if matches!(self.command, Command::Write | Command::IndexRead) {
+            if let Some(index_creation_query) = self.schema.construct_index_creation_query() {
+                session
+                    .query_unpaged(index_creation_query, ())
+                    .await
+                    .context("Failed to create secondary index")?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Verifies that the tables used by the command exist (see `-schema existing`).
+    fn verify_existing_schema(&self, session: &Session) -> Result<()> {
+        let (standard, counter) = match &self.command {
+            Command::CounterWrite | Command::CounterRead => (false, true),
+            Command::Mixed => {
+                // Validated during parsing.
+                let ratio = &self.command_params.mixed.as_ref().unwrap().operation_ratio;
+                (
+                    ratio.contains(&MixedSubcommand::Read)
+                        || ratio.contains(&MixedSubcommand::Write),
+                    ratio.contains(&MixedSubcommand::CounterRead)
+                        || ratio.contains(&MixedSubcommand::CounterWrite),
+                )
+            }
+            _ => (true, false),
+        };
+
+        let cluster_data = session.get_cluster_data();
+        let keyspace = cluster_data.get_keyspace_info().get(&self.schema.keyspace);
+        let table = |name: &str| keyspace.and_then(|keyspace| keyspace.tables.get(name));
+        if standard {
+            self.schema.verify_existing_table(
+                table(&self.schema.table),
+                &self.column.columns,
+                self.column.has_clustering_column(),
+            )?;
+        }
+        if counter {
+            self.schema.verify_existing_counter_table(
+                table(&self.schema.counter_table),
+                &self.column.columns,
+            )?;
+        }
         Ok(())
     }
 }
@@ -202,7 +340,7 @@ where
     let result = || {
         let (cmd, mut payload) = prepare_parse_payload(&args)?;
 
-        let (command, command_params) = match parse_command(cmd, &mut payload) {
+        let (command, mut command_params) = match parse_command(cmd, &mut payload) {
             Ok((_, None)) => return Ok(CassandraStressParsingResult::SpecialCommand),
             Ok((cmd, Some(params))) => (cmd, params),
             Err(e) => return Err(e),
@@ -211,16 +349,171 @@ where
         let node = NodeOption::parse(&mut payload)?;
         let rate = RateOption::parse(&mut payload)?;
         let mode = ModeOption::parse(&mut payload)?;
+        let errors = ErrorsOption::parse(&mut payload)?;
+        let insert = InsertOption::parse(&mut payload)?;
         let schema = SchemaOption::parse(&mut payload)?;
+        let transport = TransportOption::parse(&mut payload)?;
+        let log = LogOption::parse(&mut payload)?;
+        let seed = SeedOption::parse(&mut payload)?;
+        let dry_run = DryRunOption::parse(&mut payload)?;
+        let report = ReportOption::parse(&mut payload)?;
+        let control_file = ControlFileOption::parse(&mut payload)?;
         let column = ColumnOption::parse(&mut payload)?;
 
-        // The default distribution (if not specified) is SEQ(1..operation_count).
+        if let Some(index) = &schema.index {
+            anyhow::ensure!(
+                column.columns.contains(index),
+                "-schema index= must be one of the columns: {:?}",
+                column.columns
+            );
+            // Otherwise, the rows written without the indexed column couldn't be read via the index.
+            anyhow::ensure!(
+                column.count_distribution.is_none(),
+                "-schema index= requires a fixed number of columns (-col n=)"
+            );
+        }
+        anyhow::ensure!(
+            command != Command::IndexRead || schema.index.is_some(),
+            "index_read command requires -schema index="
+        );
+        anyhow::ensure!(
+            !rate.streams || command == Command::Mixed,
+            "-rate streams requires the mixed command"
+        );
+        anyhow::ensure!(
+            log.measure_latency || !matches!(rate.threads_info, ThreadsInfo::AutoLatency { .. }),
+            "-rate auto-latency cannot be used together with -log no-latency"
+        );
+        // Both would change the rate during the run.
+        anyhow::ensure!(
+            control_file.path.is_none()
+                || !matches!(rate.threads_info, ThreadsInfo::AutoLatency { .. }),
+            "-rate auto-latency cannot be used together with -control-file"
+        );
+        if column.has_clustering_column() {
+            // The other operations assume a single row per partition.
+            let supported = match command {
+                Command::Write | Command::Read | Command::Scan => true,
+                Command::Mixed => command_params.mixed.as_ref().is_some_and(|mixed| {
+                    !mixed
+                        .operation_ratio
+                        .contains(&MixedSubcommand::CounterRead)
+                        && !mixed
+                            .operation_ratio
+                            .contains(&MixedSubcommand::CounterWrite)
+                }),
+                _ => false,
+            };
+            anyhow::ensure!(
+                supported,
+                "-col clustering= is supported only by the write, read, scan and mixed (without counter operations) commands"
+            );
+        }
+        if insert.visits.is_some() {
+            anyhow::ensure!(
+                column.has_clustering_column(),
+                "-insert visits= requires -col clustering="
+            );
+            // The other operations read or write whole partitions.
+            anyhow::ensure!(
+                command == Command::Write,
+                "-insert visits= is supported only by the write command"
+            );
+            // The batches consist of the rows of whole partitions.
+            anyhow::ensure!(
+                command_params
+                    .write
+                    .as_ref()
+                    .is_some_and(|write| write.batch_size.get() == 1),
+                "-insert visits= cannot be used together with batch="
+            );
+        }
+        // The statements of the profile are prepared in the keyspace of -schema.
+        #[cfg(feature = "user-profile")]
+        if let Some(user) = command_params
+            .mixed
+            .as_ref()
+            .and_then(|mixed| mixed.user.as_ref())
+        {
+            anyhow::ensure!(
+                user.keyspace == schema.keyspace,
+                "The keyspace of the profile ({}) must be the keyspace of -schema ({}) to mix its queries with the commands",
+                user.keyspace,
+                schema.keyspace
+            );
+        }
+        if let Some(filter) = command_params
+            .scan
+            .as_ref()
+            .and_then(|scan| scan.filter.as_ref())
+        {
+            anyhow::ensure!(
+                column.columns.contains(&filter.column),
+                "The column of filter= must be one of the columns: {:?}",
+                column.columns
+            );
+        }
+        if let Some(read) = command_params.read.as_ref() {
+            anyhow::ensure!(
+                column.has_clustering_column()
+                    || (read.restriction.is_none() && read.order.is_none()),
+                "slice=, in= and order= require -col clustering="
+            );
+            if let Some(ClusteringRestriction::Slice(rows) | ClusteringRestriction::In(rows)) =
+                read.restriction
+            {
+                anyhow::ensure!(
+                    rows.get() <= column.clustering,
+                    "The number of read clustering rows ({}) cannot exceed -col clustering= ({})",
+                    rows,
+                    column.clustering
+                );
+            }
+        }
+        if let Some(incremented_columns) = command_params
+            .counter
+            .as_ref()
+            .and_then(|counter| counter.incremented_columns)
+        {
+            anyhow::ensure!(
+                incremented_columns.get() <= column.columns.len(),
+                "columns= ({}) cannot exceed the number of counter columns ({})",
+                incremented_columns,
+                column.columns.len()
+            );
+        }
+
+        if node.cloud_config.is_some() {
+            anyhow::ensure!(
+                !transport.ssl_enabled(),
+                "-transport cannot be used together with -node cloudconf=, the bundle provides the TLS settings"
+            );
+            anyhow::ensure!(
+                mode.user_credentials.is_none(),
+                "-mode user= and password= cannot be used together with -node cloudconf=, the bundle provides the credentials"
+            );
+            anyhow::ensure!(
+                mode.authenticator.is_none(),
+                "-mode authenticator= cannot be used together with -node cloudconf=, the bundle provides the credentials"
+            );
+        }
+
+        // The default distribution (if not specified) is SEQ(1..operation_count) for the writes
+        // and GAUSSIAN(1..operation_count) for the other commands.
         // If operation_count is not specified, then the default is 1M.
         let operation_count = command_params
             .common
             .operation_count
             .map_or(String::from("1000000"), |op| format!("{op}"));
-        let population = PopulationOption::parse(&mut payload, &operation_count)?;
+        let population = PopulationOption::parse(&mut payload, &command, &operation_count)?;
+        // Each of the loaders sharing the population performs its part of the operations.
+        if let (Some(split), Some(operation_count)) = (
+            population.split,
+            command_params.common.operation_count.as_mut(),
+        ) {
+            let part = split.part(*operation_count);
+            *operation_count = part.end - part.start;
+        }
 
         // List the unknown options along with their parameters.
         let build_unknown_arguments_err_message = || -> String {
@@ -249,7 +542,15 @@ where
                 node,
                 rate,
                 mode,
+                errors,
+                insert,
                 schema,
+                transport,
+                log,
+                seed,
+                dry_run,
+                report,
+                control_file,
                 column,
                 population,
             },
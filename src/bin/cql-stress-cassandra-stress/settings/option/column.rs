@@ -1,8 +1,10 @@
+use std::num::NonZeroU32;
+
 use anyhow::{Context, Result};
-use cql_stress::distribution::{parse_description, SyntaxFlavor};
+use cql_stress::compressible::parse_compressible;
 
 use crate::{
-    java_generate::distribution::DistributionFactory,
+    java_generate::distribution::{fixed::FixedDistributionFactory, DistributionFactory},
     settings::{
         param::{
             types::{CommaDelimitedList, Parsable},
@@ -12,9 +14,26 @@ use crate::{
     },
 };
 
+/// The size of the header embedded in each value with `-col checksum`.
+/// See `operation/checksum.rs` for its format.
+pub const CHECKSUM_HEADER_SIZE: usize = 12;
+
+/// The name of the clustering column of the standard table,
+/// added to the schema with `-col clustering=` greater than 1.
+pub const CLUSTERING_COLUMN: &str = "ck";
+
 pub struct ColumnOption {
     pub columns: Vec<String>,
     pub size_distribution: Box<dyn DistributionFactory>,
+    /// Distribution of the number of columns written to each row, if it varies.
+    /// In such case, `columns` contains as many columns as the distribution's maximum.
+    pub count_distribution: Option<Box<dyn DistributionFactory>>,
+    /// Whether the values embed a checksum, verified by the reads.
+    pub checksum: bool,
+    /// Number of the clustering rows of each partition.
+    pub clustering: usize,
+    /// Fraction of each value made compressible, 0 for random values only.
+    pub compressible: f64,
 }
 
 impl ColumnOption {
@@ -28,7 +47,56 @@ impl ColumnOption {
         let params = cl_args.remove(Self::CLI_STRING).unwrap_or_default();
         let (parser, handles) = prepare_parser();
         parser.parse(params)?;
-        Ok(Self::from_handles(handles))
+        let column = Self::from_handles(handles);
+        anyhow::ensure!(
+            !column.checksum
+                || column.size_distribution.create().min_value() >= CHECKSUM_HEADER_SIZE as i64,
+            "-col checksum requires the cells of at least {} bytes (size=)",
+            CHECKSUM_HEADER_SIZE
+        );
+        if column.has_clustering_column() {
+            anyhow::ensure!(
+                column.clustering <= i32::MAX as usize,
+                "-col clustering= cannot exceed {}",
+                i32::MAX
+            );
+            // Otherwise, the rows of the partition would differ in the number of columns,
+            // which is sampled based on the partition key only.
+            anyhow::ensure!(
+                column.count_distribution.is_none(),
+                "-col clustering= requires a fixed number of columns (n=)"
+            );
+            anyhow::ensure!(
+                !column
+                    .columns
+                    .iter()
+                    .any(|name| name.eq_ignore_ascii_case(CLUSTERING_COLUMN)),
+                "-col names= cannot contain the clustering column: {}",
+                CLUSTERING_COLUMN
+            );
+        }
+        Ok(column)
+    }
+
+    /// Whether the standard table has the clustering column,
+    /// i.e. whether each partition consists of multiple rows.
+    pub fn has_clustering_column(&self) -> bool {
+        self.clustering > 1
+    }
+
+    /// Returns the number of the leading values of each row
+    /// which make up its primary key.
+    pub fn key_len(&self) -> usize {
+        if self.has_clustering_column() {
+            2
+        } else {
+            1
+        }
+    }
+
+    /// Returns the number of the values of each row, including the primary key.
+    pub fn row_len(&self) -> usize {
+        self.key_len() + self.columns.len()
     }
 
     pub fn print_help() {
@@ -40,58 +108,87 @@ impl ColumnOption {
         println!("Column:");
         println!("  Column names: {:?}", self.columns);
         println!("  Size distribution: {}", self.size_distribution);
+        if let Some(count_distribution) = &self.count_distribution {
+            println!("  Count distribution: {}", count_distribution);
+        }
+        println!("  Checksum: {}", self.checksum);
+        println!("  Clustering rows per partition: {}", self.clustering);
+        println!("  Compressible fraction: {}", self.compressible);
     }
 
     fn from_handles(handles: ColumnParamHandles) -> Self {
         let names = handles.names.get();
         let columns_count = handles.columns_count.get();
         let size_distribution = handles.size_distribution.get().unwrap();
+        let checksum = handles.checksum.get().is_some();
+        let clustering = handles.clustering.get().unwrap().get() as usize;
+        let compressible = handles.compressible.get().unwrap();
 
-        let columns = match names {
-            Some(names) => names,
-            None => (0..columns_count.unwrap())
-                .map(|n| format!("C{n}"))
-                .collect(),
+        let (columns, count_distribution) = match names {
+            Some(names) => (names, None),
+            None => {
+                let columns_count = columns_count.unwrap();
+                let distribution = columns_count.create();
+                let columns = (0..distribution.max_value())
+                    .map(|n| format!("C{n}"))
+                    .collect();
+                let count_distribution =
+                    (distribution.min_value() != distribution.max_value()).then_some(columns_count);
+                (columns, count_distribution)
+            }
         };
 
         Self {
             columns,
             size_distribution,
+            count_distribution,
+            checksum,
+            clustering,
+            compressible,
         }
     }
 }
 
 /// A type for parsing `-col n=` parameter.
 ///
-/// In cassandra-stress, CLI originally accepts a distribution.
-/// However, it only supports FIXED(?) distribution. Other distributions
-/// are supported only for thrift mode, which is not supported by the
-/// rust driver.
+/// In cassandra-stress, CLI accepts a distribution of the number of columns.
+/// For convenience, a plain u64 value is accepted as well, and it's
+/// equivalent to FIXED(?) distribution.
 ///
-/// It would make much more sense to accept simply a u64 value instead of distribution.
-/// OTOH, we want cql-stress CLI to be compatible with original c-s CLI.
-/// This is why this type is introduced, so the users can choose between
-/// providing either u64 value or FIXED(?) distribution.
+/// If the distribution is not fixed, the table is created with as many
+/// columns as the distribution's maximum, and each row is written with
+/// the number of columns sampled from the distribution.
 struct ColumnCount;
 
 impl Parsable for ColumnCount {
-    type Parsed = u64;
+    type Parsed = Box<dyn DistributionFactory>;
 
     fn parse(s: &str) -> Result<Self::Parsed> {
-        let parse_u64_result = u64::parse(s);
-        if parse_u64_result.is_ok() {
-            return parse_u64_result;
+        if let Ok(count) = u64::parse(s) {
+            return Ok(Box::new(FixedDistributionFactory(count as i64)));
         }
 
-        || -> Result<u64, anyhow::Error> {
-            let s = &s.to_lowercase();
-            let description = parse_description(s, SyntaxFlavor::Classic)
+        || -> Result<Self::Parsed, anyhow::Error> {
+            let factory = <Box<dyn DistributionFactory>>::parse(s)
                 .context("Failed to parse distribution description.")?;
-            anyhow::ensure!(description.name == "fixed", "Expected FIXED distribution.");
-            description.check_argument_count(1)?;
-            u64::parse(description.args[0]).context("Failed to parse u64 value")
+            anyhow::ensure!(
+                factory.create().min_value() >= 0,
+                "The number of columns cannot be negative."
+            );
+            Ok(factory)
         }()
-        .context("Invalid value. Available values are either <u64> or FIXED(<u64>).")
+        .context("Invalid value. Available values are either <u64> or DIST(?).")
+    }
+}
+
+/// A type for parsing `-col compressible=` parameter, a fraction in [0, 1].
+struct Compressible;
+
+impl Parsable for Compressible {
+    type Parsed = f64;
+
+    fn parse(s: &str) -> Result<Self::Parsed> {
+        parse_compressible(s)
     }
 }
 
@@ -99,22 +196,67 @@ struct ColumnParamHandles {
     names: SimpleParamHandle<CommaDelimitedList>,
     columns_count: SimpleParamHandle<ColumnCount>,
     size_distribution: SimpleParamHandle<Box<dyn DistributionFactory>>,
+    checksum: SimpleParamHandle<bool>,
+    clustering: SimpleParamHandle<NonZeroU32>,
+    compressible: SimpleParamHandle<Compressible>,
 }
 
 fn prepare_parser() -> (ParamsParser, ColumnParamHandles) {
     let mut parser = ParamsParser::new(ColumnOption::CLI_STRING);
 
     let names = parser.simple_param("names=", None, "Column names", true);
-    let columns_count = parser.simple_param("n=", Some("5"), "Number of columns", false);
+    let columns_count = parser.simple_param(
+        "n=",
+        Some("5"),
+        "Number of columns, either fixed (e.g. n=5) or a distribution of the number of columns written to each row (e.g. n=uniform(1..10))",
+        false,
+    );
     let size_distribution =
         parser.distribution_param("size=", Some("fixed(34)"), "Cell size distribution", false);
+    let checksum = parser.simple_param(
+        "checksum",
+        None,
+        "Embed a checksum in each cell and verify it on reads",
+        false,
+    );
+    let clustering = parser.simple_param(
+        "clustering=",
+        Some("1"),
+        "Number of the clustering rows of each partition, written and read together. If greater than 1, the table is created with the clustering column (ck)",
+        false,
+    );
+    let compressible = parser.simple_param(
+        "compressible=",
+        Some("0"),
+        "Fraction of each cell, between 0 and 1, made compressible by zeroing it instead of generating random bytes",
+        false,
+    );
 
     // $ ./cassandra-stress help -col
-    // Usage: -col [n=?] [size=DIST(?)]
+    // Usage: -col [n=DIST(?)] [size=DIST(?)]
     //  OR
     // Usage: -col names=? [size=DIST(?)]
-    parser.group(&[&names, &size_distribution]);
-    parser.group(&[&columns_count, &size_distribution]);
+    parser.group(&[
+        &names,
+        &size_distribution,
+        &checksum,
+        &clustering,
+        &compressible,
+    ]);
+    parser.group(&[
+        &columns_count,
+        &size_distribution,
+        &checksum,
+        &clustering,
+        &compressible,
+    ]);
+
+    parser.example("n=10 size=uniform(10..100)");
+    parser.example("names=foo,bar,baz");
+    parser.example("n=uniform(1..10)");
+    parser.example("n=5 size=fixed(64) checksum");
+    parser.example("n=5 clustering=10");
+    parser.example("n=5 size=fixed(1024) compressible=0.5");
 
     (
         parser,
@@ -122,6 +264,9 @@ fn prepare_parser() -> (ParamsParser, ColumnParamHandles) {
             names,
             columns_count,
             size_distribution,
+            checksum,
+            clustering,
+            compressible,
         },
     )
 }
@@ -141,6 +286,9 @@ mod tests {
 
         let params = ColumnOption::from_handles(handles);
         assert_eq!(&["C0", "C1", "C2", "C3", "C4"], params.columns.as_slice());
+        assert_eq!(1, params.clustering);
+        assert!(!params.has_clustering_column());
+        assert_eq!(6, params.row_len());
     }
 
     #[test]
@@ -154,6 +302,77 @@ mod tests {
         assert_eq!(&["foo", "bar", "baz"], params.columns.as_slice());
     }
 
+    #[test]
+    fn col_fixed_count_params_test() {
+        let args = vec!["n=FIXED(3)"];
+        let (parser, handles) = prepare_parser();
+
+        assert!(parser.parse(args).is_ok());
+
+        let params = ColumnOption::from_handles(handles);
+        assert_eq!(&["C0", "C1", "C2"], params.columns.as_slice());
+        assert!(params.count_distribution.is_none());
+    }
+
+    #[test]
+    fn col_variable_count_params_test() {
+        let args = vec!["n=uniform(2..4)"];
+        let (parser, handles) = prepare_parser();
+
+        assert!(parser.parse(args).is_ok());
+
+        let params = ColumnOption::from_handles(handles);
+        assert_eq!(&["C0", "C1", "C2", "C3"], params.columns.as_slice());
+        assert!(params.count_distribution.is_some());
+    }
+
+    #[test]
+    fn col_checksum_params_test() {
+        let args = vec!["n=3", "checksum"];
+        let (parser, handles) = prepare_parser();
+
+        assert!(parser.parse(args).is_ok());
+
+        let params = ColumnOption::from_handles(handles);
+        assert!(params.checksum);
+    }
+
+    #[test]
+    fn col_clustering_params_test() {
+        let args = vec!["n=3", "clustering=10"];
+        let (parser, handles) = prepare_parser();
+
+        assert!(parser.parse(args).is_ok());
+
+        let params = ColumnOption::from_handles(handles);
+        assert_eq!(10, params.clustering);
+        assert!(params.has_clustering_column());
+        assert_eq!(2, params.key_len());
+        assert_eq!(5, params.row_len());
+    }
+
+    #[test]
+    fn col_zero_clustering_params_test() {
+        let args = vec!["clustering=0"];
+        let (parser, _) = prepare_parser();
+
+        assert!(parser.parse(args).is_err());
+    }
+
+    #[test]
+    fn col_compressible_params_test() {
+        let (parser, handles) = prepare_parser();
+        assert!(parser.parse(vec![]).is_ok());
+        assert_eq!(0.0, ColumnOption::from_handles(handles).compressible);
+
+        let (parser, handles) = prepare_parser();
+        assert!(parser.parse(vec!["n=3", "compressible=0.5"]).is_ok());
+        assert_eq!(0.5, ColumnOption::from_handles(handles).compressible);
+
+        let (parser, _) = prepare_parser();
+        assert!(parser.parse(vec!["compressible=1.5"]).is_err());
+    }
+
     #[test]
     fn col_bad_params_test() {
         let args = vec!["names=foo,bar,baz", "n=10"];
@@ -161,4 +380,12 @@ mod tests {
 
         assert!(parser.parse(args).is_err());
     }
+
+    #[test]
+    fn col_negative_count_params_test() {
+        let args = vec!["n=uniform(-5..5)"];
+        let (parser, _) = prepare_parser();
+
+        assert!(parser.parse(args).is_err());
+    }
 }
@@ -0,0 +1,93 @@
+use anyhow::{Context, Result};
+
+use crate::settings::{
+    param::{ParamsParser, SimpleParamHandle},
+    ParsePayload,
+};
+
+pub struct ControlFileOption {
+    pub path: Option<String>,
+}
+
+impl ControlFileOption {
+    pub const CLI_STRING: &'static str = "-control-file";
+
+    pub fn description() -> &'static str {
+        "Apply the changes of the rate and the remaining duration made in a file during the run"
+    }
+
+    pub fn parse(cl_args: &mut ParsePayload) -> Result<Self> {
+        // The path is required only if the option is present.
+        let params = match cl_args.remove(Self::CLI_STRING) {
+            Some(params) => params,
+            None => return Ok(Self { path: None }),
+        };
+        let (parser, handles) = prepare_parser();
+        parser
+            .parse(params)
+            .context("Failed to parse -control-file option parameters")?;
+        Ok(Self::from_handles(handles))
+    }
+
+    pub fn print_help() {
+        let (parser, _) = prepare_parser();
+        parser.print_help();
+    }
+
+    pub fn print_settings(&self) {
+        if let Some(path) = &self.path {
+            println!("Control File:");
+            println!("  Path: {}", path);
+        }
+    }
+
+    fn from_handles(handles: ControlFileParamHandles) -> Self {
+        Self {
+            path: handles.path.get(),
+        }
+    }
+}
+
+struct ControlFileParamHandles {
+    path: SimpleParamHandle<String>,
+}
+
+fn prepare_parser() -> (ParamsParser, ControlFileParamHandles) {
+    let mut parser = ParamsParser::new(ControlFileOption::CLI_STRING);
+
+    let path = parser.simple_param(
+        "",
+        None,
+        "Path of the file polled every second for the rate (op/s or unlimited) and remaining_duration (e.g. 10m or unlimited) keys, in key = value lines",
+        true,
+    );
+
+    // $ ./cql-stress-cassandra-stress help -control-file
+    // Usage: -control-file ?
+    parser.group(&[&path]);
+
+    parser.example("/tmp/control");
+
+    (parser, ControlFileParamHandles { path })
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::settings::option::{control_file::prepare_parser, ControlFileOption};
+
+    #[test]
+    fn control_file_good_params_test() {
+        let (parser, handles) = prepare_parser();
+
+        assert!(parser.parse(vec!["/tmp/control"]).is_ok());
+
+        let params = ControlFileOption::from_handles(handles);
+        assert_eq!("/tmp/control", params.path.unwrap());
+    }
+
+    #[test]
+    fn control_file_bad_params_test() {
+        let (parser, _) = prepare_parser();
+        assert!(parser.parse(vec![]).is_err());
+    }
+}
@@ -0,0 +1,102 @@
+use std::num::NonZeroUsize;
+
+use anyhow::{Context, Result};
+
+use crate::settings::{
+    param::{ParamsParser, SimpleParamHandle},
+    ParsePayload,
+};
+
+pub struct DryRunOption {
+    pub operation_count: Option<NonZeroUsize>,
+}
+
+impl DryRunOption {
+    pub const CLI_STRING: &'static str = "-dry-run";
+
+    pub fn description() -> &'static str {
+        "Print the generated statements instead of running the benchmark"
+    }
+
+    pub fn parse(cl_args: &mut ParsePayload) -> Result<Self> {
+        // The count is required only if the option is present.
+        let params = match cl_args.remove(Self::CLI_STRING) {
+            Some(params) => params,
+            None => {
+                return Ok(Self {
+                    operation_count: None,
+                })
+            }
+        };
+        let (parser, handles) = prepare_parser();
+        parser
+            .parse(params)
+            .context("Failed to parse -dry-run option parameters")?;
+        Ok(Self::from_handles(handles))
+    }
+
+    pub fn print_help() {
+        let (parser, _) = prepare_parser();
+        parser.print_help();
+    }
+
+    pub fn print_settings(&self) {
+        if let Some(operation_count) = self.operation_count {
+            println!("Dry Run:");
+            println!("  Operations: {}", operation_count);
+        }
+    }
+
+    fn from_handles(handles: DryRunParamHandles) -> Self {
+        Self {
+            operation_count: handles.operation_count.get(),
+        }
+    }
+}
+
+struct DryRunParamHandles {
+    operation_count: SimpleParamHandle<NonZeroUsize>,
+}
+
+fn prepare_parser() -> (ParamsParser, DryRunParamHandles) {
+    let mut parser = ParamsParser::new(DryRunOption::CLI_STRING);
+
+    let operation_count = parser.simple_param(
+        "",
+        None,
+        "Number of operations of each type to print, with the statements and the values bound to them. The benchmark doesn't connect to the cluster",
+        true,
+    );
+
+    // $ ./cql-stress-cassandra-stress help -dry-run
+    // Usage: -dry-run ?
+    parser.group(&[&operation_count]);
+
+    parser.example("10");
+
+    (parser, DryRunParamHandles { operation_count })
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::settings::option::{dry_run::prepare_parser, DryRunOption};
+
+    #[test]
+    fn dry_run_good_params_test() {
+        let (parser, handles) = prepare_parser();
+
+        assert!(parser.parse(vec!["10"]).is_ok());
+
+        let params = DryRunOption::from_handles(handles);
+        assert_eq!(10, params.operation_count.unwrap().get());
+    }
+
+    #[test]
+    fn dry_run_bad_params_test() {
+        let (parser, _) = prepare_parser();
+        assert!(parser.parse(vec![]).is_err());
+
+        let (parser, _) = prepare_parser();
+        assert!(parser.parse(vec!["0"]).is_err());
+    }
+}
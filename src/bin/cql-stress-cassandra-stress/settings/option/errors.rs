@@ -0,0 +1,210 @@
+use anyhow::{Context, Result};
+use cql_stress::configuration::RetryBackoff;
+
+use crate::settings::{
+    param::{
+        types::{Latency, Parsable, Rate},
+        ParamsParser, SimpleParamHandle,
+    },
+    ParsePayload,
+};
+
+pub struct ErrorsOption {
+    pub retries: u64,
+    pub backoff: RetryBackoff,
+    pub budget: Option<u64>,
+}
+
+impl ErrorsOption {
+    pub const CLI_STRING: &'static str = "-errors";
+
+    pub fn description() -> &'static str {
+        "How to handle errors when encountered during stress"
+    }
+
+    pub fn parse(cl_args: &mut ParsePayload) -> Result<Self> {
+        let params = cl_args.remove(Self::CLI_STRING).unwrap_or_default();
+        let (parser, handles) = prepare_parser();
+        parser
+            .parse(params)
+            .context("Failed to parse -errors option parameters")?;
+        Self::from_handles(handles)
+    }
+
+    pub fn print_help() {
+        let (parser, _) = prepare_parser();
+        parser.print_help();
+    }
+
+    pub fn print_settings(&self) {
+        println!("Errors:");
+        println!("  Max Retries: {}", self.retries);
+        println!("  Retry Backoff: {}", self.backoff);
+        match self.budget {
+            Some(budget) => println!("  Retry Budget: {}/s", budget),
+            None => println!("  Retry Budget: unlimited"),
+        }
+    }
+
+    fn from_handles(handles: ErrorsParamHandles) -> Result<Self> {
+        let budget = handles.budget.get();
+        anyhow::ensure!(budget != Some(0), "budget= must be positive");
+        Ok(Self {
+            retries: handles.retries.get().unwrap(),
+            backoff: handles.backoff.get().unwrap(),
+            budget,
+        })
+    }
+}
+
+/// The delay between the retries, given as `none`, `fixed(<delay>)`
+/// or `exponential(<min>..<max>)`.
+struct Backoff;
+
+impl Parsable for Backoff {
+    type Parsed = RetryBackoff;
+
+    fn parse(s: &str) -> Result<Self::Parsed> {
+        let invalid = || format!("Invalid backoff: {}", s);
+        if s == "none" {
+            return Ok(RetryBackoff::None);
+        }
+        let (kind, args) = s
+            .strip_suffix(')')
+            .and_then(|s| s.split_once('('))
+            .with_context(invalid)?;
+        match kind {
+            "fixed" => Ok(RetryBackoff::Fixed(
+                Latency::parse(args).with_context(invalid)?,
+            )),
+            "exponential" => {
+                let (min, max) = args.split_once("..").with_context(invalid)?;
+                let min = Latency::parse(min).with_context(invalid)?;
+                let max = Latency::parse(max).with_context(invalid)?;
+                anyhow::ensure!(
+                    min <= max,
+                    "The minimum of backoff {} exceeds the maximum",
+                    s
+                );
+                Ok(RetryBackoff::Exponential { min, max })
+            }
+            _ => Err(anyhow::anyhow!(invalid())),
+        }
+    }
+
+    fn value_pattern() -> Option<&'static str> {
+        Some(r"none|fixed\([0-9]+(us|ms|s)\)|exponential\([0-9]+(us|ms|s)..[0-9]+(us|ms|s)\)")
+    }
+}
+
+struct ErrorsParamHandles {
+    retries: SimpleParamHandle<u64>,
+    backoff: SimpleParamHandle<Backoff>,
+    budget: SimpleParamHandle<Rate>,
+}
+
+fn prepare_parser() -> (ParamsParser, ErrorsParamHandles) {
+    let mut parser = ParamsParser::new(ErrorsOption::CLI_STRING);
+
+    let retries = parser.simple_param(
+        "retries=",
+        Some("9"),
+        "Number of tries to perform for each operation before failing",
+        false,
+    );
+    let backoff = parser.simple_param(
+        "backoff=",
+        Some("none"),
+        "Delay before retrying a failed operation: none, fixed(<delay>), or exponential(<min>..<max>), which doubles with each retry and is randomized between its half and its whole",
+        false,
+    );
+    let budget = parser.simple_param(
+        "budget=",
+        None,
+        "Maximum number of retries per second across all threads. The operations failing after the budget is spent are not retried",
+        false,
+    );
+
+    // $ ./cql-stress-cassandra-stress help -errors
+    // Usage: -errors [retries=?] [backoff=?] [budget=?]
+    parser.group(&[&retries, &backoff, &budget]);
+
+    parser.example("retries=5 backoff=exponential(10ms..1s) budget=100/s");
+
+    (
+        parser,
+        ErrorsParamHandles {
+            retries,
+            backoff,
+            budget,
+        },
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use cql_stress::configuration::RetryBackoff;
+
+    use crate::settings::{
+        option::{errors::prepare_parser, ErrorsOption},
+        param::types::Parsable,
+    };
+
+    use super::Backoff;
+
+    #[test]
+    fn errors_good_params_test() {
+        let (parser, handles) = prepare_parser();
+
+        assert!(parser
+            .parse(vec!["retries=3", "backoff=fixed(50ms)", "budget=20/s"])
+            .is_ok());
+
+        let params = ErrorsOption::from_handles(handles).unwrap();
+        assert_eq!(3, params.retries);
+        assert_eq!(
+            RetryBackoff::Fixed(Duration::from_millis(50)),
+            params.backoff
+        );
+        assert_eq!(Some(20), params.budget);
+    }
+
+    #[test]
+    fn errors_default_params_test() {
+        let (parser, handles) = prepare_parser();
+
+        assert!(parser.parse(vec![]).is_ok());
+
+        let params = ErrorsOption::from_handles(handles).unwrap();
+        assert_eq!(9, params.retries);
+        assert_eq!(RetryBackoff::None, params.backoff);
+        assert_eq!(None, params.budget);
+    }
+
+    #[test]
+    fn errors_zero_budget_test() {
+        let (parser, handles) = prepare_parser();
+
+        assert!(parser.parse(vec!["budget=0/s"]).is_ok());
+        assert!(ErrorsOption::from_handles(handles).is_err());
+    }
+
+    #[test]
+    fn backoff_parse_test() {
+        assert_eq!(
+            RetryBackoff::Exponential {
+                min: Duration::from_millis(10),
+                max: Duration::from_secs(1),
+            },
+            Backoff::parse("exponential(10ms..1s)").unwrap()
+        );
+
+        assert!(Backoff::parse("fixed").is_err());
+        assert!(Backoff::parse("fixed(0ms)").is_err());
+        assert!(Backoff::parse("exponential(10ms)").is_err());
+        assert!(Backoff::parse("exponential(1s..10ms)").is_err());
+        assert!(Backoff::parse("linear(10ms)").is_err());
+    }
+}
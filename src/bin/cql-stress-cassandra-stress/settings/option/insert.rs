@@ -0,0 +1,119 @@
+use anyhow::{Context, Result};
+
+use crate::{
+    java_generate::distribution::DistributionFactory,
+    settings::{
+        param::{ParamsParser, SimpleParamHandle},
+        ParsePayload,
+    },
+};
+
+pub struct InsertOption {
+    /// Number of the writes each partition is split into. None if the partitions
+    /// are written whole, which is the same as `visits=fixed(1)`.
+    pub visits: Option<Box<dyn DistributionFactory>>,
+    pub revisit: Box<dyn DistributionFactory>,
+}
+
+impl InsertOption {
+    pub const CLI_STRING: &'static str = "-insert";
+
+    pub fn description() -> &'static str {
+        "Insert specific options relating to various methods for batching and splitting partition updates"
+    }
+
+    pub fn parse(cl_args: &mut ParsePayload) -> Result<Self> {
+        let params = cl_args.remove(Self::CLI_STRING).unwrap_or_default();
+        let (parser, handles) = prepare_parser();
+        parser
+            .parse(params)
+            .context("Failed to parse -insert option parameters")?;
+        Ok(Self::from_handles(handles))
+    }
+
+    pub fn print_help() {
+        let (parser, _) = prepare_parser();
+        parser.print_help();
+    }
+
+    pub fn print_settings(&self) {
+        println!("Insert:");
+        match &self.visits {
+            Some(visits) => println!("  Visits: {}", visits),
+            None => println!("  Visits: FIXED(1)"),
+        }
+        println!("  Revisits: {}", self.revisit);
+    }
+
+    fn from_handles(handles: InsertParamHandles) -> Self {
+        Self {
+            visits: handles.visits.get(),
+            revisit: handles.revisit.get().unwrap(),
+        }
+    }
+}
+
+struct InsertParamHandles {
+    visits: SimpleParamHandle<Box<dyn DistributionFactory>>,
+    revisit: SimpleParamHandle<Box<dyn DistributionFactory>>,
+}
+
+fn prepare_parser() -> (ParamsParser, InsertParamHandles) {
+    let mut parser = ParamsParser::new(InsertOption::CLI_STRING);
+
+    let visits = parser.distribution_param(
+        "visits=",
+        None,
+        "The target number of inserts to split a partition into (-col clustering=); if more than one, the partition will be placed in the revisit set. Defaults to fixed(1)",
+        false,
+    );
+    let revisit = parser.distribution_param(
+        "revisit=",
+        Some("uniform(1..1000000)"),
+        "The distribution with which we revisit partial writes (see visits); implicitly defines size of the write-partition buffer",
+        false,
+    );
+
+    // $ ./cql-stress-cassandra-stress help -insert
+    // Usage: -insert [visits=DIST(?)] [revisit=DIST(?)]
+    parser.group(&[&visits, &revisit]);
+
+    parser.example("visits=fixed(4) revisit=uniform(1..100)");
+
+    (parser, InsertParamHandles { visits, revisit })
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::settings::option::{insert::prepare_parser, InsertOption};
+
+    #[test]
+    fn insert_good_params_test() {
+        let (parser, handles) = prepare_parser();
+
+        assert!(parser
+            .parse(vec!["visits=fixed(4)", "revisit=uniform(1..100)"])
+            .is_ok());
+
+        let params = InsertOption::from_handles(handles);
+        assert_eq!("FIXED(4)", params.visits.unwrap().to_string());
+        assert_eq!("UNIFORM(1..100)", params.revisit.to_string());
+    }
+
+    #[test]
+    fn insert_default_params_test() {
+        let (parser, handles) = prepare_parser();
+
+        assert!(parser.parse(vec![]).is_ok());
+
+        let params = InsertOption::from_handles(handles);
+        assert!(params.visits.is_none());
+        assert_eq!("UNIFORM(1..1000000)", params.revisit.to_string());
+    }
+
+    #[test]
+    fn insert_bad_params_test() {
+        let (parser, _) = prepare_parser();
+        assert!(parser.parse(vec!["visits=foo(1)"]).is_err());
+    }
+}
@@ -0,0 +1,479 @@
+use std::time::Duration;
+
+use anyhow::{Context, Result};
+
+use crate::settings::{
+    param::{
+        types::{Latency, LogInterval, Parsable, UnitInterval},
+        ParamsParser, SimpleParamHandle,
+    },
+    ParsePayload,
+};
+
+pub struct LogOption {
+    pub errors_file: Option<String>,
+    pub errors_rate: u64,
+    pub trace_file: Option<String>,
+    pub trace_ratio: f64,
+    pub level: OutputLevel,
+    /// Whether the latency of the operations is recorded to the histograms.
+    pub measure_latency: bool,
+    /// The number of significant figures of the latency histograms.
+    pub hdr_sig_figs: u8,
+    /// The highest latency tracked by the histograms. If not set,
+    /// the histograms grow as needed.
+    pub hdr_max_latency: Option<Duration>,
+    /// The file the latency histograms of each interval are written to,
+    /// in the CSV format of [cql_stress::latency_heatmap].
+    pub heatmap_file: Option<String>,
+    /// How often the stats of the last interval are printed
+    /// (and written to the heatmap and reported to the `-report` server).
+    pub interval: Duration,
+    /// How often the summary of the operations performed so far is printed.
+    pub summary_interval: Option<Duration>,
+    /// How often the heatmap file is flushed. If not set, it's flushed
+    /// only at the end of the benchmark and on the dump signals.
+    pub flush_interval: Option<Duration>,
+    /// Whether the snapshot of the cluster is printed at the start and at the end.
+    pub cluster_info: bool,
+}
+
+/// How much is printed during the benchmark.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum OutputLevel {
+    /// Only the final summary.
+    Minimal,
+    /// The settings, the stats of each interval and the final summary.
+    Normal,
+    /// Additionally, the metrics of the driver (e.g. the number of retries).
+    Verbose,
+}
+
+impl std::fmt::Display for OutputLevel {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            Self::Minimal => "MINIMAL",
+            Self::Normal => "NORMAL",
+            Self::Verbose => "VERBOSE",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+impl Parsable for OutputLevel {
+    type Parsed = OutputLevel;
+
+    fn parse(s: &str) -> Result<Self::Parsed> {
+        match s.to_lowercase().as_str() {
+            "minimal" => Ok(Self::Minimal),
+            "normal" => Ok(Self::Normal),
+            "verbose" => Ok(Self::Verbose),
+            _ => anyhow::bail!(
+                "Invalid output level: {}. Must be one of: minimal|normal|verbose",
+                s
+            ),
+        }
+    }
+
+    fn value_pattern() -> Option<&'static str> {
+        Some("minimal|normal|verbose")
+    }
+}
+
+impl LogOption {
+    pub const CLI_STRING: &'static str = "-log";
+
+    pub fn description() -> &'static str {
+        "Where to log the failed and traced operations and how much to print"
+    }
+
+    pub fn parse(cl_args: &mut ParsePayload) -> Result<Self> {
+        let params = cl_args.remove(Self::CLI_STRING).unwrap_or_default();
+        let (parser, handles) = prepare_parser();
+        parser
+            .parse(params)
+            .context("Failed to parse -log option parameters")?;
+        Self::from_handles(handles)
+    }
+
+    pub fn print_help() {
+        let (parser, _) = prepare_parser();
+        parser.print_help();
+    }
+
+    pub fn print_settings(&self) {
+        println!("Log:");
+        println!("  Level: {}", self.level);
+        if let Some(errors_file) = &self.errors_file {
+            println!("  Errors file: {}", errors_file);
+            println!("  Errors rate: {}/s", self.errors_rate);
+        }
+        if let Some(trace_file) = &self.trace_file {
+            println!("  Trace file: {}", trace_file);
+            println!("  Trace ratio: {}", self.trace_ratio);
+        }
+        println!("  Interval: {:?}", self.interval);
+        if let Some(summary_interval) = self.summary_interval {
+            println!("  Summary interval: {:?}", summary_interval);
+        }
+        if let Some(flush_interval) = self.flush_interval {
+            println!("  Flush interval: {:?}", flush_interval);
+        }
+        println!("  Cluster info: {}", self.cluster_info);
+        if !self.measure_latency {
+            println!("  Latency: not measured");
+            return;
+        }
+        println!("  Histogram significant figures: {}", self.hdr_sig_figs);
+        if let Some(max_latency) = self.hdr_max_latency {
+            println!("  Histogram max latency: {:?}", max_latency);
+        }
+        if let Some(heatmap_file) = &self.heatmap_file {
+            println!("  Latency heatmap file: {}", heatmap_file);
+        }
+    }
+
+    fn from_handles(handles: LogParamHandles) -> Result<Self> {
+        let errors_file = handles.errors_file.get();
+        let errors_rate = handles.errors_rate.get().unwrap();
+        let trace_file = handles.trace_file.get();
+        let trace_ratio = handles.trace_ratio.get().unwrap();
+        let level = handles.level.get().unwrap();
+        let measure_latency = handles.no_latency.get().is_none();
+        let hdr_sig_figs = handles.hdr_sig_figs.get().unwrap();
+        let hdr_max_latency = handles.hdr_max_latency.get();
+        let heatmap_file = handles.heatmap_file.get();
+        let interval = handles.interval.get().unwrap();
+        let summary_interval = handles.summary_interval.get();
+        let flush_interval = handles.flush_interval.get();
+        let cluster_info = handles.cluster_info.get().is_some();
+
+        anyhow::ensure!(errors_rate > 0, "errorsrate must be positive");
+        anyhow::ensure!(trace_ratio > 0.0, "traceratio must be positive");
+        // The limits of the histograms.
+        anyhow::ensure!(
+            (1..=5).contains(&hdr_sig_figs),
+            "hdrsigfigs must be between 1 and 5"
+        );
+        anyhow::ensure!(
+            heatmap_file.is_none() || measure_latency,
+            "heatmap cannot be used together with no-latency"
+        );
+        anyhow::ensure!(
+            summary_interval.map_or(true, |summary_interval| summary_interval >= interval),
+            "summary-interval cannot be shorter than interval"
+        );
+        anyhow::ensure!(
+            flush_interval.is_none() || heatmap_file.is_some(),
+            "flush-interval requires heatmap"
+        );
+
+        Ok(Self {
+            errors_file,
+            errors_rate,
+            trace_file,
+            trace_ratio,
+            level,
+            measure_latency,
+            hdr_sig_figs: hdr_sig_figs as u8,
+            hdr_max_latency,
+            heatmap_file,
+            interval,
+            summary_interval,
+            flush_interval,
+            cluster_info,
+        })
+    }
+}
+
+struct LogParamHandles {
+    errors_file: SimpleParamHandle<String>,
+    errors_rate: SimpleParamHandle<u64>,
+    trace_file: SimpleParamHandle<String>,
+    trace_ratio: SimpleParamHandle<UnitInterval>,
+    level: SimpleParamHandle<OutputLevel>,
+    hdr_sig_figs: SimpleParamHandle<u64>,
+    hdr_max_latency: SimpleParamHandle<Latency>,
+    no_latency: SimpleParamHandle<bool>,
+    heatmap_file: SimpleParamHandle<String>,
+    interval: SimpleParamHandle<LogInterval>,
+    summary_interval: SimpleParamHandle<LogInterval>,
+    flush_interval: SimpleParamHandle<LogInterval>,
+    cluster_info: SimpleParamHandle<bool>,
+}
+
+fn prepare_parser() -> (ParamsParser, LogParamHandles) {
+    let mut parser = ParamsParser::new(LogOption::CLI_STRING);
+
+    let errors_file = parser.simple_param(
+        "errorsfile=",
+        None,
+        "Log the failed operations (partition key, operation and error) to this file",
+        false,
+    );
+    let errors_rate = parser.simple_param(
+        "errorsrate=",
+        Some("100"),
+        "Maximum number of failed operations logged per second",
+        false,
+    );
+    let trace_file = parser.simple_param(
+        "tracefile=",
+        None,
+        "Enable server-side tracing of a sample of operations and log their tracing session ids to this file",
+        false,
+    );
+    let trace_ratio = parser.simple_param(
+        "traceratio=",
+        Some("0.001"),
+        "Fraction of the operations traced if tracefile is given",
+        false,
+    );
+    let level = parser.simple_param(
+        "level=",
+        Some("normal"),
+        "Logging level (minimal prints only the final summary, verbose adds the driver metrics)",
+        false,
+    );
+    let hdr_sig_figs = parser.simple_param(
+        "hdrsigfigs=",
+        Some("3"),
+        "Number of significant figures of the latency histograms (1-5), lower values use less memory",
+        false,
+    );
+    let hdr_max_latency = parser.simple_param(
+        "hdrmaxlatency=",
+        None,
+        "Highest latency tracked by the histograms, higher latencies are recorded as this value (by default, the histograms grow as needed)",
+        false,
+    );
+    let no_latency = parser.simple_param(
+        "no-latency",
+        None,
+        "Don't measure the latency of the operations",
+        false,
+    );
+    let heatmap_file = parser.simple_param(
+        "heatmap=",
+        None,
+        "Write the latency histogram of each interval to this CSV file, with fixed latency buckets suitable for latency-over-time heatmaps",
+        false,
+    );
+    let interval = parser.simple_param(
+        "interval=",
+        Some("1s"),
+        "How often the stats of the last interval are printed (seconds by default, e.g. 5 or 500ms)",
+        false,
+    );
+    let summary_interval = parser.simple_param(
+        "summary-interval=",
+        None,
+        "How often the summary of the operations performed so far is printed, e.g. 60s",
+        false,
+    );
+    let flush_interval = parser.simple_param(
+        "flush-interval=",
+        None,
+        "How often the heatmap file is flushed (by default, at the end of the benchmark)",
+        false,
+    );
+    let cluster_info = parser.simple_param(
+        "cluster-info",
+        None,
+        "Print the name of the cluster and the location, the state and the version of each node at the start and at the end of the run",
+        false,
+    );
+
+    // $ ./cql-stress-cassandra-stress help -log
+    // Usage: -log [errorsfile=?] [errorsrate=?] [tracefile=?] [traceratio=?] [level=?] [hdrsigfigs=?] [hdrmaxlatency=?] [no-latency] [heatmap=?] [interval=?] [summary-interval=?] [flush-interval=?] [cluster-info]
+    parser.group(&[
+        &errors_file,
+        &errors_rate,
+        &trace_file,
+        &trace_ratio,
+        &level,
+        &hdr_sig_figs,
+        &hdr_max_latency,
+        &no_latency,
+        &heatmap_file,
+        &interval,
+        &summary_interval,
+        &flush_interval,
+        &cluster_info,
+    ]);
+
+    parser.example("errorsfile=errors.log errorsrate=10");
+    parser.example("tracefile=traces.log traceratio=0.0001");
+    parser.example("level=verbose");
+    parser.example("hdrsigfigs=2 hdrmaxlatency=60s");
+    parser.example("no-latency");
+    parser.example("heatmap=latency.csv");
+    parser.example("interval=5s summary-interval=60s");
+    parser.example("level=minimal cluster-info");
+
+    (
+        parser,
+        LogParamHandles {
+            errors_file,
+            errors_rate,
+            trace_file,
+            trace_ratio,
+            level,
+            hdr_sig_figs,
+            hdr_max_latency,
+            no_latency,
+            heatmap_file,
+            interval,
+            summary_interval,
+            flush_interval,
+            cluster_info,
+        },
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use crate::settings::option::{
+        log::{prepare_parser, OutputLevel},
+        LogOption,
+    };
+
+    #[test]
+    fn log_good_params_test() {
+        let args = vec!["errorsfile=/tmp/errors.log", "errorsrate=10"];
+        let (parser, handles) = prepare_parser();
+
+        assert!(parser.parse(args).is_ok());
+
+        let params = LogOption::from_handles(handles).unwrap();
+        assert_eq!(Some("/tmp/errors.log"), params.errors_file.as_deref());
+        assert_eq!(10, params.errors_rate);
+    }
+
+    #[test]
+    fn log_default_params_test() {
+        let (parser, handles) = prepare_parser();
+
+        assert!(parser.parse(vec![]).is_ok());
+
+        let params = LogOption::from_handles(handles).unwrap();
+        assert!(params.errors_file.is_none());
+        assert_eq!(100, params.errors_rate);
+        assert!(params.trace_file.is_none());
+        assert_eq!(0.001, params.trace_ratio);
+        assert_eq!(OutputLevel::Normal, params.level);
+        assert!(params.measure_latency);
+        assert_eq!(3, params.hdr_sig_figs);
+        assert!(params.hdr_max_latency.is_none());
+        assert!(params.heatmap_file.is_none());
+        assert_eq!(Duration::from_secs(1), params.interval);
+        assert!(params.summary_interval.is_none());
+        assert!(params.flush_interval.is_none());
+        assert!(!params.cluster_info);
+    }
+
+    #[test]
+    fn log_trace_params_test() {
+        let args = vec!["tracefile=/tmp/traces.log", "traceratio=0.01"];
+        let (parser, handles) = prepare_parser();
+
+        assert!(parser.parse(args).is_ok());
+
+        let params = LogOption::from_handles(handles).unwrap();
+        assert_eq!(Some("/tmp/traces.log"), params.trace_file.as_deref());
+        assert_eq!(0.01, params.trace_ratio);
+    }
+
+    #[test]
+    fn log_level_params_test() {
+        let (parser, handles) = prepare_parser();
+
+        assert!(parser.parse(vec!["level=VERBOSE"]).is_ok());
+
+        let params = LogOption::from_handles(handles).unwrap();
+        assert_eq!(OutputLevel::Verbose, params.level);
+    }
+
+    #[test]
+    fn log_cluster_info_params_test() {
+        let (parser, handles) = prepare_parser();
+
+        assert!(parser.parse(vec!["level=minimal", "cluster-info"]).is_ok());
+
+        let params = LogOption::from_handles(handles).unwrap();
+        assert!(params.cluster_info);
+    }
+
+    #[test]
+    fn log_histogram_params_test() {
+        let (parser, handles) = prepare_parser();
+
+        assert!(parser
+            .parse(vec!["hdrsigfigs=2", "hdrmaxlatency=60s"])
+            .is_ok());
+
+        let params = LogOption::from_handles(handles).unwrap();
+        assert!(params.measure_latency);
+        assert_eq!(2, params.hdr_sig_figs);
+        assert_eq!(Some(Duration::from_secs(60)), params.hdr_max_latency);
+
+        let (parser, handles) = prepare_parser();
+        assert!(parser.parse(vec!["no-latency"]).is_ok());
+        assert!(!LogOption::from_handles(handles).unwrap().measure_latency);
+
+        let (parser, handles) = prepare_parser();
+        assert!(parser.parse(vec!["hdrsigfigs=6"]).is_ok());
+        assert!(LogOption::from_handles(handles).is_err());
+    }
+
+    #[test]
+    fn log_heatmap_params_test() {
+        let (parser, handles) = prepare_parser();
+        assert!(parser.parse(vec!["heatmap=/tmp/latency.csv"]).is_ok());
+        let params = LogOption::from_handles(handles).unwrap();
+        assert_eq!(Some("/tmp/latency.csv"), params.heatmap_file.as_deref());
+
+        let (parser, handles) = prepare_parser();
+        assert!(parser
+            .parse(vec!["heatmap=/tmp/latency.csv", "no-latency"])
+            .is_ok());
+        assert!(LogOption::from_handles(handles).is_err());
+    }
+
+    #[test]
+    fn log_interval_params_test() {
+        let (parser, handles) = prepare_parser();
+        assert!(parser
+            .parse(vec![
+                "interval=500ms",
+                "summary-interval=60",
+                "heatmap=/tmp/latency.csv",
+                "flush-interval=10s"
+            ])
+            .is_ok());
+        let params = LogOption::from_handles(handles).unwrap();
+        assert_eq!(Duration::from_millis(500), params.interval);
+        assert_eq!(Some(Duration::from_secs(60)), params.summary_interval);
+        assert_eq!(Some(Duration::from_secs(10)), params.flush_interval);
+
+        let (parser, handles) = prepare_parser();
+        assert!(parser
+            .parse(vec!["interval=5s", "summary-interval=1s"])
+            .is_ok());
+        assert!(LogOption::from_handles(handles).is_err());
+
+        let (parser, handles) = prepare_parser();
+        assert!(parser.parse(vec!["flush-interval=10s"]).is_ok());
+        assert!(LogOption::from_handles(handles).is_err());
+    }
+
+    #[test]
+    fn log_bad_params_test() {
+        let (parser, handles) = prepare_parser();
+
+        assert!(parser.parse(vec!["errorsrate=0"]).is_ok());
+        assert!(LogOption::from_handles(handles).is_err());
+    }
+}
@@ -1,19 +1,38 @@
 mod column;
+mod control_file;
+mod dry_run;
+mod errors;
+mod insert;
+mod log;
 mod mode;
 mod node;
 mod population;
 mod rate;
+mod report;
 mod schema;
+mod seed;
+mod transport;
 
 use anyhow::Result;
 
 pub use column::ColumnOption;
+pub use column::{CHECKSUM_HEADER_SIZE, CLUSTERING_COLUMN};
+pub use control_file::ControlFileOption;
+pub use dry_run::DryRunOption;
+pub use errors::ErrorsOption;
+pub use insert::InsertOption;
+pub use log::LogOption;
+pub use log::OutputLevel;
 pub use mode::ModeOption;
 pub use node::NodeOption;
 pub use population::PopulationOption;
+pub use population::SequenceEnd;
 pub use rate::RateOption;
 pub use rate::ThreadsInfo;
+pub use report::ReportOption;
 pub use schema::SchemaOption;
+pub use seed::SeedOption;
+pub use transport::TransportOption;
 
 pub struct Options;
 
@@ -23,7 +42,18 @@ impl Options {
             (NodeOption::CLI_STRING, NodeOption::description()),
             (RateOption::CLI_STRING, RateOption::description()),
             (ModeOption::CLI_STRING, ModeOption::description()),
+            (ErrorsOption::CLI_STRING, ErrorsOption::description()),
+            (InsertOption::CLI_STRING, InsertOption::description()),
             (SchemaOption::CLI_STRING, SchemaOption::description()),
+            (TransportOption::CLI_STRING, TransportOption::description()),
+            (LogOption::CLI_STRING, LogOption::description()),
+            (SeedOption::CLI_STRING, SeedOption::description()),
+            (DryRunOption::CLI_STRING, DryRunOption::description()),
+            (ReportOption::CLI_STRING, ReportOption::description()),
+            (
+                ControlFileOption::CLI_STRING,
+                ControlFileOption::description(),
+            ),
             (ColumnOption::CLI_STRING, ColumnOption::description()),
             (
                 PopulationOption::CLI_STRING,
@@ -48,6 +78,14 @@ impl Options {
             ColumnOption::CLI_STRING => ColumnOption::print_help(),
             PopulationOption::CLI_STRING => PopulationOption::print_help(),
             ModeOption::CLI_STRING => ModeOption::print_help(),
+            ErrorsOption::CLI_STRING => ErrorsOption::print_help(),
+            InsertOption::CLI_STRING => InsertOption::print_help(),
+            TransportOption::CLI_STRING => TransportOption::print_help(),
+            LogOption::CLI_STRING => LogOption::print_help(),
+            SeedOption::CLI_STRING => SeedOption::print_help(),
+            DryRunOption::CLI_STRING => DryRunOption::print_help(),
+            ReportOption::CLI_STRING => ReportOption::print_help(),
+            ControlFileOption::CLI_STRING => ControlFileOption::print_help(),
             _ => return Err(anyhow::anyhow!("Invalid option provided to command help")),
         }
 
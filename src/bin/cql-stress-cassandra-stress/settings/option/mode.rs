@@ -1,14 +1,114 @@
+use std::{
+    num::{NonZeroU32, NonZeroUsize},
+    str::FromStr,
+    time::Duration,
+};
+
 use anyhow::{Context, Result};
-use scylla::{frame::Compression, transport::session::PoolSize};
+use scylla::{
+    frame::Compression,
+    retry_policy::{DefaultRetryPolicy, FallthroughRetryPolicy, RetryPolicy},
+    transport::{
+        downgrading_consistency_retry_policy::DowngradingConsistencyRetryPolicy, session::PoolSize,
+    },
+};
+use strum::IntoEnumIterator;
+use strum_macros::{AsRefStr, EnumIter, EnumString};
 
 use crate::settings::{
     param::{
-        types::{ConnectionsPerHost, ConnectionsPerShard},
+        types::{ConnectionsPerHost, ConnectionsPerShard, ExplicitBool, Parsable},
         ParamsParser, SimpleParamHandle,
     },
     ParsePayload,
 };
 
+/// Retry policy used by the driver.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, AsRefStr, EnumString, EnumIter)]
+#[strum(serialize_all = "lowercase")]
+#[strum(ascii_case_insensitive)]
+pub enum RetryPolicyKind {
+    Default,
+    Fallthrough,
+    Downgrading,
+}
+
+impl RetryPolicyKind {
+    fn show(&self) -> &str {
+        self.as_ref()
+    }
+
+    pub fn create(&self) -> Box<dyn RetryPolicy> {
+        match self {
+            RetryPolicyKind::Default => Box::new(DefaultRetryPolicy::new()),
+            RetryPolicyKind::Fallthrough => Box::new(FallthroughRetryPolicy::new()),
+            RetryPolicyKind::Downgrading => Box::new(DowngradingConsistencyRetryPolicy::new()),
+        }
+    }
+}
+
+impl Parsable for RetryPolicyKind {
+    type Parsed = RetryPolicyKind;
+
+    fn parse(s: &str) -> Result<Self::Parsed> {
+        let create_err_msg = || {
+            let concat = Self::iter()
+                .map(|policy| policy.show().to_owned())
+                .collect::<Vec<String>>()
+                .join("|");
+
+            format!("Invalid retry policy: {}. Must be one of: {}", s, concat)
+        };
+
+        Self::from_str(s).with_context(create_err_msg)
+    }
+}
+
+/// Partitioner of the cluster, which maps the partition keys to the tokens.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, AsRefStr, EnumString, EnumIter)]
+#[strum(serialize_all = "lowercase")]
+#[strum(ascii_case_insensitive)]
+pub enum PartitionerKind {
+    #[strum(to_string = "murmur3", serialize = "Murmur3Partitioner")]
+    Murmur3,
+    #[strum(to_string = "byteordered", serialize = "ByteOrderedPartitioner")]
+    ByteOrdered,
+}
+
+impl PartitionerKind {
+    fn show(&self) -> &str {
+        self.as_ref()
+    }
+
+    /// Whether the driver can compute the tokens of the keys, so that
+    /// the requests are routed to the replicas. The driver computes
+    /// the tokens of Murmur3Partitioner, and would route the requests
+    /// to random nodes under ByteOrderedPartitioner.
+    pub fn token_aware(&self) -> bool {
+        match self {
+            PartitionerKind::Murmur3 => true,
+            PartitionerKind::ByteOrdered => false,
+        }
+    }
+}
+
+impl Parsable for PartitionerKind {
+    type Parsed = PartitionerKind;
+
+    fn parse(s: &str) -> Result<Self::Parsed> {
+        let create_err_msg = || {
+            let concat = Self::iter()
+                .map(|partitioner| partitioner.show().to_owned())
+                .collect::<Vec<String>>()
+                .join("|");
+
+            format!("Invalid partitioner: {}. Must be one of: {}", s, concat)
+        };
+
+        Self::from_str(s).with_context(create_err_msg)
+    }
+}
+
 #[derive(PartialEq, Eq, Debug)]
 pub struct Credentials {
     pub username: String,
@@ -18,7 +118,22 @@ pub struct Credentials {
 pub struct ModeOption {
     pub compression: Option<Compression>,
     pub user_credentials: Option<Credentials>,
+    /// The authenticator plugin, given as `name[:options]`.
+    /// Requires the `auth-plugins` crate feature.
+    pub authenticator: Option<String>,
     pub pool_size: PoolSize,
+    /// The maximum number of requests in flight per connection.
+    pub max_pending: Option<NonZeroUsize>,
+    pub request_timeout: Option<Duration>,
+    pub retry_policy: RetryPolicyKind,
+    /// If false, the connections are opened per host (see `pool_size`)
+    /// and the shard-aware port isn't used.
+    pub shard_aware: bool,
+    pub partitioner: PartitionerKind,
+    pub keepalive_interval: Option<Duration>,
+    /// The page size of the reads. If set, the reads fetch all of the pages
+    /// of the result, otherwise they are executed unpaged.
+    pub fetch_size: Option<i32>,
 }
 
 impl ModeOption {
@@ -49,7 +164,33 @@ impl ModeOption {
             println!("  Username: {}", creds.username);
             println!("  Password: {}", creds.password);
         }
+        if let Some(authenticator) = &self.authenticator {
+            println!("  Authenticator: {}", authenticator);
+        }
         println!("  Pool size: {:?}", self.pool_size);
+        if let Some(max_pending) = self.max_pending {
+            println!("  Max pending per connection: {}", max_pending);
+        }
+        match self.request_timeout {
+            Some(timeout) => println!("  Request timeout: {} MILLISECONDS", timeout.as_millis()),
+            None => println!("  Request timeout: driver default"),
+        }
+        println!("  Retry policy: {}", self.retry_policy.show());
+        println!("  Shard aware: {}", self.shard_aware);
+        println!("  Partitioner: {}", self.partitioner.show());
+        match self.keepalive_interval {
+            Some(interval) => {
+                println!(
+                    "  Keepalive interval: {} MILLISECONDS",
+                    interval.as_millis()
+                )
+            }
+            None => println!("  Keepalive interval: driver default"),
+        }
+        match self.fetch_size {
+            Some(fetch_size) => println!("  Fetch size: {}", fetch_size),
+            None => println!("  Fetch size: unpaged"),
+        }
     }
 
     fn from_handles(handles: ModeParamHandles) -> Result<ModeOption> {
@@ -63,15 +204,63 @@ impl ModeOption {
                 ))
             }
         };
+        let authenticator = handles.authenticator.get();
+        if let Some(authenticator) = &authenticator {
+            anyhow::ensure!(
+                user_credentials.is_none(),
+                "authenticator= cannot be used together with user= and password="
+            );
+            #[cfg(feature = "auth-plugins")]
+            cql_stress::auth::AuthenticatorSpec::parse(authenticator)?;
+            #[cfg(not(feature = "auth-plugins"))]
+            anyhow::bail!(
+                "authenticator={} requires the auth-plugins crate feature, which is disabled in this build (see --list-features)",
+                authenticator
+            );
+        }
         let pool_size = match handles.connections_per_shard.get() {
             Some(per_shard) => per_shard,
             None => handles.connections_per_host.get().unwrap(),
         };
+        let max_pending = handles.max_pending.get();
+        let request_timeout = handles.request_timeout.get();
+        let retry_policy = handles.retry_policy.get().unwrap();
+        anyhow::ensure!(
+            request_timeout != Some(Duration::ZERO),
+            "Request timeout must be positive"
+        );
+        let shard_aware = handles.shard_aware.get().unwrap();
+        // Without shard awareness, the connections aren't bound to the shards,
+        // so the driver can't route the requests to them either.
+        let pool_size = match pool_size {
+            PoolSize::PerShard(connections) if !shard_aware => PoolSize::PerHost(connections),
+            pool_size => pool_size,
+        };
+        let partitioner = handles.partitioner.get().unwrap();
+        let keepalive_interval = handles.keepalive_interval.get();
+        anyhow::ensure!(
+            keepalive_interval != Some(Duration::ZERO),
+            "Keepalive interval must be positive"
+        );
+        let fetch_size = handles
+            .fetch_size
+            .get()
+            .map(|fetch_size| i32::try_from(fetch_size.get()))
+            .transpose()
+            .context("Fetch size cannot exceed i32::MAX")?;
 
         Ok(Self {
             compression,
             user_credentials,
+            authenticator,
             pool_size,
+            max_pending,
+            request_timeout,
+            retry_policy,
+            shard_aware,
+            partitioner,
+            keepalive_interval,
+            fetch_size,
         })
     }
 }
@@ -80,8 +269,16 @@ struct ModeParamHandles {
     compression: SimpleParamHandle<Option<Compression>>,
     username: SimpleParamHandle<String>,
     password: SimpleParamHandle<String>,
+    authenticator: SimpleParamHandle<String>,
     connections_per_host: SimpleParamHandle<ConnectionsPerHost>,
     connections_per_shard: SimpleParamHandle<ConnectionsPerShard>,
+    max_pending: SimpleParamHandle<NonZeroUsize>,
+    request_timeout: SimpleParamHandle<Duration>,
+    retry_policy: SimpleParamHandle<RetryPolicyKind>,
+    shard_aware: SimpleParamHandle<ExplicitBool>,
+    partitioner: SimpleParamHandle<PartitionerKind>,
+    keepalive_interval: SimpleParamHandle<Duration>,
+    fetch_size: SimpleParamHandle<NonZeroU32>,
 }
 
 fn prepare_parser() -> (ParamsParser, ModeParamHandles) {
@@ -101,6 +298,12 @@ fn prepare_parser() -> (ParamsParser, ModeParamHandles) {
     );
     let username = parser.simple_param("user=", None, "username", false);
     let password = parser.simple_param("password=", None, "password", false);
+    let authenticator = parser.simple_param(
+        "authenticator=",
+        None,
+        "Authenticator plugin given as name[:options], e.g. sasl-plain (requires the auth-plugins feature)",
+        false,
+    );
     let connections_per_shard = parser.simple_param(
         "connectionsPerShard=",
         Some("1"),
@@ -113,18 +316,71 @@ fn prepare_parser() -> (ParamsParser, ModeParamHandles) {
         "Number of connections per host",
         false,
     );
+    // The driver doesn't let us bound the number of in-flight requests per connection,
+    // so the limit is multiplied by the number of connections of the session
+    // and enforced as the limit of the operations in flight of the whole run.
+    let max_pending = parser.simple_param(
+        "maxPending=",
+        None,
+        "Maximum pending requests per connection, enforced as the limit of the operations in flight across all of the connections",
+        false,
+    );
+    let request_timeout = parser.simple_param(
+        "requestTimeout=",
+        None,
+        "Client-side request timeout (in seconds, minutes or hours), default:driver's default",
+        false,
+    );
+    let retry_policy = parser.simple_param(
+        "retry=",
+        Some("default"),
+        "Driver retry policy: default, fallthrough (surface all errors to the tool), downgrading",
+        false,
+    );
+    let shard_aware = parser.simple_param(
+        "shardAware=",
+        Some("true"),
+        "Open the connections per shard, over the shard-aware port, and route the requests to the shards (true|false). With false, connectionsPerShard= is the number of connections per host",
+        false,
+    );
+    let partitioner = parser.simple_param(
+        "partitioner=",
+        Some("murmur3"),
+        "Partitioner of the cluster: murmur3 or byteordered. The requests are routed to the replicas only under murmur3",
+        false,
+    );
+    let keepalive_interval = parser.simple_param(
+        "keepalive=",
+        None,
+        "Interval of the CQL keepalive requests sent on idle connections (in seconds, minutes or hours), default:driver's default",
+        false,
+    );
+    let fetch_size = parser.simple_param(
+        "fetchsize=",
+        None,
+        "Page size of the reads, which then fetch all of the pages of the result. The reads are unpaged by default",
+        false,
+    );
 
-    // $ ./cql-stress-cassandra-stress help -node
-    // Usage: -mode cql3 native [compression=?] [user=?] [password=?] [connectionsPerShard=?]
+    // $ ./cql-stress-cassandra-stress help -mode
+    // Usage: -mode cql3 native [compression=?] [user=?] [password=?] [authenticator=?] [connectionsPerShard=?] [maxPending=?] [requestTimeout=?] [retry=?] [shardAware=?] [partitioner=?] [keepalive=?] [fetchsize=?]
     //  OR
-    // Usage: -mode cql3 native [compression=?] [user=?] [password=?] [connectionsPerHost=?]
+    // Usage: -mode cql3 native [compression=?] [user=?] [password=?] [authenticator=?] [connectionsPerHost=?] [maxPending=?] [requestTimeout=?] [retry=?] [shardAware=?] [partitioner=?] [keepalive=?] [fetchsize=?]
     parser.group(&[
         &cql3,
         &native,
         &compression,
         &username,
         &password,
+        &authenticator,
         &connections_per_shard,
+        &max_pending,
+        &request_timeout,
+        &retry_policy,
+        &shard_aware,
+        &partitioner,
+        &keepalive_interval,
+        &fetch_size,
     ]);
     parser.group(&[
         &cql3,
@@ -132,29 +388,48 @@ fn prepare_parser() -> (ParamsParser, ModeParamHandles) {
         &compression,
         &username,
         &password,
+        &authenticator,
         &connections_per_host,
+        &max_pending,
+        &request_timeout,
+        &retry_policy,
+        &shard_aware,
+        &partitioner,
+        &keepalive_interval,
+        &fetch_size,
     ]);
 
+    parser.example("cql3 native compression=lz4 connectionsPerShard=2");
+    parser.example("native cql3 user=cassandra password=cassandra");
+
     (
         parser,
         ModeParamHandles {
             compression,
             username,
             password,
+            authenticator,
             connections_per_host,
             connections_per_shard,
+            max_pending,
+            request_timeout,
+            retry_policy,
+            shard_aware,
+            partitioner,
+            keepalive_interval,
+            fetch_size,
         },
     )
 }
 
 #[cfg(test)]
 mod tests {
-    use std::num::NonZeroUsize;
+    use std::{num::NonZeroUsize, time::Duration};
 
     use scylla::{frame::Compression, transport::session::PoolSize};
 
     use crate::settings::option::{
-        mode::{prepare_parser, Credentials},
+        mode::{prepare_parser, Credentials, PartitionerKind, RetryPolicyKind},
         ModeOption,
     };
 
@@ -204,6 +479,112 @@ mod tests {
         }
     }
 
+    #[test]
+    fn mode_good_params_test_with_request_timeout() {
+        let args = vec!["maxPending=128", "requestTimeout=10s"];
+        let (parser, handles) = prepare_parser();
+
+        assert!(parser.parse(args).is_ok());
+
+        let params = ModeOption::from_handles(handles).unwrap();
+        assert_eq!(NonZeroUsize::new(128), params.max_pending);
+        assert_eq!(Some(Duration::from_secs(10)), params.request_timeout);
+        assert_eq!(RetryPolicyKind::Default, params.retry_policy);
+        assert!(params.shard_aware);
+        assert_eq!(PartitionerKind::Murmur3, params.partitioner);
+        assert_eq!(None, params.keepalive_interval);
+        assert_eq!(None, params.fetch_size);
+    }
+
+    #[test]
+    fn mode_good_params_test_with_fetch_size() {
+        let args = vec!["fetchsize=100"];
+        let (parser, handles) = prepare_parser();
+
+        assert!(parser.parse(args).is_ok());
+
+        let params = ModeOption::from_handles(handles).unwrap();
+        assert_eq!(Some(100), params.fetch_size);
+    }
+
+    #[test]
+    fn mode_bad_params_fetch_size_test() {
+        let (parser, _handles) = prepare_parser();
+        assert!(parser.parse(vec!["fetchsize=0"]).is_err());
+
+        let (parser, handles) = prepare_parser();
+        assert!(parser.parse(vec!["fetchsize=3000000000"]).is_ok());
+        assert!(ModeOption::from_handles(handles).is_err());
+    }
+
+    #[test]
+    fn mode_good_params_test_with_retry_policy() {
+        let args = vec!["retry=Fallthrough"];
+        let (parser, handles) = prepare_parser();
+
+        assert!(parser.parse(args).is_ok());
+
+        let params = ModeOption::from_handles(handles).unwrap();
+        assert_eq!(RetryPolicyKind::Fallthrough, params.retry_policy);
+    }
+
+    #[test]
+    fn mode_good_params_test_with_pool_config() {
+        let args = vec!["connectionsPerShard=4", "shardAware=false", "keepalive=30s"];
+        let (parser, handles) = prepare_parser();
+
+        assert!(parser.parse(args).is_ok());
+
+        let params = ModeOption::from_handles(handles).unwrap();
+        assert!(!params.shard_aware);
+        assert_eq!(Some(Duration::from_secs(30)), params.keepalive_interval);
+        // The connections are opened per host without shard awareness.
+        // PoolSize doesn't derive Eq/PartialEq.
+        match params.pool_size {
+            PoolSize::PerHost(v) if v == NonZeroUsize::new(4).unwrap() => (),
+            _ => panic!("Expected PoolSize::PerHost(4)"),
+        }
+    }
+
+    #[test]
+    fn mode_good_params_test_with_partitioner() {
+        for (arg, partitioner) in [
+            ("partitioner=murmur3", PartitionerKind::Murmur3),
+            ("partitioner=ByteOrdered", PartitionerKind::ByteOrdered),
+            (
+                "partitioner=ByteOrderedPartitioner",
+                PartitionerKind::ByteOrdered,
+            ),
+        ] {
+            let (parser, handles) = prepare_parser();
+            assert!(parser.parse(vec![arg]).is_ok());
+            let params = ModeOption::from_handles(handles).unwrap();
+            assert_eq!(partitioner, params.partitioner);
+        }
+        assert!(PartitionerKind::Murmur3.token_aware());
+        assert!(!PartitionerKind::ByteOrdered.token_aware());
+
+        let (parser, _handles) = prepare_parser();
+        assert!(parser.parse(vec!["partitioner=random"]).is_err());
+    }
+
+    #[test]
+    fn mode_bad_params_retry_policy_test() {
+        let args = vec!["retry=foo"];
+        let (parser, _handles) = prepare_parser();
+
+        assert!(parser.parse(args).is_err());
+    }
+
+    #[test]
+    fn mode_bad_params_zero_request_timeout_test() {
+        let args = vec!["requestTimeout=0s"];
+        let (parser, handles) = prepare_parser();
+
+        assert!(parser.parse(args).is_ok());
+        assert!(ModeOption::from_handles(handles).is_err());
+    }
+
     #[test]
     fn mode_bad_params_grouping_test() {
         let args = vec!["connectionsperhost=3", "connectionspershard=1"];
@@ -221,4 +602,38 @@ mod tests {
         assert!(parser.parse(args).is_ok());
         assert!(ModeOption::from_handles(handles).is_err());
     }
+
+    #[test]
+    fn mode_authenticator_test() {
+        let args = vec!["authenticator=sasl-plain"];
+        let (parser, handles) = prepare_parser();
+
+        assert!(parser.parse(args).is_ok());
+        let params = ModeOption::from_handles(handles);
+        #[cfg(feature = "auth-plugins")]
+        assert_eq!(Some("sasl-plain"), params.unwrap().authenticator.as_deref());
+        // Without the feature the plugins are not available.
+        #[cfg(not(feature = "auth-plugins"))]
+        assert!(params.is_err());
+    }
+
+    #[test]
+    fn mode_bad_params_authenticator_test() {
+        // The authenticator replaces the plain credentials.
+        let args = vec![
+            "user=cassandra",
+            "password=cassandra",
+            "authenticator=sasl-plain",
+        ];
+        let (parser, handles) = prepare_parser();
+
+        assert!(parser.parse(args).is_ok());
+        assert!(ModeOption::from_handles(handles).is_err());
+
+        let args = vec!["authenticator=unknown"];
+        let (parser, handles) = prepare_parser();
+
+        assert!(parser.parse(args).is_ok());
+        assert!(ModeOption::from_handles(handles).is_err());
+    }
 }
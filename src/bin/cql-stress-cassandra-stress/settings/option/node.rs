@@ -1,13 +1,18 @@
 use std::{
+    collections::HashSet,
     fs::File,
     io::{self, BufRead},
+    net::{IpAddr, SocketAddr, ToSocketAddrs},
     sync::Arc,
+    time::Duration,
 };
 
 use anyhow::{Context, Result};
 use scylla::{
     host_filter::{AllowListHostFilter, HostFilter},
-    load_balancing::{DefaultPolicy, LoadBalancingPolicy},
+    load_balancing::{DefaultPolicy, FallbackPlan, LoadBalancingPolicy, RoutingInfo},
+    routing::Shard,
+    transport::{errors::QueryError, ClusterData, NodeRef},
 };
 
 use crate::settings::{
@@ -15,10 +20,15 @@ use crate::settings::{
     ParsePayload,
 };
 
+/// The port of the nodes given without one, the same as in the driver.
+const DEFAULT_PORT: u16 = 9042;
+
 pub struct NodeOption {
     pub nodes: Vec<String>,
     pub whitelist: bool,
     pub datacenter: Option<String>,
+    pub rack: Option<String>,
+    pub cloud_config: Option<String>,
 }
 
 impl NodeOption {
@@ -45,53 +55,164 @@ impl NodeOption {
         println!("  Nodes: {:?}", self.nodes);
         println!("  Is White List: {}", self.whitelist);
         println!("  Datacenter: {:?}", self.datacenter);
+        println!("  Rack: {:?}", self.rack);
+        if let Some(cloud_config) = &self.cloud_config {
+            println!("  Cloud config: {}", cloud_config);
+        }
     }
 
     fn from_handles(handles: NodeParamHandles) -> Result<NodeOption> {
         let datacenter = handles.datacenter.get();
+        let rack = handles.rack.get();
+        anyhow::ensure!(
+            rack.is_none() || datacenter.is_some(),
+            "Preferred rack can be specified only along with the preferred datacenter"
+        );
         let whitelist = handles.whitelist.get().is_some();
         let file = handles.file.get();
         let nodes = handles.nodes.get();
+        let cloud_config = handles.cloud_config.get();
 
-        let nodes = match nodes {
-            Some(nodes) => nodes,
-            // SAFETY: Parameters are grouped in a way that either `nodes` or `file` is Some.
-            // Note that it's never the case that both of them are Some.
-            _ => read_nodes_from_file(&file.unwrap())?,
+        // Parameters are grouped in a way that exactly one of `nodes`, `file`
+        // and `cloud_config` is Some.
+        let nodes = match (nodes, file) {
+            (Some(nodes), _) => nodes,
+            (None, Some(file)) => read_nodes_from_file(&file)?,
+            // The contact points are provided by the cloud config.
+            (None, None) => Vec::new(),
         };
 
         Ok(Self {
             nodes,
             whitelist,
             datacenter,
+            rack,
+            cloud_config,
         })
     }
 
-    /// Define a token-aware load balancing policy with a preferred datacenter (if specified).
-    pub fn load_balancing_policy(&self) -> Arc<dyn LoadBalancingPolicy> {
-        let mut builder = DefaultPolicy::builder().token_aware(true);
-        if let Some(datacenter) = &self.datacenter {
-            builder = builder.prefer_datacenter(datacenter.to_owned());
+    /// Define a load balancing policy with a preferred datacenter
+    /// and rack (if specified), token-aware if `token_aware` is set.
+    /// If `whitelist` is set, the policy never routes the requests to the nodes
+    /// other than the specified ones.
+    pub fn load_balancing_policy(&self, token_aware: bool) -> Result<Arc<dyn LoadBalancingPolicy>> {
+        let mut builder = DefaultPolicy::builder().token_aware(token_aware);
+        match (&self.datacenter, &self.rack) {
+            (Some(datacenter), Some(rack)) => {
+                builder =
+                    builder.prefer_datacenter_and_rack(datacenter.to_owned(), rack.to_owned());
+            }
+            (Some(datacenter), None) => {
+                builder = builder.prefer_datacenter(datacenter.to_owned());
+            }
+            // Rack without the datacenter is rejected during parsing.
+            (None, _) => (),
         };
-        builder.build()
+        let policy = builder.build();
+
+        if !self.whitelist {
+            return Ok(policy);
+        }
+        let allowed = self.resolve_nodes()?.iter().map(SocketAddr::ip).collect();
+        Ok(Arc::new(WhitelistPolicy {
+            allowed,
+            inner: policy,
+        }))
     }
 
     /// Limit the communication to the specified nodes (if `whitelist` is set).
-    pub fn host_filter(&self, port: u16) -> Option<Result<Arc<dyn HostFilter>>> {
+    pub fn host_filter(&self) -> Option<Result<Arc<dyn HostFilter>>> {
         self.whitelist.then(|| -> Result<Arc<dyn HostFilter>> {
-            let addrs = self.nodes.iter().map(|ip| (ip.as_ref(), port));
+            let addrs = self.resolve_nodes()?;
             Ok(Arc::new(
                 AllowListHostFilter::new(addrs).context("Failed to prepare host filter")?,
             ))
         })
     }
+
+    // Resolves the nodes the same way as the driver resolves the contact points,
+    // i.e. the nodes given without a port listen on the default port.
+    fn resolve_nodes(&self) -> Result<HashSet<SocketAddr>> {
+        let mut addrs = HashSet::new();
+        for node in self.nodes.iter() {
+            let resolved = node
+                .to_socket_addrs()
+                .or_else(|_| (node.as_str(), DEFAULT_PORT).to_socket_addrs())
+                .with_context(|| format!("Failed to resolve node address: {}", node))?;
+            addrs.extend(resolved);
+        }
+        Ok(addrs)
+    }
+}
+
+/// Wraps the load balancing policy so that the query plans contain only
+/// the whitelisted nodes. Other peers discovered via topology metadata
+/// are never contacted.
+///
+/// The nodes are matched by their addresses only, as the port reported
+/// by the topology metadata may differ from the port of the contact point.
+#[derive(Debug)]
+struct WhitelistPolicy {
+    allowed: HashSet<IpAddr>,
+    inner: Arc<dyn LoadBalancingPolicy>,
+}
+
+impl WhitelistPolicy {
+    fn is_allowed(&self, node: NodeRef<'_>) -> bool {
+        self.allowed.contains(&node.address.ip())
+    }
+}
+
+impl LoadBalancingPolicy for WhitelistPolicy {
+    fn pick<'a>(
+        &'a self,
+        query: &'a RoutingInfo,
+        cluster: &'a ClusterData,
+    ) -> Option<(NodeRef<'a>, Option<Shard>)> {
+        // If the picked node is not whitelisted, the driver falls back to the fallback plan.
+        self.inner
+            .pick(query, cluster)
+            .filter(|(node, _)| self.is_allowed(node))
+    }
+
+    fn fallback<'a>(
+        &'a self,
+        query: &'a RoutingInfo,
+        cluster: &'a ClusterData,
+    ) -> FallbackPlan<'a> {
+        Box::new(
+            self.inner
+                .fallback(query, cluster)
+                .filter(|(node, _)| self.is_allowed(node)),
+        )
+    }
+
+    fn on_query_success(&self, query: &RoutingInfo, latency: Duration, node: NodeRef<'_>) {
+        self.inner.on_query_success(query, latency, node)
+    }
+
+    fn on_query_failure(
+        &self,
+        query: &RoutingInfo,
+        latency: Duration,
+        node: NodeRef<'_>,
+        error: &QueryError,
+    ) {
+        self.inner.on_query_failure(query, latency, node, error)
+    }
+
+    fn name(&self) -> String {
+        format!("WhitelistPolicy({})", self.inner.name())
+    }
 }
 
 struct NodeParamHandles {
     datacenter: SimpleParamHandle<String>,
+    rack: SimpleParamHandle<String>,
     whitelist: SimpleParamHandle<bool>,
     file: SimpleParamHandle<String>,
     nodes: SimpleParamHandle<CommaDelimitedList>,
+    cloud_config: SimpleParamHandle<String>,
 }
 
 fn prepare_parser() -> (ParamsParser, NodeParamHandles) {
@@ -103,6 +224,12 @@ fn prepare_parser() -> (ParamsParser, NodeParamHandles) {
         "Preferred datacenter for the default load balancing policy",
         false,
     );
+    let rack = parser.simple_param(
+        "rack=",
+        None,
+        "Preferred rack (within the preferred datacenter) for the default load balancing policy",
+        false,
+    );
     let whitelist = parser.simple_param(
         "whitelist",
         None,
@@ -116,21 +243,36 @@ fn prepare_parser() -> (ParamsParser, NodeParamHandles) {
         "comma delimited list of nodes",
         false,
     );
+    let cloud_config = parser.simple_param(
+        "cloudconf=",
+        None,
+        "Scylla Cloud connection bundle (yaml) providing the nodes, credentials and TLS settings",
+        true,
+    );
 
     // $ ./cassandra-stress help -node
     // Usage: -node [datacenter=?] [whitelist] []
     //  OR
     // Usage: -node [datacenter=?] [whitelist] [file=?]
-    parser.group(&[&datacenter, &whitelist, &nodes]);
-    parser.group(&[&datacenter, &whitelist, &file]);
+    // Additionally, the tool accepts [rack=?] in both groups
+    // and supports connecting via the cloud connection bundle:
+    // Usage: -node [datacenter=?] [rack=?] cloudconf=?
+    parser.group(&[&datacenter, &rack, &whitelist, &nodes]);
+    parser.group(&[&datacenter, &rack, &whitelist, &file]);
+    parser.group(&[&datacenter, &rack, &cloud_config]);
+
+    parser.example("127.0.0.1,127.0.0.2");
+    parser.example("whitelist datacenter=dc1 10.0.0.1,10.0.0.2");
 
     (
         parser,
         NodeParamHandles {
             datacenter,
+            rack,
             whitelist,
             file,
             nodes,
+            cloud_config,
         },
     )
 }
@@ -163,10 +305,60 @@ mod tests {
 
         let params = NodeOption::from_handles(handles).unwrap();
         assert_eq!(None, params.datacenter);
+        assert_eq!(None, params.rack);
         assert!(params.whitelist);
         assert_eq!(vec!["127.0.0.1", "localhost", "192.168.0.1"], params.nodes);
     }
 
+    #[test]
+    fn node_good_params_with_rack_test() {
+        let args = vec!["datacenter=dc1", "rack=rack1", "127.0.0.1"];
+        let (parser, handles) = prepare_parser();
+
+        assert!(parser.parse(args).is_ok());
+
+        let params = NodeOption::from_handles(handles).unwrap();
+        assert_eq!(Some("dc1"), params.datacenter.as_deref());
+        assert_eq!(Some("rack1"), params.rack.as_deref());
+    }
+
+    #[test]
+    fn node_bad_params_rack_without_datacenter_test() {
+        let args = vec!["rack=rack1", "127.0.0.1"];
+        let (parser, handles) = prepare_parser();
+
+        assert!(parser.parse(args).is_ok());
+        assert!(NodeOption::from_handles(handles).is_err());
+    }
+
+    #[test]
+    fn node_whitelist_resolve_test() {
+        let args = vec!["whitelist", "127.0.0.1,192.168.0.1:19042"];
+        let (parser, handles) = prepare_parser();
+
+        assert!(parser.parse(args).is_ok());
+
+        let params = NodeOption::from_handles(handles).unwrap();
+        let addrs = params.resolve_nodes().unwrap();
+        assert_eq!(2, addrs.len());
+        assert!(addrs.contains(&"127.0.0.1:9042".parse().unwrap()));
+        assert!(addrs.contains(&"192.168.0.1:19042".parse().unwrap()));
+        assert!(params.load_balancing_policy(true).is_ok());
+    }
+
+    #[test]
+    fn node_cloud_config_test() {
+        let args = vec!["datacenter=dc1", "cloudconf=/path/to/bundle.yaml"];
+        let (parser, handles) = prepare_parser();
+
+        assert!(parser.parse(args).is_ok());
+
+        let params = NodeOption::from_handles(handles).unwrap();
+        assert_eq!(Some("/path/to/bundle.yaml"), params.cloud_config.as_deref());
+        assert!(params.nodes.is_empty());
+        assert!(!params.whitelist);
+    }
+
     #[test]
     fn node_bad_params_test() {
         let args = vec!["whitelist", "127.0.0.1,localhost,192.168.0.1,"];
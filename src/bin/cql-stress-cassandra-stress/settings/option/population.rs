@@ -1,18 +1,71 @@
 use anyhow::Result;
+use cql_stress::split::Split;
 
 use crate::{
-    java_generate::distribution::{sequence::SeqDistributionFactory, DistributionFactory},
+    java_generate::distribution::{
+        file::FileDistributionFactory, sequence::SeqDistributionFactory, DistributionFactory,
+    },
     settings::{
         param::{
             types::{Count, Parsable, Range},
             ParamsParser, SimpleParamHandle,
         },
-        ParsePayload,
+        Command, ParsePayload,
     },
 };
 
 pub struct PopulationOption {
     pub pk_seed_distribution: Box<dyn DistributionFactory>,
+    pub split: Option<Split>,
+    /// The number of the seeds of `seq=` (of this instance's part of it, if split).
+    pub sequence_len: Option<u64>,
+    /// What happens once all of the seeds of `seq=` are sampled.
+    pub sequence_end: SequenceEnd,
+    /// Mixed into the sampled seeds when generating the partition keys.
+    /// The runs with the same seed generate the same keys and values.
+    pub seed: u64,
+}
+
+/// What happens once all of the seeds of `-pop seq=` are sampled.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SequenceEnd {
+    /// The sequence starts over, revisiting the partitions (as in cassandra-stress).
+    Wrap,
+    /// The workload stops, even if the operation count or duration isn't reached.
+    Stop,
+    /// The workload fails.
+    Error,
+}
+
+impl std::fmt::Display for SequenceEnd {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            Self::Wrap => "WRAP",
+            Self::Stop => "STOP",
+            Self::Error => "ERROR",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+impl Parsable for SequenceEnd {
+    type Parsed = SequenceEnd;
+
+    fn parse(s: &str) -> Result<Self::Parsed> {
+        match s.to_lowercase().as_str() {
+            "wrap" => Ok(Self::Wrap),
+            "stop" => Ok(Self::Stop),
+            "error" => Ok(Self::Error),
+            _ => anyhow::bail!(
+                "Invalid value of exhausted=: {}. Must be one of: wrap|stop|error",
+                s
+            ),
+        }
+    }
+
+    fn value_pattern() -> Option<&'static str> {
+        Some("wrap|stop|error")
+    }
 }
 
 impl PopulationOption {
@@ -22,15 +75,19 @@ impl PopulationOption {
         "Population distribution"
     }
 
-    pub fn parse(cl_args: &mut ParsePayload, operation_count: &str) -> Result<Self> {
+    pub fn parse(
+        cl_args: &mut ParsePayload,
+        command: &Command,
+        operation_count: &str,
+    ) -> Result<Self> {
         let params = cl_args.remove(Self::CLI_STRING).unwrap_or_default();
-        let (parser, handles) = prepare_parser(operation_count);
+        let (parser, handles) = prepare_parser(operation_count, sequential_by_default(command));
         parser.parse(params)?;
-        Ok(Self::from_handles(handles))
+        Self::from_handles(handles)
     }
 
     pub fn print_help() {
-        let (parser, _) = prepare_parser("1000000");
+        let (parser, _) = prepare_parser("1000000", true);
         parser.print_help();
     }
 
@@ -40,74 +97,372 @@ impl PopulationOption {
             "  Partition key seed distribution: {}",
             self.pk_seed_distribution
         );
+        if let Some(split) = &self.split {
+            println!("  Split: {}", split);
+        }
+        if self.sequence_len.is_some() {
+            println!("  Exhausted sequence: {}", self.sequence_end);
+        }
+        println!("  Seed: {}", self.seed);
     }
 
-    fn from_handles(handles: PopulationParamHandles) -> Self {
-        let pk_seed_distribution = match handles.bash_friendly_seq_distribution.get() {
-            Some(dist) => dist,
-            None => handles.pk_seed_distribution.get().unwrap(),
+    fn from_handles(handles: PopulationParamHandles) -> Result<Self> {
+        let split = handles.split.get();
+        // None, unless the group of seq= is chosen.
+        let sequence_end = handles.sequence_end.get().unwrap_or(SequenceEnd::Wrap);
+        let mut sequence_len = None;
+        let pk_seed_distribution = match (
+            handles.seeds_file.get(),
+            handles.bash_friendly_seq_distribution.get(),
+        ) {
+            (Some(dist), _) => dist,
+            (None, Some((from, to))) => {
+                // This instance populates its part of the sequence.
+                let (from, to) = match split {
+                    Some(split) => {
+                        let part = split.part((to - from + 1) as u64);
+                        (from + part.start as i64, from + part.end as i64 - 1)
+                    }
+                    None => (from, to),
+                };
+                sequence_len = Some((to - from + 1) as u64);
+                Box::new(SeqDistributionFactory::new(from, to)?)
+            }
+            (None, None) => {
+                // The sequence is not the default for this command.
+                anyhow::ensure!(split.is_none(), "split= requires seq=");
+                anyhow::ensure!(
+                    sequence_end == SequenceEnd::Wrap,
+                    "exhausted= requires seq="
+                );
+                handles.pk_seed_distribution.get().unwrap()
+            }
         };
 
-        Self {
+        Ok(Self {
             pk_seed_distribution,
-        }
+            split,
+            sequence_len,
+            sequence_end,
+            seed: handles.seed.get().unwrap(),
+        })
     }
 }
 
+/// Same as in Cassandra-Stress, the write workloads populate the keys in sequence
+/// by default, while the rest of the workloads sample them from GAUSSIAN(1..N) distribution.
+/// See https://github.com/scylladb/scylla-tools-java/blob/master/tools/stress/src/org/apache/cassandra/stress/settings/SettingsPopulation.java.
+fn sequential_by_default(command: &Command) -> bool {
+    matches!(command, Command::Write | Command::CounterWrite)
+}
+
 /// Cassandra-Stress supports bash-friendly syntax for SEQ distribution: -pop seq=1..10000
 /// This is equivalent to: -pop 'dist=SEQ(1..1000)'
+/// The distribution is created once the `split=` is known.
 struct BashFriendlySeqDistribution;
 impl Parsable for BashFriendlySeqDistribution {
-    type Parsed = Box<dyn DistributionFactory>;
+    type Parsed = (i64, i64);
 
     fn parse(s: &str) -> Result<Self::Parsed> {
         let (from, to) = <Range<Count> as Parsable>::parse(s)?;
-        let dist = SeqDistributionFactory::new(from as i64, to as i64)?;
-        Ok(Box::new(dist))
+        // Validates the range.
+        SeqDistributionFactory::new(from as i64, to as i64)?;
+        Ok((from as i64, to as i64))
+    }
+}
+
+/// The part of the sequence populated by this instance: -pop seq=1..1000000 split=3/8
+/// This is an extension of the original cassandra-stress.
+struct PopulationSplit;
+impl Parsable for PopulationSplit {
+    type Parsed = Split;
+
+    fn parse(s: &str) -> Result<Self::Parsed> {
+        Split::parse(s)
+    }
+
+    fn value_pattern() -> Option<&'static str> {
+        Some("<index>/<count>")
+    }
+}
+
+/// The seeds are read from the file: -pop file=seeds.txt
+/// This is an extension of the original cassandra-stress.
+struct SeedsFile;
+impl Parsable for SeedsFile {
+    type Parsed = Box<dyn DistributionFactory>;
+
+    fn parse(s: &str) -> Result<Self::Parsed> {
+        Ok(Box::new(FileDistributionFactory::new(s)?))
     }
 }
 
 struct PopulationParamHandles {
     pk_seed_distribution: SimpleParamHandle<Box<dyn DistributionFactory>>,
     bash_friendly_seq_distribution: SimpleParamHandle<BashFriendlySeqDistribution>,
+    seeds_file: SimpleParamHandle<SeedsFile>,
+    split: SimpleParamHandle<PopulationSplit>,
+    sequence_end: SimpleParamHandle<SequenceEnd>,
+    seed: SimpleParamHandle<u64>,
 }
 
-fn prepare_parser(operation_count: &str) -> (ParamsParser, PopulationParamHandles) {
+fn prepare_parser(
+    operation_count: &str,
+    sequential_by_default: bool,
+) -> (ParamsParser, PopulationParamHandles) {
     let mut parser = ParamsParser::new(PopulationOption::CLI_STRING);
 
-    let bash_friendly_seq_distribution = parser.simple_param("seq=", Some(&format!("1..{operation_count}")), "Generate all seeds in sequence. The default value is 1..N where N is operation count if specified, 1000000 otherwise.", false);
+    let seq_default = format!("1..{operation_count}");
+    let dist_default = format!("gaussian(1..{operation_count})");
+    let bash_friendly_seq_distribution = parser.simple_param("seq=", sequential_by_default.then_some(seq_default.as_str()), "Generate all seeds in sequence. The default value (for write workloads) is 1..N where N is operation count if specified, 1000000 otherwise.", false);
     let pk_seed_distribution = parser.distribution_param(
         "dist=",
+        (!sequential_by_default).then_some(dist_default.as_str()),
+        "Seeds are selected from this distribution. The default value (for non-write workloads) is GAUSSIAN(1..N) where N is operation count if specified, 1000000 otherwise.",
+        false,
+    );
+    let seeds_file = parser.simple_param(
+        "file=",
         None,
-        "Seeds are selected from this distribution.",
+        "Read the seeds from this file, one per line, and cycle over them in order.",
+        true,
+    );
+
+    let split = parser.simple_param(
+        "split=",
+        None,
+        "Populate only the given part of the sequence, e.g. 3/8 for the third of 8 loaders. The operation count is split the same way.",
+        false,
+    );
+    let sequence_end = parser.simple_param(
+        "exhausted=",
+        Some("wrap"),
+        "What to do once all of the seeds of the sequence are used: start over and revisit the partitions (wrap), stop the workload (stop) or fail it (error).",
+        false,
+    );
+    let seed = parser.simple_param(
+        "seed=",
+        Some("0"),
+        "Seed mixed into the seeds of the partition keys. A read run with the same seed, keysize and columns regenerates the values written by the write run. The default 0 generates the keys of cassandra-stress.",
         false,
     );
 
     // $ ./cassandra-stress help -pop
-    // Usage: -pop [seq=?]
+    // Usage: -pop [seq=?] [split=?] [exhausted=?] [seed=?]
+    //   OR
+    // Usage: -pop [dist=DIST(?)] [seed=?]
     //   OR
-    // Usage: -pop [dist=DIST(?)]
-    parser.group(&[&bash_friendly_seq_distribution]);
-    parser.group(&[&pk_seed_distribution]);
+    // Usage: -pop file=? [seed=?]
+    //
+    // The first satisfied group is chosen when no parameters are provided,
+    // so the group with the default value goes first.
+    if sequential_by_default {
+        parser.group(&[
+            &bash_friendly_seq_distribution,
+            &split,
+            &sequence_end,
+            &seed,
+        ]);
+        parser.group(&[&pk_seed_distribution, &seed]);
+    } else {
+        parser.group(&[&pk_seed_distribution, &seed]);
+        parser.group(&[
+            &bash_friendly_seq_distribution,
+            &split,
+            &sequence_end,
+            &seed,
+        ]);
+    }
+    parser.group(&[&seeds_file, &seed]);
+
+    parser.example("seq=1..1000000");
+    parser.example("dist=gaussian(1..1000000,5)");
+    parser.example("file=seeds.txt");
+    parser.example("seq=1..1000000 split=3/8");
+    parser.example("seq=1..1000000 exhausted=stop");
+    parser.example("seq=1..1000000 seed=7");
 
     (
         parser,
         PopulationParamHandles {
             pk_seed_distribution,
             bash_friendly_seq_distribution,
+            seeds_file,
+            split,
+            sequence_end,
+            seed,
         },
     )
 }
 
 #[cfg(test)]
 mod tests {
-    use super::prepare_parser;
+    use super::{prepare_parser, PopulationOption, SequenceEnd};
 
     #[test]
     fn pop_default_params_test() {
         let args = vec![];
-        let (parser, _) = prepare_parser("100");
+        let (parser, _) = prepare_parser("100", true);
 
         assert!(parser.parse(args).is_ok());
     }
+
+    #[test]
+    fn pop_default_params_sequential_test() {
+        let (parser, handles) = prepare_parser("100", true);
+
+        assert!(parser.parse(vec![]).is_ok());
+
+        let params = PopulationOption::from_handles(handles).unwrap();
+        assert_eq!(
+            "SEQ(1..100)",
+            params.pk_seed_distribution.to_string().to_uppercase()
+        );
+    }
+
+    #[test]
+    fn pop_default_params_gaussian_test() {
+        let (parser, handles) = prepare_parser("100", false);
+
+        assert!(parser.parse(vec![]).is_ok());
+
+        let params = PopulationOption::from_handles(handles).unwrap();
+        assert!(params
+            .pk_seed_distribution
+            .to_string()
+            .to_lowercase()
+            .starts_with("gaussian(1..100"));
+    }
+
+    #[test]
+    fn pop_dist_params_test() {
+        let args = vec!["dist=gaussian(1..1000,5)"];
+        let (parser, handles) = prepare_parser("100", false);
+
+        assert!(parser.parse(args).is_ok());
+
+        let params = PopulationOption::from_handles(handles).unwrap();
+        assert!(params
+            .pk_seed_distribution
+            .to_string()
+            .to_lowercase()
+            .starts_with("gaussian(1..1000"));
+    }
+
+    #[test]
+    fn pop_split_params_test() {
+        let args = vec!["seq=1..10", "split=2/3"];
+        let (parser, handles) = prepare_parser("100", false);
+
+        assert!(parser.parse(args).is_ok());
+
+        let params = PopulationOption::from_handles(handles).unwrap();
+        assert_eq!(
+            "SEQ(4..6)",
+            params.pk_seed_distribution.to_string().to_uppercase()
+        );
+
+        // The default sequence is split as well.
+        let (parser, handles) = prepare_parser("100", true);
+        assert!(parser.parse(vec!["split=3/3"]).is_ok());
+        let params = PopulationOption::from_handles(handles).unwrap();
+        assert_eq!(
+            "SEQ(67..100)",
+            params.pk_seed_distribution.to_string().to_uppercase()
+        );
+
+        let (parser, _) = prepare_parser("100", false);
+        assert!(parser
+            .parse(vec!["dist=gaussian(1..10,5)", "split=1/2"])
+            .is_err());
+
+        let (parser, handles) = prepare_parser("100", false);
+        assert!(parser.parse(vec!["split=1/2"]).is_ok());
+        assert!(PopulationOption::from_handles(handles).is_err());
+    }
+
+    #[test]
+    fn pop_exhausted_params_test() {
+        let (parser, handles) = prepare_parser("100", true);
+        assert!(parser.parse(vec![]).is_ok());
+        let params = PopulationOption::from_handles(handles).unwrap();
+        assert_eq!(SequenceEnd::Wrap, params.sequence_end);
+        assert_eq!(Some(100), params.sequence_len);
+
+        let (parser, handles) = prepare_parser("100", false);
+        assert!(parser
+            .parse(vec!["seq=1..10", "split=2/3", "exhausted=STOP"])
+            .is_ok());
+        let params = PopulationOption::from_handles(handles).unwrap();
+        assert_eq!(SequenceEnd::Stop, params.sequence_end);
+        assert_eq!(Some(3), params.sequence_len);
+
+        let (parser, _) = prepare_parser("100", true);
+        assert!(parser.parse(vec!["exhausted=never"]).is_err());
+
+        let (parser, _) = prepare_parser("100", false);
+        assert!(parser
+            .parse(vec!["dist=gaussian(1..10,5)", "exhausted=error"])
+            .is_err());
+
+        let (parser, handles) = prepare_parser("100", false);
+        assert!(parser.parse(vec!["exhausted=error"]).is_ok());
+        assert!(PopulationOption::from_handles(handles).is_err());
+    }
+
+    #[test]
+    fn pop_file_params_test() {
+        let path = std::env::temp_dir().join(format!("cs-pop-seeds-test-{}", std::process::id()));
+        std::fs::write(&path, "3\n1\n2\n").unwrap();
+        let arg = format!("file={}", path.display());
+        let (parser, handles) = prepare_parser("100", true);
+
+        assert!(parser.parse(vec![arg.as_str()]).is_ok());
+
+        let params = PopulationOption::from_handles(handles).unwrap();
+        let dist = params.pk_seed_distribution.create();
+        let seeds = (0..4).map(|_| dist.next_i64()).collect::<Vec<_>>();
+        assert_eq!(vec![3, 1, 2, 3], seeds);
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn pop_seq_params_for_read_test() {
+        let args = vec!["seq=1..50"];
+        let (parser, handles) = prepare_parser("100", false);
+
+        assert!(parser.parse(args).is_ok());
+
+        let params = PopulationOption::from_handles(handles).unwrap();
+        assert_eq!(
+            "SEQ(1..50)",
+            params.pk_seed_distribution.to_string().to_uppercase()
+        );
+    }
+
+    #[test]
+    fn pop_seed_params_test() {
+        let (parser, handles) = prepare_parser("100", true);
+        assert!(parser.parse(vec![]).is_ok());
+        let params = PopulationOption::from_handles(handles).unwrap();
+        assert_eq!(0, params.seed);
+
+        let (parser, handles) = prepare_parser("100", true);
+        assert!(parser.parse(vec!["seq=1..10", "seed=7"]).is_ok());
+        let params = PopulationOption::from_handles(handles).unwrap();
+        assert_eq!(7, params.seed);
+
+        let (parser, handles) = prepare_parser("100", false);
+        assert!(parser.parse(vec!["seed=7"]).is_ok());
+        let params = PopulationOption::from_handles(handles).unwrap();
+        assert_eq!(7, params.seed);
+        assert!(params
+            .pk_seed_distribution
+            .to_string()
+            .to_lowercase()
+            .starts_with("gaussian(1..100"));
+
+        let (parser, _) = prepare_parser("100", true);
+        assert!(parser.parse(vec!["seed=-1"]).is_err());
+    }
 }
@@ -1,11 +1,71 @@
 use crate::settings::{
-    param::{types::Rate, ParamsParser, SimpleParamHandle},
+    param::{
+        types::{Latency, Parsable, Rate},
+        ParamsParser, SimpleParamHandle,
+    },
     ParsePayload,
 };
-use anyhow::Result;
+use anyhow::{Context, Result};
+use cql_stress::{
+    configuration::{Arrival, DutyCycle, RateLimitMode},
+    partition_lock::PartitionLocks,
+};
+use std::{sync::Arc, time::Duration};
 
 pub struct RateOption {
     pub threads_info: ThreadsInfo,
+    /// Whether the operations on the same partition are executed one at a time.
+    pub serialize_partitions: bool,
+    /// Whether each operation of the mixed command is performed
+    /// by a separate group of threads, with its own throttle.
+    pub streams: bool,
+    /// The maximum number of operations executed at the same time
+    /// by all of the threads.
+    pub max_in_flight: Option<u64>,
+    /// How the start times of the rate limited operations are spread.
+    pub arrival: Arrival,
+    /// The number of dedicated runtimes the threads are spread between.
+    pub runtimes: Option<u64>,
+    /// The windows in which the load runs and is paused.
+    pub duty_cycle: Option<DutyCycle>,
+}
+
+impl Parsable for Arrival {
+    type Parsed = Arrival;
+
+    fn parse(s: &str) -> Result<Self::Parsed> {
+        match s.to_lowercase().as_str() {
+            "uniform" => Ok(Self::Uniform),
+            "poisson" => Ok(Self::Poisson),
+            _ => anyhow::bail!("Invalid arrival: {}. Must be one of: uniform|poisson", s),
+        }
+    }
+
+    fn value_pattern() -> Option<&'static str> {
+        Some("uniform|poisson")
+    }
+}
+
+impl Parsable for DutyCycle {
+    type Parsed = DutyCycle;
+
+    fn parse(s: &str) -> Result<Self::Parsed> {
+        let (on, off) = s
+            .split_once('/')
+            .with_context(|| format!("Invalid duty cycle: {}. Expected <on>/<off>", s))?;
+        let on = Duration::parse(on)?;
+        let off = Duration::parse(off)?;
+        anyhow::ensure!(
+            !on.is_zero() && !off.is_zero(),
+            "The windows of duty cycle {} must be positive",
+            s
+        );
+        Ok(DutyCycle { on, off })
+    }
+
+    fn value_pattern() -> Option<&'static str> {
+        Some(r"[0-9]+[dhms]/[0-9]+[dhms]")
+    }
 }
 
 #[derive(PartialEq, Debug)]
@@ -13,6 +73,9 @@ pub enum ThreadsInfo {
     Fixed {
         threads: u64,
         throttle: Option<u64>,
+        /// Whether the operations follow the schedule implied by the rate
+        /// (`fixed=`), instead of only being capped by it (`throttle=`).
+        fixed_schedule: bool,
         co_fixed: bool,
     },
     Auto {
@@ -20,19 +83,45 @@ pub enum ThreadsInfo {
         max_threads: u64,
         auto: bool,
     },
+    /// The rate is adjusted during the benchmark to find the maximum
+    /// throughput which keeps the latency percentile under the target.
+    AutoLatency {
+        threads: u64,
+        percentile: f64,
+        target: Duration,
+    },
 }
 
 impl ThreadsInfo {
+    /// How the workers treat the operations which couldn't be started on time.
+    /// `throttle=` only caps the throughput, while `fixed=` catches up
+    /// with the schedule implied by the rate.
+    pub fn rate_limit_mode(&self) -> RateLimitMode {
+        match self {
+            Self::Fixed {
+                fixed_schedule: false,
+                ..
+            } => RateLimitMode::Throttle,
+            // The latency target controller drops the backlog
+            // itself whenever it changes the rate.
+            Self::Fixed { .. } | Self::Auto { .. } | Self::AutoLatency { .. } => {
+                RateLimitMode::Schedule
+            }
+        }
+    }
+
     fn print_settings(&self) {
         match &self {
             Self::Fixed {
                 threads,
                 throttle,
+                fixed_schedule,
                 co_fixed,
             } => {
                 println!("  Thread count: {}", threads);
                 if let Some(throttle) = throttle {
                     println!("  OpsPer Sec: {}", throttle);
+                    println!("  Fixed schedule: {}", fixed_schedule);
                 }
                 println!("  Coordinated-Omission-Fixed latencies: {}", co_fixed);
             }
@@ -45,6 +134,14 @@ impl ThreadsInfo {
                 println!("  Max threads: {}", max_threads);
                 println!("  auto: {}", auto);
             }
+            Self::AutoLatency {
+                threads,
+                percentile,
+                target,
+            } => {
+                println!("  Thread count: {}", threads);
+                println!("  Latency target: p{} <= {:?}", percentile, target);
+            }
         }
     }
 }
@@ -71,42 +168,137 @@ impl RateOption {
     pub fn print_settings(&self) {
         println!("Rate:");
         self.threads_info.print_settings();
+        println!("  Serialize partitions: {}", self.serialize_partitions);
+        println!("  Streams: {}", self.streams);
+        if let Some(max_in_flight) = self.max_in_flight {
+            println!("  Max in flight: {}", max_in_flight);
+        }
+        println!("  Arrival: {}", self.arrival);
+        if let Some(runtimes) = self.runtimes {
+            println!("  Runtimes: {}", runtimes);
+        }
+        if let Some(duty_cycle) = self.duty_cycle {
+            println!("  Duty cycle: {}", duty_cycle);
+        }
+    }
+
+    /// Creates the locks shared by the workers if the operations
+    /// on the same partition should be serialized.
+    pub fn create_partition_locks(&self) -> Option<Arc<PartitionLocks>> {
+        if !self.serialize_partitions {
+            return None;
+        }
+        let concurrency = match &self.threads_info {
+            ThreadsInfo::Fixed { threads, .. } => *threads,
+            ThreadsInfo::Auto { max_threads, .. } => *max_threads,
+            ThreadsInfo::AutoLatency { threads, .. } => *threads,
+        };
+        Some(Arc::new(PartitionLocks::new(concurrency)))
     }
 
     fn from_handles(handles: RateParamHandles) -> Result<Self> {
         let threads = handles.threads.get();
         let throttle = handles.throttle.get();
+        let fixed = handles.fixed.get();
         let co_fixed = handles.co_fixed.get().is_some();
         let min_threads = handles.threads_gte.get();
         let max_threads = handles.threads_lte.get();
         let auto = handles.auto.get().is_some();
+        let serialize_partitions = handles.serialize_partitions.get().is_some();
+        let streams = handles.streams.get().is_some();
+        let max_in_flight = handles.max_in_flight.get();
+        anyhow::ensure!(max_in_flight != Some(0), "max-in-flight= must not be zero");
+        let arrival = handles.arrival.get().unwrap_or_default();
+        let runtimes = handles.runtimes.get();
+        anyhow::ensure!(runtimes != Some(0), "runtimes= must not be zero");
+        let auto_latency = handles.auto_latency.get().is_some();
+        let latency_targets = [
+            (95.0, handles.p95.get()),
+            (99.0, handles.p99.get()),
+            (99.9, handles.p999.get()),
+        ]
+        .into_iter()
+        .filter_map(|(percentile, target)| target.map(|target| (percentile, target)))
+        .collect::<Vec<_>>();
 
-        let threads_info = match (min_threads, max_threads) {
-            (Some(min_threads), Some(max_threads)) => ThreadsInfo::Auto {
-                min_threads,
-                max_threads,
-                auto,
-            },
-            _ => ThreadsInfo::Fixed {
-                // SAFETY: The parameters are grouped in a way that this won't ever panic
-                // when entering this branch.
+        let threads_info = if auto_latency {
+            anyhow::ensure!(
+                latency_targets.len() == 1,
+                "auto-latency requires exactly one of p95=, p99= or p999="
+            );
+            let (percentile, target) = latency_targets[0];
+            ThreadsInfo::AutoLatency {
+                // SAFETY: threads= is required in the group of auto-latency.
                 threads: threads.unwrap(),
-                throttle,
-                co_fixed,
-            },
+                percentile,
+                target,
+            }
+        } else {
+            anyhow::ensure!(
+                throttle.is_none() || fixed.is_none(),
+                "throttle= and fixed= can't be used together"
+            );
+            match (min_threads, max_threads) {
+                (Some(min_threads), Some(max_threads)) => ThreadsInfo::Auto {
+                    min_threads,
+                    max_threads,
+                    auto,
+                },
+                _ => ThreadsInfo::Fixed {
+                    // SAFETY: The parameters are grouped in a way that this won't ever panic
+                    // when entering this branch.
+                    threads: threads.unwrap(),
+                    throttle: fixed.or(throttle),
+                    fixed_schedule: fixed.is_some(),
+                    // The latencies of the fixed schedule are measured
+                    // from the intended start of the operations.
+                    co_fixed: co_fixed || fixed.is_some(),
+                },
+            }
         };
 
-        Ok(Self { threads_info })
+        let rate_limited = matches!(
+            threads_info,
+            ThreadsInfo::Fixed {
+                throttle: Some(_),
+                ..
+            } | ThreadsInfo::AutoLatency { .. }
+        );
+        anyhow::ensure!(
+            arrival == Arrival::Uniform || rate_limited,
+            "arrival= requires throttle=, fixed= or auto-latency"
+        );
+
+        Ok(Self {
+            threads_info,
+            serialize_partitions,
+            streams,
+            max_in_flight,
+            arrival,
+            runtimes,
+            duty_cycle: handles.duty_cycle.get(),
+        })
     }
 }
 
 struct RateParamHandles {
     pub threads: SimpleParamHandle<u64>,
     pub throttle: SimpleParamHandle<Rate>,
+    pub fixed: SimpleParamHandle<Rate>,
     pub co_fixed: SimpleParamHandle<bool>,
     pub threads_gte: SimpleParamHandle<u64>,
     pub threads_lte: SimpleParamHandle<u64>,
     pub auto: SimpleParamHandle<bool>,
+    pub serialize_partitions: SimpleParamHandle<bool>,
+    pub streams: SimpleParamHandle<bool>,
+    pub max_in_flight: SimpleParamHandle<u64>,
+    pub arrival: SimpleParamHandle<Arrival>,
+    pub runtimes: SimpleParamHandle<u64>,
+    pub duty_cycle: SimpleParamHandle<DutyCycle>,
+    pub auto_latency: SimpleParamHandle<bool>,
+    pub p95: SimpleParamHandle<Latency>,
+    pub p99: SimpleParamHandle<Latency>,
+    pub p999: SimpleParamHandle<Latency>,
 }
 
 fn prepare_parser() -> (ParamsParser, RateParamHandles) {
@@ -119,6 +311,13 @@ fn prepare_parser() -> (ParamsParser, RateParamHandles) {
         "throttle operations per second across all clients to a maximum rate (or less) with no implied schedule",
         false,
     );
+    // Must be declared before the `fixed` flag, which is its prefix.
+    let fixed = parser.simple_param(
+        "fixed=",
+        None,
+        "expect fixed rate of operations per second across all clients with implied schedule",
+        false,
+    );
     let co_fixed = parser.simple_param(
         "fixed",
         None,
@@ -143,29 +342,138 @@ fn prepare_parser() -> (ParamsParser, RateParamHandles) {
         "stop increasing threads once throughput saturates",
         false,
     );
+    let serialize_partitions = parser.simple_param(
+        "serialize-partitions",
+        None,
+        "never execute the operations on the same partition concurrently",
+        false,
+    );
+    let streams = parser.simple_param(
+        "streams",
+        None,
+        "perform each operation of the mixed command by separate threads, splitting the threads and the throttle according to the ratio",
+        false,
+    );
+    let max_in_flight = parser.simple_param(
+        "max-in-flight=",
+        None,
+        "execute at most this many operations at the same time, regardless of the number of clients",
+        false,
+    );
+    let arrival = parser.simple_param(
+        "arrival=",
+        None,
+        "distribution of the start times of the rate limited operations: uniform (equal intervals, default) or poisson (exponentially distributed intervals)",
+        false,
+    );
+    let runtimes = parser.simple_param(
+        "runtimes=",
+        None,
+        "spread the clients between this many single-threaded runtimes with their own threads and shares of the rate, e.g. one per core for very high rates",
+        false,
+    );
+    let duty_cycle = parser.simple_param(
+        "duty-cycle=",
+        None,
+        "run the load only in windows, pausing the clients in between, given as on/off, e.g. 30s/30s",
+        false,
+    );
+    let auto_latency = parser.simple_param(
+        "auto-latency",
+        None,
+        "adjust the rate to find the maximum throughput which keeps the latency under the target",
+        true,
+    );
+    let p95 = parser.simple_param(
+        "p95=",
+        None,
+        "target of the 95th percentile of latency",
+        false,
+    );
+    let p99 = parser.simple_param(
+        "p99=",
+        None,
+        "target of the 99th percentile of latency",
+        false,
+    );
+    let p999 = parser.simple_param(
+        "p999=",
+        None,
+        "target of the 99.9th percentile of latency",
+        false,
+    );
 
     // $ ./cassandra-stress help -rate
-    // Usage: -rate threads=? [throttle=?] [fixed]
+    // Usage: -rate threads=? [throttle=?] [fixed=?] [fixed]
     //  OR
     // Usage: -rate [threads>=?] [threads<=?] [auto]
-    parser.group(&[&threads, &throttle, &co_fixed]);
-    parser.group(&[&threads_gte, &threads_lte, &auto]);
+    parser.group(&[
+        &threads,
+        &throttle,
+        &fixed,
+        &co_fixed,
+        &serialize_partitions,
+        &streams,
+        &max_in_flight,
+        &arrival,
+        &runtimes,
+        &duty_cycle,
+    ]);
+    parser.group(&[&threads_gte, &threads_lte, &auto, &serialize_partitions]);
+    parser.group(&[
+        &threads,
+        &auto_latency,
+        &p95,
+        &p99,
+        &p999,
+        &serialize_partitions,
+        &max_in_flight,
+        &arrival,
+        &runtimes,
+        &duty_cycle,
+    ]);
+
+    parser.example("threads=80 throttle=8000/s");
+    parser.example("threads=80 fixed=8000/s");
+    parser.example("threads>=4 threads<=64 auto");
+    parser.example("threads=16 serialize-partitions");
+    parser.example("threads=64 auto-latency p99=10ms");
+    parser.example("threads=110 throttle=55000/s streams");
+    parser.example("threads=1000 fixed=50000/s max-in-flight=200");
+    parser.example("threads=100 fixed=10000/s arrival=poisson");
+    parser.example("threads=512 throttle=1000000/s runtimes=16");
+    parser.example("threads=64 throttle=10000/s duty-cycle=30s/30s");
 
     (
         parser,
         RateParamHandles {
             threads,
             throttle,
+            fixed,
             co_fixed,
             threads_gte,
             threads_lte,
             auto,
+            serialize_partitions,
+            streams,
+            max_in_flight,
+            arrival,
+            runtimes,
+            duty_cycle,
+            auto_latency,
+            p95,
+            p99,
+            p999,
         },
     )
 }
 
 #[cfg(test)]
 mod tests {
+    use std::time::Duration;
+
+    use cql_stress::configuration::{Arrival, DutyCycle};
+
     use crate::settings::option::{rate::ThreadsInfo, RateOption};
 
     use super::prepare_parser;
@@ -182,12 +490,39 @@ mod tests {
             ThreadsInfo::Fixed {
                 threads: 100,
                 throttle: Some(15),
+                fixed_schedule: false,
                 co_fixed: false
             },
             params.threads_info
         );
     }
 
+    #[test]
+    fn rate_fixed_params_test() {
+        let args = vec!["threads=100", "fixed=15/s"];
+        let (parser, handles) = prepare_parser();
+
+        assert!(parser.parse(args).is_ok());
+
+        let params = RateOption::from_handles(handles).unwrap();
+        assert_eq!(
+            ThreadsInfo::Fixed {
+                threads: 100,
+                throttle: Some(15),
+                fixed_schedule: true,
+                co_fixed: true
+            },
+            params.threads_info
+        );
+
+        // The fixed rate and the throttle are mutually exclusive.
+        let args = vec!["threads=100", "throttle=15/s", "fixed=15/s"];
+        let (parser, handles) = prepare_parser();
+
+        assert!(parser.parse(args).is_ok());
+        assert!(RateOption::from_handles(handles).is_err());
+    }
+
     #[test]
     fn rate_good_params_group_two_test() {
         let args = vec!["threads<=200", "auto"];
@@ -213,4 +548,129 @@ mod tests {
 
         assert!(parser.parse(args).is_err());
     }
+
+    #[test]
+    fn rate_serialize_partitions_test() {
+        let args = vec!["threads=8", "serialize-partitions"];
+        let (parser, handles) = prepare_parser();
+
+        assert!(parser.parse(args).is_ok());
+
+        let params = RateOption::from_handles(handles).unwrap();
+        assert!(params.serialize_partitions);
+        assert!(params.create_partition_locks().is_some());
+    }
+
+    #[test]
+    fn rate_streams_test() {
+        let args = vec!["threads=110", "throttle=55000/s", "streams"];
+        let (parser, handles) = prepare_parser();
+
+        assert!(parser.parse(args).is_ok());
+
+        let params = RateOption::from_handles(handles).unwrap();
+        assert!(params.streams);
+    }
+
+    #[test]
+    fn rate_max_in_flight_test() {
+        let args = vec!["threads=1000", "fixed=50000/s", "max-in-flight=200"];
+        let (parser, handles) = prepare_parser();
+
+        assert!(parser.parse(args).is_ok());
+
+        let params = RateOption::from_handles(handles).unwrap();
+        assert_eq!(Some(200), params.max_in_flight);
+
+        let (parser, handles) = prepare_parser();
+        assert!(parser.parse(vec!["threads=10", "max-in-flight=0"]).is_ok());
+        assert!(RateOption::from_handles(handles).is_err());
+    }
+
+    #[test]
+    fn rate_arrival_test() {
+        let args = vec!["threads=100", "fixed=10000/s", "arrival=poisson"];
+        let (parser, handles) = prepare_parser();
+
+        assert!(parser.parse(args).is_ok());
+
+        let params = RateOption::from_handles(handles).unwrap();
+        assert_eq!(Arrival::Poisson, params.arrival);
+
+        // The arrival requires a rate limit.
+        let (parser, handles) = prepare_parser();
+        assert!(parser.parse(vec!["threads=100", "arrival=poisson"]).is_ok());
+        assert!(RateOption::from_handles(handles).is_err());
+    }
+
+    #[test]
+    fn rate_runtimes_test() {
+        let args = vec!["threads=512", "throttle=1000000/s", "runtimes=16"];
+        let (parser, handles) = prepare_parser();
+
+        assert!(parser.parse(args).is_ok());
+
+        let params = RateOption::from_handles(handles).unwrap();
+        assert_eq!(Some(16), params.runtimes);
+
+        let (parser, handles) = prepare_parser();
+        assert!(parser.parse(vec!["threads=10", "runtimes=0"]).is_ok());
+        assert!(RateOption::from_handles(handles).is_err());
+    }
+
+    #[test]
+    fn rate_duty_cycle_test() {
+        let args = vec!["threads=64", "throttle=10000/s", "duty-cycle=30s/1m"];
+        let (parser, handles) = prepare_parser();
+
+        assert!(parser.parse(args).is_ok());
+
+        let params = RateOption::from_handles(handles).unwrap();
+        assert_eq!(
+            Some(DutyCycle {
+                on: Duration::from_secs(30),
+                off: Duration::from_secs(60),
+            }),
+            params.duty_cycle
+        );
+
+        let (parser, _) = prepare_parser();
+        assert!(parser.parse(vec!["threads=64", "duty-cycle=30s"]).is_err());
+        let (parser, _) = prepare_parser();
+        assert!(parser
+            .parse(vec!["threads=64", "duty-cycle=0s/30s"])
+            .is_err());
+    }
+
+    #[test]
+    fn rate_auto_latency_test() {
+        let args = vec!["threads=32", "auto-latency", "p99=10ms"];
+        let (parser, handles) = prepare_parser();
+
+        assert!(parser.parse(args).is_ok());
+
+        let params = RateOption::from_handles(handles).unwrap();
+        assert_eq!(
+            ThreadsInfo::AutoLatency {
+                threads: 32,
+                percentile: 99.0,
+                target: Duration::from_millis(10),
+            },
+            params.threads_info
+        );
+    }
+
+    #[test]
+    fn rate_auto_latency_bad_params_test() {
+        // The target requires auto-latency.
+        let (parser, _) = prepare_parser();
+        assert!(parser.parse(vec!["threads=32", "p99=10ms"]).is_err());
+
+        // Exactly one target is required.
+        let (parser, handles) = prepare_parser();
+        assert!(parser
+            .parse(vec!["threads=32", "auto-latency", "p95=5ms", "p99=10ms"])
+            .is_ok());
+        assert!(RateOption::from_handles(handles).is_err());
+    }
 }
@@ -0,0 +1,151 @@
+use anyhow::{Context, Result};
+use cql_stress::stats_report::{ReportProtocol, ReportTarget};
+
+use crate::settings::{
+    param::{ParamsParser, SimpleParamHandle},
+    ParsePayload,
+};
+
+pub struct ReportOption {
+    pub target: Option<ReportTarget>,
+}
+
+impl ReportOption {
+    pub const CLI_STRING: &'static str = "-report";
+
+    pub fn description() -> &'static str {
+        "Send the stats of each interval to a Graphite or StatsD server"
+    }
+
+    pub fn parse(cl_args: &mut ParsePayload) -> Result<Self> {
+        // Nothing is reported unless the option is present.
+        let params = match cl_args.remove(Self::CLI_STRING) {
+            Some(params) => params,
+            None => return Ok(Self { target: None }),
+        };
+        let (parser, handles) = prepare_parser();
+        parser
+            .parse(params)
+            .context("Failed to parse -report option parameters")?;
+        Self::from_handles(handles)
+    }
+
+    pub fn print_help() {
+        let (parser, _) = prepare_parser();
+        parser.print_help();
+    }
+
+    pub fn print_settings(&self) {
+        if let Some(target) = &self.target {
+            println!("Report:");
+            println!("  Target: {}", target.describe());
+        }
+    }
+
+    fn from_handles(handles: ReportParamHandles) -> Result<Self> {
+        let prefix = handles.prefix.get().unwrap();
+        let target = match (handles.graphite.get(), handles.statsd.get()) {
+            (Some(address), None) => ReportTarget::new(ReportProtocol::Graphite, &address, &prefix),
+            (None, Some(address)) => ReportTarget::new(ReportProtocol::Statsd, &address, &prefix),
+            // Guaranteed by the parameter groups.
+            _ => unreachable!(),
+        }?;
+        Ok(Self {
+            target: Some(target),
+        })
+    }
+}
+
+struct ReportParamHandles {
+    graphite: SimpleParamHandle<String>,
+    statsd: SimpleParamHandle<String>,
+    prefix: SimpleParamHandle<String>,
+}
+
+fn prepare_parser() -> (ParamsParser, ReportParamHandles) {
+    let mut parser = ParamsParser::new(ReportOption::CLI_STRING);
+
+    let graphite = parser.simple_param(
+        "graphite=",
+        None,
+        "Send the metrics to the Graphite server at host:port, in the plaintext protocol",
+        true,
+    );
+    let statsd = parser.simple_param(
+        "statsd=",
+        None,
+        "Send the metrics to the StatsD server at host:port, as gauges",
+        true,
+    );
+    let prefix = parser.simple_param(
+        "prefix=",
+        Some("cassandra-stress"),
+        "Prefix of the names of the metrics",
+        false,
+    );
+
+    // $ ./cql-stress-cassandra-stress help -report
+    // Usage: -report graphite=? [prefix=?]
+    //  OR
+    // Usage: -report statsd=? [prefix=?]
+    parser.group(&[&graphite, &prefix]);
+    parser.group(&[&statsd, &prefix]);
+
+    parser.example("graphite=localhost:2003 prefix=loader1");
+    parser.example("statsd=localhost:8125");
+
+    (
+        parser,
+        ReportParamHandles {
+            graphite,
+            statsd,
+            prefix,
+        },
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use cql_stress::stats_report::ReportProtocol;
+
+    use crate::settings::option::{report::prepare_parser, ReportOption};
+
+    #[test]
+    fn report_good_params_test() {
+        let (parser, handles) = prepare_parser();
+
+        assert!(parser
+            .parse(vec!["graphite=localhost:2003", "prefix=loader1"])
+            .is_ok());
+
+        let params = ReportOption::from_handles(handles).unwrap();
+        let target = params.target.unwrap();
+        assert_eq!(ReportProtocol::Graphite, target.protocol);
+        assert_eq!("localhost:2003", target.address);
+        assert_eq!("loader1", target.prefix);
+
+        let (parser, handles) = prepare_parser();
+
+        assert!(parser.parse(vec!["statsd=127.0.0.1:8125"]).is_ok());
+
+        let params = ReportOption::from_handles(handles).unwrap();
+        let target = params.target.unwrap();
+        assert_eq!(ReportProtocol::Statsd, target.protocol);
+        assert_eq!("cassandra-stress", target.prefix);
+    }
+
+    #[test]
+    fn report_bad_params_test() {
+        let (parser, _) = prepare_parser();
+        assert!(parser.parse(vec![]).is_err());
+
+        let (parser, _) = prepare_parser();
+        assert!(parser
+            .parse(vec!["graphite=localhost:2003", "statsd=localhost:8125"])
+            .is_err());
+
+        let (parser, handles) = prepare_parser();
+        assert!(parser.parse(vec!["graphite=localhost"]).is_ok());
+        assert!(ReportOption::from_handles(handles).is_err());
+    }
+}
@@ -2,14 +2,49 @@ use crate::settings::{
     param::{MultiParamAcceptsArbitraryHandle, ParamsParser, SimpleParamHandle},
     ParsePayload,
 };
-use anyhow::Result;
-use std::collections::HashMap;
+use anyhow::{Context, Result};
+use cql_stress::replication::Replication;
+use scylla::transport::topology::{CqlType, NativeType, Table};
+use std::{collections::HashMap, num::NonZeroU32};
+
+use super::CLUSTERING_COLUMN;
+
+// The names of the keyspaces and tables are limited by the database.
+const MAX_NAME_LENGTH: usize = 48;
+
+fn validate_name(kind: &str, name: &str) -> Result<()> {
+    anyhow::ensure!(
+        !name.is_empty() && name.len() <= MAX_NAME_LENGTH,
+        "The {} name must have between 1 and {} characters, got: {:?}",
+        kind,
+        MAX_NAME_LENGTH,
+        name
+    );
+    anyhow::ensure!(
+        name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_'),
+        "The {} name can contain only alphanumeric characters and underscores, got: {:?}",
+        kind,
+        name
+    );
+    Ok(())
+}
+
+/// Quotes the identifier (validated with [validate_name]), so that it's case-sensitive.
+fn quote_identifier(identifier: &str) -> String {
+    format!("\"{}\"", identifier)
+}
 
 pub struct SchemaOption {
-    pub replication_opts: HashMap<String, String>,
+    pub replication: Replication,
     pub keyspace: String,
+    pub table: String,
+    pub counter_table: String,
     pub compaction_opts: HashMap<String, String>,
     pub compression: Option<String>,
+    pub ttl: Option<NonZeroU32>,
+    pub index: Option<String>,
+    /// Use the existing keyspace and tables instead of creating them.
+    pub existing: bool,
 }
 
 impl SchemaOption {
@@ -23,7 +58,7 @@ impl SchemaOption {
         let params = cl_args.remove(Self::CLI_STRING).unwrap_or_default();
         let (parser, handles) = prepare_parser();
         parser.parse(params)?;
-        Ok(Self::from_handles(handles))
+        Self::from_handles(handles)
     }
 
     pub fn print_help() {
@@ -34,29 +69,41 @@ impl SchemaOption {
     pub fn print_settings(&self) {
         println!("Schema:");
         println!("  Keyspace: {}", self.keyspace);
+        println!("  Table: {}", self.table);
+        println!("  Counter Table: {}", self.counter_table);
         println!(
             "  Replication Strategy Options: {:?}",
-            self.replication_opts
+            self.replication.options
         );
         println!("  Table Compression: {:?}", self.compression);
         println!("  Table Compaction Options: {:?}", self.compaction_opts);
+        match self.ttl {
+            Some(ttl) => println!("  Insert TTL: {} SECONDS", ttl),
+            None => println!("  Insert TTL: none"),
+        }
+        match &self.index {
+            Some(index) => println!("  Secondary Index: \"{}\"", index),
+            None => println!("  Secondary Index: none"),
+        }
+        println!("  Use Existing Schema: {}", self.existing);
     }
 
-    fn from_handles(handles: SchemaParamHandles) -> Self {
+    fn from_handles(handles: SchemaParamHandles) -> Result<Self> {
         let replication_strategy = handles.replication_strategy.get().unwrap();
-        let replication_factor = handles.replication_factor.get().unwrap();
-        let mut replication_opts = handles.replication_opts.get_arbitrary().unwrap();
+        let replication_factor = handles.replication_factor.get();
+        let replication_opts = handles.replication_opts.get_arbitrary().unwrap();
         let keyspace = handles.keyspace.get().unwrap();
+        let table = handles.table.get().unwrap();
+        let counter_table = handles.counter_table.get().unwrap();
         let compaction_strategy = handles.compaction_strategy.get();
         let mut compaction_opts = handles.compaction_opts.get_arbitrary().unwrap();
         let compression = handles.compression.get();
+        let ttl = handles.ttl.get();
+        let index = handles.index.get();
+        let existing = handles.existing.get().is_some();
 
-        replication_opts
-            .entry(String::from("replication_factor"))
-            .or_insert_with(|| replication_factor.to_string());
-        replication_opts
-            .entry(String::from("class"))
-            .or_insert(replication_strategy);
+        let replication =
+            Replication::new(&replication_strategy, replication_factor, replication_opts)?;
 
         if let Some(compaction_strategy) = compaction_strategy {
             compaction_opts
@@ -64,30 +111,43 @@ impl SchemaOption {
                 .or_insert(compaction_strategy);
         }
 
-        Self {
-            replication_opts,
+        validate_name("keyspace", &keyspace)?;
+        validate_name("table", &table)?;
+        validate_name("counter table", &counter_table)?;
+
+        Ok(Self {
+            replication,
             keyspace,
+            table,
+            counter_table,
             compaction_opts,
             compression,
-        }
+            ttl,
+            index,
+            existing,
+        })
     }
 
-    fn construct_replication_string(&self) -> String {
-        let options_str = self
-            .replication_opts
-            .iter()
-            .map(|(key, value)| format!("'{}': '{}'", key, value))
-            .collect::<Vec<_>>()
-            .join(", ");
+    /// Returns the quoted name of the standard table, used in the statements.
+    pub fn table_identifier(&self) -> String {
+        quote_identifier(&self.table)
+    }
+
+    /// Returns the quoted name of the counter table, used in the statements.
+    pub fn counter_table_identifier(&self) -> String {
+        quote_identifier(&self.counter_table)
+    }
 
-        format!("{{{}}}", options_str)
+    /// Returns the `USING TTL` clause that should be appended to insert statements.
+    pub fn construct_ttl_clause(&self) -> Option<String> {
+        self.ttl.map(|ttl| format!(" USING TTL {}", ttl))
     }
 
     pub fn construct_keyspace_creation_query(&self) -> String {
         format!(
-            "CREATE KEYSPACE IF NOT EXISTS \"{}\" WITH REPLICATION = {};",
-            self.keyspace,
-            self.construct_replication_string()
+            "CREATE KEYSPACE IF NOT EXISTS {} WITH REPLICATION = {};",
+            quote_identifier(&self.keyspace),
+            self.replication.to_cql()
         )
     }
 
@@ -108,17 +168,26 @@ impl SchemaOption {
     // we will allow the user to define some other types to use.
     fn construct_table_creation_query_with(
         &self,
-        table_name: &'static str,
+        table_name: &str,
         column_type: &'static str,
         column_names: &[String],
+        clustering_column: Option<&str>,
     ) -> String {
         // Note that for now we hardcode the columns.
         // In the future, `-col` option will be supported, that lets the user define column names as well as the number of columns.
         let mut result = format!("CREATE TABLE IF NOT EXISTS {0} (key blob", table_name);
+        if let Some(clustering_column) = clustering_column {
+            result += &format!(", {} int", clustering_column);
+        }
         for column in column_names {
             result += &format!(", \"{}\" {}", column, column_type);
         }
-        result += ", PRIMARY KEY (key))";
+        match clustering_column {
+            Some(clustering_column) => {
+                result += &format!(", PRIMARY KEY (key, {}))", clustering_column)
+            }
+            None => result += ", PRIMARY KEY (key))",
+        }
         result += " WITH compression = {";
         if let Some(compression) = &self.compression {
             result += &format!("'sstable_compression': '{}'", compression);
@@ -131,13 +200,126 @@ impl SchemaOption {
         result
     }
 
-    pub fn construct_table_creation_query(&self, column_names: &[String]) -> String {
-        self.construct_table_creation_query_with("standard1", "blob", column_names)
+    /// Returns the query creating the standard table, with the clustering column
+    /// if each partition consists of multiple rows (see `-col clustering=`).
+    pub fn construct_table_creation_query(
+        &self,
+        column_names: &[String],
+        clustering: bool,
+    ) -> String {
+        self.construct_table_creation_query_with(
+            &self.table_identifier(),
+            "blob",
+            column_names,
+            clustering.then_some(CLUSTERING_COLUMN),
+        )
+    }
+
+    /// Returns the query creating the secondary index on the `index=` column of the standard table.
+    pub fn construct_index_creation_query(&self) -> Option<String> {
+        self.index.as_ref().map(|index| {
+            format!(
+                "CREATE INDEX IF NOT EXISTS ON {} (\"{}\");",
+                self.table_identifier(),
+                index
+            )
+        })
     }
 
     pub fn construct_counter_table_creation_query(&self, column_names: &[String]) -> String {
-        self.construct_table_creation_query_with("counter1", "counter", column_names)
+        self.construct_table_creation_query_with(
+            &self.counter_table_identifier(),
+            "counter",
+            column_names,
+            None,
+        )
+    }
+
+    /// Verifies that the existing standard table has the same columns as the table
+    /// created by [Self::construct_table_creation_query].
+    pub fn verify_existing_table(
+        &self,
+        table: Option<&Table>,
+        column_names: &[String],
+        clustering: bool,
+    ) -> Result<()> {
+        verify_table_columns(
+            table,
+            NativeType::Blob,
+            column_names,
+            clustering.then_some(CLUSTERING_COLUMN),
+        )
+        .with_context(|| format!("Invalid existing table {}.{}", self.keyspace, self.table))
+    }
+
+    /// Verifies that the existing counter table has the same columns as the table
+    /// created by [Self::construct_counter_table_creation_query].
+    pub fn verify_existing_counter_table(
+        &self,
+        table: Option<&Table>,
+        column_names: &[String],
+    ) -> Result<()> {
+        verify_table_columns(table, NativeType::Counter, column_names, None).with_context(|| {
+            format!(
+                "Invalid existing counter table {}.{}",
+                self.keyspace, self.counter_table
+            )
+        })
+    }
+}
+
+fn verify_table_columns(
+    table: Option<&Table>,
+    column_type: NativeType,
+    column_names: &[String],
+    clustering_column: Option<&str>,
+) -> Result<()> {
+    let table = table.context("The table doesn't exist")?;
+    anyhow::ensure!(
+        table.partition_key == ["key"],
+        "The partition key must be the key column, got: {:?}",
+        table.partition_key
+    );
+    anyhow::ensure!(
+        table
+            .clustering_key
+            .iter()
+            .map(String::as_str)
+            .eq(clustering_column),
+        "The clustering key must be {:?} (see -col clustering=), got: {:?}",
+        clustering_column,
+        table.clustering_key
+    );
+
+    let mut expected_columns = vec![("key", NativeType::Blob)];
+    expected_columns.extend(clustering_column.map(|column| (column, NativeType::Int)));
+    expected_columns.extend(
+        column_names
+            .iter()
+            .map(|column| (column.as_str(), column_type.clone())),
+    );
+    for (column, expected_type) in expected_columns.iter() {
+        let actual_type = table
+            .columns
+            .get(*column)
+            .with_context(|| format!("Missing column {} (see -col)", column))?
+            .type_
+            .clone();
+        anyhow::ensure!(
+            actual_type == CqlType::Native(expected_type.clone()),
+            "The column {} must be of type {:?}, got: {:?}",
+            column,
+            expected_type,
+            actual_type
+        );
     }
+    // Otherwise, the reads of all of the columns wouldn't match the generated rows.
+    anyhow::ensure!(
+        table.columns.len() == expected_columns.len(),
+        "The table has more columns than configured with -col: {:?}",
+        table.columns.keys().collect::<Vec<_>>()
+    );
+    Ok(())
 }
 
 struct SchemaParamHandles {
@@ -145,9 +327,14 @@ struct SchemaParamHandles {
     replication_strategy: SimpleParamHandle<String>,
     replication_opts: MultiParamAcceptsArbitraryHandle,
     keyspace: SimpleParamHandle<String>,
+    table: SimpleParamHandle<String>,
+    counter_table: SimpleParamHandle<String>,
     compaction_strategy: SimpleParamHandle<String>,
     compaction_opts: MultiParamAcceptsArbitraryHandle,
     compression: SimpleParamHandle<String>,
+    ttl: SimpleParamHandle<NonZeroU32>,
+    index: SimpleParamHandle<String>,
+    existing: SimpleParamHandle<bool>,
 }
 
 fn prepare_parser() -> (ParamsParser, SchemaParamHandles) {
@@ -159,8 +346,12 @@ fn prepare_parser() -> (ParamsParser, SchemaParamHandles) {
         "The replication strategy to use",
         false,
     );
-    let replication_factor =
-        parser.simple_subparam("factor=", Some("1"), "The number of replicas", false);
+    let replication_factor = parser.simple_subparam(
+        "factor=",
+        None,
+        "The number of replicas, default:1 (NetworkTopologyStrategy accepts the per-datacenter factors instead)",
+        false,
+    );
     // Multiparameter with two predefined parameters: `strategy` and `factor`.
     let replication = parser.multi_param(
         "replication",
@@ -174,6 +365,18 @@ fn prepare_parser() -> (ParamsParser, SchemaParamHandles) {
         "The keyspace name to use",
         false,
     );
+    let table = parser.simple_param(
+        "table=",
+        Some("standard1"),
+        "The name of the standard table (case-sensitive)",
+        false,
+    );
+    let counter_table = parser.simple_param(
+        "counter-table=",
+        Some("counter1"),
+        "The name of the counter table (case-sensitive)",
+        false,
+    );
     let compaction_strategy =
         parser.simple_subparam("strategy=", None, "The compaction strategy to use", false);
     let compaction = parser.multi_param(
@@ -189,9 +392,45 @@ fn prepare_parser() -> (ParamsParser, SchemaParamHandles) {
         false,
     );
 
+    let ttl = parser.simple_param(
+        "ttl=",
+        None,
+        "Time to live (in seconds) of the inserted rows, default:no TTL",
+        false,
+    );
+
+    let index = parser.simple_param(
+        "index=",
+        None,
+        "Create a secondary index on the given column of the standard table, queried by the index_read command, default:no index",
+        false,
+    );
+
+    let existing = parser.simple_param(
+        "existing",
+        None,
+        "Use the existing keyspace and tables instead of creating them, verifying that their columns match the -col settings",
+        false,
+    );
+
     // $ ./cassandra-stress help -schema
     // Usage: -schema [replication(?)] [keyspace=?] [compaction(?)] [compression=?]
-    parser.group(&[&replication, &keyspace, &compaction, &compression]);
+    // Additionally, the tool accepts [table=?], [counter-table=?], [ttl=?], [index=?] and [existing].
+    parser.group(&[
+        &replication,
+        &keyspace,
+        &table,
+        &counter_table,
+        &compaction,
+        &compression,
+        &ttl,
+        &index,
+        &existing,
+    ]);
+
+    parser.example("replication(factor=3) keyspace=keyspace1");
+    parser.example("keyspace=Job1 table=standard1 counter-table=counter1");
+    parser.example("replication(strategy=NetworkTopologyStrategy,dc1=3) compression=LZ4Compressor");
 
     (
         parser,
@@ -200,17 +439,49 @@ fn prepare_parser() -> (ParamsParser, SchemaParamHandles) {
             replication_strategy,
             replication_opts: replication,
             keyspace,
+            table,
+            counter_table,
             compaction_strategy,
             compaction_opts: compaction,
             compression,
+            ttl,
+            index,
+            existing,
         },
     )
 }
 
 #[cfg(test)]
 mod tests {
+    use scylla::transport::topology::{Column, ColumnKind, CqlType, NativeType, Table};
+
     use super::{prepare_parser, SchemaOption};
 
+    fn table(columns: &[(&str, ColumnKind, NativeType)]) -> Table {
+        let names = |kind: ColumnKind| {
+            columns
+                .iter()
+                .filter(|(_, column_kind, _)| *column_kind == kind)
+                .map(|(name, _, _)| name.to_string())
+                .collect()
+        };
+        Table {
+            columns: columns
+                .iter()
+                .map(|(name, kind, type_)| {
+                    let column = Column {
+                        type_: CqlType::Native(type_.clone()),
+                        kind: kind.clone(),
+                    };
+                    (name.to_string(), column)
+                })
+                .collect(),
+            partition_key: names(ColumnKind::PartitionKey),
+            clustering_key: names(ColumnKind::Clustering),
+            partitioner: None,
+        }
+    }
+
     #[test]
     fn schema_param_good_test() {
         let args = vec![
@@ -222,27 +493,28 @@ mod tests {
         let (parser, handles) = prepare_parser();
         assert!(parser.parse(args).is_ok());
 
-        let params = SchemaOption::from_handles(handles);
+        let params = SchemaOption::from_handles(handles).unwrap();
 
-        assert_eq!(4, params.replication_opts.len());
+        assert_eq!(4, params.replication.options.len());
         assert_eq!(
             Some("3"),
             params
-                .replication_opts
+                .replication
+                .options
                 .get("replication_factor")
                 .map(String::as_str)
         );
         assert_eq!(
             Some("MyStrategy"),
-            params.replication_opts.get("class").map(String::as_str)
+            params.replication.options.get("class").map(String::as_str)
         );
         assert_eq!(
             Some("value1"),
-            params.replication_opts.get("key1").map(String::as_str)
+            params.replication.options.get("key1").map(String::as_str)
         );
         assert_eq!(
             Some("value2"),
-            params.replication_opts.get("key2").map(String::as_str)
+            params.replication.options.get("key2").map(String::as_str)
         );
         assert_eq!("my_keyspace", params.keyspace);
         assert_eq!(1, params.compaction_opts.len());
@@ -251,5 +523,155 @@ mod tests {
             params.compaction_opts.get("key1").map(String::as_str)
         );
         assert_eq!(None, params.compression);
+        assert_eq!(None, params.ttl);
+        assert_eq!(None, params.construct_index_creation_query());
+    }
+
+    #[test]
+    fn schema_table_creation_test() {
+        let (parser, handles) = prepare_parser();
+        assert!(parser.parse(vec![]).is_ok());
+        let params = SchemaOption::from_handles(handles).unwrap();
+        let columns = vec![String::from("C0")];
+
+        assert_eq!(
+            "CREATE TABLE IF NOT EXISTS \"standard1\" (key blob, \"C0\" blob, PRIMARY KEY (key)) WITH compression = {};",
+            params.construct_table_creation_query(&columns, false)
+        );
+        assert_eq!(
+            "CREATE TABLE IF NOT EXISTS \"standard1\" (key blob, ck int, \"C0\" blob, PRIMARY KEY (key, ck)) WITH compression = {};",
+            params.construct_table_creation_query(&columns, true)
+        );
+    }
+
+    #[test]
+    fn schema_param_ttl_test() {
+        let args = vec!["ttl=3600"];
+
+        let (parser, handles) = prepare_parser();
+        assert!(parser.parse(args).is_ok());
+
+        let params = SchemaOption::from_handles(handles).unwrap();
+
+        assert_eq!(Some(3600), params.ttl.map(|ttl| ttl.get()));
+        assert_eq!(
+            Some(" USING TTL 3600"),
+            params.construct_ttl_clause().as_deref()
+        );
+    }
+
+    #[test]
+    fn schema_param_index_test() {
+        let args = vec!["index=C0"];
+
+        let (parser, handles) = prepare_parser();
+        assert!(parser.parse(args).is_ok());
+
+        let params = SchemaOption::from_handles(handles).unwrap();
+
+        assert_eq!(Some("C0"), params.index.as_deref());
+        assert_eq!(
+            Some("CREATE INDEX IF NOT EXISTS ON \"standard1\" (\"C0\");"),
+            params.construct_index_creation_query().as_deref()
+        );
+    }
+
+    #[test]
+    fn schema_param_table_names_test() {
+        let args = vec![
+            "keyspace=Job1",
+            "table=Standard_2",
+            "counter-table=counter2",
+        ];
+
+        let (parser, handles) = prepare_parser();
+        assert!(parser.parse(args).is_ok());
+
+        let params = SchemaOption::from_handles(handles).unwrap();
+        let columns = vec![String::from("C0")];
+
+        assert_eq!("\"Standard_2\"", params.table_identifier());
+        assert!(params
+            .construct_keyspace_creation_query()
+            .starts_with("CREATE KEYSPACE IF NOT EXISTS \"Job1\" WITH REPLICATION"));
+        assert_eq!(
+            "CREATE TABLE IF NOT EXISTS \"counter2\" (key blob, \"C0\" counter, PRIMARY KEY (key)) WITH compression = {};",
+            params.construct_counter_table_creation_query(&columns)
+        );
+    }
+
+    #[test]
+    fn schema_param_bad_table_names_test() {
+        for args in [
+            vec!["table=standard-1"],
+            vec!["table=a\"b"],
+            vec!["keyspace=ks.table"],
+            vec!["counter-table=a_very_long_name_of_the_counter_table_exceeding_limit"],
+        ] {
+            let (parser, handles) = prepare_parser();
+            assert!(parser.parse(args).is_ok());
+            assert!(SchemaOption::from_handles(handles).is_err());
+        }
+    }
+
+    #[test]
+    fn schema_param_network_topology_strategy_test() {
+        let args = vec!["replication(strategy=NetworkTopologyStrategy,dc1=3,dc2=2)"];
+
+        let (parser, handles) = prepare_parser();
+        assert!(parser.parse(args).is_ok());
+
+        let params = SchemaOption::from_handles(handles).unwrap();
+        assert_eq!(
+            "CREATE KEYSPACE IF NOT EXISTS \"keyspace1\" WITH REPLICATION = {'class': 'NetworkTopologyStrategy', 'dc1': '3', 'dc2': '2'};",
+            params.construct_keyspace_creation_query()
+        );
+    }
+
+    #[test]
+    fn schema_param_bad_datacenter_factor_test() {
+        let args = vec!["replication(strategy=NetworkTopologyStrategy,dc1=three)"];
+
+        let (parser, handles) = prepare_parser();
+        assert!(parser.parse(args).is_ok());
+        assert!(SchemaOption::from_handles(handles).is_err());
+    }
+
+    #[test]
+    fn schema_param_existing_test() {
+        let (parser, handles) = prepare_parser();
+        assert!(parser.parse(vec!["existing"]).is_ok());
+        let params = SchemaOption::from_handles(handles).unwrap();
+        assert!(params.existing);
+
+        let columns = vec![String::from("C0"), String::from("C1")];
+        let standard = table(&[
+            ("key", ColumnKind::PartitionKey, NativeType::Blob),
+            ("ck", ColumnKind::Clustering, NativeType::Int),
+            ("C0", ColumnKind::Regular, NativeType::Blob),
+            ("C1", ColumnKind::Regular, NativeType::Blob),
+        ]);
+        assert!(params
+            .verify_existing_table(Some(&standard), &columns, true)
+            .is_ok());
+        // The clustering column is not configured with -col clustering=.
+        assert!(params
+            .verify_existing_table(Some(&standard), &columns, false)
+            .is_err());
+        // The table has a column which is not configured with -col.
+        assert!(params
+            .verify_existing_table(Some(&standard), &columns[..1], true)
+            .is_err());
+        assert!(params.verify_existing_table(None, &columns, true).is_err());
+
+        let counter = table(&[
+            ("key", ColumnKind::PartitionKey, NativeType::Blob),
+            ("C0", ColumnKind::Regular, NativeType::Counter),
+            ("C1", ColumnKind::Regular, NativeType::Blob),
+        ]);
+        // C1 is not a counter column.
+        assert!(params
+            .verify_existing_counter_table(Some(&counter), &columns)
+            .is_err());
     }
 }
@@ -0,0 +1,99 @@
+use anyhow::{Context, Result};
+
+use crate::settings::{
+    param::{ParamsParser, SimpleParamHandle},
+    ParsePayload,
+};
+
+pub struct SeedOption {
+    pub seed: Option<u64>,
+}
+
+impl SeedOption {
+    pub const CLI_STRING: &'static str = "-seed";
+
+    pub fn description() -> &'static str {
+        "Seed of the random generators, for reproducible runs"
+    }
+
+    pub fn parse(cl_args: &mut ParsePayload) -> Result<Self> {
+        let params = cl_args.remove(Self::CLI_STRING).unwrap_or_default();
+        let (parser, handles) = prepare_parser();
+        parser
+            .parse(params)
+            .context("Failed to parse -seed option parameters")?;
+        Ok(Self::from_handles(handles))
+    }
+
+    pub fn print_help() {
+        let (parser, _) = prepare_parser();
+        parser.print_help();
+    }
+
+    pub fn print_settings(&self) {
+        println!("Seed:");
+        match self.seed {
+            Some(seed) => println!("  Seed: {}", seed),
+            None => println!("  Seed: random"),
+        }
+    }
+
+    fn from_handles(handles: SeedParamHandles) -> Self {
+        Self {
+            seed: handles.seed.get(),
+        }
+    }
+}
+
+struct SeedParamHandles {
+    seed: SimpleParamHandle<u64>,
+}
+
+fn prepare_parser() -> (ParamsParser, SeedParamHandles) {
+    let mut parser = ParamsParser::new(SeedOption::CLI_STRING);
+
+    let seed = parser.simple_param(
+        "",
+        None,
+        "Seed of the random generators. Two runs with the same seed and thread count generate the same values in each of the threads",
+        false,
+    );
+
+    // $ ./cql-stress-cassandra-stress help -seed
+    // Usage: -seed []
+    parser.group(&[&seed]);
+
+    (parser, SeedParamHandles { seed })
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::settings::option::{seed::prepare_parser, SeedOption};
+
+    #[test]
+    fn seed_good_params_test() {
+        let (parser, handles) = prepare_parser();
+
+        assert!(parser.parse(vec!["42"]).is_ok());
+
+        let params = SeedOption::from_handles(handles);
+        assert_eq!(Some(42), params.seed);
+    }
+
+    #[test]
+    fn seed_default_params_test() {
+        let (parser, handles) = prepare_parser();
+
+        assert!(parser.parse(vec![]).is_ok());
+
+        let params = SeedOption::from_handles(handles);
+        assert!(params.seed.is_none());
+    }
+
+    #[test]
+    fn seed_bad_params_test() {
+        let (parser, _) = prepare_parser();
+
+        assert!(parser.parse(vec!["abc"]).is_err());
+    }
+}
@@ -0,0 +1,283 @@
+use std::net::IpAddr;
+
+use anyhow::{Context, Result};
+use openssl::ssl::{SslContext, SslContextBuilder, SslFiletype, SslMethod, SslVerifyMode};
+
+use crate::settings::{
+    param::{types::ExplicitBool, ParamsParser, SimpleParamHandle},
+    ParsePayload,
+};
+
+pub struct TransportOption {
+    pub ca_cert_file: Option<String>,
+    pub client_cert_file: Option<String>,
+    pub client_key_file: Option<String>,
+    /// Whether the certificate of the server is verified, along with
+    /// the host name it was issued for.
+    pub hostname_verification: bool,
+    /// The host name (or the address) checked by `hostname_verification`.
+    pub server_name: Option<String>,
+}
+
+impl TransportOption {
+    pub const CLI_STRING: &'static str = "-transport";
+
+    pub fn description() -> &'static str {
+        "Custom transport factories"
+    }
+
+    pub fn parse(cl_args: &mut ParsePayload) -> Result<Self> {
+        let params = cl_args.remove(Self::CLI_STRING).unwrap_or_default();
+        let (parser, handles) = prepare_parser();
+        parser
+            .parse(params)
+            .context("Failed to parse -transport option parameters")?;
+        Self::from_handles(handles)
+    }
+
+    pub fn print_help() {
+        let (parser, _) = prepare_parser();
+        parser.print_help();
+    }
+
+    pub fn print_settings(&self) {
+        println!("Transport:");
+        println!("  SSL enabled: {}", self.ssl_enabled());
+        if let Some(ca_cert_file) = &self.ca_cert_file {
+            println!("  CA certificate file: {}", ca_cert_file);
+        }
+        if let Some(client_cert_file) = &self.client_cert_file {
+            println!("  Client certificate file: {}", client_cert_file);
+        }
+        if let Some(client_key_file) = &self.client_key_file {
+            println!("  Client key file: {}", client_key_file);
+        }
+        println!("  Hostname verification: {}", self.hostname_verification);
+        if let Some(server_name) = &self.server_name {
+            println!("  Server name: {}", server_name);
+        }
+    }
+
+    /// The encryption is enabled once any of the PEM files is provided,
+    /// or the server certificates are verified.
+    pub fn ssl_enabled(&self) -> bool {
+        self.ca_cert_file.is_some() || self.client_cert_file.is_some() || self.hostname_verification
+    }
+
+    /// Builds the [SslContext] based on the provided PEM files.
+    /// Returns `None` if the encryption is disabled.
+    pub fn generate_ssl_context(&self) -> Result<Option<SslContext>> {
+        if !self.ssl_enabled() {
+            return Ok(None);
+        }
+
+        let mut context_builder = SslContextBuilder::new(SslMethod::tls_client())?;
+
+        if self.hostname_verification {
+            context_builder.set_verify(SslVerifyMode::PEER);
+            // The driver creates the TLS session of each connection from
+            // the same context, without the host it connects to, so all
+            // of the nodes are checked against the same name.
+            let server_name = self.server_name.as_deref().unwrap();
+            match server_name.parse::<IpAddr>() {
+                Ok(ip) => context_builder.verify_param_mut().set_ip(ip)?,
+                Err(_) => context_builder.verify_param_mut().set_host(server_name)?,
+            }
+            if self.ca_cert_file.is_none() {
+                context_builder.set_default_verify_paths()?;
+            }
+        } else {
+            context_builder.set_verify(SslVerifyMode::NONE);
+        }
+
+        if let Some(ca_cert_file) = &self.ca_cert_file {
+            let ca_cert_path = std::fs::canonicalize(ca_cert_file)
+                .with_context(|| format!("Invalid CA certificate path: {}", ca_cert_file))?;
+            context_builder.set_ca_file(ca_cert_path)?;
+        }
+        if let Some(client_cert_file) = &self.client_cert_file {
+            let client_cert_path = std::fs::canonicalize(client_cert_file).with_context(|| {
+                format!("Invalid client certificate path: {}", client_cert_file)
+            })?;
+            context_builder.set_certificate_file(client_cert_path, SslFiletype::PEM)?;
+        }
+        if let Some(client_key_file) = &self.client_key_file {
+            let client_key_path = std::fs::canonicalize(client_key_file)
+                .with_context(|| format!("Invalid client key path: {}", client_key_file))?;
+            context_builder.set_private_key_file(client_key_path, SslFiletype::PEM)?;
+        }
+
+        Ok(Some(context_builder.build()))
+    }
+
+    fn from_handles(handles: TransportParamHandles) -> Result<Self> {
+        let ca_cert_file = handles.ca_cert_file.get();
+        let client_cert_file = handles.client_cert_file.get();
+        let client_key_file = handles.client_key_file.get();
+        let hostname_verification = handles.hostname_verification.get().unwrap();
+        let server_name = handles.server_name.get();
+
+        anyhow::ensure!(
+            client_cert_file.is_some() == client_key_file.is_some(),
+            "client-cert-file and client-key-file need to be either both set or unspecified"
+        );
+        anyhow::ensure!(
+            hostname_verification == server_name.is_some(),
+            "hostname-verification=true and server-name= need to be either both set or unspecified"
+        );
+
+        Ok(Self {
+            ca_cert_file,
+            client_cert_file,
+            client_key_file,
+            hostname_verification,
+            server_name,
+        })
+    }
+}
+
+struct TransportParamHandles {
+    ca_cert_file: SimpleParamHandle<String>,
+    client_cert_file: SimpleParamHandle<String>,
+    client_key_file: SimpleParamHandle<String>,
+    hostname_verification: SimpleParamHandle<ExplicitBool>,
+    server_name: SimpleParamHandle<String>,
+}
+
+fn prepare_parser() -> (ParamsParser, TransportParamHandles) {
+    let mut parser = ParamsParser::new(TransportOption::CLI_STRING);
+
+    let ca_cert_file = parser.simple_param(
+        "ca-cert-file=",
+        None,
+        "Path to the CA certificate file (PEM), enables encryption",
+        false,
+    );
+    let client_cert_file = parser.simple_param(
+        "client-cert-file=",
+        None,
+        "Path to the client certificate file (PEM), enables client certificate authentication",
+        false,
+    );
+    let client_key_file = parser.simple_param(
+        "client-key-file=",
+        None,
+        "Path to the client private key file (PEM), enables client certificate authentication",
+        false,
+    );
+    let hostname_verification = parser.simple_param(
+        "hostname-verification=",
+        Some("false"),
+        "Verify the server certificate against the CA (or the system CAs, without ca-cert-file=) and the host name of server-name= (true|false), enables encryption",
+        false,
+    );
+    let server_name = parser.simple_param(
+        "server-name=",
+        None,
+        "The host name or the address the certificates of all nodes must be issued for, required by hostname-verification=true",
+        false,
+    );
+
+    // $ ./cql-stress-cassandra-stress help -transport
+    // Usage: -transport [ca-cert-file=?] [client-cert-file=?] [client-key-file=?] [hostname-verification=?] [server-name=?]
+    parser.group(&[
+        &ca_cert_file,
+        &client_cert_file,
+        &client_key_file,
+        &hostname_verification,
+        &server_name,
+    ]);
+
+    (
+        parser,
+        TransportParamHandles {
+            ca_cert_file,
+            client_cert_file,
+            client_key_file,
+            hostname_verification,
+            server_name,
+        },
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::settings::option::{transport::prepare_parser, TransportOption};
+
+    #[test]
+    fn transport_good_params_test() {
+        let args = vec![
+            "ca-cert-file=/path/to/ca.pem",
+            "client-cert-file=/path/to/client.pem",
+            "client-key-file=/path/to/client.key",
+            "hostname-verification=true",
+            "server-name=node1.example.com",
+        ];
+        let (parser, handles) = prepare_parser();
+
+        assert!(parser.parse(args).is_ok());
+
+        let params = TransportOption::from_handles(handles).unwrap();
+        assert_eq!(Some("/path/to/ca.pem"), params.ca_cert_file.as_deref());
+        assert_eq!(
+            Some("/path/to/client.pem"),
+            params.client_cert_file.as_deref()
+        );
+        assert_eq!(
+            Some("/path/to/client.key"),
+            params.client_key_file.as_deref()
+        );
+        assert!(params.hostname_verification);
+        assert_eq!(Some("node1.example.com"), params.server_name.as_deref());
+        assert!(params.ssl_enabled());
+    }
+
+    #[test]
+    fn transport_default_params_test() {
+        let (parser, handles) = prepare_parser();
+
+        assert!(parser.parse(vec![]).is_ok());
+
+        let params = TransportOption::from_handles(handles).unwrap();
+        assert!(!params.hostname_verification);
+        assert!(!params.ssl_enabled());
+        assert!(params.generate_ssl_context().unwrap().is_none());
+    }
+
+    #[test]
+    fn transport_bad_params_client_key_test() {
+        // client-cert-file is set but client-key-file is not specified
+        let args = vec!["client-cert-file=/path/to/client.pem"];
+        let (parser, handles) = prepare_parser();
+
+        assert!(parser.parse(args).is_ok());
+        assert!(TransportOption::from_handles(handles).is_err());
+    }
+
+    #[test]
+    fn transport_hostname_verification_enables_ssl_test() {
+        let args = vec!["hostname-verification=true", "server-name=10.0.0.1"];
+        let (parser, handles) = prepare_parser();
+
+        assert!(parser.parse(args).is_ok());
+
+        let params = TransportOption::from_handles(handles).unwrap();
+        assert!(params.ssl_enabled());
+        assert!(params.generate_ssl_context().unwrap().is_some());
+    }
+
+    #[test]
+    fn transport_bad_params_hostname_verification_test() {
+        let args = vec!["hostname-verification=foo"];
+        let (parser, _handles) = prepare_parser();
+
+        assert!(parser.parse(args).is_err());
+
+        // The host name to check is required.
+        let args = vec!["hostname-verification=true"];
+        let (parser, handles) = prepare_parser();
+
+        assert!(parser.parse(args).is_ok());
+        assert!(TransportOption::from_handles(handles).is_err());
+    }
+}
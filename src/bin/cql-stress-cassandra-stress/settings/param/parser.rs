@@ -2,8 +2,10 @@ use anyhow::Result;
 use std::{cell::RefCell, rc::Rc};
 
 use crate::java_generate::distribution::{
+    exponential::ExponentialDistributionFactory, extreme::ExtremeDistributionFactory,
     fixed::FixedDistributionFactory, normal::NormalDistributionFactory,
-    sequence::SeqDistributionFactory, uniform::UniformDistributionFactory, DistributionFactory,
+    sequence::SeqDistributionFactory, uniform::UniformDistributionFactory,
+    zipf::ZipfDistributionFactory, DistributionFactory,
 };
 
 use super::{
@@ -86,6 +88,7 @@ pub struct ParamsParser {
     command_name: String,
     params: Vec<ParamCell>,
     groups: Vec<ParamsGroup>,
+    examples: Vec<&'static str>,
 }
 
 impl ParamsParser {
@@ -94,9 +97,15 @@ impl ParamsParser {
             command_name: command_name.to_owned(),
             params: Vec::new(),
             groups: Vec::new(),
+            examples: Vec::new(),
         }
     }
 
+    /// Registers an example of the parameters, shown at the end of the help message.
+    pub fn example(&mut self, params: &'static str) {
+        self.examples.push(params);
+    }
+
     /// Registers the simple parameter provided by the user and returns the handle.
     /// `value_pattern` has to be a regular expression, otherwise we panic.
     pub fn simple_param<T: Parsable + 'static>(
@@ -170,6 +179,9 @@ impl ParamsParser {
                     &NormalDistributionFactory::help_description_two_args(),
                     &NormalDistributionFactory::help_description_three_args(),
                     &NormalDistributionFactory::help_description_four_args(),
+                    &ExponentialDistributionFactory::help_description(),
+                    &ExtremeDistributionFactory::help_description(),
+                    &ZipfDistributionFactory::help_description(),
                     "",
                     "    Preceding the name with ~ will invert the distribution.",
                 ];
@@ -254,6 +266,14 @@ impl ParamsParser {
             print!("  ");
             param.borrow().print_desc();
         }
+
+        if !self.examples.is_empty() {
+            println!();
+            println!("Examples:");
+            for example in self.examples.iter() {
+                println!("  {} {}", self.command_name, example);
+            }
+        }
     }
 }
 
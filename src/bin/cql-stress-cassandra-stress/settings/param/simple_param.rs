@@ -61,20 +61,34 @@ impl<T: Parsable> ParamImpl for SimpleParam<T> {
         description: &'static str,
         default_value: Option<&str>,
     ) {
-        let mut usage = String::from(param_name);
-        if !T::is_bool() {
-            usage.push('?');
-        }
-        if let Some(default) = default_value {
-            usage += &format!(" (default={default})");
-        }
-        println!("{:<40} {}", usage, description);
+        println!("{}", describe::<T>(param_name, description, default_value));
         if let Some(additional_description) = &self.additional_desc {
             println!("{additional_description}")
         }
     }
 }
 
+/// Builds the help line of the parameter, i.e. the usage followed by the default value,
+/// the description and the pattern of the accepted values (if known).
+fn describe<T: Parsable>(
+    param_name: &'static str,
+    description: &'static str,
+    default_value: Option<&str>,
+) -> String {
+    let mut usage = String::from(param_name);
+    if !T::is_bool() {
+        usage.push('?');
+    }
+    if let Some(default) = default_value {
+        usage += &format!(" (default={default})");
+    }
+    let mut line = format!("{:<40} {}", usage, description);
+    if let Some(pattern) = T::value_pattern() {
+        line += &format!(" (pattern: {pattern})");
+    }
+    line
+}
+
 impl<T: Parsable> TypedParam<SimpleParam<T>> {
     fn get(self) -> Option<T::Parsed> {
         self.satisfied.then_some(self.param.get()?)
@@ -114,3 +128,31 @@ impl<T: Parsable + 'static> ParamHandle for SimpleParamHandle<T> {
         Rc::clone(&self.cell) as ParamCell
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use crate::settings::param::types::Count;
+
+    use super::describe;
+
+    #[test]
+    fn describe_test() {
+        assert_eq!(
+            format!("{:<40} Number of operations (pattern: [0-9]+[bmk]?)", "n=?"),
+            describe::<Count>("n=", "Number of operations", None)
+        );
+        assert_eq!(
+            format!(
+                "{:<40} Run for this long (pattern: [0-9]+[smh])",
+                "duration=? (default=10s)"
+            ),
+            describe::<Duration>("duration=", "Run for this long", Some("10s"))
+        );
+        assert_eq!(
+            format!("{:<40} Skip the warmup", "no-warmup"),
+            describe::<bool>("no-warmup", "Skip the warmup", None)
+        );
+    }
+}
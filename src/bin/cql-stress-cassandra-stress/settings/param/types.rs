@@ -10,8 +10,10 @@ use cql_stress::distribution::{parse_description, SyntaxFlavor};
 use scylla::{frame::Compression, transport::session::PoolSize};
 
 use crate::java_generate::distribution::{
-    fixed::FixedDistributionFactory, normal::NormalDistributionFactory,
-    sequence::SeqDistributionFactory, uniform::UniformDistributionFactory, DistributionFactory,
+    exponential::ExponentialDistributionFactory, extreme::ExtremeDistributionFactory,
+    fixed::FixedDistributionFactory, inverted::InvertedDistributionFactory,
+    normal::NormalDistributionFactory, sequence::SeqDistributionFactory,
+    uniform::UniformDistributionFactory, zipf::ZipfDistributionFactory, DistributionFactory,
 };
 
 pub trait Parsable: Sized {
@@ -19,6 +21,11 @@ pub trait Parsable: Sized {
 
     fn parse(s: &str) -> Result<Self::Parsed>;
 
+    /// The format of the accepted values, shown in the help message.
+    fn value_pattern() -> Option<&'static str> {
+        None
+    }
+
     // Used only to print the same help message as cassandra-stress does for boolean flags.
     fn is_bool() -> bool {
         false
@@ -51,6 +58,10 @@ impl Parsable for u64 {
         s.parse::<u64>()
             .with_context(|| format!("Invalid u64 value: {s}"))
     }
+
+    fn value_pattern() -> Option<&'static str> {
+        Some(r"[0-9]+")
+    }
 }
 
 impl Parsable for NonZeroUsize {
@@ -60,6 +71,10 @@ impl Parsable for NonZeroUsize {
         s.parse::<NonZeroUsize>()
             .with_context(|| format!("Invalid non-zero usize value: {s}"))
     }
+
+    fn value_pattern() -> Option<&'static str> {
+        Some(r"[1-9][0-9]*")
+    }
 }
 
 impl Parsable for NonZeroU32 {
@@ -69,6 +84,10 @@ impl Parsable for NonZeroU32 {
         s.parse::<NonZeroU32>()
             .with_context(|| format!("Invalid non-zero u32 value: {s}"))
     }
+
+    fn value_pattern() -> Option<&'static str> {
+        Some(r"[1-9][0-9]*")
+    }
 }
 
 impl Parsable for f64 {
@@ -79,6 +98,10 @@ impl Parsable for f64 {
         s.parse::<f64>()
             .with_context(|| format!("Invalid f64 argument: {s}"))
     }
+
+    fn value_pattern() -> Option<&'static str> {
+        Some(r"[0-9]+(\.[0-9]+)?")
+    }
 }
 
 pub struct UnitInterval;
@@ -90,6 +113,10 @@ impl Parsable for UnitInterval {
         s.parse::<f64>()
             .with_context(|| format!("Invalid f64 argument: {s}"))
     }
+
+    fn value_pattern() -> Option<&'static str> {
+        Some(r"0\.[0-9]+")
+    }
 }
 
 impl Parsable for bool {
@@ -110,6 +137,26 @@ impl Parsable for bool {
     }
 }
 
+/// A boolean parameter with an explicit value, i.e. `param=true` or `param=false`.
+/// Unlike the `bool` flags, the presence of such parameter doesn't imply `true`.
+pub struct ExplicitBool;
+
+impl Parsable for ExplicitBool {
+    type Parsed = bool;
+
+    fn parse(s: &str) -> Result<Self::Parsed> {
+        match s.to_lowercase().as_str() {
+            "true" => Ok(true),
+            "false" => Ok(false),
+            _ => anyhow::bail!("Invalid boolean value: {}. Must be one of: true|false", s),
+        }
+    }
+
+    fn value_pattern() -> Option<&'static str> {
+        Some("true|false")
+    }
+}
+
 impl Parsable for String {
     type Parsed = String;
 
@@ -118,32 +165,18 @@ impl Parsable for String {
     }
 }
 
+/// Accepts a sequence of values with units, e.g. `1h30m` or `1.5d`.
+/// The days (`d`) and the combined and fractional forms are the extensions
+/// of the original cassandra-stress, which accepts only `[0-9]+[smh]`.
 impl Parsable for Duration {
     type Parsed = Duration;
 
     fn parse(s: &str) -> Result<Self::Parsed> {
-        let s = &s.to_lowercase();
-        ensure_regex!(s, r"^[0-9]+[smh]$");
-
-        let parse_duration_unit = |unit: char| -> Result<u64> {
-            match unit {
-                's' => Ok(1),
-                'm' => Ok(60),
-                'h' => Ok(60 * 60),
-                _ => anyhow::bail!("Invalid duration unit: {unit}"),
-            }
-        };
+        cql_stress::duration::parse_duration(s)
+    }
 
-        let multiplier = parse_duration_unit(
-            s.chars()
-                .last()
-                .ok_or_else(|| anyhow::anyhow!("Invalid argument: {}", s))?,
-        )?;
-        let value_str = &s[0..s.len() - 1];
-        let value = value_str
-            .parse::<u64>()
-            .with_context(|| format!("Invalid u64 value: {}", value_str))?;
-        Ok(Duration::from_secs(value * multiplier))
+    fn value_pattern() -> Option<&'static str> {
+        Some(r"([0-9]+(\.[0-9]+)?[dhms])+")
     }
 }
 
@@ -183,6 +216,10 @@ impl Parsable for Count {
             .with_context(|| format!("Invalid u64 value: {}", number_slice))?;
         Ok(value * multiplier)
     }
+
+    fn value_pattern() -> Option<&'static str> {
+        Some(r"[0-9]+[bmk]?")
+    }
 }
 
 pub struct CommaDelimitedList;
@@ -194,6 +231,10 @@ impl Parsable for CommaDelimitedList {
         ensure_regex!(s, r"^[^=,]+(,[^=,]+)*$");
         Ok(s.split(',').map(|e| e.to_owned()).collect())
     }
+
+    fn value_pattern() -> Option<&'static str> {
+        Some("<value1>,<value2>,...")
+    }
 }
 
 pub struct Rate;
@@ -211,6 +252,70 @@ impl Parsable for Rate {
             .with_context(|| format!("Invalid u64 value: {value_slice}"))?;
         Ok(value)
     }
+
+    fn value_pattern() -> Option<&'static str> {
+        Some(r"[0-9]+/s")
+    }
+}
+
+/// Wrapper over the parameter's value matching pattern "[0-9]+(us|ms|s)".
+/// Used for the latency targets, which are usually given in milliseconds.
+pub struct Latency;
+
+impl Parsable for Latency {
+    type Parsed = Duration;
+
+    fn parse(s: &str) -> Result<Self::Parsed> {
+        let s = &s.to_lowercase();
+        ensure_regex!(s, r"^[0-9]+(us|ms|s)$");
+
+        let unit_start = s.find(|c: char| !c.is_ascii_digit()).unwrap();
+        let (value_slice, unit) = s.split_at(unit_start);
+        let value = value_slice
+            .parse::<u64>()
+            .with_context(|| format!("Invalid u64 value: {value_slice}"))?;
+        let latency = match unit {
+            "us" => Duration::from_micros(value),
+            "ms" => Duration::from_millis(value),
+            _ => Duration::from_secs(value),
+        };
+        anyhow::ensure!(!latency.is_zero(), "Latency must be positive");
+        Ok(latency)
+    }
+
+    fn value_pattern() -> Option<&'static str> {
+        Some(r"[0-9]+(us|ms|s)")
+    }
+}
+
+/// Wrapper over the parameter's value matching pattern "[0-9]+(ms|s|)",
+/// the same as the intervals of the original cassandra-stress.
+/// The value without a unit is in seconds.
+pub struct LogInterval;
+
+impl Parsable for LogInterval {
+    type Parsed = Duration;
+
+    fn parse(s: &str) -> Result<Self::Parsed> {
+        let s = &s.to_lowercase();
+        ensure_regex!(s, r"^[0-9]+(ms|s)?$");
+
+        let unit_start = s.find(|c: char| !c.is_ascii_digit()).unwrap_or(s.len());
+        let (value_slice, unit) = s.split_at(unit_start);
+        let value = value_slice
+            .parse::<u64>()
+            .with_context(|| format!("Invalid u64 value: {value_slice}"))?;
+        let interval = match unit {
+            "ms" => Duration::from_millis(value),
+            _ => Duration::from_secs(value),
+        };
+        anyhow::ensure!(!interval.is_zero(), "Interval must be positive");
+        Ok(interval)
+    }
+
+    fn value_pattern() -> Option<&'static str> {
+        Some(r"[0-9]+(ms|s|)")
+    }
 }
 
 impl Parsable for Box<dyn DistributionFactory> {
@@ -219,24 +324,28 @@ impl Parsable for Box<dyn DistributionFactory> {
     fn parse(s: &str) -> Result<Self::Parsed> {
         let s = &s.to_lowercase();
         let description = parse_description(s, SyntaxFlavor::Classic)?;
+        let inverted = description.inverted;
 
-        anyhow::ensure!(
-            !description.inverted,
-            "Inverted distributions are not yet supported!"
-        );
-
-        match description.name {
+        let factory = match description.name {
             "fixed" => FixedDistributionFactory::parse_from_description(description),
             "seq" => SeqDistributionFactory::parse_from_description(description),
             "uniform" => UniformDistributionFactory::parse_from_description(description),
             "gaussian" | "gauss" | "norm" | "normal" => {
                 NormalDistributionFactory::parse_from_description(description)
             }
+            "exp" => ExponentialDistributionFactory::parse_from_description(description),
+            "extreme" => ExtremeDistributionFactory::parse_from_description(description),
+            "zipf" => ZipfDistributionFactory::parse_from_description(description),
             _ => Err(anyhow::anyhow!(
                 "Invalid distribution name: {}",
                 description.name
             )),
+        }?;
+
+        if inverted {
+            return Ok(Box::new(InvertedDistributionFactory(factory)));
         }
+        Ok(factory)
     }
 }
 
@@ -275,6 +384,10 @@ impl Parsable for Option<Compression> {
             _ => Err(anyhow::anyhow!("Invalid compression algorithm: {}. Valid compression algorithms: none, lz4, snappy.", s))
         }
     }
+
+    fn value_pattern() -> Option<&'static str> {
+        Some("none|lz4|snappy")
+    }
 }
 
 pub struct ConnectionsPerHost;
@@ -286,6 +399,10 @@ impl Parsable for ConnectionsPerHost {
         let value = <NonZeroUsize as Parsable>::parse(s)?;
         Ok(PoolSize::PerHost(value))
     }
+
+    fn value_pattern() -> Option<&'static str> {
+        Some(r"[1-9][0-9]*")
+    }
 }
 
 pub struct ConnectionsPerShard;
@@ -297,6 +414,10 @@ impl Parsable for ConnectionsPerShard {
         let value = <NonZeroUsize as Parsable>::parse(s)?;
         Ok(PoolSize::PerShard(value))
     }
+
+    fn value_pattern() -> Option<&'static str> {
+        Some(r"[1-9][0-9]*")
+    }
 }
 
 /// A ratio map which should match the following pattern:
@@ -371,12 +492,19 @@ impl Parsable for RatioMap {
     fn parse(s: &str) -> Result<Self::Parsed> {
         Self::do_parse(s).with_context(|| format!("Invalid ratio specification: {}", s))
     }
+
+    fn value_pattern() -> Option<&'static str> {
+        Some("(<item1>=<f64>,<item2>=<f64>,...)")
+    }
 }
 
 #[cfg(test)]
 mod tests {
+    use std::time::Duration;
+
     use crate::{
-        java_generate::distribution::DistributionFactory, settings::param::types::RatioMap,
+        java_generate::distribution::DistributionFactory,
+        settings::param::types::{Latency, LogInterval, RatioMap},
     };
 
     use super::Parsable;
@@ -426,6 +554,19 @@ mod tests {
         }
     }
 
+    #[test]
+    fn distribution_param_inverted_test() {
+        let good_test_cases = &["~exp(1..10)", "~seq(1..100)", "~gaussian(1..100,5)"];
+        for input in good_test_cases {
+            assert!(DistributionTestType::parse(input).is_ok());
+        }
+
+        let bad_test_cases = &["~~exp(1..10)", "~exp(10..1)", "~foo(1..10)"];
+        for input in bad_test_cases {
+            assert!(DistributionTestType::parse(input).is_err());
+        }
+    }
+
     #[test]
     fn distribution_param_uniform_test() {
         let good_test_cases = &["uniform(45..50)", "uniform(1..100000)", "uniform(2..2)"];
@@ -497,4 +638,55 @@ mod tests {
             assert!(RatioMap::parse(input).is_err())
         }
     }
+
+    #[test]
+    fn duration_param_test() {
+        let test_cases = &[
+            ("30s", Duration::from_secs(30)),
+            ("20m", Duration::from_secs(20 * 60)),
+            ("2D", Duration::from_secs(2 * 24 * 60 * 60)),
+            ("1h30m", Duration::from_secs(90 * 60)),
+            (
+                "1d2h3m4s",
+                Duration::from_secs(((24 + 2) * 60 + 3) * 60 + 4),
+            ),
+            ("1.5h", Duration::from_secs(90 * 60)),
+            ("0.5s", Duration::from_millis(500)),
+        ];
+        for (input, expected) in test_cases {
+            assert_eq!(*expected, <Duration as Parsable>::parse(input).unwrap());
+        }
+
+        let bad_test_cases = &["10", "h", "1hour", "1.h", ".5h", "1h 30m", "-1s"];
+        for input in bad_test_cases {
+            assert!(<Duration as Parsable>::parse(input).is_err());
+        }
+    }
+
+    #[test]
+    fn latency_param_test() {
+        assert_eq!(Duration::from_millis(10), Latency::parse("10ms").unwrap());
+        assert_eq!(Duration::from_micros(500), Latency::parse("500us").unwrap());
+        assert_eq!(Duration::from_secs(1), Latency::parse("1S").unwrap());
+
+        let bad_test_cases = &["10", "ms", "0ms", "1.5ms", "10m"];
+        for input in bad_test_cases {
+            assert!(Latency::parse(input).is_err());
+        }
+    }
+
+    #[test]
+    fn log_interval_param_test() {
+        assert_eq!(Duration::from_secs(5), LogInterval::parse("5").unwrap());
+        assert_eq!(Duration::from_secs(5), LogInterval::parse("5s").unwrap());
+        assert_eq!(
+            Duration::from_millis(250),
+            LogInterval::parse("250MS").unwrap()
+        );
+
+        let bad_test_cases = &["", "s", "0", "0ms", "1.5s", "1m", "500us"];
+        for input in bad_test_cases {
+            assert!(LogInterval::parse(input).is_err());
+        }
+    }
 }
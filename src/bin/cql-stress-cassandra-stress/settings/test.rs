@@ -1,4 +1,4 @@
-use super::{parse_cassandra_stress_args, repair_params};
+use super::{parse_cassandra_stress_args, repair_params, CassandraStressParsingResult};
 
 const DATA_GOOD: &str = include_str!("cs_args_good_test.in");
 const DATA_BAD: &str = include_str!("cs_args_bad_test.in");
@@ -69,3 +69,48 @@ fn repair_params_test() {
         result
     );
 }
+
+#[test]
+fn run_metadata_test() {
+    let args = "cassandra-stress write n=100 -rate threads=8 -node 10.0.0.1,10.0.0.2 -seed 7";
+    let settings = match parse_cassandra_stress_args(args.split_ascii_whitespace()).unwrap() {
+        CassandraStressParsingResult::Workload(settings) => settings,
+        CassandraStressParsingResult::SpecialCommand => panic!("expected a workload"),
+    };
+
+    let metadata = settings.run_metadata();
+    assert_eq!(vec!["10.0.0.1", "10.0.0.2"], metadata.nodes);
+    let setting = |name: &str| {
+        metadata
+            .configuration
+            .iter()
+            .find(|(setting, _)| setting == name)
+            .map(|(_, value)| value.as_str())
+    };
+    assert_eq!(Some(settings.command.show()), setting("Command"));
+    assert_eq!(Some("100"), setting("Count"));
+    assert_eq!(Some("8"), setting("Threads"));
+    assert_eq!(Some("7"), setting("Seed"));
+    assert_eq!(None, setting("Duration"));
+}
+
+#[test]
+fn default_population_test() {
+    let population =
+        |args: &str| match parse_cassandra_stress_args(args.split_ascii_whitespace()).unwrap() {
+            CassandraStressParsingResult::Workload(settings) => settings
+                .population
+                .pk_seed_distribution
+                .to_string()
+                .to_lowercase(),
+            CassandraStressParsingResult::SpecialCommand => panic!("expected a workload"),
+        };
+
+    assert_eq!("seq(1..100)", population("cassandra-stress write n=100"));
+    assert!(population("cassandra-stress read n=100").starts_with("gaussian(1..100"));
+    assert!(population("cassandra-stress mixed n=100").starts_with("gaussian(1..100"));
+    assert_eq!(
+        "seq(1..100)",
+        population("cassandra-stress read n=100 -pop seq=1..100")
+    );
+}
@@ -1,61 +1,136 @@
-use std::{sync::Arc, time::Duration};
+use std::{
+    net::SocketAddr,
+    sync::Arc,
+    time::{Duration, SystemTime},
+};
 
 use anyhow::Result;
-use cql_stress::{configuration::OperationContext, sharded_stats};
+use cql_stress::{
+    client_stats::ClientStats,
+    configuration::OperationContext,
+    coordinator::NodeCounts,
+    driver_metrics::DriverMetrics,
+    latency_heatmap::LatencyHeatmapWriter,
+    run_metadata::RunMetadata,
+    sharded_stats::{self, ErrorCounts, ErrorKind, OperationTiming},
+    stats_report::StatsSnapshot,
+};
 use hdrhistogram::Histogram;
+use scylla::Session;
 use tokio::time::Instant;
 
-use crate::settings::{CassandraStressSettings, ThreadsInfo};
+use crate::settings::{CassandraStressSettings, OutputLevel, ThreadsInfo};
 
 /// An interface for latency calculation logic.
 /// c-s can display either raw or coordinated-omission-fixed latencies.
 trait LatencyCalculator: Send + Sync {
-    fn calculate(&self, ctx: &OperationContext) -> u64;
+    fn calculate(&self, timing: &OperationTiming) -> u64;
 }
 
 struct RawLatencyCalculator;
 struct CoordinatedOmissionFixedLatencyCalculator;
 
 impl LatencyCalculator for RawLatencyCalculator {
-    fn calculate(&self, ctx: &OperationContext) -> u64 {
-        let now = Instant::now();
-        (now - ctx.actual_start_time).as_nanos() as u64
+    fn calculate(&self, timing: &OperationTiming) -> u64 {
+        timing.raw_latency().as_nanos() as u64
     }
 }
 
 impl LatencyCalculator for CoordinatedOmissionFixedLatencyCalculator {
-    fn calculate(&self, ctx: &OperationContext) -> u64 {
-        let now = Instant::now();
-        (now - ctx.scheduled_start_time).as_nanos() as u64
+    fn calculate(&self, timing: &OperationTiming) -> u64 {
+        timing.co_fixed_latency().as_nanos() as u64
     }
 }
 
 pub type ShardedStats = sharded_stats::ShardedStats<StatsFactory>;
+pub type StatsBuffer = sharded_stats::StatsBuffer<StatsFactory>;
 
 pub struct StatsFactory {
     coordinated_omission_fixed: bool,
+    measure_latency: bool,
+    hdr_sig_figs: u8,
+    hdr_max_latency: Option<u64>,
 }
 
 pub struct Stats {
     operations: u64,
     errors: u64,
+    error_counts: ErrorCounts,
+    reprepares: u64,
+    rows_scanned: u64,
+    rows_matched: u64,
+    // The operations and the errors of each coordinator.
+    nodes: NodeCounts,
+    // The queue delay and the operations in flight, printed at the verbose level.
+    client: ClientStats,
     latency_calculator: Box<dyn LatencyCalculator>,
-    latency_histogram: Histogram<u64>,
+    // None if the latency is not measured (see `-log no-latency`).
+    latency_histogram: Option<Histogram<u64>>,
+    // The higher latencies are recorded as this value (see `-log hdrmaxlatency=`).
+    max_latency: Option<u64>,
+}
+
+/// The rows visited by a scan (see the scan command). Without a filter,
+/// all of the scanned rows match.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ScannedRows {
+    pub scanned: u64,
+    pub matched: u64,
+}
+
+/// The outcome of a single operation, buffered before it's recorded to [Stats].
+pub struct Sample {
+    timing: OperationTiming,
+    error: Option<ErrorKind>,
+    coordinator: Option<SocketAddr>,
+    scanned_rows: Option<ScannedRows>,
+}
+
+impl Sample {
+    pub fn new<T>(ctx: &OperationContext, result: &Result<T>) -> Self {
+        Self {
+            timing: OperationTiming::ended_now(ctx),
+            error: result.as_ref().err().map(ErrorKind::classify),
+            coordinator: None,
+            scanned_rows: None,
+        }
+    }
+
+    pub fn with_coordinator(mut self, coordinator: Option<SocketAddr>) -> Self {
+        self.coordinator = coordinator;
+        self
+    }
+
+    pub fn with_scanned_rows(mut self, scanned_rows: Option<ScannedRows>) -> Self {
+        self.scanned_rows = scanned_rows;
+        self
+    }
 }
 
 impl StatsFactory {
     pub fn new(settings: &Arc<CassandraStressSettings>) -> Self {
         let coordinated_omission_fixed = match settings.rate.threads_info {
-            ThreadsInfo::Fixed {
-                threads: _,
-                throttle: _,
-                co_fixed,
-            } => co_fixed,
-            ThreadsInfo::Auto { .. } => false,
+            ThreadsInfo::Fixed { co_fixed, .. } => co_fixed,
+            ThreadsInfo::Auto { .. } | ThreadsInfo::AutoLatency { .. } => false,
         };
 
         Self {
             coordinated_omission_fixed,
+            measure_latency: settings.log.measure_latency,
+            hdr_sig_figs: settings.log.hdr_sig_figs,
+            hdr_max_latency: settings
+                .log
+                .hdr_max_latency
+                .map(|latency| latency.as_nanos() as u64),
+        }
+    }
+
+    fn create_histogram(&self) -> Histogram<u64> {
+        // This cannot panic since 1 <= sigfig <= 5, and the max latency
+        // is at least 1us, which is validated during parsing.
+        match self.hdr_max_latency {
+            Some(max_latency) => Histogram::new_with_max(max_latency, self.hdr_sig_figs).unwrap(),
+            None => Histogram::new(self.hdr_sig_figs).unwrap(),
         }
     }
 }
@@ -67,10 +142,15 @@ impl sharded_stats::StatsFactory for StatsFactory {
         Stats {
             operations: 0,
             errors: 0,
-            // This cannot panic since 1 <= sigfig <= 5.
-            // 3 is the recommended value, as well as used in Java's c-s implementation.
-            // AFAIK, there is no c-s option which lets the user define this value.
-            latency_histogram: Histogram::new(3).unwrap(),
+            error_counts: ErrorCounts::default(),
+            reprepares: 0,
+            rows_scanned: 0,
+            rows_matched: 0,
+            nodes: NodeCounts::default(),
+            client: ClientStats::default(),
+            // 3 significant figures are the default, as well as used in Java's c-s implementation.
+            latency_histogram: self.measure_latency.then(|| self.create_histogram()),
+            max_latency: self.hdr_max_latency,
             latency_calculator: if self.coordinated_omission_fixed {
                 Box::new(CoordinatedOmissionFixedLatencyCalculator)
             } else {
@@ -80,31 +160,93 @@ impl sharded_stats::StatsFactory for StatsFactory {
     }
 }
 
-impl Stats {
-    pub fn account_operation<T, E>(&mut self, ctx: &OperationContext, result: &Result<T, E>) {
+impl sharded_stats::RecordSample for Stats {
+    type Sample = Sample;
+
+    fn record_sample(&mut self, sample: &Sample) {
         self.operations += 1;
-        match result {
-            Ok(_) => {
-                self.latency_histogram
-                    .record(self.latency_calculator.calculate(ctx))
-                    .unwrap();
+        self.client.record(&sample.timing);
+        match sample.error {
+            None => {
+                if let Some(histogram) = &mut self.latency_histogram {
+                    let mut latency = self.latency_calculator.calculate(&sample.timing);
+                    if let Some(max_latency) = self.max_latency {
+                        latency = latency.min(max_latency);
+                    }
+                    histogram.record(latency).unwrap();
+                }
             }
-            Err(_) => {
+            Some(kind) => {
                 self.errors += 1;
+                self.error_counts.record_kind(kind);
             }
         }
+        if let Some(node) = sample.coordinator {
+            self.nodes.record(node, sample.error.is_some());
+        }
+        if let Some(rows) = sample.scanned_rows {
+            self.rows_scanned += rows.scanned;
+            self.rows_matched += rows.matched;
+        }
+    }
+}
+
+impl Stats {
+    pub fn account_reprepare(&mut self) {
+        self.reprepares += 1;
+    }
+
+    fn scans_rows(&self) -> bool {
+        self.rows_scanned != 0 || self.rows_matched != 0
     }
 
-    fn op_rate(&self, interval_duration: Duration) -> f64 {
+    pub fn op_rate(&self, interval_duration: Duration) -> f64 {
         self.operations as f64 / interval_duration.as_secs_f64()
     }
 
+    /// Returns the metrics of the interval reported to the -report server.
+    pub fn report_snapshot(&self) -> StatsSnapshot {
+        let mut snapshot = StatsSnapshot::new(SystemTime::now());
+        snapshot.add("operations", self.operations as f64);
+        snapshot.add("errors", self.errors as f64);
+        if self.scans_rows() {
+            snapshot.add("rows_scanned", self.rows_scanned as f64);
+            snapshot.add("rows_matched", self.rows_matched as f64);
+        }
+        if let Some(histogram) = &self.latency_histogram {
+            // The histograms record the latencies in nanoseconds.
+            snapshot.add_latencies("latency", histogram, 1);
+        }
+        snapshot
+    }
+
+    fn measures_latency(&self) -> bool {
+        self.latency_histogram.is_some()
+    }
+
+    // The latencies are NaN if they are not measured.
+    fn latency_ms(&self, f: impl FnOnce(&Histogram<u64>) -> f64) -> f64 {
+        self.latency_histogram
+            .as_ref()
+            .map_or(f64::NAN, |histogram| f(histogram) * 1e-6)
+    }
+
     fn mean_latency_ms(&self) -> f64 {
-        self.latency_histogram.mean() * 1e-6
+        self.latency_ms(|histogram| histogram.mean())
     }
 
     fn latency_at_quantile_ms(&self, quantile: f64) -> f64 {
-        self.latency_histogram.value_at_quantile(quantile) as f64 * 1e-6
+        self.latency_ms(|histogram| histogram.value_at_quantile(quantile) as f64)
+    }
+
+    /// Returns zero if the latency is not measured, which is not allowed
+    /// together with the latency targets (see `-rate auto-latency`).
+    pub fn latency_at_percentile(&self, percentile: f64) -> Duration {
+        self.latency_histogram
+            .as_ref()
+            .map_or(Duration::ZERO, |histogram| {
+                Duration::from_nanos(histogram.value_at_percentile(percentile))
+            })
     }
 
     fn median_latency_ms(&self) -> f64 {
@@ -112,7 +254,55 @@ impl Stats {
     }
 
     fn max_latency_ms(&self) -> f64 {
-        self.latency_histogram.max() as f64 * 1e-6
+        self.latency_ms(|histogram| histogram.max() as f64)
+    }
+}
+
+/// Writes the latency histogram of each interval to the heatmap file
+/// (see `-log heatmap=`).
+pub struct HeatmapLog {
+    writer: LatencyHeatmapWriter,
+    // Whether the latencies are coordinated-omission-fixed or raw.
+    tag: &'static str,
+    start_time: Instant,
+    previous_time: Instant,
+}
+
+impl HeatmapLog {
+    pub async fn create(
+        path: &str,
+        stats_factory: &StatsFactory,
+        metadata: &RunMetadata,
+    ) -> Result<Self> {
+        // The histograms record the latencies in nanoseconds.
+        let writer = LatencyHeatmapWriter::create(path, 1, metadata).await?;
+        let tag = if stats_factory.coordinated_omission_fixed {
+            "co-fixed"
+        } else {
+            "raw"
+        };
+        Ok(Self {
+            writer,
+            tag,
+            start_time: Instant::now(),
+            previous_time: Instant::now(),
+        })
+    }
+
+    pub async fn write_partial(&mut self, partial_stats: &Stats) -> Result<()> {
+        let now = Instant::now();
+        let interval = (self.previous_time - self.start_time)..(now - self.start_time);
+        self.previous_time = now;
+        if let Some(histogram) = &partial_stats.latency_histogram {
+            self.writer
+                .write_interval(interval, self.tag, histogram)
+                .await?;
+        }
+        Ok(())
+    }
+
+    pub async fn flush(&mut self) -> Result<()> {
+        self.writer.flush().await
     }
 }
 
@@ -120,34 +310,64 @@ impl sharded_stats::Stats for Stats {
     fn clear(&mut self) {
         self.operations = 0;
         self.errors = 0;
-        self.latency_histogram.reset();
+        self.error_counts.clear();
+        self.reprepares = 0;
+        self.rows_scanned = 0;
+        self.rows_matched = 0;
+        self.nodes.clear();
+        self.client.clear();
+        if let Some(histogram) = &mut self.latency_histogram {
+            histogram.reset();
+        }
     }
 
     fn combine(&mut self, other: &Self) {
         self.operations += other.operations;
         self.errors += other.errors;
-        self.latency_histogram
-            .add(&other.latency_histogram)
-            .unwrap();
+        self.error_counts.combine(&other.error_counts);
+        self.reprepares += other.reprepares;
+        self.rows_scanned += other.rows_scanned;
+        self.rows_matched += other.rows_matched;
+        self.nodes.combine(&other.nodes);
+        self.client.combine(&other.client);
+        if let (Some(histogram), Some(other_histogram)) =
+            (&mut self.latency_histogram, &other.latency_histogram)
+        {
+            histogram.add(other_histogram).unwrap();
+        }
     }
 }
 
+/// Prints the stats in sections, depending on the output level (see `-log level=`):
+/// - the per-interval lines (normal and verbose),
+/// - the per-interval metrics of the driver and the client (verbose),
+/// - the summary (always),
+/// - the total metrics of the driver and the client (verbose).
 pub struct StatsPrinter {
+    level: OutputLevel,
     start_time: Instant,
     previous_time: Instant,
     total_ops: u64,
 }
 
 impl StatsPrinter {
-    pub fn new() -> Self {
+    pub fn new(level: OutputLevel) -> Self {
         Self {
+            level,
             start_time: Instant::now(),
             previous_time: Instant::now(),
             total_ops: 0,
         }
     }
 
+    fn prints_intervals(&self) -> bool {
+        self.level != OutputLevel::Minimal
+    }
+
     pub fn print_header(&self) {
+        if !self.prints_intervals() {
+            return;
+        }
         println!(
             "{:10},{:>8},{:>8},{:>8},{:>8},{:>8},{:>8},{:>8},{:>7},{:>7}",
             "total ops", "op/s", "mean", "med", ".95", ".99", ".999", "max", "time", "errors"
@@ -161,6 +381,9 @@ impl StatsPrinter {
         let interval_duration = now - self.previous_time;
         self.previous_time = now;
 
+        if !self.prints_intervals() {
+            return;
+        }
         println!(
             "{:10},{:>8.0},{:>8.1},{:>8.1},{:>8.1},{:>8.1},{:>8.1},{:>8.1},{:>7.1},{:>7.0}",
             self.total_ops,
@@ -174,52 +397,208 @@ impl StatsPrinter {
             total_time_secs,
             partial_stats.errors,
         );
+        if partial_stats.errors != 0 {
+            println!("  errors: {}", partial_stats.error_counts.breakdown());
+        }
+        if self.level == OutputLevel::Verbose {
+            println!("  client: {}", partial_stats.client.breakdown());
+        }
+    }
+
+    pub fn print_partial_driver_metrics(&self, metrics: &DriverMetrics) {
+        if self.level == OutputLevel::Verbose {
+            println!("  driver: {}", metrics.breakdown());
+        }
+    }
+
+    pub fn print_rate_limit(&self, rate: f64) {
+        if self.prints_intervals() {
+            println!("  rate limit: {:.0} op/s", rate);
+        }
+    }
+
+    pub fn print_latency_target_summary(&self, best_rate: Option<f64>) {
+        match best_rate {
+            Some(rate) => println!("Max rate within target    : {:>8.0} op/s", rate),
+            None => println!("Max rate within target    :      none"),
+        }
+    }
+
+    /// Prints a shorter summary of one of the streams (see `-rate streams`).
+    pub fn print_stream_summary(&self, name: &str, stats: &Stats) {
+        let benchmark_duration = Instant::now() - self.start_time;
+
+        println!();
+        println!("Stream {}:", name);
+        println!(
+            "Op rate                   : {:>8.0} op/s",
+            stats.op_rate(benchmark_duration)
+        );
+        if stats.measures_latency() {
+            println!(
+                "Latency mean              : {:>6.1} ms",
+                stats.mean_latency_ms()
+            );
+            println!(
+                "Latency 99th percentile   : {:>6.1} ms",
+                stats.latency_at_quantile_ms(0.99)
+            );
+        }
+        println!("Total operations          : {:>10}", stats.operations);
+        println!("Total errors              : {:>10}", stats.errors);
+    }
+
+    pub fn print_summary(&self, final_stats: &Stats, session: &Session) {
+        self.print_results("Results", final_stats, session);
+    }
+
+    /// Prints the summary of the operations performed so far (see `-log summary-interval=`).
+    pub fn print_intermediate_summary(&self, stats: &Stats, session: &Session) {
+        self.print_results("Intermediate results", stats, session);
     }
 
-    pub fn print_summary(&self, final_stats: &Stats) {
+    fn print_results(&self, title: &str, final_stats: &Stats, session: &Session) {
         let now = Instant::now();
         let benchmark_duration = now - self.start_time;
 
         println!();
-        println!("Results:");
+        println!("{}:", title);
 
         println!(
             "Op rate                   : {:>8.0} op/s",
             final_stats.op_rate(benchmark_duration)
         );
+        if final_stats.measures_latency() {
+            println!(
+                "Latency mean              : {:>6.1} ms",
+                final_stats.mean_latency_ms()
+            );
+            println!(
+                "Latency median            : {:>6.1} ms",
+                final_stats.median_latency_ms()
+            );
+            println!(
+                "Latency 95th percentile   : {:>6.1} ms",
+                final_stats.latency_at_quantile_ms(0.95)
+            );
+            println!(
+                "Latency 99th percentile   : {:>6.1} ms",
+                final_stats.latency_at_quantile_ms(0.99)
+            );
+            println!(
+                "Latency 99.9th percentile : {:>6.1} ms",
+                final_stats.latency_at_quantile_ms(0.999)
+            );
+            println!(
+                "Latency max               : {:>6.1} ms",
+                final_stats.max_latency_ms()
+            );
+        }
+        println!("Total operations          : {:>10}", final_stats.operations);
+        println!("Total errors              : {:>10}", final_stats.errors);
+        for kind in ErrorKind::ALL {
+            let count = final_stats.error_counts.get(kind);
+            if count != 0 {
+                println!("{:<26}: {:>10}", format!("  {} errors", kind.name()), count);
+            }
+        }
+        if final_stats.reprepares != 0 {
+            println!("Total re-preparations     : {:>10}", final_stats.reprepares);
+        }
+        if final_stats.scans_rows() {
+            println!(
+                "Total rows scanned        : {:>10}",
+                final_stats.rows_scanned
+            );
+            println!(
+                "Total rows matched        : {:>10}",
+                final_stats.rows_matched
+            );
+        }
+        if !final_stats.nodes.is_empty() {
+            println!("Operations per node:");
+            for (node, count) in final_stats.nodes.sorted() {
+                println!(
+                    "{:<26}: {:>10} ops, {} errors",
+                    format!("  {}", node),
+                    count.operations,
+                    count.errors
+                );
+            }
+        }
+
+        let seconds = benchmark_duration.as_secs() % 60;
+        let minutes = (benchmark_duration.as_secs() / 60) % 60;
+        let hours = (benchmark_duration.as_secs() / 60) / 60;
         println!(
-            "Latency mean              : {:>6.1} ms",
-            final_stats.mean_latency_ms()
+            "Total operation time      : {:0>2}:{:0>2}:{:0>2}",
+            hours, minutes, seconds
         );
+
+        if self.level == OutputLevel::Verbose {
+            Self::print_driver_metrics(session);
+            Self::print_client_metrics(&final_stats.client);
+        }
+    }
+
+    fn print_client_metrics(client: &ClientStats) {
+        let ms = |nanos: u64| nanos as f64 * 1e-6;
+        let queue_delay = client.queue_delay();
+
+        println!();
+        println!("Client metrics:");
+        println!("Max in-flight operations  : {:>10}", client.max_in_flight());
         println!(
-            "Latency median            : {:>6.1} ms",
-            final_stats.median_latency_ms()
+            "Queue delay mean          : {:>6.1} ms",
+            queue_delay.mean() * 1e-6
         );
         println!(
-            "Latency 95th percentile   : {:>6.1} ms",
-            final_stats.latency_at_quantile_ms(0.95)
+            "Queue delay 99th pct      : {:>6.1} ms",
+            ms(queue_delay.value_at_quantile(0.99))
         );
         println!(
-            "Latency 99th percentile   : {:>6.1} ms",
-            final_stats.latency_at_quantile_ms(0.99)
+            "Queue delay max           : {:>6.1} ms",
+            ms(queue_delay.max())
         );
+    }
+
+    fn print_driver_metrics(session: &Session) {
+        let metrics = session.get_metrics();
+        // The driver doesn't expose the number of open connections,
+        // so the state of the nodes is reported instead.
+        let cluster_data = session.get_cluster_data();
+        let nodes = cluster_data.get_nodes_info();
+        let nodes_down = nodes.iter().filter(|node| node.is_down()).count();
+
+        println!();
+        println!("Driver metrics:");
         println!(
-            "Latency 99.9th percentile : {:>6.1} ms",
-            final_stats.latency_at_quantile_ms(0.999)
+            "Queries                   : {:>10}",
+            metrics.get_queries_num()
         );
         println!(
-            "Latency max               : {:>6.1} ms",
-            final_stats.max_latency_ms()
+            "Paged queries             : {:>10}",
+            metrics.get_queries_iter_num()
         );
-        println!("Total operations          : {:>10}", final_stats.operations);
-        println!("Total errors              : {:>10}", final_stats.errors);
-
-        let seconds = benchmark_duration.as_secs() % 60;
-        let minutes = (benchmark_duration.as_secs() / 60) % 60;
-        let hours = (benchmark_duration.as_secs() / 60) / 60;
         println!(
-            "Total operation time      : {:0>2}:{:0>2}:{:0>2}",
-            hours, minutes, seconds
-        )
+            "Errors                    : {:>10}",
+            metrics.get_errors_num()
+        );
+        println!(
+            "Paged query errors        : {:>10}",
+            metrics.get_errors_iter_num()
+        );
+        println!(
+            "Retries                   : {:>10}",
+            metrics.get_retries_num()
+        );
+        if let Ok(mean) = metrics.get_latency_avg_ms() {
+            println!("Driver latency mean       : {:>6} ms", mean);
+        }
+        if let Ok(p99) = metrics.get_latency_percentile_ms(99.0) {
+            println!("Driver latency 99th pct   : {:>6} ms", p99);
+        }
+        println!("Nodes                     : {:>10}", nodes.len());
+        println!("Nodes down                : {:>10}", nodes_down);
     }
 }
@@ -5,20 +5,40 @@ use std::time::{Duration, SystemTime};
 
 use anyhow::{Context, Result};
 use scylla::load_balancing::{DefaultPolicy, LoadBalancingPolicy};
-use scylla::statement::Consistency;
+use scylla::retry_policy::{DefaultRetryPolicy, FallthroughRetryPolicy, RetryPolicy};
+use scylla::statement::{Consistency, SerialConsistency};
+use scylla::transport::downgrading_consistency_retry_policy::DowngradingConsistencyRetryPolicy;
 
-use crate::distribution::{parse_distribution, Distribution, Fixed};
+use cql_stress::compressible::parse_compressible;
+use cql_stress::configuration::{Arrival, DutyCycle, RetryBackoff};
+use cql_stress::replication::Replication;
+use cql_stress::row_count::ExpectedRowCount;
+use cql_stress::run_metadata::RunMetadata;
+use cql_stress::split::Split;
+use cql_stress::stats_report::{ReportProtocol, ReportTarget};
+
+use crate::distribution::{
+    parse_distribution, parse_row_distribution, Distribution, Fixed, RowDistribution,
+};
 use crate::gocompat::flags::{GoValue, ParserBuilder};
-use crate::gocompat::strconv::format_duration;
+use crate::gocompat::strconv::{format_duration, parse_duration};
 use crate::stats::LatencyType;
+use crate::workload::registry;
+
+// The maximum TTL accepted by Scylla/Cassandra (20 years).
+const MAX_TTL: Duration = Duration::from_secs(20 * 365 * 24 * 60 * 60);
 
 // Explicitly marked as `pub(crate)`, because with `pub` rustc doesn't
 // complain about fields which are never read
 pub(crate) struct ScyllaBenchArgs {
     pub workload: WorkloadType,
     pub consistency_level: Consistency,
-    pub replication_factor: i64,
+    pub serial_consistency_level: SerialConsistency,
+    pub replication: Replication,
     pub nodes: Vec<String>,
+    pub cloud_config: Option<String>,
+    pub clusters: Vec<ClusterTarget>,
+    pub cluster_mode: ClusterMode,
     pub ca_cert_file: String,
     pub client_cert_file: String,
     pub client_key_file: String,
@@ -26,8 +46,12 @@ pub(crate) struct ScyllaBenchArgs {
     pub host_verification: bool,
     pub client_compression: bool,
     pub shard_connection_count: NonZeroUsize,
-    pub page_size: i64,
+    pub page_size: i32,
     pub partition_offset: i64,
+    pub split: Option<Split>,
+    pub tablet: Option<TabletTarget>,
+    pub keys_file: Option<String>,
+    pub keys_file_loop: bool,
 
     // (Timeseries-related parameters)
     pub write_rate: u64,
@@ -35,22 +59,43 @@ pub(crate) struct ScyllaBenchArgs {
     pub start_timestamp: u64,
 
     pub host_selection_policy: Arc<dyn LoadBalancingPolicy>,
+    pub retry_policy: RetryPolicyKind,
     pub tls_encryption: bool,
     pub keyspace_name: String,
     pub table_name: String,
     pub counter_table_name: String,
+    pub counter_columns: u64,
+    pub counter_columns_per_update: u64,
     pub username: String,
     pub password: String,
+    // The authenticator plugin, given as name[:options].
+    // Requires the auth-plugins crate feature.
+    pub authenticator: Option<String>,
     pub mode: Mode,
     pub latency_type: LatencyType,
     pub max_retries_per_op: u64,
+    pub retry_backoff: RetryBackoff,
+    pub retry_budget: Option<u64>,
     pub concurrency: u64,
     pub maximum_rate: u64,
+    pub arrival: Arrival,
+    pub max_in_flight: Option<u64>,
+    pub runtimes: Option<u64>,
+    pub duty_cycle: Option<DutyCycle>,
+    pub background_write_rate: Option<u64>,
+    pub read_recent_writes: Option<u64>,
+    pub serialize_partitions: bool,
+    pub no_schema: bool,
 
     pub test_duration: Duration,
+    pub max_operations: Option<u64>,
     pub partition_count: u64,
     pub clustering_row_count: u64,
     pub clustering_row_size_dist: Arc<dyn Distribution>,
+    pub clustering_row_distribution: RowDistribution,
+    pub partition_distribution: RowDistribution,
+    pub max_clustering_rows_per_op: Option<u64>,
+    pub partition_growth: Option<u64>,
 
     pub rows_per_request: u64,
     pub provide_upper_bound: bool,
@@ -58,17 +103,41 @@ pub(crate) struct ScyllaBenchArgs {
     pub select_order_by: Vec<OrderBy>,
     pub no_lower_bound: bool,
     pub bypass_cache: bool,
+    pub lwt: bool,
+    pub write_timestamp: Option<i64>,
+    pub ttl: Option<Duration>,
+    pub twcs_window: Option<Duration>,
+    pub twcs_retention: Option<Duration>,
 
     pub range_count: u64,
     pub timeout: Duration,
     pub iterations: u64,
+    pub no_wrap: bool,
+    pub state_file: Option<String>,
+    pub control_file: Option<String>,
     // // Any error response that comes with delay greater than errorToTimeoutCutoffTime
     // // to be considered as timeout error and recorded to histogram as such
     pub measure_latency: bool,
+    pub quiet: bool,
+    pub driver_metrics: bool,
+    pub client_metrics: bool,
+    pub cluster_info: bool,
     pub hdr_latency_file: String,
+    pub latency_heatmap_file: Option<String>,
+    pub report: Option<ReportTarget>,
     pub hdr_latency_resolution: u64,
     pub hdr_latency_sig_fig: u64,
     pub validate_data: bool,
+    pub compressible: Option<f64>,
+    pub validate_all_pages: bool,
+    pub expected_rows: Option<ExpectedRowCount>,
+    pub error_log: Option<String>,
+    pub error_log_rate: u64,
+    pub trace_file: Option<String>,
+    pub trace_fraction: f64,
+    pub key_log: Option<String>,
+    pub seed: Option<u64>,
+    pub dry_run: Option<u64>,
 }
 
 // Parses and validates scylla bench params.
@@ -84,11 +153,44 @@ where
 
     let mut flag = ParserBuilder::new();
 
-    let workload = flag.string_var("workload", "", "workload: sequential, uniform, timeseries");
+    let workload = flag.string_var(
+        "workload",
+        "",
+        "workload: sequential, uniform, timeseries, wide_partition, single_partition",
+    );
     let consistency_level = flag.string_var("consistency-level", "quorum", "consistency level");
-    let replication_factor = flag.i64_var("replication-factor", 1, "replication factor");
+    let serial_consistency_level = flag.string_var(
+        "serial-consistency-level",
+        "serial",
+        "serial consistency level used by conditional (LWT) statements: serial, local_serial",
+    );
+    let replication_factor = flag.u64_var("replication-factor", 1, "replication factor");
+    let replication = flag.string_var(
+        "replication",
+        "",
+        "replication of the keyspace given as comma-separated options, e.g. \
+        strategy=NetworkTopologyStrategy,dc1=3,dc2=2; the strategy is SimpleStrategy \
+        and the factor is replication-factor by default",
+    );
 
     let nodes = flag.string_var("nodes", "127.0.0.1:9042", "cluster contact nodes");
+    let cloud_config = flag.string_var(
+        "cloud-config",
+        "",
+        "path to the Scylla Cloud connection bundle (yaml), overrides nodes, credentials and TLS settings",
+    );
+    let clusters = flag.string_var(
+        "clusters",
+        "",
+        "benchmark several clusters at once, given as name=host1,host2 entries separated \
+        with semicolons, e.g. 'source=10.0.0.1,10.0.0.2;target=10.0.1.1'; overrides nodes",
+    );
+    let cluster_mode = flag.string_var(
+        "cluster-mode",
+        "duplicate",
+        "how the load is distributed among the clusters: duplicate (each cluster gets \
+        the whole load), split (the clusters share the load)",
+    );
     let server_name = flag.string_var(
         "tls-server-name",
         "",
@@ -133,6 +235,30 @@ where
         0,
         "start of the partition range (only for sequential workload)",
     );
+    let split = flag.string_var(
+        "split",
+        "",
+        "run only the given part of the partition range, e.g. 3/8 for the third of 8 loaders \
+        sharing the population (only for sequential workload)",
+    );
+    let tablet = flag.string_var(
+        "tablet",
+        "",
+        "constrain the partitions to a single tablet (tablet index) or spread them evenly \
+        across tablets (even); requires a tablet-enabled keyspace (only for uniform workload)",
+    );
+    let keys_file = flag.string_var(
+        "keys-file",
+        "",
+        "read the keys of the operations from a file instead of the workload, one partition key \
+        per line, optionally followed by a comma and the first clustering key",
+    );
+    let keys_file_loop = flag.bool_var(
+        "keys-file-loop",
+        false,
+        "start over from the beginning of keys-file once all of its keys are used, \
+        instead of finishing the run",
+    );
 
     let write_rate = flag.u64_var(
         "write-rate",
@@ -156,6 +282,12 @@ where
         "set the driver host selection policy \
         (round-robin,token-aware,dc-aware:name-of-local-dc),default 'token-aware'",
     );
+    let retry_policy = flag.string_var(
+        "retry-policy",
+        "default",
+        "set the driver retry policy (default,fallthrough,downgrading), \
+        'fallthrough' surfaces all errors to the tool's own retry logic",
+    );
     let tls_encryption = flag.bool_var(
         "tls",
         false,
@@ -165,12 +297,29 @@ where
     let table_name = flag.string_var("table", "test", "table to use");
     let counter_table_name =
         flag.string_var("counter-table", "test_counters", "counter table to use");
+    let counter_columns = flag.u64_var(
+        "counter-columns",
+        5,
+        "number of counter columns (c1, c2, ...) in the counter table",
+    );
+    let counter_columns_per_update = flag.u64_var(
+        "counter-columns-per-update",
+        0,
+        "number of counter columns incremented by each update in counter_update mode, \
+        starting from c1; counter_read mode reads the same columns (0 for all)",
+    );
     let username = flag.string_var("username", "", "cql username for authentication");
     let password = flag.string_var("password", "", "cql password for authentication");
+    let authenticator = flag.string_var(
+        "authenticator",
+        "",
+        "authenticator plugin given as name[:options], e.g. sasl-plain, used instead of \
+        username and password (requires the auth-plugins feature)",
+    );
     let mode = flag.string_var(
         "mode",
         "",
-        "operating mode: write, read, counter_update, counter_read, scan",
+        "operating mode: write, read, counter_update, counter_read, scan, read_modify_write",
     );
     let latency_type = flag.string_var(
         "latency-type",
@@ -184,18 +333,84 @@ where
         After exceeding it, the workflow will terminate with an error. \
         Set to 0 if you want to have unlimited retries",
     );
+    let retry_interval = flag.string_var(
+        "retry-interval",
+        "",
+        "the delay before retrying a failed operation, either fixed (e.g. 100ms) \
+        or exponential with jitter, given as min,max (e.g. 80ms,1s). \
+        Empty to retry immediately",
+    );
+    let retry_budget = flag.u64_var(
+        "retry-budget",
+        0,
+        "the maximum number of retries per second, after which the failed operations \
+        are not retried and the workflow terminates with an error (0 for unlimited)",
+    );
     let concurrency = flag.u64_var("concurrency", 16, "number of used tasks");
     let maximum_rate = flag.u64_var(
         "max-rate",
         0,
         "the maximum rate of outbound requests in op/s (0 for unlimited)",
     );
+    let arrival = flag.string_var(
+        "arrival",
+        "uniform",
+        "the distribution of the start times of the operations limited by max-rate: \
+        uniform (equal intervals), poisson (exponentially distributed intervals)",
+    );
+    let max_in_flight = flag.u64_var(
+        "max-in-flight",
+        0,
+        "the maximum number of requests in flight, regardless of the concurrency (0 for unlimited)",
+    );
+    let duty_cycle = flag.string_var(
+        "duty-cycle",
+        "",
+        "run the load only in windows, pausing it in between, given as on/off, e.g. 30s/30s \
+        (empty to run the load continuously)",
+    );
+    let runtimes = flag.u64_var(
+        "runtimes",
+        0,
+        "spread the tasks between this many single-threaded runtimes with their own threads and \
+        shares of max-rate, e.g. one per core for very high rates (0 to use the shared runtime)",
+    );
+    let background_write_rate = flag.u64_var(
+        "background-write-rate",
+        0,
+        "rate of the writes in op/s performed in the background by concurrency additional tasks, \
+        while the reads are measured (only for read mode, 0 for no background writes)",
+    );
+    let read_recent_writes = flag.u64_var(
+        "read-recent-writes",
+        0,
+        "read only the rows among this many most recently written by the background writes, \
+        which are likely to be served from the row cache (0 to read the keys of the workload)",
+    );
+    let serialize_partitions = flag.bool_var(
+        "serialize-partitions",
+        false,
+        "never execute the operations on the same partition concurrently, \
+        so that each of them observes the effects of the previous ones",
+    );
+    let no_schema = flag.bool_var(
+        "no-schema",
+        false,
+        "don't create the keyspace and the tables, which must already exist, \
+        e.g. if the schema is managed externally",
+    );
 
     let test_duration = flag.duration_var(
         "duration",
         Duration::ZERO,
         "duration of the test in seconds (0 for unlimited)",
     );
+    let max_operations = flag.u64_var(
+        "ops",
+        0,
+        "number of operations after which the test finishes, in any workload \
+        (0 for unlimited); the test finishes after the duration if it comes first",
+    );
     let partition_count = flag.u64_var("partition-count", 10_000, "number of partitions");
     let clustering_row_count = flag.u64_var(
         "clustering-row-count",
@@ -208,6 +423,32 @@ where
         ScyllaBenchDistribution(default_dist),
         "size of a single clustering row, can use random values",
     );
+    let clustering_row_distribution = flag.string_var(
+        "clustering-row-dist",
+        "uniform",
+        "distribution of the clustering rows picked by operations: uniform, zipf[:exponent], \
+        normal[:stdev], exp[:mean]; stdev and mean are fractions of clustering-row-count \
+        (only for uniform workload)",
+    );
+    let partition_distribution = flag.string_var(
+        "partition-dist",
+        "uniform",
+        "distribution of the partitions picked by operations, e.g. to simulate contention \
+        on hot counters in counter_update mode: uniform, zipf[:exponent], normal[:stdev], \
+        exp[:mean]; stdev and mean are fractions of partition-count (only for uniform workload)",
+    );
+    let max_clustering_rows_per_op = flag.u64_var(
+        "max-clustering-rows-per-op",
+        0,
+        "maximum number of clustering rows appended to a partition by a single operation, \
+        the actual number is random (only for wide_partition workload, 0 for rows-per-request)",
+    );
+    let partition_growth = flag.u64_var(
+        "partition-growth",
+        0,
+        "number of clustering rows the partitions grow to before the benchmark ends \
+        (only for wide_partition workload, 0 for unlimited)",
+    );
 
     let rows_per_request =
         flag.u64_var("rows-per-request", 1, "clustering rows per single request");
@@ -238,6 +479,35 @@ where
         false,
         "Execute queries with the \"BYPASS CACHE\" CQL clause",
     );
+    let lwt = flag.bool_var(
+        "lwt",
+        false,
+        "use lightweight transactions (INSERT ... IF NOT EXISTS) in write mode, \
+        and conditional writes in read_modify_write mode",
+    );
+    let write_timestamp = flag.i64_var(
+        "write-timestamp",
+        0,
+        "timestamp (in microseconds) applied to writes with USING TIMESTAMP \
+        (0 to let the server assign timestamps)",
+    );
+    let ttl = flag.duration_var(
+        "ttl",
+        Duration::ZERO,
+        "time to live of the written rows, rounded down to seconds (0 for no TTL)",
+    );
+    let twcs_window = flag.duration_var(
+        "twcs-window",
+        Duration::ZERO,
+        "create the table with TimeWindowCompactionStrategy using windows of this size, \
+        a multiple of a minute (only for timeseries workload, 0 for the default compaction)",
+    );
+    let twcs_retention = flag.duration_var(
+        "twcs-retention",
+        Duration::ZERO,
+        "time after which the written rows expire, the TTL of each row is derived \
+        from its timestamp (only together with twcs-window, 0 for no TTL)",
+    );
 
     let range_count = flag.u64_var(
         "range-count",
@@ -251,8 +521,46 @@ where
         "number of iterations to run (0 for unlimited, relevant only for workloads \
         that have a defined number of ops to execute)",
     );
+    let no_wrap = flag.bool_var(
+        "no-wrap",
+        false,
+        "write the next partition-count partitions in each iteration instead of revisiting \
+        the same ones (only for sequential workload)",
+    );
+    let state_file = flag.string_var(
+        "state-file",
+        "",
+        "periodically save the progress into a file and resume from it if the file exists, \
+        so that an interrupted run doesn't start from scratch (only for sequential workload)",
+    );
+    let control_file = flag.string_var(
+        "control-file",
+        "",
+        "watch a file for changes of the rate and remaining_duration keys \
+        and apply them to the running benchmark",
+    );
 
     let measure_latency = flag.bool_var("measure-latency", true, "measure request latency");
+    let quiet = flag.bool_var(
+        "quiet",
+        false,
+        "print only the final results, without the configuration and the per-second stats",
+    );
+    let driver_metrics = flag.bool_var(
+        "driver-metrics",
+        false,
+        "print the metrics of the driver (requests, pages, retries, nodes down) every second",
+    );
+    let client_metrics = flag.bool_var(
+        "client-metrics",
+        false,
+        "print the max number of the operations in flight and the queue delay (the delay of their start after the schedule) every second",
+    );
+    let cluster_info = flag.bool_var(
+        "cluster-info",
+        false,
+        "print the name of the cluster and the location, the state and the version of each node at the start and at the end of the run",
+    );
 
     let hdr_latency_file = flag.string_var(
         "hdr-latency-file",
@@ -269,12 +577,89 @@ where
         3,
         "significant figures of the hdr histogram, number from 1 to 5 (default: 3)",
     );
+    let latency_heatmap_file = flag.string_var(
+        "latency-heatmap-file",
+        "",
+        "write the co-fixed and raw latency histograms of each second into a CSV file \
+        with fixed latency buckets, suitable for latency-over-time heatmaps",
+    );
+    let report = flag.string_var(
+        "report",
+        "",
+        "send the stats of each second to a Graphite (graphite=host:port) \
+        or StatsD (statsd=host:port) server",
+    );
+    let report_prefix = flag.string_var(
+        "report-prefix",
+        "scylla-bench",
+        "prefix of the names of the metrics sent to the -report server",
+    );
 
     let validate_data = flag.bool_var(
         "validate-data",
         false,
         "write meaningful data and validate while reading",
     );
+    let compressible = flag.string_var(
+        "compressible",
+        "",
+        "fraction (0..1) of the bytes of each value which compresses away, e.g. 0.5 makes \
+        the values compress to about half of their size; by default, the values are random \
+        with validate-data and zeroed without it",
+    );
+    let validate_all_pages = flag.bool_var(
+        "validate-all-pages",
+        false,
+        "check that the rows returned by all pages of a read are in the clustering order \
+        without duplicates and there are no more of them than requested (only for read modes)",
+    );
+    let expected_rows = flag.string_var(
+        "expected-rows",
+        "",
+        "number of rows each read is expected to return, either exact (e.g. 100) or a range \
+        (e.g. 90..100), the other results are counted as data loss errors (only for read modes)",
+    );
+
+    let error_log = flag.string_var(
+        "error-log",
+        "",
+        "log the failed operations (partition key, statement and error) into a file",
+    );
+    let error_log_rate = flag.u64_var(
+        "error-log-rate",
+        100,
+        "maximum number of failed operations logged per second",
+    );
+    let trace_file = flag.string_var(
+        "trace-file",
+        "",
+        "enable server-side tracing of a sample of operations and log their tracing session ids into a file \
+        (only for write, read, counter_update and counter_read modes)",
+    );
+    let trace_fraction = flag.string_var(
+        "trace-fraction",
+        "0.001",
+        "fraction of the operations traced if trace-file is set",
+    );
+    let key_log = flag.string_var(
+        "key-log",
+        "",
+        "record the keys of the successfully written rows into a file, which can be read back \
+        with keys-file (only for write mode)",
+    );
+
+    let seed = flag.u64_var(
+        "seed",
+        0,
+        "seed for the random generators, makes the generated keys and values reproducible for the same concurrency; 0 means a random seed",
+    );
+
+    let dry_run = flag.u64_var(
+        "dry-run",
+        0,
+        "print this many operations of each type, with the statements and the values bound to them, \
+        instead of running the benchmark; doesn't connect to the cluster (0 to run the benchmark)",
+    );
 
     let (parser, desc) = flag.build();
 
@@ -282,7 +667,55 @@ where
         parser.parse_args(args)?;
 
         let nodes = nodes.get().split(',').map(str::to_string).collect();
+        let cloud_config = cloud_config.get();
+        let cloud_config = (!cloud_config.is_empty()).then_some(cloud_config);
+        anyhow::ensure!(
+            cloud_config.is_none() || !tls_encryption.get(),
+            "cloud-config cannot be used together with tls, the bundle provides the TLS settings"
+        );
+        anyhow::ensure!(
+            cloud_config.is_none() || (username.get().is_empty() && password.get().is_empty()),
+            "cloud-config cannot be used together with username and password, the bundle provides the credentials"
+        );
+        anyhow::ensure!(
+            username.get().is_empty() == password.get().is_empty(),
+            "username and password either should be both provided or left empty"
+        );
+        anyhow::ensure!(
+            client_key_file.get().is_empty() == client_cert_file.get().is_empty(),
+            "tls-client-cert-file and tls-client-key-file either should be both provided or left empty",
+        );
+        anyhow::ensure!(
+            tls_encryption.get() || client_cert_file.get().is_empty(),
+            "tls-client-cert-file and tls-client-key-file require tls"
+        );
+        let authenticator = authenticator.get();
+        let authenticator = (!authenticator.is_empty()).then_some(authenticator);
+        if let Some(authenticator) = &authenticator {
+            anyhow::ensure!(
+                username.get().is_empty() && cloud_config.is_none(),
+                "authenticator cannot be used together with username and password or cloud-config"
+            );
+            #[cfg(feature = "auth-plugins")]
+            cql_stress::auth::AuthenticatorSpec::parse(authenticator)?;
+            #[cfg(not(feature = "auth-plugins"))]
+            anyhow::bail!(
+                "authenticator {} requires the auth-plugins crate feature, which is disabled in this build (see --list-features)",
+                authenticator
+            );
+        }
+        let replication = Replication::parse(&replication.get(), replication_factor.get())?;
+        let clusters = parse_clusters(&clusters.get())?;
+        let cluster_mode = parse_cluster_mode(&cluster_mode.get())?;
+        anyhow::ensure!(
+            clusters.is_empty() || cloud_config.is_none(),
+            "clusters cannot be used together with cloud-config",
+        );
         let mode = parse_mode(&mode.get())?;
+        anyhow::ensure!(
+            clusters.is_empty() || cluster_mode == ClusterMode::Duplicate || mode != Mode::Scan,
+            "scan mode supports only the duplicate cluster mode",
+        );
         let workload = if mode == Mode::Scan {
             anyhow::ensure!(
                 workload.get() == "",
@@ -293,6 +726,103 @@ where
             parse_workload(&workload.get())?
         };
         let consistency_level = parse_consistency_level(&consistency_level.get())?;
+        let serial_consistency_level =
+            parse_serial_consistency_level(&serial_consistency_level.get())?;
+        let lwt = lwt.get();
+        let validate_all_pages = validate_all_pages.get();
+        anyhow::ensure!(
+            !validate_all_pages || mode == Mode::Read || mode == Mode::CounterRead,
+            "validate-all-pages is supported only in read and counter_read modes",
+        );
+        let expected_rows = match expected_rows.get().as_str() {
+            "" => None,
+            s => Some(ExpectedRowCount::parse(s).context("invalid expected-rows")?),
+        };
+        anyhow::ensure!(
+            expected_rows.is_none() || mode == Mode::Read || mode == Mode::CounterRead,
+            "expected-rows is supported only in read and counter_read modes",
+        );
+        let counter_columns = counter_columns.get();
+        anyhow::ensure!(
+            counter_columns > 0,
+            "the counter table must have at least one counter column",
+        );
+        let counter_columns_per_update = match counter_columns_per_update.get() {
+            0 => counter_columns,
+            n => n,
+        };
+        anyhow::ensure!(
+            counter_columns_per_update <= counter_columns,
+            "counter-columns-per-update ({}) cannot exceed counter-columns ({})",
+            counter_columns_per_update,
+            counter_columns,
+        );
+        anyhow::ensure!(
+            !lwt || mode == Mode::Write || mode == Mode::ReadModifyWrite,
+            "lightweight transactions are supported only in write and read_modify_write modes",
+        );
+        let write_timestamp = write_timestamp.get();
+        anyhow::ensure!(write_timestamp >= 0, "write timestamp cannot be negative");
+        // Counter updates don't accept custom timestamps, and the overwrites
+        // of read_modify_write would be lost with equal timestamps.
+        anyhow::ensure!(
+            write_timestamp == 0 || mode == Mode::Write,
+            "write timestamp is supported only in write mode",
+        );
+        anyhow::ensure!(
+            write_timestamp == 0 || !lwt,
+            "write timestamp cannot be used together with lightweight transactions",
+        );
+        let write_timestamp = (write_timestamp > 0).then_some(write_timestamp);
+        let ttl = ttl.get();
+        anyhow::ensure!(
+            ttl == Duration::ZERO || mode == Mode::Write,
+            "ttl is supported only in write mode",
+        );
+        anyhow::ensure!(
+            ttl == Duration::ZERO || ttl >= Duration::from_secs(1),
+            "ttl must be at least one second",
+        );
+        anyhow::ensure!(
+            ttl <= MAX_TTL,
+            "ttl cannot be greater than {}",
+            format_duration(MAX_TTL),
+        );
+        let ttl = (ttl > Duration::ZERO).then_some(ttl);
+        let twcs_window = twcs_window.get();
+        let twcs_retention = twcs_retention.get();
+        anyhow::ensure!(
+            twcs_window == Duration::ZERO || workload == WorkloadType::Timeseries,
+            "twcs-window is supported only by the timeseries workload",
+        );
+        anyhow::ensure!(
+            twcs_window.as_secs() % 60 == 0 && twcs_window.subsec_nanos() == 0,
+            "twcs-window must be a multiple of a minute",
+        );
+        anyhow::ensure!(
+            twcs_retention == Duration::ZERO || twcs_window > Duration::ZERO,
+            "twcs-retention can be used only together with twcs-window",
+        );
+        anyhow::ensure!(
+            twcs_retention == Duration::ZERO || mode == Mode::Write,
+            "twcs-retention is supported only in write mode",
+        );
+        anyhow::ensure!(
+            twcs_retention == Duration::ZERO || twcs_retention >= twcs_window,
+            "twcs-retention cannot be shorter than twcs-window",
+        );
+        anyhow::ensure!(
+            twcs_retention <= MAX_TTL,
+            "twcs-retention cannot be greater than {}",
+            format_duration(MAX_TTL),
+        );
+        anyhow::ensure!(
+            twcs_retention == Duration::ZERO
+                || (ttl.is_none() && write_timestamp.is_none() && !lwt),
+            "twcs-retention cannot be used together with ttl, write-timestamp or lwt",
+        );
+        let twcs_window = (twcs_window > Duration::ZERO).then_some(twcs_window);
+        let twcs_retention = (twcs_retention > Duration::ZERO).then_some(twcs_retention);
         let shard_connection_count = NonZeroUsize::new(shard_connection_count.get() as usize)
             .context("shard connection count cannot be 0")?;
         let distribution = parse_timeseries_distribution(&distribution.get())?;
@@ -301,11 +831,133 @@ where
             start_timestamp = SystemTime::UNIX_EPOCH.elapsed().unwrap().as_nanos() as u64;
         }
         let host_selection_policy = parse_host_selection_policy(&host_selection_policy.get())?;
+        let retry_policy = parse_retry_policy(&retry_policy.get())?;
         let select_order_by = parse_order_by_chain(&select_order_by.get())?;
+        let tablet = parse_tablet_target(&tablet.get())?;
+        anyhow::ensure!(
+            tablet.is_none() || clusters.is_empty(),
+            "tablet cannot be used together with clusters",
+        );
+        anyhow::ensure!(
+            tablet.is_none() || workload == WorkloadType::Uniform,
+            "tablet is supported only by the uniform workload",
+        );
+        anyhow::ensure!(
+            tablet.is_none() || mode == Mode::Write || mode == Mode::Read,
+            "tablet is supported only in write and read modes",
+        );
+        let split = match split.get().as_str() {
+            "" => None,
+            s => Some(Split::parse(s)?),
+        };
+        anyhow::ensure!(
+            split.is_none() || workload == WorkloadType::Sequential,
+            "split is supported only by the sequential workload",
+        );
+        let keys_file = Some(keys_file.get()).filter(|path| !path.is_empty());
+        let keys_file_loop = keys_file_loop.get();
+        anyhow::ensure!(
+            keys_file.is_some() || !keys_file_loop,
+            "keys-file-loop requires keys-file",
+        );
+        anyhow::ensure!(
+            keys_file.is_none() || (mode != Mode::Scan && workload != WorkloadType::Timeseries),
+            "keys-file is supported neither in scan mode nor by the timeseries workload",
+        );
+        anyhow::ensure!(
+            keys_file.is_none() || tablet.is_none(),
+            "keys-file cannot be used together with tablet",
+        );
+        let clustering_row_distribution =
+            parse_row_distribution(&clustering_row_distribution.get())?;
+        anyhow::ensure!(
+            clustering_row_distribution == RowDistribution::Uniform
+                || workload == WorkloadType::Uniform,
+            "clustering-row-dist is supported only by the uniform workload",
+        );
+        let partition_distribution = parse_row_distribution(&partition_distribution.get())?;
+        anyhow::ensure!(
+            partition_distribution == RowDistribution::Uniform || workload == WorkloadType::Uniform,
+            "partition-dist is supported only by the uniform workload",
+        );
+        let max_clustering_rows_per_op = max_clustering_rows_per_op.get();
+        let partition_growth = partition_growth.get();
+        anyhow::ensure!(
+            (max_clustering_rows_per_op == 0 && partition_growth == 0)
+                || workload == WorkloadType::WidePartition,
+            "max-clustering-rows-per-op and partition-growth are supported only by the wide_partition workload",
+        );
+        anyhow::ensure!(
+            workload != WorkloadType::WidePartition || mode == Mode::Write,
+            "wide_partition workload supports only write mode",
+        );
+        let max_clustering_rows_per_op =
+            (max_clustering_rows_per_op > 0).then_some(max_clustering_rows_per_op);
+        let partition_growth = (partition_growth > 0).then_some(partition_growth);
         let write_rate = write_rate.get();
         let concurrency = concurrency.get();
+        let page_size = i32::try_from(page_size.get())
+            .ok()
+            .filter(|page_size| *page_size > 0)
+            .context("page size must be positive and cannot exceed i32::MAX")?;
         let partition_count = partition_count.get();
         let maximum_rate = maximum_rate.get();
+        anyhow::ensure!(
+            clusters.len() as u64 <= concurrency,
+            "concurrency must be at least the number of clusters",
+        );
+        let background_write_rate = background_write_rate.get();
+        anyhow::ensure!(
+            background_write_rate == 0 || mode == Mode::Read,
+            "background-write-rate is supported only in read mode",
+        );
+        anyhow::ensure!(
+            background_write_rate == 0
+                || workload != WorkloadType::Timeseries
+                || background_write_rate == write_rate,
+            "background-write-rate must be equal to write-rate for the timeseries workload",
+        );
+        anyhow::ensure!(
+            background_write_rate == 0
+                || workload != WorkloadType::Timeseries
+                || concurrency <= partition_count,
+            "Time series background writes require concurrency less than or equal partition count",
+        );
+        anyhow::ensure!(
+            background_write_rate == 0 || clusters.is_empty(),
+            "background-write-rate cannot be used together with clusters",
+        );
+        anyhow::ensure!(
+            background_write_rate == 0 || keys_file.is_none(),
+            "background-write-rate cannot be used together with keys-file",
+        );
+        let background_write_rate = (background_write_rate > 0).then_some(background_write_rate);
+        let read_recent_writes = Some(read_recent_writes.get()).filter(|count| *count != 0);
+        anyhow::ensure!(
+            read_recent_writes.is_none() || background_write_rate.is_some(),
+            "read-recent-writes requires background-write-rate",
+        );
+        let no_wrap = no_wrap.get();
+        anyhow::ensure!(
+            !no_wrap || workload == WorkloadType::Sequential,
+            "no-wrap is supported only by the sequential workload",
+        );
+        let state_file = Some(state_file.get()).filter(|path| !path.is_empty());
+        anyhow::ensure!(
+            state_file.is_none() || (workload == WorkloadType::Sequential && mode != Mode::Scan),
+            "state-file is supported only by the sequential workload",
+        );
+        anyhow::ensure!(
+            state_file.is_none()
+                || (clusters.is_empty() && keys_file.is_none() && read_recent_writes.is_none()),
+            "state-file cannot be used together with clusters, keys-file or read-recent-writes",
+        );
+        let control_file = Some(control_file.get()).filter(|path| !path.is_empty());
+        let serialize_partitions = serialize_partitions.get();
+        anyhow::ensure!(
+            !serialize_partitions || mode != Mode::Scan,
+            "serialize-partitions cannot be used in scan mode",
+        );
 
         if workload == WorkloadType::Timeseries {
             if mode == Mode::Read {
@@ -329,6 +981,19 @@ where
             }
         }
 
+        let arrival = match arrival.get().as_str() {
+            "uniform" => Arrival::Uniform,
+            "poisson" => Arrival::Poisson,
+            s => anyhow::bail!(
+                "Unsupported arrival: {}; supported arrivals are: uniform, poisson",
+                s
+            ),
+        };
+        anyhow::ensure!(
+            arrival == Arrival::Uniform || maximum_rate != 0,
+            "arrival requires max-rate",
+        );
+
         let latency_type = match latency_type.get().as_str() {
             "raw" => LatencyType::Raw,
             "fixed-coordinated-omission" => LatencyType::AdjustedForCoordinatorOmission,
@@ -339,6 +1004,8 @@ where
         // and #tries == #retries + 1,
         // therefore just subtract with wraparound and treat u64::MAX as infinity
         let max_retries_per_op = max_errors_at_row.get().wrapping_sub(1);
+        let retry_backoff = parse_retry_interval(&retry_interval.get())?;
+        let duty_cycle = parse_duty_cycle(&duty_cycle.get())?;
 
         let hdr_latency_resolution = match hdr_latency_units.get().as_str() {
             "ns" => 1,
@@ -351,6 +1018,47 @@ where
             }
         };
 
+        let latency_heatmap_file = Some(latency_heatmap_file.get()).filter(|path| !path.is_empty());
+        anyhow::ensure!(
+            latency_heatmap_file.is_none() || measure_latency.get(),
+            "latency-heatmap-file requires measure-latency",
+        );
+        let report = match report.get().as_str() {
+            "" => None,
+            target => Some(parse_report_target(target, &report_prefix.get())?),
+        };
+
+        let error_log = Some(error_log.get()).filter(|path| !path.is_empty());
+        let error_log_rate = error_log_rate.get();
+        anyhow::ensure!(
+            error_log.is_none() || error_log_rate > 0,
+            "error-log-rate must be positive"
+        );
+
+        let trace_file = Some(trace_file.get()).filter(|path| !path.is_empty());
+        let trace_fraction = parse_trace_fraction(&trace_fraction.get())?;
+        let compressible = Some(compressible.get())
+            .filter(|s| !s.is_empty())
+            .map(|s| parse_compressible(&s))
+            .transpose()?;
+        let key_log = Some(key_log.get()).filter(|path| !path.is_empty());
+        anyhow::ensure!(
+            key_log.is_none() || mode == Mode::Write,
+            "key-log is supported only in write mode",
+        );
+        anyhow::ensure!(
+            key_log.is_none() || clusters.is_empty(),
+            "key-log cannot be used together with clusters",
+        );
+
+        let seed = Some(seed.get()).filter(|seed| *seed != 0);
+
+        let dry_run = Some(dry_run.get()).filter(|count| *count != 0);
+        anyhow::ensure!(
+            dry_run.is_none() || tablet.is_none(),
+            "dry-run cannot be used together with tablet, which requires a connection to the cluster",
+        );
+
         let hdr_latency_sig_fig = hdr_latency_sig_fig.get();
         if !(1..=5).contains(&hdr_latency_sig_fig) {
             return Err(anyhow::anyhow!(
@@ -361,8 +1069,12 @@ where
         Ok(ScyllaBenchArgs {
             workload,
             consistency_level,
-            replication_factor: replication_factor.get(),
+            serial_consistency_level,
+            replication,
             nodes,
+            cloud_config,
+            clusters,
+            cluster_mode,
             ca_cert_file: ca_cert_file.get(),
             client_cert_file: client_cert_file.get(),
             client_key_file: client_key_file.get(),
@@ -370,41 +1082,88 @@ where
             host_verification: host_verification.get(),
             client_compression: client_compression.get(),
             shard_connection_count,
-            page_size: page_size.get(),
+            page_size,
             partition_offset: partition_offset.get(),
+            split,
+            tablet,
+            keys_file,
+            keys_file_loop,
             write_rate,
             distribution,
             start_timestamp,
             host_selection_policy,
+            retry_policy,
             tls_encryption: tls_encryption.get(),
             keyspace_name: keyspace_name.get(),
             table_name: table_name.get(),
             counter_table_name: counter_table_name.get(),
+            counter_columns,
+            counter_columns_per_update,
             username: username.get(),
             password: password.get(),
+            authenticator,
             mode,
             concurrency,
             latency_type,
             max_retries_per_op,
+            retry_backoff,
+            retry_budget: Some(retry_budget.get()).filter(|budget| *budget != 0),
+            duty_cycle,
             maximum_rate,
+            arrival,
+            max_in_flight: Some(max_in_flight.get()).filter(|max| *max != 0),
+            runtimes: Some(runtimes.get()).filter(|runtimes| *runtimes != 0),
+            background_write_rate,
+            read_recent_writes,
+            serialize_partitions,
+            no_schema: no_schema.get(),
             test_duration: test_duration.get(),
+            max_operations: Some(max_operations.get()).filter(|ops| *ops != 0),
             partition_count,
             clustering_row_count: clustering_row_count.get(),
             clustering_row_size_dist: clustering_row_size_dist.get().0,
+            clustering_row_distribution,
+            partition_distribution,
+            max_clustering_rows_per_op,
+            partition_growth,
             rows_per_request: rows_per_request.get(),
             provide_upper_bound: provide_upper_bound.get(),
             in_restriction: in_restriction.get(),
             select_order_by,
             no_lower_bound: no_lower_bound.get(),
             bypass_cache: bypass_cache.get(),
+            lwt,
+            write_timestamp,
+            ttl,
+            twcs_window,
+            twcs_retention,
             range_count: range_count.get(),
             timeout: timeout.get(),
             iterations: iterations.get(),
+            no_wrap,
+            state_file,
+            control_file,
             measure_latency: measure_latency.get(),
+            quiet: quiet.get(),
+            driver_metrics: driver_metrics.get(),
+            client_metrics: client_metrics.get(),
+            cluster_info: cluster_info.get(),
             hdr_latency_file: hdr_latency_file.get(),
+            latency_heatmap_file,
+            report,
             hdr_latency_sig_fig,
             hdr_latency_resolution,
             validate_data: validate_data.get(),
+            compressible,
+            validate_all_pages,
+            expected_rows,
+            error_log,
+            error_log_rate,
+            trace_file,
+            trace_fraction,
+            key_log,
+            seed,
+            dry_run,
         })
     }();
 
@@ -431,16 +1190,76 @@ impl ScyllaBenchArgs {
             "Consistency level:\t {}",
             show_consistency_level(&self.consistency_level)
         );
+        if self.lwt {
+            println!(
+                "Serial consistency level: {}",
+                show_serial_consistency_level(&self.serial_consistency_level)
+            );
+        }
         println!("Partition count:\t {}", self.partition_count);
         if self.workload == WorkloadType::Sequential && self.partition_offset != 0 {
             println!("Partition offset:\t {}", self.partition_offset);
         }
+        if let Some(split) = &self.split {
+            println!("Split:\t\t\t {}", split);
+        }
+        if self.no_wrap {
+            println!("No wrap:\t\t true");
+        }
+        if let Some(tablet) = &self.tablet {
+            println!("Tablet:\t\t\t {}", show_tablet_target(tablet));
+        }
+        if let Some(keys_file) = &self.keys_file {
+            let looped = if self.keys_file_loop { " (looped)" } else { "" };
+            println!("Keys file:\t\t {}{}", keys_file, looped);
+        }
         println!("Clustering rows:\t {}", self.clustering_row_count);
         println!(
             "Clustering row size:\t {}",
             self.clustering_row_size_dist.describe()
         );
+        if self.workload == WorkloadType::Uniform {
+            println!(
+                "Partition dist:\t\t {}",
+                self.partition_distribution.describe()
+            );
+            println!(
+                "Clustering row dist:\t {}",
+                self.clustering_row_distribution.describe()
+            );
+        }
+        if self.workload == WorkloadType::WidePartition {
+            if let Some(max_clustering_rows_per_op) = self.max_clustering_rows_per_op {
+                println!("Max rows per operation:\t {}", max_clustering_rows_per_op);
+            }
+            if let Some(partition_growth) = self.partition_growth {
+                println!("Partition growth:\t {}", partition_growth);
+            }
+        }
         println!("Rows per request:\t {}", self.rows_per_request);
+        if self.mode == Mode::Write || self.mode == Mode::ReadModifyWrite {
+            println!("Lightweight transactions: {}", self.lwt);
+        }
+        if self.mode == Mode::Write {
+            if let Some(write_timestamp) = self.write_timestamp {
+                println!("Write timestamp:\t {}", write_timestamp);
+            }
+            if let Some(ttl) = self.ttl {
+                println!("TTL:\t\t\t {}", format_duration(ttl));
+            }
+        }
+        if self.mode == Mode::CounterUpdate || self.mode == Mode::CounterRead {
+            println!(
+                "Counter columns:\t {} ({} per update)",
+                self.counter_columns, self.counter_columns_per_update
+            );
+        }
+        if matches!(
+            self.mode,
+            Mode::Read | Mode::CounterRead | Mode::Scan | Mode::ReadModifyWrite
+        ) {
+            println!("Bypass cache:\t\t {}", self.bypass_cache);
+        }
         if self.mode == Mode::Read {
             println!("Provide upper bound:\t {}", self.provide_upper_bound);
             println!("IN queries:\t\t {}", self.in_restriction);
@@ -451,16 +1270,85 @@ impl ScyllaBenchArgs {
             println!("No lower bound:\t\t {}", self.no_lower_bound);
         }
         println!("Page size:\t\t {}", self.page_size);
+        if self.validate_all_pages {
+            println!("Validate all pages:\t {}", self.validate_all_pages);
+        }
+        if let Some(expected_rows) = &self.expected_rows {
+            println!("Expected rows:\t\t {}", expected_rows);
+        }
+        println!(
+            "Retry policy:\t\t {}",
+            show_retry_policy(&self.retry_policy)
+        );
         println!("Concurrency:\t\t {}", self.concurrency);
+        if let Some(max_operations) = self.max_operations {
+            println!("Operations:\t\t {}", max_operations);
+        }
         // println!("Connections:\t\t {}", self.connection_count);
         if self.maximum_rate > 0 {
             println!("Maximum rate:\t\t {}ops/s", self.maximum_rate);
+            println!("Arrival:\t\t {}", self.arrival);
         } else {
             println!("Maximum rate:\t\t unlimited");
         }
+        if let Some(max_in_flight) = self.max_in_flight {
+            println!("Max in flight:\t\t {}", max_in_flight);
+        }
+        if self.retry_backoff != RetryBackoff::None {
+            println!("Retry backoff:\t\t {}", self.retry_backoff);
+        }
+        if let Some(retry_budget) = self.retry_budget {
+            println!("Retry budget:\t\t {}/s", retry_budget);
+        }
+        if let Some(runtimes) = self.runtimes {
+            println!("Runtimes:\t\t {}", runtimes);
+        }
+        if let Some(duty_cycle) = self.duty_cycle {
+            println!("Duty cycle:\t\t {}", duty_cycle);
+        }
+        if let Some(background_write_rate) = self.background_write_rate {
+            println!("Background writes:\t {}ops/s", background_write_rate);
+        }
+        if let Some(read_recent_writes) = self.read_recent_writes {
+            println!("Read recent writes:\t {} rows", read_recent_writes);
+        }
+        if self.serialize_partitions {
+            println!("Serialize partitions:\t true");
+        }
+        if self.no_schema {
+            println!("No schema:\t\t true");
+        }
+        if self.driver_metrics {
+            println!("Driver metrics:\t\t true");
+        }
+        if self.client_metrics {
+            println!("Client metrics:\t\t true");
+        }
+        if self.cluster_info {
+            println!("Cluster info:\t\t true");
+        }
+        if let Some(cloud_config) = &self.cloud_config {
+            println!("Cloud config:\t\t {}", cloud_config);
+        }
+        if let Some(authenticator) = &self.authenticator {
+            println!("Authenticator:\t\t {}", authenticator);
+        }
+        if !self.clusters.is_empty() {
+            println!("Clusters:\t\t {}", show_clusters(&self.clusters));
+            println!(
+                "Cluster mode:\t\t {}",
+                show_cluster_mode(&self.cluster_mode)
+            );
+        }
         println!("Client compression:\t {}", self.client_compression);
         println!("Shard connection count:\t {}", self.shard_connection_count);
         if self.workload == WorkloadType::Timeseries {
+            if let Some(twcs_window) = self.twcs_window {
+                println!("TWCS window:\t\t {}", format_duration(twcs_window));
+            }
+            if let Some(twcs_retention) = self.twcs_retention {
+                println!("TWCS retention:\t\t {}", format_duration(twcs_retention));
+            }
             println!("Start timestamp:\t {}", self.start_timestamp);
             println!(
                 "Write rate:\t\t {}",
@@ -468,8 +1356,91 @@ impl ScyllaBenchArgs {
             );
         }
 
+        if let Some(latency_heatmap_file) = &self.latency_heatmap_file {
+            println!("Latency heatmap:\t {}", latency_heatmap_file);
+        }
+        if let Some(report) = &self.report {
+            println!("Report:\t\t\t {}", report.describe());
+        }
+        if let Some(error_log) = &self.error_log {
+            println!(
+                "Error log:\t\t {} (up to {} errors/s)",
+                error_log, self.error_log_rate
+            );
+        }
+        if let Some(compressible) = self.compressible {
+            println!("Compressible fraction:\t {}", compressible);
+        }
+        if let Some(trace_file) = &self.trace_file {
+            println!(
+                "Trace file:\t\t {} (fraction of {} operations)",
+                trace_file, self.trace_fraction
+            );
+        }
+        if let Some(state_file) = &self.state_file {
+            println!("State file:\t\t {}", state_file);
+        }
+        if let Some(control_file) = &self.control_file {
+            println!("Control file:\t\t {}", control_file);
+        }
+        if let Some(key_log) = &self.key_log {
+            println!("Key log:\t\t {}", key_log);
+        }
+
+        if let Some(seed) = self.seed {
+            println!("Seed:\t\t\t {}", seed);
+        }
+        if let Some(dry_run) = self.dry_run {
+            println!("Dry run:\t\t {} operations", dry_run);
+        }
+
         // println!("Hdr memory consumption:\t", results.GetHdrMemoryConsumption(concurrency), "bytes");
     }
+
+    /// Returns the metadata written at the beginning of the output files,
+    /// with the snapshot of the main settings of the run.
+    pub fn run_metadata(&self) -> RunMetadata {
+        let mut configuration = vec![
+            ("Mode", show_mode(&self.mode).to_owned()),
+            ("Workload", show_workload(&self.workload).to_owned()),
+            (
+                "Consistency level",
+                show_consistency_level(&self.consistency_level).to_owned(),
+            ),
+            ("Keyspace", self.keyspace_name.clone()),
+            ("Table", self.table_name.clone()),
+            ("Replication", self.replication.to_cql()),
+            ("Partition count", self.partition_count.to_string()),
+            ("Clustering rows", self.clustering_row_count.to_string()),
+            ("Concurrency", self.concurrency.to_string()),
+            ("Maximum rate", self.maximum_rate.to_string()),
+            ("Test duration", format_duration(self.test_duration)),
+            ("Iterations", self.iterations.to_string()),
+        ];
+        if let Some(seed) = self.seed {
+            configuration.push(("Seed", seed.to_string()));
+        }
+        let configuration = configuration
+            .into_iter()
+            .map(|(setting, value)| (setting.to_owned(), value))
+            .collect();
+
+        if self.clusters.is_empty() {
+            return RunMetadata::new(self.nodes.clone(), configuration);
+        }
+        let nodes = self
+            .clusters
+            .iter()
+            .flat_map(|cluster| cluster.nodes.iter().cloned())
+            .collect();
+        let mut metadata = RunMetadata::new(nodes, configuration);
+        metadata.cluster_names = self
+            .clusters
+            .iter()
+            .map(|cluster| cluster.name.clone())
+            .collect();
+        metadata
+    }
 }
 
 struct ScyllaBenchDistribution(Arc<dyn Distribution>);
@@ -485,6 +1456,170 @@ impl GoValue for ScyllaBenchDistribution {
     }
 }
 
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum TabletTarget {
+    // Only the partitions belonging to the tablet with given index are used.
+    Single(usize),
+    // Each operation targets a tablet chosen uniformly at random.
+    Even,
+}
+
+fn parse_tablet_target(s: &str) -> Result<Option<TabletTarget>> {
+    match s {
+        "" => Ok(None),
+        "even" => Ok(Some(TabletTarget::Even)),
+        _ => {
+            let idx = s
+                .parse()
+                .with_context(|| format!("invalid tablet: {}, expected index or 'even'", s))?;
+            Ok(Some(TabletTarget::Single(idx)))
+        }
+    }
+}
+
+fn parse_retry_interval(s: &str) -> Result<RetryBackoff> {
+    if s.is_empty() {
+        return Ok(RetryBackoff::None);
+    }
+    let parse =
+        |d: &str| parse_duration(d).with_context(|| format!("invalid retry-interval: {}", s));
+    match s.split_once(',') {
+        None => Ok(RetryBackoff::Fixed(parse(s)?)),
+        Some((min, max)) => {
+            let (min, max) = (parse(min)?, parse(max)?);
+            anyhow::ensure!(
+                min <= max,
+                "the minimum of retry-interval must not exceed the maximum, got {}",
+                s
+            );
+            Ok(RetryBackoff::Exponential { min, max })
+        }
+    }
+}
+
+fn parse_duty_cycle(s: &str) -> Result<Option<DutyCycle>> {
+    if s.is_empty() {
+        return Ok(None);
+    }
+    let (on, off) = s
+        .split_once('/')
+        .with_context(|| format!("invalid duty-cycle: {}, expected on/off", s))?;
+    let parse = |d: &str| {
+        parse_duration(d)
+            .ok()
+            .filter(|d| !d.is_zero())
+            .with_context(|| format!("invalid duty-cycle: {}, the windows must be positive", s))
+    };
+    Ok(Some(DutyCycle {
+        on: parse(on)?,
+        off: parse(off)?,
+    }))
+}
+
+fn parse_trace_fraction(s: &str) -> Result<f64> {
+    let fraction: f64 = s
+        .parse()
+        .with_context(|| format!("invalid trace-fraction: {}", s))?;
+    anyhow::ensure!(
+        fraction > 0.0 && fraction <= 1.0,
+        "trace-fraction must be in (0, 1], got {}",
+        s
+    );
+    Ok(fraction)
+}
+
+fn show_tablet_target(target: &TabletTarget) -> String {
+    match target {
+        TabletTarget::Single(idx) => idx.to_string(),
+        TabletTarget::Even => "even".to_string(),
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ClusterTarget {
+    pub name: String,
+    pub nodes: Vec<String>,
+}
+
+fn parse_report_target(s: &str, prefix: &str) -> Result<ReportTarget> {
+    let (protocol, address) = s
+        .split_once('=')
+        .with_context(|| format!("invalid report target: {}", s))?;
+    let protocol = match protocol {
+        "graphite" => ReportProtocol::Graphite,
+        "statsd" => ReportProtocol::Statsd,
+        _ => anyhow::bail!(
+            "invalid report protocol {}, expected graphite or statsd",
+            protocol
+        ),
+    };
+    ReportTarget::new(protocol, address, prefix)
+}
+
+fn parse_clusters(s: &str) -> Result<Vec<ClusterTarget>> {
+    if s.is_empty() {
+        return Ok(Vec::new());
+    }
+    let mut clusters: Vec<ClusterTarget> = Vec::new();
+    for entry in s.split(';').map(str::trim) {
+        let (name, nodes) = entry
+            .split_once('=')
+            .with_context(|| format!("invalid cluster: {}, expected name=host1,host2", entry))?;
+        anyhow::ensure!(!name.is_empty(), "cluster name cannot be empty: {}", entry);
+        anyhow::ensure!(
+            clusters.iter().all(|cluster| cluster.name != name),
+            "duplicate cluster name: {}",
+            name,
+        );
+        let nodes: Vec<String> = nodes
+            .split(',')
+            .filter(|node| !node.is_empty())
+            .map(str::to_string)
+            .collect();
+        anyhow::ensure!(!nodes.is_empty(), "cluster {} has no nodes", name);
+        clusters.push(ClusterTarget {
+            name: name.to_string(),
+            nodes,
+        });
+    }
+    anyhow::ensure!(
+        clusters.len() >= 2,
+        "at least two clusters must be given, use nodes for a single cluster",
+    );
+    Ok(clusters)
+}
+
+fn show_clusters(clusters: &[ClusterTarget]) -> String {
+    clusters
+        .iter()
+        .map(|cluster| format!("{}={}", cluster.name, cluster.nodes.join(",")))
+        .collect::<Vec<_>>()
+        .join(";")
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum ClusterMode {
+    // Each operation is performed on every cluster.
+    Duplicate,
+    // Each operation is performed on one of the clusters.
+    Split,
+}
+
+fn parse_cluster_mode(s: &str) -> Result<ClusterMode> {
+    match s {
+        "duplicate" => Ok(ClusterMode::Duplicate),
+        "split" => Ok(ClusterMode::Split),
+        _ => Err(anyhow::anyhow!("unknown cluster mode: {}", s)),
+    }
+}
+
+fn show_cluster_mode(mode: &ClusterMode) -> &'static str {
+    match mode {
+        ClusterMode::Duplicate => "duplicate",
+        ClusterMode::Split => "split",
+    }
+}
+
 #[derive(Copy, Clone, PartialEq, Eq)]
 pub enum OrderBy {
     None,
@@ -545,6 +1680,7 @@ pub enum Mode {
     CounterUpdate,
     CounterRead,
     Scan,
+    ReadModifyWrite,
 }
 
 fn parse_mode(s: &str) -> Result<Mode> {
@@ -554,18 +1690,20 @@ fn parse_mode(s: &str) -> Result<Mode> {
         "counter_update" => Ok(Mode::CounterUpdate),
         "counter_read" => Ok(Mode::CounterRead),
         "scan" => Ok(Mode::Scan),
+        "read_modify_write" => Ok(Mode::ReadModifyWrite),
         "" => Err(anyhow::anyhow!("mode needs to be specified")),
         _ => Err(anyhow::anyhow!("unknown mode: {}", s)),
     }
 }
 
-fn show_mode(m: &Mode) -> &'static str {
+pub fn show_mode(m: &Mode) -> &'static str {
     match m {
         Mode::Write => "write",
         Mode::Read => "read",
         Mode::CounterUpdate => "counter_update",
         Mode::CounterRead => "counter_read",
         Mode::Scan => "scan",
+        Mode::ReadModifyWrite => "read_modify_write",
     }
 }
 
@@ -574,7 +1712,10 @@ pub enum WorkloadType {
     Sequential,
     Uniform,
     Timeseries,
+    WidePartition,
     Scan,
+    /// A workload added to the registry, see [registry::register_workload].
+    Custom(String),
 }
 
 fn parse_workload(s: &str) -> Result<WorkloadType> {
@@ -582,18 +1723,26 @@ fn parse_workload(s: &str) -> Result<WorkloadType> {
         "sequential" => Ok(WorkloadType::Sequential),
         "uniform" => Ok(WorkloadType::Uniform),
         "timeseries" => Ok(WorkloadType::Timeseries),
+        "wide_partition" => Ok(WorkloadType::WidePartition),
         // scan workload cannot be specified through CLI
         "" => Err(anyhow::anyhow!("workload type needs to be specified")),
-        _ => Err(anyhow::anyhow!("unknown workload type: {}", s)),
+        _ if registry::is_registered(s) => Ok(WorkloadType::Custom(s.to_owned())),
+        _ => Err(anyhow::anyhow!(
+            "unknown workload type: {}. Must be one of: {}",
+            s,
+            registry::workload_names().join(", ")
+        )),
     }
 }
 
-fn show_workload(w: &WorkloadType) -> &'static str {
+pub fn show_workload(w: &WorkloadType) -> &str {
     match w {
         WorkloadType::Sequential => "sequential",
         WorkloadType::Uniform => "uniform",
         WorkloadType::Timeseries => "timeseries",
+        WorkloadType::WidePartition => "wide_partition",
         WorkloadType::Scan => "scan",
+        WorkloadType::Custom(name) => name,
     }
 }
 
@@ -615,6 +1764,22 @@ fn parse_consistency_level(s: &str) -> Result<Consistency> {
     Ok(level)
 }
 
+fn parse_serial_consistency_level(s: &str) -> Result<SerialConsistency> {
+    let level = match s {
+        "serial" => SerialConsistency::Serial,
+        "local_serial" => SerialConsistency::LocalSerial,
+        _ => return Err(anyhow::anyhow!("Unknown serial consistency level: {}", s)),
+    };
+    Ok(level)
+}
+
+fn show_serial_consistency_level(cl: &SerialConsistency) -> &'static str {
+    match cl {
+        SerialConsistency::Serial => "serial",
+        SerialConsistency::LocalSerial => "local_serial",
+    }
+}
+
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub enum TimeseriesDistribution {
     Uniform,
@@ -662,3 +1827,37 @@ fn parse_host_selection_policy(s: &str) -> Result<Arc<dyn LoadBalancingPolicy>>
     };
     Ok(policy)
 }
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RetryPolicyKind {
+    Default,
+    Fallthrough,
+    Downgrading,
+}
+
+impl RetryPolicyKind {
+    pub fn create(&self) -> Box<dyn RetryPolicy> {
+        match self {
+            RetryPolicyKind::Default => Box::new(DefaultRetryPolicy::new()),
+            RetryPolicyKind::Fallthrough => Box::new(FallthroughRetryPolicy::new()),
+            RetryPolicyKind::Downgrading => Box::new(DowngradingConsistencyRetryPolicy::new()),
+        }
+    }
+}
+
+fn parse_retry_policy(s: &str) -> Result<RetryPolicyKind> {
+    match s {
+        "default" => Ok(RetryPolicyKind::Default),
+        "fallthrough" => Ok(RetryPolicyKind::Fallthrough),
+        "downgrading" => Ok(RetryPolicyKind::Downgrading),
+        _ => Err(anyhow::anyhow!("Unknown retry policy: {}", s)),
+    }
+}
+
+fn show_retry_policy(policy: &RetryPolicyKind) -> &'static str {
+    match policy {
+        RetryPolicyKind::Default => "default",
+        RetryPolicyKind::Fallthrough => "fallthrough",
+        RetryPolicyKind::Downgrading => "downgrading",
+    }
+}
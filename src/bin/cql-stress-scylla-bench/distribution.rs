@@ -99,3 +99,185 @@ impl Distribution for Uniform {
         format!("Uniform(min={}, max={})", self.low, self.high)
     }
 }
+
+/// Describes which clustering rows of a partition are picked by the operations
+/// of the uniform workload. The rows are numbered `0..row_count`, so contrary to
+/// the [Distribution]s, the range is implied by the clustering row count.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum RowDistribution {
+    Uniform,
+    // Row `k` (counting from 1) is picked with probability proportional to 1/k^exponent.
+    Zipf { exponent: f64 },
+    // Centered in the middle of the partition, the standard deviation
+    // is expressed as a fraction of the row count.
+    Normal { stdev: f64 },
+    // Starts at the first row, the mean is expressed as a fraction of the row count.
+    Exponential { mean: f64 },
+}
+
+pub fn parse_row_distribution(s: &str) -> Result<RowDistribution> {
+    let s = s.trim();
+    // The parameters are optional, so a bare name is also accepted
+    let desc = if s.chars().all(|c| c.is_ascii_alphabetic()) {
+        Description {
+            name: s,
+            args: vec![],
+            inverted: false,
+        }
+    } else {
+        parse_description(s, SyntaxFlavor::ClassicOrShort)?
+    };
+
+    anyhow::ensure!(!desc.inverted, "Inverted distributions are not supported");
+
+    let param = |default: f64| -> Result<f64> {
+        anyhow::ensure!(desc.args.len() <= 1, "Expected at most one argument");
+        let value = match desc.args.first() {
+            Some(arg) => arg
+                .parse::<f64>()
+                .with_context(|| format!("Invalid distribution parameter: {}", arg))?,
+            None => default,
+        };
+        anyhow::ensure!(
+            value.is_finite() && value > 0.0,
+            "The distribution parameter must be positive"
+        );
+        Ok(value)
+    };
+
+    match desc.name {
+        "uniform" => {
+            desc.check_argument_count(0)?;
+            Ok(RowDistribution::Uniform)
+        }
+        "zipf" | "zipfian" => Ok(RowDistribution::Zipf {
+            exponent: param(1.0)?,
+        }),
+        "normal" | "gaussian" => Ok(RowDistribution::Normal {
+            stdev: param(0.15)?,
+        }),
+        "exp" | "exponential" => Ok(RowDistribution::Exponential { mean: param(0.1)? }),
+        other => Err(anyhow::anyhow!("Unknown row distribution: {}", other)),
+    }
+}
+
+impl RowDistribution {
+    pub fn describe(&self) -> String {
+        match self {
+            RowDistribution::Uniform => "uniform".to_string(),
+            RowDistribution::Zipf { exponent } => format!("zipf:{}", exponent),
+            RowDistribution::Normal { stdev } => format!("normal:{}", stdev),
+            RowDistribution::Exponential { mean } => format!("exp:{}", mean),
+        }
+    }
+
+    /// Creates a sampler of row numbers from range `0..row_count`.
+    pub fn sampler(&self, row_count: u64) -> Result<RowSampler> {
+        anyhow::ensure!(row_count > 0, "Row count must be greater than zero");
+        let sampler = match *self {
+            RowDistribution::Uniform => RowSampler::Uniform(rand_distr::Uniform::new(0, row_count)),
+            RowDistribution::Zipf { exponent } => {
+                RowSampler::Zipf(rand_distr::Zipf::new(row_count, exponent)?)
+            }
+            RowDistribution::Normal { stdev } => {
+                let count = row_count as f64;
+                let normal = rand_distr::Normal::new(count / 2.0, stdev * count)?;
+                RowSampler::Normal(normal, row_count)
+            }
+            RowDistribution::Exponential { mean } => {
+                let exp = rand_distr::Exp::new(1.0 / (mean * row_count as f64))?;
+                RowSampler::Exponential(exp, row_count)
+            }
+        };
+        Ok(sampler)
+    }
+}
+
+#[derive(Clone)]
+pub enum RowSampler {
+    Uniform(rand_distr::Uniform<u64>),
+    Zipf(rand_distr::Zipf<f64>),
+    // For the continuous distributions, the samples outside of `0..row_count`
+    // are rejected and drawn again.
+    Normal(rand_distr::Normal<f64>, u64),
+    Exponential(rand_distr::Exp<f64>, u64),
+}
+
+impl RowSampler {
+    pub fn sample(&self, rng: &mut RngGen) -> u64 {
+        match self {
+            RowSampler::Uniform(uniform) => uniform.sample(rng),
+            // Zipf samples from range 1..=row_count
+            RowSampler::Zipf(zipf) => zipf.sample(rng) as u64 - 1,
+            RowSampler::Normal(normal, row_count) => sample_in_range(normal, *row_count, rng),
+            RowSampler::Exponential(exp, row_count) => sample_in_range(exp, *row_count, rng),
+        }
+    }
+}
+
+fn sample_in_range(dist: &impl RandDistribution<f64>, row_count: u64, rng: &mut RngGen) -> u64 {
+    loop {
+        let x = dist.sample(rng);
+        if x >= 0.0 && x < row_count as f64 {
+            return x as u64;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::SeedableRng;
+
+    use super::*;
+
+    #[test]
+    fn test_parse_row_distribution() {
+        assert_eq!(
+            parse_row_distribution("uniform").unwrap(),
+            RowDistribution::Uniform
+        );
+        assert_eq!(
+            parse_row_distribution("zipf").unwrap(),
+            RowDistribution::Zipf { exponent: 1.0 }
+        );
+        assert_eq!(
+            parse_row_distribution("zipf:1.5").unwrap(),
+            RowDistribution::Zipf { exponent: 1.5 }
+        );
+        assert_eq!(
+            parse_row_distribution("normal(0.25)").unwrap(),
+            RowDistribution::Normal { stdev: 0.25 }
+        );
+        assert_eq!(
+            parse_row_distribution("exponential:0.05").unwrap(),
+            RowDistribution::Exponential { mean: 0.05 }
+        );
+
+        assert!(parse_row_distribution("uniform:1..10").is_err());
+        assert!(parse_row_distribution("zipf:0").is_err());
+        assert!(parse_row_distribution("zipf:-1").is_err());
+        assert!(parse_row_distribution("normal:abc").is_err());
+        assert!(parse_row_distribution("~exp").is_err());
+        assert!(parse_row_distribution("pareto").is_err());
+    }
+
+    #[test]
+    fn test_row_sampler() {
+        let mut rng = RngGen::seed_from_u64(0x1234);
+        for dist in ["uniform", "zipf:1.2", "normal:0.5", "exp:0.5"] {
+            let sampler = parse_row_distribution(dist).unwrap().sampler(100).unwrap();
+            let mut counts = [0usize; 100];
+            for _ in 0..10_000 {
+                counts[sampler.sample(&mut rng) as usize] += 1;
+            }
+
+            match dist {
+                // The first rows are the hottest ones
+                "zipf:1.2" | "exp:0.5" => assert!(counts[0] > counts[99]),
+                // The middle rows are the hottest ones
+                "normal:0.5" => assert!(counts[50] > counts[0]),
+                _ => assert!(counts.iter().all(|c| *c > 0)),
+            }
+        }
+    }
+}
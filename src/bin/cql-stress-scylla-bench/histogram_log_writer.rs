@@ -8,11 +8,24 @@ use hdrhistogram::serialization::{Serializer, V2DeflateSerializer};
 use hdrhistogram::Histogram;
 use tokio::io::{AsyncWrite, AsyncWriteExt};
 
+use cql_stress::run_metadata::RunMetadata;
+
 const LOG_FORMAT_VERSION: &str = "1.3";
 
 pub struct HistogramLogOptions<'t> {
+    /// The interval, in seconds since the base time.
     pub interval_seconds: Range<f64>,
     pub tag: &'t str,
+    /// The number of recorded units per millisecond. The max value
+    /// of the interval is logged in milliseconds.
+    pub units_per_ms: f64,
+}
+
+// The times are logged with a millisecond precision, the same as by the Java HdrHistogram.
+fn millis_since_epoch(time: SystemTime) -> u64 {
+    time.duration_since(std::time::UNIX_EPOCH)
+        .unwrap()
+        .as_millis() as u64
 }
 
 pub struct HistogramLogWriter<W: AsyncWrite + Unpin> {
@@ -29,37 +42,40 @@ impl<W: AsyncWrite + Unpin> HistogramLogWriter<W> {
         self.writer.write_all(line.as_bytes()).await
     }
 
+    /// The metadata is a comment line, ignored by the HdrHistogram log readers.
+    pub async fn output_run_metadata(&mut self, metadata: &RunMetadata) -> Result<()> {
+        let line = metadata.to_comment_line();
+        self.writer.write_all(line.as_bytes()).await
+    }
+
+    pub async fn flush(&mut self) -> Result<()> {
+        self.writer.flush().await
+    }
+
     pub async fn output_comment(&mut self, s: &str) -> Result<()> {
         let line = format!("#{}\n", s);
         self.writer.write_all(line.as_bytes()).await
     }
 
+    /// The timestamps of the intervals are relative to the base time.
     pub async fn output_base_time(&mut self, base_time: SystemTime) -> Result<()> {
-        let secs = base_time
-            .duration_since(std::time::UNIX_EPOCH)
-            .unwrap()
-            .as_secs();
-        let line = format!("#[Basetime: {} (seconds since epoch)]\n", secs);
+        let line = format!(
+            "#[BaseTime: {:.3} (seconds since epoch)]\n",
+            millis_since_epoch(base_time) as f64 / 1000.0
+        );
         self.writer.write_all(line.as_bytes()).await
     }
 
-    pub async fn output_start_time(&mut self, base_time: SystemTime) -> Result<()> {
-        let secs = base_time
-            .duration_since(std::time::UNIX_EPOCH)
-            .unwrap()
-            .as_secs();
-        let millis = base_time
-            .duration_since(std::time::UNIX_EPOCH)
-            .unwrap()
-            .as_millis();
-        let rounded_base_time = std::time::UNIX_EPOCH
-            .checked_add(Duration::from_millis(millis as u64))
+    pub async fn output_start_time(&mut self, start_time: SystemTime) -> Result<()> {
+        let millis = millis_since_epoch(start_time);
+        let rounded_start_time = std::time::UNIX_EPOCH
+            .checked_add(Duration::from_millis(millis))
             .unwrap();
-        let utc_base_time: DateTime<Utc> = rounded_base_time.into();
+        let utc_start_time: DateTime<Utc> = rounded_start_time.into();
         let line = format!(
-            "#[StartTime: {} (seconds since epoch), {}]\n",
-            secs,
-            utc_base_time.to_rfc3339(),
+            "#[StartTime: {:.3} (seconds since epoch), {}]\n",
+            millis as f64 / 1000.0,
+            utc_start_time.to_rfc3339(),
         );
         self.writer.write_all(line.as_bytes()).await
     }
@@ -73,20 +89,62 @@ impl<W: AsyncWrite + Unpin> HistogramLogWriter<W> {
         histogram: &Histogram<u64>,
         opts: HistogramLogOptions<'t>,
     ) -> Result<()> {
-        const MS_TO_NS_RATIO: f64 = 1_000_000.0;
-        let max_value = histogram.max() as f64 / MS_TO_NS_RATIO;
+        let max_value = histogram.max() as f64 / opts.units_per_ms;
         let mut raw_encoded_histogram = Vec::new();
         V2DeflateSerializer::new()
             .serialize(histogram, &mut raw_encoded_histogram)
             .unwrap();
+        let interval = opts.interval_seconds;
         let line = format!(
-            "Tag={tag},{start_time},{end_time},{max_value},{encoded}\n",
+            "Tag={tag},{start_time:.3},{length:.3},{max_value:.3},{encoded}\n",
             tag = opts.tag,
-            start_time = opts.interval_seconds.start,
-            end_time = opts.interval_seconds.end,
+            start_time = interval.start,
+            length = interval.end - interval.start,
             max_value = max_value,
             encoded = base64::encode(&raw_encoded_histogram),
         );
         self.writer.write_all(line.as_bytes()).await
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, UNIX_EPOCH};
+
+    use hdrhistogram::Histogram;
+
+    use super::{HistogramLogOptions, HistogramLogWriter};
+
+    #[tokio::test]
+    async fn test_log_times() {
+        let mut writer = HistogramLogWriter::new(Vec::new());
+        let base_time = UNIX_EPOCH + Duration::from_millis(1_700_000_000_123);
+        writer.output_base_time(base_time).await.unwrap();
+        writer.output_start_time(base_time).await.unwrap();
+
+        let mut histogram = Histogram::<u64>::new(3).unwrap();
+        histogram.record(2_000).unwrap();
+        let opts = HistogramLogOptions {
+            interval_seconds: 0.25..1.25,
+            tag: "raw",
+            units_per_ms: 1_000.0,
+        };
+        writer
+            .output_interval_histogram(&histogram, opts)
+            .await
+            .unwrap();
+
+        let log = String::from_utf8(writer.writer).unwrap();
+        let lines = log.lines().collect::<Vec<_>>();
+        assert_eq!(
+            "#[BaseTime: 1700000000.123 (seconds since epoch)]",
+            lines[0]
+        );
+        assert_eq!(
+            "#[StartTime: 1700000000.123 (seconds since epoch), 2023-11-14T22:13:20.123+00:00]",
+            lines[1]
+        );
+        // The interval is logged as its start and length, the max value in milliseconds.
+        assert!(lines[2].starts_with("Tag=raw,0.250,1.000,2.000,"));
+    }
+}
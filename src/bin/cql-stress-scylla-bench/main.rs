@@ -6,6 +6,7 @@ mod distribution;
 mod gocompat;
 mod histogram_log_writer;
 mod operation;
+mod state_file;
 pub(crate) mod stats;
 mod workload;
 
@@ -19,28 +20,53 @@ use anyhow::{Context, Result};
 use futures::future;
 use openssl::ssl::{SslContext, SslContextBuilder, SslFiletype, SslMethod, SslVerifyMode};
 use scylla::transport::session::PoolSize;
-use scylla::ExecutionProfile;
+use scylla::transport::session_builder::{GenericSessionBuilder, SessionBuilderKind};
 use scylla::{transport::Compression, Session, SessionBuilder};
+use scylla::{CloudSessionBuilder, ExecutionProfile};
 use tracing_subscriber::EnvFilter;
 
-use cql_stress::configuration::{Configuration, OperationFactory};
-use cql_stress::run::RunController;
+use cql_stress::clock;
+use cql_stress::cluster_info::ClusterInfo;
+use cql_stress::configuration::{
+    self, Arrival, Configuration, OperationFactory, RateLimitMode, StreamConfiguration,
+};
+use cql_stress::control_file;
+use cql_stress::driver_metrics::{DriverMetricsSampler, SAMPLING_PERIOD};
+use cql_stress::dry_run::{print_operations, OperationRenderer};
+use cql_stress::error_log::ErrorLog;
+use cql_stress::exit_code::ExitCode;
+use cql_stress::key_file::KeyFile;
+use cql_stress::key_log::KeyLog;
+use cql_stress::latency_heatmap::LatencyHeatmapWriter;
+use cql_stress::partition_lock::PartitionLocks;
+use cql_stress::run::{RunAborted, RunController};
 use cql_stress::sharded_stats::{Stats as _, StatsFactory as _};
+use cql_stress::signals::{DumpSignals, StopSignals};
+use cql_stress::stats_report::StatsReporter;
+use cql_stress::trace_log::TraceLog;
+use cql_stress::version::{
+    features_report, flag_requested, BuildInfo, LIST_FEATURES_FLAG, VERSION_JSON_FLAG,
+};
 
-use crate::args::{Mode, ScyllaBenchArgs, WorkloadType};
-use crate::operation::counter_update::CounterUpdateOperationFactory;
-use crate::operation::read::{ReadKind, ReadOperationFactory};
-use crate::operation::scan::ScanOperationFactory;
-use crate::operation::write::WriteOperationFactory;
-use crate::stats::{ShardedStats, StatsFactory, StatsPrinter};
+use crate::args::{show_workload, ClusterMode, Mode, ScyllaBenchArgs};
+use crate::operation::counter_update::{CounterUpdateOperationFactory, CounterUpdateRenderer};
+use crate::operation::multi_cluster::MultiClusterOperationFactory;
+use crate::operation::read::{ReadKind, ReadOperationFactory, ReadRenderer};
+use crate::operation::read_modify_write::{
+    ReadModifyWriteOperationFactory, ReadModifyWriteRenderer,
+};
+use crate::operation::scan::{ScanOperationFactory, ScanRenderer};
+use crate::operation::write::{WriteOperationFactory, WriteRenderer};
+use crate::state_file::StateFile;
+use crate::stats::{ShardedStats, Stats, StatsFactory, StatsPrinter};
+use crate::workload::registry::{create_workload, sequential_config, WorkloadContext};
 use crate::workload::{
-    SequentialConfig, SequentialFactory, TimeseriesReadConfig, TimeseriesReadFactory,
-    TimeseriesWriteConfig, TimeseriesWriteFactory, UniformConfig, UniformFactory, WorkloadFactory,
+    KeysFileFactory, RecentKeys, RecentKeysFactory, SequentialFactory, SequentialProgress,
+    TabletLayout, WorkloadFactory,
 };
 
-// TODO: Return exit code
 #[tokio::main]
-async fn main() -> Result<()> {
+async fn main() -> std::process::ExitCode {
     tracing_subscriber::fmt()
         .with_ansi(false)
         .with_env_filter(EnvFilter::try_from_default_env().unwrap_or(EnvFilter::new("warn")))
@@ -53,120 +79,574 @@ async fn main() -> Result<()> {
         );
     }
 
+    if flag_requested(std::env::args(), VERSION_JSON_FLAG) {
+        println!("{}", BuildInfo::current().to_json());
+        return ExitCode::Success.into();
+    }
+    if flag_requested(std::env::args(), LIST_FEATURES_FLAG) {
+        print!("{}", features_report());
+        return ExitCode::Success.into();
+    }
+
     let sb_config = match args::parse_scylla_bench_args(std::env::args(), true) {
         Some(sb_config) => sb_config,
-        None => return Err(anyhow::anyhow!("Failed to parse the CLI arguments")),
+        None => {
+            let err = anyhow::anyhow!("Failed to parse the CLI arguments");
+            return ExitCode::InvalidArguments.with_error(&err).into();
+        }
     };
-    let sb_config = Arc::new(sb_config);
 
-    sb_config.print_configuration();
+    let code = match run_benchmark(Arc::new(sb_config)).await {
+        Ok(code) => code,
+        Err(err) => ExitCode::OtherError.with_error(&err),
+    };
+    code.into()
+}
+
+async fn run_benchmark(sb_config: Arc<ScyllaBenchArgs>) -> Result<ExitCode> {
+    if !sb_config.quiet {
+        sb_config.print_configuration();
+    }
+
+    if let Some(count) = sb_config.dry_run {
+        let mut renderers =
+            match create_renderers(&sb_config).context("Failed to prepare the dry run") {
+                Ok(renderers) => renderers,
+                Err(err) => return Ok(ExitCode::PreparationFailed.with_error(&err)),
+            };
+        println!();
+        print_operations(&mut renderers, count, &mut std::io::stdout())?;
+        return Ok(ExitCode::Success);
+    }
 
     let stats_factory = Arc::new(StatsFactory::new(&sb_config));
-    let sharded_stats = Arc::new(ShardedStats::new(Arc::clone(&stats_factory)));
+    // Each of the clusters has its own stats, so that they can be summarized separately.
+    let cluster_stats: Vec<Arc<ShardedStats>> = (0..sb_config.clusters.len().max(1))
+        .map(|_| Arc::new(ShardedStats::new(Arc::clone(&stats_factory))))
+        .collect();
+    let background_stats = sb_config
+        .background_write_rate
+        .map(|_| Arc::new(ShardedStats::new(Arc::clone(&stats_factory))));
+
+    let metadata = sb_config.run_metadata();
+    let mut error_log = match &sb_config.error_log {
+        Some(path) => {
+            let (log, writer) = ErrorLog::new(path, sb_config.error_log_rate, &metadata).await?;
+            Some((log, tokio::task::spawn(writer.run())))
+        }
+        None => None,
+    };
+    let mut trace_log = match &sb_config.trace_file {
+        Some(path) => {
+            let (log, writer) = TraceLog::new(path, sb_config.trace_fraction, &metadata).await?;
+            Some((log, tokio::task::spawn(writer.run())))
+        }
+        None => None,
+    };
+    let mut key_log = match &sb_config.key_log {
+        Some(path) => {
+            let (log, writer) = KeyLog::new(path, &metadata).await?;
+            Some((log, tokio::task::spawn(writer.run())))
+        }
+        None => None,
+    };
+    let state_file = match &sb_config.state_file {
+        Some(path) => {
+            let state_file = StateFile::open(path, &sb_config)?;
+            if let (Some(pk), false) = (state_file.resumed_from(), sb_config.quiet) {
+                println!("Resuming from partition {}", pk);
+            }
+            Some(state_file)
+        }
+        None => None,
+    };
 
-    let run_config = prepare(sb_config.clone(), Arc::clone(&sharded_stats))
-        .await
-        .context("Failed to prepare the benchmark")?;
+    let (run_config, background_config, sessions) = match prepare(
+        sb_config.clone(),
+        cluster_stats.clone(),
+        background_stats.clone(),
+        error_log.as_ref().map(|(log, _)| Arc::clone(log)),
+        trace_log.as_ref().map(|(log, _)| Arc::clone(log)),
+        key_log.as_ref().map(|(log, _)| Arc::clone(log)),
+        state_file.as_ref().map(StateFile::progress),
+    )
+    .await
+    .context("Failed to prepare the benchmark")
+    {
+        Ok(configs) => configs,
+        Err(err) => return Ok(ExitCode::PreparationFailed.with_error(&err)),
+    };
+
+    // The snapshot of the (first) cluster, compared with the one taken at the end.
+    let cluster_info = match sb_config.cluster_info {
+        true => {
+            let session = Arc::clone(&sessions[0]);
+            let info = ClusterInfo::fetch(&session).await;
+            info.write("Cluster at the start", &mut std::io::stdout())?;
+            Some((session, info))
+        }
+        false => None,
+    };
 
     let mut combined_stats = stats_factory.create();
+    let mut combined_cluster_stats: Vec<Stats> = cluster_stats
+        .iter()
+        .map(|_| stats_factory.create())
+        .collect();
+    let mut combined_background_stats = stats_factory.create();
 
+    let stop_signals = StopSignals::new()?;
+    let mut dump_signals = DumpSignals::new()?;
+
+    // The background writes start first, so that the reads are measured under the write load.
+    let mut background_run = background_config.map(cql_stress::run::run);
     let (ctrl, run_finished) = cql_stress::run::run(run_config);
     let ctrl = Arc::new(ctrl);
 
     // Don't care about the leaking task, it won't prevent the runtime
     // from being stopped.
-    tokio::task::spawn(stop_on_signal(Arc::clone(&ctrl)));
+    tokio::task::spawn(stop_on_signal(Arc::clone(&ctrl), stop_signals));
+    if let Some(path) = &sb_config.control_file {
+        control_file::watch(path.into(), &ctrl);
+    }
 
+    let heatmap_writer = match &sb_config.latency_heatmap_file {
+        Some(path) => Some(
+            LatencyHeatmapWriter::create(path, sb_config.hdr_latency_resolution, &metadata).await?,
+        ),
+        None => None,
+    };
     let mut printer = StatsPrinter::new(
         sb_config.measure_latency.then_some(sb_config.latency_type),
         (!sb_config.hdr_latency_file.is_empty()).then_some(sb_config.hdr_latency_file.as_str()),
+        heatmap_writer,
+        sb_config.quiet,
+        &metadata,
     )
     .await?;
-    let mut ticker = tokio::time::interval(Duration::from_secs(1));
+    let mut reporter = sb_config.report.clone().map(StatsReporter::spawn);
+    let report_latency_type = sb_config.measure_latency.then_some(sb_config.latency_type);
+    let driver_metrics = sb_config
+        .driver_metrics
+        .then(|| DriverMetricsSampler::spawn(sessions, SAMPLING_PERIOD));
+    // The intervals end at full seconds of the wall clock, so the first one is shorter.
+    let mut ticker = clock::aligned_interval(Duration::from_secs(1));
     futures::pin_mut!(run_finished);
 
-    // Skip the first tick, which is immediate
-    ticker.tick().await;
-
     printer.print_header(&mut std::io::stdout())?;
 
     loop {
         tokio::select! {
             _ = ticker.tick() => {
-                let partial_stats = sharded_stats.get_combined_and_clear();
+                let partial_stats = collect_partial_stats(
+                    &stats_factory,
+                    &cluster_stats,
+                    &mut combined_cluster_stats,
+                );
                 printer.print_partial(&partial_stats, &mut std::io::stdout()).await?;
+                if let Some(reporter) = &reporter {
+                    reporter.report(partial_stats.report_snapshot(report_latency_type));
+                }
+                if let Some(sampler) = &driver_metrics {
+                    printer.print_partial_driver_metrics(
+                        &sampler.take_interval(),
+                        &mut std::io::stdout(),
+                    )?;
+                }
+                if sb_config.client_metrics {
+                    printer.print_partial_client_metrics(&partial_stats, &mut std::io::stdout())?;
+                }
                 combined_stats.combine(&partial_stats);
+                if let Some(background_stats) = &background_stats {
+                    combined_background_stats.combine(&background_stats.get_combined_and_clear());
+                }
+                if let Some(state_file) = &state_file {
+                    state_file.save().await.context("Failed to save the state")?;
+                }
             }
-            result = &mut run_finished => {
+            _ = dump_signals.recv() => {
+                // Close the current interval, so that the dump covers
+                // all operations performed so far.
+                let partial_stats = collect_partial_stats(
+                    &stats_factory,
+                    &cluster_stats,
+                    &mut combined_cluster_stats,
+                );
+                printer.print_partial(&partial_stats, &mut std::io::stdout()).await?;
+                if let Some(reporter) = &reporter {
+                    reporter.report(partial_stats.report_snapshot(report_latency_type));
+                }
+                combined_stats.combine(&partial_stats);
+                printer.print_final(&combined_stats, &mut std::io::stdout())?;
+                printer.flush().await?;
+            }
+            mut result = &mut run_finished => {
+                if let Some((background_ctrl, background_finished)) = background_run.take() {
+                    if result.as_ref().is_err_and(|err| err.is::<RunAborted>()) {
+                        background_ctrl.abort();
+                    } else {
+                        background_ctrl.ask_to_stop();
+                    }
+                    let background_result = background_finished
+                        .await
+                        .context("An error occurred during the background writes");
+                    if result.is_ok() {
+                        result = background_result;
+                    }
+                }
                 if result.is_ok() {
                     // Combine stats for the last time
-                    let partial_stats = sharded_stats.get_combined_and_clear();
+                    let partial_stats = collect_partial_stats(
+                        &stats_factory,
+                        &cluster_stats,
+                        &mut combined_cluster_stats,
+                    );
+                    if let Some(reporter) = &reporter {
+                        reporter.report(partial_stats.report_snapshot(report_latency_type));
+                    }
                     combined_stats.combine(&partial_stats);
                     printer.print_final(&combined_stats, &mut std::io::stdout())?;
+                    if sb_config.client_metrics {
+                        printer.print_final_client_metrics(&combined_stats, &mut std::io::stdout())?;
+                    }
+                    for (cluster, stats) in sb_config.clusters.iter().zip(&combined_cluster_stats) {
+                        printer.print_final_summary(
+                            &format!("Cluster {}", cluster.name),
+                            stats,
+                            &mut std::io::stdout(),
+                        )?;
+                    }
+                    if let Some(background_stats) = &background_stats {
+                        combined_background_stats
+                            .combine(&background_stats.get_combined_and_clear());
+                        printer.print_final_summary(
+                            "Background writes",
+                            &combined_background_stats,
+                            &mut std::io::stdout(),
+                        )?;
+                    }
+                    if let Some((session, start_info)) = &cluster_info {
+                        let end_info = ClusterInfo::fetch(session).await;
+                        println!();
+                        end_info.write("Cluster at the end", &mut std::io::stdout())?;
+                        end_info.write_changes_since(start_info, &mut std::io::stdout())?;
+                    }
+                }
+                printer.flush().await?;
+                if let Some(reporter) = reporter.take() {
+                    reporter.close().await;
+                }
+                // Saved also if the run failed, so that it can be resumed.
+                if let Some(state_file) = &state_file {
+                    state_file.save().await.context("Failed to save the state")?;
+                }
+                if let Some((log, writer)) = error_log.take() {
+                    log.close();
+                    writer.await?.context("Failed to write the error log")?;
+                }
+                if let Some((log, writer)) = trace_log.take() {
+                    log.close();
+                    writer.await?.context("Failed to write the trace log")?;
                 }
-                return result.context("An error occurred during the benchmark");
+                if let Some((log, writer)) = key_log.take() {
+                    log.close();
+                    writer.await?.context("Failed to write the key log")?;
+                }
+                let code = ExitCode::of_run(&result);
+                if let Err(err) = result.context("An error occurred during the benchmark") {
+                    code.with_error(&err);
+                }
+                return Ok(code);
             }
         }
     }
 }
 
-async fn stop_on_signal(runner: Arc<RunController>) {
-    tokio::signal::ctrl_c().await.unwrap();
+// Returns the stats gathered by all clusters since the last call,
+// and adds them to the totals of the respective clusters.
+fn collect_partial_stats(
+    stats_factory: &StatsFactory,
+    cluster_stats: &[Arc<ShardedStats>],
+    combined_cluster_stats: &mut [Stats],
+) -> Stats {
+    let mut partial_stats = stats_factory.create();
+    for (stats, combined) in cluster_stats.iter().zip(combined_cluster_stats) {
+        let cluster_partial_stats = stats.get_combined_and_clear();
+        combined.combine(&cluster_partial_stats);
+        partial_stats.combine(&cluster_partial_stats);
+    }
+    partial_stats
+}
+
+async fn stop_on_signal(runner: Arc<RunController>, mut signals: StopSignals) {
+    signals.recv().await;
     runner.ask_to_stop();
 
-    tokio::signal::ctrl_c().await.unwrap();
+    signals.recv().await;
     runner.abort();
 }
 
-async fn prepare(args: Arc<ScyllaBenchArgs>, stats: Arc<ShardedStats>) -> Result<Configuration> {
-    let mut builder = SessionBuilder::new().known_nodes(&args.nodes);
+async fn prepare(
+    args: Arc<ScyllaBenchArgs>,
+    cluster_stats: Vec<Arc<ShardedStats>>,
+    background_stats: Option<Arc<ShardedStats>>,
+    error_log: Option<Arc<ErrorLog>>,
+    trace_log: Option<Arc<TraceLog>>,
+    key_log: Option<Arc<KeyLog>>,
+    progress: Option<Arc<SequentialProgress>>,
+) -> Result<(Configuration, Option<Configuration>, Vec<Arc<Session>>)> {
+    let mut background_config = None;
+    let mut sessions = Vec::new();
+    let operation_factory = if args.clusters.is_empty() {
+        let session = Arc::new(connect(&args, &args.nodes).await?);
+        sessions.push(Arc::clone(&session));
+
+        create_schema(&session, &args).await?;
+        // The background writes share the partitions with the measured reads.
+        let partition_locks = create_partition_locks(&args);
+        let recent_keys = args
+            .read_recent_writes
+            .map(|count| Arc::new(RecentKeys::new(count as usize)));
+        if let (Some(rate), Some(background_stats)) = (args.background_write_rate, background_stats)
+        {
+            background_config = Some(
+                prepare_background_writes(
+                    Arc::clone(&session),
+                    background_stats,
+                    error_log.clone(),
+                    partition_locks.clone(),
+                    recent_keys.clone(),
+                    Arc::clone(&args),
+                    rate,
+                )
+                .await?,
+            );
+        }
+        // The reads of the recently written rows draw the keys from the background writes,
+        // and the resumed sequential workload continues from the saved progress.
+        let shared_workload: Option<Arc<dyn WorkloadFactory>> = match (recent_keys, progress) {
+            (Some(recent_keys), _) => Some(Arc::new(RecentKeysFactory::new(
+                recent_keys,
+                create_workload_factory(&args, &args.mode, None)?,
+            ))),
+            (None, Some(progress)) => Some(Arc::new(SequentialFactory::with_progress(
+                sequential_config(&args),
+                progress,
+            )?)),
+            (None, None) => None,
+        };
+        let stats = Arc::clone(&cluster_stats[0]);
+        create_operation_factory(
+            session,
+            stats,
+            error_log,
+            trace_log,
+            key_log,
+            partition_locks,
+            Arc::clone(&args),
+            shared_workload,
+        )
+        .await?
+    } else {
+        create_multi_cluster_operation_factory(
+            cluster_stats,
+            error_log,
+            trace_log,
+            &mut sessions,
+            Arc::clone(&args),
+        )
+        .await?
+    };
 
-    builder = builder.pool_size(PoolSize::PerShard(args.shard_connection_count));
+    // In the duplicate mode, each cluster gets its own set of workers
+    // performing the whole load.
+    let cluster_multiplier = match args.cluster_mode {
+        ClusterMode::Duplicate => args.clusters.len().max(1) as u64,
+        ClusterMode::Split => 1,
+    };
 
-    if !args.username.is_empty() && !args.password.is_empty() {
-        builder = builder.user(&args.username, &args.password);
-    }
+    let max_duration = (args.test_duration > Duration::ZERO).then_some(args.test_duration);
+    let max_operations = args.max_operations.map(|ops| ops * cluster_multiplier);
+    let rate_limit_per_second =
+        (args.maximum_rate > 0).then_some((args.maximum_rate * cluster_multiplier) as f64);
+    let max_in_flight = args
+        .max_in_flight
+        .map(|max| (max * cluster_multiplier) as usize);
+    // A scan operation issues many paged requests, each of them is
+    // already limited by the request timeout.
+    let operation_timeout =
+        (args.mode != Mode::Scan).then(|| configuration::operation_timeout(args.timeout));
+
+    let config = Configuration {
+        max_duration,
+        max_operations,
+        streams: vec![StreamConfiguration {
+            name: String::from("main"),
+            concurrency: args.concurrency * cluster_multiplier,
+            rate_limit_per_second,
+            rate_limit_mode: RateLimitMode::Schedule,
+            arrival: args.arrival,
+            operation_factory,
+        }],
+        max_retries_per_op: args.max_retries_per_op as usize,
+        operation_timeout,
+        retry_backoff: args.retry_backoff,
+        retry_budget: args.retry_budget.map(|budget| budget as f64),
+        max_in_flight,
+        runtimes: args.runtimes.map(|runtimes| runtimes as usize),
+        duty_cycle: args.duty_cycle,
+    };
+    Ok((config, background_config, sessions))
+}
 
-    if args.tls_encryption {
-        let ssl_ctx = generate_ssl_context(&args)?;
-        builder = builder.ssl_context(Some(ssl_ctx));
-    }
+async fn connect(args: &ScyllaBenchArgs, nodes: &[String]) -> Result<Session> {
+    let session = match &args.cloud_config {
+        Some(cloud_config) => {
+            // The connection bundle provides the endpoints, credentials and TLS settings.
+            let builder = CloudSessionBuilder::new(cloud_config)
+                .with_context(|| format!("Failed to load cloud config: {}", cloud_config))?;
+            configure_session(builder, args).build().await?
+        }
+        None => {
+            let mut builder = SessionBuilder::new().known_nodes(nodes);
 
-    if args.client_compression {
-        builder = builder.compression(Some(Compression::Snappy));
-    }
+            if !args.username.is_empty() && !args.password.is_empty() {
+                builder = builder.user(&args.username, &args.password);
+            }
 
-    let default_exec_profile = ExecutionProfile::builder()
-        .load_balancing_policy(Arc::clone(&args.host_selection_policy))
-        .build();
-    builder = builder.default_execution_profile_handle(default_exec_profile.into_handle());
+            #[cfg(feature = "auth-plugins")]
+            if let Some(authenticator) = &args.authenticator {
+                let spec = cql_stress::auth::AuthenticatorSpec::parse(authenticator)?;
+                builder = builder.authenticator_provider(spec.create()?);
+            }
 
-    let session = builder.build().await?;
-    let session = Arc::new(session);
+            if args.tls_encryption {
+                let ssl_ctx = generate_ssl_context(args)?;
+                builder = builder.ssl_context(Some(ssl_ctx));
+            }
 
-    create_schema(&session, &args).await?;
-    let operation_factory = create_operation_factory(session, stats, Arc::clone(&args)).await?;
+            configure_session(builder, args).build().await?
+        }
+    };
+    Ok(session)
+}
 
-    let max_duration = (args.test_duration > Duration::ZERO).then_some(args.test_duration);
-    let rate_limit_per_second = (args.maximum_rate > 0).then_some(args.maximum_rate as f64);
+// Connects to each of the clusters and prepares the operations for them.
+// In the split mode the clusters share the workload, so that each key
+// is used on only one of them.
+async fn create_multi_cluster_operation_factory(
+    cluster_stats: Vec<Arc<ShardedStats>>,
+    error_log: Option<Arc<ErrorLog>>,
+    trace_log: Option<Arc<TraceLog>>,
+    sessions: &mut Vec<Arc<Session>>,
+    args: Arc<ScyllaBenchArgs>,
+) -> Result<Arc<dyn OperationFactory>> {
+    let shared_workload: Option<Arc<dyn WorkloadFactory>> = match args.cluster_mode {
+        ClusterMode::Split => Some(create_workload_factory(&args, &args.mode, None)?.into()),
+        ClusterMode::Duplicate => None,
+    };
+
+    let mut factories = Vec::with_capacity(args.clusters.len());
+    for (cluster, stats) in args.clusters.iter().zip(cluster_stats) {
+        let session = connect(&args, &cluster.nodes)
+            .await
+            .with_context(|| format!("Failed to connect to cluster {}", cluster.name))?;
+        let session = Arc::new(session);
+        sessions.push(Arc::clone(&session));
+        create_schema(&session, &args)
+            .await
+            .with_context(|| format!("Failed to create the schema in cluster {}", cluster.name))?;
+        // The partitions of different clusters are independent.
+        let factory = create_operation_factory(
+            session,
+            stats,
+            error_log.clone(),
+            trace_log.clone(),
+            // Not supported together with clusters.
+            None,
+            create_partition_locks(&args),
+            Arc::clone(&args),
+            shared_workload.clone(),
+        )
+        .await?;
+        factories.push(factory);
+    }
+
+    Ok(Arc::new(MultiClusterOperationFactory::new(factories)))
+}
+
+// Prepares the writes performed at a fixed rate in the background of the
+// measured reads. They share the session, but are accounted separately.
+async fn prepare_background_writes(
+    session: Arc<Session>,
+    stats: Arc<ShardedStats>,
+    error_log: Option<Arc<ErrorLog>>,
+    partition_locks: Option<Arc<PartitionLocks>>,
+    recent_keys: Option<Arc<RecentKeys>>,
+    args: Arc<ScyllaBenchArgs>,
+    rate: u64,
+) -> Result<Configuration> {
+    let workload_factory = create_workload_factory(&args, &Mode::Write, None)?;
+    // Only the measured operations are traced.
+    let operation_factory = WriteOperationFactory::new(
+        session,
+        stats,
+        error_log,
+        None,
+        partition_locks,
+        workload_factory,
+        recent_keys,
+        None,
+        Arc::clone(&args),
+    )
+    .await?;
 
     Ok(Configuration {
-        max_duration,
-        concurrency: args.concurrency,
-        rate_limit_per_second,
-        operation_factory,
+        max_duration: (args.test_duration > Duration::ZERO).then_some(args.test_duration),
+        max_operations: None,
+        streams: vec![StreamConfiguration {
+            name: String::from("background writes"),
+            concurrency: args.concurrency,
+            rate_limit_per_second: Some(rate as f64),
+            rate_limit_mode: RateLimitMode::Schedule,
+            arrival: Arrival::Uniform,
+            operation_factory: Arc::new(operation_factory),
+        }],
         max_retries_per_op: args.max_retries_per_op as usize,
+        operation_timeout: Some(configuration::operation_timeout(args.timeout)),
+        retry_backoff: args.retry_backoff,
+        retry_budget: args.retry_budget.map(|budget| budget as f64),
+        max_in_flight: None,
+        runtimes: None,
+        // The background writes keep up with the foreground load.
+        duty_cycle: None,
     })
 }
 
+// Applies the settings shared by both regular and cloud sessions.
+fn configure_session<K: SessionBuilderKind>(
+    mut builder: GenericSessionBuilder<K>,
+    args: &ScyllaBenchArgs,
+) -> GenericSessionBuilder<K> {
+    builder = builder.pool_size(PoolSize::PerShard(args.shard_connection_count));
+
+    if args.client_compression {
+        builder = builder.compression(Some(Compression::Snappy));
+    }
+
+    let default_exec_profile = ExecutionProfile::builder()
+        .load_balancing_policy(Arc::clone(&args.host_selection_policy))
+        .retry_policy(args.retry_policy.create())
+        // Statements set the timeout on their own, but the batches
+        // can only inherit it from the execution profile.
+        .request_timeout(Some(args.timeout))
+        .build();
+    builder.default_execution_profile_handle(default_exec_profile.into_handle())
+}
+
 fn generate_ssl_context(args: &ScyllaBenchArgs) -> Result<SslContext> {
     let mut context_builder = SslContextBuilder::new(SslMethod::tls_client())?;
 
-    anyhow::ensure!(
-        args.client_key_file.is_empty() == args.client_cert_file.is_empty(),
-        "tls-client-cert-file and tls-client-key-file either should be both provided or left empty",
-    );
-
     if args.host_verification {
         context_builder.set_verify(SslVerifyMode::PEER);
     } else {
@@ -202,28 +682,42 @@ fn generate_ssl_context(args: &ScyllaBenchArgs) -> Result<SslContext> {
 }
 
 async fn create_schema(session: &Session, args: &ScyllaBenchArgs) -> Result<()> {
+    if args.no_schema {
+        return check_schema(session, args).await;
+    }
+
     let create_keyspace_query_str = format!(
-        "CREATE KEYSPACE IF NOT EXISTS {} WITH REPLICATION = \
-        {{'class': 'SimpleStrategy', 'replication_factor': {}}}",
-        args.keyspace_name, args.replication_factor,
+        "CREATE KEYSPACE IF NOT EXISTS {} WITH REPLICATION = {}",
+        args.keyspace_name,
+        args.replication.to_cql(),
     );
     session.query_unpaged(create_keyspace_query_str, ()).await?;
     session.use_keyspace(&args.keyspace_name, true).await?;
     session.await_schema_agreement().await?;
 
-    let create_regular_table_query_str = format!(
+    let mut create_regular_table_query_str = format!(
         "CREATE TABLE IF NOT EXISTS {} \
         (pk bigint, ck bigint, v blob, PRIMARY KEY (pk, ck)) \
         WITH compression = {{ }}",
         args.table_name,
     );
+    if let Some(twcs_window) = args.twcs_window {
+        create_regular_table_query_str += &format!(
+            " AND compaction = {{'class': 'TimeWindowCompactionStrategy', \
+            'compaction_window_unit': 'MINUTES', 'compaction_window_size': {}}}",
+            twcs_window.as_secs() / 60,
+        );
+    }
     let q1 = session.query_unpaged(create_regular_table_query_str, ());
 
+    let counter_columns = operation::counter_column_names(args.counter_columns)
+        .map(|c| format!("{} counter, ", c))
+        .collect::<String>();
     let create_counter_table_query_str = format!(
         "CREATE TABLE IF NOT EXISTS {} \
-        (pk bigint, ck bigint, c1 counter, c2 counter, c3 counter, c4 counter, c5 counter, PRIMARY KEY (pk, ck)) \
+        (pk bigint, ck bigint, {}PRIMARY KEY (pk, ck)) \
         WITH compression = {{ }}",
-        args.counter_table_name,
+        args.counter_table_name, counter_columns,
     );
     let q2 = session.query_unpaged(create_counter_table_query_str, ());
 
@@ -233,23 +727,105 @@ async fn create_schema(session: &Session, args: &ScyllaBenchArgs) -> Result<()>
     Ok(())
 }
 
+// Verifies that the schema created beforehand contains the table used by the mode.
+async fn check_schema(session: &Session, args: &ScyllaBenchArgs) -> Result<()> {
+    session
+        .use_keyspace(&args.keyspace_name, true)
+        .await
+        .with_context(|| {
+            format!(
+                "Failed to use keyspace {}, with -no-schema it must be created beforehand",
+                args.keyspace_name
+            )
+        })?;
+
+    let (table_name, value_columns) = match args.mode {
+        Mode::CounterUpdate | Mode::CounterRead => (
+            &args.counter_table_name,
+            operation::counter_column_names(args.counter_columns).collect(),
+        ),
+        _ => (&args.table_name, vec![String::from("v")]),
+    };
+    let cluster_data = session.get_cluster_data();
+    let table = cluster_data
+        .get_keyspace_info()
+        .get(&args.keyspace_name)
+        .and_then(|keyspace| keyspace.tables.get(table_name))
+        .with_context(|| {
+            format!(
+                "Table {}.{} doesn't exist, with -no-schema it must be created beforehand",
+                args.keyspace_name, table_name
+            )
+        })?;
+    for column in ["pk", "ck"]
+        .into_iter()
+        .map(String::from)
+        .chain(value_columns)
+    {
+        anyhow::ensure!(
+            table.columns.contains_key(&column),
+            "Table {}.{} doesn't have the {} column",
+            args.keyspace_name,
+            table_name,
+            column
+        );
+    }
+
+    Ok(())
+}
+
 async fn create_operation_factory(
     session: Arc<Session>,
     stats: Arc<ShardedStats>,
+    error_log: Option<Arc<ErrorLog>>,
+    trace_log: Option<Arc<TraceLog>>,
+    key_log: Option<Arc<KeyLog>>,
+    partition_locks: Option<Arc<PartitionLocks>>,
     args: Arc<ScyllaBenchArgs>,
+    shared_workload: Option<Arc<dyn WorkloadFactory>>,
 ) -> Result<Arc<dyn OperationFactory>> {
+    let tablet_layout = match &args.tablet {
+        Some(target) => {
+            let layout =
+                TabletLayout::fetch(&session, &args.keyspace_name, &args.table_name).await?;
+            layout.print_report(target);
+            Some(Arc::new(layout))
+        }
+        None => None,
+    };
+    let workload_factory =
+        |tablet_layout: Option<Arc<TabletLayout>>| -> Result<Box<dyn WorkloadFactory>> {
+            match &shared_workload {
+                Some(shared_workload) => Ok(Box::new(Arc::clone(shared_workload))),
+                None => create_workload_factory(&args, &args.mode, tablet_layout),
+            }
+        };
+
     match &args.mode {
         Mode::Write => {
-            let workload_factory = create_workload_factory(&args)?;
-            let factory =
-                WriteOperationFactory::new(session, stats, workload_factory, args).await?;
+            let workload_factory = workload_factory(tablet_layout)?;
+            let factory = WriteOperationFactory::new(
+                session,
+                stats,
+                error_log,
+                trace_log,
+                partition_locks,
+                workload_factory,
+                None,
+                key_log,
+                args,
+            )
+            .await?;
             Ok(Arc::new(factory))
         }
         Mode::Read => {
-            let workload_factory = create_workload_factory(&args)?;
+            let workload_factory = workload_factory(tablet_layout)?;
             let factory = ReadOperationFactory::new(
                 session,
                 stats,
+                error_log,
+                trace_log,
+                partition_locks,
                 ReadKind::Regular,
                 workload_factory,
                 args,
@@ -258,16 +834,27 @@ async fn create_operation_factory(
             Ok(Arc::new(factory))
         }
         Mode::CounterUpdate => {
-            let workload_factory = create_workload_factory(&args)?;
-            let factory =
-                CounterUpdateOperationFactory::new(session, stats, workload_factory, args).await?;
+            let workload_factory = workload_factory(None)?;
+            let factory = CounterUpdateOperationFactory::new(
+                session,
+                stats,
+                error_log,
+                trace_log,
+                partition_locks,
+                workload_factory,
+                args,
+            )
+            .await?;
             Ok(Arc::new(factory))
         }
         Mode::CounterRead => {
-            let workload_factory = create_workload_factory(&args)?;
+            let workload_factory = workload_factory(None)?;
             let factory = ReadOperationFactory::new(
                 session,
                 stats,
+                error_log,
+                trace_log,
+                partition_locks,
                 ReadKind::Counter,
                 workload_factory,
                 args,
@@ -276,62 +863,91 @@ async fn create_operation_factory(
             Ok(Arc::new(factory))
         }
         Mode::Scan => {
-            let factory = ScanOperationFactory::new(session, stats, args).await?;
+            let factory = ScanOperationFactory::new(session, stats, error_log, args).await?;
+            Ok(Arc::new(factory))
+        }
+        Mode::ReadModifyWrite => {
+            let workload_factory = workload_factory(None)?;
+            let factory = ReadModifyWriteOperationFactory::new(
+                session,
+                stats,
+                error_log,
+                partition_locks,
+                workload_factory,
+                args,
+            )
+            .await?;
             Ok(Arc::new(factory))
         }
     }
 }
 
-fn create_workload_factory(args: &ScyllaBenchArgs) -> Result<Box<dyn WorkloadFactory>> {
-    match (&args.workload, &args.mode) {
-        (WorkloadType::Sequential, _) => {
-            let seq_config = SequentialConfig {
-                iterations: args.iterations,
-                partition_offset: args.partition_offset,
-                pks: args.partition_count,
-                cks_per_pk: args.clustering_row_count,
-            };
-            Ok(Box::new(SequentialFactory::new(seq_config)?))
+// Creates the locks serializing the operations on the same partition,
+// if requested with -serialize-partitions.
+fn create_partition_locks(args: &ScyllaBenchArgs) -> Option<Arc<PartitionLocks>> {
+    args.serialize_partitions
+        .then(|| Arc::new(PartitionLocks::new(args.concurrency)))
+}
+
+// Creates the renderers of the operations performed by the benchmark,
+// which replace the operation factories in the dry-run mode.
+fn create_renderers(args: &Arc<ScyllaBenchArgs>) -> Result<Vec<Box<dyn OperationRenderer>>> {
+    let renderer: Box<dyn OperationRenderer> = match &args.mode {
+        Mode::Write => {
+            let workload_factory = create_workload_factory(args, &args.mode, None)?;
+            Box::new(WriteRenderer::new(
+                "write",
+                workload_factory,
+                Arc::clone(args),
+            ))
         }
-        (WorkloadType::Uniform, _) => {
-            let uni_config = UniformConfig {
-                pk_range: 0..args.partition_count,
-                ck_range: 0..args.clustering_row_count,
-            };
-            Ok(Box::new(UniformFactory::new(uni_config)?))
+        Mode::Read => {
+            let workload_factory = create_workload_factory(args, &args.mode, None)?;
+            Box::new(ReadRenderer::new(ReadKind::Regular, workload_factory, args))
         }
-        (WorkloadType::Timeseries, Mode::Write) => {
-            let tsw_config = TimeseriesWriteConfig {
-                _partition_offset: args.partition_offset,
-                pks_per_generation: args.partition_count,
-                cks_per_pk: args.clustering_row_count,
-                start_nanos: args.start_timestamp,
-                max_rate: args.maximum_rate,
-            };
-            Ok(Box::new(TimeseriesWriteFactory::new(tsw_config)?))
+        Mode::CounterUpdate => {
+            let workload_factory = create_workload_factory(args, &args.mode, None)?;
+            Box::new(CounterUpdateRenderer::new(workload_factory, args))
         }
-        (WorkloadType::Timeseries, Mode::Read) => {
-            let period = 1_000_000_000 / args.write_rate;
-            let tsr_config = TimeseriesReadConfig {
-                _partition_offset: args.partition_offset,
-                pks_per_generation: args.partition_count,
-                cks_per_pk: args.clustering_row_count,
-                start_nanos: args.start_timestamp,
-                period_nanos: period,
-                distribution: args.distribution.clone(),
-            };
-            Ok(Box::new(TimeseriesReadFactory::new(tsr_config)?))
+        Mode::CounterRead => {
+            let workload_factory = create_workload_factory(args, &args.mode, None)?;
+            Box::new(ReadRenderer::new(ReadKind::Counter, workload_factory, args))
         }
-        (WorkloadType::Timeseries, _) => Err(anyhow::anyhow!(
-            "Timeseries workload supports only write and read modes"
-        )),
-        (workload, mode) => {
-            // TODO: Implement more later
-            Err(anyhow::anyhow!(
-                "Unsupported combination of workload and mode: {:?}, {:?}",
-                workload,
-                mode,
+        Mode::Scan => Box::new(ScanRenderer::new(args)),
+        Mode::ReadModifyWrite => {
+            let workload_factory = create_workload_factory(args, &args.mode, None)?;
+            Box::new(ReadModifyWriteRenderer::new(
+                workload_factory,
+                Arc::clone(args),
             ))
         }
+    };
+
+    let mut renderers = vec![renderer];
+    if args.background_write_rate.is_some() {
+        let workload_factory = create_workload_factory(args, &Mode::Write, None)?;
+        renderers.push(Box::new(WriteRenderer::new(
+            "background write",
+            workload_factory,
+            Arc::clone(args),
+        )));
     }
+    Ok(renderers)
+}
+
+fn create_workload_factory(
+    args: &ScyllaBenchArgs,
+    mode: &Mode,
+    tablet_layout: Option<Arc<TabletLayout>>,
+) -> Result<Box<dyn WorkloadFactory>> {
+    if let Some(keys_file) = &args.keys_file {
+        let keys = KeyFile::open(keys_file, args.keys_file_loop)?;
+        return Ok(Box::new(KeysFileFactory::new(keys)));
+    }
+    let context = WorkloadContext {
+        args,
+        mode,
+        tablet_layout,
+    };
+    create_workload(show_workload(&args.workload), &context)
 }
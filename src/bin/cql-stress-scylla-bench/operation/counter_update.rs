@@ -1,66 +1,109 @@
 use std::ops::ControlFlow;
 use std::sync::Arc;
+use std::time::Instant;
 
 use anyhow::Result;
 use scylla::{prepared_statement::PreparedStatement, Session};
 use tracing::error;
+use uuid::Uuid;
 
 use cql_stress::configuration::{make_runnable, Operation, OperationContext, OperationFactory};
+use cql_stress::dry_run::{OperationRenderer, RenderedStatement};
+use cql_stress::error_log::ErrorLog;
+use cql_stress::partition_lock::PartitionLocks;
+use cql_stress::reprepare::{is_stale_statement_error, reprepare};
+use cql_stress::seed::SeedSequence;
+use cql_stress::trace_log::TraceLog;
 
 use crate::args::ScyllaBenchArgs;
-use crate::stats::ShardedStats;
+use crate::stats::{Sample, ShardedStats, StatsBuffer};
 use crate::workload::{Workload, WorkloadFactory};
 
 pub(crate) struct CounterUpdateOperationFactory {
     session: Arc<Session>,
     stats: Arc<ShardedStats>,
+    error_log: Option<Arc<ErrorLog>>,
+    trace_log: Option<Arc<TraceLog>>,
+    partition_locks: Option<Arc<PartitionLocks>>,
     statement: PreparedStatement,
     workload_factory: Box<dyn WorkloadFactory>,
+    seeds: SeedSequence,
+    updated_columns: u64,
 }
 
 struct CounterUpdateOperation {
     session: Arc<Session>,
-    stats: Arc<ShardedStats>,
+    stats: StatsBuffer,
+    error_log: Option<Arc<ErrorLog>>,
+    trace_log: Option<Arc<TraceLog>>,
+    partition_locks: Option<Arc<PartitionLocks>>,
     statement: PreparedStatement,
     workload: Box<dyn Workload>,
+    updated_columns: u64,
 }
 
 impl CounterUpdateOperationFactory {
     pub async fn new(
         session: Arc<Session>,
         stats: Arc<ShardedStats>,
+        error_log: Option<Arc<ErrorLog>>,
+        trace_log: Option<Arc<TraceLog>>,
+        partition_locks: Option<Arc<PartitionLocks>>,
         workload_factory: Box<dyn WorkloadFactory>,
         args: Arc<ScyllaBenchArgs>,
     ) -> Result<Self> {
-        let statement_str = format!(
-            "UPDATE {} SET c1 = c1 + ?, c2 = c2 + ?, c3 = c3 + ?, c4 = c4 + ?, c5 = c5 + ? \
-            WHERE pk = ? AND ck = ?",
-            args.counter_table_name,
-        );
-        let mut statement = session.prepare(statement_str).await?;
+        let mut statement = session.prepare(update_statement_str(&args)).await?;
         statement.set_consistency(args.consistency_level);
         statement.set_request_timeout(Some(args.timeout));
         Ok(Self {
             session,
             stats,
+            error_log,
+            trace_log,
+            partition_locks,
             statement,
             workload_factory,
+            seeds: SeedSequence::new(args.seed),
+            updated_columns: args.counter_columns_per_update,
         })
     }
 }
 
+fn update_statement_str(args: &ScyllaBenchArgs) -> String {
+    let assignments = super::counter_column_names(args.counter_columns_per_update)
+        .map(|c| format!("{c} = {c} + ?"))
+        .collect::<Vec<_>>()
+        .join(", ");
+    format!(
+        "UPDATE {} SET {} WHERE pk = ? AND ck = ?",
+        args.counter_table_name, assignments,
+    )
+}
+
+// The values bound to the update: the increments of the counters, pk and ck.
+pub(super) fn update_values(pk: i64, ck: i64, updated_columns: u64) -> Vec<i64> {
+    (1..=updated_columns as i64)
+        .map(|i| ck + i)
+        .chain([pk, ck])
+        .collect()
+}
+
 impl OperationFactory for CounterUpdateOperationFactory {
     fn create(&self) -> Box<dyn Operation> {
         Box::new(CounterUpdateOperation {
             session: Arc::clone(&self.session),
-            stats: Arc::clone(&self.stats),
+            stats: StatsBuffer::new(Arc::clone(&self.stats)),
+            error_log: self.error_log.clone(),
+            trace_log: self.trace_log.clone(),
+            partition_locks: self.partition_locks.clone(),
             statement: self.statement.clone(),
-            workload: self.workload_factory.create(),
+            workload: self.workload_factory.create(self.seeds.next_seed()),
+            updated_columns: self.updated_columns,
         })
     }
 }
 
-make_runnable!(CounterUpdateOperation);
+make_runnable!(CounterUpdateOperation, stats);
 impl CounterUpdateOperation {
     async fn execute(&mut self, ctx: &OperationContext) -> Result<ControlFlow<()>> {
         // Counter updates always use one key
@@ -69,7 +112,22 @@ impl CounterUpdateOperation {
             None => return Ok(ControlFlow::Break(())),
         };
 
-        let result = self.write_single(pk, cks[0]).await;
+        let _guard = super::lock_partition(self.partition_locks.as_deref(), pk).await;
+        self.statement
+            .set_tracing(super::is_traced(self.trace_log.as_deref(), ctx));
+        let start = Instant::now();
+        let mut result = self.write_single(pk, cks[0]).await;
+        if result.as_ref().is_err_and(is_stale_statement_error) {
+            result = match self.reprepare().await {
+                Ok(()) => self.write_single(pk, cks[0]).await,
+                Err(err) => Err(err),
+            };
+        }
+        let result = result.map(|tracing_id| {
+            if let (Some(trace_log), Some(tracing_id)) = (&self.trace_log, tracing_id) {
+                trace_log.record("counter update", pk, start.elapsed(), &[tracing_id]);
+            }
+        });
 
         if let Err(err) = result.as_ref() {
             error!(
@@ -78,10 +136,12 @@ impl CounterUpdateOperation {
                 clustering_keys = ?cks,
                 "write error",
             );
+            if let Some(error_log) = &self.error_log {
+                error_log.report("counter update", pk, err);
+            }
         }
 
-        let mut stats = self.stats.get_shard_mut();
-        stats.account_op(ctx, &result, cks.len());
+        self.stats.record(Sample::of_op(ctx, &result, cks.len()));
 
         result?;
         Ok(ControlFlow::Continue(()))
@@ -89,14 +149,52 @@ impl CounterUpdateOperation {
 }
 
 impl CounterUpdateOperation {
-    async fn write_single(&mut self, pk: i64, ck: i64) -> Result<()> {
+    // Called after the schema change invalidated the statement.
+    async fn reprepare(&mut self) -> Result<()> {
+        self.statement = reprepare(&self.session, &self.statement).await?;
+        self.stats.sharded().get_shard_mut().reprepares += 1;
+        Ok(())
+    }
+
+    // Returns the id of the tracing session if the update was traced.
+    async fn write_single(&mut self, pk: i64, ck: i64) -> Result<Option<Uuid>> {
         // execute_npaged, since it's an UPDATE statement.
-        self.session
-            .execute_unpaged(
-                &self.statement,
-                (ck + 1, ck + 2, ck + 3, ck + 4, ck + 5, pk, ck),
-            )
+        let result = self
+            .session
+            .execute_unpaged(&self.statement, update_values(pk, ck, self.updated_columns))
             .await?;
-        Ok(())
+        Ok(result.tracing_id)
+    }
+}
+
+/// Renders the counter updates performed by the first worker.
+pub(crate) struct CounterUpdateRenderer {
+    statement: String,
+    workload: Box<dyn Workload>,
+    updated_columns: u64,
+}
+
+impl CounterUpdateRenderer {
+    pub fn new(workload_factory: Box<dyn WorkloadFactory>, args: &ScyllaBenchArgs) -> Self {
+        Self {
+            statement: update_statement_str(args),
+            workload: workload_factory.create(SeedSequence::new(args.seed).next_seed()),
+            updated_columns: args.counter_columns_per_update,
+        }
+    }
+}
+
+impl OperationRenderer for CounterUpdateRenderer {
+    fn name(&self) -> &str {
+        "counter update"
+    }
+
+    fn render(&mut self) -> Option<Vec<RenderedStatement>> {
+        let (pk, cks) = self.workload.generate_keys(1)?;
+        let values = update_values(pk, cks[0], self.updated_columns)
+            .iter()
+            .map(|v| v.to_string())
+            .collect();
+        Some(vec![RenderedStatement::new(self.statement.clone(), values)])
     }
 }
@@ -1,29 +1,60 @@
 pub mod counter_update;
+pub mod multi_cluster;
 pub mod read;
+pub mod read_modify_write;
 pub mod scan;
 pub mod write;
 
 use std::fmt::Display;
 
 use anyhow::Result;
+use cql_stress::compressible::make_compressible;
+use cql_stress::configuration::OperationContext;
+use cql_stress::partition_lock::{PartitionGuard, PartitionLocks};
+use cql_stress::sharded_stats::{ErrorCounts, ErrorKind};
+use cql_stress::trace_log::TraceLog;
 use rand::RngCore;
 use sha2::{Digest, Sha256};
 use tracing::error;
 
+use crate::args::ScyllaBenchArgs;
+use crate::distribution::{Distribution, RngGen};
+
 const GENERATED_DATA_HEADER_SIZE: usize = 24;
 const GENERATED_DATA_MIN_SIZE: usize = GENERATED_DATA_HEADER_SIZE + 33;
 
-fn generate_row_data(pk: i64, ck: i64, size: usize) -> Vec<u8> {
+fn generate_row_data(
+    pk: i64,
+    ck: i64,
+    size: usize,
+    compressible: f64,
+    rng: &mut impl RngCore,
+) -> Vec<u8> {
+    let mut buf = Vec::new();
+    fill_row_data(pk, ck, size, compressible, rng, &mut buf);
+    buf
+}
+
+// Same as `generate_row_data`, but reuses the buffer `buf`.
+fn fill_row_data(
+    pk: i64,
+    ck: i64,
+    size: usize,
+    compressible: f64,
+    rng: &mut impl RngCore,
+    buf: &mut Vec<u8>,
+) {
+    buf.clear();
     if size == 0 {
-        Vec::new()
-    } else if size < GENERATED_DATA_HEADER_SIZE {
-        let mut buf = Vec::with_capacity(std::cmp::max(1 + 8, size));
+        return;
+    }
+    if size < GENERATED_DATA_HEADER_SIZE {
+        buf.reserve(std::cmp::max(1 + 8, size));
         buf.push(size as u8);
         buf.extend((pk ^ ck).to_le_bytes());
         buf.resize(size, 0u8);
-        buf
     } else {
-        let mut buf = Vec::with_capacity(std::cmp::max(GENERATED_DATA_MIN_SIZE, size));
+        buf.reserve(std::cmp::max(GENERATED_DATA_MIN_SIZE, size));
         buf.extend((size as u64).to_le_bytes());
         buf.extend(pk.to_le_bytes());
         buf.extend(ck.to_le_bytes());
@@ -35,7 +66,8 @@ fn generate_row_data(pk: i64, ck: i64, size: usize) -> Vec<u8> {
 
             // Generate random payload
             let payload = &mut buf[GENERATED_DATA_HEADER_SIZE..size - 32];
-            rand::thread_rng().fill_bytes(payload);
+            rng.fill_bytes(payload);
+            make_compressible(payload, compressible);
 
             // Hash it with SHA256
             let mut hasher = Sha256::new();
@@ -45,7 +77,73 @@ fn generate_row_data(pk: i64, ck: i64, size: usize) -> Vec<u8> {
             // Put the hash at the end
             buf.extend(&hash[..]);
         }
-        buf
+    }
+}
+
+// Generates the value of a single clustering row, with the size drawn from
+// `size_dist`. Only the values written with data validation enabled carry
+// the content checked by `validate_row_data`. The others consist of zeros,
+// unless the `compressible` fraction of their bytes is given (see `-compressible`).
+fn generate_value(
+    size_dist: &dyn Distribution,
+    validate_data: bool,
+    compressible: Option<f64>,
+    pk: i64,
+    ck: i64,
+    gen: &mut RngGen,
+) -> Vec<u8> {
+    let mut buf = Vec::new();
+    fill_value(
+        size_dist,
+        validate_data,
+        compressible,
+        pk,
+        ck,
+        gen,
+        &mut buf,
+    );
+    buf
+}
+
+// Same as `generate_value`, but reuses the buffer `buf`, so that
+// the operations don't allocate a new one for each of the rows.
+fn fill_value(
+    size_dist: &dyn Distribution,
+    validate_data: bool,
+    compressible: Option<f64>,
+    pk: i64,
+    ck: i64,
+    gen: &mut RngGen,
+    buf: &mut Vec<u8>,
+) {
+    let clen = size_dist.get_u64(gen) as usize;
+    if validate_data {
+        fill_row_data(pk, ck, clen, compressible.unwrap_or(0.0), gen, buf);
+    } else {
+        buf.clear();
+        buf.resize(clen, 0);
+        if let Some(compressible) = compressible {
+            gen.fill_bytes(buf);
+            make_compressible(buf, compressible);
+        }
+    }
+}
+
+// Number of leading bytes of a value shown in the dry-run output.
+const RENDERED_BLOB_PREFIX: usize = 16;
+
+// Renders a blob value for the dry-run output. Long values are shortened
+// to their first bytes, followed by the total size.
+fn render_blob(data: &[u8]) -> String {
+    let hex: String = data
+        .iter()
+        .take(RENDERED_BLOB_PREFIX)
+        .map(|b| format!("{:02x}", b))
+        .collect();
+    if data.len() > RENDERED_BLOB_PREFIX {
+        format!("0x{}... ({} bytes)", hex, data.len())
+    } else {
+        format!("0x{}", hex)
     }
 }
 
@@ -77,7 +175,8 @@ fn validate_row_data(pk: i64, ck: i64, data: &[u8]) -> Result<()> {
     // There is no random payload for sizes < GENERATED_DATA_MIN_SIZE
     if size < GENERATED_DATA_MIN_SIZE {
         // TODO: Probably we could the check without an allocation
-        let expected_data = generate_row_data(pk, ck, size);
+        // The random generator is not used for values without the payload.
+        let expected_data = generate_row_data(pk, ck, size, 0.0, &mut rand::thread_rng());
         anyhow::ensure!(
             original_data == expected_data,
             "Actual value doesn't match expected value; expected: {:?}, actual: {:?}",
@@ -120,62 +219,103 @@ fn validate_row_data(pk: i64, ck: i64, data: &[u8]) -> Result<()> {
     Ok(())
 }
 
-fn validate_counter_row_data(
-    pk: i64,
-    ck: i64,
-    c1: i64,
-    c2: i64,
-    c3: i64,
-    c4: i64,
-    c5: i64,
-) -> Result<()> {
-    let update_num = if ck == 0 { c2 } else { c1 / ck };
-    let ok = c1 != ck * update_num
-        || c2 != c1 + update_num
-        || c3 != c2 + update_num
-        || c4 != c3 + update_num
-        || c5 != c4 + update_num;
+/// Appends the clauses shared by all of the SELECT statements (`-bypass-cache`).
+fn with_select_clauses(mut statement_str: String, args: &ScyllaBenchArgs) -> String {
+    if args.bypass_cache {
+        statement_str += " BYPASS CACHE";
+    }
+    statement_str
+}
+
+/// Appends the USING clauses of the INSERT statement: the ones shared by all
+/// of the writes (`-write-timestamp`), followed by the statement specific ones.
+///
+/// Counter updates don't go through here, since the server doesn't accept
+/// custom timestamps for counter cells.
+fn with_using_clauses(
+    mut statement_str: String,
+    args: &ScyllaBenchArgs,
+    specific_clauses: impl IntoIterator<Item = String>,
+) -> String {
+    let using_clauses = args
+        .write_timestamp
+        .map(|write_timestamp| format!("TIMESTAMP {}", write_timestamp))
+        .into_iter()
+        .chain(specific_clauses)
+        .collect::<Vec<_>>();
+    if !using_clauses.is_empty() {
+        statement_str += &format!(" USING {}", using_clauses.join(" AND "));
+    }
+    statement_str
+}
+
+/// Names of the first `count` counter columns of the counter table: c1, c2, ...
+pub(crate) fn counter_column_names(count: u64) -> impl Iterator<Item = String> {
+    (1..=count).map(|i| format!("c{}", i))
+}
+
+// Each counter update increments the i-th column by ck + i (see counter_update.rs),
+// so after n updates the i-th column is equal to n * (ck + i).
+fn validate_counter_row_data(pk: i64, ck: i64, counters: &[i64]) -> Result<()> {
+    let update_num = counters.first().map_or(0, |c1| c1 / (ck + 1));
+    let ok = counters
+        .iter()
+        .zip(1..)
+        .all(|(c, i)| *c == update_num * (ck + i));
 
     anyhow::ensure!(
         ok,
-        "Corrupt counter data: invalid counter values, \
-        pk: {}, ck: {}, c1: {}, c2: {}, c3: {}, c4: {}, c5: {}",
+        "Corrupt counter data: invalid counter values, pk: {}, ck: {}, counters: {:?}",
         pk,
         ck,
-        c1,
-        c2,
-        c3,
-        c4,
-        c5,
+        counters,
     );
 
     Ok(())
 }
 
+// Locks the partition for the time of the operation,
+// if the operations on the same partition are serialized (see -serialize-partitions).
+async fn lock_partition(
+    partition_locks: Option<&PartitionLocks>,
+    pk: i64,
+) -> Option<PartitionGuard> {
+    match partition_locks {
+        Some(locks) => Some(locks.lock([pk]).await),
+        None => None,
+    }
+}
+
+// Tells whether the operation is sampled by the trace log (see -trace-file),
+// i.e. whether its statements are executed with tracing enabled.
+fn is_traced(trace_log: Option<&TraceLog>, ctx: &OperationContext) -> bool {
+    trace_log.is_some_and(|trace_log| trace_log.should_trace(ctx.operation_id))
+}
+
 #[derive(Default)]
 pub struct ReadContext {
-    pub errors: u64,
+    pub errors: ErrorCounts,
     pub rows_read: u64,
 }
 
 impl ReadContext {
-    pub fn failed_read(&mut self, err: &impl Display, pk: i64, cks: &[i64]) {
+    pub fn failed_read(&mut self, err: &anyhow::Error, pk: i64, cks: &[i64]) {
         error!(
             error = %err,
             partition_key = pk,
             clustering_keys = ?cks,
             "read error",
         );
-        self.errors += 1;
+        self.errors.record(err);
     }
-    pub fn failed_scan(&mut self, err: &impl Display, first: i64, last: i64) {
+    pub fn failed_scan(&mut self, err: &anyhow::Error, first: i64, last: i64) {
         error!(
             error = %err,
             first_token = first,
             last_token = last,
             "scan error",
         );
-        self.errors += 1;
+        self.errors.record(err);
     }
     pub fn data_corruption(&mut self, pk: i64, ck: i64, err: &impl Display) {
         eprintln!("data corruption in pk({}), ck({}): {}", pk, ck, err);
@@ -185,7 +325,16 @@ impl ReadContext {
             clustering_key = ck,
             "data corruption",
         );
-        self.errors += 1;
+        self.errors.record_kind(ErrorKind::Client);
+    }
+    pub fn data_loss(&mut self, pk: i64, err: &impl Display) {
+        eprintln!("data loss in pk({}): {}", pk, err);
+        error!(
+            error = %err,
+            partition_key = pk,
+            "data loss",
+        );
+        self.errors.record_kind(ErrorKind::DataLoss);
     }
     pub fn row_read(&mut self) {
         self.rows_read += 1;
@@ -196,12 +345,27 @@ impl ReadContext {
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_validate_counter_row_data() {
+        let (pk, ck) = (123, 4);
+        let update_values = counter_update::update_values(pk, ck, 3);
+        let counters = update_values[..3]
+            .iter()
+            .map(|increment| increment * 7)
+            .collect::<Vec<_>>();
+        validate_counter_row_data(pk, ck, &counters).unwrap();
+
+        let mut corrupted = counters.clone();
+        corrupted[2] += 1;
+        assert!(validate_counter_row_data(pk, ck, &corrupted).is_err());
+    }
+
     #[test]
     fn test_generate_validate_data() {
         let pk = 123;
         let ck = 456;
         for size in 1..=100 {
-            let mut data = generate_row_data(pk, ck, size);
+            let mut data = generate_row_data(pk, ck, size, 0.0, &mut rand::thread_rng());
             assert_eq!(data.len(), size);
 
             // Check that the data is valid
@@ -222,4 +386,64 @@ mod tests {
             }
         }
     }
+
+    #[test]
+    fn test_fill_reused_buffer() {
+        let (pk, ck) = (123, 456);
+        // The buffer is left with the longer value of the previous row.
+        let mut buf = vec![0xff; 200];
+        for size in (0..=100).rev() {
+            fill_row_data(pk, ck, size, 0.0, &mut rand::thread_rng(), &mut buf);
+            assert_eq!(buf.len(), size);
+            validate_row_data(pk, ck, &buf).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_compressible_row_data() {
+        let (pk, ck) = (123, 456);
+        let data = generate_row_data(pk, ck, 1000, 0.5, &mut rand::thread_rng());
+        validate_row_data(pk, ck, &data).unwrap();
+        // About a half of the payload (without the header and the hash) is zeroed.
+        let zeros = data.iter().filter(|b| **b == 0).count();
+        assert!((400..520).contains(&zeros), "{} zeros", zeros);
+    }
+
+    #[test]
+    fn test_render_blob() {
+        assert_eq!(render_blob(&[]), "0x");
+        assert_eq!(render_blob(&[0x01, 0xab]), "0x01ab");
+        assert_eq!(
+            render_blob(&[0xff; 20]),
+            "0xffffffffffffffffffffffffffffffff... (20 bytes)"
+        );
+    }
+
+    #[test]
+    fn test_statement_clauses() {
+        let parse = |args: &str| {
+            crate::args::parse_scylla_bench_args(args.split_ascii_whitespace(), false).unwrap()
+        };
+
+        let args = parse("scylla-bench -mode=scan -bypass-cache");
+        assert_eq!(
+            with_select_clauses("SELECT v FROM t".to_string(), &args),
+            "SELECT v FROM t BYPASS CACHE"
+        );
+        assert_eq!(
+            with_using_clauses("INSERT INTO t".to_string(), &args, []),
+            "INSERT INTO t"
+        );
+
+        let args =
+            parse("scylla-bench -workload=uniform -mode=write -write-timestamp=1700000000000000");
+        assert_eq!(
+            with_select_clauses("SELECT v FROM t".to_string(), &args),
+            "SELECT v FROM t"
+        );
+        assert_eq!(
+            with_using_clauses("INSERT INTO t".to_string(), &args, ["TTL 60".to_string()]),
+            "INSERT INTO t USING TIMESTAMP 1700000000000000 AND TTL 60"
+        );
+    }
 }
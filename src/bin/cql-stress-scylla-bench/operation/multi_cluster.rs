@@ -0,0 +1,29 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+use cql_stress::configuration::{Operation, OperationFactory};
+
+/// Hands out the workers to the operation factories of several clusters
+/// in a round robin fashion, so that each cluster gets an equal share
+/// of the workers.
+pub struct MultiClusterOperationFactory {
+    factories: Vec<Arc<dyn OperationFactory>>,
+    next: AtomicUsize,
+}
+
+impl MultiClusterOperationFactory {
+    pub fn new(factories: Vec<Arc<dyn OperationFactory>>) -> Self {
+        assert!(!factories.is_empty());
+        Self {
+            factories,
+            next: AtomicUsize::new(0),
+        }
+    }
+}
+
+impl OperationFactory for MultiClusterOperationFactory {
+    fn create(&self) -> Box<dyn Operation> {
+        let idx = self.next.fetch_add(1, Ordering::Relaxed) % self.factories.len();
+        self.factories[idx].create()
+    }
+}
@@ -1,17 +1,26 @@
 use std::ops::ControlFlow;
 use std::sync::Arc;
+use std::time::Instant;
 
 use anyhow::Result;
 use futures::{stream, StreamExt, TryStreamExt};
-use scylla::cql_to_rust::FromRow;
+use scylla::cql_to_rust::{FromCqlVal, FromRow};
 use scylla::frame::value::Counter;
 use scylla::{prepared_statement::PreparedStatement, Session};
+use uuid::Uuid;
 
 use cql_stress::configuration::{make_runnable, Operation, OperationContext, OperationFactory};
+use cql_stress::dry_run::{OperationRenderer, RenderedStatement};
+use cql_stress::error_log::ErrorLog;
+use cql_stress::partition_lock::PartitionLocks;
+use cql_stress::reprepare::{is_stale_statement_error, reprepare};
+use cql_stress::row_count::ExpectedRowCount;
+use cql_stress::seed::SeedSequence;
+use cql_stress::trace_log::TraceLog;
 
 use crate::args::{OrderBy, ScyllaBenchArgs};
 use crate::operation::ReadContext;
-use crate::stats::ShardedStats;
+use crate::stats::{Sample, ShardedStats, StatsBuffer};
 use crate::workload::{Workload, WorkloadFactory};
 
 #[derive(Copy, Clone)]
@@ -23,8 +32,12 @@ pub enum ReadKind {
 pub(crate) struct ReadOperationFactory {
     session: Arc<Session>,
     stats: Arc<ShardedStats>,
+    error_log: Option<Arc<ErrorLog>>,
+    trace_log: Option<Arc<TraceLog>>,
+    partition_locks: Option<Arc<PartitionLocks>>,
     statements: Vec<PreparedStatement>,
     workload_factory: Box<dyn WorkloadFactory>,
+    seeds: SeedSequence,
     read_kind: ReadKind,
     read_restriction: ReadRestrictionKind,
     args: Arc<ScyllaBenchArgs>,
@@ -32,12 +45,21 @@ pub(crate) struct ReadOperationFactory {
 
 struct ReadOperation {
     session: Arc<Session>,
-    stats: Arc<ShardedStats>,
+    stats: StatsBuffer,
+    error_log: Option<Arc<ErrorLog>>,
+    trace_log: Option<Arc<TraceLog>>,
+    partition_locks: Option<Arc<PartitionLocks>>,
     statements: Vec<PreparedStatement>,
     workload: Box<dyn Workload>,
     read_kind: ReadKind,
     read_restriction: ReadRestrictionKind,
     validate_data: bool,
+    validate_all_pages: bool,
+    expected_rows: Option<ExpectedRowCount>,
+    // The ordering of the rows returned by the corresponding statement.
+    orders: Vec<OrderBy>,
+    // The tracing sessions of the pages fetched by the traced read.
+    tracing_ids: Vec<Uuid>,
 
     current_statement_idx: usize,
 }
@@ -46,27 +68,14 @@ impl ReadOperationFactory {
     pub async fn new(
         session: Arc<Session>,
         stats: Arc<ShardedStats>,
+        error_log: Option<Arc<ErrorLog>>,
+        trace_log: Option<Arc<TraceLog>>,
+        partition_locks: Option<Arc<PartitionLocks>>,
         read_kind: ReadKind,
         workload_factory: Box<dyn WorkloadFactory>,
         args: Arc<ScyllaBenchArgs>,
     ) -> Result<Self> {
-        let read_restriction = if args.in_restriction {
-            ReadRestrictionKind::InRestriction {
-                cks_to_select: args.rows_per_request,
-            }
-        } else if args.provide_upper_bound {
-            ReadRestrictionKind::BothBounds {
-                cks_to_select: args.rows_per_request,
-            }
-        } else if args.no_lower_bound {
-            ReadRestrictionKind::NoBounds {
-                limit: args.rows_per_request,
-            }
-        } else {
-            ReadRestrictionKind::OnlyLowerBound {
-                limit: args.rows_per_request,
-            }
-        };
+        let read_restriction = ReadRestrictionKind::new(&args);
 
         let statements = stream::iter(&args.select_order_by)
             .then(|order_by| {
@@ -78,8 +87,12 @@ impl ReadOperationFactory {
         Ok(Self {
             session,
             stats,
+            error_log,
+            trace_log,
+            partition_locks,
             statements,
             workload_factory,
+            seeds: SeedSequence::new(args.seed),
             read_kind,
             read_restriction,
             args,
@@ -94,30 +107,43 @@ async fn prepare_statement(
     read_restriction: &ReadRestrictionKind,
     order_by: &OrderBy,
 ) -> Result<PreparedStatement> {
+    let statement_str = select_statement_str(args, read_kind, read_restriction, order_by);
+    let mut statement = session.prepare(statement_str).await?;
+    statement.set_is_idempotent(true);
+    statement.set_page_size(args.page_size);
+    statement.set_consistency(args.consistency_level);
+    statement.set_request_timeout(Some(args.timeout));
+
+    Ok(statement)
+}
+
+fn select_statement_str(
+    args: &ScyllaBenchArgs,
+    read_kind: ReadKind,
+    read_restriction: &ReadRestrictionKind,
+    order_by: &OrderBy,
+) -> String {
     let selector = read_restriction.get_selector_string();
     let order_by = get_order_by_string(order_by);
     let limit = read_restriction.get_limit_string();
 
-    let mut statement_str = match read_kind {
+    let statement_str = match read_kind {
         ReadKind::Regular => format!(
             "SELECT ck, v FROM {} WHERE pk = ? {} {} {}",
             args.table_name, selector, order_by, limit,
         ),
         ReadKind::Counter => format!(
-            "SELECT ck, c1, c2, c3, c4, c5 FROM {} WHERE pk = ? {} {} {}",
-            args.counter_table_name, selector, order_by, limit,
+            "SELECT ck, {} FROM {} WHERE pk = ? {} {} {}",
+            super::counter_column_names(args.counter_columns_per_update)
+                .collect::<Vec<_>>()
+                .join(", "),
+            args.counter_table_name,
+            selector,
+            order_by,
+            limit,
         ),
     };
-    if args.bypass_cache {
-        statement_str += " BYPASS CACHE";
-    }
-    let mut statement = session.prepare(statement_str).await?;
-    statement.set_is_idempotent(true);
-    statement.set_page_size(args.page_size.try_into()?);
-    statement.set_consistency(args.consistency_level);
-    statement.set_request_timeout(Some(args.timeout));
-
-    Ok(statement)
+    super::with_select_clauses(statement_str, args)
 }
 
 fn get_order_by_string(order: &OrderBy) -> &'static str {
@@ -132,19 +158,26 @@ impl OperationFactory for ReadOperationFactory {
     fn create(&self) -> Box<dyn Operation> {
         Box::new(ReadOperation {
             session: Arc::clone(&self.session),
-            stats: Arc::clone(&self.stats),
+            stats: StatsBuffer::new(Arc::clone(&self.stats)),
+            error_log: self.error_log.clone(),
+            trace_log: self.trace_log.clone(),
+            partition_locks: self.partition_locks.clone(),
             statements: self.statements.clone(),
-            workload: self.workload_factory.create(),
+            workload: self.workload_factory.create(self.seeds.next_seed()),
             read_kind: self.read_kind,
             read_restriction: self.read_restriction,
             validate_data: self.args.validate_data,
+            validate_all_pages: self.args.validate_all_pages,
+            expected_rows: self.args.expected_rows.clone(),
+            orders: self.args.select_order_by.clone(),
+            tracing_ids: Vec::new(),
 
             current_statement_idx: 0,
         })
     }
 }
 
-make_runnable!(ReadOperation);
+make_runnable!(ReadOperation, stats);
 impl ReadOperation {
     async fn execute(&mut self, ctx: &OperationContext) -> Result<ControlFlow<()>> {
         let mut rctx = ReadContext::default();
@@ -154,47 +187,95 @@ impl ReadOperation {
             None => return Ok(ControlFlow::Break(())),
         };
 
-        let mut values = Vec::with_capacity(cks.len() + 1);
-        values.push(pk);
-        for ck in cks.iter() {
-            values.push(*ck);
-        }
+        let values = || {
+            let mut values = Vec::with_capacity(cks.len() + 1);
+            values.push(pk);
+            for ck in cks.iter() {
+                values.push(*ck);
+            }
+            values
+        };
 
-        let stmt = self.statements[self.current_statement_idx].clone();
+        let stmt_idx = self.current_statement_idx;
         self.current_statement_idx = (self.current_statement_idx + 1) % self.statements.len();
 
-        let result = self.do_execute(&mut rctx, pk, stmt, values).await;
+        let _guard = super::lock_partition(self.partition_locks.as_deref(), pk).await;
+        let traced = super::is_traced(self.trace_log.as_deref(), ctx);
+        let start = Instant::now();
+        let mut result = self
+            .do_execute(&mut rctx, pk, stmt_idx, values(), traced)
+            .await;
+        if result.as_ref().is_err_and(is_stale_statement_error) {
+            result = match self.reprepare(stmt_idx).await {
+                Ok(()) => {
+                    self.do_execute(&mut rctx, pk, stmt_idx, values(), traced)
+                        .await
+                }
+                Err(err) => Err(err),
+            };
+        }
 
+        let operation = match self.read_kind {
+            ReadKind::Regular => "read",
+            ReadKind::Counter => "counter read",
+        };
         if let Err(err) = &result {
             rctx.failed_read(err, pk, &cks);
+            if let Some(error_log) = &self.error_log {
+                error_log.report(operation, pk, err);
+            }
+        }
+        if let (Some(trace_log), Ok(_)) = (&self.trace_log, &result) {
+            if !self.tracing_ids.is_empty() {
+                trace_log.record(operation, pk, start.elapsed(), &self.tracing_ids);
+                self.tracing_ids.clear();
+            }
         }
 
-        let mut stats_lock = self.stats.get_shard_mut();
-        let stats = &mut *stats_lock;
-        stats.operations += 1;
-        stats.errors += rctx.errors;
-        stats.clustering_rows += rctx.rows_read;
-        stats_lock.account_latency(ctx);
+        self.stats
+            .record(Sample::of_read(ctx, &rctx.errors, rctx.rows_read));
 
         result
     }
 }
 
 impl ReadOperation {
+    // Called after the schema change invalidated the statement.
+    async fn reprepare(&mut self, stmt_idx: usize) -> Result<()> {
+        self.statements[stmt_idx] = reprepare(&self.session, &self.statements[stmt_idx]).await?;
+        self.stats.sharded().get_shard_mut().reprepares += 1;
+        Ok(())
+    }
+
     async fn do_execute(
         &mut self,
         rctx: &mut ReadContext,
         pk: i64,
-        stmt: PreparedStatement,
+        stmt_idx: usize,
         values: Vec<i64>,
+        traced: bool,
     ) -> Result<ControlFlow<()>> {
+        let mut stmt = self.statements[stmt_idx].clone();
+        stmt.set_tracing(traced);
+        let order = self.orders[stmt_idx];
         let mut iter = self.session.execute_iter(stmt, values).await?;
+        let mut page_validator = self
+            .validate_all_pages
+            .then(|| PageValidator::new(order, self.read_restriction.max_rows()));
 
+        // The rows are fetched page by page, so only the current page is kept in memory.
+        let mut rows = 0;
         while let Some(row) = iter.try_next().await? {
             rctx.row_read();
+            rows += 1;
             match self.read_kind {
                 ReadKind::Regular => {
                     let (ck, v) = <(i64, Vec<u8>) as FromRow>::from_row(row)?;
+                    if let Some(page_validator) = &mut page_validator {
+                        if let Err(err) = page_validator.validate(ck) {
+                            rctx.data_corruption(pk, ck, &err);
+                        }
+                    }
                     if self.validate_data {
                         if let Err(err) = super::validate_row_data(pk, ck, &v) {
                             rctx.data_corruption(pk, ck, &err);
@@ -202,14 +283,18 @@ impl ReadOperation {
                     }
                 }
                 ReadKind::Counter => {
-                    let (ck, c1, c2, c3, c4, c5) =
-                        <(i64, Counter, Counter, Counter, Counter, Counter) as FromRow>::from_row(
-                            row,
-                        )?;
+                    let mut columns = row.columns.into_iter();
+                    let ck = i64::from_cql(columns.next().flatten())?;
+                    let counters = columns
+                        .map(|c| Counter::from_cql(c).map(|c| c.0))
+                        .collect::<Result<Vec<_>, _>>()?;
+                    if let Some(page_validator) = &mut page_validator {
+                        if let Err(err) = page_validator.validate(ck) {
+                            rctx.data_corruption(pk, ck, &err);
+                        }
+                    }
                     if self.validate_data {
-                        if let Err(err) =
-                            super::validate_counter_row_data(pk, ck, c1.0, c2.0, c3.0, c4.0, c5.0)
-                        {
+                        if let Err(err) = super::validate_counter_row_data(pk, ck, &counters) {
                             rctx.data_corruption(pk, ck, &err);
                         }
                     }
@@ -217,6 +302,15 @@ impl ReadOperation {
             }
         }
 
+        if let Some(expected_rows) = &self.expected_rows {
+            if let Err(err) = expected_rows.check(rows) {
+                rctx.data_loss(pk, &err);
+            }
+        }
+        if traced {
+            self.tracing_ids = iter.get_tracing_ids().to_vec();
+        }
+
         Ok(ControlFlow::Continue(()))
     }
 }
@@ -230,6 +324,26 @@ pub enum ReadRestrictionKind {
 }
 
 impl ReadRestrictionKind {
+    fn new(args: &ScyllaBenchArgs) -> Self {
+        if args.in_restriction {
+            ReadRestrictionKind::InRestriction {
+                cks_to_select: args.rows_per_request,
+            }
+        } else if args.provide_upper_bound {
+            ReadRestrictionKind::BothBounds {
+                cks_to_select: args.rows_per_request,
+            }
+        } else if args.no_lower_bound {
+            ReadRestrictionKind::NoBounds {
+                limit: args.rows_per_request,
+            }
+        } else {
+            ReadRestrictionKind::OnlyLowerBound {
+                limit: args.rows_per_request,
+            }
+        }
+    }
+
     fn get_selector_string(&self) -> String {
         match *self {
             ReadRestrictionKind::InRestriction { cks_to_select } => {
@@ -256,6 +370,17 @@ impl ReadRestrictionKind {
         }
     }
 
+    // The maximum number of rows the statement can return, if it is bounded.
+    fn max_rows(&self) -> Option<u64> {
+        match *self {
+            ReadRestrictionKind::InRestriction { cks_to_select: 0 } => None,
+            ReadRestrictionKind::InRestriction { cks_to_select }
+            | ReadRestrictionKind::BothBounds { cks_to_select } => Some(cks_to_select),
+            ReadRestrictionKind::OnlyLowerBound { limit }
+            | ReadRestrictionKind::NoBounds { limit } => Some(limit),
+        }
+    }
+
     fn generate_values(&self, workload: &mut dyn Workload) -> Option<(i64, Vec<i64>)> {
         match *self {
             ReadRestrictionKind::InRestriction { cks_to_select } => {
@@ -280,3 +405,134 @@ impl ReadRestrictionKind {
         }
     }
 }
+
+/// Renders the reads performed by the first worker, cycling through
+/// the statements of all the orderings like the operation does.
+pub(crate) struct ReadRenderer {
+    statements: Vec<String>,
+    workload: Box<dyn Workload>,
+    read_kind: ReadKind,
+    read_restriction: ReadRestrictionKind,
+
+    current_statement_idx: usize,
+}
+
+impl ReadRenderer {
+    pub fn new(
+        read_kind: ReadKind,
+        workload_factory: Box<dyn WorkloadFactory>,
+        args: &ScyllaBenchArgs,
+    ) -> Self {
+        let read_restriction = ReadRestrictionKind::new(args);
+        let statements = args
+            .select_order_by
+            .iter()
+            .map(|order_by| select_statement_str(args, read_kind, &read_restriction, order_by))
+            .collect();
+        Self {
+            statements,
+            workload: workload_factory.create(SeedSequence::new(args.seed).next_seed()),
+            read_kind,
+            read_restriction,
+
+            current_statement_idx: 0,
+        }
+    }
+}
+
+impl OperationRenderer for ReadRenderer {
+    fn name(&self) -> &str {
+        match self.read_kind {
+            ReadKind::Regular => "read",
+            ReadKind::Counter => "counter read",
+        }
+    }
+
+    fn render(&mut self) -> Option<Vec<RenderedStatement>> {
+        let (pk, cks) = self.read_restriction.generate_values(&mut *self.workload)?;
+
+        let stmt_idx = self.current_statement_idx;
+        self.current_statement_idx = (self.current_statement_idx + 1) % self.statements.len();
+
+        let values = std::iter::once(pk)
+            .chain(cks)
+            .map(|v| v.to_string())
+            .collect();
+        Some(vec![RenderedStatement::new(
+            self.statements[stmt_idx].clone(),
+            values,
+        )])
+    }
+}
+
+/// Validates the rows of a whole paged result, one by one, with a constant memory.
+///
+/// Checks that the clustering keys follow the order of the statement,
+/// i.e. no row is returned twice or out of order on the page boundaries,
+/// and that the result doesn't contain more rows than the statement requested.
+struct PageValidator {
+    descending: bool,
+    max_rows: Option<u64>,
+    rows: u64,
+    last_ck: Option<i64>,
+}
+
+impl PageValidator {
+    fn new(order: OrderBy, max_rows: Option<u64>) -> Self {
+        Self {
+            descending: order == OrderBy::Desc,
+            max_rows,
+            rows: 0,
+            last_ck: None,
+        }
+    }
+
+    fn validate(&mut self, ck: i64) -> Result<(), String> {
+        self.rows += 1;
+        if let Some(max_rows) = self.max_rows {
+            if self.rows > max_rows {
+                return Err(format!(
+                    "received more rows than requested: {} > {}",
+                    self.rows, max_rows,
+                ));
+            }
+        }
+
+        let last_ck = self.last_ck.replace(ck);
+        match last_ck {
+            Some(last_ck) if self.descending && ck >= last_ck => Err(format!(
+                "clustering key {} received after {} in descending order",
+                ck, last_ck,
+            )),
+            Some(last_ck) if !self.descending && ck <= last_ck => Err(format!(
+                "clustering key {} received after {} in ascending order",
+                ck, last_ck,
+            )),
+            _ => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_page_validator() {
+        let mut validator = PageValidator::new(OrderBy::None, Some(3));
+        assert!(validator.validate(1).is_ok());
+        assert!(validator.validate(5).is_ok());
+        assert!(validator.validate(5).is_err());
+        assert!(validator.validate(6).is_err());
+
+        let mut validator = PageValidator::new(OrderBy::Desc, None);
+        assert!(validator.validate(10).is_ok());
+        assert!(validator.validate(7).is_ok());
+        assert!(validator.validate(8).is_err());
+        assert!(validator.validate(3).is_ok());
+
+        let mut validator = PageValidator::new(OrderBy::Asc, Some(1));
+        assert!(validator.validate(0).is_ok());
+        assert!(validator.validate(1).is_err());
+    }
+}
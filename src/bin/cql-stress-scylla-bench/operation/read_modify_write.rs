@@ -0,0 +1,310 @@
+use std::ops::ControlFlow;
+use std::sync::Arc;
+
+use anyhow::{Context, Result};
+use rand::SeedableRng;
+use scylla::frame::response::result::CqlValue;
+use scylla::{prepared_statement::PreparedStatement, QueryResult, Session};
+use tracing::error;
+
+use cql_stress::configuration::{make_runnable, Operation, OperationContext, OperationFactory};
+use cql_stress::dry_run::{OperationRenderer, RenderedStatement};
+use cql_stress::error_log::ErrorLog;
+use cql_stress::partition_lock::PartitionLocks;
+use cql_stress::reprepare::{is_stale_statement_error, reprepare};
+use cql_stress::seed::{derive_seed, SeedSequence};
+
+use crate::args::ScyllaBenchArgs;
+use crate::distribution::{Distribution, RngGen};
+use crate::stats::{Sample, ShardedStats, StatsBuffer};
+use crate::workload::{Workload, WorkloadFactory};
+
+pub(crate) struct ReadModifyWriteOperationFactory {
+    session: Arc<Session>,
+    stats: Arc<ShardedStats>,
+    error_log: Option<Arc<ErrorLog>>,
+    partition_locks: Option<Arc<PartitionLocks>>,
+    statements: Statements,
+    workload_factory: Box<dyn WorkloadFactory>,
+    seeds: SeedSequence,
+    args: Arc<ScyllaBenchArgs>,
+}
+
+#[derive(Clone)]
+struct Statements {
+    select: PreparedStatement,
+    // INSERT ... IF NOT EXISTS with lightweight transactions.
+    insert: PreparedStatement,
+    // UPDATE ... IF v = ?, used only with lightweight transactions.
+    update: Option<PreparedStatement>,
+}
+
+struct ReadModifyWriteOperation {
+    session: Arc<Session>,
+    stats: StatsBuffer,
+    error_log: Option<Arc<ErrorLog>>,
+    partition_locks: Option<Arc<PartitionLocks>>,
+    statements: Statements,
+    workload: Box<dyn Workload>,
+    clustering_row_size_dist: Arc<dyn Distribution>,
+    validate_data: bool,
+    compressible: Option<f64>,
+
+    gen: RngGen,
+}
+
+impl ReadModifyWriteOperationFactory {
+    pub async fn new(
+        session: Arc<Session>,
+        stats: Arc<ShardedStats>,
+        error_log: Option<Arc<ErrorLog>>,
+        partition_locks: Option<Arc<PartitionLocks>>,
+        workload_factory: Box<dyn WorkloadFactory>,
+        args: Arc<ScyllaBenchArgs>,
+    ) -> Result<Self> {
+        let mut select = prepare_statement(&session, &args, select_statement_str(&args)).await?;
+        select.set_is_idempotent(true);
+
+        let insert = prepare_statement(&session, &args, insert_statement_str(&args)).await?;
+
+        let update = if args.lwt {
+            let update_str = format!(
+                "UPDATE {} SET v = ? WHERE pk = ? AND ck = ? IF v = ?",
+                args.table_name,
+            );
+            Some(prepare_statement(&session, &args, update_str).await?)
+        } else {
+            None
+        };
+
+        Ok(Self {
+            session,
+            stats,
+            error_log,
+            partition_locks,
+            statements: Statements {
+                select,
+                insert,
+                update,
+            },
+            workload_factory,
+            seeds: SeedSequence::new(args.seed),
+            args,
+        })
+    }
+}
+
+fn select_statement_str(args: &ScyllaBenchArgs) -> String {
+    let statement_str = format!("SELECT v FROM {} WHERE pk = ? AND ck = ?", args.table_name);
+    super::with_select_clauses(statement_str, args)
+}
+
+fn insert_statement_str(args: &ScyllaBenchArgs) -> String {
+    let mut insert_str = format!(
+        "INSERT INTO {} (pk, ck, v) VALUES (?, ?, ?)",
+        args.table_name,
+    );
+    if args.lwt {
+        insert_str += " IF NOT EXISTS";
+    }
+    super::with_using_clauses(insert_str, args, [])
+}
+
+async fn prepare_statement(
+    session: &Session,
+    args: &ScyllaBenchArgs,
+    statement_str: String,
+) -> Result<PreparedStatement> {
+    let mut statement = session.prepare(statement_str).await?;
+    statement.set_consistency(args.consistency_level);
+    statement.set_serial_consistency(Some(args.serial_consistency_level));
+    statement.set_request_timeout(Some(args.timeout));
+    Ok(statement)
+}
+
+impl OperationFactory for ReadModifyWriteOperationFactory {
+    fn create(&self) -> Box<dyn Operation> {
+        let seed = self.seeds.next_seed();
+        Box::new(ReadModifyWriteOperation {
+            session: Arc::clone(&self.session),
+            stats: StatsBuffer::new(Arc::clone(&self.stats)),
+            error_log: self.error_log.clone(),
+            partition_locks: self.partition_locks.clone(),
+            statements: self.statements.clone(),
+            workload: self.workload_factory.create(seed),
+            clustering_row_size_dist: Arc::clone(&self.args.clustering_row_size_dist),
+            validate_data: self.args.validate_data,
+            compressible: self.args.compressible,
+
+            gen: RngGen::seed_from_u64(derive_seed(seed, 1)),
+        })
+    }
+}
+
+make_runnable!(ReadModifyWriteOperation, stats);
+impl ReadModifyWriteOperation {
+    async fn execute(&mut self, ctx: &OperationContext) -> Result<ControlFlow<()>> {
+        // Each operation reads and writes back a single row
+        let (pk, cks) = match self.workload.generate_keys(1) {
+            Some((pk, cks)) => (pk, cks),
+            None => return Ok(ControlFlow::Break(())),
+        };
+
+        let _guard = super::lock_partition(self.partition_locks.as_deref(), pk).await;
+        let mut result = self.read_modify_write(pk, cks[0]).await;
+        if result.as_ref().is_err_and(is_stale_statement_error) {
+            result = match self.reprepare().await {
+                Ok(()) => self.read_modify_write(pk, cks[0]).await,
+                Err(err) => Err(err),
+            };
+        }
+
+        if let Err(err) = result.as_ref() {
+            error!(
+                error = %err,
+                partition_key = pk,
+                clustering_keys = ?cks,
+                "read-modify-write error",
+            );
+            if let Some(error_log) = &self.error_log {
+                error_log.report("read-modify-write", pk, err);
+            }
+        }
+
+        // The latency covers both the read and the write.
+        self.stats.record(Sample::of_op(ctx, &result, cks.len()));
+
+        result?;
+        Ok(ControlFlow::Continue(()))
+    }
+}
+
+impl ReadModifyWriteOperation {
+    // Called after the schema change invalidated the statements.
+    async fn reprepare(&mut self) -> Result<()> {
+        let statements = &mut self.statements;
+        statements.select = reprepare(&self.session, &statements.select).await?;
+        statements.insert = reprepare(&self.session, &statements.insert).await?;
+        if let Some(update) = &mut statements.update {
+            *update = reprepare(&self.session, update).await?;
+        }
+        self.stats.sharded().get_shard_mut().reprepares += 1;
+        Ok(())
+    }
+
+    async fn read_modify_write(&mut self, pk: i64, ck: i64) -> Result<()> {
+        let current = self
+            .session
+            .execute_unpaged(&self.statements.select, (pk, ck))
+            .await?
+            .maybe_first_row_typed::<(Option<Vec<u8>>,)>()?
+            .and_then(|(v,)| v);
+
+        if self.validate_data {
+            if let Some(current) = &current {
+                super::validate_row_data(pk, ck, current)
+                    .with_context(|| format!("data corruption in pk({}), ck({})", pk, ck))?;
+            }
+        }
+
+        let modified = self.generate_row(pk, ck);
+        let result = match (&self.statements.update, current) {
+            (Some(update), Some(current)) => {
+                self.session
+                    .execute_unpaged(update, (modified, pk, ck, current))
+                    .await?
+            }
+            // No lightweight transactions, or the row doesn't exist yet.
+            _ => {
+                self.session
+                    .execute_unpaged(&self.statements.insert, (pk, ck, modified))
+                    .await?
+            }
+        };
+
+        if self.statements.update.is_some() {
+            anyhow::ensure!(
+                was_applied(result)?,
+                "conditional write was not applied, the row was modified concurrently",
+            );
+        }
+        Ok(())
+    }
+
+    fn generate_row(&mut self, pk: i64, ck: i64) -> Vec<u8> {
+        super::generate_value(
+            self.clustering_row_size_dist.as_ref(),
+            self.validate_data,
+            self.compressible,
+            pk,
+            ck,
+            &mut self.gen,
+        )
+    }
+}
+
+/// Renders the read-modify-write operations performed by the first worker.
+/// The result of the read is not known without the cluster, so the row
+/// is rendered as if it didn't exist yet, i.e. it's written with the insert.
+pub(crate) struct ReadModifyWriteRenderer {
+    select: String,
+    insert: String,
+    workload: Box<dyn Workload>,
+    args: Arc<ScyllaBenchArgs>,
+
+    gen: RngGen,
+}
+
+impl ReadModifyWriteRenderer {
+    pub fn new(workload_factory: Box<dyn WorkloadFactory>, args: Arc<ScyllaBenchArgs>) -> Self {
+        let seed = SeedSequence::new(args.seed).next_seed();
+        Self {
+            select: select_statement_str(&args),
+            insert: insert_statement_str(&args),
+            workload: workload_factory.create(seed),
+            args,
+
+            gen: RngGen::seed_from_u64(derive_seed(seed, 1)),
+        }
+    }
+}
+
+impl OperationRenderer for ReadModifyWriteRenderer {
+    fn name(&self) -> &str {
+        "read-modify-write"
+    }
+
+    fn render(&mut self) -> Option<Vec<RenderedStatement>> {
+        let (pk, cks) = self.workload.generate_keys(1)?;
+        let ck = cks[0];
+        let modified = super::generate_value(
+            self.args.clustering_row_size_dist.as_ref(),
+            self.args.validate_data,
+            self.args.compressible,
+            pk,
+            ck,
+            &mut self.gen,
+        );
+        Some(vec![
+            RenderedStatement::new(self.select.clone(), vec![pk.to_string(), ck.to_string()]),
+            RenderedStatement::new(
+                self.insert.clone(),
+                vec![
+                    pk.to_string(),
+                    ck.to_string(),
+                    super::render_blob(&modified),
+                ],
+            ),
+        ])
+    }
+}
+
+// The first column of the result of a conditional statement is `[applied]`.
+fn was_applied(result: QueryResult) -> Result<bool> {
+    match result.first_row()?.columns.first() {
+        Some(Some(CqlValue::Boolean(applied))) => Ok(*applied),
+        _ => Err(anyhow::anyhow!(
+            "the result of a conditional statement has no [applied] column"
+        )),
+    }
+}
@@ -7,10 +7,13 @@ use futures::TryStreamExt;
 use scylla::{prepared_statement::PreparedStatement, Session};
 
 use cql_stress::configuration::{make_runnable, Operation, OperationContext, OperationFactory};
+use cql_stress::dry_run::{OperationRenderer, RenderedStatement};
+use cql_stress::error_log::ErrorLog;
+use cql_stress::reprepare::{is_stale_statement_error, reprepare};
 
 use crate::args::ScyllaBenchArgs;
 use crate::operation::ReadContext;
-use crate::stats::ShardedStats;
+use crate::stats::{Sample, ShardedStats, StatsBuffer};
 
 struct SharedState {
     pub next_range_idx: AtomicU64,
@@ -19,6 +22,7 @@ struct SharedState {
 pub(crate) struct ScanOperationFactory {
     session: Arc<Session>,
     stats: Arc<ShardedStats>,
+    error_log: Option<Arc<ErrorLog>>,
     statement: PreparedStatement,
     args: Arc<ScyllaBenchArgs>,
 
@@ -27,7 +31,8 @@ pub(crate) struct ScanOperationFactory {
 
 struct ScanOperation {
     session: Arc<Session>,
-    stats: Arc<ShardedStats>,
+    stats: StatsBuffer,
+    error_log: Option<Arc<ErrorLog>>,
     statement: PreparedStatement,
     args: Arc<ScyllaBenchArgs>,
 
@@ -38,13 +43,11 @@ impl ScanOperationFactory {
     pub async fn new(
         session: Arc<Session>,
         stats: Arc<ShardedStats>,
+        error_log: Option<Arc<ErrorLog>>,
         args: Arc<ScyllaBenchArgs>,
     ) -> Result<Self> {
-        let statement_str = format!(
-            "SELECT pk, ck, v FROM {} WHERE token(pk) >= ? AND token(pk) <= ?",
-            args.table_name,
-        );
-        let mut statement = session.prepare(statement_str).await?;
+        let mut statement = session.prepare(scan_statement_str(&args)).await?;
+        statement.set_page_size(args.page_size);
         statement.set_consistency(args.consistency_level);
         statement.set_request_timeout(Some(args.timeout));
 
@@ -55,6 +58,7 @@ impl ScanOperationFactory {
         Ok(Self {
             session,
             stats,
+            error_log,
             statement,
             args,
 
@@ -63,11 +67,31 @@ impl ScanOperationFactory {
     }
 }
 
+fn scan_statement_str(args: &ScyllaBenchArgs) -> String {
+    let statement_str = format!(
+        "SELECT pk, ck, v FROM {} WHERE token(pk) >= ? AND token(pk) <= ?",
+        args.table_name,
+    );
+    super::with_select_clauses(statement_str, args)
+}
+
+// Returns the first and the last token of the given range,
+// out of `range_count` ranges evenly splitting the token ring.
+fn token_range(range_idx: u64, range_count: u64) -> (i64, i64) {
+    let calc_bound = |idx: u64| {
+        let shifted = (idx as u128) << 64;
+        let biased = shifted / range_count as u128;
+        biased as i64 + i64::MIN
+    };
+    (calc_bound(range_idx), calc_bound(range_idx + 1))
+}
+
 impl OperationFactory for ScanOperationFactory {
     fn create(&self) -> Box<dyn Operation> {
         Box::new(ScanOperation {
             session: Arc::clone(&self.session),
-            stats: Arc::clone(&self.stats),
+            stats: StatsBuffer::new(Arc::clone(&self.stats)),
+            error_log: self.error_log.clone(),
             statement: self.statement.clone(),
             args: self.args.clone(),
 
@@ -76,7 +100,7 @@ impl OperationFactory for ScanOperationFactory {
     }
 }
 
-make_runnable!(ScanOperation);
+make_runnable!(ScanOperation, stats);
 impl ScanOperation {
     async fn execute(&mut self, ctx: &OperationContext) -> Result<ControlFlow<()>> {
         let mut rctx = ReadContext::default();
@@ -87,34 +111,38 @@ impl ScanOperation {
             .fetch_add(1, Ordering::Relaxed);
 
         let range_idx = range_idx % self.args.range_count;
-
-        let calc_bound = |idx: u64| {
-            let shifted = (idx as u128) << 64;
-            let biased = shifted / self.args.range_count as u128;
-            biased as i64 + i64::MIN
-        };
-
-        let range_begin = calc_bound(range_idx);
-        let range_end = calc_bound(range_idx + 1);
-
-        let result = self.do_execute(&mut rctx, range_begin, range_end).await;
+        let (range_begin, range_end) = token_range(range_idx, self.args.range_count);
+
+        let mut result = self.do_execute(&mut rctx, range_begin, range_end).await;
+        if result.as_ref().is_err_and(is_stale_statement_error) {
+            result = match self.reprepare().await {
+                Ok(()) => self.do_execute(&mut rctx, range_begin, range_end).await,
+                Err(err) => Err(err),
+            };
+        }
 
         if let Err(err) = &result {
             rctx.failed_scan(err, range_begin, range_end);
+            if let Some(error_log) = &self.error_log {
+                error_log.report("scan", range_begin..range_end, err);
+            }
         }
 
-        let mut stats_lock = self.stats.get_shard_mut();
-        let stats = &mut *stats_lock;
-        stats.operations += 1;
-        stats.errors += rctx.errors;
-        stats.clustering_rows += rctx.rows_read;
-        stats_lock.account_latency(ctx);
+        self.stats
+            .record(Sample::of_read(ctx, &rctx.errors, rctx.rows_read));
 
         result
     }
 }
 
 impl ScanOperation {
+    // Called after the schema change invalidated the statement.
+    async fn reprepare(&mut self) -> Result<()> {
+        self.statement = reprepare(&self.session, &self.statement).await?;
+        self.stats.sharded().get_shard_mut().reprepares += 1;
+        Ok(())
+    }
+
     async fn do_execute(
         &mut self,
         rctx: &mut ReadContext,
@@ -140,3 +168,49 @@ impl ScanOperation {
         Ok(ControlFlow::Continue(()))
     }
 }
+
+/// Renders the scans of the consecutive token ranges.
+pub(crate) struct ScanRenderer {
+    statement: String,
+    range_count: u64,
+
+    next_range_idx: u64,
+}
+
+impl ScanRenderer {
+    pub fn new(args: &ScyllaBenchArgs) -> Self {
+        Self {
+            statement: scan_statement_str(args),
+            range_count: args.range_count,
+
+            next_range_idx: 0,
+        }
+    }
+}
+
+impl OperationRenderer for ScanRenderer {
+    fn name(&self) -> &str {
+        "scan"
+    }
+
+    fn render(&mut self) -> Option<Vec<RenderedStatement>> {
+        let (first, last) = token_range(self.next_range_idx, self.range_count);
+        self.next_range_idx = (self.next_range_idx + 1) % self.range_count;
+        Some(vec![RenderedStatement::new(
+            self.statement.clone(),
+            vec![first.to_string(), last.to_string()],
+        )])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::token_range;
+
+    #[test]
+    fn test_token_range() {
+        assert_eq!(token_range(0, 4), (i64::MIN, i64::MIN / 2));
+        let (_, last) = token_range(0, 1_000);
+        assert_eq!(token_range(1, 1_000).0, last);
+    }
+}
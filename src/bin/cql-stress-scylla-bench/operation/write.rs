@@ -1,39 +1,67 @@
 use std::cmp::Ordering;
 use std::ops::ControlFlow;
 use std::sync::Arc;
+use std::time::Instant;
 
 use anyhow::Result;
-use rand::Rng;
+use rand::SeedableRng;
 use scylla::{
     batch::{Batch, BatchType},
     prepared_statement::PreparedStatement,
     Session,
 };
 use tracing::error;
+use uuid::Uuid;
 
 use cql_stress::configuration::{make_runnable, Operation, OperationContext, OperationFactory};
+use cql_stress::dry_run::{OperationRenderer, RenderedStatement};
+use cql_stress::error_log::ErrorLog;
+use cql_stress::key_log::KeyLog;
+use cql_stress::partition_lock::PartitionLocks;
+use cql_stress::reprepare::{is_stale_statement_error, reprepare};
+use cql_stress::seed::{derive_seed, SeedSequence};
+use cql_stress::trace_log::TraceLog;
 
 use crate::args::ScyllaBenchArgs;
 use crate::distribution::{Distribution, RngGen};
-use crate::stats::ShardedStats;
-use crate::workload::{Workload, WorkloadFactory};
+use crate::stats::{Sample, ShardedStats, StatsBuffer};
+use crate::workload::{RecentKeys, Workload, WorkloadFactory};
 
 pub(crate) struct WriteOperationFactory {
     session: Arc<Session>,
     stats: Arc<ShardedStats>,
+    error_log: Option<Arc<ErrorLog>>,
+    trace_log: Option<Arc<TraceLog>>,
+    partition_locks: Option<Arc<PartitionLocks>>,
     statement: PreparedStatement,
+    batch: Batch,
     workload_factory: Box<dyn WorkloadFactory>,
+    recent_keys: Option<Arc<RecentKeys>>,
+    key_log: Option<Arc<KeyLog>>,
+    seeds: SeedSequence,
     args: Arc<ScyllaBenchArgs>,
 }
 
 struct WriteOperation {
     session: Arc<Session>,
-    stats: Arc<ShardedStats>,
+    stats: StatsBuffer,
+    error_log: Option<Arc<ErrorLog>>,
+    trace_log: Option<Arc<TraceLog>>,
+    partition_locks: Option<Arc<PartitionLocks>>,
     statement: PreparedStatement,
+    // Batch of `rows_per_op` inserts, reused by all multi-row operations.
+    batch: Batch,
     workload: Box<dyn Workload>,
+    // The successfully written keys are recorded for the recent-writes reads.
+    recent_keys: Option<Arc<RecentKeys>>,
+    key_log: Option<Arc<KeyLog>>,
     clustering_row_size_dist: Arc<dyn Distribution>,
     rows_per_op: u64,
     validate_data: bool,
+    compressible: Option<f64>,
+    // The values of the rows of the current operation. The buffers
+    // are reused by the following operations.
+    values: Vec<Vec<u8>>,
 
     gen: RngGen,
 }
@@ -42,45 +70,92 @@ impl WriteOperationFactory {
     pub async fn new(
         session: Arc<Session>,
         stats: Arc<ShardedStats>,
+        error_log: Option<Arc<ErrorLog>>,
+        trace_log: Option<Arc<TraceLog>>,
+        partition_locks: Option<Arc<PartitionLocks>>,
         workload_factory: Box<dyn WorkloadFactory>,
+        recent_keys: Option<Arc<RecentKeys>>,
+        key_log: Option<Arc<KeyLog>>,
         args: Arc<ScyllaBenchArgs>,
     ) -> Result<Self> {
-        let statement_str = format!(
-            "INSERT INTO {} (pk, ck, v) VALUES (?, ?, ?)",
-            args.table_name,
-        );
-        let mut statement = session.prepare(statement_str).await?;
-        statement.set_is_idempotent(true);
+        let mut statement = session.prepare(insert_statement_str(&args)).await?;
+        // A retry of a conditional write would fail its condition
+        // if the first attempt was applied.
+        statement.set_is_idempotent(!args.lwt);
         statement.set_consistency(args.consistency_level);
+        statement.set_serial_consistency(Some(args.serial_consistency_level));
         statement.set_request_timeout(Some(args.timeout));
 
+        let mut batch = Batch::new(BatchType::Unlogged);
+        batch.set_is_idempotent(!args.lwt);
+        batch.set_consistency(args.consistency_level);
+        batch.set_serial_consistency(Some(args.serial_consistency_level));
+        for _ in 0..args.rows_per_request {
+            batch.append_statement(statement.clone());
+        }
+
         Ok(Self {
             session,
             stats,
+            error_log,
+            trace_log,
+            partition_locks,
             statement,
+            batch,
             workload_factory,
+            recent_keys,
+            key_log,
+            seeds: SeedSequence::new(args.seed),
             args,
         })
     }
 }
 
+fn insert_statement_str(args: &ScyllaBenchArgs) -> String {
+    let mut statement_str = format!(
+        "INSERT INTO {} (pk, ck, v) VALUES (?, ?, ?)",
+        args.table_name,
+    );
+    if args.lwt {
+        statement_str += " IF NOT EXISTS";
+    }
+    let mut using_clauses = Vec::new();
+    if let Some(ttl) = args.ttl {
+        using_clauses.push(format!("TTL {}", ttl.as_secs()));
+    }
+    if args.twcs_retention.is_some() {
+        // Bound per row, see `Workload::row_expiration`.
+        using_clauses.push("TIMESTAMP ? AND TTL ?".to_string());
+    }
+    super::with_using_clauses(statement_str, args, using_clauses)
+}
+
 impl OperationFactory for WriteOperationFactory {
     fn create(&self) -> Box<dyn Operation> {
+        let seed = self.seeds.next_seed();
         Box::new(WriteOperation {
             session: Arc::clone(&self.session),
-            stats: Arc::clone(&self.stats),
+            stats: StatsBuffer::new(Arc::clone(&self.stats)),
+            error_log: self.error_log.clone(),
+            trace_log: self.trace_log.clone(),
+            partition_locks: self.partition_locks.clone(),
             statement: self.statement.clone(),
-            workload: self.workload_factory.create(),
+            batch: self.batch.clone(),
+            workload: self.workload_factory.create(seed),
+            recent_keys: self.recent_keys.clone(),
+            key_log: self.key_log.clone(),
             clustering_row_size_dist: Arc::clone(&self.args.clustering_row_size_dist),
             rows_per_op: self.args.rows_per_request,
             validate_data: self.args.validate_data,
+            compressible: self.args.compressible,
+            values: Vec::new(),
 
-            gen: RngGen::new(rand::thread_rng().gen()),
+            gen: RngGen::seed_from_u64(derive_seed(seed, 1)),
         })
     }
 }
 
-make_runnable!(WriteOperation);
+make_runnable!(WriteOperation, stats);
 impl WriteOperation {
     async fn execute(&mut self, ctx: &OperationContext) -> Result<ControlFlow<()>> {
         let (pk, cks) = match self.workload.generate_keys(self.rows_per_op as usize) {
@@ -88,11 +163,24 @@ impl WriteOperation {
             None => return Ok(ControlFlow::Break(())),
         };
 
-        let result = match cks.len().cmp(&1) {
-            Ordering::Equal => self.write_single(pk, cks[0]).await,
-            Ordering::Greater => self.write_batch(pk, &cks).await,
-            Ordering::Less => Ok(()),
-        };
+        let _guard = super::lock_partition(self.partition_locks.as_deref(), pk).await;
+        let traced = super::is_traced(self.trace_log.as_deref(), ctx);
+        self.statement.set_tracing(traced);
+        self.batch.set_tracing(traced);
+        self.generate_values(pk, &cks);
+        let start = Instant::now();
+        let mut result = self.write(pk, &cks).await;
+        if result.as_ref().is_err_and(is_stale_statement_error) {
+            result = match self.reprepare().await {
+                Ok(()) => self.write(pk, &cks).await,
+                Err(err) => Err(err),
+            };
+        }
+        let result = result.map(|tracing_id| {
+            if let (Some(trace_log), Some(tracing_id)) = (&self.trace_log, tracing_id) {
+                trace_log.record("write", pk, start.elapsed(), &[tracing_id]);
+            }
+        });
 
         if let Err(err) = result.as_ref() {
             error!(
@@ -101,46 +189,170 @@ impl WriteOperation {
                 clustering_keys = ?cks,
                 "write error",
             );
+            if let Some(error_log) = &self.error_log {
+                error_log.report("write", pk, err);
+            }
+        }
+
+        if let (Ok(()), Some(recent_keys)) = (&result, &self.recent_keys) {
+            recent_keys.record(pk, &cks);
         }
 
-        let mut stats = self.stats.get_shard_mut();
-        stats.account_op(ctx, &result, cks.len());
+        self.stats.record(Sample::of_op(ctx, &result, cks.len()));
 
         result?;
+        // Recorded after the latency is measured, since it may wait for the writer.
+        if let Some(key_log) = &self.key_log {
+            key_log.record(pk, &cks).await;
+        }
         Ok(ControlFlow::Continue(()))
     }
 }
 
 impl WriteOperation {
-    async fn write_single(&mut self, pk: i64, ck: i64) -> Result<()> {
-        let data = self.generate_row(pk, ck);
-        // execute_unpaged, since it's an INSERT statement.
-        self.session
-            .execute_unpaged(&self.statement, (pk, ck, data))
-            .await?;
-        Ok(())
+    // Returns the id of the tracing session if the write was traced.
+    // The values are generated once, so that the write retried
+    // after the reprepare writes the same values.
+    async fn write(&self, pk: i64, cks: &[i64]) -> Result<Option<Uuid>> {
+        match cks.len().cmp(&1) {
+            Ordering::Equal => self.write_single(pk, cks[0]).await,
+            Ordering::Greater => self.write_batch(pk, cks).await,
+            Ordering::Less => Ok(None),
+        }
     }
 
-    async fn write_batch(&mut self, pk: i64, cks: &[i64]) -> Result<()> {
-        let mut batch = Batch::new(BatchType::Unlogged);
-        batch.set_is_idempotent(true);
-        batch.set_consistency(self.statement.get_consistency().unwrap());
-        let mut vals = Vec::with_capacity(cks.len());
-        for ck in cks {
-            let data = self.generate_row(pk, *ck);
-            batch.append_statement(self.statement.clone());
-            vals.push((pk, ck, data));
+    // Called after the schema change invalidated the statement.
+    async fn reprepare(&mut self) -> Result<()> {
+        self.statement = reprepare(&self.session, &self.statement).await?;
+        for statement in self.batch.statements.iter_mut() {
+            *statement = self.statement.clone().into();
         }
-        self.session.batch(&batch, vals).await?;
+        self.stats.sharded().get_shard_mut().reprepares += 1;
         Ok(())
     }
 
-    fn generate_row(&mut self, pk: i64, ck: i64) -> Vec<u8> {
-        let clen = self.clustering_row_size_dist.get_u64(&mut self.gen) as usize;
-        if self.validate_data {
-            super::generate_row_data(pk, ck, clen)
+    async fn write_single(&self, pk: i64, ck: i64) -> Result<Option<Uuid>> {
+        let data = self.values[0].as_slice();
+        // execute_unpaged, since it's an INSERT statement.
+        let result = match self.workload.row_expiration(pk, ck) {
+            Some(expiration) => {
+                let values = (
+                    pk,
+                    ck,
+                    data,
+                    expiration.timestamp_micros,
+                    expiration.ttl_secs,
+                );
+                self.session
+                    .execute_unpaged(&self.statement, values)
+                    .await?
+            }
+            None => {
+                self.session
+                    .execute_unpaged(&self.statement, (pk, ck, data))
+                    .await?
+            }
+        };
+        Ok(result.tracing_id)
+    }
+
+    async fn write_batch(&self, pk: i64, cks: &[i64]) -> Result<Option<Uuid>> {
+        let vals = cks
+            .iter()
+            .zip(&self.values)
+            .map(|(ck, data)| (pk, *ck, data.as_slice()))
+            .collect::<Vec<_>>();
+
+        // The workload may generate a different number of rows than requested,
+        // e.g. less for the last operation on a partition in the sequential workload,
+        // or a random number in the wide partition workload.
+        let result = if cks.len() == self.batch.statements.len() {
+            self.session.batch(&self.batch, vals).await?
         } else {
-            vec![0; clen]
+            let mut batch = self.batch.clone();
+            batch
+                .statements
+                .resize(cks.len(), self.statement.clone().into());
+            self.session.batch(&batch, vals).await?
+        };
+        Ok(result.tracing_id)
+    }
+
+    // Generates the values of the rows `cks` into `self.values`.
+    fn generate_values(&mut self, pk: i64, cks: &[i64]) {
+        if self.values.len() < cks.len() {
+            self.values.resize_with(cks.len(), Vec::new);
+        }
+        for (ck, buf) in cks.iter().zip(self.values.iter_mut()) {
+            super::fill_value(
+                self.clustering_row_size_dist.as_ref(),
+                self.validate_data,
+                self.compressible,
+                pk,
+                *ck,
+                &mut self.gen,
+                buf,
+            );
+        }
+    }
+}
+
+/// Renders the inserts of the write operations performed by the first worker.
+pub(crate) struct WriteRenderer {
+    name: &'static str,
+    statement: String,
+    workload: Box<dyn Workload>,
+    args: Arc<ScyllaBenchArgs>,
+
+    gen: RngGen,
+}
+
+impl WriteRenderer {
+    pub fn new(
+        name: &'static str,
+        workload_factory: Box<dyn WorkloadFactory>,
+        args: Arc<ScyllaBenchArgs>,
+    ) -> Self {
+        let seed = SeedSequence::new(args.seed).next_seed();
+        Self {
+            name,
+            statement: insert_statement_str(&args),
+            workload: workload_factory.create(seed),
+            args,
+
+            gen: RngGen::seed_from_u64(derive_seed(seed, 1)),
+        }
+    }
+}
+
+impl OperationRenderer for WriteRenderer {
+    fn name(&self) -> &str {
+        self.name
+    }
+
+    fn render(&mut self) -> Option<Vec<RenderedStatement>> {
+        let (pk, cks) = self
+            .workload
+            .generate_keys(self.args.rows_per_request as usize)?;
+
+        // Multiple rows are written in a single batch, one statement per row.
+        let mut statements = Vec::with_capacity(cks.len());
+        for ck in cks {
+            let data = super::generate_value(
+                self.args.clustering_row_size_dist.as_ref(),
+                self.args.validate_data,
+                self.args.compressible,
+                pk,
+                ck,
+                &mut self.gen,
+            );
+            let mut values = vec![pk.to_string(), ck.to_string(), super::render_blob(&data)];
+            if let Some(expiration) = self.workload.row_expiration(pk, ck) {
+                values.push(expiration.timestamp_micros.to_string());
+                values.push(expiration.ttl_secs.to_string());
+            }
+            statements.push(RenderedStatement::new(self.statement.clone(), values));
         }
+        Some(statements)
     }
 }
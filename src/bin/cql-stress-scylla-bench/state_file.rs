@@ -0,0 +1,155 @@
+//! Persisting the progress of the sequential workload, so that an interrupted
+//! run (e.g. a population job) can be resumed with the same `-state-file`
+//! instead of starting from scratch.
+
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use anyhow::{Context, Result};
+use tokio::io::AsyncWriteExt;
+
+use crate::args::ScyllaBenchArgs;
+use crate::workload::SequentialProgress;
+
+const NEXT_PARTITION: &str = "next-partition";
+
+pub(crate) struct StateFile {
+    path: PathBuf,
+    // The parameters of the workload the state belongs to.
+    workload: Vec<(&'static str, String)>,
+    progress: Arc<SequentialProgress>,
+    resumed_from: Option<u64>,
+}
+
+impl StateFile {
+    /// Resumes the progress saved in the file, or starts from the beginning
+    /// if it doesn't exist yet. The saved state must belong to the same workload.
+    pub fn open(path: impl AsRef<Path>, args: &ScyllaBenchArgs) -> Result<Self> {
+        let path = path.as_ref();
+        let workload = vec![
+            ("partition-count", args.partition_count.to_string()),
+            (
+                "clustering-row-count",
+                args.clustering_row_count.to_string(),
+            ),
+            ("partition-offset", args.partition_offset.to_string()),
+            ("iterations", args.iterations.to_string()),
+            ("no-wrap", args.no_wrap.to_string()),
+            (
+                "split",
+                args.split
+                    .map_or("1/1".to_string(), |split| split.to_string()),
+            ),
+        ];
+
+        let resumed_from = match std::fs::read_to_string(path) {
+            Ok(contents) => Some(
+                parse_state(&contents, &workload)
+                    .with_context(|| format!("Invalid state file {}", path.display()))?,
+            ),
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => None,
+            Err(err) => {
+                return Err(err)
+                    .with_context(|| format!("Failed to read the state file {}", path.display()))
+            }
+        };
+
+        Ok(Self {
+            path: path.to_owned(),
+            workload,
+            progress: Arc::new(SequentialProgress::new(resumed_from.unwrap_or(0))),
+            resumed_from,
+        })
+    }
+
+    /// The progress to be shared with the workload.
+    pub fn progress(&self) -> Arc<SequentialProgress> {
+        Arc::clone(&self.progress)
+    }
+
+    /// The index of the partition the run resumes from, if the state was saved before.
+    pub fn resumed_from(&self) -> Option<u64> {
+        self.resumed_from
+    }
+
+    /// Saves the current progress. The file is replaced atomically,
+    /// so it's never left incomplete, even if the tool is killed while saving.
+    pub async fn save(&self) -> Result<()> {
+        let mut contents = String::from("# The progress of the scylla-bench sequential workload\n");
+        for (key, value) in &self.workload {
+            contents += &format!("{}={}\n", key, value);
+        }
+        contents += &format!("{}={}\n", NEXT_PARTITION, self.progress.checkpoint());
+
+        let mut tmp_path = self.path.clone().into_os_string();
+        tmp_path.push(".tmp");
+        let mut file = tokio::fs::File::create(&tmp_path).await?;
+        file.write_all(contents.as_bytes()).await?;
+        file.sync_all().await?;
+        tokio::fs::rename(&tmp_path, &self.path).await?;
+
+        Ok(())
+    }
+}
+
+// Returns the index of the partition to resume from.
+fn parse_state(contents: &str, workload: &[(&'static str, String)]) -> Result<u64> {
+    let entries = contents
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(|line| {
+            line.split_once('=')
+                .with_context(|| format!("invalid line: {}", line))
+        })
+        .collect::<Result<Vec<_>>>()?;
+    let get = |key: &str| -> Result<&str> {
+        match entries.iter().find(|(k, _)| *k == key) {
+            Some((_, value)) => Ok(*value),
+            None => anyhow::bail!("missing {}", key),
+        }
+    };
+
+    for (key, value) in workload {
+        let saved = get(key)?;
+        anyhow::ensure!(
+            saved == value.as_str(),
+            "the state was saved for {}={}, but the current run has {}",
+            key,
+            saved,
+            value,
+        );
+    }
+    let next_partition = get(NEXT_PARTITION)?;
+    next_partition
+        .parse()
+        .with_context(|| format!("invalid {}: {}", NEXT_PARTITION, next_partition))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::parse_state;
+
+    #[test]
+    fn test_parse_state() {
+        let workload = vec![
+            ("partition-count", "100".to_string()),
+            ("iterations", "1".to_string()),
+        ];
+
+        let state = "# comment\npartition-count=100\niterations=1\nnext-partition=42\n";
+        assert_eq!(parse_state(state, &workload).unwrap(), 42);
+
+        let bad_states = [
+            // A different workload.
+            "partition-count=200\niterations=1\nnext-partition=42\n",
+            "partition-count=100\nnext-partition=42\n",
+            "partition-count=100\niterations=1\n",
+            "partition-count=100\niterations=1\nnext-partition=x\n",
+            "partition-count=100\niterations=1\nnext-partition\n",
+        ];
+        for state in bad_states {
+            assert!(parse_state(state, &workload).is_err());
+        }
+    }
+}
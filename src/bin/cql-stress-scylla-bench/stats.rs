@@ -8,14 +8,21 @@ use hdrhistogram::Histogram;
 use tokio::fs::File;
 use tokio::time::Instant;
 
+use cql_stress::client_stats::ClientStats;
+use cql_stress::clock::ClockBase;
 use cql_stress::configuration::OperationContext;
-use cql_stress::sharded_stats;
+use cql_stress::driver_metrics::DriverMetrics;
+use cql_stress::latency_heatmap::LatencyHeatmapWriter;
+use cql_stress::run_metadata::RunMetadata;
+use cql_stress::sharded_stats::{self, ErrorCounts, ErrorKind, OperationTiming};
+use cql_stress::stats_report::StatsSnapshot;
 
 use crate::args::ScyllaBenchArgs;
 use crate::gocompat::strconv::format_duration;
 use crate::histogram_log_writer::{HistogramLogOptions, HistogramLogWriter};
 
 pub type ShardedStats = sharded_stats::ShardedStats<StatsFactory>;
+pub type StatsBuffer = sharded_stats::StatsBuffer<StatsFactory>;
 
 pub struct StatsFactory {
     measure_latency: bool,
@@ -45,6 +52,9 @@ impl sharded_stats::StatsFactory for StatsFactory {
             operations: 0,
             clustering_rows: 0,
             errors: 0,
+            error_counts: ErrorCounts::default(),
+            reprepares: 0,
+            client: ClientStats::default(),
             latencies: self.measure_latency.then(|| LatencyHistograms {
                 raw: self.create_histogram(),
                 co_fixed: self.create_histogram(),
@@ -59,6 +69,11 @@ pub struct Stats {
     pub operations: u64,
     pub clustering_rows: u64,
     pub errors: u64,
+    pub error_counts: ErrorCounts,
+    // Statements prepared again after a schema change
+    pub reprepares: u64,
+    // The queue delay and the operations in flight (see -client-metrics)
+    pub client: ClientStats,
 
     pub latencies: Option<LatencyHistograms>,
 
@@ -77,6 +92,9 @@ impl sharded_stats::Stats for Stats {
         self.operations = 0;
         self.clustering_rows = 0;
         self.errors = 0;
+        self.error_counts.clear();
+        self.reprepares = 0;
+        self.client.clear();
         if let Some(ls) = &mut self.latencies {
             ls.raw.reset();
             ls.co_fixed.reset();
@@ -87,6 +105,9 @@ impl sharded_stats::Stats for Stats {
         self.operations += other.operations;
         self.clustering_rows += other.clustering_rows;
         self.errors += other.errors;
+        self.error_counts.combine(&other.error_counts);
+        self.reprepares += other.reprepares;
+        self.client.combine(&other.client);
         if let (Some(ls1), Some(ls2)) = (&mut self.latencies, &other.latencies) {
             ls1.raw.add(&ls2.raw).unwrap();
             ls1.co_fixed.add(&ls2.co_fixed).unwrap();
@@ -94,29 +115,67 @@ impl sharded_stats::Stats for Stats {
     }
 }
 
-impl Stats {
-    pub fn account_op(&mut self, ctx: &OperationContext, result: &Result<()>, rows: usize) {
+/// The outcome of a single operation, buffered before it's recorded to [Stats].
+pub struct Sample {
+    timing: OperationTiming,
+    errors: ErrorCounts,
+    clustering_rows: u64,
+    // Whether the latency of the operation is recorded.
+    record_latency: bool,
+}
+
+impl Sample {
+    /// The sample of an operation on `rows` rows, which fails as a whole.
+    /// Only the latencies of the successful ones are recorded.
+    pub fn of_op(ctx: &OperationContext, result: &Result<()>, rows: usize) -> Self {
+        let mut errors = ErrorCounts::default();
+        if let Err(err) = result {
+            errors.record(err);
+        }
+        Self {
+            timing: OperationTiming::ended_now(ctx),
+            errors,
+            clustering_rows: if result.is_ok() { rows as u64 } else { 0 },
+            record_latency: result.is_ok(),
+        }
+    }
+
+    /// The sample of a read or a scan, which counts the errors of its requests
+    /// along with the rows read by the other ones. Its latency is always recorded.
+    pub fn of_read(ctx: &OperationContext, errors: &ErrorCounts, rows_read: u64) -> Self {
+        Self {
+            timing: OperationTiming::ended_now(ctx),
+            errors: errors.clone(),
+            clustering_rows: rows_read,
+            record_latency: true,
+        }
+    }
+}
+
+impl sharded_stats::RecordSample for Stats {
+    type Sample = Sample;
+
+    fn record_sample(&mut self, sample: &Sample) {
         self.operations += 1;
-        match result {
-            Ok(()) => {
-                self.clustering_rows += rows as u64;
-                self.account_latency(ctx);
-            }
-            Err(_) => {
-                self.errors += 1;
-            }
+        self.errors += sample.errors.total();
+        self.error_counts.combine(&sample.errors);
+        self.clustering_rows += sample.clustering_rows;
+        self.client.record(&sample.timing);
+        if sample.record_latency {
+            self.account_latency(&sample.timing);
         }
     }
+}
 
-    pub fn account_latency(&mut self, ctx: &OperationContext) {
+impl Stats {
+    fn account_latency(&mut self, timing: &OperationTiming) {
         if let Some(ls) = &mut self.latencies {
-            let now = Instant::now();
             let _ = ls
                 .raw
-                .record((now - ctx.actual_start_time).as_nanos() as u64 / self.latency_resolution);
-            let _ = ls.co_fixed.record(
-                (now - ctx.scheduled_start_time).as_nanos() as u64 / self.latency_resolution,
-            );
+                .record(timing.raw_latency().as_nanos() as u64 / self.latency_resolution);
+            let _ = ls
+                .co_fixed
+                .record(timing.co_fixed_latency().as_nanos() as u64 / self.latency_resolution);
         }
     }
 
@@ -128,6 +187,18 @@ impl Stats {
         };
         Some(histogram)
     }
+
+    /// Returns the metrics of the interval reported to the -report server.
+    pub fn report_snapshot(&self, latency_type: Option<LatencyType>) -> StatsSnapshot {
+        let mut snapshot = StatsSnapshot::new(SystemTime::now());
+        snapshot.add("operations", self.operations as f64);
+        snapshot.add("rows", self.clustering_rows as f64);
+        snapshot.add("errors", self.errors as f64);
+        if let Some(histogram) = latency_type.and_then(|typ| self.get_histogram(typ)) {
+            snapshot.add_latencies("latency", histogram, self.latency_resolution);
+        }
+        snapshot
+    }
 }
 
 #[derive(Clone, Copy)]
@@ -140,33 +211,45 @@ type HistogramWriter = HistogramLogWriter<File>;
 
 // TODO: Should we have two impls, one with latency and another without?
 pub struct StatsPrinter {
-    start_time: Instant,
+    // The start of the benchmark, which is also the base time of the latency file.
+    start: ClockBase,
     previous_time: Instant,
     latency_type: Option<LatencyType>,
     histogram_writer: Option<HistogramWriter>,
+    heatmap_writer: Option<LatencyHeatmapWriter>,
+    // Only the final results are printed, the latency file is still written.
+    quiet: bool,
 }
 
 impl StatsPrinter {
     pub async fn new(
         latency_type: Option<LatencyType>,
         latency_file_name: Option<&str>,
+        heatmap_writer: Option<LatencyHeatmapWriter>,
+        quiet: bool,
+        metadata: &RunMetadata,
     ) -> Result<Self> {
+        let start = ClockBase::now();
         let histogram_writer = if let Some(latency_file_name) = latency_file_name {
-            Some(init_hdr_log_writer(latency_file_name).await?)
+            Some(init_hdr_log_writer(latency_file_name, start.system_time, metadata).await?)
         } else {
             None
         };
 
-        let now = Instant::now();
         Ok(Self {
-            start_time: now,
-            previous_time: now,
+            start,
+            previous_time: start.instant,
             latency_type,
             histogram_writer,
+            heatmap_writer,
+            quiet,
         })
     }
 
     pub fn print_header(&self, out: &mut impl Write) -> Result<()> {
+        if self.quiet {
+            return Ok(());
+        }
         if self.latency_type.is_some() {
             writeln!(
                 out,
@@ -196,8 +279,40 @@ impl StatsPrinter {
 
     pub async fn print_partial(&mut self, stats: &Stats, out: &mut impl Write) -> Result<()> {
         let now = Instant::now();
-        let time = now - self.start_time;
+        let time = now - self.start.instant;
+
+        if !self.quiet {
+            self.print_interval(stats, time, out)?;
+        }
 
+        if let (Some(latencies), Some(writer)) = (&stats.latencies, &mut self.histogram_writer) {
+            // The timestamps are relative to the base time, which is the start
+            // of the benchmark, and are measured with the monotonic clock.
+            let prev_time = self.previous_time - self.start.instant;
+            write_to_latencies_file(
+                writer,
+                latencies,
+                prev_time.as_secs_f64()..time.as_secs_f64(),
+                1_000_000.0 / stats.latency_resolution as f64,
+            )
+            .await?;
+        }
+        if let (Some(latencies), Some(writer)) = (&stats.latencies, &mut self.heatmap_writer) {
+            let interval = (self.previous_time - self.start.instant)..time;
+            writer
+                .write_interval(interval.clone(), "co-fixed", &latencies.co_fixed)
+                .await?;
+            writer
+                .write_interval(interval, "raw", &latencies.raw)
+                .await?;
+        }
+
+        self.previous_time = now;
+
+        Ok(())
+    }
+
+    fn print_interval(&self, stats: &Stats, time: Duration, out: &mut impl Write) -> Result<()> {
         if let Some(typ) = self.latency_type {
             let histogram = stats.get_histogram(typ).unwrap();
 
@@ -237,23 +352,50 @@ impl StatsPrinter {
             )?;
         }
 
-        if let (Some(latencies), Some(writer)) = (&stats.latencies, &mut self.histogram_writer) {
-            let prev_time = self.previous_time - self.start_time;
-            write_to_latencies_file(
-                writer,
-                latencies,
-                prev_time.as_secs_f64()..time.as_secs_f64(),
-            )
-            .await?;
+        if stats.errors != 0 {
+            writeln!(out, "  errors: {}", stats.error_counts.breakdown())?;
         }
 
-        self.previous_time = now;
+        Ok(())
+    }
+
+    pub fn print_partial_driver_metrics(
+        &self,
+        metrics: &DriverMetrics,
+        out: &mut impl Write,
+    ) -> Result<()> {
+        if !self.quiet {
+            writeln!(out, "  driver: {}", metrics.breakdown())?;
+        }
+        Ok(())
+    }
+
+    pub fn print_partial_client_metrics(&self, stats: &Stats, out: &mut impl Write) -> Result<()> {
+        if !self.quiet {
+            writeln!(out, "  client: {}", stats.client.breakdown())?;
+        }
+        Ok(())
+    }
+
+    /// Prints the max number of the operations in flight and the queue delay of the run.
+    pub fn print_final_client_metrics(&self, stats: &Stats, out: &mut impl Write) -> Result<()> {
+        writeln!(out, "Max in-flight:\t{}", stats.client.max_in_flight())?;
+        self.print_final_latency_histogram("queue delay", stats.client.queue_delay(), out)
+    }
 
+    /// Makes sure that the intervals logged so far reach the latency files.
+    pub async fn flush(&mut self) -> Result<()> {
+        if let Some(writer) = &mut self.histogram_writer {
+            writer.flush().await?;
+        }
+        if let Some(writer) = &mut self.heatmap_writer {
+            writer.flush().await?;
+        }
         Ok(())
     }
 
     pub fn print_final(&self, stats: &Stats, out: &mut impl Write) -> Result<()> {
-        let time = Instant::now() - self.start_time;
+        let time = Instant::now() - self.start.instant;
         writeln!(out)?;
         writeln!(out, "Results:")?;
         writeln!(out, "Time (avg):\t{}", format_duration(time))?;
@@ -261,6 +403,16 @@ impl StatsPrinter {
         writeln!(out, "Total rows:\t{}", stats.clustering_rows)?;
         if stats.errors != 0 {
             writeln!(out, "Total errors:\t{}", stats.errors)?;
+            for kind in ErrorKind::ALL {
+                let count = stats.error_counts.get(kind);
+                if count != 0 {
+                    writeln!(out, "  {}:\t{}", kind.name(), count)?;
+                }
+            }
+        }
+
+        if stats.reprepares != 0 {
+            writeln!(out, "Re-prepared:\t{}", stats.reprepares)?;
         }
 
         let ops_per_second = stats.operations as f64 / time.as_secs_f64();
@@ -279,6 +431,30 @@ impl StatsPrinter {
         Ok(())
     }
 
+    /// Prints the summary of the writes performed in the background of the measured reads.
+    // Prints a shorter summary of a part of the load, e.g. the background writes.
+    pub fn print_final_summary(
+        &self,
+        title: &str,
+        stats: &Stats,
+        out: &mut impl Write,
+    ) -> Result<()> {
+        let time = Instant::now() - self.start.instant;
+        writeln!(out)?;
+        writeln!(out, "{}:", title)?;
+        writeln!(out, "Total ops:\t{}", stats.operations)?;
+        writeln!(out, "Total rows:\t{}", stats.clustering_rows)?;
+        if stats.errors != 0 {
+            writeln!(out, "Total errors:\t{}", stats.errors)?;
+            writeln!(out, "  {}", stats.error_counts.breakdown())?;
+        }
+
+        let ops_per_second = stats.operations as f64 / time.as_secs_f64();
+        writeln!(out, "Operations/s:\t{}", ops_per_second)?;
+
+        Ok(())
+    }
+
     fn print_final_latency_histogram(
         &self,
         name: &str,
@@ -308,7 +484,11 @@ impl StatsPrinter {
     }
 }
 
-async fn init_hdr_log_writer(file_name: &str) -> Result<HistogramWriter> {
+async fn init_hdr_log_writer(
+    file_name: &str,
+    base_time: SystemTime,
+    metadata: &RunMetadata,
+) -> Result<HistogramWriter> {
     let dir_path = Path::new(file_name)
         .parent()
         .ok_or_else(|| anyhow::anyhow!("could not get parent dir of the histogram file path"))?;
@@ -322,12 +502,12 @@ async fn init_hdr_log_writer(file_name: &str) -> Result<HistogramWriter> {
     let file = File::create(file_name).await?;
     let mut log_writer = HistogramLogWriter::new(file);
 
+    log_writer.output_run_metadata(metadata).await?;
     log_writer.output_log_format_version().await?;
     log_writer
         .output_comment("Logging op latencies for scylla-bench")
         .await?;
 
-    let base_time = SystemTime::now();
     log_writer.output_base_time(base_time).await?;
     log_writer.output_start_time(base_time).await?;
     log_writer.output_legend().await?;
@@ -339,10 +519,12 @@ async fn write_to_latencies_file(
     writer: &mut HistogramWriter,
     latencies: &LatencyHistograms,
     interval_seconds: Range<f64>,
+    units_per_ms: f64,
 ) -> Result<()> {
     let opts_co_fixed = HistogramLogOptions {
         interval_seconds: interval_seconds.clone(),
         tag: "co-fixed",
+        units_per_ms,
     };
     writer
         .output_interval_histogram(&latencies.co_fixed, opts_co_fixed)
@@ -351,6 +533,7 @@ async fn write_to_latencies_file(
     let opts_raw = HistogramLogOptions {
         interval_seconds,
         tag: "raw",
+        units_per_ms,
     };
     writer
         .output_interval_histogram(&latencies.raw, opts_raw)
@@ -0,0 +1,71 @@
+use std::sync::Arc;
+
+use cql_stress::key_file::KeyFile;
+
+use super::{Workload, WorkloadFactory};
+
+/// Creates workloads which replay the keys read from a file,
+/// shared by all of the workers so that each key is used once per pass.
+///
+/// The clustering keys start at the one given in the file, or at 0 if the
+/// line contains only the partition key. Unless the file is looped,
+/// the run finishes once all of its keys are used.
+pub struct KeysFileFactory {
+    keys: Arc<KeyFile>,
+}
+
+struct KeysFileWorkload {
+    keys: Arc<KeyFile>,
+}
+
+impl KeysFileFactory {
+    pub fn new(keys: KeyFile) -> Self {
+        Self {
+            keys: Arc::new(keys),
+        }
+    }
+}
+
+impl WorkloadFactory for KeysFileFactory {
+    fn create(&self, _seed: u64) -> Box<dyn Workload> {
+        Box::new(KeysFileWorkload {
+            keys: Arc::clone(&self.keys),
+        })
+    }
+}
+
+impl Workload for KeysFileWorkload {
+    fn generate_keys(&mut self, ck_count: usize) -> Option<(i64, Vec<i64>)> {
+        let key = self.keys.next_key()?;
+        let ck = key.ck.unwrap_or(0);
+        Some((key.pk, (0..ck_count as i64).map(|i| ck + i).collect()))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_keys_file_workload() {
+        let path = std::env::temp_dir().join(format!("sb-keys-file-test-{}", std::process::id()));
+        std::fs::write(&path, "1\n2,5\n").unwrap();
+
+        let factory = KeysFileFactory::new(KeyFile::open(&path, false).unwrap());
+        let mut first = factory.create(0);
+        let mut second = factory.create(1);
+        // The workers share the file.
+        assert_eq!(first.generate_keys(2), Some((1, vec![0, 1])));
+        assert_eq!(second.generate_keys(2), Some((2, vec![5, 6])));
+        assert_eq!(first.generate_keys(2), None);
+
+        let looped = KeysFileFactory::new(KeyFile::open(&path, true).unwrap());
+        let mut workload = looped.create(0);
+        let pks = (0..3)
+            .map(|_| workload.generate_keys(1).unwrap().0)
+            .collect::<Vec<_>>();
+        assert_eq!(pks, vec![1, 2, 1]);
+
+        std::fs::remove_file(&path).unwrap();
+    }
+}
@@ -1,18 +1,54 @@
+mod keys_file;
+mod recent_keys;
+pub mod registry;
 mod sequential;
+mod single_partition;
+mod tablets;
 mod timeseries_read;
 mod timeseries_write;
 mod uniform;
+mod wide_partition;
 
-pub use sequential::{SequentialConfig, SequentialFactory};
+use std::sync::Arc;
+
+pub use keys_file::KeysFileFactory;
+pub use recent_keys::{RecentKeys, RecentKeysFactory};
+pub use sequential::{SequentialConfig, SequentialFactory, SequentialProgress};
+pub use single_partition::{SinglePartitionConfig, SinglePartitionFactory};
+pub use tablets::{TabletAwareFactory, TabletLayout};
 pub use timeseries_read::{TimeseriesReadConfig, TimeseriesReadFactory};
 pub use timeseries_write::{TimeseriesWriteConfig, TimeseriesWriteFactory};
 pub use uniform::{UniformConfig, UniformFactory};
+pub use wide_partition::{WidePartitionConfig, WidePartitionFactory};
 
 pub trait WorkloadFactory: Sync + Send {
-    fn create(&self) -> Box<dyn Workload>;
+    /// Creates a workload for a single worker. The workloads which
+    /// generate random keys use `seed` to seed their random generator.
+    fn create(&self, seed: u64) -> Box<dyn Workload>;
+}
+
+// Lets several operation factories draw the keys from the same workload,
+// e.g. when the load is split among multiple clusters.
+impl WorkloadFactory for Arc<dyn WorkloadFactory> {
+    fn create(&self, seed: u64) -> Box<dyn Workload> {
+        self.as_ref().create(seed)
+    }
 }
 
 pub trait Workload: Sync + Send {
     /// Generates the partition key and clustering keys to be inserted in this operation.
     fn generate_keys(&mut self, ck_count: usize) -> Option<(i64, Vec<i64>)>;
+
+    /// Returns the write timestamp and TTL the given row should be written with,
+    /// or `None` if the row should be written with the statement's defaults.
+    fn row_expiration(&self, _pk: i64, _ck: i64) -> Option<RowExpiration> {
+        None
+    }
+}
+
+/// The write timestamp and TTL of a single row.
+#[derive(Debug, PartialEq, Eq)]
+pub struct RowExpiration {
+    pub timestamp_micros: i64,
+    pub ttl_secs: i32,
 }
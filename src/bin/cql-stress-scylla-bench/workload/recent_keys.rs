@@ -0,0 +1,129 @@
+use std::collections::VecDeque;
+use std::sync::Arc;
+
+use parking_lot::Mutex;
+use rand::{Rng, SeedableRng};
+
+use cql_stress::seed::derive_seed;
+
+use crate::distribution::RngGen;
+
+use super::{Workload, WorkloadFactory};
+
+/// The keys of the rows written most recently, shared between
+/// the operations writing them and the operations reading them back.
+///
+/// Once full, each recorded key replaces the oldest one.
+pub struct RecentKeys {
+    capacity: usize,
+    keys: Mutex<VecDeque<(i64, i64)>>,
+}
+
+impl RecentKeys {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            keys: Mutex::new(VecDeque::with_capacity(capacity)),
+        }
+    }
+
+    /// Records the rows of the partition `pk` which were successfully written.
+    pub fn record(&self, pk: i64, cks: &[i64]) {
+        let mut keys = self.keys.lock();
+        for ck in cks {
+            if keys.len() == self.capacity {
+                keys.pop_front();
+            }
+            keys.push_back((pk, *ck));
+        }
+    }
+
+    /// Returns one of the recorded keys, or `None` if nothing was written yet.
+    fn sample(&self, gen: &mut RngGen) -> Option<(i64, i64)> {
+        let keys = self.keys.lock();
+        if keys.is_empty() {
+            return None;
+        }
+        keys.get(gen.gen_range(0..keys.len())).copied()
+    }
+}
+
+/// Creates workloads which read the rows written recently, so they are likely
+/// to be served from the row cache.
+///
+/// Until the first row is written, the keys are drawn from the inner workload.
+pub struct RecentKeysFactory {
+    recent_keys: Arc<RecentKeys>,
+    inner: Box<dyn WorkloadFactory>,
+}
+
+struct RecentKeysWorkload {
+    recent_keys: Arc<RecentKeys>,
+    inner: Box<dyn Workload>,
+    gen: RngGen,
+}
+
+impl RecentKeysFactory {
+    pub fn new(recent_keys: Arc<RecentKeys>, inner: Box<dyn WorkloadFactory>) -> Self {
+        Self { recent_keys, inner }
+    }
+}
+
+impl WorkloadFactory for RecentKeysFactory {
+    fn create(&self, seed: u64) -> Box<dyn Workload> {
+        Box::new(RecentKeysWorkload {
+            recent_keys: Arc::clone(&self.recent_keys),
+            inner: self.inner.create(derive_seed(seed, 0)),
+            gen: RngGen::seed_from_u64(seed),
+        })
+    }
+}
+
+impl Workload for RecentKeysWorkload {
+    fn generate_keys(&mut self, ck_count: usize) -> Option<(i64, Vec<i64>)> {
+        match self.recent_keys.sample(&mut self.gen) {
+            // Multi-row reads start at the recently written row.
+            Some((pk, ck)) => Some((pk, (0..ck_count as i64).map(|i| ck + i).collect())),
+            None => self.inner.generate_keys(ck_count),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::distribution::RowDistribution;
+    use crate::workload::{UniformConfig, UniformFactory};
+
+    fn uniform() -> Box<dyn WorkloadFactory> {
+        let config = UniformConfig {
+            pk_range: 1000..2000,
+            ck_range: 0..1,
+            pk_distribution: RowDistribution::Uniform,
+            ck_distribution: RowDistribution::Uniform,
+        };
+        Box::new(UniformFactory::new(config).unwrap())
+    }
+
+    #[test]
+    fn test_recent_keys_workload() {
+        let recent_keys = Arc::new(RecentKeys::new(3));
+        let mut workload = RecentKeysFactory::new(Arc::clone(&recent_keys), uniform()).create(0);
+
+        // Nothing was written yet, the keys come from the inner workload.
+        let (pk, _) = workload.generate_keys(1).unwrap();
+        assert!((1000..2000).contains(&pk));
+
+        recent_keys.record(1, &[10]);
+        recent_keys.record(2, &[20, 21, 22]);
+        // The oldest key was evicted.
+        for _ in 0..100 {
+            let (pk, cks) = workload.generate_keys(1).unwrap();
+            assert_eq!(pk, 2);
+            assert!([20, 21, 22].contains(&cks[0]));
+        }
+
+        let (_, cks) = workload.generate_keys(2).unwrap();
+        assert_eq!(cks[1], cks[0] + 1);
+    }
+}
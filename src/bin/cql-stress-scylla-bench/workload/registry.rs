@@ -0,0 +1,302 @@
+//! The workloads which can be selected with `-workload`.
+//!
+//! A workload is registered under its name, either for a single mode
+//! or for all of them, together with the function creating its factory
+//! from the arguments. Custom workloads are added with [register_workload]
+//! before the arguments are parsed.
+
+use std::sync::Arc;
+
+use anyhow::Result;
+use lazy_static::lazy_static;
+use parking_lot::RwLock;
+
+use crate::args::{show_mode, Mode, ScyllaBenchArgs};
+
+use super::{
+    SequentialConfig, SequentialFactory, SinglePartitionConfig, SinglePartitionFactory,
+    TabletAwareFactory, TabletLayout, TimeseriesReadConfig, TimeseriesReadFactory,
+    TimeseriesWriteConfig, TimeseriesWriteFactory, UniformConfig, UniformFactory,
+    WidePartitionConfig, WidePartitionFactory, WorkloadFactory,
+};
+
+/// Creates the factory of a workload.
+pub type CreateWorkload = fn(&WorkloadContext) -> Result<Box<dyn WorkloadFactory>>;
+
+/// The parameters the workload is created with.
+pub struct WorkloadContext<'a> {
+    pub args: &'a ScyllaBenchArgs,
+    pub mode: &'a Mode,
+    /// The tablets of the table, fetched if `-tablet` is given.
+    pub tablet_layout: Option<Arc<TabletLayout>>,
+}
+
+struct RegisteredWorkload {
+    name: String,
+    /// The mode the workload is registered for, `None` for all of them.
+    mode: Option<Mode>,
+    create: CreateWorkload,
+}
+
+/// The workloads keyed by their name and mode.
+#[derive(Default)]
+pub struct WorkloadRegistry {
+    workloads: Vec<RegisteredWorkload>,
+}
+
+impl WorkloadRegistry {
+    /// Creates the registry of the workloads built into the tool.
+    pub fn with_builtin() -> Self {
+        let mut registry = Self::default();
+        let builtin: [(&str, Option<Mode>, CreateWorkload); 6] = [
+            ("sequential", None, create_sequential),
+            ("uniform", None, create_uniform),
+            ("timeseries", Some(Mode::Write), create_timeseries_write),
+            ("timeseries", Some(Mode::Read), create_timeseries_read),
+            ("wide_partition", Some(Mode::Write), create_wide_partition),
+            ("single_partition", None, create_single_partition),
+        ];
+        for (name, mode, create) in builtin {
+            // The built-in workloads are unique.
+            registry.register(name, mode, create).unwrap();
+        }
+        registry
+    }
+
+    /// Registers the workload for the given mode, or for all of them if `mode` is `None`.
+    pub fn register(
+        &mut self,
+        name: &str,
+        mode: Option<Mode>,
+        create: CreateWorkload,
+    ) -> Result<()> {
+        anyhow::ensure!(!name.is_empty(), "Workload name cannot be empty");
+        anyhow::ensure!(
+            !self
+                .workloads
+                .iter()
+                .any(|workload| workload.name == name && workload.mode == mode),
+            "Workload {} is already registered for {}",
+            name,
+            mode.as_ref().map_or("all modes", show_mode),
+        );
+        self.workloads.push(RegisteredWorkload {
+            name: name.to_owned(),
+            mode,
+            create,
+        });
+        Ok(())
+    }
+
+    pub fn contains(&self, name: &str) -> bool {
+        self.workloads.iter().any(|workload| workload.name == name)
+    }
+
+    /// Returns the names of the registered workloads, in the order of registration.
+    pub fn names(&self) -> Vec<String> {
+        let mut names: Vec<String> = Vec::new();
+        for workload in &self.workloads {
+            if !names.contains(&workload.name) {
+                names.push(workload.name.clone());
+            }
+        }
+        names
+    }
+
+    /// Creates the named workload for the mode of the context. The workload
+    /// registered for the mode takes precedence over the one registered for all modes.
+    pub fn create(
+        &self,
+        name: &str,
+        context: &WorkloadContext,
+    ) -> Result<Box<dyn WorkloadFactory>> {
+        let candidates = self
+            .workloads
+            .iter()
+            .filter(|workload| workload.name == name)
+            .collect::<Vec<_>>();
+        anyhow::ensure!(!candidates.is_empty(), "unknown workload type: {}", name);
+
+        let workload = candidates
+            .iter()
+            .find(|workload| workload.mode.as_ref() == Some(context.mode))
+            .or_else(|| candidates.iter().find(|workload| workload.mode.is_none()));
+        match workload {
+            Some(workload) => (workload.create)(context),
+            None => {
+                let modes = candidates
+                    .iter()
+                    .filter_map(|workload| workload.mode.as_ref().map(show_mode))
+                    .collect::<Vec<_>>();
+                Err(anyhow::anyhow!(
+                    "{} workload supports only {} mode",
+                    name,
+                    modes.join(" and "),
+                ))
+            }
+        }
+    }
+}
+
+lazy_static! {
+    static ref WORKLOADS: RwLock<WorkloadRegistry> = RwLock::new(WorkloadRegistry::with_builtin());
+}
+
+/// Registers a custom workload, selectable with `-workload <name>`.
+///
+/// Must be called before the arguments are parsed.
+#[allow(dead_code)] // Used by the custom workloads only.
+pub fn register_workload(name: &str, mode: Option<Mode>, create: CreateWorkload) -> Result<()> {
+    WORKLOADS.write().register(name, mode, create)
+}
+
+pub fn is_registered(name: &str) -> bool {
+    WORKLOADS.read().contains(name)
+}
+
+pub fn workload_names() -> Vec<String> {
+    WORKLOADS.read().names()
+}
+
+pub fn create_workload(name: &str, context: &WorkloadContext) -> Result<Box<dyn WorkloadFactory>> {
+    WORKLOADS.read().create(name, context)
+}
+
+pub fn sequential_config(args: &ScyllaBenchArgs) -> SequentialConfig {
+    // Each of the loaders sharing the population writes its part of the partitions.
+    let (partition_offset, pks) = match args.split {
+        Some(split) => {
+            let part = split.part(args.partition_count);
+            (
+                args.partition_offset + part.start as i64,
+                part.end - part.start,
+            )
+        }
+        None => (args.partition_offset, args.partition_count),
+    };
+    SequentialConfig {
+        iterations: args.iterations,
+        partition_offset,
+        pks,
+        cks_per_pk: args.clustering_row_count,
+        // The next iterations continue after all of the loaders' parts.
+        iteration_stride: if args.no_wrap {
+            args.partition_count
+        } else {
+            0
+        },
+    }
+}
+
+fn create_sequential(context: &WorkloadContext) -> Result<Box<dyn WorkloadFactory>> {
+    Ok(Box::new(SequentialFactory::new(sequential_config(
+        context.args,
+    ))?))
+}
+
+fn create_uniform(context: &WorkloadContext) -> Result<Box<dyn WorkloadFactory>> {
+    let args = context.args;
+    let uni_config = UniformConfig {
+        pk_range: 0..args.partition_count,
+        ck_range: 0..args.clustering_row_count,
+        pk_distribution: args.partition_distribution,
+        ck_distribution: args.clustering_row_distribution,
+    };
+    let factory = Box::new(UniformFactory::new(uni_config)?);
+    match (&context.tablet_layout, args.tablet) {
+        (Some(layout), Some(target)) => Ok(Box::new(TabletAwareFactory::new(
+            factory,
+            Arc::clone(layout),
+            target,
+            0..args.partition_count,
+        )?)),
+        _ => Ok(factory),
+    }
+}
+
+fn create_timeseries_write(context: &WorkloadContext) -> Result<Box<dyn WorkloadFactory>> {
+    let args = context.args;
+    let tsw_config = TimeseriesWriteConfig {
+        _partition_offset: args.partition_offset,
+        pks_per_generation: args.partition_count,
+        cks_per_pk: args.clustering_row_count,
+        start_nanos: args.start_timestamp,
+        // The background writes of the read mode have their own rate.
+        max_rate: args.background_write_rate.unwrap_or(args.maximum_rate),
+        retention: args.twcs_retention,
+    };
+    Ok(Box::new(TimeseriesWriteFactory::new(tsw_config)?))
+}
+
+fn create_timeseries_read(context: &WorkloadContext) -> Result<Box<dyn WorkloadFactory>> {
+    let args = context.args;
+    let period = 1_000_000_000 / args.write_rate;
+    let tsr_config = TimeseriesReadConfig {
+        _partition_offset: args.partition_offset,
+        pks_per_generation: args.partition_count,
+        cks_per_pk: args.clustering_row_count,
+        start_nanos: args.start_timestamp,
+        period_nanos: period,
+        distribution: args.distribution.clone(),
+    };
+    Ok(Box::new(TimeseriesReadFactory::new(tsr_config)?))
+}
+
+fn create_wide_partition(context: &WorkloadContext) -> Result<Box<dyn WorkloadFactory>> {
+    let args = context.args;
+    let wide_config = WidePartitionConfig {
+        pks: args.partition_count,
+        max_cks_per_op: args.max_clustering_rows_per_op,
+        max_cks_per_pk: args.partition_growth,
+    };
+    Ok(Box::new(WidePartitionFactory::new(wide_config)?))
+}
+
+// An example of a workload added through the registry: all of the operations
+// go to the partition `-partition-offset`.
+fn create_single_partition(context: &WorkloadContext) -> Result<Box<dyn WorkloadFactory>> {
+    let single_config = SinglePartitionConfig {
+        pk: context.args.partition_offset,
+        cks_per_pk: context.args.clustering_row_count,
+    };
+    Ok(Box::new(SinglePartitionFactory::new(single_config)?))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn create_dummy(_context: &WorkloadContext) -> Result<Box<dyn WorkloadFactory>> {
+        Err(anyhow::anyhow!("dummy workload"))
+    }
+
+    #[test]
+    fn test_registry() {
+        let mut registry = WorkloadRegistry::with_builtin();
+        assert_eq!(
+            vec![
+                "sequential",
+                "uniform",
+                "timeseries",
+                "wide_partition",
+                "single_partition"
+            ],
+            registry.names()
+        );
+        assert!(registry.contains("timeseries"));
+        assert!(!registry.contains("dummy"));
+
+        registry
+            .register("dummy", Some(Mode::Write), create_dummy)
+            .unwrap();
+        registry
+            .register("dummy", Some(Mode::Read), create_dummy)
+            .unwrap();
+        assert!(registry.contains("dummy"));
+        assert!(registry
+            .register("dummy", Some(Mode::Write), create_dummy)
+            .is_err());
+        assert!(registry.register("uniform", None, create_dummy).is_err());
+        assert!(registry.register("", None, create_dummy).is_err());
+    }
+}
@@ -1,12 +1,25 @@
-use std::sync::atomic::{AtomicU64, Ordering};
+use std::collections::BTreeMap;
 use std::sync::Arc;
 
 use anyhow::Result;
+use parking_lot::Mutex;
 
 use super::{Workload, WorkloadFactory};
 
-struct SharedState {
-    pub next_pk: AtomicU64,
+/// The progress of the workers of a sequential workload, which tells
+/// where an interrupted run can be resumed from.
+///
+/// The partitions are identified by their index in the whole run,
+/// i.e. they keep increasing with each iteration.
+pub struct SequentialProgress {
+    state: Mutex<ProgressState>,
+}
+
+struct ProgressState {
+    next_pk: u64,
+    // The partitions taken by the workers which didn't move
+    // to the next one yet, with the number of such workers.
+    in_progress: BTreeMap<u64, usize>,
 }
 
 /// Creates workloads which write data sequentially.
@@ -15,13 +28,14 @@ struct SharedState {
 /// behavior.
 pub struct SequentialFactory {
     config: SequentialConfig,
-    shared_state: Arc<SharedState>,
+    progress: Arc<SequentialProgress>,
 }
 
 struct Sequential {
     config: SequentialConfig,
-    shared_state: Arc<SharedState>,
-    current_pk: u64,
+    progress: Arc<SequentialProgress>,
+    // None until the first partition is taken.
+    current_pk: Option<u64>,
     current_ck: u64,
 }
 
@@ -42,52 +56,104 @@ struct Sequential {
 /// operation on a given partition may insert less rows.
 ///
 /// The whole data set will be written one or more times, depending on
-/// the `iterations` parameter.
+/// the `iterations` parameter. Each of the iterations writes the partitions
+/// shifted by `iteration_stride` from the previous one, so with 0 they
+/// revisit the same partitions (see `-no-wrap`).
 #[derive(Clone)]
 pub struct SequentialConfig {
     pub iterations: u64,
     pub partition_offset: i64,
     pub pks: u64,
     pub cks_per_pk: u64,
+    pub iteration_stride: u64,
+}
+
+impl SequentialProgress {
+    /// Creates the progress of a run starting at the partition with index `start`.
+    pub fn new(start: u64) -> Self {
+        Self {
+            state: Mutex::new(ProgressState {
+                next_pk: start,
+                in_progress: BTreeMap::new(),
+            }),
+        }
+    }
+
+    /// Returns the index of the first partition which may not be fully
+    /// written yet. All of the partitions before it were completed.
+    pub fn checkpoint(&self) -> u64 {
+        let state = self.state.lock();
+        match state.in_progress.keys().next() {
+            Some(pk) => *pk,
+            None => state.next_pk,
+        }
+    }
+
+    // Marks the `previous` partition of the worker as completed
+    // and takes the next one.
+    fn next(&self, previous: Option<u64>) -> u64 {
+        let mut state = self.state.lock();
+        if let Some(previous) = previous {
+            state.complete(previous);
+        }
+        let pk = state.next_pk;
+        state.next_pk += 1;
+        *state.in_progress.entry(pk).or_default() += 1;
+        pk
+    }
+
+    fn complete(&self, pk: u64) {
+        self.state.lock().complete(pk);
+    }
+}
+
+impl ProgressState {
+    fn complete(&mut self, pk: u64) {
+        if let Some(count) = self.in_progress.get_mut(&pk) {
+            *count -= 1;
+            if *count == 0 {
+                self.in_progress.remove(&pk);
+            }
+        }
+    }
 }
 
 impl SequentialFactory {
     pub fn new(config: SequentialConfig) -> Result<Self> {
+        Self::with_progress(config, Arc::new(SequentialProgress::new(0)))
+    }
+
+    /// Creates the workloads which start at the checkpoint of the `progress`
+    /// and keep updating it, e.g. when resuming an interrupted run.
+    pub fn with_progress(
+        config: SequentialConfig,
+        progress: Arc<SequentialProgress>,
+    ) -> Result<Self> {
         anyhow::ensure!(config.pks > 0, "Partition count must be greater than zero");
         anyhow::ensure!(
             config.cks_per_pk > 0,
             "Clustering key per partition count must be greater than zero",
         );
 
-        let shared_state = Arc::new(SharedState {
-            next_pk: AtomicU64::new(0),
-        });
-
-        Ok(Self {
-            config,
-            shared_state,
-        })
+        Ok(Self { config, progress })
     }
 }
 
 impl WorkloadFactory for SequentialFactory {
-    fn create(&self) -> Box<dyn Workload> {
-        Box::new(Sequential::new(
-            self.config.clone(),
-            self.shared_state.clone(),
-        ))
+    fn create(&self, _seed: u64) -> Box<dyn Workload> {
+        Box::new(Sequential::new(self.config.clone(), self.progress.clone()))
     }
 }
 
 impl Sequential {
-    fn new(config: SequentialConfig, shared_state: Arc<SharedState>) -> Self {
+    fn new(config: SequentialConfig, progress: Arc<SequentialProgress>) -> Self {
         // This is dummy state, just in order to trigger choosing pk
         // on first `generate_keys` invocation
         let current_ck = config.cks_per_pk;
         Sequential {
             config,
-            shared_state,
-            current_pk: 0,
+            progress,
+            current_pk: None,
             current_ck,
         }
     }
@@ -96,16 +162,24 @@ impl Sequential {
 impl Workload for Sequential {
     fn generate_keys(&mut self, ck_count: usize) -> Option<(i64, Vec<i64>)> {
         if self.current_ck >= self.config.cks_per_pk {
-            self.current_ck = 0;
-            self.current_pk = self.shared_state.next_pk.fetch_add(1, Ordering::Relaxed);
-            if self.config.iterations > 0
-                && self.current_pk >= self.config.pks * self.config.iterations
+            // The previous partition is completed, since the worker asks
+            // for the next keys only after the previous operation finished.
+            // If the run is interrupted, the current one stays in progress.
+            let current_pk = self.progress.next(self.current_pk.take());
+            if self.config.iterations > 0 && current_pk >= self.config.pks * self.config.iterations
             {
+                self.progress.complete(current_pk);
                 return None;
             }
+            self.current_pk = Some(current_pk);
+            self.current_ck = 0;
         }
 
-        let pk = (self.current_pk % self.config.pks) as i64 + self.config.partition_offset;
+        // Taken above on the first invocation.
+        let current_pk = self.current_pk.unwrap();
+        let iteration = current_pk / self.config.pks;
+        let pk = (current_pk % self.config.pks + iteration * self.config.iteration_stride) as i64
+            + self.config.partition_offset;
         let ck_end = std::cmp::min(self.current_ck + ck_count as u64, self.config.cks_per_pk);
         let cks = (self.current_ck..ck_end).map(|x| x as i64).collect();
         self.current_ck = ck_end;
@@ -122,7 +196,7 @@ mod tests {
     fn test_sequential_workload() {
         let check = |config: SequentialConfig, rpk: usize, expected: &[(i64, Vec<i64>)]| {
             let factory = SequentialFactory::new(config).unwrap();
-            let mut seq = factory.create();
+            let mut seq = factory.create(0);
             let mut actual = Vec::new();
             while let Some((pk, cks)) = seq.generate_keys(rpk) {
                 actual.push((pk, cks));
@@ -138,6 +212,7 @@ mod tests {
                 partition_offset: 0,
                 pks: 3,
                 cks_per_pk: 1,
+                iteration_stride: 0,
             },
             1,
             &[(0, vec![0]), (1, vec![0]), (2, vec![0])],
@@ -150,6 +225,7 @@ mod tests {
                 partition_offset: 0,
                 pks: 3,
                 cks_per_pk: 1,
+                iteration_stride: 0,
             },
             1,
             &[
@@ -162,6 +238,19 @@ mod tests {
             ],
         );
 
+        // Two iterations without wrapping
+        check(
+            SequentialConfig {
+                iterations: 2,
+                partition_offset: 10,
+                pks: 2,
+                cks_per_pk: 1,
+                iteration_stride: 5,
+            },
+            1,
+            &[(10, vec![0]), (11, vec![0]), (15, vec![0]), (16, vec![0])],
+        );
+
         // Two clustering keys
         check(
             SequentialConfig {
@@ -169,6 +258,7 @@ mod tests {
                 partition_offset: 0,
                 pks: 3,
                 cks_per_pk: 2,
+                iteration_stride: 0,
             },
             1,
             &[
@@ -188,6 +278,7 @@ mod tests {
                 partition_offset: 0,
                 pks: 2,
                 cks_per_pk: 5,
+                iteration_stride: 0,
             },
             3,
             &[
@@ -198,4 +289,38 @@ mod tests {
             ],
         );
     }
+
+    #[test]
+    fn test_sequential_progress() {
+        let config = SequentialConfig {
+            iterations: 1,
+            partition_offset: 0,
+            pks: 10,
+            cks_per_pk: 2,
+            iteration_stride: 0,
+        };
+        let progress = Arc::new(SequentialProgress::new(3));
+        let factory = SequentialFactory::with_progress(config, Arc::clone(&progress)).unwrap();
+        let mut first = factory.create(0);
+        let mut second = factory.create(1);
+
+        // The run starts at the given partition.
+        assert_eq!(first.generate_keys(1), Some((3, vec![0])));
+        assert_eq!(second.generate_keys(1), Some((4, vec![0])));
+        assert_eq!(progress.checkpoint(), 3);
+
+        // The partition 4 is completed, but 3 is still in progress.
+        assert_eq!(second.generate_keys(1), Some((4, vec![1])));
+        assert_eq!(second.generate_keys(1), Some((5, vec![0])));
+        assert_eq!(progress.checkpoint(), 3);
+
+        assert_eq!(first.generate_keys(1), Some((3, vec![1])));
+        assert_eq!(first.generate_keys(1), Some((6, vec![0])));
+        assert_eq!(progress.checkpoint(), 5);
+
+        // All partitions are completed once the workload is exhausted.
+        while first.generate_keys(2).is_some() {}
+        while second.generate_keys(2).is_some() {}
+        assert!(progress.checkpoint() >= 10);
+    }
 }
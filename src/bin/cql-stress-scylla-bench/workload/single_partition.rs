@@ -0,0 +1,84 @@
+use anyhow::Result;
+
+use super::{Workload, WorkloadFactory};
+
+/// Creates workloads which direct all of the operations to a single partition,
+/// e.g. to load a hot partition.
+pub struct SinglePartitionFactory {
+    config: SinglePartitionConfig,
+}
+
+/// Defines parameters of a single partition workload.
+///
+/// The operations visit the clustering keys `0..cks_per_pk` of the partition `pk`
+/// in order, wrapping around.
+#[derive(Clone)]
+pub struct SinglePartitionConfig {
+    pub pk: i64,
+    pub cks_per_pk: u64,
+}
+
+struct SinglePartition {
+    pk: i64,
+    cks_per_pk: u64,
+    next_ck: u64,
+}
+
+impl SinglePartitionFactory {
+    pub fn new(config: SinglePartitionConfig) -> Result<Self> {
+        anyhow::ensure!(
+            config.cks_per_pk > 0,
+            "Invalid number of clustering keys per partition",
+        );
+        Ok(Self { config })
+    }
+}
+
+impl WorkloadFactory for SinglePartitionFactory {
+    fn create(&self, seed: u64) -> Box<dyn Workload> {
+        // The workers start from different rows, so that they don't update
+        // the same row at the same time.
+        Box::new(SinglePartition {
+            pk: self.config.pk,
+            cks_per_pk: self.config.cks_per_pk,
+            next_ck: seed % self.config.cks_per_pk,
+        })
+    }
+}
+
+impl Workload for SinglePartition {
+    fn generate_keys(&mut self, ck_count: usize) -> Option<(i64, Vec<i64>)> {
+        let cks = (0..ck_count)
+            .map(|_| {
+                let ck = self.next_ck;
+                self.next_ck = (self.next_ck + 1) % self.cks_per_pk;
+                ck as i64
+            })
+            .collect();
+
+        Some((self.pk, cks))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_single_partition_workload() {
+        let config = SinglePartitionConfig {
+            pk: 7,
+            cks_per_pk: 3,
+        };
+        let mut workload = SinglePartitionFactory::new(config).unwrap().create(4);
+
+        assert_eq!(Some((7, vec![1, 2])), workload.generate_keys(2));
+        assert_eq!(Some((7, vec![0, 1, 2, 0])), workload.generate_keys(4));
+
+        let config = SinglePartitionConfig {
+            pk: 0,
+            cks_per_pk: 0,
+        };
+        assert!(SinglePartitionFactory::new(config).is_err());
+    }
+}
@@ -0,0 +1,290 @@
+use std::ops::Range;
+use std::sync::Arc;
+
+use anyhow::{Context, Result};
+use cql_stress::seed::derive_seed;
+use rand::{Rng, SeedableRng};
+use scylla::transport::partitioner::{Murmur3Partitioner, Partitioner};
+use scylla::Session;
+
+use crate::args::TabletTarget;
+use crate::distribution::RngGen;
+
+use super::{Workload, WorkloadFactory};
+
+/// The maximum number of partition keys hashed to find the keys of each tablet.
+/// With more partitions, an evenly spread sample of them is used.
+const KEY_SAMPLE_SIZE: u64 = 1 << 20;
+
+/// Token ranges of the tablets of a single table, as reported by `system.tablets`.
+///
+/// The i-th tablet owns the tokens in range (last_tokens[i - 1], last_tokens[i]].
+/// The first tablet starts at the lowest token.
+pub struct TabletLayout {
+    last_tokens: Vec<i64>,
+}
+
+impl TabletLayout {
+    /// Fetches the tablet metadata of given table.
+    pub async fn fetch(session: &Session, keyspace: &str, table: &str) -> Result<Self> {
+        let result = session
+            .query_unpaged(
+                "SELECT last_token FROM system.tablets \
+                WHERE keyspace_name = ? AND table_name = ? ALLOW FILTERING",
+                (keyspace, table),
+            )
+            .await
+            .context("Failed to fetch tablet metadata")?;
+
+        let mut last_tokens = result
+            .rows_typed::<(i64,)>()?
+            .map(|row| row.map(|(last_token,)| last_token))
+            .collect::<Result<Vec<_>, _>>()?;
+        anyhow::ensure!(
+            !last_tokens.is_empty(),
+            "Table {}.{} does not use tablets",
+            keyspace,
+            table,
+        );
+        last_tokens.sort_unstable();
+
+        Self::from_last_tokens(last_tokens)
+    }
+
+    pub fn from_last_tokens(last_tokens: Vec<i64>) -> Result<Self> {
+        anyhow::ensure!(
+            last_tokens.last() == Some(&i64::MAX),
+            "Tablets do not cover the whole token ring",
+        );
+        anyhow::ensure!(
+            last_tokens.windows(2).all(|w| w[0] < w[1]),
+            "Tablet boundaries are not strictly increasing",
+        );
+        Ok(Self { last_tokens })
+    }
+
+    pub fn tablet_count(&self) -> usize {
+        self.last_tokens.len()
+    }
+
+    /// Returns the index of the tablet owning given partition key.
+    pub fn tablet_for_pk(&self, pk: i64) -> usize {
+        let token = Murmur3Partitioner.hash_one(&pk.to_be_bytes()).value();
+        self.last_tokens.partition_point(|&last| last < token)
+    }
+
+    /// Returns the (inclusive) token range owned by given tablet.
+    pub fn token_range(&self, tablet: usize) -> (i64, i64) {
+        let first = match tablet {
+            0 => i64::MIN + 1,
+            _ => self.last_tokens[tablet - 1] + 1,
+        };
+        (first, self.last_tokens[tablet])
+    }
+
+    pub fn print_report(&self, target: &TabletTarget) {
+        println!("Tablet count:\t\t {}", self.tablet_count());
+        if let TabletTarget::Single(tablet) = target {
+            let (first, last) = self.token_range(*tablet);
+            println!("Tablet token range:\t [{}, {}]", first, last);
+        }
+    }
+}
+
+/// Creates workloads which restrict the partitions generated by the inner
+/// workload to the targeted tablets.
+///
+/// The keys of each tablet are found once, from a sample of the partitions
+/// (see [KEY_SAMPLE_SIZE]), and each partition key generated by the inner
+/// workload is mapped onto the keys of the targeted tablet, so that generating
+/// a key doesn't take hashing about as many keys as there are tablets.
+pub struct TabletAwareFactory {
+    inner: Box<dyn WorkloadFactory>,
+    tablet_keys: Arc<Vec<Vec<i64>>>,
+    target: TabletTarget,
+}
+
+struct TabletAware {
+    inner: Box<dyn Workload>,
+    // The sampled partition keys owned by each of the tablets.
+    tablet_keys: Arc<Vec<Vec<i64>>>,
+    target: TabletTarget,
+    gen: RngGen,
+}
+
+impl TabletAwareFactory {
+    pub fn new(
+        inner: Box<dyn WorkloadFactory>,
+        layout: Arc<TabletLayout>,
+        target: TabletTarget,
+        pk_range: Range<u64>,
+    ) -> Result<TabletAwareFactory> {
+        let tablet_keys = sample_tablet_keys(&layout, pk_range);
+
+        // Make sure that each targeted tablet owns at least one partition,
+        // otherwise the workload would have no key to generate.
+        match target {
+            TabletTarget::Single(tablet) => {
+                anyhow::ensure!(
+                    tablet < layout.tablet_count(),
+                    "Invalid tablet {}, the table has {} tablets",
+                    tablet,
+                    layout.tablet_count(),
+                );
+                anyhow::ensure!(
+                    !tablet_keys[tablet].is_empty(),
+                    "None of the partitions belongs to tablet {}",
+                    tablet,
+                );
+            }
+            TabletTarget::Even => {
+                let covered = tablet_keys.iter().filter(|keys| !keys.is_empty()).count();
+                anyhow::ensure!(
+                    covered == layout.tablet_count(),
+                    "Only {} out of {} tablets own any partition, increase the partition count",
+                    covered,
+                    layout.tablet_count(),
+                );
+            }
+        }
+
+        Ok(TabletAwareFactory {
+            inner,
+            tablet_keys: Arc::new(tablet_keys),
+            target,
+        })
+    }
+}
+
+// Groups the partition keys by their tablets. At most KEY_SAMPLE_SIZE keys
+// are hashed, evenly spread over the partitions if there are more of them.
+fn sample_tablet_keys(layout: &TabletLayout, pk_range: Range<u64>) -> Vec<Vec<i64>> {
+    let mut tablet_keys = vec![Vec::new(); layout.tablet_count()];
+    let step = (pk_range.end - pk_range.start)
+        .div_ceil(KEY_SAMPLE_SIZE)
+        .max(1);
+    for pk in pk_range.step_by(step as usize) {
+        let pk = pk as i64;
+        tablet_keys[layout.tablet_for_pk(pk)].push(pk);
+    }
+    tablet_keys
+}
+
+impl WorkloadFactory for TabletAwareFactory {
+    fn create(&self, seed: u64) -> Box<dyn Workload> {
+        Box::new(TabletAware {
+            inner: self.inner.create(derive_seed(seed, 0)),
+            tablet_keys: Arc::clone(&self.tablet_keys),
+            target: self.target,
+            gen: RngGen::seed_from_u64(seed),
+        })
+    }
+}
+
+impl Workload for TabletAware {
+    fn generate_keys(&mut self, ck_count: usize) -> Option<(i64, Vec<i64>)> {
+        let tablet = match self.target {
+            TabletTarget::Single(tablet) => tablet,
+            TabletTarget::Even => self.gen.gen_range(0..self.tablet_keys.len()),
+        };
+
+        // The same key of the inner workload is always mapped onto the same
+        // key of given tablet, so its distribution carries over to the tablet.
+        let (pk, cks) = self.inner.generate_keys(ck_count)?;
+        let keys = &self.tablet_keys[tablet];
+        Some((keys[(pk as u64 % keys.len() as u64) as usize], cks))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::HashSet;
+
+    use super::*;
+    use crate::distribution::RowDistribution;
+    use crate::workload::{UniformConfig, UniformFactory};
+
+    fn layout() -> Arc<TabletLayout> {
+        Arc::new(TabletLayout::from_last_tokens(vec![-1, i64::MAX]).unwrap())
+    }
+
+    fn uniform(pks: u64) -> Box<dyn WorkloadFactory> {
+        let config = UniformConfig {
+            pk_range: 0..pks,
+            ck_range: 0..1,
+            pk_distribution: RowDistribution::Uniform,
+            ck_distribution: RowDistribution::Uniform,
+        };
+        Box::new(UniformFactory::new(config).unwrap())
+    }
+
+    #[test]
+    fn test_tablet_layout() {
+        assert!(TabletLayout::from_last_tokens(vec![]).is_err());
+        assert!(TabletLayout::from_last_tokens(vec![0, -1, i64::MAX]).is_err());
+
+        let layout = layout();
+        assert_eq!(layout.tablet_count(), 2);
+        assert_eq!(layout.token_range(0), (i64::MIN + 1, -1));
+        assert_eq!(layout.token_range(1), (0, i64::MAX));
+    }
+
+    #[test]
+    fn test_single_tablet_workload() {
+        let layout = layout();
+        let factory = TabletAwareFactory::new(
+            uniform(100),
+            Arc::clone(&layout),
+            TabletTarget::Single(1),
+            0..100,
+        )
+        .unwrap();
+        let mut workload = factory.create(0);
+
+        for _ in 0..100 {
+            let (pk, _) = workload.generate_keys(1).unwrap();
+            assert_eq!(layout.tablet_for_pk(pk), 1);
+        }
+    }
+
+    #[test]
+    fn test_even_tablet_workload() {
+        let layout = layout();
+        let factory = TabletAwareFactory::new(
+            uniform(100),
+            Arc::clone(&layout),
+            TabletTarget::Even,
+            0..100,
+        )
+        .unwrap();
+        let mut workload = factory.create(0);
+
+        let tablets: HashSet<_> = (0..100)
+            .map(|_| layout.tablet_for_pk(workload.generate_keys(1).unwrap().0))
+            .collect();
+        assert_eq!(tablets.len(), 2);
+    }
+
+    #[test]
+    fn test_sample_tablet_keys() {
+        let layout = layout();
+        let tablet_keys = sample_tablet_keys(&layout, 0..100);
+        assert_eq!(tablet_keys.iter().map(Vec::len).sum::<usize>(), 100);
+        for (tablet, keys) in tablet_keys.iter().enumerate() {
+            assert!(keys.iter().all(|&pk| layout.tablet_for_pk(pk) == tablet));
+        }
+
+        // Only a sample of the keys is hashed, if there are more of them.
+        let tablet_keys = sample_tablet_keys(&layout, 0..10 * KEY_SAMPLE_SIZE);
+        let sampled = tablet_keys.iter().map(Vec::len).sum::<usize>();
+        assert_eq!(sampled as u64, KEY_SAMPLE_SIZE);
+    }
+
+    #[test]
+    fn test_invalid_tablet() {
+        assert!(
+            TabletAwareFactory::new(uniform(100), layout(), TabletTarget::Single(2), 0..100)
+                .is_err()
+        );
+    }
+}
@@ -3,7 +3,7 @@ use std::sync::Arc;
 use std::time::SystemTime;
 
 use anyhow::Result;
-use rand::Rng;
+use rand::{Rng, SeedableRng};
 use rand_distr::{Distribution, StandardNormal};
 
 use crate::args::TimeseriesDistribution;
@@ -51,19 +51,24 @@ impl TimeseriesReadFactory {
 }
 
 impl WorkloadFactory for TimeseriesReadFactory {
-    fn create(&self) -> Box<dyn Workload> {
+    fn create(&self, seed: u64) -> Box<dyn Workload> {
         Box::new(TimeseriesRead::new(
             self.config.clone(),
             Arc::clone(&self.shared_state),
+            seed,
         ))
     }
 }
 
 impl TimeseriesRead {
-    fn new(config: TimeseriesReadConfig, shared_state: Arc<SharedState>) -> TimeseriesRead {
+    fn new(
+        config: TimeseriesReadConfig,
+        shared_state: Arc<SharedState>,
+        seed: u64,
+    ) -> TimeseriesRead {
         TimeseriesRead {
             config,
-            gen: RngGen::new(rand::thread_rng().gen()),
+            gen: RngGen::seed_from_u64(seed),
             shared_state,
         }
     }
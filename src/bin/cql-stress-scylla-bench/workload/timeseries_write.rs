@@ -1,9 +1,10 @@
 use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
+use std::time::{Duration, SystemTime};
 
 use anyhow::Result;
 
-use super::{Workload, WorkloadFactory};
+use super::{RowExpiration, Workload, WorkloadFactory};
 
 pub struct TimeseriesWriteFactory {
     config: TimeseriesWriteConfig,
@@ -27,6 +28,8 @@ pub struct TimeseriesWriteConfig {
     pub cks_per_pk: u64,
     pub start_nanos: u64,
     pub max_rate: u64,
+    // If set, each row expires `retention` after its timestamp.
+    pub retention: Option<Duration>,
 }
 
 impl TimeseriesWriteFactory {
@@ -43,7 +46,7 @@ impl TimeseriesWriteFactory {
 }
 
 impl WorkloadFactory for TimeseriesWriteFactory {
-    fn create(&self) -> Box<dyn Workload> {
+    fn create(&self, _seed: u64) -> Box<dyn Workload> {
         Box::new(TimeseriesWrite::new(
             self.config.clone(),
             Arc::clone(&self.shared_state),
@@ -75,4 +78,87 @@ impl Workload for TimeseriesWrite {
 
         Some((pk as i64, vec![ck]))
     }
+
+    fn row_expiration(&self, _pk: i64, ck: i64) -> Option<RowExpiration> {
+        let retention = self.config.retention?;
+        let now_nanos = SystemTime::UNIX_EPOCH.elapsed().unwrap().as_nanos() as u64;
+        Some(compute_row_expiration((-ck) as u64, retention, now_nanos))
+    }
+}
+
+// The rows are written with their own timestamps, so that TWCS puts them
+// into the windows they belong to, and expire `retention` after that timestamp.
+// The rows which are already past their retention expire as soon as possible.
+fn compute_row_expiration(row_nanos: u64, retention: Duration, now_nanos: u64) -> RowExpiration {
+    let expiration_nanos = row_nanos + retention.as_nanos() as u64;
+    let ttl_secs = expiration_nanos.saturating_sub(now_nanos) / 1_000_000_000;
+    RowExpiration {
+        timestamp_micros: (row_nanos / 1_000) as i64,
+        ttl_secs: ttl_secs.max(1) as i32,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_compute_row_expiration() {
+        let hour = Duration::from_secs(3600);
+        let now_nanos = 10 * hour.as_nanos() as u64;
+
+        // Written right now.
+        assert_eq!(
+            compute_row_expiration(now_nanos, hour, now_nanos),
+            RowExpiration {
+                timestamp_micros: 36_000_000_000,
+                ttl_secs: 3600,
+            },
+        );
+
+        // Written half an hour ago.
+        assert_eq!(
+            compute_row_expiration(now_nanos - hour.as_nanos() as u64 / 2, hour, now_nanos),
+            RowExpiration {
+                timestamp_micros: 34_200_000_000,
+                ttl_secs: 1800,
+            },
+        );
+
+        // Already past the retention.
+        assert_eq!(
+            compute_row_expiration(now_nanos - 2 * hour.as_nanos() as u64, hour, now_nanos),
+            RowExpiration {
+                timestamp_micros: 28_800_000_000,
+                ttl_secs: 1,
+            },
+        );
+    }
+
+    #[test]
+    fn test_timeseries_write_row_expiration() {
+        let config = TimeseriesWriteConfig {
+            _partition_offset: 0,
+            pks_per_generation: 2,
+            cks_per_pk: 10,
+            start_nanos: SystemTime::UNIX_EPOCH.elapsed().unwrap().as_nanos() as u64,
+            max_rate: 1000,
+            retention: None,
+        };
+        let factory = TimeseriesWriteFactory::new(config.clone()).unwrap();
+        let mut workload = factory.create(0);
+        let (pk, cks) = workload.generate_keys(1).unwrap();
+        assert_eq!(workload.row_expiration(pk, cks[0]), None);
+
+        let factory = TimeseriesWriteFactory::new(TimeseriesWriteConfig {
+            retention: Some(Duration::from_secs(3600)),
+            ..config
+        })
+        .unwrap();
+        let mut workload = factory.create(0);
+        let (pk, cks) = workload.generate_keys(1).unwrap();
+        let expiration = workload.row_expiration(pk, cks[0]).unwrap();
+        assert_eq!(expiration.timestamp_micros, -cks[0] / 1_000);
+        assert!((3590..=3600).contains(&expiration.ttl_secs));
+    }
 }
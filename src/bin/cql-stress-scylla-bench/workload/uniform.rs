@@ -1,29 +1,38 @@
 use std::ops::Range;
 
-use crate::distribution::RngGen;
+use crate::distribution::{RngGen, RowDistribution, RowSampler};
 
 use anyhow::Result;
-use rand::Rng;
-use rand_distr::Distribution;
+use rand::SeedableRng;
 
 use super::{Workload, WorkloadFactory};
 
 /// Creates workloads which write data uniformly.
 pub struct UniformFactory {
     config: UniformConfig,
+    pk_sampler: RowSampler,
+    ck_sampler: RowSampler,
 }
 
 struct Uniform {
     gen: RngGen,
-    pk_distribution: rand_distr::Uniform<u64>,
-    ck_distribution: rand_distr::Uniform<u64>,
+    pk_offset: u64,
+    pk_sampler: RowSampler,
+    ck_offset: u64,
+    ck_sampler: RowSampler,
 }
 
 /// Defines parameters of a uniform workload.
+///
+/// Partition keys are chosen from `pk_range` according to `pk_distribution`,
+/// while clustering keys are chosen from `ck_range` according to `ck_distribution`.
+/// Both distributions are uniform by default.
 #[derive(Clone)]
 pub struct UniformConfig {
     pub pk_range: Range<u64>,
     pub ck_range: Range<u64>,
+    pub pk_distribution: RowDistribution,
+    pub ck_distribution: RowDistribution,
 }
 
 impl UniformFactory {
@@ -37,35 +46,57 @@ impl UniformFactory {
             "Invalid clustering key key range",
         );
 
-        Ok(UniformFactory { config })
+        let pk_sampler = config
+            .pk_distribution
+            .sampler(config.pk_range.end - config.pk_range.start)?;
+        let ck_sampler = config
+            .ck_distribution
+            .sampler(config.ck_range.end - config.ck_range.start)?;
+
+        Ok(UniformFactory {
+            config,
+            pk_sampler,
+            ck_sampler,
+        })
     }
 }
 
 impl WorkloadFactory for UniformFactory {
-    fn create(&self) -> Box<dyn Workload> {
-        Box::new(Uniform::new(self.config.clone()))
+    fn create(&self, seed: u64) -> Box<dyn Workload> {
+        Box::new(Uniform::new(
+            self.config.pk_range.start,
+            self.pk_sampler.clone(),
+            self.config.ck_range.start,
+            self.ck_sampler.clone(),
+            seed,
+        ))
     }
 }
 
 impl Uniform {
     /// Creates a new uniform workload.
-    fn new(config: UniformConfig) -> Uniform {
+    fn new(
+        pk_offset: u64,
+        pk_sampler: RowSampler,
+        ck_offset: u64,
+        ck_sampler: RowSampler,
+        seed: u64,
+    ) -> Uniform {
         Uniform {
-            pk_distribution: config.pk_range.into(),
-            ck_distribution: config.ck_range.into(),
-            gen: RngGen::new(rand::thread_rng().gen()),
+            pk_offset,
+            pk_sampler,
+            ck_offset,
+            ck_sampler,
+            gen: RngGen::seed_from_u64(seed),
         }
     }
 }
 
 impl Workload for Uniform {
     fn generate_keys(&mut self, ck_count: usize) -> Option<(i64, Vec<i64>)> {
-        let pk = self.pk_distribution.sample(&mut self.gen) as i64;
-        let cks = self
-            .ck_distribution
-            .sample_iter(&mut self.gen)
-            .map(|x| x as i64)
-            .take(ck_count)
+        let pk = (self.pk_offset + self.pk_sampler.sample(&mut self.gen)) as i64;
+        let cks = (0..ck_count)
+            .map(|_| (self.ck_offset + self.ck_sampler.sample(&mut self.gen)) as i64)
             .collect();
 
         Some((pk, cks))
@@ -81,7 +112,7 @@ mod test {
     #[test]
     fn test_uniform_workload() {
         let check = |config: UniformConfig, rpk: usize, expected: &[(i64, i64)]| {
-            let mut seq = Uniform::new(config);
+            let mut seq = UniformFactory::new(config).unwrap().create(0);
             let mut actual = HashSet::new();
 
             // Generate 1000 times, hoping that we cover whole range
@@ -100,6 +131,8 @@ mod test {
             UniformConfig {
                 pk_range: (0..3),
                 ck_range: (0..3),
+                pk_distribution: RowDistribution::Uniform,
+                ck_distribution: RowDistribution::Uniform,
             },
             1,
             &[
@@ -119,6 +152,8 @@ mod test {
             UniformConfig {
                 pk_range: (0..3),
                 ck_range: (0..3),
+                pk_distribution: RowDistribution::Uniform,
+                ck_distribution: RowDistribution::Uniform,
             },
             3,
             &[
@@ -134,4 +169,66 @@ mod test {
             ],
         );
     }
+
+    #[test]
+    fn test_uniform_workload_zipf_cks() {
+        let config = UniformConfig {
+            pk_range: (0..1),
+            ck_range: (10..20),
+            pk_distribution: RowDistribution::Uniform,
+            ck_distribution: RowDistribution::Zipf { exponent: 2.0 },
+        };
+        let mut seq = UniformFactory::new(config).unwrap().create(0);
+
+        let mut counts = [0usize; 10];
+        for _ in 0..1000 {
+            let (_, cks) = seq.generate_keys(1).unwrap();
+            assert!((10..20).contains(&cks[0]));
+            counts[cks[0] as usize - 10] += 1;
+        }
+        // The first clustering rows are the hot ones
+        assert!(counts[0] > counts[1]);
+        assert!(counts[1] > counts[9]);
+    }
+
+    #[test]
+    fn test_uniform_workload_normal_pks() {
+        let config = UniformConfig {
+            pk_range: (100..200),
+            ck_range: (0..1),
+            pk_distribution: RowDistribution::Normal { stdev: 0.05 },
+            ck_distribution: RowDistribution::Uniform,
+        };
+        let mut seq = UniformFactory::new(config).unwrap().create(0);
+
+        let mut counts = [0usize; 100];
+        for _ in 0..1000 {
+            let (pk, _) = seq.generate_keys(1).unwrap();
+            assert!((100..200).contains(&pk));
+            counts[pk as usize - 100] += 1;
+        }
+        // The partitions in the middle of the range are the hot ones
+        let hot: usize = counts[40..60].iter().sum();
+        assert!(hot > 900);
+    }
+
+    #[test]
+    fn test_uniform_workload_seed() {
+        let factory = UniformFactory::new(UniformConfig {
+            pk_range: (0..1000),
+            ck_range: (0..1000),
+            pk_distribution: RowDistribution::Uniform,
+            ck_distribution: RowDistribution::Uniform,
+        })
+        .unwrap();
+        let generate = |seed: u64| {
+            let mut seq = factory.create(seed);
+            (0..100)
+                .map(|_| seq.generate_keys(3).unwrap())
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(generate(1), generate(1));
+        assert_ne!(generate(1), generate(2));
+    }
 }
@@ -0,0 +1,158 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+
+use anyhow::Result;
+use rand::{Rng, SeedableRng};
+
+use crate::distribution::RngGen;
+
+use super::{Workload, WorkloadFactory};
+
+struct SharedState {
+    next_pk: AtomicU64,
+    // The next clustering key to be appended to each of the partitions.
+    next_cks: Vec<AtomicU64>,
+}
+
+/// Creates workloads which grow a small set of very wide partitions.
+///
+/// See [WidePartitionConfig] for more detailed information about the workloads'
+/// behavior.
+pub struct WidePartitionFactory {
+    config: WidePartitionConfig,
+    shared_state: Arc<SharedState>,
+}
+
+struct WidePartition {
+    config: WidePartitionConfig,
+    shared_state: Arc<SharedState>,
+    gen: RngGen,
+}
+
+/// Defines parameters of a wide partition workload.
+///
+/// The data set consists of `pks` partitions, numbered `0..pks`. Each
+/// operation appends new clustering rows at the end of one of the partitions,
+/// so the partitions keep growing. The partitions are chosen in a round robin
+/// fashion, so that they grow at a similar pace.
+///
+/// If `max_cks_per_op` is set, each operation appends a random number
+/// of rows from `1..=max_cks_per_op`. Otherwise, it appends the number
+/// of rows requested by the operation.
+///
+/// If `max_cks_per_pk` is set, the partitions stop growing after reaching
+/// that many rows, and the workload ends once all of them are full.
+#[derive(Clone)]
+pub struct WidePartitionConfig {
+    pub pks: u64,
+    pub max_cks_per_op: Option<u64>,
+    pub max_cks_per_pk: Option<u64>,
+}
+
+impl WidePartitionFactory {
+    pub fn new(config: WidePartitionConfig) -> Result<Self> {
+        anyhow::ensure!(config.pks > 0, "Partition count must be greater than zero");
+
+        let shared_state = Arc::new(SharedState {
+            next_pk: AtomicU64::new(0),
+            next_cks: (0..config.pks).map(|_| AtomicU64::new(0)).collect(),
+        });
+
+        Ok(Self {
+            config,
+            shared_state,
+        })
+    }
+}
+
+impl WorkloadFactory for WidePartitionFactory {
+    fn create(&self, seed: u64) -> Box<dyn Workload> {
+        Box::new(WidePartition {
+            config: self.config.clone(),
+            shared_state: self.shared_state.clone(),
+            gen: RngGen::seed_from_u64(seed),
+        })
+    }
+}
+
+impl Workload for WidePartition {
+    fn generate_keys(&mut self, ck_count: usize) -> Option<(i64, Vec<i64>)> {
+        let ck_count = match self.config.max_cks_per_op {
+            Some(max_cks_per_op) => self.gen.gen_range(1..=max_cks_per_op),
+            None => ck_count as u64,
+        };
+
+        // Skip the partitions which are already full.
+        for _ in 0..self.config.pks {
+            let pk = self.shared_state.next_pk.fetch_add(1, Ordering::Relaxed) % self.config.pks;
+            let ck_start =
+                self.shared_state.next_cks[pk as usize].fetch_add(ck_count, Ordering::Relaxed);
+            let ck_end = match self.config.max_cks_per_pk {
+                Some(max_cks_per_pk) => std::cmp::min(ck_start + ck_count, max_cks_per_pk),
+                None => ck_start + ck_count,
+            };
+
+            if ck_start < ck_end {
+                let cks = (ck_start..ck_end).map(|x| x as i64).collect();
+                return Some((pk as i64, cks));
+            }
+        }
+
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_wide_partition_workload() {
+        let factory = WidePartitionFactory::new(WidePartitionConfig {
+            pks: 2,
+            max_cks_per_op: None,
+            max_cks_per_pk: Some(5),
+        })
+        .unwrap();
+        let mut wide = factory.create(0);
+        let mut actual = Vec::new();
+        while let Some((pk, cks)) = wide.generate_keys(2) {
+            actual.push((pk, cks));
+        }
+
+        assert_eq!(
+            actual,
+            [
+                (0, vec![0, 1]),
+                (1, vec![0, 1]),
+                (0, vec![2, 3]),
+                (1, vec![2, 3]),
+                (0, vec![4]),
+                (1, vec![4]),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_wide_partition_workload_random_row_count() {
+        let factory = WidePartitionFactory::new(WidePartitionConfig {
+            pks: 3,
+            max_cks_per_op: Some(4),
+            max_cks_per_pk: None,
+        })
+        .unwrap();
+        let mut wide = factory.create(0);
+        let mut next_cks = [0; 3];
+        for _ in 0..100 {
+            let (pk, cks) = wide.generate_keys(1).unwrap();
+            assert!((1..=4).contains(&cks.len()));
+
+            // The rows are appended at the end of the partition.
+            let expected = (next_cks[pk as usize]..)
+                .take(cks.len())
+                .collect::<Vec<_>>();
+            assert_eq!(cks, expected);
+            next_cks[pk as usize] += cks.len() as i64;
+        }
+    }
+}
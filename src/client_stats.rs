@@ -0,0 +1,102 @@
+//! Statistics of the load generator itself, as opposed to the latencies
+//! of the cluster. If the operations start long after their schedule, or far
+//! fewer of them are in flight than the configured concurrency, the client
+//! is the bottleneck rather than the cluster.
+
+use std::time::Duration;
+
+use hdrhistogram::Histogram;
+
+use crate::sharded_stats::OperationTiming;
+
+pub struct ClientStats {
+    // The delays of the starts of the operations after their schedule, in nanoseconds.
+    queue_delay: Histogram<u64>,
+    // The maximum number of the operations in flight at once.
+    max_in_flight: u64,
+}
+
+impl Default for ClientStats {
+    fn default() -> Self {
+        Self {
+            // Auto-resizing histogram with 3 significant figures, cannot fail.
+            queue_delay: Histogram::new(3).unwrap(),
+            max_in_flight: 0,
+        }
+    }
+}
+
+impl ClientStats {
+    pub fn record(&mut self, timing: &OperationTiming) {
+        self.record_values(timing.queue_delay(), timing.in_flight);
+    }
+
+    pub fn record_values(&mut self, queue_delay: Duration, in_flight: u64) {
+        self.queue_delay
+            .saturating_record(queue_delay.as_nanos() as u64);
+        self.max_in_flight = self.max_in_flight.max(in_flight);
+    }
+
+    pub fn queue_delay(&self) -> &Histogram<u64> {
+        &self.queue_delay
+    }
+
+    pub fn max_in_flight(&self) -> u64 {
+        self.max_in_flight
+    }
+
+    pub fn clear(&mut self) {
+        self.queue_delay.reset();
+        self.max_in_flight = 0;
+    }
+
+    pub fn combine(&mut self, other: &Self) {
+        // Both histograms are auto-resizing, so adding cannot fail.
+        self.queue_delay.add(&other.queue_delay).unwrap();
+        self.max_in_flight = self.max_in_flight.max(other.max_in_flight);
+    }
+
+    /// Returns a one-line description of the stats,
+    /// e.g. "max in-flight: 64, queue delay mean: 0.012ms, 99th: 0.150ms, max: 2.301ms".
+    pub fn breakdown(&self) -> String {
+        let ms = |nanos: f64| nanos * 1e-6;
+        format!(
+            "max in-flight: {}, queue delay mean: {:.3}ms, 99th: {:.3}ms, max: {:.3}ms",
+            self.max_in_flight,
+            ms(self.queue_delay.mean()),
+            ms(self.queue_delay.value_at_quantile(0.99) as f64),
+            ms(self.queue_delay.max() as f64),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::ClientStats;
+
+    #[test]
+    fn test_client_stats() {
+        let mut stats = ClientStats::default();
+        // The values below 2048ns are recorded exactly with 3 significant figures.
+        stats.record_values(Duration::from_nanos(1000), 3);
+        stats.record_values(Duration::ZERO, 5);
+
+        let mut other = ClientStats::default();
+        other.record_values(Duration::from_nanos(2000), 4);
+        stats.combine(&other);
+
+        assert_eq!(5, stats.max_in_flight());
+        assert_eq!(3, stats.queue_delay().len());
+        assert_eq!(2000, stats.queue_delay().max());
+        assert_eq!(
+            "max in-flight: 5, queue delay mean: 0.001ms, 99th: 0.002ms, max: 0.002ms",
+            stats.breakdown()
+        );
+
+        stats.clear();
+        assert_eq!(0, stats.max_in_flight());
+        assert!(stats.queue_delay().is_empty());
+    }
+}
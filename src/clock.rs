@@ -0,0 +1,93 @@
+//! Alignment of the reporting intervals to the wall clock.
+//!
+//! The stats are reported in intervals, which are easier to correlate
+//! with the metrics of the servers if they start at the same wall-clock
+//! boundaries, e.g. at full seconds. The timestamps of the intervals
+//! are derived from the monotonic clock, so they never go backwards,
+//! even if the system clock is adjusted during the benchmark.
+
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use tokio::time::{Instant, Interval};
+
+/// The monotonic and the wall-clock time of the same moment.
+#[derive(Clone, Copy, Debug)]
+pub struct ClockBase {
+    pub instant: Instant,
+    pub system_time: SystemTime,
+}
+
+impl ClockBase {
+    pub fn now() -> Self {
+        Self {
+            instant: Instant::now(),
+            system_time: SystemTime::now(),
+        }
+    }
+
+    /// Returns the wall-clock time of the given moment.
+    pub fn system_time_of(&self, instant: Instant) -> SystemTime {
+        self.system_time + instant.saturating_duration_since(self.instant)
+    }
+}
+
+/// Returns the time from `time` until the next wall-clock multiple of `period`.
+/// If `time` is such a multiple, the next one is taken.
+pub fn until_boundary(time: SystemTime, period: Duration) -> Duration {
+    let since_epoch = time.duration_since(UNIX_EPOCH).unwrap_or_default();
+    let remainder = since_epoch.as_nanos() % period.as_nanos();
+    period - Duration::from_nanos(remainder as u64)
+}
+
+/// Creates an interval which ticks at the wall-clock multiples of `period`.
+///
+/// Unlike [tokio::time::interval], the first tick doesn't complete immediately,
+/// but at the first boundary, so the first interval is shorter than `period`.
+pub fn aligned_interval(period: Duration) -> Interval {
+    let base = ClockBase::now();
+    let start = base.instant + until_boundary(base.system_time, period);
+    tokio::time::interval_at(start, period)
+}
+
+/// Waits for the next tick of the interval, or forever if there is none,
+/// so that the optional intervals can be polled in `tokio::select!`.
+pub async fn tick_optional(interval: &mut Option<Interval>) -> Instant {
+    match interval {
+        Some(interval) => interval.tick().await,
+        None => std::future::pending().await,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, UNIX_EPOCH};
+
+    use super::{until_boundary, ClockBase};
+
+    #[test]
+    fn test_until_boundary() {
+        let sec = Duration::from_secs(1);
+        let at = |millis| UNIX_EPOCH + Duration::from_millis(millis);
+
+        assert_eq!(Duration::from_millis(750), until_boundary(at(10_250), sec));
+        assert_eq!(sec, until_boundary(at(10_000), sec));
+        assert_eq!(
+            Duration::from_millis(4_750),
+            until_boundary(at(10_250), 5 * sec)
+        );
+    }
+
+    #[test]
+    fn test_system_time_of() {
+        let base = ClockBase::now();
+        let later = base.instant + Duration::from_millis(1500);
+        assert_eq!(
+            base.system_time + Duration::from_millis(1500),
+            base.system_time_of(later)
+        );
+        // The earlier moments are clamped to the base.
+        if let Some(earlier) = base.instant.checked_sub(Duration::from_secs(1)) {
+            assert_eq!(base.system_time, base.system_time_of(earlier));
+        }
+    }
+}
@@ -0,0 +1,277 @@
+//! A snapshot of the cluster: its name, and the location, the state and
+//! the version of each of its nodes. Taken at the start and at the end of
+//! the run, it helps to attribute the changes of the performance during
+//! the run to e.g. a rolling upgrade or a node which went down.
+
+use std::collections::BTreeMap;
+use std::io::Write;
+use std::net::IpAddr;
+use std::sync::Arc;
+
+use scylla::load_balancing::{FallbackPlan, LoadBalancingPolicy, RoutingInfo};
+use scylla::query::Query;
+use scylla::routing::Shard;
+use scylla::transport::{ClusterData, Node, NodeRef};
+use scylla::{ExecutionProfile, Session};
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct NodeInfo {
+    pub address: IpAddr,
+    pub datacenter: Option<String>,
+    pub rack: Option<String>,
+    /// Whether the driver considers the node to be up.
+    pub up: bool,
+    /// The version of ScyllaDB (or the release version of Cassandra).
+    /// None if the node is down or it couldn't be queried.
+    pub version: Option<String>,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ClusterInfo {
+    pub name: Option<String>,
+    /// Sorted by the address.
+    pub nodes: Vec<NodeInfo>,
+}
+
+impl ClusterInfo {
+    /// Takes the snapshot of the cluster known to the session. The topology
+    /// comes from the metadata of the driver, while the name and the versions
+    /// are queried from each of the nodes which are up.
+    pub async fn fetch(session: &Session) -> Self {
+        let cluster_data = session.get_cluster_data();
+        let mut name = None;
+        let mut nodes = Vec::new();
+        for node in cluster_data.get_nodes_info() {
+            let up = !node.is_down();
+            let (node_name, version) = if up {
+                query_node(session, node).await
+            } else {
+                (None, None)
+            };
+            name = name.or(node_name);
+            nodes.push(NodeInfo {
+                address: node.address.ip(),
+                datacenter: node.datacenter.clone(),
+                rack: node.rack.clone(),
+                up,
+                version,
+            });
+        }
+        nodes.sort_by_key(|node| node.address);
+        Self { name, nodes }
+    }
+
+    /// Writes the snapshot, one line per node, under the given title.
+    pub fn write(&self, title: &str, out: &mut impl Write) -> std::io::Result<()> {
+        let down = self.nodes.iter().filter(|node| !node.up).count();
+        writeln!(
+            out,
+            "{}: {} ({} nodes, {} down)",
+            title,
+            self.name.as_deref().unwrap_or("unknown"),
+            self.nodes.len(),
+            down
+        )?;
+        for node in &self.nodes {
+            writeln!(
+                out,
+                "  {}: {}/{}, version {}, {}",
+                node.address,
+                node.datacenter.as_deref().unwrap_or("unknown"),
+                node.rack.as_deref().unwrap_or("unknown"),
+                node.version.as_deref().unwrap_or("unknown"),
+                if node.up { "up" } else { "down" }
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Writes the changes since the earlier snapshot (see [Self::changes_since]), if any.
+    pub fn write_changes_since(&self, earlier: &Self, out: &mut impl Write) -> std::io::Result<()> {
+        let changes = self.changes_since(earlier);
+        if !changes.is_empty() {
+            writeln!(out, "Changes during the run:")?;
+            for change in changes {
+                writeln!(out, "  {}", change)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Describes how the cluster changed since the earlier snapshot:
+    /// the nodes which joined or left it, went down or up, or changed their version.
+    pub fn changes_since(&self, earlier: &Self) -> Vec<String> {
+        let by_address = |info: &Self| {
+            info.nodes
+                .iter()
+                .map(|node| (node.address, node.clone()))
+                .collect::<BTreeMap<_, _>>()
+        };
+        let before = by_address(earlier);
+        let after = by_address(self);
+
+        let mut changes = Vec::new();
+        for (address, node) in &after {
+            let Some(previous) = before.get(address) else {
+                changes.push(format!("{} joined", address));
+                continue;
+            };
+            if previous.up != node.up {
+                let state = if node.up { "came up" } else { "went down" };
+                changes.push(format!("{} {}", address, state));
+            }
+            // The version is unknown while the node is down.
+            if let (Some(from), Some(to)) = (&previous.version, &node.version) {
+                if from != to {
+                    changes.push(format!(
+                        "{} changed version from {} to {}",
+                        address, from, to
+                    ));
+                }
+            }
+        }
+        for address in before.keys().filter(|address| !after.contains_key(address)) {
+            changes.push(format!("{} left", address));
+        }
+        changes
+    }
+}
+
+// Queries the name of the cluster and the version of the given node.
+// ScyllaDB reports a Cassandra compatible `release_version` in `system.local`,
+// so its own version is read from `system.versions`, if present.
+async fn query_node(session: &Session, node: &Arc<Node>) -> (Option<String>, Option<String>) {
+    let profile = ExecutionProfile::builder()
+        .load_balancing_policy(Arc::new(SingleNodePolicy(Arc::clone(node))))
+        .build()
+        .into_handle();
+    let query = |cql: &str| {
+        let mut query = Query::new(cql);
+        query.set_execution_profile_handle(Some(profile.clone()));
+        query
+    };
+
+    let local = match session
+        .query_unpaged(
+            query("SELECT cluster_name, release_version FROM system.local WHERE key = 'local'"),
+            (),
+        )
+        .await
+    {
+        Ok(result) => result
+            .single_row_typed::<(Option<String>, Option<String>)>()
+            .ok(),
+        Err(_) => None,
+    };
+    let (name, release_version) = local.unwrap_or_default();
+
+    let scylla_version = match session
+        .query_unpaged(
+            query("SELECT version FROM system.versions WHERE key = 'local'"),
+            (),
+        )
+        .await
+    {
+        Ok(result) => result
+            .single_row_typed::<(Option<String>,)>()
+            .ok()
+            .and_then(|(version,)| version),
+        Err(_) => None,
+    };
+
+    (name, scylla_version.or(release_version))
+}
+
+/// Sends the queries to the given node only, without a fallback.
+#[derive(Debug)]
+struct SingleNodePolicy(Arc<Node>);
+
+impl LoadBalancingPolicy for SingleNodePolicy {
+    fn pick<'a>(
+        &'a self,
+        _query: &'a RoutingInfo,
+        _cluster: &'a ClusterData,
+    ) -> Option<(NodeRef<'a>, Option<Shard>)> {
+        Some((&self.0, None))
+    }
+
+    fn fallback<'a>(
+        &'a self,
+        _query: &'a RoutingInfo,
+        _cluster: &'a ClusterData,
+    ) -> FallbackPlan<'a> {
+        Box::new(std::iter::empty())
+    }
+
+    fn name(&self) -> String {
+        format!("SingleNodePolicy({})", self.0.address.ip())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::IpAddr;
+
+    use super::{ClusterInfo, NodeInfo};
+
+    fn node(address: &str, up: bool, version: Option<&str>) -> NodeInfo {
+        NodeInfo {
+            address: address.parse::<IpAddr>().unwrap(),
+            datacenter: Some(String::from("dc1")),
+            rack: Some(String::from("rack1")),
+            up,
+            version: version.map(String::from),
+        }
+    }
+
+    #[test]
+    fn write_test() {
+        let info = ClusterInfo {
+            name: Some(String::from("Test Cluster")),
+            nodes: vec![
+                node("127.0.0.1", true, Some("6.0.0")),
+                node("127.0.0.2", false, None),
+            ],
+        };
+        let mut out = Vec::new();
+        info.write("Cluster at the start", &mut out).unwrap();
+        assert_eq!(
+            "Cluster at the start: Test Cluster (2 nodes, 1 down)\n\
+            \x20 127.0.0.1: dc1/rack1, version 6.0.0, up\n\
+            \x20 127.0.0.2: dc1/rack1, version unknown, down\n",
+            String::from_utf8(out).unwrap()
+        );
+    }
+
+    #[test]
+    fn changes_since_test() {
+        let start = ClusterInfo {
+            name: Some(String::from("Test Cluster")),
+            nodes: vec![
+                node("127.0.0.1", true, Some("5.4.0")),
+                node("127.0.0.2", true, Some("5.4.0")),
+                node("127.0.0.3", true, Some("5.4.0")),
+            ],
+        };
+        assert!(start.changes_since(&start).is_empty());
+
+        let end = ClusterInfo {
+            name: Some(String::from("Test Cluster")),
+            nodes: vec![
+                node("127.0.0.1", true, Some("6.0.0")),
+                // The version of a node which is down is unknown.
+                node("127.0.0.2", false, None),
+                node("127.0.0.4", true, Some("6.0.0")),
+            ],
+        };
+        assert_eq!(
+            vec![
+                "127.0.0.1 changed version from 5.4.0 to 6.0.0",
+                "127.0.0.2 went down",
+                "127.0.0.4 joined",
+                "127.0.0.3 left",
+            ],
+            end.changes_since(&start)
+        );
+    }
+}
@@ -0,0 +1,67 @@
+//! Payloads of a given compressibility, so that the benchmarks sensitive
+//! to the compression ratio don't have to write random bytes only.
+
+use anyhow::{Context, Result};
+
+/// Size of the segments the payload is divided into. Each of them starts
+/// with its random bytes, followed by the zeros of its compressible part,
+/// which are compressed by the block compressors (e.g. LZ4) to almost nothing.
+const SEGMENT_SIZE: usize = 256;
+
+/// Replaces the `compressible` fraction of each segment of the random
+/// `payload` with zeros, so that it's compressed to about `1 - compressible`
+/// of its size. The fraction 0 leaves the payload random, while 1 zeroes it.
+pub fn make_compressible(payload: &mut [u8], compressible: f64) {
+    let random_len = ((1.0 - compressible) * SEGMENT_SIZE as f64).round() as usize;
+    for segment in payload.chunks_mut(SEGMENT_SIZE) {
+        if random_len < segment.len() {
+            segment[random_len..].fill(0);
+        }
+    }
+}
+
+/// Parses the compressible fraction of the payload, in [0, 1].
+pub fn parse_compressible(s: &str) -> Result<f64> {
+    let compressible: f64 = s
+        .parse()
+        .with_context(|| format!("Invalid compressible fraction: {}", s))?;
+    anyhow::ensure!(
+        (0.0..=1.0).contains(&compressible),
+        "The compressible fraction must be in [0, 1], got {}",
+        s
+    );
+    Ok(compressible)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{make_compressible, parse_compressible, SEGMENT_SIZE};
+
+    #[test]
+    fn make_compressible_test() {
+        let zeros = |payload: &[u8]| payload.iter().filter(|b| **b == 0).count();
+
+        let mut payload = vec![0xff; 1000];
+        make_compressible(&mut payload, 0.0);
+        assert_eq!(0, zeros(&payload));
+
+        make_compressible(&mut payload, 0.25);
+        // Each of the full segments consists of 192 random bytes and 64 zeros.
+        assert_eq!([0xff; 192], payload[..192]);
+        assert_eq!([0; 64], payload[192..SEGMENT_SIZE]);
+        // The last one of 232 bytes is shorter.
+        assert_eq!(3 * 64 + 40, zeros(&payload));
+
+        make_compressible(&mut payload, 1.0);
+        assert_eq!(payload.len(), zeros(&payload));
+    }
+
+    #[test]
+    fn parse_compressible_test() {
+        assert_eq!(0.5, parse_compressible("0.5").unwrap());
+        assert_eq!(1.0, parse_compressible("1").unwrap());
+        assert!(parse_compressible("-0.1").is_err());
+        assert!(parse_compressible("1.5").is_err());
+        assert!(parse_compressible("half").is_err());
+    }
+}
@@ -2,6 +2,7 @@ use std::sync::Arc;
 use std::time::Duration;
 
 use anyhow::Result;
+use rand::Rng;
 use tokio::time::Instant;
 
 use crate::run::WorkerSession;
@@ -17,7 +18,102 @@ pub struct Configuration {
     /// If `None`, the test duration is unlimited.
     pub max_duration: Option<Duration>,
 
-    /// The concurrency with which the benchmark operations will be performed.
+    /// The maximum number of operations to be performed by each of the streams.
+    ///
+    /// The run finishes after all of the operations were performed,
+    /// or after `max_duration` - whichever comes first.
+    ///
+    /// If `None`, the number of operations is unlimited.
+    pub max_operations: Option<u64>,
+
+    /// The groups of workers performing the benchmark operations.
+    ///
+    /// The streams run concurrently and independently of each other,
+    /// e.g. each of them has its own rate limit and its own sequence
+    /// of operation IDs.
+    ///
+    /// Must not be empty.
+    pub streams: Vec<StreamConfiguration>,
+
+    /// The maximum number of attempts an operation should be retried
+    /// before giving up.
+    pub max_retries_per_op: usize,
+
+    /// The maximum duration of a single operation attempt.
+    ///
+    /// An attempt which takes longer is cancelled and treated as a failed
+    /// one, i.e. it is retried according to `max_retries_per_op`. It is
+    /// reported to [OperationFactory::record_timeout] of its stream.
+    ///
+    /// It covers the whole attempt, which can consist of several requests,
+    /// so it should be longer than the request timeout of the driver.
+    /// Otherwise the attempts are cancelled before the driver reports
+    /// the timeout of the request.
+    ///
+    /// If `None`, the operations are not timed out by the tool.
+    pub operation_timeout: Option<Duration>,
+
+    /// How long the workers wait before retrying a failed operation.
+    pub retry_backoff: RetryBackoff,
+
+    /// The maximum number of retries per second, shared by all of the streams.
+    ///
+    /// Up to a second's worth of retries can be made at once. An operation
+    /// which fails when the budget is exhausted is not retried, as if it ran
+    /// out of `max_retries_per_op`, so that the retries don't multiply
+    /// the load of a struggling cluster.
+    ///
+    /// If `None`, the retries are limited only by `max_retries_per_op`.
+    /// Must be positive.
+    pub retry_budget: Option<f64>,
+
+    /// The maximum number of operation attempts executed at the same time
+    /// by all of the streams.
+    ///
+    /// The workers which would exceed it wait until one of the attempts
+    /// in flight finishes. This allows running more workers than the number
+    /// of requests the cluster should see at once, e.g. so that the workers
+    /// keep up with the schedule of the rate limit.
+    ///
+    /// If `None`, the number of attempts in flight is limited only
+    /// by the concurrency of the streams. Must not be zero.
+    pub max_in_flight: Option<usize>,
+
+    /// The number of dedicated runtimes the workers are spread between.
+    ///
+    /// Each runtime is single-threaded and runs on its own thread,
+    /// and the workers of each stream are split evenly between them.
+    /// Each group of workers has its own rate limiter with its share
+    /// of the rate of the stream, so that the workers on different threads
+    /// don't contend for it. It's useful at very high rates, when a single
+    /// shared runtime becomes the bottleneck.
+    ///
+    /// If `None`, the workers are spawned on the runtime of the caller.
+    /// Must not be zero.
+    pub runtimes: Option<usize>,
+
+    /// Alternates the windows in which the load runs with the ones
+    /// in which the workers are paused, starting with the former.
+    ///
+    /// If `None`, the load runs for the whole duration of the test.
+    pub duty_cycle: Option<DutyCycle>,
+}
+
+/// Returns the [Configuration::operation_timeout] of the operations whose
+/// requests are limited by `request_timeout` of the driver.
+///
+/// It's twice as long, so that the driver reports the timeout of a request
+/// first, and an operation of a couple of requests isn't cancelled early.
+pub fn operation_timeout(request_timeout: Duration) -> Duration {
+    request_timeout.saturating_mul(2)
+}
+
+/// Defines one of the groups of workers of a benchmark.
+pub struct StreamConfiguration {
+    /// The name identifying the stream, e.g. in the stats.
+    pub name: String,
+
+    /// The concurrency with which the operations of the stream will be performed.
     ///
     /// The tool will spawn as many tokio tasks as this number specifies,
     /// and each task will sequentially perform the benchmark operations.
@@ -25,17 +121,123 @@ pub struct Configuration {
     /// Must not be zero.
     pub concurrency: u64,
 
-    /// The maximum number of operations to be performed per second.
+    /// The maximum number of operations of the stream to be performed per second.
     /// If `None`, then there is no rate limit imposed.
     pub rate_limit_per_second: Option<f64>,
 
-    /// A factory which creates operations that will be executed'
-    /// during the stress.
+    /// How the rate limit treats the operations which couldn't be started
+    /// on time. Ignored if there is no rate limit.
+    pub rate_limit_mode: RateLimitMode,
+
+    /// How the start times of the rate limited operations are spread.
+    /// Ignored if there is no rate limit.
+    pub arrival: Arrival,
+
+    /// A factory which creates operations that will be executed
+    /// by the workers of the stream.
     pub operation_factory: Arc<dyn OperationFactory>,
+}
 
-    /// The maximum number of attempts an operation should be retried
-    /// before giving up.
-    pub max_retries_per_op: usize,
+/// Determines what happens when the workers fall behind the rate limit,
+/// e.g. because the cluster is overloaded.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum RateLimitMode {
+    /// The operations are scheduled at fixed intervals since the start
+    /// of the benchmark. The operations which couldn't be started on time
+    /// are started as soon as possible, until the workers catch up with
+    /// the schedule.
+    #[default]
+    Schedule,
+
+    /// The rate limit only caps the throughput. The slots which were missed
+    /// are skipped, so the workers never run faster than the rate
+    /// to catch up with them.
+    Throttle,
+}
+
+/// The distribution of the intervals between the start times
+/// of the rate limited operations.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Arrival {
+    /// The operations start at equal intervals.
+    #[default]
+    Uniform,
+
+    /// The intervals are drawn from the exponential distribution with
+    /// the mean implied by the rate, so that the operations arrive like
+    /// the independent requests of many clients (a Poisson process).
+    Poisson,
+}
+
+impl std::fmt::Display for Arrival {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Uniform => write!(f, "uniform"),
+            Self::Poisson => write!(f, "poisson"),
+        }
+    }
+}
+
+/// The windows of a [Configuration::duty_cycle], e.g. to let the cluster
+/// catch up with its compaction backlog between the bursts of load.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct DutyCycle {
+    /// How long the load runs in each cycle. Must not be zero.
+    pub on: Duration,
+
+    /// How long the workers are paused in each cycle. Must not be zero.
+    pub off: Duration,
+}
+
+impl std::fmt::Display for DutyCycle {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:?} on, {:?} off", self.on, self.off)
+    }
+}
+
+/// The delay between the attempts of the same operation.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum RetryBackoff {
+    /// The failed operations are retried immediately.
+    #[default]
+    None,
+
+    /// The workers wait for the same time before each retry.
+    Fixed(Duration),
+
+    /// The delay starts at `min` and doubles with each retry of the operation,
+    /// up to `max`. The actual delay is drawn uniformly between the half
+    /// of it and all of it, so that the workers which failed at the same time
+    /// don't retry at the same time either.
+    Exponential { min: Duration, max: Duration },
+}
+
+impl RetryBackoff {
+    /// Returns the delay before the given retry of an operation,
+    /// counting from 1.
+    pub fn delay(&self, retry: usize) -> Duration {
+        match *self {
+            Self::None => Duration::ZERO,
+            Self::Fixed(delay) => delay,
+            Self::Exponential { min, max } => {
+                let doublings = retry.saturating_sub(1).min(31) as u32;
+                let delay = min
+                    .checked_mul(1u32 << doublings)
+                    .map_or(max, |delay| delay.min(max));
+                delay.mul_f64(rand::thread_rng().gen_range(0.5..=1.0))
+            }
+        }
+    }
+}
+
+impl std::fmt::Display for RetryBackoff {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::None => write!(f, "none"),
+            Self::Fixed(delay) => write!(f, "fixed {:?}", delay),
+            Self::Exponential { min, max } => write!(f, "exponential {:?}..{:?}", min, max),
+        }
+    }
 }
 
 /// Contains all necessary context needed to execute an Operation.
@@ -66,6 +268,14 @@ pub struct OperationContext {
     /// with configured rate, this will be either equal or close
     /// to `scheduled_start_time`.
     pub actual_start_time: Instant,
+
+    /// The number of the operations in flight when this one started,
+    /// including itself, across all of the workers of the run.
+    ///
+    /// If it stays well below the number of the workers, the workers
+    /// spend their time outside of the operations, e.g. waiting for
+    /// the rate limiter or the `max_in_flight` limit.
+    pub in_flight: u64,
 }
 
 /// Creates operations which can later be used by workers during the stress.
@@ -75,6 +285,13 @@ pub trait OperationFactory: Send + Sync {
     /// The single operation will be used from within a single worker.
     /// It can have its own state.
     fn create(&self) -> Box<dyn Operation>;
+
+    /// Records an attempt of an operation cancelled by the runner
+    /// because it exceeded [Configuration::operation_timeout].
+    ///
+    /// The cancelled operation doesn't get to record its result, so the
+    /// factories which gather stats should count the error here.
+    fn record_timeout(&self, _ctx: &OperationContext, _err: &anyhow::Error) {}
 }
 
 /// Represents an operation which runs its own operation loop.
@@ -109,14 +326,22 @@ pub trait Operation: Send + Sync {
 /// putting returned futures in a Box due to current language limitations.
 /// Boxing the futures imply an allocation per operation and those allocations
 /// can be clearly visible on the flamegraphs.
+///
+/// The optional second argument names the [StatsBuffer](crate::sharded_stats::StatsBuffer)
+/// field of the operation, e.g. `make_runnable!(ReadOperation, stats)`,
+/// which is flushed when the worker waits for its next operation,
+/// e.g. while the run is paused or rate limited.
 #[macro_export]
 macro_rules! make_runnable {
-    ($op:ident$(<$($targ:tt: $tbound:tt),+>)?) => {
+    ($op:ident$(<$($targ:tt: $tbound:tt),+>)? $(, $stats:ident)?) => {
         #[async_trait]
         impl$(<$($targ: $tbound),+>)? $crate::configuration::Operation for $op$(<$($targ),*>)? {
             async fn run(&mut self, mut session: $crate::run::WorkerSession) -> anyhow::Result<()> {
-                while let Some(ctx) = session.start_operation().await {
-                    let result = self.execute(&ctx).await;
+                while let Some(ctx) = session
+                    .start_operation(|_until| { $(self.$stats.flush_before_wait(_until);)? })
+                    .await
+                {
+                    let result = session.run_operation(&ctx, self.execute(&ctx)).await;
                     if let std::ops::ControlFlow::Break(_) = session.end_operation(result)? {
                         return Ok(());
                     }
@@ -0,0 +1,260 @@
+//! Changing the rate and the duration of a run while it runs.
+//!
+//! A control file is polled for changes during the run. It consists of
+//! `key = value` lines, `#` starts a comment:
+//!
+//! ```text
+//! # The total number of operations started per second, or "unlimited".
+//! rate = 5000
+//! # The time left until the run stops, counted from the moment
+//! # the change is noticed, or "unlimited".
+//! remaining_duration = 10m
+//! ```
+//!
+//! Both keys are optional. The contents of the file at the start of the run
+//! are not applied, only the values which change later are. A key which is
+//! removed from the file leaves the last applied value in place.
+
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Weak};
+use std::time::Duration;
+
+use anyhow::{Context, Result};
+use tokio::time::Instant;
+
+use crate::duration;
+use crate::run::RunController;
+
+/// How often the control file is read.
+pub const POLL_PERIOD: Duration = Duration::from_secs(1);
+
+/// A value of the control file which can be lifted.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Limit<T> {
+    Unlimited,
+    Limited(T),
+}
+
+/// The contents of the control file. A key missing from the file is `None`.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct ControlSettings {
+    pub rate: Option<Limit<f64>>,
+    pub remaining_duration: Option<Limit<Duration>>,
+}
+
+impl ControlSettings {
+    pub fn parse(contents: &str) -> Result<Self> {
+        let mut settings = Self::default();
+        for (idx, line) in contents.lines().enumerate() {
+            let line = line.split('#').next().unwrap().trim();
+            if line.is_empty() {
+                continue;
+            }
+            settings
+                .parse_line(line)
+                .with_context(|| format!("Invalid line {} of the control file", idx + 1))?;
+        }
+        Ok(settings)
+    }
+
+    fn parse_line(&mut self, line: &str) -> Result<()> {
+        let (key, value) = line
+            .split_once('=')
+            .context("Expected a line in the form of key = value")?;
+        let (key, value) = (key.trim(), value.trim());
+        match key {
+            "rate" => {
+                anyhow::ensure!(self.rate.is_none(), "Duplicate key rate");
+                self.rate = Some(parse_limit(value, parse_rate)?);
+            }
+            "remaining_duration" => {
+                anyhow::ensure!(
+                    self.remaining_duration.is_none(),
+                    "Duplicate key remaining_duration"
+                );
+                self.remaining_duration = Some(parse_limit(value, parse_duration)?);
+            }
+            _ => anyhow::bail!("Unknown key {}", key),
+        }
+        Ok(())
+    }
+}
+
+fn parse_limit<T>(value: &str, parse: impl Fn(&str) -> Result<T>) -> Result<Limit<T>> {
+    if value == "unlimited" {
+        Ok(Limit::Unlimited)
+    } else {
+        parse(value).map(Limit::Limited)
+    }
+}
+
+fn parse_rate(value: &str) -> Result<f64> {
+    let rate = value
+        .parse::<f64>()
+        .with_context(|| format!("Invalid rate {}", value))?;
+    anyhow::ensure!(
+        rate.is_finite() && rate > 0.0,
+        "Rate must be a positive number"
+    );
+    Ok(rate)
+}
+
+// Parses a duration with units, e.g. "1h30m" (see [duration::parse_duration]),
+// or a number of seconds without a unit.
+fn parse_duration(value: &str) -> Result<Duration> {
+    if let Ok(seconds) = value.parse::<u64>() {
+        return Ok(Duration::from_secs(seconds));
+    }
+    duration::parse_duration(value)
+}
+
+/// Spawns a task polling the control file and applying the changes
+/// of its values to the run. The task finishes with the run,
+/// once the controller is dropped.
+pub fn watch(path: PathBuf, controller: &Arc<RunController>) {
+    let weak = Arc::downgrade(controller);
+    tokio::task::spawn(async move {
+        let mut watcher = Watcher {
+            applied: ControlSettings::default(),
+            last_error: None,
+        };
+        // The initial contents are the baseline, not a change.
+        if let Ok(settings) = read(&path).await {
+            watcher.applied = settings;
+        }
+
+        let mut ticker = tokio::time::interval(POLL_PERIOD);
+        ticker.tick().await;
+        loop {
+            ticker.tick().await;
+            let controller = match Weak::upgrade(&weak) {
+                Some(controller) => controller,
+                None => break,
+            };
+            match read(&path).await {
+                Ok(settings) => {
+                    watcher.last_error = None;
+                    watcher.apply(&settings, &controller);
+                }
+                Err(err) => watcher.report_error(&path, err),
+            }
+        }
+    });
+}
+
+async fn read(path: &Path) -> Result<ControlSettings> {
+    match tokio::fs::read_to_string(path).await {
+        Ok(contents) => ControlSettings::parse(&contents),
+        // The file may be created later.
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(ControlSettings::default()),
+        Err(err) => Err(err.into()),
+    }
+}
+
+struct Watcher {
+    applied: ControlSettings,
+    // The same error is printed only once, not at each poll.
+    last_error: Option<String>,
+}
+
+impl Watcher {
+    fn apply(&mut self, settings: &ControlSettings, controller: &RunController) {
+        if let Some(rate) = settings
+            .rate
+            .filter(|rate| Some(*rate) != self.applied.rate)
+        {
+            match rate {
+                Limit::Limited(rate) => {
+                    println!("Control file: the rate is now limited to {} op/s", rate);
+                    controller.set_total_rate_limit(Some(rate));
+                }
+                Limit::Unlimited => {
+                    println!("Control file: the rate is now unlimited");
+                    controller.set_total_rate_limit(None);
+                }
+            }
+            self.applied.rate = Some(rate);
+        }
+
+        if let Some(remaining) = settings
+            .remaining_duration
+            .filter(|remaining| Some(*remaining) != self.applied.remaining_duration)
+        {
+            match remaining {
+                Limit::Limited(remaining) => {
+                    println!("Control file: the run stops in {}s", remaining.as_secs());
+                    controller.set_deadline(Some(Instant::now() + remaining));
+                }
+                Limit::Unlimited => {
+                    println!("Control file: the run continues until it's stopped");
+                    controller.set_deadline(None);
+                }
+            }
+            self.applied.remaining_duration = Some(remaining);
+        }
+    }
+
+    fn report_error(&mut self, path: &Path, err: anyhow::Error) {
+        let message = format!("{:#}", err);
+        if self.last_error.as_ref() != Some(&message) {
+            eprintln!(
+                "Failed to read the control file {}: {}",
+                path.display(),
+                message
+            );
+            self.last_error = Some(message);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        let settings = ControlSettings::parse(
+            "# The rate.\nrate = 5000\n\n  remaining_duration=1h30m  # Comment\n",
+        )
+        .unwrap();
+        assert_eq!(Some(Limit::Limited(5000.0)), settings.rate);
+        assert_eq!(
+            Some(Limit::Limited(Duration::from_secs(5400))),
+            settings.remaining_duration
+        );
+
+        let settings = ControlSettings::parse("rate = unlimited").unwrap();
+        assert_eq!(Some(Limit::Unlimited), settings.rate);
+        assert_eq!(None, settings.remaining_duration);
+
+        assert_eq!(
+            ControlSettings::default(),
+            ControlSettings::parse("").unwrap()
+        );
+
+        assert!(ControlSettings::parse("rate").is_err());
+        assert!(ControlSettings::parse("rate = 0").is_err());
+        assert!(ControlSettings::parse("rate = fast").is_err());
+        assert!(ControlSettings::parse("rate = 10\nrate = 20").is_err());
+        assert!(ControlSettings::parse("duration = 10s").is_err());
+        assert!(ControlSettings::parse("remaining_duration = 10x").is_err());
+    }
+
+    #[test]
+    fn test_parse_duration() {
+        assert_eq!(Duration::from_secs(90), parse_duration("90").unwrap());
+        assert_eq!(Duration::from_secs(90), parse_duration("90s").unwrap());
+        assert_eq!(Duration::from_secs(600), parse_duration("10m").unwrap());
+        assert_eq!(Duration::from_secs(3630), parse_duration("1h30s").unwrap());
+        assert_eq!(Duration::from_secs(86_400), parse_duration("1d").unwrap());
+        assert_eq!(Duration::from_secs(5400), parse_duration("1.5h").unwrap());
+
+        assert!(parse_duration("").is_err());
+        assert!(parse_duration("10m5").is_err());
+        assert!(parse_duration("m").is_err());
+        assert!(parse_duration("-1s").is_err());
+        assert!(parse_duration("99999999999999999999h").is_err());
+    }
+}
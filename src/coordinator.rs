@@ -0,0 +1,192 @@
+//! Attributing the operations to the nodes which coordinated them.
+//!
+//! The driver doesn't return the coordinator along with the result,
+//! but it reports the node of each attempt to the history listener
+//! of the statement. Each operation sets its own [LastCoordinator] on its
+//! statements and takes the coordinator once it completes, so that
+//! the operations and the errors are counted per node by [NodeCounts].
+
+use std::collections::HashMap;
+use std::net::SocketAddr;
+
+use parking_lot::Mutex;
+use scylla::history::{AttemptId, HistoryListener, QueryId, SpeculativeId};
+use scylla::retry_policy::RetryDecision;
+use scylla::transport::errors::QueryError;
+
+/// Remembers the coordinator of the queries executed by a single operation,
+/// since the last call to [LastCoordinator::take].
+///
+/// The coordinator is the node of the last successful attempt, or of the last
+/// failed one if none of them succeeded, e.g. after the retries on other nodes.
+#[derive(Debug, Default)]
+pub struct LastCoordinator(Mutex<Attempts>);
+
+#[derive(Debug, Default)]
+struct Attempts {
+    // The nodes of the attempts, indexed by their ids.
+    nodes: Vec<SocketAddr>,
+    succeeded: Option<SocketAddr>,
+    failed: Option<SocketAddr>,
+}
+
+impl LastCoordinator {
+    pub fn take(&self) -> Option<SocketAddr> {
+        let attempts = std::mem::take(&mut *self.0.lock());
+        attempts.succeeded.or(attempts.failed)
+    }
+}
+
+impl HistoryListener for LastCoordinator {
+    fn log_query_start(&self) -> QueryId {
+        QueryId(0)
+    }
+
+    fn log_query_success(&self, _query_id: QueryId) {}
+
+    fn log_query_error(&self, _query_id: QueryId, _error: &QueryError) {}
+
+    fn log_new_speculative_fiber(&self, _query_id: QueryId) -> SpeculativeId {
+        SpeculativeId(0)
+    }
+
+    fn log_attempt_start(
+        &self,
+        _query_id: QueryId,
+        _speculative_id: Option<SpeculativeId>,
+        node_addr: SocketAddr,
+    ) -> AttemptId {
+        let mut attempts = self.0.lock();
+        attempts.nodes.push(node_addr);
+        AttemptId(attempts.nodes.len() - 1)
+    }
+
+    fn log_attempt_success(&self, attempt_id: AttemptId) {
+        let mut attempts = self.0.lock();
+        attempts.succeeded = attempts.nodes.get(attempt_id.0).copied();
+    }
+
+    fn log_attempt_error(
+        &self,
+        attempt_id: AttemptId,
+        _error: &QueryError,
+        _retry_decision: &RetryDecision,
+    ) {
+        let mut attempts = self.0.lock();
+        attempts.failed = attempts.nodes.get(attempt_id.0).copied();
+    }
+}
+
+/// The numbers of the operations and of the errors coordinated by each node.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct NodeCounts(HashMap<SocketAddr, NodeCount>);
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct NodeCount {
+    pub operations: u64,
+    pub errors: u64,
+}
+
+impl NodeCounts {
+    pub fn record(&mut self, node: SocketAddr, failed: bool) {
+        let count = self.0.entry(node).or_default();
+        count.operations += 1;
+        if failed {
+            count.errors += 1;
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.0.clear();
+    }
+
+    pub fn combine(&mut self, other: &Self) {
+        for (node, other_count) in &other.0 {
+            let count = self.0.entry(*node).or_default();
+            count.operations += other_count.operations;
+            count.errors += other_count.errors;
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Returns the counts of the nodes, sorted by their addresses.
+    pub fn sorted(&self) -> Vec<(SocketAddr, NodeCount)> {
+        let mut counts: Vec<_> = self.0.iter().map(|(node, count)| (*node, *count)).collect();
+        counts.sort_by_key(|(node, _)| *node);
+        counts
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::SocketAddr;
+
+    use scylla::history::{HistoryListener, QueryId};
+    use scylla::retry_policy::RetryDecision;
+    use scylla::transport::errors::QueryError;
+
+    use super::{LastCoordinator, NodeCount, NodeCounts};
+
+    fn node(idx: u8) -> SocketAddr {
+        SocketAddr::from(([127, 0, 0, idx], 9042))
+    }
+
+    #[test]
+    fn test_last_coordinator() {
+        let coordinator = LastCoordinator::default();
+        assert_eq!(None, coordinator.take());
+
+        // The failed attempt was retried on another node.
+        let failed = coordinator.log_attempt_start(QueryId(0), None, node(1));
+        let succeeded = coordinator.log_attempt_start(QueryId(0), None, node(2));
+        coordinator.log_attempt_error(
+            failed,
+            &QueryError::TimeoutError,
+            &RetryDecision::RetryNextNode(None),
+        );
+        coordinator.log_attempt_success(succeeded);
+        assert_eq!(Some(node(2)), coordinator.take());
+        assert_eq!(None, coordinator.take());
+
+        let failed = coordinator.log_attempt_start(QueryId(0), None, node(3));
+        coordinator.log_attempt_error(failed, &QueryError::TimeoutError, &RetryDecision::DontRetry);
+        assert_eq!(Some(node(3)), coordinator.take());
+    }
+
+    #[test]
+    fn test_node_counts() {
+        let mut counts = NodeCounts::default();
+        counts.record(node(2), false);
+        counts.record(node(1), true);
+
+        let mut other = NodeCounts::default();
+        other.record(node(1), false);
+        counts.combine(&other);
+
+        assert_eq!(
+            vec![
+                (
+                    node(1),
+                    NodeCount {
+                        operations: 2,
+                        errors: 1
+                    }
+                ),
+                (
+                    node(2),
+                    NodeCount {
+                        operations: 1,
+                        errors: 0
+                    }
+                ),
+            ],
+            counts.sorted()
+        );
+
+        counts.clear();
+        assert!(counts.is_empty());
+    }
+}
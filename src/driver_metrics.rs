@@ -0,0 +1,202 @@
+//! Sampling of the metrics collected by the driver.
+//!
+//! Apart from the outcome of each operation, which the workers record in their
+//! stats, it's useful to know what the driver did to achieve it, e.g. how many
+//! times a request was retried by the retry policy, or how many pages
+//! the paged queries fetched. A sampling task periodically reads the counters
+//! of the sessions and accumulates their increments, which the frontends take
+//! at the end of each interval, the same as the stats of the workers.
+//!
+//! The driver exposes neither the number of open connections nor the number
+//! of speculative executions, so the number of nodes marked as down
+//! is reported instead.
+
+use std::sync::{Arc, Weak};
+use std::time::Duration;
+
+use parking_lot::Mutex;
+use scylla::Session;
+
+/// How often the sampling task reads the metrics of the sessions.
+/// Shorter than the reporting interval, so that the increments are
+/// attributed to the right interval with a small error.
+pub const SAMPLING_PERIOD: Duration = Duration::from_millis(100);
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct DriverMetrics {
+    /// Unpaged requests sent by the driver, including the retries.
+    pub queries: u64,
+    /// Pages requested by the paged queries.
+    pub pages: u64,
+    /// Failed unpaged requests.
+    pub errors: u64,
+    /// Failed page requests.
+    pub page_errors: u64,
+    /// Retries decided by the retry policy.
+    pub retries: u64,
+    /// Nodes known to the driver. Unlike the counters above,
+    /// it's the state at the time of the last sample.
+    pub nodes: usize,
+    /// Nodes which the driver has no working connection to.
+    pub nodes_down: usize,
+}
+
+impl DriverMetrics {
+    /// Reads the current metrics of the given sessions and sums them up.
+    pub fn read(sessions: &[Arc<Session>]) -> Self {
+        let mut total = Self::default();
+        for session in sessions {
+            let metrics = session.get_metrics();
+            let cluster_data = session.get_cluster_data();
+            let nodes = cluster_data.get_nodes_info();
+            total.combine(&Self {
+                queries: metrics.get_queries_num(),
+                pages: metrics.get_queries_iter_num(),
+                errors: metrics.get_errors_num(),
+                page_errors: metrics.get_errors_iter_num(),
+                retries: metrics.get_retries_num(),
+                nodes: nodes.len(),
+                nodes_down: nodes.iter().filter(|node| node.is_down()).count(),
+            });
+        }
+        total
+    }
+
+    /// Returns the increments of the counters since the `earlier` sample,
+    /// along with the current state of the nodes.
+    pub fn since(&self, earlier: &Self) -> Self {
+        Self {
+            queries: self.queries.saturating_sub(earlier.queries),
+            pages: self.pages.saturating_sub(earlier.pages),
+            errors: self.errors.saturating_sub(earlier.errors),
+            page_errors: self.page_errors.saturating_sub(earlier.page_errors),
+            retries: self.retries.saturating_sub(earlier.retries),
+            nodes: self.nodes,
+            nodes_down: self.nodes_down,
+        }
+    }
+
+    fn combine(&mut self, other: &Self) {
+        self.queries += other.queries;
+        self.pages += other.pages;
+        self.errors += other.errors;
+        self.page_errors += other.page_errors;
+        self.retries += other.retries;
+        self.nodes += other.nodes;
+        self.nodes_down += other.nodes_down;
+    }
+
+    /// Returns a one-line description of the metrics,
+    /// e.g. "queries: 1000, pages: 0, errors: 2, page errors: 0, retries: 2, nodes down: 1/3".
+    pub fn breakdown(&self) -> String {
+        format!(
+            "queries: {}, pages: {}, errors: {}, page errors: {}, retries: {}, nodes down: {}/{}",
+            self.queries,
+            self.pages,
+            self.errors,
+            self.page_errors,
+            self.retries,
+            self.nodes_down,
+            self.nodes
+        )
+    }
+}
+
+/// Accumulates the increments of the driver metrics read by the sampling task.
+pub struct DriverMetricsSampler {
+    interval: Mutex<DriverMetrics>,
+}
+
+impl DriverMetricsSampler {
+    /// Spawns the task sampling the metrics of the given sessions.
+    /// The task stops once the returned sampler is dropped.
+    pub fn spawn(sessions: Vec<Arc<Session>>, period: Duration) -> Arc<Self> {
+        let mut previous = DriverMetrics::read(&sessions);
+        let sampler = Arc::new(Self {
+            interval: Mutex::new(DriverMetrics {
+                nodes: previous.nodes,
+                nodes_down: previous.nodes_down,
+                ..Default::default()
+            }),
+        });
+
+        let weak = Arc::downgrade(&sampler);
+        tokio::task::spawn(async move {
+            let mut ticker = tokio::time::interval(period);
+            loop {
+                ticker.tick().await;
+                let sampler = match Weak::upgrade(&weak) {
+                    Some(sampler) => sampler,
+                    None => break,
+                };
+                let current = DriverMetrics::read(&sessions);
+                sampler.record(&current.since(&previous));
+                previous = current;
+            }
+        });
+
+        sampler
+    }
+
+    fn record(&self, increment: &DriverMetrics) {
+        let mut interval = self.interval.lock();
+        // The state of the nodes is not accumulated, only the latest one is kept.
+        interval.nodes = 0;
+        interval.nodes_down = 0;
+        interval.combine(increment);
+    }
+
+    /// Returns the metrics accumulated since the previous call and clears them.
+    pub fn take_interval(&self) -> DriverMetrics {
+        let mut interval = self.interval.lock();
+        let taken = *interval;
+        *interval = DriverMetrics {
+            nodes: taken.nodes,
+            nodes_down: taken.nodes_down,
+            ..Default::default()
+        };
+        taken
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use parking_lot::Mutex;
+
+    use super::{DriverMetrics, DriverMetricsSampler};
+
+    #[test]
+    fn test_sampler_accumulates_increments() {
+        let sampler = DriverMetricsSampler {
+            interval: Mutex::new(DriverMetrics::default()),
+        };
+
+        let first = DriverMetrics {
+            queries: 10,
+            retries: 1,
+            nodes: 3,
+            ..Default::default()
+        };
+        let second = DriverMetrics {
+            queries: 25,
+            retries: 4,
+            nodes: 3,
+            nodes_down: 1,
+            ..Default::default()
+        };
+        sampler.record(&first.since(&DriverMetrics::default()));
+        sampler.record(&second.since(&first));
+
+        let interval = sampler.take_interval();
+        assert_eq!(25, interval.queries);
+        assert_eq!(4, interval.retries);
+        assert_eq!(3, interval.nodes);
+        assert_eq!(1, interval.nodes_down);
+
+        // The counters are cleared, the state of the nodes is kept.
+        let interval = sampler.take_interval();
+        assert_eq!(0, interval.queries);
+        assert_eq!(0, interval.retries);
+        assert_eq!(1, interval.nodes_down);
+    }
+}
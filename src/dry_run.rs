@@ -0,0 +1,118 @@
+//! The dry-run mode, in which the operations are rendered instead of being executed.
+//!
+//! The frontends implement an [OperationRenderer] for each type of operation
+//! of the configured workload. Each renderer generates the keys and values
+//! exactly as the operation would, but instead of sending the statements
+//! to the cluster it returns their CQL text along with the bound values.
+//! This doesn't require a connection to the cluster, so it can be used
+//! to validate the workload settings before running the benchmark.
+
+use std::fmt;
+use std::io::Write;
+
+use anyhow::Result;
+
+/// A statement of an operation along with the values bound to it.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RenderedStatement {
+    pub statement: String,
+    pub values: Vec<String>,
+}
+
+impl RenderedStatement {
+    pub fn new(statement: impl Into<String>, values: Vec<String>) -> Self {
+        Self {
+            statement: statement.into(),
+            values,
+        }
+    }
+}
+
+impl fmt::Display for RenderedStatement {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}; values: [{}]",
+            self.statement,
+            self.values.join(", ")
+        )
+    }
+}
+
+/// Renders the consecutive operations of a single type.
+pub trait OperationRenderer {
+    /// The name of the rendered operation type, e.g. "write".
+    fn name(&self) -> &str;
+
+    /// Renders the statements executed by the next operation,
+    /// or returns `None` if the workload has finished.
+    fn render(&mut self) -> Option<Vec<RenderedStatement>>;
+}
+
+/// Renders `count` operations of each type and prints them to `out`.
+pub fn print_operations(
+    renderers: &mut [Box<dyn OperationRenderer>],
+    count: u64,
+    out: &mut impl Write,
+) -> Result<()> {
+    for renderer in renderers.iter_mut() {
+        writeln!(out, "Operation: {}", renderer.name())?;
+        for i in 1..=count {
+            let statements = match renderer.render() {
+                Some(statements) => statements,
+                None => {
+                    writeln!(out, "  (workload finished)")?;
+                    break;
+                }
+            };
+            for statement in statements {
+                writeln!(out, "  #{}: {}", i, statement)?;
+            }
+        }
+        writeln!(out)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{print_operations, OperationRenderer, RenderedStatement};
+
+    struct CountingRenderer {
+        remaining: u64,
+    }
+
+    impl OperationRenderer for CountingRenderer {
+        fn name(&self) -> &str {
+            "counting"
+        }
+
+        fn render(&mut self) -> Option<Vec<RenderedStatement>> {
+            if self.remaining == 0 {
+                return None;
+            }
+            self.remaining -= 1;
+            Some(vec![RenderedStatement::new(
+                "SELECT * FROM t WHERE pk = ?",
+                vec![self.remaining.to_string()],
+            )])
+        }
+    }
+
+    #[test]
+    fn test_print_operations() {
+        let mut renderers: Vec<Box<dyn OperationRenderer>> =
+            vec![Box::new(CountingRenderer { remaining: 2 })];
+        let mut out = Vec::new();
+        print_operations(&mut renderers, 3, &mut out).unwrap();
+
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "Operation: counting\n\
+            \x20 #1: SELECT * FROM t WHERE pk = ?; values: [1]\n\
+            \x20 #2: SELECT * FROM t WHERE pk = ?; values: [0]\n\
+            \x20 (workload finished)\n\
+            \n"
+        );
+    }
+}
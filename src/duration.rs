@@ -0,0 +1,79 @@
+//! Parsing the durations given with units, e.g. `1h30m` or `1.5d`,
+//! shared by the cassandra-stress options and the control file.
+
+use std::time::Duration;
+
+use anyhow::{Context, Result};
+
+/// Parses a sequence of numbers followed by their units, e.g. `1h30m` or `1.5d`.
+/// The units are `d`, `h`, `m` and `s`, in any case, and the numbers
+/// may have a fractional part.
+pub fn parse_duration(value: &str) -> Result<Duration> {
+    anyhow::ensure!(!value.is_empty(), "Empty duration");
+    let mut seconds = 0.0;
+    let mut rest = value;
+    while !rest.is_empty() {
+        let len = rest
+            .find(|c: char| !c.is_ascii_digit() && c != '.')
+            .with_context(|| format!("Missing unit in duration {}", value))?;
+        let number = &rest[..len];
+        anyhow::ensure!(is_decimal(number), "Invalid duration {}", value);
+        let number = number
+            .parse::<f64>()
+            .with_context(|| format!("Invalid duration {}", value))?;
+        let unit = rest[len..].chars().next().unwrap();
+        let multiplier = match unit.to_ascii_lowercase() {
+            's' => 1.0,
+            'm' => 60.0,
+            'h' => 60.0 * 60.0,
+            'd' => 24.0 * 60.0 * 60.0,
+            _ => anyhow::bail!(
+                "Invalid unit {} in duration {}, expected d, h, m or s",
+                unit,
+                value
+            ),
+        };
+        seconds += number * multiplier;
+        rest = &rest[len + unit.len_utf8()..];
+    }
+    // Fails if the duration doesn't fit, rather than overflowing.
+    Duration::try_from_secs_f64(seconds).with_context(|| format!("Invalid duration {}", value))
+}
+
+// Accepts `[0-9]+(\.[0-9]+)?`, e.g. rejects `.5` and `1.`, which f64 accepts.
+fn is_decimal(number: &str) -> bool {
+    let (integer, fraction) = number.split_once('.').unwrap_or((number, "0"));
+    let digits = |s: &str| !s.is_empty() && s.bytes().all(|b| b.is_ascii_digit());
+    digits(integer) && digits(fraction)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::parse_duration;
+
+    #[test]
+    fn test_parse_duration() {
+        assert_eq!(Duration::from_secs(90), parse_duration("90s").unwrap());
+        assert_eq!(Duration::from_secs(5400), parse_duration("1h30m").unwrap());
+        assert_eq!(Duration::from_secs(5400), parse_duration("1.5H").unwrap());
+        assert_eq!(
+            Duration::from_secs(86_410),
+            parse_duration("1d10s").unwrap()
+        );
+        assert_eq!(Duration::from_millis(500), parse_duration("0.5s").unwrap());
+
+        assert!(parse_duration("").is_err());
+        assert!(parse_duration("10").is_err());
+        assert!(parse_duration("10m5").is_err());
+        assert!(parse_duration("m").is_err());
+        assert!(parse_duration("-1s").is_err());
+        assert!(parse_duration("1.h").is_err());
+        assert!(parse_duration(".5h").is_err());
+        assert!(parse_duration("1.2.3h").is_err());
+        assert!(parse_duration("1h 30m").is_err());
+        // Too long for a Duration, rather than an overflow.
+        assert!(parse_duration("99999999999999999999d").is_err());
+    }
+}
@@ -0,0 +1,194 @@
+//! A log of the failed operations.
+//!
+//! Operations report their errors to the [ErrorLog], which is cheap to call
+//! from the hot path: the entries above the rate limit are counted but not
+//! formatted, and the accepted entries are handed over to the [ErrorLogWriter]
+//! task without waiting for it.
+
+use std::fmt::{Debug, Display};
+use std::path::Path;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+
+use anyhow::{Context, Result};
+use chrono::Utc;
+use parking_lot::Mutex;
+use tokio::fs::File;
+use tokio::io::{AsyncWriteExt, BufWriter};
+use tokio::sync::mpsc;
+use tokio::time::Instant;
+
+use crate::run_metadata::RunMetadata;
+use crate::sharded_stats::ErrorKind;
+
+pub struct ErrorLog {
+    // None after the log is closed.
+    sender: Mutex<Option<mpsc::Sender<String>>>,
+    max_per_second: u64,
+    start_time: Instant,
+    current_second: AtomicU64,
+    current_second_count: AtomicU64,
+    suppressed: Arc<AtomicU64>,
+}
+
+/// Writes the entries accepted by the [ErrorLog] to the file.
+pub struct ErrorLogWriter {
+    receiver: mpsc::Receiver<String>,
+    out: BufWriter<File>,
+    suppressed: Arc<AtomicU64>,
+}
+
+impl ErrorLog {
+    /// Creates the log which writes at most `max_per_second` entries
+    /// per second to the file at `path`, starting with the metadata of the run.
+    ///
+    /// The returned [ErrorLogWriter] needs to be run for the entries
+    /// to be written.
+    pub async fn new(
+        path: impl AsRef<Path>,
+        max_per_second: u64,
+        metadata: &RunMetadata,
+    ) -> Result<(Arc<Self>, ErrorLogWriter)> {
+        let path = path.as_ref();
+        let file = File::create(path)
+            .await
+            .with_context(|| format!("Failed to create the error log {}", path.display()))?;
+
+        // If the writer can't keep up, the entries are dropped
+        // instead of blocking the operations.
+        let (sender, receiver) = mpsc::channel(max_per_second.clamp(1, 1024) as usize);
+        let suppressed = Arc::new(AtomicU64::new(0));
+
+        let log = Arc::new(Self {
+            sender: Mutex::new(Some(sender)),
+            max_per_second,
+            start_time: Instant::now(),
+            current_second: AtomicU64::new(0),
+            current_second_count: AtomicU64::new(0),
+            suppressed: Arc::clone(&suppressed),
+        });
+        let mut out = BufWriter::new(file);
+        out.write_all(metadata.to_comment_line().as_bytes()).await?;
+        let writer = ErrorLogWriter {
+            receiver,
+            out,
+            suppressed,
+        };
+
+        Ok((log, writer))
+    }
+
+    /// Reports the failure of the operation of given kind (e.g. the name
+    /// of the executed statement) on the given partition.
+    /// Neither of the arguments is formatted if the entry is over the rate limit.
+    pub fn report(&self, operation: impl Display, partition_key: impl Debug, err: &anyhow::Error) {
+        if !self.try_acquire() {
+            self.suppressed.fetch_add(1, Ordering::Relaxed);
+            return;
+        }
+
+        let entry = format!(
+            "{}\t{}\tpk={:?}\t{}\t{:#}\n",
+            Utc::now().to_rfc3339(),
+            operation,
+            partition_key,
+            ErrorKind::classify(err).name(),
+            err,
+        );
+
+        let sent = match &*self.sender.lock() {
+            Some(sender) => sender.try_send(entry).is_ok(),
+            None => false,
+        };
+        if !sent {
+            self.suppressed.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    /// Stops accepting new entries. The writer finishes
+    /// after writing the entries which were already reported.
+    pub fn close(&self) {
+        self.sender.lock().take();
+    }
+
+    fn try_acquire(&self) -> bool {
+        let second = (Instant::now() - self.start_time).as_secs();
+        let current = self.current_second.load(Ordering::Relaxed);
+        if current != second
+            && self
+                .current_second
+                .compare_exchange(current, second, Ordering::Relaxed, Ordering::Relaxed)
+                .is_ok()
+        {
+            self.current_second_count.store(0, Ordering::Relaxed);
+        }
+        self.current_second_count.fetch_add(1, Ordering::Relaxed) < self.max_per_second
+    }
+}
+
+impl ErrorLogWriter {
+    /// Writes the entries until the [ErrorLog] is closed.
+    pub async fn run(mut self) -> Result<()> {
+        while let Some(entry) = self.receiver.recv().await {
+            self.out.write_all(entry.as_bytes()).await?;
+        }
+
+        let suppressed = self.suppressed.load(Ordering::Relaxed);
+        if suppressed > 0 {
+            let note = format!(
+                "# {} errors were not logged due to the rate limit\n",
+                suppressed
+            );
+            self.out.write_all(note.as_bytes()).await?;
+        }
+        self.out.flush().await?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::run_metadata::RunMetadata;
+
+    use super::ErrorLog;
+
+    #[tokio::test]
+    async fn test_error_log_rate_limit() {
+        let path = std::env::temp_dir().join(format!(
+            "cql-stress-error-log-test-{}.log",
+            std::process::id()
+        ));
+        let (log, writer) = ErrorLog::new(&path, 3, &RunMetadata::new(vec![], vec![]))
+            .await
+            .unwrap();
+        let writer = tokio::spawn(writer.run());
+
+        let err = anyhow::anyhow!("some error");
+        for pk in 0..10i64 {
+            log.report("write", pk, &err);
+        }
+        log.close();
+        writer.await.unwrap().unwrap();
+
+        let contents = std::fs::read_to_string(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert!(contents.starts_with("# run-metadata: "));
+        let lines = contents.lines().skip(1).collect::<Vec<_>>();
+        assert_eq!(lines.len(), 4);
+        for (pk, line) in lines[..3].iter().enumerate() {
+            let fields = line.split('\t').collect::<Vec<_>>();
+            assert_eq!(
+                &fields[1..],
+                &[
+                    "write",
+                    format!("pk={}", pk).as_str(),
+                    "client",
+                    "some error"
+                ]
+            );
+        }
+        assert_eq!(lines[3], "# 7 errors were not logged due to the rate limit");
+    }
+}
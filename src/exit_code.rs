@@ -0,0 +1,67 @@
+//! Exit codes of the frontends.
+//!
+//! Both frontends use the same codes, so that the tools wrapping them
+//! can tell the reason of the failure without parsing the output.
+
+use anyhow::Result;
+
+use crate::run::RunAborted;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ExitCode {
+    Success = 0,
+    /// A failure not covered by the other codes, e.g. failure to write
+    /// the results.
+    OtherError = 1,
+    /// The command line arguments could not be parsed or are invalid.
+    InvalidArguments = 2,
+    /// Failed to connect to the cluster or to create the schema.
+    PreparationFailed = 3,
+    /// The benchmark was aborted, e.g. by a second Ctrl+C.
+    Aborted = 4,
+    /// An operation failed more times than allowed by the retry settings.
+    ErrorThresholdExceeded = 5,
+}
+
+impl ExitCode {
+    /// Returns the exit code corresponding to the result of [crate::run::run].
+    pub fn of_run(result: &Result<()>) -> Self {
+        match result {
+            Ok(()) => Self::Success,
+            Err(err) if err.is::<RunAborted>() => Self::Aborted,
+            Err(_) => Self::ErrorThresholdExceeded,
+        }
+    }
+
+    /// Prints the error the same way as returning it from `main` would,
+    /// and returns the code.
+    pub fn with_error(self, err: &anyhow::Error) -> Self {
+        eprintln!("Error: {:?}", err);
+        self
+    }
+}
+
+impl From<ExitCode> for std::process::ExitCode {
+    fn from(code: ExitCode) -> Self {
+        Self::from(code as u8)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::Context;
+
+    use super::ExitCode;
+    use crate::run::RunAborted;
+
+    #[test]
+    fn test_exit_code_of_run() {
+        assert_eq!(ExitCode::of_run(&Ok(())), ExitCode::Success);
+
+        let aborted = Err(anyhow::Error::from(RunAborted)).context("benchmark failed");
+        assert_eq!(ExitCode::of_run(&aborted), ExitCode::Aborted);
+
+        let failed = Err(anyhow::anyhow!("write failed"));
+        assert_eq!(ExitCode::of_run(&failed), ExitCode::ErrorThresholdExceeded);
+    }
+}
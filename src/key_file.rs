@@ -0,0 +1,228 @@
+//! Reading the keys of the operations from a file.
+//!
+//! Instead of generating the keys, the workload can replay the keys exported
+//! e.g. from a production dataset. Each line of the file contains a partition
+//! key (or a seed it's generated from), optionally followed by a clustering key
+//! after a comma. Empty lines and lines starting with `#` are skipped.
+//!
+//! The file is streamed from disk, so its size isn't limited by the memory.
+//! It's read once when opened to validate all of its lines, so that
+//! the workers don't run into a malformed line in the middle of the run.
+
+use std::fs::File;
+use std::io::{BufRead, BufReader, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use parking_lot::Mutex;
+
+const BUFFER_SIZE: usize = 64 * 1024;
+
+/// A single line of the key file.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct FileKey {
+    pub pk: i64,
+    pub ck: Option<i64>,
+}
+
+/// A key file shared by the workers, each key is read by one of them.
+pub struct KeyFile {
+    path: PathBuf,
+    reader: Mutex<KeyReader>,
+    looped: bool,
+    count: u64,
+    min_pk: i64,
+    max_pk: i64,
+    has_clustering_keys: bool,
+}
+
+struct KeyReader {
+    reader: BufReader<File>,
+    line: String,
+}
+
+impl KeyFile {
+    /// Opens the file and validates its contents. If `looped` is set,
+    /// the keys are read over again once the end of the file is reached.
+    pub fn open(path: impl AsRef<Path>, looped: bool) -> Result<Self> {
+        let path = path.as_ref();
+        let open = || {
+            File::open(path)
+                .with_context(|| format!("Failed to open the key file {}", path.display()))
+        };
+
+        let mut count = 0;
+        let mut min_pk = i64::MAX;
+        let mut max_pk = i64::MIN;
+        let mut has_clustering_keys = false;
+        for (idx, line) in BufReader::with_capacity(BUFFER_SIZE, open()?)
+            .lines()
+            .enumerate()
+        {
+            let line = line.with_context(|| format!("Failed to read {}", path.display()))?;
+            let key = match parse_line(&line)
+                .with_context(|| format!("Invalid key in {}:{}", path.display(), idx + 1))?
+            {
+                Some(key) => key,
+                None => continue,
+            };
+            count += 1;
+            min_pk = min_pk.min(key.pk);
+            max_pk = max_pk.max(key.pk);
+            has_clustering_keys |= key.ck.is_some();
+        }
+        anyhow::ensure!(count > 0, "The key file {} is empty", path.display());
+
+        Ok(Self {
+            path: path.to_owned(),
+            reader: Mutex::new(KeyReader {
+                reader: BufReader::with_capacity(BUFFER_SIZE, open()?),
+                line: String::new(),
+            }),
+            looped,
+            count,
+            min_pk,
+            max_pk,
+            has_clustering_keys,
+        })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// The number of keys in the file.
+    pub fn count(&self) -> u64 {
+        self.count
+    }
+
+    pub fn min_pk(&self) -> i64 {
+        self.min_pk
+    }
+
+    pub fn max_pk(&self) -> i64 {
+        self.max_pk
+    }
+
+    /// Tells whether any of the lines contains a clustering key.
+    pub fn has_clustering_keys(&self) -> bool {
+        self.has_clustering_keys
+    }
+
+    /// Returns the next key, or `None` once all keys were read
+    /// if the file is not looped.
+    ///
+    /// # Panics
+    ///
+    /// If the file can't be read anymore, e.g. it was modified during the run.
+    pub fn next_key(&self) -> Option<FileKey> {
+        let mut guard = self.reader.lock();
+        let KeyReader { reader, line } = &mut *guard;
+        let mut rewound = false;
+        loop {
+            line.clear();
+            let read = reader
+                .read_line(line)
+                .unwrap_or_else(|err| panic!("Failed to read {}: {}", self.path.display(), err));
+            if read == 0 {
+                // The file contains at least one key, so it's found
+                // after rewinding, unless the file was truncated.
+                if !self.looped || rewound {
+                    return None;
+                }
+                reader.seek(SeekFrom::Start(0)).unwrap_or_else(|err| {
+                    panic!("Failed to rewind {}: {}", self.path.display(), err)
+                });
+                rewound = true;
+                continue;
+            }
+            match parse_line(line) {
+                Ok(Some(key)) => return Some(key),
+                Ok(None) => continue,
+                Err(err) => panic!("Invalid key in {}: {:#}", self.path.display(), err),
+            }
+        }
+    }
+}
+
+fn parse_line(line: &str) -> Result<Option<FileKey>> {
+    let line = line.trim();
+    if line.is_empty() || line.starts_with('#') {
+        return Ok(None);
+    }
+
+    let (pk, ck) = match line.split_once(',') {
+        Some((pk, ck)) => (pk, Some(ck)),
+        None => (line, None),
+    };
+    let pk = pk
+        .trim()
+        .parse()
+        .with_context(|| format!("invalid partition key: {}", pk))?;
+    let ck = match ck {
+        Some(ck) => Some(
+            ck.trim()
+                .parse()
+                .with_context(|| format!("invalid clustering key: {}", ck))?,
+        ),
+        None => None,
+    };
+    Ok(Some(FileKey { pk, ck }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{FileKey, KeyFile};
+
+    fn write_key_file(name: &str, contents: &str) -> std::path::PathBuf {
+        let path = std::env::temp_dir().join(format!(
+            "cql-stress-key-file-test-{}-{}",
+            name,
+            std::process::id()
+        ));
+        std::fs::write(&path, contents).unwrap();
+        path
+    }
+
+    #[test]
+    fn test_key_file() {
+        let path = write_key_file("valid", "# pk,ck\n5\n\n-3, 7\n10\n");
+
+        let keys = KeyFile::open(&path, false).unwrap();
+        assert_eq!(3, keys.count());
+        assert_eq!(-3, keys.min_pk());
+        assert_eq!(10, keys.max_pk());
+        assert!(keys.has_clustering_keys());
+        let read = std::iter::from_fn(|| keys.next_key()).collect::<Vec<_>>();
+        assert_eq!(
+            vec![
+                FileKey { pk: 5, ck: None },
+                FileKey {
+                    pk: -3,
+                    ck: Some(7)
+                },
+                FileKey { pk: 10, ck: None },
+            ],
+            read
+        );
+
+        let looped = KeyFile::open(&path, true).unwrap();
+        let pks = (0..7)
+            .map(|_| looped.next_key().unwrap().pk)
+            .collect::<Vec<_>>();
+        assert_eq!(vec![5, -3, 10, 5, -3, 10, 5], pks);
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_invalid_key_file() {
+        let empty = write_key_file("empty", "# nothing here\n");
+        assert!(KeyFile::open(&empty, true).is_err());
+        std::fs::remove_file(&empty).unwrap();
+
+        let invalid = write_key_file("invalid", "1\n2,x\n");
+        assert!(KeyFile::open(&invalid, false).is_err());
+        std::fs::remove_file(&invalid).unwrap();
+    }
+}
@@ -0,0 +1,143 @@
+//! A log of the keys of the successfully written rows.
+//!
+//! The file has the format of a [key file](crate::key_file), so that a later
+//! run can read back exactly the rows written by this one. Unlike the other
+//! logs, no entry can be dropped: if the [KeyLogWriter] can't keep up,
+//! the operations wait for it.
+
+use std::fmt::Write as _;
+use std::path::Path;
+use std::sync::Arc;
+
+use anyhow::{Context, Result};
+use parking_lot::Mutex;
+use tokio::fs::File;
+use tokio::io::{AsyncWriteExt, BufWriter};
+use tokio::sync::mpsc;
+
+use crate::run_metadata::RunMetadata;
+
+// Each entry holds the keys of a single operation.
+const CHANNEL_CAPACITY: usize = 4096;
+const BUFFER_SIZE: usize = 1024 * 1024;
+
+pub struct KeyLog {
+    // None after the log is closed.
+    sender: Mutex<Option<mpsc::Sender<(i64, Vec<i64>)>>>,
+}
+
+/// Writes the keys recorded by the [KeyLog] to the file.
+pub struct KeyLogWriter {
+    receiver: mpsc::Receiver<(i64, Vec<i64>)>,
+    out: BufWriter<File>,
+}
+
+impl KeyLog {
+    /// Creates the log of the keys written to the file at `path`.
+    /// The metadata of the run is written as a comment, skipped by the key file reader.
+    ///
+    /// The returned [KeyLogWriter] needs to be run for the keys
+    /// to be written.
+    pub async fn new(
+        path: impl AsRef<Path>,
+        metadata: &RunMetadata,
+    ) -> Result<(Arc<Self>, KeyLogWriter)> {
+        let path = path.as_ref();
+        let file = File::create(path)
+            .await
+            .with_context(|| format!("Failed to create the key log {}", path.display()))?;
+
+        let (sender, receiver) = mpsc::channel(CHANNEL_CAPACITY);
+        let log = Arc::new(Self {
+            sender: Mutex::new(Some(sender)),
+        });
+        let mut out = BufWriter::with_capacity(BUFFER_SIZE, file);
+        out.write_all(metadata.to_comment_line().as_bytes()).await?;
+        out.write_all(b"# pk,ck\n").await?;
+        let writer = KeyLogWriter { receiver, out };
+
+        Ok((log, writer))
+    }
+
+    /// Records the rows of the partition `pk` which were successfully written.
+    /// Waits if the writer is behind.
+    pub async fn record(&self, pk: i64, cks: &[i64]) {
+        let sender = self.sender.lock().clone();
+        if let Some(sender) = sender {
+            // The send fails only if the writer stopped due to an error,
+            // which is reported by `KeyLogWriter::run`.
+            let _ = sender.send((pk, cks.to_vec())).await;
+        }
+    }
+
+    /// Stops accepting new keys. The writer finishes
+    /// after writing the keys which were already recorded.
+    pub fn close(&self) {
+        self.sender.lock().take();
+    }
+}
+
+impl KeyLogWriter {
+    /// Writes the keys until the [KeyLog] is closed.
+    pub async fn run(mut self) -> Result<()> {
+        let mut lines = String::new();
+        while let Some((pk, cks)) = self.receiver.recv().await {
+            lines.clear();
+            for ck in cks {
+                writeln!(lines, "{},{}", pk, ck).unwrap();
+            }
+            self.out.write_all(lines.as_bytes()).await?;
+        }
+        self.out.flush().await?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::key_file::{FileKey, KeyFile};
+    use crate::run_metadata::RunMetadata;
+
+    use super::KeyLog;
+
+    #[tokio::test]
+    async fn test_key_log_is_a_key_file() {
+        let path = std::env::temp_dir().join(format!(
+            "cql-stress-key-log-test-{}.csv",
+            std::process::id()
+        ));
+        let (log, writer) = KeyLog::new(&path, &RunMetadata::new(vec![], vec![]))
+            .await
+            .unwrap();
+        let writer = tokio::spawn(writer.run());
+
+        log.record(1, &[10, 11]).await;
+        log.record(-2, &[0]).await;
+        log.close();
+        // Ignored after the log is closed.
+        log.record(3, &[0]).await;
+        writer.await.unwrap().unwrap();
+
+        let keys = KeyFile::open(&path, false).unwrap();
+        let read = std::iter::from_fn(|| keys.next_key()).collect::<Vec<_>>();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(
+            read,
+            vec![
+                FileKey {
+                    pk: 1,
+                    ck: Some(10)
+                },
+                FileKey {
+                    pk: 1,
+                    ck: Some(11)
+                },
+                FileKey {
+                    pk: -2,
+                    ck: Some(0)
+                },
+            ]
+        );
+    }
+}
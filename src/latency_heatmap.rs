@@ -0,0 +1,170 @@
+//! The per-interval latency histograms in a compact CSV format, suitable
+//! for building latency-over-time heatmaps.
+//!
+//! Each line holds the histogram of a single interval: the end and the length
+//! of the interval in seconds since the start of the benchmark, the tag of the
+//! histogram, the number of the recorded latencies and the number of latencies
+//! in each of the buckets. The buckets are the same in all of the lines: their
+//! columns are named after their upper bound in microseconds, doubling from 1us,
+//! and the last one, `+Inf`, counts the latencies above 2^24us (about 16.8s).
+
+use std::fmt::Write as _;
+use std::ops::Range;
+use std::path::Path;
+use std::time::Duration;
+
+use anyhow::{Context, Result};
+use hdrhistogram::Histogram;
+use tokio::fs::File;
+use tokio::io::{AsyncWriteExt, BufWriter};
+
+use crate::run_metadata::RunMetadata;
+
+// The upper bounds of the buckets are 2^0..=2^MAX_BUCKET_EXPONENT microseconds.
+const MAX_BUCKET_EXPONENT: u32 = 24;
+
+/// Writes the latency histograms of the intervals to the file.
+pub struct LatencyHeatmapWriter {
+    out: BufWriter<File>,
+    ns_per_unit: u64,
+}
+
+impl LatencyHeatmapWriter {
+    /// Creates the file at `path` and writes the metadata of the run,
+    /// as a comment line, and the header.
+    ///
+    /// `ns_per_unit` is the number of nanoseconds in the unit
+    /// in which the histograms record the latencies.
+    pub async fn create(
+        path: impl AsRef<Path>,
+        ns_per_unit: u64,
+        metadata: &RunMetadata,
+    ) -> Result<Self> {
+        let path = path.as_ref();
+        let file = File::create(path)
+            .await
+            .with_context(|| format!("Failed to create the latency heatmap {}", path.display()))?;
+        let mut out = BufWriter::new(file);
+        out.write_all(metadata.to_comment_line().as_bytes()).await?;
+        out.write_all(header().as_bytes()).await?;
+
+        Ok(Self { out, ns_per_unit })
+    }
+
+    /// Writes the histogram of the interval, given relative to the start of the benchmark.
+    pub async fn write_interval(
+        &mut self,
+        interval: Range<Duration>,
+        tag: &str,
+        histogram: &Histogram<u64>,
+    ) -> Result<()> {
+        let line = format_interval(interval, tag, histogram, self.ns_per_unit);
+        self.out.write_all(line.as_bytes()).await?;
+        Ok(())
+    }
+
+    /// Makes sure that the intervals written so far reach the file.
+    pub async fn flush(&mut self) -> Result<()> {
+        self.out.flush().await?;
+        Ok(())
+    }
+}
+
+fn bucket_bounds_us() -> impl Iterator<Item = u64> {
+    (0..=MAX_BUCKET_EXPONENT).map(|exponent| 1 << exponent)
+}
+
+fn header() -> String {
+    let mut header = String::from("time_s,interval_s,tag,count");
+    for bound in bucket_bounds_us() {
+        write!(header, ",{}", bound).unwrap();
+    }
+    header.push_str(",+Inf\n");
+    header
+}
+
+fn format_interval(
+    interval: Range<Duration>,
+    tag: &str,
+    histogram: &Histogram<u64>,
+    ns_per_unit: u64,
+) -> String {
+    let bounds_ns = bucket_bounds_us()
+        .map(|bound| bound * 1000)
+        .collect::<Vec<_>>();
+    // The last bucket counts the latencies above the highest bound.
+    let mut counts = vec![0u64; bounds_ns.len() + 1];
+    for value in histogram.iter_recorded() {
+        let latency_ns = value.value_iterated_to() * ns_per_unit;
+        let bucket = bounds_ns.partition_point(|&bound| bound < latency_ns);
+        counts[bucket] += value.count_since_last_iteration();
+    }
+
+    let mut line = format!(
+        "{:.3},{:.3},{},{}",
+        interval.end.as_secs_f64(),
+        (interval.end - interval.start).as_secs_f64(),
+        tag,
+        histogram.len(),
+    );
+    for count in counts {
+        write!(line, ",{}", count).unwrap();
+    }
+    line.push('\n');
+    line
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn header_test() {
+        let header = header();
+        assert!(header.starts_with("time_s,interval_s,tag,count,1,2,4,8,"));
+        assert!(header.ends_with(",8388608,16777216,+Inf\n"));
+    }
+
+    #[test]
+    fn format_interval_test() {
+        let mut histogram = Histogram::<u64>::new(3).unwrap();
+        // 500ns, 3us and 3.5us with the 1ns unit.
+        histogram.record(500).unwrap();
+        histogram.record(3_000).unwrap();
+        histogram.record(3_500).unwrap();
+        // Above the highest bound.
+        histogram.record(20_000_000_000).unwrap();
+
+        let line = format_interval(
+            Duration::from_millis(1500)..Duration::from_secs(2),
+            "raw",
+            &histogram,
+            1,
+        );
+        let columns = line.trim_end().split(',').collect::<Vec<_>>();
+        assert_eq!(vec!["2.000", "0.500", "raw", "4"], columns[..4]);
+        let buckets = &columns[4..];
+        assert_eq!(MAX_BUCKET_EXPONENT as usize + 2, buckets.len());
+        // The 1us, 2us and 4us buckets, and the last one.
+        assert_eq!(vec!["1", "0", "2"], buckets[..3]);
+        assert_eq!("1", buckets[buckets.len() - 1]);
+        assert_eq!(
+            4,
+            buckets
+                .iter()
+                .map(|c| c.parse::<u64>().unwrap())
+                .sum::<u64>()
+        );
+
+        // With the microsecond unit, 3 is in the 4us bucket.
+        let mut histogram = Histogram::<u64>::new(3).unwrap();
+        histogram.record(3).unwrap();
+        let line = format_interval(
+            Duration::ZERO..Duration::from_secs(1),
+            "co-fixed",
+            &histogram,
+            1000,
+        );
+        assert!(line.starts_with("1.000,1.000,co-fixed,1,0,0,1,0,"));
+    }
+}
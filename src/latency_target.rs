@@ -0,0 +1,121 @@
+//! Searching for the maximum throughput which keeps the latency under a target.
+//!
+//! At the end of each interval, the frontend feeds the controller with the
+//! latency percentile and the throughput measured in that interval,
+//! and applies the returned rate via [RunController::set_rate_limit].
+//! Until the target is exceeded for the first time, the rate is doubled
+//! each interval to quickly reach the order of magnitude of the sustainable
+//! rate. Afterwards, it's increased slowly while the latency stays under
+//! the target, and decreased multiplicatively as soon as it exceeds it,
+//! so it oscillates slightly below the maximum sustainable rate.
+//!
+//! [RunController::set_rate_limit]: crate::run::RunController::set_rate_limit
+
+use std::time::Duration;
+
+/// The rate the search starts with.
+pub const INITIAL_RATE: f64 = 1000.0;
+
+/// The rate is never decreased below this value, so that
+/// the latency is still measured after a sudden spike.
+const MIN_RATE: f64 = 10.0;
+
+const FAST_INCREASE: f64 = 2.0;
+const SLOW_INCREASE: f64 = 1.1;
+const DECREASE: f64 = 0.8;
+
+pub struct LatencyTargetController {
+    target: Duration,
+    rate: f64,
+    best_rate: Option<f64>,
+    // Whether the target was already exceeded.
+    overshot: bool,
+}
+
+impl LatencyTargetController {
+    pub fn new(target: Duration, initial_rate: f64) -> Self {
+        Self {
+            target,
+            rate: initial_rate.max(MIN_RATE),
+            best_rate: None,
+            overshot: false,
+        }
+    }
+
+    /// The rate limit currently applied.
+    pub fn rate(&self) -> f64 {
+        self.rate
+    }
+
+    /// The highest throughput achieved in an interval which met the target.
+    pub fn best_rate(&self) -> Option<f64> {
+        self.best_rate
+    }
+
+    /// Computes the rate limit for the next interval, given the latency
+    /// and the throughput measured in the last one.
+    pub fn update(&mut self, latency: Duration, achieved_rate: f64) -> f64 {
+        if latency <= self.target {
+            if achieved_rate > 0.0 {
+                self.best_rate = Some(self.best_rate.unwrap_or(0.0).max(achieved_rate));
+            }
+            let factor = if self.overshot {
+                SLOW_INCREASE
+            } else {
+                FAST_INCREASE
+            };
+            // If the workers can't keep up with the current rate (e.g. the
+            // concurrency is too low), raising it further would only build
+            // up a backlog, so the increase is relative to the achieved rate.
+            self.rate = (self.rate * factor).min(achieved_rate * factor);
+        } else {
+            self.overshot = true;
+            self.rate = self.rate.min(achieved_rate) * DECREASE;
+        }
+        self.rate = self.rate.max(MIN_RATE);
+        self.rate
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::{LatencyTargetController, SLOW_INCREASE};
+
+    #[test]
+    fn test_converges_to_sustainable_rate() {
+        // A simulated system whose latency grows sharply above 5000 op/s.
+        let latency_at = |rate: f64| -> Duration {
+            if rate <= 5000.0 {
+                Duration::from_millis(2)
+            } else {
+                Duration::from_millis(50)
+            }
+        };
+
+        let target = Duration::from_millis(10);
+        let mut controller = LatencyTargetController::new(target, 1000.0);
+        for _ in 0..100 {
+            let rate = controller.rate();
+            controller.update(latency_at(rate), rate);
+        }
+
+        assert!(controller.rate() <= 5000.0 * SLOW_INCREASE);
+        let best_rate = controller.best_rate().unwrap();
+        assert!(best_rate > 4000.0 && best_rate <= 5000.0);
+    }
+
+    #[test]
+    fn test_does_not_outrun_workers() {
+        let target = Duration::from_millis(10);
+        let mut controller = LatencyTargetController::new(target, 1000.0);
+        // The workers achieve only 500 op/s, but the latency is fine.
+        let rate = controller.update(Duration::from_millis(1), 500.0);
+        assert_eq!(rate, 1000.0);
+
+        // The latency is exceeded.
+        let rate = controller.update(Duration::from_millis(20), 500.0);
+        assert_eq!(rate, 400.0);
+    }
+}
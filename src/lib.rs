@@ -4,10 +4,39 @@ extern crate async_trait;
 #[cfg(test)]
 pub(crate) mod test_util;
 
+#[cfg(feature = "auth-plugins")]
+pub mod auth;
+pub mod bench;
+pub mod client_stats;
+pub mod clock;
+pub mod cluster_info;
+pub mod compressible;
 pub mod configuration;
+pub mod control_file;
+pub mod coordinator;
 pub mod distribution;
+pub mod driver_metrics;
+pub mod dry_run;
+pub mod duration;
+pub mod error_log;
+pub mod exit_code;
+pub mod key_file;
+pub mod key_log;
+pub mod latency_heatmap;
+pub mod latency_target;
+pub mod partition_lock;
+pub mod replication;
+pub mod reprepare;
+pub mod row_count;
 pub mod run;
+pub mod run_metadata;
+pub mod seed;
 pub mod sharded_stats;
+pub mod signals;
+pub mod split;
+pub mod stats_report;
+pub mod trace_log;
+pub mod version;
 
 #[cfg(test)]
 mod tests {
@@ -0,0 +1,131 @@
+//! Serialization of the operations touching the same partition.
+//!
+//! Validation workloads may require that a read observes the previous write
+//! to the same partition. It's not guaranteed if the operations are executed
+//! concurrently by different workers, so the frontends can optionally lock
+//! the partitions touched by an operation for the time of its execution.
+
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::sync::Arc;
+
+use tokio::sync::{Mutex, OwnedMutexGuard};
+
+/// The number of locks per worker.
+///
+/// The partitions are mapped to a fixed set of locks ("stripes"),
+/// so the unrelated partitions may share a lock. The more stripes,
+/// the less likely it is that the workers wait for each other needlessly.
+const STRIPES_PER_WORKER: usize = 64;
+
+/// Locks shared by the workers, which ensure that the operations
+/// touching the same partition are never in flight concurrently.
+pub struct PartitionLocks {
+    stripes: Box<[Arc<Mutex<()>>]>,
+}
+
+/// Holds the locks of the partitions until dropped.
+pub struct PartitionGuard {
+    _guards: Vec<OwnedMutexGuard<()>>,
+}
+
+impl PartitionLocks {
+    pub fn new(concurrency: u64) -> Self {
+        let stripe_count = (concurrency as usize).max(1) * STRIPES_PER_WORKER;
+        Self {
+            stripes: (0..stripe_count)
+                .map(|_| Arc::new(Mutex::new(())))
+                .collect(),
+        }
+    }
+
+    /// Waits until none of the partitions with given keys is locked
+    /// by another operation, and locks them.
+    ///
+    /// The locks are acquired in a fixed order, so the operations touching
+    /// several partitions (e.g. batches) can't deadlock.
+    pub async fn lock<K: Hash>(&self, keys: impl IntoIterator<Item = K>) -> PartitionGuard {
+        let mut stripe_indices = keys
+            .into_iter()
+            .map(|key| self.stripe_index(&key))
+            .collect::<Vec<_>>();
+        stripe_indices.sort_unstable();
+        stripe_indices.dedup();
+
+        let mut guards = Vec::with_capacity(stripe_indices.len());
+        for idx in stripe_indices {
+            guards.push(Arc::clone(&self.stripes[idx]).lock_owned().await);
+        }
+        PartitionGuard { _guards: guards }
+    }
+
+    fn stripe_index<K: Hash>(&self, key: &K) -> usize {
+        let mut hasher = DefaultHasher::new();
+        key.hash(&mut hasher);
+        (hasher.finish() % self.stripes.len() as u64) as usize
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    use super::PartitionLocks;
+
+    #[tokio::test]
+    async fn test_same_partition_is_serialized() {
+        let locks = Arc::new(PartitionLocks::new(4));
+        let in_flight = Arc::new(AtomicBool::new(false));
+
+        let tasks = (0..8)
+            .map(|_| {
+                let locks = Arc::clone(&locks);
+                let in_flight = Arc::clone(&in_flight);
+                tokio::spawn(async move {
+                    for _ in 0..10 {
+                        let _guard = locks.lock([42i64]).await;
+                        assert!(!in_flight.swap(true, Ordering::SeqCst));
+                        tokio::time::sleep(Duration::from_millis(1)).await;
+                        in_flight.store(false, Ordering::SeqCst);
+                    }
+                })
+            })
+            .collect::<Vec<_>>();
+
+        for task in tasks {
+            task.await.unwrap();
+        }
+    }
+
+    #[tokio::test]
+    async fn test_overlapping_keys_do_not_deadlock() {
+        let locks = Arc::new(PartitionLocks::new(1));
+
+        let tasks = (0..8i64)
+            .map(|i| {
+                let locks = Arc::clone(&locks);
+                tokio::spawn(async move {
+                    for _ in 0..100 {
+                        // Each task locks the keys in a different order.
+                        let keys = if i % 2 == 0 { [1, 2, 3] } else { [3, 2, 1] };
+                        let _guard = locks.lock(keys).await;
+                        tokio::task::yield_now().await;
+                    }
+                })
+            })
+            .collect::<Vec<_>>();
+
+        for task in tasks {
+            task.await.unwrap();
+        }
+    }
+
+    #[tokio::test]
+    async fn test_duplicate_keys() {
+        let locks = PartitionLocks::new(1);
+        // Locking the same partition twice within an operation must not deadlock.
+        let _guard = locks.lock([7i64, 7]).await;
+    }
+}
@@ -0,0 +1,212 @@
+//! The replication settings of the keyspaces created by the tool.
+//!
+//! Both frontends describe the replication with the strategy, the replication
+//! factor and the strategy-specific options, e.g. the per-datacenter factors of
+//! `NetworkTopologyStrategy`: `strategy=NetworkTopologyStrategy,dc1=3,dc2=2`.
+
+use std::collections::BTreeMap;
+
+use anyhow::{Context, Result};
+
+pub const SIMPLE_STRATEGY: &str = "SimpleStrategy";
+pub const NETWORK_TOPOLOGY_STRATEGY: &str = "NetworkTopologyStrategy";
+
+const CLASS_OPTION: &str = "class";
+const REPLICATION_FACTOR_OPTION: &str = "replication_factor";
+
+/// The replication options of a keyspace, i.e. the `class` of the strategy
+/// and its parameters, ordered by name.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Replication {
+    pub options: BTreeMap<String, String>,
+}
+
+impl Replication {
+    /// Creates the replication of the given strategy.
+    ///
+    /// The `class` given among the `options` takes precedence over the `strategy`.
+    /// `NetworkTopologyStrategy` accepts the per-datacenter factors instead of
+    /// the `factor`, which is then set only if given explicitly. The other
+    /// strategies get `factor`, 1 by default.
+    pub fn new(
+        strategy: &str,
+        factor: Option<u64>,
+        options: impl IntoIterator<Item = (String, String)>,
+    ) -> Result<Self> {
+        let mut options = options.into_iter().collect::<BTreeMap<_, _>>();
+        let strategy = options
+            .entry(String::from(CLASS_OPTION))
+            .or_insert_with(|| strategy.to_owned())
+            .clone();
+
+        if is_network_topology_strategy(&strategy) {
+            for (datacenter, datacenter_factor) in options.iter() {
+                if datacenter == CLASS_OPTION {
+                    continue;
+                }
+                datacenter_factor.parse::<u64>().with_context(|| {
+                    format!(
+                        "Invalid replication factor of {}: {}",
+                        datacenter, datacenter_factor
+                    )
+                })?;
+            }
+            let has_datacenter_factors = options.len() > 1;
+            if factor.is_some() || !has_datacenter_factors {
+                options
+                    .entry(String::from(REPLICATION_FACTOR_OPTION))
+                    .or_insert_with(|| factor.unwrap_or(1).to_string());
+            }
+        } else {
+            options
+                .entry(String::from(REPLICATION_FACTOR_OPTION))
+                .or_insert_with(|| factor.unwrap_or(1).to_string());
+        }
+
+        Ok(Self { options })
+    }
+
+    /// Parses the comma-separated `key=value` options, where `strategy=` sets the
+    /// strategy ([SIMPLE_STRATEGY] by default), `factor=` the replication factor
+    /// (`default_factor` if not given) and the remaining keys the strategy-specific
+    /// options, e.g. `strategy=NetworkTopologyStrategy,dc1=3,dc2=2`.
+    pub fn parse(s: &str, default_factor: u64) -> Result<Self> {
+        let mut strategy = SIMPLE_STRATEGY;
+        let mut factor = None;
+        let mut options = Vec::new();
+        for option in s
+            .split(',')
+            .map(str::trim)
+            .filter(|option| !option.is_empty())
+        {
+            let (key, value) = option
+                .split_once('=')
+                .with_context(|| format!("Invalid replication option: {}", option))?;
+            match key {
+                "strategy" => strategy = value,
+                "factor" => {
+                    factor = Some(
+                        value
+                            .parse()
+                            .with_context(|| format!("Invalid replication factor: {}", value))?,
+                    )
+                }
+                _ => options.push((key.to_owned(), value.to_owned())),
+            }
+        }
+        // The per-datacenter factors replace the default factor.
+        if factor.is_none() && (options.is_empty() || !is_network_topology_strategy(strategy)) {
+            factor = Some(default_factor);
+        }
+        Self::new(strategy, factor, options)
+    }
+
+    /// Returns the CQL map literal of the options, used in `CREATE KEYSPACE`.
+    pub fn to_cql(&self) -> String {
+        let options_str = self
+            .options
+            .iter()
+            .map(|(key, value)| format!("'{}': '{}'", key, value))
+            .collect::<Vec<_>>()
+            .join(", ");
+
+        format!("{{{}}}", options_str)
+    }
+}
+
+/// Returns true if the strategy (possibly given with the Java package name)
+/// is `NetworkTopologyStrategy`.
+pub fn is_network_topology_strategy(strategy: &str) -> bool {
+    let class_name = strategy.rsplit('.').next().unwrap_or(strategy);
+    class_name.eq_ignore_ascii_case(NETWORK_TOPOLOGY_STRATEGY)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn options(replication: &Replication) -> Vec<(&str, &str)> {
+        replication
+            .options
+            .iter()
+            .map(|(key, value)| (key.as_str(), value.as_str()))
+            .collect()
+    }
+
+    #[test]
+    fn simple_strategy_test() {
+        let replication = Replication::new(SIMPLE_STRATEGY, Some(3), vec![]).unwrap();
+        assert_eq!(
+            "{'class': 'SimpleStrategy', 'replication_factor': '3'}",
+            replication.to_cql()
+        );
+        let replication = Replication::new(SIMPLE_STRATEGY, None, vec![]).unwrap();
+        assert_eq!(
+            vec![("class", "SimpleStrategy"), ("replication_factor", "1")],
+            options(&replication)
+        );
+    }
+
+    #[test]
+    fn network_topology_strategy_test() {
+        let dc_factors = || {
+            vec![
+                (String::from("dc2"), String::from("2")),
+                (String::from("dc1"), String::from("3")),
+            ]
+        };
+
+        let replication = Replication::new(NETWORK_TOPOLOGY_STRATEGY, None, dc_factors()).unwrap();
+        assert_eq!(
+            "{'class': 'NetworkTopologyStrategy', 'dc1': '3', 'dc2': '2'}",
+            replication.to_cql()
+        );
+
+        // The explicit factor applies to the datacenters without their own factor.
+        let replication = Replication::new(
+            "org.apache.cassandra.locator.NetworkTopologyStrategy",
+            Some(1),
+            dc_factors(),
+        )
+        .unwrap();
+        assert_eq!(
+            Some("1"),
+            replication
+                .options
+                .get("replication_factor")
+                .map(String::as_str)
+        );
+
+        let replication = Replication::new(NETWORK_TOPOLOGY_STRATEGY, None, vec![]).unwrap();
+        assert_eq!(
+            Some("1"),
+            replication
+                .options
+                .get("replication_factor")
+                .map(String::as_str)
+        );
+
+        let bad_factors = vec![(String::from("dc1"), String::from("three"))];
+        assert!(Replication::new(NETWORK_TOPOLOGY_STRATEGY, None, bad_factors).is_err());
+    }
+
+    #[test]
+    fn parse_test() {
+        assert_eq!(
+            Replication::new(SIMPLE_STRATEGY, Some(2), vec![]).unwrap(),
+            Replication::parse("", 2).unwrap()
+        );
+        assert_eq!(
+            "{'class': 'SimpleStrategy', 'replication_factor': '3'}",
+            Replication::parse("factor=3", 2).unwrap().to_cql()
+        );
+        assert_eq!(
+            "{'class': 'NetworkTopologyStrategy', 'dc1': '3', 'dc2': '2'}",
+            Replication::parse("strategy=NetworkTopologyStrategy,dc1=3,dc2=2", 1)
+                .unwrap()
+                .to_cql()
+        );
+        assert!(Replication::parse("strategy=NetworkTopologyStrategy,dc1", 1).is_err());
+        assert!(Replication::parse("factor=-1", 1).is_err());
+    }
+}
@@ -0,0 +1,107 @@
+//! Re-preparation of the statements invalidated by schema changes.
+//!
+//! The driver re-prepares a statement on its own when the node reports
+//! that it doesn't know the statement, but the metadata of the statement
+//! (e.g. the types of the bind markers) is never refreshed. After the table
+//! is altered or recreated, e.g. during a rolling upgrade, the operations
+//! need to prepare their statements again instead of failing until the end
+//! of the run.
+
+use anyhow::Result;
+use scylla::prepared_statement::PreparedStatement;
+use scylla::transport::errors::{BadQuery, DbError, QueryError};
+use scylla::Session;
+
+/// Tells whether the operation failed because its prepared statement
+/// doesn't match the current schema, so preparing it again may help.
+pub fn is_stale_statement_error(err: &anyhow::Error) -> bool {
+    matches!(
+        err.downcast_ref::<QueryError>(),
+        Some(
+            QueryError::DbError(DbError::Unprepared { .. }, _)
+                | QueryError::BadQuery(
+                    BadQuery::SerializationError(_) | BadQuery::SerializeValuesError(_)
+                )
+        )
+    )
+}
+
+/// Prepares the statement again, keeping the settings of the old one
+/// (consistency, timeout, idempotence etc.).
+pub async fn reprepare(session: &Session, old: &PreparedStatement) -> Result<PreparedStatement> {
+    let mut statement = session.prepare(old.get_statement()).await?;
+
+    if let Some(consistency) = old.get_consistency() {
+        statement.set_consistency(consistency);
+    }
+    statement.set_serial_consistency(old.get_serial_consistency());
+    statement.set_is_idempotent(old.get_is_idempotent());
+    statement.set_tracing(old.get_tracing());
+    statement.set_use_cached_result_metadata(old.get_use_cached_result_metadata());
+    statement.set_timestamp(old.get_timestamp());
+    statement.set_request_timeout(old.get_request_timeout());
+    statement.set_page_size(old.get_page_size());
+    statement.set_retry_policy(old.get_retry_policy().cloned());
+    statement.set_execution_profile_handle(old.get_execution_profile_handle().cloned());
+
+    Ok(statement)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use scylla::statement::{Consistency, SerialConsistency};
+    use scylla::transport::errors::{BadQuery, DbError, QueryError};
+
+    use super::{is_stale_statement_error, reprepare};
+    use crate::test_util::new_test_session;
+
+    #[test]
+    fn test_is_stale_statement_error() {
+        let unprepared = QueryError::DbError(
+            DbError::Unprepared {
+                statement_id: Default::default(),
+            },
+            String::new(),
+        );
+        assert!(is_stale_statement_error(&unprepared.into()));
+
+        let bad_values =
+            anyhow::Error::from(QueryError::BadQuery(BadQuery::ValuesTooLongForKey(1, 2)))
+                .context("write error");
+        assert!(!is_stale_statement_error(&bad_values));
+
+        let overloaded = QueryError::DbError(DbError::Overloaded, String::new());
+        assert!(!is_stale_statement_error(&overloaded.into()));
+        assert!(!is_stale_statement_error(&anyhow::anyhow!("other")));
+    }
+
+    #[tokio::test]
+    async fn test_reprepare_keeps_settings() {
+        let session = new_test_session().await;
+        let mut statement = session
+            .prepare("SELECT * FROM system.local WHERE key = ?")
+            .await
+            .unwrap();
+        statement.set_consistency(Consistency::One);
+        statement.set_serial_consistency(Some(SerialConsistency::LocalSerial));
+        statement.set_is_idempotent(true);
+        statement.set_request_timeout(Some(Duration::from_secs(3)));
+        statement.set_page_size(100);
+
+        let reprepared = reprepare(&session, &statement).await.unwrap();
+        assert_eq!(reprepared.get_statement(), statement.get_statement());
+        assert_eq!(reprepared.get_consistency(), Some(Consistency::One));
+        assert_eq!(
+            reprepared.get_serial_consistency(),
+            Some(SerialConsistency::LocalSerial)
+        );
+        assert!(reprepared.get_is_idempotent());
+        assert_eq!(
+            reprepared.get_request_timeout(),
+            Some(Duration::from_secs(3))
+        );
+        assert_eq!(reprepared.get_page_size(), 100);
+    }
+}
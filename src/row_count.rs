@@ -0,0 +1,108 @@
+//! Assertions on the number of rows returned by the reads.
+
+use std::fmt;
+use std::ops::RangeInclusive;
+
+use anyhow::{Context, Result};
+
+/// The number of rows a single read is expected to return,
+/// either an exact count or an inclusive range.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ExpectedRowCount(RangeInclusive<u64>);
+
+impl ExpectedRowCount {
+    /// Parses the expected row count in the form of `N` or `MIN..MAX`.
+    pub fn parse(s: &str) -> Result<Self> {
+        let parse_count = |count: &str| {
+            count
+                .trim()
+                .parse::<u64>()
+                .with_context(|| format!("Invalid row count: {}", count))
+        };
+        let range = match s.split_once("..") {
+            Some((min, max)) => parse_count(min)?..=parse_count(max)?,
+            None => {
+                let count = parse_count(s)?;
+                count..=count
+            }
+        };
+        anyhow::ensure!(
+            range.start() <= range.end(),
+            "Invalid row count range: {}",
+            s
+        );
+        Ok(Self(range))
+    }
+
+    /// Fails with [RowCountMismatch] if `actual` is not the expected row count.
+    pub fn check(&self, actual: u64) -> Result<(), RowCountMismatch> {
+        if self.0.contains(&actual) {
+            Ok(())
+        } else {
+            Err(RowCountMismatch {
+                expected: self.clone(),
+                actual,
+            })
+        }
+    }
+}
+
+impl fmt::Display for ExpectedRowCount {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.0.start() == self.0.end() {
+            write!(f, "{}", self.0.start())
+        } else {
+            write!(f, "{}..{}", self.0.start(), self.0.end())
+        }
+    }
+}
+
+/// The error of a read which returned an unexpected number of rows.
+/// It's counted as a data loss error in the stats.
+#[derive(Debug)]
+pub struct RowCountMismatch {
+    pub expected: ExpectedRowCount,
+    pub actual: u64,
+}
+
+impl fmt::Display for RowCountMismatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "expected {} rows, but the read returned {}",
+            self.expected, self.actual
+        )
+    }
+}
+
+impl std::error::Error for RowCountMismatch {}
+
+#[cfg(test)]
+mod tests {
+    use super::ExpectedRowCount;
+
+    #[test]
+    fn test_expected_row_count() {
+        let exact = ExpectedRowCount::parse("10").unwrap();
+        assert_eq!(exact.to_string(), "10");
+        assert!(exact.check(10).is_ok());
+        assert!(exact.check(9).is_err());
+        assert!(exact.check(11).is_err());
+
+        let range = ExpectedRowCount::parse("1..5").unwrap();
+        assert_eq!(range.to_string(), "1..5");
+        assert!(range.check(1).is_ok());
+        assert!(range.check(5).is_ok());
+        assert!(range.check(0).is_err());
+        assert_eq!(
+            range.check(6).unwrap_err().to_string(),
+            "expected 1..5 rows, but the read returned 6"
+        );
+
+        assert!(ExpectedRowCount::parse("").is_err());
+        assert!(ExpectedRowCount::parse("abc").is_err());
+        assert!(ExpectedRowCount::parse("-1").is_err());
+        assert!(ExpectedRowCount::parse("5..1").is_err());
+        assert!(ExpectedRowCount::parse("1..").is_err());
+    }
+}
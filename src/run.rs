@@ -1,42 +1,188 @@
 use std::future::Future;
 use std::ops::ControlFlow;
-use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
 use std::sync::{Arc, Mutex};
 use std::time::Duration;
 
 use anyhow::Result;
 use futures::future::{AbortHandle, Abortable, Fuse, FutureExt};
 use futures::stream::{FuturesUnordered, StreamExt};
-use tokio::sync::oneshot;
+use rand::Rng;
+use rand_distr::Exp1;
+use tokio::runtime::Handle;
+use tokio::sync::{oneshot, Notify, OwnedSemaphorePermit, Semaphore};
 use tokio::time::Instant;
 
-use crate::configuration::{Configuration, OperationContext};
+use crate::configuration::{
+    Arrival, Configuration, OperationContext, OperationFactory, RateLimitMode, RetryBackoff,
+    StreamConfiguration,
+};
 
 // Rate limits operations by issuing timestamps indicating when the next
 // operation should happen. Uses atomics, can be shared between threads.
 struct RateLimiter {
     base: Instant,
-    increment_nanos: u64,
+    increment_nanos: AtomicU64,
     nanos_counter: AtomicU64,
+    mode: RateLimitMode,
+    arrival: Arrival,
 }
 
 impl RateLimiter {
-    pub fn new(base: Instant, ops_per_second: f64) -> Self {
-        let increment_nanos = (1_000_000_000f64 / ops_per_second) as u64;
+    pub fn new(base: Instant, ops_per_second: f64, mode: RateLimitMode, arrival: Arrival) -> Self {
         Self {
             base,
-            increment_nanos,
+            increment_nanos: AtomicU64::new(Self::increment_nanos(ops_per_second)),
             nanos_counter: AtomicU64::new(0),
+            mode,
+            arrival,
         }
     }
 
+    fn increment_nanos(ops_per_second: f64) -> u64 {
+        (1_000_000_000f64 / ops_per_second) as u64
+    }
+
     pub fn issue_next_start_time(&self) -> Instant {
-        let nanos = self
-            .nanos_counter
-            .fetch_add(self.increment_nanos, Ordering::Relaxed);
+        match self.mode {
+            RateLimitMode::Schedule => self.issue_start_time_after(None),
+            RateLimitMode::Throttle => self.issue_start_time_after(Some(Instant::now())),
+        }
+    }
+
+    // Issues the next slot of the schedule. If `now` is given, the slots
+    // before it were missed and are skipped instead of being issued.
+    fn issue_start_time_after(&self, now: Option<Instant>) -> Instant {
+        let increment_nanos = self.next_increment_nanos();
+        let nanos = match now {
+            None => self
+                .nanos_counter
+                .fetch_add(increment_nanos, Ordering::Relaxed),
+            Some(now) => {
+                let now_nanos = now.saturating_duration_since(self.base).as_nanos() as u64;
+                let previous = self
+                    .nanos_counter
+                    .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |nanos| {
+                        Some(nanos.max(now_nanos) + increment_nanos)
+                    })
+                    .unwrap();
+                previous.max(now_nanos)
+            }
+        };
 
         self.base + Duration::from_nanos(nanos)
     }
+
+    // Draws the interval before the next slot, according to the arrival.
+    fn next_increment_nanos(&self) -> u64 {
+        let mean_nanos = self.increment_nanos.load(Ordering::Relaxed);
+        match self.arrival {
+            Arrival::Uniform => mean_nanos,
+            Arrival::Poisson => {
+                let factor: f64 = rand::thread_rng().sample(Exp1);
+                (mean_nanos as f64 * factor) as u64
+            }
+        }
+    }
+
+    // Changes the rate of the timestamps issued from now on.
+    pub fn set_rate(&self, ops_per_second: f64, now: Instant) {
+        self.increment_nanos
+            .store(Self::increment_nanos(ops_per_second), Ordering::Relaxed);
+        // Drop the backlog of the operations which were scheduled with
+        // the previous rate, but not started yet.
+        self.skip_backlog(now);
+    }
+
+    // Drops the slots before `now` which weren't issued yet. Otherwise,
+    // the workers would try to catch up with them at full speed.
+    pub fn skip_backlog(&self, now: Instant) {
+        let now_nanos = (now - self.base).as_nanos() as u64;
+        self.nanos_counter.fetch_max(now_nanos, Ordering::Relaxed);
+    }
+}
+
+// Limits the number of retries per second with a token bucket, which holds
+// up to a second's worth of retries and is refilled continuously.
+struct RetryBudget {
+    per_second: f64,
+    // The tokens left and the time they were last refilled at.
+    state: Mutex<(f64, Instant)>,
+}
+
+impl RetryBudget {
+    fn new(per_second: f64, now: Instant) -> Self {
+        Self {
+            per_second,
+            state: Mutex::new((per_second.max(1.0), now)),
+        }
+    }
+
+    // Takes a token for a retry, if there is any left.
+    fn try_take(&self, now: Instant) -> bool {
+        let mut state = self.state.lock().unwrap();
+        let (tokens, refilled_at) = &mut *state;
+        let elapsed = now.saturating_duration_since(*refilled_at).as_secs_f64();
+        *tokens = (*tokens + elapsed * self.per_second).min(self.per_second.max(1.0));
+        *refilled_at = now;
+        if *tokens >= 1.0 {
+            *tokens -= 1.0;
+            true
+        } else {
+            false
+        }
+    }
+}
+
+// Splits the workers of a stream into groups, one per runtime
+// (see `Configuration::runtimes`). Returns the number of workers in each group.
+fn worker_groups(concurrency: u64, runtimes: usize) -> Vec<u64> {
+    let count = (runtimes as u64).min(concurrency).max(1);
+    (0..count)
+        .map(|group| concurrency / count + u64::from(group < concurrency % count))
+        .collect()
+}
+
+// Single-threaded runtimes, each running on its own thread, on which
+// the workers are spawned instead of the runtime of the caller.
+// The runtimes, together with the workers still running on them,
+// are shut down once this is dropped.
+struct WorkerRuntimes {
+    handles: Vec<Handle>,
+    // Dropping the senders lets the threads finish.
+    _stop_senders: Vec<oneshot::Sender<()>>,
+}
+
+impl WorkerRuntimes {
+    fn start(count: usize) -> Result<Self> {
+        let mut handles = Vec::with_capacity(count);
+        let mut stop_senders = Vec::with_capacity(count);
+        for idx in 0..count {
+            let runtime = tokio::runtime::Builder::new_current_thread()
+                .enable_all()
+                .build()?;
+            let (stop_sender, stop_receiver) = oneshot::channel::<()>();
+            handles.push(runtime.handle().clone());
+            stop_senders.push(stop_sender);
+            std::thread::Builder::new()
+                .name(format!("cql-stress-worker-{}", idx))
+                .spawn(move || {
+                    // A current-thread runtime runs its tasks only inside `block_on`.
+                    runtime.block_on(async {
+                        let _ = stop_receiver.await;
+                    });
+                })?;
+        }
+
+        Ok(Self {
+            handles,
+            _stop_senders: stop_senders,
+        })
+    }
+
+    fn spawn(&self, idx: usize, fut: impl Future<Output = ()> + Send + 'static) {
+        self.handles[idx].spawn(fut);
+    }
 }
 
 // When an operation ID equal or larger to this value is issued, the worker
@@ -50,20 +196,77 @@ const INVALID_OP_ID_THRESHOLD: u64 = 1u64 << 63u64;
 // Represents shareable state and configuration of a worker.
 struct WorkerContext {
     operation_counter: AtomicU64,
-
-    rate_limiter: Option<RateLimiter>,
+    // Operation IDs equal or larger to this value are not issued.
+    operation_limit: u64,
+
+    // One per group of the workers, each limiting its share of the rate.
+    // Only consulted while `rate_limited` is set, so that the limit
+    // can be imposed on a stream started without one.
+    rate_limiters: Vec<RateLimiter>,
+    rate_limited: AtomicBool,
+    // The fraction of the total rate of the run given to this stream.
+    rate_share: f64,
+    // The fraction of the workers of the stream in each of the groups.
+    group_shares: Vec<f64>,
     max_retries_per_op: usize,
+    operation_timeout: Option<Duration>,
+    // Records the attempts cancelled because of `operation_timeout`.
+    operation_factory: Arc<dyn OperationFactory>,
+    retry_backoff: RetryBackoff,
+    // Shared by the contexts of all streams.
+    in_flight: Option<Arc<Semaphore>>,
+    // The number of the operations in flight, shared by the contexts of all streams.
+    running: Arc<AtomicU64>,
+    retry_budget: Option<Arc<RetryBudget>>,
+    // While set, the workers don't start new operations.
+    paused: AtomicBool,
+    resumed: Notify,
 }
 
 impl WorkerContext {
-    pub fn new(config: &Configuration, now: Instant) -> Self {
+    pub fn new(
+        config: &Configuration,
+        stream: &StreamConfiguration,
+        in_flight: Option<Arc<Semaphore>>,
+        running: Arc<AtomicU64>,
+        retry_budget: Option<Arc<RetryBudget>>,
+        rate_share: f64,
+        now: Instant,
+    ) -> Self {
+        let group_shares = worker_groups(stream.concurrency, config.runtimes.unwrap_or(1))
+            .into_iter()
+            .map(|workers| workers as f64 / stream.concurrency as f64)
+            .collect::<Vec<_>>();
+        // Without a limit, the rate is only a placeholder until one is set.
+        let rate = stream.rate_limit_per_second.unwrap_or(1.0);
+        let rate_limiters = group_shares
+            .iter()
+            .map(|share| {
+                RateLimiter::new(now, rate * share, stream.rate_limit_mode, stream.arrival)
+            })
+            .collect();
+
         Self {
             operation_counter: AtomicU64::new(0),
-
-            rate_limiter: config
-                .rate_limit_per_second
-                .map(|rate| RateLimiter::new(now, rate)),
+            operation_limit: config
+                .max_operations
+                .map_or(INVALID_OP_ID_THRESHOLD, |max_ops| {
+                    max_ops.min(INVALID_OP_ID_THRESHOLD)
+                }),
+
+            rate_limiters,
+            rate_limited: AtomicBool::new(stream.rate_limit_per_second.is_some()),
+            rate_share,
+            group_shares,
             max_retries_per_op: config.max_retries_per_op,
+            operation_timeout: config.operation_timeout,
+            operation_factory: Arc::clone(&stream.operation_factory),
+            retry_backoff: config.retry_backoff,
+            in_flight,
+            running,
+            retry_budget,
+            paused: AtomicBool::new(false),
+            resumed: Notify::new(),
         }
     }
 
@@ -71,6 +274,35 @@ impl WorkerContext {
     pub fn ask_to_stop(&self) {
         self.operation_counter
             .store(INVALID_OP_ID_THRESHOLD, Ordering::Relaxed);
+        // Let the paused workers notice it.
+        self.resumed.notify_waiters();
+    }
+
+    // Holds the workers back from starting new operations.
+    fn pause(&self) {
+        self.paused.store(true, Ordering::Relaxed);
+    }
+
+    // Lets the paused workers continue. The operations which would have
+    // been started during the pause according to the rate are skipped.
+    fn resume(&self, now: Instant) {
+        for rate_limiter in &self.rate_limiters {
+            rate_limiter.skip_backlog(now);
+        }
+        self.paused.store(false, Ordering::Relaxed);
+        self.resumed.notify_waiters();
+    }
+
+    // Waits until the workers are resumed or asked to stop.
+    async fn wait_while_paused(&self) {
+        loop {
+            // Registered before checking the state, so that no change is missed.
+            let resumed = self.resumed.notified();
+            if !self.paused.load(Ordering::Relaxed) || self.should_stop() {
+                return;
+            }
+            resumed.await;
+        }
     }
 
     // Was the worker asked to stop?
@@ -79,55 +311,130 @@ impl WorkerContext {
     }
 
     // Issues the next operation id. If the context got a signal to stop
-    // the stress operation, it will return `None`.
+    // the stress operation or the operation limit was reached,
+    // it will return `None`.
     fn issue_operation_id(&self) -> Option<u64> {
         let id = self.operation_counter.fetch_add(1, Ordering::Relaxed);
-        (id < INVALID_OP_ID_THRESHOLD).then_some(id)
+        (id < self.operation_limit).then_some(id)
+    }
+
+    // Whether the budget allows one more retry.
+    fn may_retry(&self) -> bool {
+        self.retry_budget
+            .as_ref()
+            .map_or(true, |budget| budget.try_take(Instant::now()))
     }
 }
 
 pub struct WorkerSession {
     context: Arc<WorkerContext>,
+    // The group of the workers of the stream this worker belongs to.
+    group: usize,
     op_id: u64,
     trial_idx: usize,
+    // Held while the attempt is in flight, if their number is limited.
+    in_flight_permit: Option<OwnedSemaphorePermit>,
+    // Whether the attempt is counted in `WorkerContext::running`.
+    running: bool,
 }
 
 // Not the most beautiful interface, but it works - unlike async callbacks,
 // which I also tried, but failed to make the types work.
 impl WorkerSession {
-    fn new(context: Arc<WorkerContext>) -> Self {
+    fn new(context: Arc<WorkerContext>, group: usize) -> Self {
         Self {
             context,
+            group,
             op_id: 0,
             trial_idx: 0,
+            in_flight_permit: None,
+            running: false,
         }
     }
 
     // Should be called before starting an operation.
-    pub async fn start_operation(&mut self) -> Option<OperationContext> {
+    // `on_wait` is called before the worker waits until the given instant,
+    // or indefinitely (`None`), e.g. so that the operation can flush
+    // the stats it buffered instead of keeping them for the time of the wait.
+    pub async fn start_operation(
+        &mut self,
+        mut on_wait: impl FnMut(Option<std::time::Instant>),
+    ) -> Option<OperationContext> {
+        if self.context.paused.load(Ordering::Relaxed) {
+            on_wait(None);
+        }
+        self.context.wait_while_paused().await;
         if self.trial_idx == 0 {
             let next_op_id = self.context.issue_operation_id()?;
             self.op_id = next_op_id;
+        } else {
+            let delay = self.context.retry_backoff.delay(self.trial_idx);
+            if !delay.is_zero() {
+                on_wait(Some(std::time::Instant::now() + delay));
+                tokio::time::sleep(delay).await;
+            }
         }
 
-        let scheduled_start_time = if let Some(rate_limiter) = &self.context.rate_limiter {
-            let start_time = rate_limiter.issue_next_start_time();
+        let scheduled_start_time = if self.context.rate_limited.load(Ordering::Relaxed) {
+            let start_time = self.context.rate_limiters[self.group].issue_next_start_time();
+            if start_time > Instant::now() {
+                on_wait(Some(start_time.into_std()));
+            }
             tokio::time::sleep_until(start_time).await;
             start_time
         } else {
             Instant::now()
         };
+        if let Some(in_flight) = &self.context.in_flight {
+            let permit = match Arc::clone(in_flight).try_acquire_owned() {
+                Ok(permit) => permit,
+                Err(_) => {
+                    on_wait(None);
+                    // The semaphore is never closed.
+                    Arc::clone(in_flight).acquire_owned().await.unwrap()
+                }
+            };
+            self.in_flight_permit = Some(permit);
+        }
+        let in_flight = self.context.running.fetch_add(1, Ordering::Relaxed) + 1;
+        self.running = true;
         let actual_start_time = Instant::now();
 
         Some(OperationContext {
             operation_id: self.op_id,
             scheduled_start_time,
             actual_start_time,
+            in_flight,
         })
     }
 
+    // Executes the operation, enforcing the operation timeout if configured.
+    // A cancelled attempt can't record its result on its own, so it is
+    // reported to the operation factory instead.
+    pub async fn run_operation(
+        &self,
+        ctx: &OperationContext,
+        op: impl Future<Output = Result<ControlFlow<()>>>,
+    ) -> Result<ControlFlow<()>> {
+        let Some(timeout) = self.context.operation_timeout else {
+            return op.await;
+        };
+        match tokio::time::timeout(timeout, op).await {
+            Ok(result) => result,
+            Err(_) => {
+                let err = OperationTimedOut(timeout).into();
+                self.context.operation_factory.record_timeout(ctx, &err);
+                Err(err)
+            }
+        }
+    }
+
     // Should be called after ending an operation.
     pub fn end_operation(&mut self, result: Result<ControlFlow<()>>) -> Result<ControlFlow<()>> {
+        self.in_flight_permit = None;
+        if std::mem::take(&mut self.running) {
+            self.context.running.fetch_sub(1, Ordering::Relaxed);
+        }
         match result {
             Ok(flow) => {
                 self.trial_idx = 0;
@@ -135,6 +442,7 @@ impl WorkerSession {
             }
             Err(err) if self.trial_idx >= self.context.max_retries_per_op => Err(err),
             Err(err) if self.context.should_stop() => Err(err),
+            Err(err) if !self.context.may_retry() => Err(err),
             Err(_) => {
                 self.trial_idx += 1;
                 Ok(ControlFlow::Continue(()))
@@ -143,13 +451,21 @@ impl WorkerSession {
     }
 }
 
+// The time at which the run is stopped, which can be changed while it runs.
+struct Deadline {
+    time: Mutex<Option<Instant>>,
+    changed: Notify,
+}
+
 /// Allows controlling the state of the run.
 ///
-/// Currently, the `RunController` is only able to either gracefully stop
-/// or abort the run.
+/// The `RunController` is able to gracefully stop or abort the run,
+/// to pause it, and to change its rate limit and deadline while it runs.
 pub struct RunController {
     stop_sender: Mutex<Option<oneshot::Sender<()>>>,
     abort_handle: AbortHandle,
+    contexts: Vec<Arc<WorkerContext>>,
+    deadline: Arc<Deadline>,
 }
 
 impl RunController {
@@ -172,8 +488,95 @@ impl RunController {
     pub fn abort(&self) {
         self.abort_handle.abort();
     }
+
+    /// Changes the maximum number of operations of the given stream
+    /// (the index in [Configuration::streams]) started per second.
+    ///
+    /// The stream becomes rate limited if it was started without a limit.
+    pub fn set_rate_limit(&self, stream: usize, ops_per_second: f64) {
+        let context = &self.contexts[stream];
+        let now = Instant::now();
+        for (rate_limiter, share) in context.rate_limiters.iter().zip(&context.group_shares) {
+            rate_limiter.set_rate(ops_per_second * share, now);
+        }
+        context.rate_limited.store(true, Ordering::Relaxed);
+    }
+
+    /// Lets the given stream start the operations as fast as it can.
+    pub fn remove_rate_limit(&self, stream: usize) {
+        self.contexts[stream]
+            .rate_limited
+            .store(false, Ordering::Relaxed);
+    }
+
+    /// Changes the maximum number of operations started per second by all streams,
+    /// or removes the limit if `None`.
+    ///
+    /// The rate is split between the streams in proportion to their initial
+    /// rate limits if all of them had one, or to their concurrency otherwise.
+    pub fn set_total_rate_limit(&self, ops_per_second: Option<f64>) {
+        for (stream, context) in self.contexts.iter().enumerate() {
+            match ops_per_second {
+                Some(rate) => self.set_rate_limit(stream, rate * context.rate_share),
+                None => self.remove_rate_limit(stream),
+            }
+        }
+    }
+
+    /// Pauses the run: the workers finish their current operations,
+    /// but don't start new ones until [RunController::resume] is called.
+    ///
+    /// The time of the pause counts towards the duration of the run.
+    pub fn pause(&self) {
+        for context in &self.contexts {
+            context.pause();
+        }
+    }
+
+    /// Resumes the paused run. The rate limited streams don't try to catch up
+    /// with the operations they would have started during the pause.
+    pub fn resume(&self) {
+        let now = Instant::now();
+        for context in &self.contexts {
+            context.resume(now);
+        }
+    }
+
+    /// Changes the time at which the run is stopped, or lets it run
+    /// until asked to stop if `None`.
+    ///
+    /// The run stops right away if the new deadline has already passed.
+    pub fn set_deadline(&self, deadline: Option<Instant>) {
+        *self.deadline.time.lock().unwrap() = deadline;
+        self.deadline.changed.notify_one();
+    }
 }
 
+/// The error returned by the run which was aborted via [RunController::abort].
+#[derive(Debug)]
+pub struct RunAborted;
+
+impl std::fmt::Display for RunAborted {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "The run was aborted")
+    }
+}
+
+impl std::error::Error for RunAborted {}
+
+/// The error of an operation attempt cancelled by the runner because it took
+/// longer than [Configuration::operation_timeout].
+#[derive(Debug)]
+pub struct OperationTimedOut(pub Duration);
+
+impl std::fmt::Display for OperationTimedOut {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Operation timed out after {}ms", self.0.as_millis())
+    }
+}
+
+impl std::error::Error for OperationTimedOut {}
+
 /// Runs an operation multiple times in parallel, according to config.
 ///
 /// Returns a pair (controller, future), where:
@@ -184,8 +587,40 @@ pub fn run(config: Configuration) -> (RunController, impl Future<Output = Result
     let (stop_sender, stop_receiver) = oneshot::channel();
     let (result_sender, result_receiver) = oneshot::channel();
 
+    let start_time = Instant::now();
+    let in_flight = config
+        .max_in_flight
+        .map(|max_in_flight| Arc::new(Semaphore::new(max_in_flight)));
+    let running = Arc::new(AtomicU64::new(0));
+    let retry_budget = config
+        .retry_budget
+        .map(|per_second| Arc::new(RetryBudget::new(per_second, start_time)));
+    let rate_shares = rate_shares(&config.streams);
+    let contexts = config
+        .streams
+        .iter()
+        .zip(rate_shares)
+        .map(|(stream, rate_share)| {
+            Arc::new(WorkerContext::new(
+                &config,
+                stream,
+                in_flight.clone(),
+                Arc::clone(&running),
+                retry_budget.clone(),
+                rate_share,
+                start_time,
+            ))
+        })
+        .collect::<Vec<_>>();
+    let deadline = Arc::new(Deadline {
+        time: Mutex::new(config.max_duration.map(|duration| start_time + duration)),
+        changed: Notify::new(),
+    });
+
+    let ctxs = contexts.clone();
+    let run_deadline = Arc::clone(&deadline);
     let fut = async move {
-        let res = do_run(config, stop_receiver).await;
+        let res = do_run(config, ctxs, run_deadline, stop_receiver).await;
         let _ = result_sender.send(res);
     };
 
@@ -196,58 +631,128 @@ pub fn run(config: Configuration) -> (RunController, impl Future<Output = Result
     let controller = RunController {
         stop_sender: Mutex::new(Some(stop_sender)),
         abort_handle,
+        contexts,
+        deadline,
     };
 
     let result_fut = async move {
         // If the run was aborted before it completed, the result channel
         // will be closed without sending a result.
         let result: Result<Result<()>, _> = result_receiver.await;
-        result.unwrap_or_else(|_| Err(anyhow::anyhow!("The run was aborted")))
+        result.unwrap_or_else(|_| Err(RunAborted.into()))
     };
 
     (controller, result_fut)
 }
 
-async fn do_run(config: Configuration, stop_receiver: oneshot::Receiver<()>) -> Result<()> {
-    let start_time = Instant::now();
-    let ctx = Arc::new(WorkerContext::new(&config, start_time));
-
-    // Spawn as many worker tasks as the concurrency allows
-    let mut worker_handles = (0..config.concurrency)
-        .map(|_| {
-            let ctx_clone = Arc::clone(&ctx);
-            let session = WorkerSession::new(ctx_clone);
-            let mut operation = config.operation_factory.create();
-            let (fut, handle) = async move { operation.run(session).await }.remote_handle();
-            tokio::task::spawn(fut);
-            handle
+// The fraction of the total rate limit given to each of the streams.
+fn rate_shares(streams: &[StreamConfiguration]) -> Vec<f64> {
+    let rates = streams
+        .iter()
+        .map(|stream| stream.rate_limit_per_second)
+        .collect::<Option<Vec<_>>>()
+        .filter(|rates| rates.iter().sum::<f64>() > 0.0);
+    let weights = rates.unwrap_or_else(|| {
+        streams
+            .iter()
+            .map(|stream| stream.concurrency as f64)
+            .collect()
+    });
+    let total = weights.iter().sum::<f64>();
+    weights.into_iter().map(|weight| weight / total).collect()
+}
+
+async fn do_run(
+    config: Configuration,
+    ctxs: Vec<Arc<WorkerContext>>,
+    deadline: Arc<Deadline>,
+    mut stop_receiver: oneshot::Receiver<()>,
+) -> Result<()> {
+    let runtimes = config.runtimes.map(WorkerRuntimes::start).transpose()?;
+
+    // Spawn as many worker tasks as the concurrency of each stream allows
+    let mut worker_handles = config
+        .streams
+        .iter()
+        .zip(&ctxs)
+        .flat_map(|(stream, ctx)| {
+            let runtimes = &runtimes;
+            (0..stream.concurrency).map(move |idx| {
+                // The workers of the group `i` run on the runtime `i`.
+                let group = (idx % ctx.group_shares.len() as u64) as usize;
+                let session = WorkerSession::new(Arc::clone(ctx), group);
+                let mut operation = stream.operation_factory.create();
+                let (fut, handle) = async move { operation.run(session).await }.remote_handle();
+                match runtimes {
+                    Some(runtimes) => runtimes.spawn(group, fut),
+                    None => {
+                        tokio::task::spawn(fut);
+                    }
+                }
+                handle
+            })
         })
         .collect::<FuturesUnordered<_>>();
 
-    // If there is a time limit, stop the run after the defined duration
-    let ctx_clone = Arc::clone(&ctx);
-    let sleeper = match config.max_duration {
-        Some(duration) => tokio::time::sleep_until(start_time + duration).fuse(),
-        None => Fuse::terminated(),
+    let ask_all_to_stop = |ctxs: &[Arc<WorkerContext>]| {
+        for ctx in ctxs {
+            ctx.ask_to_stop();
+        }
     };
+
+    // If there is a time limit, stop the run once the deadline passes.
+    // The deadline is waited for again whenever it changes.
+    let ctxs_clone = ctxs.clone();
     let _stopper_handle = {
         let (fut, handle) = async move {
-            futures::pin_mut!(sleeper);
-            futures::future::select(sleeper, stop_receiver).await;
-            ctx_clone.ask_to_stop();
+            loop {
+                // Registered before reading the deadline, so that no change is missed.
+                let changed = deadline.changed.notified();
+                let sleeper = match *deadline.time.lock().unwrap() {
+                    Some(time) => tokio::time::sleep_until(time).fuse(),
+                    None => Fuse::terminated(),
+                };
+                tokio::select! {
+                    _ = sleeper => break,
+                    _ = &mut stop_receiver => break,
+                    _ = changed => {}
+                }
+            }
+            ask_all_to_stop(&ctxs_clone);
         }
         .remote_handle();
         tokio::task::spawn(fut);
         handle
     };
 
+    // Pause and resume the workers according to the duty cycle, if any.
+    let _duty_cycle_handle = config.duty_cycle.map(|duty_cycle| {
+        let ctxs = ctxs.clone();
+        let (fut, handle) = async move {
+            loop {
+                tokio::time::sleep(duty_cycle.on).await;
+                for ctx in &ctxs {
+                    ctx.pause();
+                }
+                tokio::time::sleep(duty_cycle.off).await;
+                let now = Instant::now();
+                for ctx in &ctxs {
+                    ctx.resume(now);
+                }
+            }
+        }
+        .remote_handle();
+        tokio::task::spawn(fut);
+        handle
+    });
+
     let mut result: Result<()> = Ok(());
 
     // TODO: Collect all errors and report them
     while let Some(worker_result) = worker_handles.next().await {
         if let Err(err) = worker_result {
             result = Err(err);
-            ctx.ask_to_stop();
+            ask_all_to_stop(&ctxs);
         }
     }
 
@@ -266,7 +771,8 @@ mod tests {
 
     use super::*;
     use crate::configuration::{
-        make_runnable, Configuration, Operation, OperationContext, OperationFactory,
+        make_runnable, Configuration, DutyCycle, Operation, OperationContext, OperationFactory,
+        RetryBackoff, StreamConfiguration,
     };
 
     struct FnOperationFactory<F>(pub F);
@@ -286,7 +792,7 @@ mod tests {
         let count_in_period = |ops: f64, period: Duration| -> usize {
             let start = Instant::now();
             let end = start + period;
-            let limiter = RateLimiter::new(start, ops);
+            let limiter = RateLimiter::new(start, ops, RateLimitMode::Schedule, Arrival::Uniform);
 
             let mut count = 0;
             while limiter.issue_next_start_time() < end {
@@ -303,6 +809,51 @@ mod tests {
         assert_eq!(count_in_period(2.0, 10 * sec), 20);
     }
 
+    #[test]
+    fn test_rate_limiter_set_rate() {
+        let start = Instant::now();
+        let sec = Duration::from_secs(1);
+        let limiter = RateLimiter::new(start, 1.0, RateLimitMode::Schedule, Arrival::Uniform);
+
+        // The backlog of the previous rate is dropped.
+        limiter.set_rate(10.0, start + 5 * sec);
+        assert_eq!(limiter.issue_next_start_time(), start + 5 * sec);
+
+        let mut count = 1;
+        while limiter.issue_next_start_time() < start + 10 * sec {
+            count += 1;
+        }
+        assert_eq!(count, 50);
+
+        // Operations scheduled ahead of time are not rescheduled.
+        limiter.set_rate(1.0, start + 5 * sec);
+        assert_eq!(limiter.issue_next_start_time(), start + 10 * sec + sec / 10);
+    }
+
+    #[test]
+    fn test_rate_limiter_modes() {
+        let start = Instant::now();
+        let sec = Duration::from_secs(1);
+
+        // The workers were stalled for 5 seconds after the first operation.
+        let schedule = RateLimiter::new(start, 1.0, RateLimitMode::Schedule, Arrival::Uniform);
+        assert_eq!(schedule.issue_start_time_after(None), start);
+        // The missed slots are issued, so that the operations are started
+        // immediately and their latency is measured from the schedule.
+        for i in 1..=5 {
+            assert_eq!(schedule.issue_start_time_after(None), start + i * sec);
+        }
+
+        let throttle = RateLimiter::new(start, 1.0, RateLimitMode::Throttle, Arrival::Uniform);
+        assert_eq!(throttle.issue_start_time_after(Some(start)), start);
+        // The missed slots are skipped.
+        let now = start + 5 * sec + sec / 2;
+        assert_eq!(throttle.issue_start_time_after(Some(now)), now);
+        assert_eq!(throttle.issue_start_time_after(Some(now)), now + sec);
+        // The slots issued ahead of time are kept.
+        assert_eq!(throttle.issue_start_time_after(Some(now)), now + 2 * sec);
+    }
+
     fn make_test_cfg<T, F>(f: F) -> Configuration
     where
         T: Operation + 'static,
@@ -310,13 +861,44 @@ mod tests {
     {
         Configuration {
             max_duration: None,
-            concurrency: 10,
-            rate_limit_per_second: None,
-            operation_factory: Arc::new(FnOperationFactory(f)),
+            max_operations: None,
+            streams: vec![StreamConfiguration {
+                name: String::from("test"),
+                concurrency: 10,
+                rate_limit_per_second: None,
+                rate_limit_mode: RateLimitMode::Schedule,
+                arrival: Arrival::Uniform,
+                operation_factory: Arc::new(FnOperationFactory(f)),
+            }],
             max_retries_per_op: 0,
+            operation_timeout: None,
+            retry_backoff: RetryBackoff::None,
+            retry_budget: None,
+            max_in_flight: None,
+            runtimes: None,
+            duty_cycle: None,
         }
     }
 
+    #[test]
+    fn test_rate_limiter_poisson_arrival() {
+        let start = Instant::now();
+        let limiter = RateLimiter::new(start, 1000.0, RateLimitMode::Schedule, Arrival::Poisson);
+
+        let starts = (0..100_000)
+            .map(|_| limiter.issue_next_start_time())
+            .collect::<Vec<_>>();
+        let intervals = starts
+            .windows(2)
+            .map(|w| w[1] - w[0])
+            .collect::<HashSet<_>>();
+        assert!(intervals.len() > 1000);
+
+        // On average, the rate is kept.
+        let elapsed = (*starts.last().unwrap() - start).as_secs_f64();
+        assert!((95.0..105.0).contains(&elapsed), "{}", elapsed);
+    }
+
     #[tokio::test]
     async fn test_run_to_completion() {
         let counter = Arc::new(AtomicU64::new(0));
@@ -344,6 +926,111 @@ mod tests {
         assert_eq!(counter.load(Ordering::SeqCst), 499500);
     }
 
+    #[tokio::test]
+    async fn test_run_with_operation_limit() {
+        let counter = Arc::new(AtomicU64::new(0));
+
+        struct Op(Arc<AtomicU64>);
+        make_runnable!(Op);
+
+        impl Op {
+            async fn execute(&mut self, ctx: &OperationContext) -> Result<ControlFlow<()>> {
+                self.0.fetch_add(ctx.operation_id, Ordering::SeqCst);
+                Ok(ControlFlow::Continue(()))
+            }
+        }
+
+        let mut cfg = {
+            let counter = counter.clone();
+            make_test_cfg(move || Op(counter.clone()))
+        };
+        cfg.max_operations = Some(1000);
+
+        let (_, fut) = run(cfg);
+        fut.await.unwrap();
+        assert_eq!(counter.load(Ordering::SeqCst), 499500);
+    }
+
+    #[tokio::test]
+    async fn test_run_with_max_in_flight() {
+        // The number of operations in flight and its maximum.
+        #[derive(Default)]
+        struct InFlight {
+            current: AtomicU64,
+            max: AtomicU64,
+            // The maximum reported by the contexts of the operations.
+            max_reported: AtomicU64,
+        }
+
+        struct Op(Arc<InFlight>);
+        make_runnable!(Op);
+
+        impl Op {
+            async fn execute(&mut self, ctx: &OperationContext) -> Result<ControlFlow<()>> {
+                let current = self.0.current.fetch_add(1, Ordering::SeqCst) + 1;
+                self.0.max.fetch_max(current, Ordering::SeqCst);
+                self.0
+                    .max_reported
+                    .fetch_max(ctx.in_flight, Ordering::SeqCst);
+                tokio::time::sleep(Duration::from_millis(1)).await;
+                self.0.current.fetch_sub(1, Ordering::SeqCst);
+                Ok(ControlFlow::Continue(()))
+            }
+        }
+
+        let in_flight = Arc::new(InFlight::default());
+        let mut cfg = {
+            let in_flight = in_flight.clone();
+            make_test_cfg(move || Op(in_flight.clone()))
+        };
+        cfg.max_operations = Some(100);
+        cfg.max_in_flight = Some(3);
+
+        let (_, fut) = run(cfg);
+        fut.await.unwrap();
+        // The test configuration runs 10 workers.
+        assert_eq!(in_flight.max.load(Ordering::SeqCst), 3);
+        assert_eq!(in_flight.max_reported.load(Ordering::SeqCst), 3);
+    }
+
+    #[test]
+    fn test_worker_groups() {
+        assert_eq!(worker_groups(10, 1), vec![10]);
+        assert_eq!(worker_groups(10, 4), vec![3, 3, 2, 2]);
+        // There are no empty groups.
+        assert_eq!(worker_groups(2, 4), vec![1, 1]);
+    }
+
+    #[tokio::test]
+    async fn test_run_on_runtimes() {
+        let threads = Arc::new(Mutex::new(HashSet::new()));
+
+        struct Op(Arc<Mutex<HashSet<std::thread::ThreadId>>>);
+        make_runnable!(Op);
+
+        impl Op {
+            async fn execute(&mut self, _ctx: &OperationContext) -> Result<ControlFlow<()>> {
+                self.0.lock().unwrap().insert(std::thread::current().id());
+                Ok(ControlFlow::Continue(()))
+            }
+        }
+
+        let mut cfg = {
+            let threads = threads.clone();
+            make_test_cfg(move || Op(threads.clone()))
+        };
+        cfg.max_operations = Some(1000);
+        cfg.streams[0].rate_limit_per_second = Some(100_000.0);
+        cfg.runtimes = Some(3);
+
+        let (_, fut) = run(cfg);
+        fut.await.unwrap();
+        // The workers run on the threads of the dedicated runtimes.
+        let threads = threads.lock().unwrap();
+        assert_eq!(threads.len(), 3);
+        assert!(!threads.contains(&std::thread::current().id()));
+    }
+
     #[tokio::test]
     async fn test_run_to_error() {
         let counter = Arc::new(AtomicU64::new(0));
@@ -371,6 +1058,42 @@ mod tests {
         assert_eq!(counter.load(Ordering::SeqCst), 500);
     }
 
+    #[tokio::test]
+    async fn test_run_multiple_streams() {
+        struct Op(Arc<AtomicU64>);
+        make_runnable!(Op);
+
+        impl Op {
+            async fn execute(&mut self, ctx: &OperationContext) -> Result<ControlFlow<()>> {
+                self.0.fetch_add(ctx.operation_id, Ordering::SeqCst);
+                Ok(ControlFlow::Continue(()))
+            }
+        }
+
+        let counters = [Arc::new(AtomicU64::new(0)), Arc::new(AtomicU64::new(0))];
+        let mut cfg = {
+            let counter = counters[0].clone();
+            make_test_cfg(move || Op(counter.clone()))
+        };
+        let counter = counters[1].clone();
+        cfg.streams.push(StreamConfiguration {
+            name: String::from("second"),
+            concurrency: 3,
+            rate_limit_per_second: None,
+            rate_limit_mode: RateLimitMode::Schedule,
+            arrival: Arrival::Uniform,
+            operation_factory: Arc::new(FnOperationFactory(move || Op(counter.clone()))),
+        });
+        cfg.max_operations = Some(1000);
+
+        let (_, fut) = run(cfg);
+        fut.await.unwrap();
+        // Each of the streams performs its own sequence of operations.
+        for counter in counters {
+            assert_eq!(counter.load(Ordering::SeqCst), 499500);
+        }
+    }
+
     struct IdleOp;
 
     make_runnable!(IdleOp);
@@ -400,6 +1123,129 @@ mod tests {
         fut.await.unwrap();
     }
 
+    #[tokio::test]
+    #[ntest::timeout(1000)]
+    async fn test_run_with_changed_deadline() {
+        // Shortened, the run stops before its initial deadline.
+        let mut cfg = make_test_cfg(|| IdleOp);
+        cfg.max_duration = Some(Duration::from_secs(3600));
+        let (ctrl, fut) = run(cfg);
+        ctrl.set_deadline(Some(Instant::now() + Duration::from_millis(100)));
+        fut.await.unwrap();
+
+        // Removed, the run continues past its initial deadline.
+        let mut cfg = make_test_cfg(|| IdleOp);
+        cfg.max_duration = Some(Duration::from_millis(50));
+        let (ctrl, fut) = run(cfg);
+        ctrl.set_deadline(None);
+        let fut = tokio::spawn(fut);
+        tokio::time::sleep(Duration::from_millis(200)).await;
+        assert!(!fut.is_finished());
+        ctrl.set_deadline(Some(Instant::now()));
+        fut.await.unwrap().unwrap();
+    }
+
+    struct CountingOp(Arc<AtomicU64>);
+
+    make_runnable!(CountingOp);
+    impl CountingOp {
+        async fn execute(&mut self, _ctx: &OperationContext) -> Result<ControlFlow<()>> {
+            self.0.fetch_add(1, Ordering::Relaxed);
+            tokio::task::yield_now().await;
+            Ok(ControlFlow::Continue(()))
+        }
+    }
+
+    #[tokio::test]
+    #[ntest::timeout(2000)]
+    async fn test_run_with_changed_rate_limit() {
+        let counter = Arc::new(AtomicU64::new(0));
+        let counter_clone = Arc::clone(&counter);
+        let cfg = make_test_cfg(move || CountingOp(Arc::clone(&counter_clone)));
+
+        // The stream was started without a limit.
+        let (ctrl, fut) = run(cfg);
+        ctrl.set_total_rate_limit(Some(10.0));
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        let limited_start = counter.load(Ordering::Relaxed);
+        tokio::time::sleep(Duration::from_millis(500)).await;
+        let limited = counter.load(Ordering::Relaxed) - limited_start;
+        assert!(limited <= 10, "{} operations", limited);
+
+        ctrl.set_total_rate_limit(None);
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        assert!(counter.load(Ordering::Relaxed) - limited_start > 100);
+
+        ctrl.ask_to_stop();
+        fut.await.unwrap();
+    }
+
+    #[tokio::test]
+    #[ntest::timeout(2000)]
+    async fn test_pause_and_resume() {
+        let counter = Arc::new(AtomicU64::new(0));
+        let counter_clone = Arc::clone(&counter);
+        let cfg = make_test_cfg(move || CountingOp(Arc::clone(&counter_clone)));
+
+        let (ctrl, fut) = run(cfg);
+        ctrl.pause();
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        let paused_start = counter.load(Ordering::Relaxed);
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        assert_eq!(paused_start, counter.load(Ordering::Relaxed));
+
+        ctrl.resume();
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert!(counter.load(Ordering::Relaxed) > paused_start);
+
+        // The paused workers stop when asked to.
+        ctrl.pause();
+        ctrl.ask_to_stop();
+        fut.await.unwrap();
+    }
+
+    #[tokio::test]
+    #[ntest::timeout(2000)]
+    async fn test_run_with_duty_cycle() {
+        let counter = Arc::new(AtomicU64::new(0));
+        let counter_clone = Arc::clone(&counter);
+        let mut cfg = make_test_cfg(move || CountingOp(Arc::clone(&counter_clone)));
+        cfg.duty_cycle = Some(DutyCycle {
+            on: Duration::from_millis(50),
+            off: Duration::from_secs(3600),
+        });
+
+        let (ctrl, fut) = run(cfg);
+        tokio::time::sleep(Duration::from_millis(150)).await;
+        let after_window = counter.load(Ordering::Relaxed);
+        assert!(after_window > 0);
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        assert_eq!(after_window, counter.load(Ordering::Relaxed));
+
+        ctrl.ask_to_stop();
+        fut.await.unwrap();
+    }
+
+    #[test]
+    fn test_rate_shares() {
+        let stream = |concurrency, rate| StreamConfiguration {
+            name: String::from("test"),
+            concurrency,
+            rate_limit_per_second: rate,
+            rate_limit_mode: RateLimitMode::Schedule,
+            arrival: Arrival::Uniform,
+            operation_factory: Arc::new(FnOperationFactory(|| IdleOp)),
+        };
+
+        // By the initial rates, if all streams had one.
+        let streams = vec![stream(1, Some(300.0)), stream(3, Some(100.0))];
+        assert_eq!(rate_shares(&streams), vec![0.75, 0.25]);
+
+        // By the concurrency otherwise.
+        let streams = vec![stream(1, Some(300.0)), stream(3, None)];
+        assert_eq!(rate_shares(&streams), vec![0.25, 0.75]);
+    }
+
     struct StuckOp(pub Arc<Semaphore>);
 
     make_runnable!(StuckOp);
@@ -418,7 +1264,7 @@ mod tests {
         let sem_clone = Arc::clone(&sem);
 
         let cfg = make_test_cfg(move || StuckOp(Arc::clone(&sem_clone)));
-        let concurrency = cfg.concurrency as u32;
+        let concurrency = cfg.streams[0].concurrency as u32;
 
         let (ctrl, fut) = run(cfg);
 
@@ -427,7 +1273,81 @@ mod tests {
 
         // Abort and check that the stuck operations weren't a problem
         ctrl.abort();
-        fut.await.unwrap_err();
+        assert!(fut.await.unwrap_err().is::<RunAborted>());
+    }
+
+    #[tokio::test]
+    #[ntest::timeout(1000)]
+    async fn test_operation_timeout() {
+        let sem = Arc::new(Semaphore::new(0));
+        let sem_clone = Arc::clone(&sem);
+
+        // Without retries, the first timed out operation fails the run
+        let mut cfg = make_test_cfg(move || StuckOp(Arc::clone(&sem_clone)));
+        cfg.operation_timeout = Some(Duration::from_millis(10));
+        let (_, fut) = run(cfg);
+        let err = fut.await.unwrap_err();
+        assert!(err.to_string().contains("timed out"));
+    }
+
+    struct SlowFirstAttemptOp {
+        tried_ops: HashSet<u64>,
+    }
+
+    make_runnable!(SlowFirstAttemptOp);
+    impl SlowFirstAttemptOp {
+        async fn execute(&mut self, ctx: &OperationContext) -> Result<ControlFlow<()>> {
+            if ctx.operation_id >= 100 {
+                return Ok(ControlFlow::Break(()));
+            }
+            if self.tried_ops.insert(ctx.operation_id) {
+                // First attempt - get stuck until timed out
+                futures::future::pending::<()>().await;
+            }
+            Ok(ControlFlow::Continue(()))
+        }
+    }
+
+    #[tokio::test]
+    #[ntest::timeout(1000)]
+    async fn test_operation_timeout_retried() {
+        let mut cfg = make_test_cfg(|| SlowFirstAttemptOp {
+            tried_ops: HashSet::new(),
+        });
+        cfg.operation_timeout = Some(Duration::from_millis(10));
+        cfg.max_retries_per_op = 1;
+        let (_, fut) = run(cfg);
+        fut.await.unwrap(); // The timed out attempts were retried
+    }
+
+    struct TimeoutCountingFactory(Arc<AtomicU64>);
+
+    impl OperationFactory for TimeoutCountingFactory {
+        fn create(&self) -> Box<dyn Operation> {
+            Box::new(SlowFirstAttemptOp {
+                tried_ops: HashSet::new(),
+            })
+        }
+
+        fn record_timeout(&self, _ctx: &OperationContext, err: &anyhow::Error) {
+            assert!(err.is::<OperationTimedOut>());
+            self.0.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    #[tokio::test]
+    #[ntest::timeout(1000)]
+    async fn test_operation_timeout_recorded() {
+        let timeouts = Arc::new(AtomicU64::new(0));
+        let mut cfg = make_test_cfg(|| IdleOp);
+        cfg.streams[0].operation_factory = Arc::new(TimeoutCountingFactory(Arc::clone(&timeouts)));
+        cfg.operation_timeout = Some(Duration::from_millis(10));
+        cfg.max_retries_per_op = 1;
+        let (_, fut) = run(cfg);
+        fut.await.unwrap();
+
+        // Each of the operations timed out once, before being retried
+        assert_eq!(timeouts.load(Ordering::Relaxed), 100);
     }
 
     struct AlternatingSuccessFailOp {
@@ -471,6 +1391,65 @@ mod tests {
         fut.await.unwrap(); // Expect success as each op was retried
     }
 
+    #[tokio::test]
+    #[ntest::timeout(1000)]
+    async fn test_retrying_with_backoff() {
+        let mut cfg = make_test_cfg(AlternatingSuccessFailOp::new);
+        cfg.max_retries_per_op = 1;
+        cfg.retry_backoff = RetryBackoff::Exponential {
+            min: Duration::from_millis(1),
+            max: Duration::from_millis(4),
+        };
+        let (_, fut) = run(cfg);
+        fut.await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_retry_budget_exhausted() {
+        // Only a single retry can be made within the first second.
+        let mut cfg = make_test_cfg(AlternatingSuccessFailOp::new);
+        cfg.max_retries_per_op = 1;
+        cfg.retry_budget = Some(0.5);
+        let (_, fut) = run(cfg);
+        fut.await.unwrap_err();
+    }
+
+    #[test]
+    fn test_retry_budget() {
+        let now = Instant::now();
+        let budget = RetryBudget::new(2.0, now);
+        assert!(budget.try_take(now));
+        assert!(budget.try_take(now));
+        assert!(!budget.try_take(now));
+
+        // Refilled at the rate of the budget, up to a second's worth.
+        assert!(budget.try_take(now + Duration::from_millis(500)));
+        assert!(!budget.try_take(now + Duration::from_millis(500)));
+        let later = now + Duration::from_secs(10);
+        assert!(budget.try_take(later));
+        assert!(budget.try_take(later));
+        assert!(!budget.try_take(later));
+    }
+
+    #[test]
+    fn test_retry_backoff_delay() {
+        let ms = Duration::from_millis;
+        assert_eq!(RetryBackoff::None.delay(3), Duration::ZERO);
+        assert_eq!(RetryBackoff::Fixed(ms(10)).delay(3), ms(10));
+
+        let backoff = RetryBackoff::Exponential {
+            min: ms(10),
+            max: ms(100),
+        };
+        for (retry, full) in [(1, ms(10)), (2, ms(20)), (4, ms(80)), (5, ms(100))] {
+            for _ in 0..100 {
+                let delay = backoff.delay(retry);
+                assert!(full / 2 <= delay && delay <= full, "{:?}", delay);
+            }
+        }
+        assert!(backoff.delay(usize::MAX) <= ms(100));
+    }
+
     struct AlwaysFailsOp(pub Option<Arc<Semaphore>>);
 
     make_runnable!(AlwaysFailsOp);
@@ -492,7 +1471,7 @@ mod tests {
 
         let mut cfg = make_test_cfg(move || AlwaysFailsOp(Some(sem_clone.clone())));
         cfg.max_retries_per_op = usize::MAX;
-        let concurrency = cfg.concurrency as u32;
+        let concurrency = cfg.streams[0].concurrency as u32;
 
         let (ctrl, fut) = run(cfg);
 
@@ -0,0 +1,109 @@
+//! The metadata of a run, written at the beginning of each of its output files
+//! (the latency logs, the error, trace and key logs), so that the results can
+//! be traced back to the exact invocation.
+//!
+//! The metadata is a single line JSON object, written as a comment line:
+//! `# run-metadata: {...}`.
+
+use chrono::Utc;
+
+use crate::version::{json_string, BuildInfo};
+
+/// The prefix of the comment line holding the metadata.
+pub const COMMENT_PREFIX: &str = "# run-metadata: ";
+
+/// The metadata of a run.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RunMetadata {
+    /// The command line of the tool, including the program name.
+    pub command_line: Vec<String>,
+    /// The start time of the run in the RFC 3339 format.
+    pub start_time: String,
+    pub build: BuildInfo,
+    /// The names of the clusters, if they are named (e.g. by `-clusters`).
+    pub cluster_names: Vec<String>,
+    /// The contact points of the clusters.
+    pub nodes: Vec<String>,
+    /// The snapshot of the effective configuration, as setting and value pairs.
+    pub configuration: Vec<(String, String)>,
+}
+
+impl RunMetadata {
+    /// Creates the metadata of the current process started now.
+    pub fn new(nodes: Vec<String>, configuration: Vec<(String, String)>) -> Self {
+        Self {
+            command_line: std::env::args().collect(),
+            start_time: Utc::now().to_rfc3339(),
+            build: BuildInfo::current(),
+            cluster_names: Vec::new(),
+            nodes,
+            configuration,
+        }
+    }
+
+    /// Serializes the metadata to a single line JSON object.
+    pub fn to_json(&self) -> String {
+        let json_strings = |strings: &[String]| {
+            strings
+                .iter()
+                .map(|s| json_string(s))
+                .collect::<Vec<_>>()
+                .join(",")
+        };
+        let configuration = self
+            .configuration
+            .iter()
+            .map(|(setting, value)| format!("{}:{}", json_string(setting), json_string(value)))
+            .collect::<Vec<_>>()
+            .join(",");
+        format!(
+            "{{\"command_line\":[{}],\"start_time\":{},\"build\":{},\"cluster_names\":[{}],\"nodes\":[{}],\"configuration\":{{{}}}}}",
+            json_strings(&self.command_line),
+            json_string(&self.start_time),
+            self.build.to_json(),
+            json_strings(&self.cluster_names),
+            json_strings(&self.nodes),
+            configuration,
+        )
+    }
+
+    /// Returns the comment line, terminated by a newline, which starts the output files.
+    pub fn to_comment_line(&self) -> String {
+        format!("{}{}\n", COMMENT_PREFIX, self.to_json())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn to_json_test() {
+        let metadata = RunMetadata {
+            command_line: vec![String::from("cql-stress"), String::from("-mode=\"write\"")],
+            start_time: String::from("2024-01-01T00:00:00+00:00"),
+            build: BuildInfo {
+                version: "0.1.0",
+                git_commit: None,
+                build_date: None,
+                features: vec![],
+                driver_version: None,
+            },
+            cluster_names: vec![String::from("main")],
+            nodes: vec![String::from("127.0.0.1")],
+            configuration: vec![(String::from("Mode"), String::from("write"))],
+        };
+        assert_eq!(
+            "{\"command_line\":[\"cql-stress\",\"-mode=\\\"write\\\"\"],\
+            \"start_time\":\"2024-01-01T00:00:00+00:00\",\
+            \"build\":{\"version\":\"0.1.0\",\"git_commit\":null,\"build_date\":null,\"features\":[],\"driver_version\":null},\
+            \"cluster_names\":[\"main\"],\"nodes\":[\"127.0.0.1\"],\
+            \"configuration\":{\"Mode\":\"write\"}}",
+            metadata.to_json()
+        );
+        assert!(metadata
+            .to_comment_line()
+            .starts_with("# run-metadata: {\"command_line\":"));
+        assert!(metadata.to_comment_line().ends_with("}}\n"));
+    }
+}
@@ -0,0 +1,70 @@
+//! Seeding of the random generators used by the workers.
+//!
+//! When the user provides a seed, each worker gets its own seed derived
+//! from the user's seed and the index of the worker, so two runs with
+//! the same seed and concurrency generate the same sequences of values
+//! in each of the workers.
+
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// Hands out the seeds for the consecutively created workers.
+pub struct SeedSequence {
+    base: Option<u64>,
+    next_index: AtomicU64,
+}
+
+impl SeedSequence {
+    /// Creates a sequence derived from `base`. If `base` is `None`,
+    /// the sequence yields random seeds.
+    pub fn new(base: Option<u64>) -> Self {
+        Self {
+            base,
+            next_index: AtomicU64::new(0),
+        }
+    }
+
+    /// Returns the seed for the next worker.
+    pub fn next_seed(&self) -> u64 {
+        match self.base {
+            Some(base) => {
+                let index = self.next_index.fetch_add(1, Ordering::Relaxed);
+                derive_seed(base, index)
+            }
+            None => rand::random(),
+        }
+    }
+}
+
+/// Derives an independent seed for the `stream`-th generator
+/// of the worker seeded with `seed`.
+pub fn derive_seed(seed: u64, stream: u64) -> u64 {
+    // SplitMix64 finalizer - maps consecutive inputs to unrelated outputs.
+    let mut z = seed.wrapping_add(stream.wrapping_add(1).wrapping_mul(0x9e3779b97f4a7c15));
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+    z ^ (z >> 31)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{derive_seed, SeedSequence};
+
+    #[test]
+    fn test_seed_sequence_is_deterministic() {
+        let first = SeedSequence::new(Some(42));
+        let second = SeedSequence::new(Some(42));
+        let first_seeds = (0..10).map(|_| first.next_seed()).collect::<Vec<_>>();
+        let second_seeds = (0..10).map(|_| second.next_seed()).collect::<Vec<_>>();
+        assert_eq!(first_seeds, second_seeds);
+
+        // The workers don't share the seeds.
+        let mut unique = first_seeds.clone();
+        unique.sort();
+        unique.dedup();
+        assert_eq!(unique.len(), first_seeds.len());
+
+        let other = SeedSequence::new(Some(43));
+        assert_ne!(other.next_seed(), first_seeds[0]);
+        assert_ne!(derive_seed(42, 0), derive_seed(42, 1));
+    }
+}
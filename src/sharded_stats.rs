@@ -1,8 +1,14 @@
 use std::sync::Arc;
+use std::time::{Duration, Instant};
 
 use parking_lot::{Mutex, MutexGuard};
+use scylla::transport::errors::{DbError, QueryError};
 use thread_local::ThreadLocal;
 
+use crate::configuration::OperationContext;
+use crate::row_count::RowCountMismatch;
+use crate::run::OperationTimedOut;
+
 pub trait StatsFactory: Send + Sync {
     type Stats: Stats;
     fn create(&self) -> Self::Stats;
@@ -64,6 +70,157 @@ impl<F: StatsFactory> ShardedStats<F> {
     }
 }
 
+/// The number of operations after which [StatsBuffer] is flushed.
+pub const FLUSH_OPERATIONS: usize = 64;
+
+/// The time after which [StatsBuffer] is flushed, even if it hasn't
+/// collected [FLUSH_OPERATIONS] operations.
+pub const FLUSH_INTERVAL: Duration = Duration::from_millis(50);
+
+/// Stats which can be recorded from the samples buffered by [StatsBuffer].
+pub trait RecordSample: Stats {
+    /// Everything recorded about a single operation.
+    type Sample: Send + Sync;
+
+    fn record_sample(&mut self, sample: &Self::Sample);
+}
+
+/// The times of an operation, taken when it ends, so that its latency
+/// can be recorded later from a buffered sample (see [StatsBuffer]).
+#[derive(Clone, Copy, Debug)]
+pub struct OperationTiming {
+    pub scheduled_start_time: tokio::time::Instant,
+    pub actual_start_time: tokio::time::Instant,
+    pub end_time: tokio::time::Instant,
+    /// See [OperationContext::in_flight].
+    pub in_flight: u64,
+}
+
+impl OperationTiming {
+    /// Returns the times of the operation which ends now.
+    pub fn ended_now(ctx: &OperationContext) -> Self {
+        Self {
+            scheduled_start_time: ctx.scheduled_start_time,
+            actual_start_time: ctx.actual_start_time,
+            end_time: tokio::time::Instant::now(),
+            in_flight: ctx.in_flight,
+        }
+    }
+
+    /// The latency measured from the actual start of the operation.
+    pub fn raw_latency(&self) -> Duration {
+        self.end_time - self.actual_start_time
+    }
+
+    /// The latency measured from the scheduled start of the operation,
+    /// i.e. with the coordinated omission fix.
+    pub fn co_fixed_latency(&self) -> Duration {
+        self.end_time - self.scheduled_start_time
+    }
+
+    /// The delay of the start of the operation after its schedule.
+    pub fn queue_delay(&self) -> Duration {
+        self.actual_start_time - self.scheduled_start_time
+    }
+}
+
+/// A buffer of the stats of a single operation (i.e. a single worker).
+///
+/// Locking the shard for each operation shows up at very high rates,
+/// so the operations record their samples into a fixed-size local buffer,
+/// which is recorded into the shard of the current thread every
+/// [FLUSH_OPERATIONS] operations or [FLUSH_INTERVAL], whichever comes first.
+/// The buffer is also flushed when dropped, i.e. when the worker finishes,
+/// so the stats combined after the run include all operations.
+///
+/// The samples are recorded to the shard as if they were recorded directly,
+/// so the histograms lose no accuracy, and a worker doesn't keep a copy
+/// of the histograms of the stats. Before the worker waits for its next
+/// operation, e.g. while the run is paused or rate limited, the buffer is flushed
+/// if it becomes due during the wait (see [StatsBuffer::flush_before_wait]),
+/// so the samples of an idle worker are not held back until its next operation.
+pub struct StatsBuffer<F: StatsFactory>
+where
+    F::Stats: RecordSample,
+{
+    sharded: Arc<ShardedStats<F>>,
+    samples: Vec<<F::Stats as RecordSample>::Sample>,
+    last_flush: Instant,
+}
+
+impl<F: StatsFactory> StatsBuffer<F>
+where
+    F::Stats: RecordSample,
+{
+    pub fn new(sharded: Arc<ShardedStats<F>>) -> Self {
+        Self {
+            sharded,
+            samples: Vec::with_capacity(FLUSH_OPERATIONS),
+            last_flush: Instant::now(),
+        }
+    }
+
+    /// Records the sample of an operation into the buffer,
+    /// and flushes the buffer if it's due.
+    pub fn record(&mut self, sample: <F::Stats as RecordSample>::Sample) {
+        self.record_at(sample, Instant::now());
+    }
+
+    fn record_at(&mut self, sample: <F::Stats as RecordSample>::Sample, now: Instant) {
+        self.samples.push(sample);
+        if self.samples.len() >= FLUSH_OPERATIONS || now - self.last_flush >= FLUSH_INTERVAL {
+            self.flush_at(now);
+        }
+    }
+
+    /// Records the buffered samples into the shard of the current thread.
+    pub fn flush(&mut self) {
+        self.flush_at(Instant::now());
+    }
+
+    /// Flushes the buffer before the worker waits until `until`,
+    /// or indefinitely (`None`), if the buffer becomes due before the wait ends.
+    /// Otherwise, the samples would be kept until the next operation,
+    /// e.g. for the whole time the run is paused.
+    pub fn flush_before_wait(&mut self, until: Option<Instant>) {
+        self.flush_before_wait_at(until, Instant::now());
+    }
+
+    fn flush_before_wait_at(&mut self, until: Option<Instant>, now: Instant) {
+        let due = until.map_or(true, |until| {
+            until.saturating_duration_since(self.last_flush) >= FLUSH_INTERVAL
+        });
+        if due {
+            self.flush_at(now);
+        }
+    }
+
+    fn flush_at(&mut self, now: Instant) {
+        if !self.samples.is_empty() {
+            let mut shard = self.sharded.get_shard_mut();
+            for sample in self.samples.drain(..) {
+                shard.record_sample(&sample);
+            }
+        }
+        self.last_flush = now;
+    }
+
+    /// Returns the stats the buffer is flushed into, e.g. to account
+    /// the rare events directly.
+    pub fn sharded(&self) -> &ShardedStats<F> {
+        &self.sharded
+    }
+}
+
+impl<F: StatsFactory> Drop for StatsBuffer<F>
+where
+    F::Stats: RecordSample,
+{
+    fn drop(&mut self) {
+        self.flush();
+    }
+}
+
 pub struct NoStatsFactory;
 
 impl StatsFactory for NoStatsFactory {
@@ -79,3 +236,270 @@ impl Stats for NoStats {
     fn clear(&mut self) {}
     fn combine(&mut self, _other: &Self) {}
 }
+
+/// The category of an operation error, used to break down the error counts.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ErrorKind {
+    /// Server-side read/write timeout, client-side request timeout
+    /// or an operation cancelled by the runner after its timeout.
+    Timeout,
+    Unavailable,
+    /// The node is overloaded or the rate limit was reached.
+    Overloaded,
+    /// Any other error returned by the server.
+    OtherServer,
+    /// Errors which did not come from the server, e.g. connection errors
+    /// or failed data validation.
+    Client,
+    /// A read returned an unexpected number of rows.
+    DataLoss,
+}
+
+impl ErrorKind {
+    pub const ALL: [ErrorKind; 6] = [
+        ErrorKind::Timeout,
+        ErrorKind::Unavailable,
+        ErrorKind::Overloaded,
+        ErrorKind::OtherServer,
+        ErrorKind::Client,
+        ErrorKind::DataLoss,
+    ];
+
+    /// Classifies the error based on the driver error, the [RowCountMismatch]
+    /// or the [OperationTimedOut] it originates from.
+    pub fn classify(err: &anyhow::Error) -> Self {
+        if err.downcast_ref::<RowCountMismatch>().is_some() {
+            return ErrorKind::DataLoss;
+        }
+        if err.is::<OperationTimedOut>() {
+            return ErrorKind::Timeout;
+        }
+        match err.downcast_ref::<QueryError>() {
+            Some(QueryError::DbError(db_error, _)) => match db_error {
+                DbError::ReadTimeout { .. } | DbError::WriteTimeout { .. } => ErrorKind::Timeout,
+                DbError::Unavailable { .. } => ErrorKind::Unavailable,
+                DbError::Overloaded | DbError::RateLimitReached { .. } => ErrorKind::Overloaded,
+                _ => ErrorKind::OtherServer,
+            },
+            Some(QueryError::RequestTimeout(_) | QueryError::TimeoutError) => ErrorKind::Timeout,
+            _ => ErrorKind::Client,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            ErrorKind::Timeout => "timeout",
+            ErrorKind::Unavailable => "unavailable",
+            ErrorKind::Overloaded => "overloaded",
+            ErrorKind::OtherServer => "server",
+            ErrorKind::Client => "client",
+            ErrorKind::DataLoss => "data_loss",
+        }
+    }
+}
+
+/// Counts the errors of each [ErrorKind].
+#[derive(Clone, Default, Debug, PartialEq, Eq)]
+pub struct ErrorCounts {
+    counts: [u64; ErrorKind::ALL.len()],
+}
+
+impl ErrorCounts {
+    pub fn record(&mut self, err: &anyhow::Error) {
+        self.record_kind(ErrorKind::classify(err));
+    }
+
+    pub fn record_kind(&mut self, kind: ErrorKind) {
+        self.counts[kind as usize] += 1;
+    }
+
+    pub fn get(&self, kind: ErrorKind) -> u64 {
+        self.counts[kind as usize]
+    }
+
+    pub fn total(&self) -> u64 {
+        self.counts.iter().sum()
+    }
+
+    pub fn clear(&mut self) {
+        self.counts = Default::default();
+    }
+
+    pub fn combine(&mut self, other: &Self) {
+        for (count, other_count) in self.counts.iter_mut().zip(other.counts.iter()) {
+            *count += other_count;
+        }
+    }
+
+    /// Returns the non-zero counts in the form of "kind=count" pairs,
+    /// separated with spaces, or an empty string if there were no errors.
+    pub fn breakdown(&self) -> String {
+        ErrorKind::ALL
+            .iter()
+            .filter(|kind| self.get(**kind) > 0)
+            .map(|kind| format!("{}={}", kind.name(), self.get(*kind)))
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use scylla::statement::Consistency;
+    use scylla::transport::errors::{DbError, QueryError, WriteType};
+
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    use super::{
+        ErrorCounts, ErrorKind, RecordSample, ShardedStats, Stats, StatsBuffer, StatsFactory,
+        FLUSH_INTERVAL, FLUSH_OPERATIONS,
+    };
+    use crate::row_count::{ExpectedRowCount, RowCountMismatch};
+    use crate::run::OperationTimedOut;
+
+    struct CountStatsFactory;
+
+    impl StatsFactory for CountStatsFactory {
+        type Stats = CountStats;
+        fn create(&self) -> CountStats {
+            CountStats(0)
+        }
+    }
+
+    struct CountStats(usize);
+
+    impl Stats for CountStats {
+        fn clear(&mut self) {
+            self.0 = 0;
+        }
+        fn combine(&mut self, other: &Self) {
+            self.0 += other.0;
+        }
+    }
+
+    impl RecordSample for CountStats {
+        type Sample = usize;
+        fn record_sample(&mut self, sample: &usize) {
+            self.0 += sample;
+        }
+    }
+
+    #[test]
+    fn test_error_classification() {
+        let db_error = |err: DbError| anyhow::Error::from(QueryError::DbError(err, String::new()));
+
+        assert_eq!(
+            ErrorKind::Timeout,
+            ErrorKind::classify(&db_error(DbError::WriteTimeout {
+                consistency: Consistency::Quorum,
+                received: 1,
+                required: 2,
+                write_type: WriteType::Simple,
+            }))
+        );
+        assert_eq!(
+            ErrorKind::Unavailable,
+            ErrorKind::classify(&db_error(DbError::Unavailable {
+                consistency: Consistency::Quorum,
+                required: 2,
+                alive: 1,
+            }))
+        );
+        assert_eq!(
+            ErrorKind::Overloaded,
+            ErrorKind::classify(&db_error(DbError::Overloaded))
+        );
+        assert_eq!(
+            ErrorKind::OtherServer,
+            ErrorKind::classify(&db_error(DbError::ServerError))
+        );
+        assert_eq!(
+            ErrorKind::Timeout,
+            ErrorKind::classify(&QueryError::RequestTimeout(String::new()).into())
+        );
+        assert_eq!(
+            ErrorKind::Timeout,
+            ErrorKind::classify(&OperationTimedOut(Duration::from_secs(1)).into())
+        );
+        assert_eq!(
+            ErrorKind::Client,
+            ErrorKind::classify(&QueryError::UnableToAllocStreamId.into())
+        );
+        assert_eq!(
+            ErrorKind::Client,
+            ErrorKind::classify(&anyhow::anyhow!("data corruption"))
+        );
+        let row_count_mismatch = RowCountMismatch {
+            expected: ExpectedRowCount::parse("10").unwrap(),
+            actual: 9,
+        };
+        assert_eq!(
+            ErrorKind::DataLoss,
+            ErrorKind::classify(&anyhow::Error::from(row_count_mismatch).context("read error"))
+        );
+
+        // The classification sees through the added context.
+        let with_context = db_error(DbError::Overloaded).context("write error");
+        assert_eq!(ErrorKind::Overloaded, ErrorKind::classify(&with_context));
+    }
+
+    #[test]
+    fn test_error_counts() {
+        let mut counts = ErrorCounts::default();
+        assert_eq!("", counts.breakdown());
+
+        counts.record_kind(ErrorKind::Timeout);
+        counts.record_kind(ErrorKind::Timeout);
+        counts.record_kind(ErrorKind::Client);
+
+        let mut other = ErrorCounts::default();
+        other.record_kind(ErrorKind::Overloaded);
+        counts.combine(&other);
+
+        assert_eq!(4, counts.total());
+        assert_eq!("timeout=2 overloaded=1 client=1", counts.breakdown());
+
+        counts.clear();
+        assert_eq!(0, counts.total());
+    }
+
+    #[test]
+    fn test_stats_buffer() {
+        let sharded = Arc::new(ShardedStats::new(Arc::new(CountStatsFactory)));
+        let mut buffer = StatsBuffer::new(Arc::clone(&sharded));
+        let start = buffer.last_flush;
+
+        for _ in 0..FLUSH_OPERATIONS - 1 {
+            buffer.record_at(1, start);
+        }
+        // The samples are kept in the buffer until enough operations are collected...
+        assert_eq!(0, sharded.get_combined_and_clear().0);
+        buffer.record_at(1, start);
+        assert_eq!(FLUSH_OPERATIONS, sharded.get_combined_and_clear().0);
+        assert!(buffer.samples.is_empty());
+
+        // ...or until enough time passes.
+        buffer.record_at(1, start);
+        assert_eq!(0, sharded.get_combined_and_clear().0);
+        buffer.record_at(1, start + FLUSH_INTERVAL);
+        assert_eq!(2, sharded.get_combined_and_clear().0);
+
+        // ...or before the worker waits past the flush interval...
+        buffer.record_at(1, start + FLUSH_INTERVAL);
+        buffer.flush_before_wait_at(Some(start + FLUSH_INTERVAL), start + FLUSH_INTERVAL);
+        assert_eq!(0, sharded.get_combined_and_clear().0);
+        buffer.flush_before_wait_at(Some(start + 2 * FLUSH_INTERVAL), start + FLUSH_INTERVAL);
+        assert_eq!(1, sharded.get_combined_and_clear().0);
+
+        // ...or indefinitely, e.g. while the run is paused.
+        buffer.record_at(1, start + FLUSH_INTERVAL);
+        buffer.flush_before_wait_at(None, start + FLUSH_INTERVAL);
+        assert_eq!(1, sharded.get_combined_and_clear().0);
+
+        // The remaining samples are flushed when the buffer is dropped.
+        buffer.record_at(1, start + FLUSH_INTERVAL);
+        drop(buffer);
+        assert_eq!(1, sharded.get_combined_and_clear().0);
+    }
+}
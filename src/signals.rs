@@ -0,0 +1,98 @@
+//! Signals controlling a running benchmark.
+//!
+//! Ctrl+C (SIGINT) and SIGTERM ask the benchmark to stop, so that the tool
+//! stops gracefully also when it is terminated by an orchestrator
+//! (e.g. Kubernetes). SIGHUP and SIGUSR1 ask the frontend to dump the
+//! statistics gathered so far, without stopping the benchmark.
+//!
+//! The handlers are installed when the listeners are created, so they should
+//! be created before the benchmark starts - otherwise, the default action
+//! of the signal (termination) may be taken.
+
+use anyhow::Result;
+#[cfg(unix)]
+use tokio::signal::unix::{signal, Signal, SignalKind};
+
+/// Receives the signals asking the benchmark to stop.
+pub struct StopSignals {
+    #[cfg(unix)]
+    sigterm: Signal,
+}
+
+impl StopSignals {
+    pub fn new() -> Result<Self> {
+        Ok(Self {
+            #[cfg(unix)]
+            sigterm: signal(SignalKind::terminate())?,
+        })
+    }
+
+    /// Waits for Ctrl+C or SIGTERM.
+    pub async fn recv(&mut self) {
+        #[cfg(unix)]
+        tokio::select! {
+            result = tokio::signal::ctrl_c() => result.unwrap(),
+            _ = self.sigterm.recv() => {}
+        }
+
+        #[cfg(not(unix))]
+        tokio::signal::ctrl_c().await.unwrap();
+    }
+}
+
+/// Receives the signals asking for a dump of the statistics.
+pub struct DumpSignals {
+    #[cfg(unix)]
+    sighup: Signal,
+    #[cfg(unix)]
+    sigusr1: Signal,
+}
+
+impl DumpSignals {
+    pub fn new() -> Result<Self> {
+        Ok(Self {
+            #[cfg(unix)]
+            sighup: signal(SignalKind::hangup())?,
+            #[cfg(unix)]
+            sigusr1: signal(SignalKind::user_defined1())?,
+        })
+    }
+
+    /// Waits for SIGHUP or SIGUSR1. Never finishes on platforms
+    /// which don't support these signals.
+    pub async fn recv(&mut self) {
+        #[cfg(unix)]
+        tokio::select! {
+            _ = self.sighup.recv() => {}
+            _ = self.sigusr1.recv() => {}
+        }
+
+        #[cfg(not(unix))]
+        std::future::pending::<()>().await;
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use std::process::Command;
+    use std::time::Duration;
+
+    use super::DumpSignals;
+
+    #[tokio::test]
+    async fn test_dump_signals() {
+        let mut signals = DumpSignals::new().unwrap();
+
+        for name in ["-HUP", "-USR1"] {
+            let status = Command::new("kill")
+                .args([name, &std::process::id().to_string()])
+                .status()
+                .unwrap();
+            assert!(status.success());
+
+            tokio::time::timeout(Duration::from_secs(5), signals.recv())
+                .await
+                .unwrap();
+        }
+    }
+}
@@ -0,0 +1,79 @@
+//! Splitting a workload between several loader instances.
+//!
+//! Each of the instances is given the same workload along with its part,
+//! e.g. `3/8` for the third of eight instances, and computes its own share
+//! of the keys and of the operations.
+
+use std::fmt::Display;
+use std::ops::Range;
+
+use anyhow::{Context, Result};
+
+/// The part of the workload run by one of `count` instances.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Split {
+    // 1-based, so that `1/2` and `2/2` are the halves.
+    index: u64,
+    count: u64,
+}
+
+impl Split {
+    /// Parses the part given as `index/count`, where `1 <= index <= count`.
+    pub fn parse(s: &str) -> Result<Self> {
+        let (index, count) = s
+            .split_once('/')
+            .with_context(|| format!("Invalid split {}, expected index/count", s))?;
+        let index = index
+            .trim()
+            .parse::<u64>()
+            .with_context(|| format!("Invalid split index: {}", index))?;
+        let count = count
+            .trim()
+            .parse::<u64>()
+            .with_context(|| format!("Invalid split count: {}", count))?;
+        anyhow::ensure!(
+            1 <= index && index <= count,
+            "Invalid split {}, the index must be between 1 and the count",
+            s,
+        );
+        Ok(Self { index, count })
+    }
+
+    /// Returns the part of `0..len` handled by this instance. The parts
+    /// of all instances cover it without overlapping, and their lengths
+    /// differ by at most one.
+    pub fn part(&self, len: u64) -> Range<u64> {
+        let boundary = |index: u64| (len as u128 * index as u128 / self.count as u128) as u64;
+        boundary(self.index - 1)..boundary(self.index)
+    }
+}
+
+impl Display for Split {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}/{}", self.index, self.count)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Split;
+
+    #[test]
+    fn test_split_parts() {
+        let parts = (1..=3)
+            .map(|index| Split::parse(&format!("{}/3", index)).unwrap().part(10))
+            .collect::<Vec<_>>();
+        assert_eq!(parts, vec![0..3, 3..6, 6..10]);
+
+        assert_eq!(Split::parse("1/1").unwrap().part(10), 0..10);
+        assert_eq!(Split::parse("8/8").unwrap().part(u64::MAX).end, u64::MAX);
+        assert_eq!(Split::parse("3/8").unwrap().to_string(), "3/8");
+    }
+
+    #[test]
+    fn test_invalid_split() {
+        for s in ["", "3", "0/8", "9/8", "1/0", "a/8", "3/b", "-1/8"] {
+            assert!(Split::parse(s).is_err(), "{}", s);
+        }
+    }
+}
@@ -0,0 +1,371 @@
+//! Shipping of the interval stats to a Graphite or StatsD server.
+//!
+//! At the end of each interval, the frontends turn the stats combined from the
+//! shards into a [StatsSnapshot] and hand it over to the [StatsReporter], whose
+//! task sends it to the server in the background. The reporting is best effort:
+//! a server which is down or can't keep up doesn't slow down the benchmark,
+//! the snapshots are dropped instead.
+
+use std::fmt::Write as _;
+use std::time::{Duration, SystemTime};
+
+use anyhow::{Context, Result};
+use hdrhistogram::Histogram;
+use tokio::io::AsyncWriteExt;
+use tokio::net::{TcpStream, UdpSocket};
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+
+// The snapshots are sent once per second, so a short queue covers
+// a few seconds of the server being slow.
+const CHANNEL_CAPACITY: usize = 16;
+
+// Keeps the StatsD datagrams within the MTU of an Ethernet network.
+const MAX_DATAGRAM_SIZE: usize = 1432;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ReportProtocol {
+    /// The plaintext protocol of Graphite (carbon), over TCP.
+    Graphite,
+    /// The StatsD protocol over UDP. The metrics are sent as gauges.
+    Statsd,
+}
+
+/// The server the stats are reported to.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ReportTarget {
+    pub protocol: ReportProtocol,
+    /// The `host:port` address of the server.
+    pub address: String,
+    /// Prepended to the names of the metrics, e.g. `cql-stress.operations`.
+    pub prefix: String,
+}
+
+impl ReportTarget {
+    pub fn new(protocol: ReportProtocol, address: &str, prefix: &str) -> Result<Self> {
+        let (host, port) = address
+            .rsplit_once(':')
+            .with_context(|| format!("Invalid address {}, expected host:port", address))?;
+        anyhow::ensure!(
+            !host.is_empty(),
+            "Invalid address {}, missing host",
+            address
+        );
+        port.parse::<u16>()
+            .with_context(|| format!("Invalid port in address {}", address))?;
+        anyhow::ensure!(
+            !prefix.contains(char::is_whitespace),
+            "Metric prefix cannot contain whitespace"
+        );
+
+        Ok(Self {
+            protocol,
+            address: address.to_owned(),
+            prefix: prefix.trim_end_matches('.').to_owned(),
+        })
+    }
+
+    /// Describes the target, e.g. "graphite localhost:2003 (prefix cql-stress)".
+    pub fn describe(&self) -> String {
+        let protocol = match self.protocol {
+            ReportProtocol::Graphite => "graphite",
+            ReportProtocol::Statsd => "statsd",
+        };
+        format!("{} {} (prefix {})", protocol, self.address, self.prefix)
+    }
+}
+
+/// The metrics of a single interval.
+#[derive(Clone, Debug, PartialEq)]
+pub struct StatsSnapshot {
+    /// The end of the interval.
+    pub time: SystemTime,
+    /// The values of the metrics, named relative to the prefix of the target.
+    pub metrics: Vec<(String, f64)>,
+}
+
+impl StatsSnapshot {
+    pub fn new(time: SystemTime) -> Self {
+        Self {
+            time,
+            metrics: Vec::new(),
+        }
+    }
+
+    pub fn add(&mut self, name: &str, value: f64) {
+        self.metrics.push((name.to_owned(), value));
+    }
+
+    /// Adds the mean, the max and the percentiles of the latencies, in milliseconds,
+    /// as `<name>.mean`, `<name>.max`, `<name>.p50` etc.
+    ///
+    /// `ns_per_unit` is the number of nanoseconds in the unit
+    /// in which the histogram records the latencies.
+    pub fn add_latencies(&mut self, name: &str, histogram: &Histogram<u64>, ns_per_unit: u64) {
+        let to_ms = |value: f64| value * ns_per_unit as f64 / 1_000_000.0;
+        self.add(&format!("{}.mean", name), to_ms(histogram.mean()));
+        for (suffix, quantile) in [
+            ("p50", 0.5),
+            ("p90", 0.9),
+            ("p95", 0.95),
+            ("p99", 0.99),
+            ("p999", 0.999),
+        ] {
+            self.add(
+                &format!("{}.{}", name, suffix),
+                to_ms(histogram.value_at_quantile(quantile) as f64),
+            );
+        }
+        self.add(&format!("{}.max", name), to_ms(histogram.max() as f64));
+    }
+}
+
+/// Hands the snapshots over to the task sending them to the server.
+pub struct StatsReporter {
+    sender: mpsc::Sender<StatsSnapshot>,
+    task: JoinHandle<()>,
+}
+
+impl StatsReporter {
+    /// Spawns the task reporting the snapshots to the target.
+    /// The connection is established with the first snapshot.
+    pub fn spawn(target: ReportTarget) -> Self {
+        let (sender, receiver) = mpsc::channel(CHANNEL_CAPACITY);
+        let task = tokio::task::spawn(run(target, receiver));
+        Self { sender, task }
+    }
+
+    /// Queues the snapshot to be sent. The snapshot is dropped if the task is behind.
+    pub fn report(&self, snapshot: StatsSnapshot) {
+        let _ = self.sender.try_send(snapshot);
+    }
+
+    /// Waits until the queued snapshots are sent.
+    pub async fn close(self) {
+        drop(self.sender);
+        let _ = self.task.await;
+    }
+}
+
+enum Connection {
+    Graphite(TcpStream),
+    Statsd(UdpSocket),
+}
+
+async fn connect(target: &ReportTarget) -> std::io::Result<Connection> {
+    match target.protocol {
+        ReportProtocol::Graphite => Ok(Connection::Graphite(
+            TcpStream::connect(target.address.as_str()).await?,
+        )),
+        ReportProtocol::Statsd => {
+            let address = tokio::net::lookup_host(target.address.as_str())
+                .await?
+                .next()
+                .ok_or_else(|| {
+                    std::io::Error::new(std::io::ErrorKind::NotFound, "no address for the host")
+                })?;
+            let local_address = if address.is_ipv4() {
+                "0.0.0.0:0"
+            } else {
+                "[::]:0"
+            };
+            let socket = UdpSocket::bind(local_address).await?;
+            socket.connect(address).await?;
+            Ok(Connection::Statsd(socket))
+        }
+    }
+}
+
+async fn send(
+    connection: &mut Connection,
+    target: &ReportTarget,
+    snapshot: &StatsSnapshot,
+) -> std::io::Result<()> {
+    match connection {
+        Connection::Graphite(stream) => {
+            let lines = format_graphite(&target.prefix, snapshot);
+            stream.write_all(lines.as_bytes()).await
+        }
+        Connection::Statsd(socket) => {
+            for datagram in format_statsd(&target.prefix, snapshot) {
+                socket.send(datagram.as_bytes()).await?;
+            }
+            Ok(())
+        }
+    }
+}
+
+async fn connect_and_send(
+    connection: &mut Option<Connection>,
+    target: &ReportTarget,
+    snapshot: &StatsSnapshot,
+) -> std::io::Result<()> {
+    if connection.is_none() {
+        *connection = Some(connect(target).await?);
+    }
+    send(connection.as_mut().unwrap(), target, snapshot).await
+}
+
+async fn run(target: ReportTarget, mut receiver: mpsc::Receiver<StatsSnapshot>) {
+    let mut connection = None;
+    // Only the first error of a series is printed, not one per second.
+    let mut failing = false;
+    while let Some(snapshot) = receiver.recv().await {
+        match connect_and_send(&mut connection, &target, &snapshot).await {
+            Ok(()) => failing = false,
+            Err(err) => {
+                if !failing {
+                    eprintln!(
+                        "Failed to report the stats to {}: {}",
+                        target.describe(),
+                        err
+                    );
+                }
+                failing = true;
+                // Reconnect with the next snapshot.
+                connection = None;
+            }
+        }
+    }
+}
+
+fn metric_name(prefix: &str, name: &str) -> String {
+    if prefix.is_empty() {
+        name.to_owned()
+    } else {
+        format!("{}.{}", prefix, name)
+    }
+}
+
+// Formats the snapshot in the plaintext protocol: a `<path> <value> <timestamp>` line per metric.
+fn format_graphite(prefix: &str, snapshot: &StatsSnapshot) -> String {
+    let timestamp = snapshot
+        .time
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .unwrap_or(Duration::ZERO)
+        .as_secs();
+    let mut lines = String::new();
+    for (name, value) in &snapshot.metrics {
+        writeln!(
+            lines,
+            "{} {} {}",
+            metric_name(prefix, name),
+            value,
+            timestamp
+        )
+        .unwrap();
+    }
+    lines
+}
+
+// Formats the snapshot as `<name>:<value>|g` gauges, packed into as few datagrams as possible.
+fn format_statsd(prefix: &str, snapshot: &StatsSnapshot) -> Vec<String> {
+    let mut datagrams = Vec::new();
+    let mut datagram = String::new();
+    for (name, value) in &snapshot.metrics {
+        let line = format!("{}:{}|g", metric_name(prefix, name), value);
+        if !datagram.is_empty() && datagram.len() + 1 + line.len() > MAX_DATAGRAM_SIZE {
+            datagrams.push(std::mem::take(&mut datagram));
+        }
+        if !datagram.is_empty() {
+            datagram.push('\n');
+        }
+        datagram.push_str(&line);
+    }
+    if !datagram.is_empty() {
+        datagrams.push(datagram);
+    }
+    datagrams
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, SystemTime};
+
+    use hdrhistogram::Histogram;
+
+    use super::*;
+
+    fn snapshot() -> StatsSnapshot {
+        let mut snapshot =
+            StatsSnapshot::new(SystemTime::UNIX_EPOCH + Duration::from_millis(1_700_000_000_500));
+        snapshot.add("operations", 1000.0);
+        snapshot.add("errors", 0.0);
+        snapshot
+    }
+
+    #[test]
+    fn test_target() {
+        let target = ReportTarget::new(ReportProtocol::Graphite, "localhost:2003", "sb.").unwrap();
+        assert_eq!("sb", target.prefix);
+        assert_eq!("graphite localhost:2003 (prefix sb)", target.describe());
+        assert!(ReportTarget::new(ReportProtocol::Statsd, "[::1]:8125", "").is_ok());
+
+        assert!(ReportTarget::new(ReportProtocol::Statsd, "localhost", "sb").is_err());
+        assert!(ReportTarget::new(ReportProtocol::Statsd, ":8125", "sb").is_err());
+        assert!(ReportTarget::new(ReportProtocol::Statsd, "localhost:99999", "sb").is_err());
+        assert!(ReportTarget::new(ReportProtocol::Statsd, "localhost:8125", "a b").is_err());
+    }
+
+    #[test]
+    fn test_format_graphite() {
+        assert_eq!(
+            "sb.operations 1000 1700000000\nsb.errors 0 1700000000\n",
+            format_graphite("sb", &snapshot())
+        );
+        assert_eq!(
+            "operations 1000 1700000000\nerrors 0 1700000000\n",
+            format_graphite("", &snapshot())
+        );
+    }
+
+    #[test]
+    fn test_format_statsd() {
+        assert_eq!(
+            vec!["sb.operations:1000|g\nsb.errors:0|g"],
+            format_statsd("sb", &snapshot())
+        );
+
+        let mut snapshot = StatsSnapshot::new(SystemTime::UNIX_EPOCH);
+        for i in 0..200 {
+            snapshot.add(&format!("metric{}", i), i as f64);
+        }
+        let datagrams = format_statsd("sb", &snapshot);
+        assert!(datagrams.len() > 1);
+        assert!(datagrams.iter().all(|d| d.len() <= MAX_DATAGRAM_SIZE));
+        assert_eq!(
+            200,
+            datagrams.iter().map(|d| d.lines().count()).sum::<usize>()
+        );
+    }
+
+    #[test]
+    fn test_add_latencies() {
+        let mut histogram = Histogram::<u64>::new(3).unwrap();
+        // 2ms in microseconds.
+        histogram.record(2_000).unwrap();
+        let mut snapshot = StatsSnapshot::new(SystemTime::UNIX_EPOCH);
+        snapshot.add_latencies("latency", &histogram, 1000);
+
+        let names = snapshot
+            .metrics
+            .iter()
+            .map(|(name, _)| name.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            vec![
+                "latency.mean",
+                "latency.p50",
+                "latency.p90",
+                "latency.p95",
+                "latency.p99",
+                "latency.p999",
+                "latency.max"
+            ],
+            names
+        );
+        for (_, value) in &snapshot.metrics {
+            assert!((value - 2.0).abs() < 0.01, "{}", value);
+        }
+    }
+}
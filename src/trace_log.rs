@@ -0,0 +1,212 @@
+//! A log of the server-side traces of sampled operations.
+//!
+//! Tracing every operation would overload the cluster, so only a small
+//! fraction of them is traced, as decided by [TraceLog::should_trace].
+//! The ids of their tracing sessions are written to the file along with
+//! the latency of the operation, so that the traces of the slowest ones
+//! can be looked up in `system_traces.sessions` and `system_traces.events`.
+
+use std::fmt::{Debug, Display};
+use std::path::Path;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::{Context, Result};
+use chrono::Utc;
+use parking_lot::Mutex;
+use tokio::fs::File;
+use tokio::io::{AsyncWriteExt, BufWriter};
+use tokio::sync::mpsc;
+use uuid::Uuid;
+
+use crate::run_metadata::RunMetadata;
+
+// The traced operations are rare, so the writer keeps up unless
+// the fraction is large and the file system is slow.
+const CHANNEL_CAPACITY: usize = 1024;
+
+pub struct TraceLog {
+    // None after the log is closed.
+    sender: Mutex<Option<mpsc::Sender<String>>>,
+    fraction: f64,
+    dropped: Arc<AtomicU64>,
+}
+
+/// Writes the entries recorded by the [TraceLog] to the file.
+pub struct TraceLogWriter {
+    receiver: mpsc::Receiver<String>,
+    out: BufWriter<File>,
+    dropped: Arc<AtomicU64>,
+}
+
+impl TraceLog {
+    /// Creates the log of the traces of the given fraction of operations,
+    /// written to the file at `path` after the metadata of the run.
+    ///
+    /// The returned [TraceLogWriter] needs to be run for the entries
+    /// to be written.
+    pub async fn new(
+        path: impl AsRef<Path>,
+        fraction: f64,
+        metadata: &RunMetadata,
+    ) -> Result<(Arc<Self>, TraceLogWriter)> {
+        let path = path.as_ref();
+        let file = File::create(path)
+            .await
+            .with_context(|| format!("Failed to create the trace log {}", path.display()))?;
+
+        let (sender, receiver) = mpsc::channel(CHANNEL_CAPACITY);
+        let dropped = Arc::new(AtomicU64::new(0));
+
+        let log = Arc::new(Self {
+            sender: Mutex::new(Some(sender)),
+            fraction,
+            dropped: Arc::clone(&dropped),
+        });
+        let mut out = BufWriter::new(file);
+        out.write_all(metadata.to_comment_line().as_bytes()).await?;
+        out.write_all(b"# time\toperation\tpartition key\tlatency\ttracing session ids\n")
+            .await?;
+        let writer = TraceLogWriter {
+            receiver,
+            out,
+            dropped,
+        };
+
+        Ok((log, writer))
+    }
+
+    /// Tells whether the operation with the given id should be traced.
+    ///
+    /// The traced operations are spread evenly among the ids,
+    /// i.e. one in each `1 / fraction` consecutive ids is traced.
+    pub fn should_trace(&self, operation_id: u64) -> bool {
+        should_trace(self.fraction, operation_id)
+    }
+
+    /// Records the tracing sessions of the operation of given kind
+    /// (e.g. the name of the executed statement) on the given partition.
+    /// A paged read has a separate tracing session for each of its pages.
+    pub fn record(
+        &self,
+        operation: impl Display,
+        partition_key: impl Debug,
+        latency: Duration,
+        tracing_ids: &[Uuid],
+    ) {
+        let tracing_ids = tracing_ids
+            .iter()
+            .map(Uuid::to_string)
+            .collect::<Vec<_>>()
+            .join(",");
+        let entry = format!(
+            "{}\t{}\tpk={:?}\t{:.3}ms\t{}\n",
+            Utc::now().to_rfc3339(),
+            operation,
+            partition_key,
+            latency.as_secs_f64() * 1000.0,
+            tracing_ids,
+        );
+
+        let sent = match &*self.sender.lock() {
+            Some(sender) => sender.try_send(entry).is_ok(),
+            None => false,
+        };
+        if !sent {
+            self.dropped.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    /// Stops accepting new entries. The writer finishes
+    /// after writing the entries which were already recorded.
+    pub fn close(&self) {
+        self.sender.lock().take();
+    }
+}
+
+fn should_trace(fraction: f64, operation_id: u64) -> bool {
+    // The id is traced if a multiple of `1 / fraction` falls into [id, id + 1).
+    (operation_id as f64 * fraction).ceil() < ((operation_id + 1) as f64 * fraction).ceil()
+}
+
+impl TraceLogWriter {
+    /// Writes the entries until the [TraceLog] is closed.
+    pub async fn run(mut self) -> Result<()> {
+        while let Some(entry) = self.receiver.recv().await {
+            self.out.write_all(entry.as_bytes()).await?;
+        }
+
+        let dropped = self.dropped.load(Ordering::Relaxed);
+        if dropped > 0 {
+            let note = format!(
+                "# {} traces were not logged, the writer couldn't keep up\n",
+                dropped
+            );
+            self.out.write_all(note.as_bytes()).await?;
+        }
+        self.out.flush().await?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use uuid::Uuid;
+
+    use crate::run_metadata::RunMetadata;
+
+    use super::{should_trace, TraceLog};
+
+    #[test]
+    fn test_sampling_is_spread_evenly() {
+        let traced = |fraction: f64| {
+            (0..1000u64)
+                .filter(|id| should_trace(fraction, *id))
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(traced(0.0), Vec::<u64>::new());
+        assert_eq!(traced(1.0), (0..1000).collect::<Vec<_>>());
+        assert_eq!(traced(0.01), (0..10).map(|i| i * 100).collect::<Vec<_>>());
+        assert_eq!(traced(0.25).len(), 250);
+    }
+
+    #[tokio::test]
+    async fn test_trace_log_entries() {
+        let path = std::env::temp_dir().join(format!(
+            "cql-stress-trace-log-test-{}.log",
+            std::process::id()
+        ));
+        let (log, writer) = TraceLog::new(&path, 0.5, &RunMetadata::new(vec![], vec![]))
+            .await
+            .unwrap();
+        let writer = tokio::spawn(writer.run());
+
+        let tracing_ids = [Uuid::from_u128(0x1234), Uuid::from_u128(0x5678)];
+        log.record("read", 42i64, Duration::from_micros(1500), &tracing_ids);
+        log.close();
+        writer.await.unwrap().unwrap();
+
+        let contents = std::fs::read_to_string(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        let lines = contents.lines().collect::<Vec<_>>();
+        assert_eq!(lines.len(), 3);
+        assert!(lines[0].starts_with("# run-metadata: "));
+        assert!(lines[1].starts_with('#'));
+        let fields = lines[2].split('\t').collect::<Vec<_>>();
+        assert_eq!(
+            &fields[1..],
+            &[
+                "read",
+                "pk=42",
+                "1.500ms",
+                "00000000-0000-0000-0000-000000001234,00000000-0000-0000-0000-000000005678"
+            ]
+        );
+    }
+}
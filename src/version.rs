@@ -0,0 +1,183 @@
+//! The version and the build metadata of the tool.
+//!
+//! Both frontends print it as JSON with `--version-json`, so that the test
+//! frameworks can record the exact provenance of the tool used in a run,
+//! and list the crate features of the build with `--list-features`.
+
+use chrono::DateTime;
+
+/// The command line flag printing [BuildInfo::to_json] of the current build.
+pub const VERSION_JSON_FLAG: &str = "--version-json";
+
+/// The command line flag printing [features_report] of the current build.
+pub const LIST_FEATURES_FLAG: &str = "--list-features";
+
+/// The optional crate features, and whether they are enabled in the current build.
+pub const FEATURES: &[(&str, bool)] = &[
+    ("user-profile", cfg!(feature = "user-profile")),
+    ("auth-plugins", cfg!(feature = "auth-plugins")),
+];
+
+/// The version and the build metadata of the tool.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BuildInfo {
+    /// The version of the crate.
+    pub version: &'static str,
+    /// The git commit the tool was built from, if built from a git checkout.
+    pub git_commit: Option<&'static str>,
+    /// The build date in the RFC 3339 format.
+    pub build_date: Option<String>,
+    /// The enabled crate features.
+    pub features: Vec<&'static str>,
+    /// The version of the scylla-rust-driver the tool was built with.
+    pub driver_version: Option<&'static str>,
+}
+
+impl BuildInfo {
+    /// Returns the metadata of the current build.
+    pub fn current() -> Self {
+        let build_date = option_env!("CQL_STRESS_BUILD_TIMESTAMP")
+            .and_then(|timestamp| timestamp.parse::<i64>().ok())
+            .and_then(|timestamp| DateTime::from_timestamp(timestamp, 0))
+            .map(|date| date.to_rfc3339());
+        let features = FEATURES
+            .iter()
+            .filter(|(_, enabled)| *enabled)
+            .map(|(feature, _)| *feature)
+            .collect();
+        Self {
+            version: env!("CARGO_PKG_VERSION"),
+            git_commit: option_env!("CQL_STRESS_GIT_COMMIT"),
+            build_date,
+            features,
+            driver_version: option_env!("CQL_STRESS_DRIVER_VERSION"),
+        }
+    }
+
+    /// Serializes the metadata to a single line JSON object.
+    /// The unknown values are serialized as `null`.
+    pub fn to_json(&self) -> String {
+        let features = self
+            .features
+            .iter()
+            .map(|feature| json_string(feature))
+            .collect::<Vec<_>>()
+            .join(",");
+        format!(
+            "{{\"version\":{},\"git_commit\":{},\"build_date\":{},\"features\":[{}],\"driver_version\":{}}}",
+            json_string(self.version),
+            json_optional_string(self.git_commit),
+            json_optional_string(self.build_date.as_deref()),
+            features,
+            json_optional_string(self.driver_version),
+        )
+    }
+}
+
+/// Returns the report of the optional crate features, one `feature: enabled|disabled` per line.
+pub fn features_report() -> String {
+    FEATURES
+        .iter()
+        .map(|(feature, enabled)| {
+            let state = if *enabled { "enabled" } else { "disabled" };
+            format!("{}: {}\n", feature, state)
+        })
+        .collect()
+}
+
+/// Returns true if the command line arguments (including the program name)
+/// contain the given flag, e.g. [VERSION_JSON_FLAG].
+pub fn flag_requested<S: AsRef<str>>(args: impl IntoIterator<Item = S>, flag: &str) -> bool {
+    args.into_iter().skip(1).any(|arg| arg.as_ref() == flag)
+}
+
+fn json_optional_string(s: Option<&str>) -> String {
+    s.map_or_else(|| "null".to_owned(), json_string)
+}
+
+pub(crate) fn json_string(s: &str) -> String {
+    let mut escaped = String::with_capacity(s.len() + 2);
+    escaped.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            '\t' => escaped.push_str("\\t"),
+            c if c.is_control() => escaped.push_str(&format!("\\u{:04x}", c as u32)),
+            c => escaped.push(c),
+        }
+    }
+    escaped.push('"');
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_to_json() {
+        let info = BuildInfo {
+            version: "0.1.0",
+            git_commit: Some("abc123"),
+            build_date: Some("2024-01-01T00:00:00+00:00".to_owned()),
+            features: vec!["user-profile"],
+            driver_version: None,
+        };
+        assert_eq!(
+            info.to_json(),
+            "{\"version\":\"0.1.0\",\"git_commit\":\"abc123\",\"build_date\":\"2024-01-01T00:00:00+00:00\",\"features\":[\"user-profile\"],\"driver_version\":null}"
+        );
+
+        let info = BuildInfo {
+            features: vec![],
+            ..info
+        };
+        assert!(info.to_json().contains("\"features\":[]"));
+    }
+
+    #[test]
+    fn test_json_string() {
+        assert_eq!(json_string("a\"b\\c\nd\u{1}"), "\"a\\\"b\\\\c\\nd\\u0001\"");
+    }
+
+    #[test]
+    fn test_current() {
+        let info = BuildInfo::current();
+        assert_eq!(info.version, env!("CARGO_PKG_VERSION"));
+        assert_eq!(
+            info.features.contains(&"user-profile"),
+            cfg!(feature = "user-profile")
+        );
+    }
+
+    #[test]
+    fn test_features_report() {
+        let show = |enabled| if enabled { "enabled" } else { "disabled" };
+        let expected = format!(
+            "user-profile: {}\nauth-plugins: {}\n",
+            show(cfg!(feature = "user-profile")),
+            show(cfg!(feature = "auth-plugins"))
+        );
+        assert_eq!(features_report(), expected);
+    }
+
+    #[test]
+    fn test_flag_requested() {
+        assert!(flag_requested(
+            ["cql-stress", "--version-json"],
+            VERSION_JSON_FLAG
+        ));
+        assert!(!flag_requested(["--version-json"], VERSION_JSON_FLAG));
+        assert!(!flag_requested(
+            ["cql-stress", "write", "n=10"],
+            VERSION_JSON_FLAG
+        ));
+        assert!(flag_requested(
+            ["cql-stress", "--list-features"],
+            LIST_FEATURES_FLAG
+        ));
+    }
+}